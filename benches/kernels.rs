@@ -0,0 +1,58 @@
+// Kernel Benchmarks - elementwise ops and matmul called directly,
+// without DSL parsing, so SIMD/layout changes show up undiluted
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use linal::core::tensor::{Shape, Tensor, TensorId};
+use linal::engine::kernels;
+
+fn tensor(dims: Vec<usize>) -> Tensor {
+    let len = dims.iter().product();
+    let data = (0..len).map(|i| (i % 100) as f32 + 1.0).collect();
+    Tensor::new(TensorId(1), Shape::new(dims), data).unwrap()
+}
+
+fn elementwise_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kernel_elementwise");
+
+    for size in [1_024, 65_536, 1_048_576] {
+        let a = tensor(vec![size]);
+        let b = tensor(vec![size]);
+        group.throughput(Throughput::Elements(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("add", size), &size, |bench, _| {
+            bench.iter(|| kernels::add(black_box(&a), black_box(&b), TensorId(2)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("multiply", size), &size, |bench, _| {
+            bench.iter(|| kernels::multiply(black_box(&a), black_box(&b), TensorId(2)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("scalar_mul", size), &size, |bench, _| {
+            bench.iter(|| kernels::scalar_mul(black_box(&a), 2.5, TensorId(2)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("dot", size), &size, |bench, _| {
+            bench.iter(|| kernels::dot_1d(black_box(&a), black_box(&b)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("cosine", size), &size, |bench, _| {
+            bench.iter(|| kernels::cosine_similarity_1d(black_box(&a), black_box(&b)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn matmul_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kernel_matmul");
+    group.sample_size(20);
+
+    for n in [16, 64, 128, 256] {
+        let a = tensor(vec![n, n]);
+        let b = tensor(vec![n, n]);
+        // Multiply-adds per product
+        group.throughput(Throughput::Elements((n * n * n) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |bench, _| {
+            bench.iter(|| kernels::matmul(black_box(&a), black_box(&b), TensorId(2)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, elementwise_benchmark, matmul_benchmark);
+criterion_main!(benches);
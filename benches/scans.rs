@@ -0,0 +1,160 @@
+// Scan Benchmarks - filter/aggregate scans and index build/lookup at
+// 10K and 1M rows, plus vector index build and top-k search
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use linal::core::tuple::{Field, Schema, Tuple};
+use linal::core::value::{Value, ValueType};
+use linal::dsl::execute_line;
+use linal::engine::db::TensorDb;
+use std::sync::Arc;
+
+const ROW_COUNTS: [usize; 2] = [10_000, 1_000_000];
+const VECTOR_COUNT: usize = 10_000;
+const VECTOR_DIM: usize = 128;
+
+/// `events(id, grp, score, active)` with 16 groups and scores in 0..1000.
+/// Rows go in through the engine API; DSL inserts would dominate setup.
+fn events_db(rows: usize) -> TensorDb {
+    let mut db = TensorDb::new();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", ValueType::Int),
+        Field::new("grp", ValueType::String),
+        Field::new("score", ValueType::Float),
+        Field::new("active", ValueType::Bool),
+    ]));
+    db.create_dataset("events".into(), schema.clone()).unwrap();
+    let tuples = (0..rows)
+        .map(|i| {
+            Tuple::new(
+                schema.clone(),
+                vec![
+                    Value::Int(i as i64),
+                    Value::String(format!("g{}", i % 16)),
+                    Value::Float(((i * 7919) % 1000) as f32),
+                    Value::Bool(i % 3 == 0),
+                ],
+            )
+            .unwrap()
+        })
+        .collect();
+    db.insert_rows("events", tuples).unwrap();
+    db
+}
+
+fn embedding(seed: usize) -> Vec<f32> {
+    (0..VECTOR_DIM)
+        .map(|d| ((seed * 31 + d * 17) % 97) as f32 / 97.0 - 0.5)
+        .collect()
+}
+
+fn scan_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+    group.sample_size(10);
+
+    for rows in ROW_COUNTS {
+        let mut db = events_db(rows);
+        group.throughput(Throughput::Elements(rows as u64));
+
+        group.bench_with_input(BenchmarkId::new("filter", rows), &rows, |bench, _| {
+            bench.iter(|| {
+                execute_line(
+                    &mut db,
+                    black_box("SELECT id FROM events WHERE score > 900.0 AND active"),
+                    1,
+                )
+                .unwrap()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("count", rows), &rows, |bench, _| {
+            bench.iter(|| {
+                execute_line(
+                    &mut db,
+                    black_box("SELECT COUNT(*) FROM events WHERE score > 500.0"),
+                    1,
+                )
+                .unwrap()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("group_by", rows), &rows, |bench, _| {
+            bench.iter(|| {
+                execute_line(
+                    &mut db,
+                    black_box("SELECT grp, SUM(score), AVG(score) FROM events GROUP BY grp"),
+                    1,
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn hash_index_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash_index");
+    group.sample_size(10);
+
+    for rows in ROW_COUNTS {
+        let mut db = events_db(rows);
+
+        // Creating the index again replaces it, so each iteration is a full build
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::new("build", rows), &rows, |bench, _| {
+            bench.iter(|| db.create_index("events", "id").unwrap())
+        });
+
+        group.throughput(Throughput::Elements(1));
+        let lookup = format!("SELECT * FROM events WHERE id = {}", rows / 2);
+        group.bench_with_input(BenchmarkId::new("lookup", rows), &rows, |bench, _| {
+            bench.iter(|| execute_line(&mut db, black_box(&lookup), 1).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn vector_index_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vector_index");
+    group.sample_size(10);
+
+    let mut db = TensorDb::new();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", ValueType::Int),
+        Field::new("emb", ValueType::Vector(VECTOR_DIM)),
+    ]));
+    db.create_dataset("items".into(), schema.clone()).unwrap();
+    let tuples = (0..VECTOR_COUNT)
+        .map(|i| {
+            Tuple::new(
+                schema.clone(),
+                vec![Value::Int(i as i64), Value::Vector(embedding(i))],
+            )
+            .unwrap()
+        })
+        .collect();
+    db.insert_rows("items", tuples).unwrap();
+    group.throughput(Throughput::Elements(VECTOR_COUNT as u64));
+
+    group.bench_function(BenchmarkId::new("build", VECTOR_COUNT), |bench| {
+        bench.iter(|| db.create_vector_index("items", "emb").unwrap())
+    });
+
+    let query: Vec<String> = embedding(42).iter().map(|x| format!("{:.4}", x)).collect();
+    for k in [1, 10, 100] {
+        let search = format!(
+            "SEARCH res FROM items QUERY [{}] ON emb K={}",
+            query.join(", "),
+            k
+        );
+        group.bench_with_input(BenchmarkId::new("search_k", k), &k, |bench, _| {
+            bench.iter(|| execute_line(&mut db, black_box(&search), 1).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    scan_benchmark,
+    hash_index_benchmark,
+    vector_index_benchmark
+);
+criterion_main!(benches);
@@ -42,7 +42,7 @@ impl ComputeBackend for CpuBackend {
         b: &Tensor,
         new_id: TensorId,
     ) -> Result<Tensor, String> {
-        if self.use_simd(a.len()) {
+        if a.shape == b.shape && self.use_simd(a.len()) {
             self.simd.add(ctx, a, b, new_id)
         } else {
             self.scalar.add(ctx, a, b, new_id)
@@ -56,7 +56,7 @@ impl ComputeBackend for CpuBackend {
         b: &Tensor,
         new_id: TensorId,
     ) -> Result<Tensor, String> {
-        if self.use_simd(a.len()) {
+        if a.shape == b.shape && self.use_simd(a.len()) {
             self.simd.sub(ctx, a, b, new_id)
         } else {
             self.scalar.sub(ctx, a, b, new_id)
@@ -70,7 +70,7 @@ impl ComputeBackend for CpuBackend {
         b: &Tensor,
         new_id: TensorId,
     ) -> Result<Tensor, String> {
-        if self.use_simd(a.len()) {
+        if a.shape == b.shape && self.use_simd(a.len()) {
             self.simd.multiply(ctx, a, b, new_id)
         } else {
             self.scalar.multiply(ctx, a, b, new_id)
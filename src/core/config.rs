@@ -1,29 +1,371 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EngineConfig {
     pub storage: StorageConfig,
+    #[serde(default)]
+    pub rerank: RerankConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub versioning: VersioningConfig,
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    #[serde(default)]
+    pub seed: SeedConfig,
+    #[serde(default)]
+    pub memory: MemoryConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub security: SecurityConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     pub data_dir: PathBuf,
     pub default_db: String,
+    /// Flush datasets to storage after every mutating command and reload them on startup
+    #[serde(default)]
+    pub auto_persist: bool,
+    /// Log mutating commands to `{data_dir}/{db}/wal.log` and replay them on startup
+    #[serde(default)]
+    pub wal: bool,
+    /// Where datasets and tensors are persisted; the WAL always stays in `data_dir`
+    #[serde(default)]
+    pub backend: StorageBackend,
+    /// Bucket for `s3`/`gcs`, or root directory for `local`
+    #[serde(default)]
+    pub bucket: Option<String>,
+    /// Key prefix under which every database is stored
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// How often the server drops rows and datasets past their TTL (0 disables it)
+    #[serde(default = "default_ttl_reap_interval_secs")]
+    pub ttl_reap_interval_secs: u64,
+    /// Rows per row group of saved Parquet files; a save holds one row group
+    /// in memory as Arrow arrays at a time
+    #[serde(default = "default_parquet_row_group_rows")]
+    pub parquet_row_group_rows: usize,
+    /// Codec of saved and exported Parquet files
+    #[serde(default)]
+    pub parquet_compression: ParquetCompression,
+    /// Dictionary-encode Parquet columns
+    #[serde(default = "default_true")]
+    pub parquet_dictionary: bool,
+    /// Write min/max and null count statistics used to skip pages on scans
+    #[serde(default = "default_true")]
+    pub parquet_statistics: bool,
+}
+
+fn default_ttl_reap_interval_secs() -> u64 {
+    60
+}
+
+fn default_parquet_row_group_rows() -> usize {
+    crate::core::storage::DEFAULT_ROW_GROUP_ROWS
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: PathBuf::from("./data"),
+            default_db: "default".to_string(),
+            auto_persist: false,
+            wal: false,
+            backend: StorageBackend::default(),
+            bucket: None,
+            prefix: None,
+            ttl_reap_interval_secs: default_ttl_reap_interval_secs(),
+            parquet_row_group_rows: default_parquet_row_group_rows(),
+            parquet_compression: ParquetCompression::default(),
+            parquet_dictionary: true,
+            parquet_statistics: true,
+        }
+    }
+}
+
+/// Storage backend for datasets and tensors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Parquet files under `data_dir`
+    #[default]
+    File,
+    /// `object_store` local filesystem rooted at `bucket`
+    Local,
+    /// Amazon S3 (requires the `s3` feature)
+    S3,
+    /// Google Cloud Storage (requires the `gcs` feature)
+    Gcs,
+}
+
+/// Compression codec of Parquet files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParquetCompression {
+    None,
+    Snappy,
+    Gzip,
+    Lz4,
+    #[default]
+    Zstd,
+}
+
+impl ParquetCompression {
+    /// Parse a codec name (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "none" | "uncompressed" => Some(Self::None),
+            "snappy" => Some(Self::Snappy),
+            "gzip" => Some(Self::Gzip),
+            "lz4" => Some(Self::Lz4),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Audit trail of mutating commands, queryable as `system.audit_log`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// Append entries to `{data_dir}/audit_log.jsonl` and reload them on startup
+    #[serde(default)]
+    pub persist: bool,
 }
 
-impl Default for EngineConfig {
+/// Dataset history for `SELECT ... FROM name VERSION n` and `AS OF <timestamp>`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VersioningConfig {
+    /// Snapshots kept per dataset, oldest dropped first (0 disables time travel)
+    #[serde(default)]
+    pub retention: usize,
+}
+
+/// Query execution settings. In deterministic mode the same script over the
+/// same data gives the same rows in the same order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionConfig {
+    /// Emit GROUP BY results sorted by group key and seed random operators from `seed`
+    #[serde(default)]
+    pub deterministic: bool,
+    /// Seed of the random number generators handed out in deterministic mode
+    #[serde(default)]
+    pub seed: u64,
+    /// Row count from which filters and global aggregates run on columnar batches
+    #[serde(default = "default_columnar_threshold")]
+    pub columnar_threshold: usize,
+    /// Largest estimated row count of a join input that is hashed whole and
+    /// probed by the other side; bigger joins are hash-partitioned
+    #[serde(default = "default_broadcast_join_threshold")]
+    pub broadcast_join_threshold: usize,
+    /// What tensor operations, aggregates and column statistics do with NaN and Inf
+    #[serde(default)]
+    pub non_finite: NonFinitePolicy,
+    /// Seconds a command runs before SHOW QUERIES and GET /queries list it
+    #[serde(default = "default_progress_after_secs")]
+    pub progress_after_secs: u64,
+    /// Finished, failed and cancelled background jobs SHOW JOBS keeps
+    /// listing; older ones are forgotten
+    #[serde(default = "default_finished_jobs_kept")]
+    pub finished_jobs_kept: usize,
+}
+
+/// Handling of NaN and infinite floats (`[execution] non_finite`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NonFinitePolicy {
+    /// Keep them; they flow into results as IEEE arithmetic dictates
+    #[default]
+    Propagate,
+    /// Aggregates and column statistics ignore them, like NULLs
+    Skip,
+    /// Tensor operations and aggregates that meet them fail
+    Error,
+}
+
+fn default_columnar_threshold() -> usize {
+    4096
+}
+
+fn default_broadcast_join_threshold() -> usize {
+    10_000
+}
+
+fn default_progress_after_secs() -> u64 {
+    5
+}
+
+fn default_finished_jobs_kept() -> usize {
+    100
+}
+
+impl Default for ExecutionConfig {
     fn default() -> Self {
         Self {
-            storage: StorageConfig {
-                data_dir: PathBuf::from("./data"),
-                default_db: "default".to_string(),
-            },
+            deterministic: false,
+            seed: 0,
+            columnar_threshold: default_columnar_threshold(),
+            broadcast_join_threshold: default_broadcast_join_threshold(),
+            non_finite: NonFinitePolicy::default(),
+            progress_after_secs: default_progress_after_secs(),
+            finished_jobs_kept: default_finished_jobs_kept(),
         }
     }
 }
 
+/// Soft limit on the memory held by datasets. Past `evict_at` of the budget,
+/// least recently used datasets are spilled to storage (auto_persist only).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    /// Estimated bytes of dataset rows to keep in memory (0 disables eviction)
+    #[serde(default)]
+    pub budget_bytes: usize,
+    /// Fraction of the budget from which datasets are evicted
+    #[serde(default = "default_evict_at")]
+    pub evict_at: f64,
+}
+
+fn default_evict_at() -> f64 {
+    0.9
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            budget_bytes: 0,
+            evict_at: default_evict_at(),
+        }
+    }
+}
+
+/// Admission control of the HTTP server: commands past `max_concurrent_queries`
+/// wait in a bounded queue, and requests finding the queue full get a 503
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Commands executed at once by `POST /execute` (0 = no limit)
+    #[serde(default)]
+    pub max_concurrent_queries: usize,
+    /// Commands waiting for a slot before new ones are turned away
+    #[serde(default = "default_max_queued_queries")]
+    pub max_queued_queries: usize,
+    /// Seconds sent in the Retry-After header of rejected requests
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+    /// Seconds a transaction may go without a command from the session that
+    /// began it before it is rolled back (0 = never)
+    #[serde(default = "default_transaction_idle_timeout_secs")]
+    pub transaction_idle_timeout_secs: u64,
+}
+
+fn default_max_queued_queries() -> usize {
+    64
+}
+
+fn default_retry_after_secs() -> u64 {
+    1
+}
+
+fn default_transaction_idle_timeout_secs() -> u64 {
+    300
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_queries: 0,
+            max_queued_queries: default_max_queued_queries(),
+            retry_after_secs: default_retry_after_secs(),
+            transaction_idle_timeout_secs: default_transaction_idle_timeout_secs(),
+        }
+    }
+}
+
+/// Roles of HTTP callers, checked against column masking policies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityConfig {
+    /// Role of each X-API-Key
+    #[serde(default)]
+    pub api_key_roles: HashMap<String, String>,
+    /// Role of callers whose key is missing or not listed
+    #[serde(default = "default_role")]
+    pub default_role: String,
+    /// Roles that may set and remove any masking policy and export
+    /// database snapshots, which hold raw values
+    #[serde(default = "default_admin_roles")]
+    pub admin_roles: Vec<String>,
+}
+
+fn default_role() -> String {
+    "public".to_string()
+}
+
+fn default_admin_roles() -> Vec<String> {
+    vec!["admin".to_string()]
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            api_key_roles: HashMap::new(),
+            default_role: default_role(),
+            admin_roles: default_admin_roles(),
+        }
+    }
+}
+
+impl SecurityConfig {
+    /// Role of a caller presenting `api_key`
+    pub fn role_of(&self, api_key: Option<&str>) -> String {
+        api_key
+            .and_then(|key| self.api_key_roles.get(key))
+            .unwrap_or(&self.default_role)
+            .clone()
+    }
+
+    /// Whether `role` is one of `admin_roles`
+    pub fn is_admin(&self, role: &str) -> bool {
+        self.admin_roles.iter().any(|r| r == role)
+    }
+}
+
+/// Data the server loads when it starts on an empty `data_dir`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeedConfig {
+    /// Directories written by `SAVE ALL`; their datasets and tensors are loaded first
+    #[serde(default)]
+    pub parquet: Vec<PathBuf>,
+    /// `.lnl` scripts, executed in order, each starting on the default database
+    #[serde(default)]
+    pub scripts: Vec<PathBuf>,
+}
+
+/// External rerank backends, referenced by `RERANK USING SERVICE "name"`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RerankConfig {
+    #[serde(default)]
+    pub services: HashMap<String, RerankServiceConfig>,
+}
+
+/// HTTP endpoint that receives `{"query", "documents"}` and answers `{"scores"}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RerankServiceConfig {
+    pub url: String,
+    #[serde(default = "default_rerank_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_rerank_timeout_ms() -> u64 {
+    10_000
+}
+
 impl EngineConfig {
     pub fn load() -> Self {
         let config_path = "linal.toml";
@@ -1,10 +1,18 @@
-use super::tuple::{Schema, Tuple};
+use super::config::NonFinitePolicy;
+use super::sketch::HyperLogLog;
+use super::tuple::{Field, Schema, Tuple};
 use super::value::{Value, ValueType};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Metadata key holding a dataset's TTL in seconds
+pub const TTL_METADATA_KEY: &str = "ttl";
+
+/// Metadata key selecting what the TTL expires: "rows" (default) or "dataset"
+pub const TTL_SCOPE_METADATA_KEY: &str = "ttl_scope";
+
 /// Unique identifier for datasets
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 pub struct DatasetId(pub u64);
@@ -16,6 +24,200 @@ pub struct ColumnStats {
     pub null_count: usize,
     pub min: Option<Value>,
     pub max: Option<Value>,
+    /// NaN and infinite values, left out of `min`/`max` unless the policy propagates them
+    #[serde(default)]
+    pub non_finite_count: usize,
+    /// Approximate number of distinct non-null values (HyperLogLog), set by `ANALYZE`
+    #[serde(default)]
+    pub distinct_count: Option<u64>,
+    /// Distribution of the ordered values, set by `ANALYZE`
+    #[serde(default)]
+    pub histogram: Option<Histogram>,
+}
+
+impl ColumnStats {
+    /// Widen `min` and `max` to cover `value`
+    fn include(&mut self, value: &Value) {
+        // Update min
+        if let Some(ref current_min) = self.min {
+            if let Some(ord) = value.compare(current_min) {
+                if ord == std::cmp::Ordering::Less {
+                    self.min = Some(value.clone());
+                }
+            }
+        } else {
+            self.min = Some(value.clone());
+        }
+
+        // Update max
+        if let Some(ref current_max) = self.max {
+            if let Some(ord) = value.compare(current_max) {
+                if ord == std::cmp::Ordering::Greater {
+                    self.max = Some(value.clone());
+                }
+            }
+        } else {
+            self.max = Some(value.clone());
+        }
+    }
+
+    /// Count `value` into the statistics. Under `Propagate` a NaN makes
+    /// `min` and `max` NaN for good; otherwise NaN and Inf are only counted.
+    fn observe(&mut self, value: &Value, policy: NonFinitePolicy) {
+        if value.is_null() {
+            self.null_count += 1;
+        } else if value.is_non_finite() {
+            self.non_finite_count += 1;
+            if policy == NonFinitePolicy::Propagate {
+                // NaN is unordered, so it cannot take part in the comparisons
+                if matches!(value, Value::Float(f) if f.is_nan()) {
+                    self.min = Some(value.clone());
+                    self.max = Some(value.clone());
+                } else {
+                    self.include(value);
+                }
+            }
+        } else {
+            self.include(value);
+        }
+    }
+
+    /// Undo the counts of a `value` that was overwritten. `min` and `max`
+    /// cannot shrink without a rescan, so they stay until `ANALYZE`.
+    fn forget(&mut self, value: &Value) {
+        if value.is_null() {
+            self.null_count = self.null_count.saturating_sub(1);
+        } else if value.is_non_finite() {
+            self.non_finite_count = self.non_finite_count.saturating_sub(1);
+        }
+    }
+
+    /// Estimated fraction of the `row_count` rows for which `column op value`
+    /// holds. `None` for operators it cannot judge and before `ANALYZE` has
+    /// built the distinct count (and, for ranges, the histogram).
+    pub fn selectivity(&self, op: &str, value: &Value, row_count: usize) -> Option<f64> {
+        if row_count == 0 {
+            return Some(0.0);
+        }
+        let non_null = row_count.saturating_sub(self.null_count) as f64 / row_count as f64;
+        let outside = |min: &Option<Value>, max: &Option<Value>| {
+            min.as_ref()
+                .and_then(|min| value.compare(min))
+                .is_some_and(|o| o == std::cmp::Ordering::Less)
+                || max
+                    .as_ref()
+                    .and_then(|max| value.compare(max))
+                    .is_some_and(|o| o == std::cmp::Ordering::Greater)
+        };
+        let equal = if value.is_null() || outside(&self.min, &self.max) {
+            0.0
+        } else {
+            non_null / self.distinct_count?.max(1) as f64
+        };
+
+        let at_most = || -> Option<f64> {
+            let histogram = self.histogram.as_ref()?;
+            Some(non_null * histogram.fraction_at_most(value, self.min.as_ref()?)?)
+        };
+        let selectivity = match op {
+            "=" => equal,
+            "!=" | "<>" => non_null - equal,
+            "<=" => at_most()?,
+            "<" => at_most()? - equal,
+            ">" => non_null - at_most()?,
+            ">=" => non_null - at_most()? + equal,
+            _ => return None,
+        };
+        Some(selectivity.clamp(0.0, 1.0))
+    }
+}
+
+/// Buckets of the histograms built by `ANALYZE`
+const HISTOGRAM_BUCKETS: usize = 32;
+
+/// Equi-depth histogram of a column's non-null, finite values. Bucket `i`
+/// holds the `counts[i]` values above `bounds[i - 1]` (from the column's
+/// `min` for the first bucket) up to and including `bounds[i]`. Buckets hold
+/// about the same number of values, except that equal values never span two.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub bounds: Vec<Value>,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Histogram of `values`, sorted ascending; `None` when there are none
+    fn from_sorted(values: &[&Value]) -> Option<Self> {
+        let buckets = HISTOGRAM_BUCKETS.min(values.len());
+        let mut histogram = Histogram {
+            bounds: Vec::with_capacity(buckets),
+            counts: Vec::with_capacity(buckets),
+        };
+        let mut start = 0;
+        for i in 1..=buckets {
+            let mut end = values.len() * i / buckets;
+            if end <= start {
+                continue;
+            }
+            while end < values.len()
+                && values[end].compare(values[end - 1]) == Some(std::cmp::Ordering::Equal)
+            {
+                end += 1;
+            }
+            histogram.bounds.push(values[end - 1].clone());
+            histogram.counts.push(end - start);
+            start = end;
+        }
+        (!histogram.counts.is_empty()).then_some(histogram)
+    }
+
+    /// Estimated fraction of the values that are `<= value`. Numbers and
+    /// timestamps are interpolated inside their bucket; other values count
+    /// half of it. `None` when `value` does not compare with the bounds.
+    pub fn fraction_at_most(&self, value: &Value, min: &Value) -> Option<f64> {
+        use std::cmp::Ordering;
+
+        let total: usize = self.counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let mut below = 0.0;
+        let mut lower = min;
+        for (bound, &count) in self.bounds.iter().zip(&self.counts) {
+            if value.compare(bound)? != Ordering::Less {
+                below += count as f64;
+                lower = bound;
+                continue;
+            }
+            if value.compare(lower)? == Ordering::Greater {
+                let share = match (as_position(lower), as_position(value), as_position(bound)) {
+                    (Some(lo), Some(v), Some(hi)) if hi > lo => (v - lo) / (hi - lo),
+                    _ => 0.5,
+                };
+                below += count as f64 * share;
+            }
+            break;
+        }
+        Some(below / total as f64)
+    }
+}
+
+/// Position of an ordered value on a number line, for interpolation
+fn as_position(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(i) => Some(*i as f64),
+        Value::Float(f) => Some(*f as f64),
+        Value::Timestamp(t) => Some(t.timestamp_micros() as f64),
+        _ => None,
+    }
+}
+
+/// Types whose values have a total order a histogram can bucket
+fn is_ordered(value_type: &ValueType) -> bool {
+    matches!(
+        value_type,
+        ValueType::Int | ValueType::Float | ValueType::String | ValueType::Timestamp
+    )
 }
 
 /// Metadata about a dataset
@@ -29,6 +231,33 @@ pub struct DatasetMetadata {
     pub column_stats: HashMap<String, ColumnStats>,
     pub schema: Schema,
     pub extra: HashMap<String, String>,
+    /// Policy `column_stats` were computed under
+    #[serde(default)]
+    pub non_finite: NonFinitePolicy,
+    /// Where each column came from, for datasets created from a query
+    #[serde(default)]
+    pub lineage: Vec<ColumnLineage>,
+    /// Masking policy of each masked column
+    #[serde(default)]
+    pub masking: HashMap<String, MaskingPolicy>,
+}
+
+/// What callers see of a masked column: `expression` evaluated over the
+/// row instead of the column's value, unless their role is exempt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaskingPolicy {
+    /// Expression over the dataset's columns, e.g. `MASK_EMAIL(email)`
+    pub expression: String,
+    #[serde(default)]
+    pub exempt_roles: Vec<String>,
+}
+
+impl MaskingPolicy {
+    /// Whether a caller with `role` sees masked values. Callers without a
+    /// role (local sessions) see raw values.
+    pub fn applies_to(&self, role: Option<&str>) -> bool {
+        role.is_some_and(|role| !self.exempt_roles.iter().any(|r| r == role))
+    }
 }
 
 impl DatasetMetadata {
@@ -43,60 +272,166 @@ impl DatasetMetadata {
             column_stats: HashMap::new(),
             schema,
             extra: HashMap::new(),
+            non_finite: NonFinitePolicy::default(),
+            lineage: Vec::new(),
+            masking: HashMap::new(),
         }
     }
 
-    /// Update statistics based on current rows
+    /// Recompute statistics from all current rows
     pub fn update_stats(&mut self, schema: &Schema, rows: &[Tuple]) {
+        self.update_stats_with(schema, rows, self.non_finite);
+    }
+
+    /// Update statistics, treating NaN and Inf as `policy` says
+    pub fn update_stats_with(&mut self, schema: &Schema, rows: &[Tuple], policy: NonFinitePolicy) {
         self.row_count = rows.len();
         self.updated_at = Utc::now();
-        self.column_stats.clear();
+        self.update_column_stats(schema, rows, policy);
+    }
+
+    /// Recompute the per-column statistics. Under `Propagate` a NaN makes
+    /// `min` and `max` NaN; otherwise NaN and Inf are only counted. Distinct
+    /// counts and histograms are kept as they were until the next `analyze`.
+    pub fn update_column_stats(
+        &mut self,
+        schema: &Schema,
+        rows: &[Tuple],
+        policy: NonFinitePolicy,
+    ) {
+        let previous = std::mem::take(&mut self.column_stats);
+        self.non_finite = policy;
+        self.record_column_stats(schema, rows);
+        for (name, old) in previous {
+            if let Some(stats) = self.column_stats.get_mut(&name) {
+                stats.distinct_count = old.distinct_count;
+                stats.histogram = old.histogram;
+            }
+        }
+    }
 
+    /// Recompute every statistic from all current rows, including the
+    /// distinct counts and histograms that appends do not maintain
+    pub fn analyze(&mut self, schema: &Schema, rows: &[Tuple], policy: NonFinitePolicy) {
+        self.update_stats_with(schema, rows, policy);
         for field in &schema.fields {
-            let mut stats = ColumnStats {
-                value_type: field.value_type.clone(),
-                null_count: 0,
-                min: None,
-                max: None,
+            let Some(stats) = self.column_stats.get_mut(&field.name) else {
+                continue;
             };
+            let mut distinct = HyperLogLog::new();
+            let mut ordered = Vec::new();
+            for value in rows.iter().filter_map(|row| row.get(&field.name)) {
+                if value.is_null() {
+                    continue;
+                }
+                distinct.add(value);
+                if !value.is_non_finite() && is_ordered(&field.value_type) {
+                    ordered.push(value);
+                }
+            }
+            ordered.sort_by(|a, b| a.compare(b).unwrap_or(std::cmp::Ordering::Equal));
+            stats.distinct_count = Some(distinct.estimate());
+            stats.histogram = Histogram::from_sorted(&ordered);
+        }
+    }
+
+    /// Fold newly appended `rows` into the statistics without rescanning
+    /// the rows already counted
+    pub fn record_rows(&mut self, schema: &Schema, rows: &[Tuple]) {
+        self.row_count += rows.len();
+        self.updated_at = Utc::now();
+        self.record_column_stats(schema, rows);
+    }
 
+    /// Account for `old` being overwritten by `new` in place
+    pub fn record_replacement(&mut self, schema: &Schema, old: &Tuple, new: &Tuple) {
+        self.updated_at = Utc::now();
+        for field in &schema.fields {
+            if let Some(stats) = self.column_stats.get_mut(&field.name) {
+                if let Some(value) = old.get(&field.name) {
+                    stats.forget(value);
+                }
+            }
+        }
+        self.record_column_stats(schema, std::slice::from_ref(new));
+    }
+
+    fn record_column_stats(&mut self, schema: &Schema, rows: &[Tuple]) {
+        let policy = self.non_finite;
+        for field in &schema.fields {
+            let stats = self
+                .column_stats
+                .entry(field.name.clone())
+                .or_insert_with(|| ColumnStats {
+                    value_type: field.value_type.clone(),
+                    null_count: 0,
+                    min: None,
+                    max: None,
+                    non_finite_count: 0,
+                    distinct_count: None,
+                    histogram: None,
+                });
             for row in rows {
                 if let Some(value) = row.get(&field.name) {
-                    if value.is_null() {
-                        stats.null_count += 1;
-                    } else {
-                        // Update min
-                        if let Some(ref current_min) = stats.min {
-                            if let Some(ord) = value.compare(current_min) {
-                                if ord == std::cmp::Ordering::Less {
-                                    stats.min = Some(value.clone());
-                                }
-                            }
-                        } else {
-                            stats.min = Some(value.clone());
-                        }
+                    stats.observe(value, policy);
+                }
+            }
+        }
+    }
+}
+
+/// A random subset of rows: `SAMPLE n PERCENT` or `SAMPLE n ROWS`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sample {
+    /// Keep each row independently with probability `n / 100` (Bernoulli)
+    Percent(f64),
+    /// Keep exactly `n` rows, or all of them when there are fewer (reservoir)
+    Rows(usize),
+}
 
-                        // Update max
-                        if let Some(ref current_max) = stats.max {
-                            if let Some(ord) = value.compare(current_max) {
-                                if ord == std::cmp::Ordering::Greater {
-                                    stats.max = Some(value.clone());
-                                }
-                            }
-                        } else {
-                            stats.max = Some(value.clone());
+impl Sample {
+    /// The sampled rows, in input order
+    pub fn apply<T>(&self, rows: impl IntoIterator<Item = T>, rng: &mut Rng) -> Vec<T> {
+        match *self {
+            Sample::Percent(percent) => {
+                let p = percent / 100.0;
+                rows.into_iter().filter(|_| rng.next_f64() < p).collect()
+            }
+            Sample::Rows(n) => {
+                let mut reservoir: Vec<(usize, T)> = Vec::with_capacity(n);
+                for (i, row) in rows.into_iter().enumerate() {
+                    if i < n {
+                        reservoir.push((i, row));
+                    } else {
+                        let slot = rng.below(i + 1);
+                        if slot < n {
+                            reservoir[slot] = (i, row);
                         }
                     }
                 }
+                reservoir.sort_unstable_by_key(|(i, _)| *i);
+                reservoir.into_iter().map(|(_, row)| row).collect()
             }
+        }
+    }
+}
 
-            self.column_stats.insert(field.name.clone(), stats);
+impl std::fmt::Display for Sample {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sample::Percent(percent) => write!(f, "{} PERCENT", percent),
+            Sample::Rows(n) => write!(f, "{} ROWS", n),
         }
     }
 }
 
 use crate::core::index::Index;
+use crate::query::lineage::ColumnLineage;
 use crate::query::logical::Expr;
+use crate::utils::rng::Rng;
+
+/// Schema and rows of a dataset with its lazy columns evaluated
+pub type EvaluatedRows = (Arc<Schema>, Vec<Tuple>);
 
 /// Dataset represents a table-like collection of tuples
 #[derive(Debug, Clone, Serialize)]
@@ -109,6 +444,9 @@ pub struct Dataset {
     pub indices: HashMap<String, Box<dyn Index>>,
     #[serde(skip)]
     pub lazy_expressions: HashMap<String, Expr>, // column_name -> expression for lazy evaluation
+    /// Insertion time of each row appended with `add_row`, used by TTL expiry
+    #[serde(skip)]
+    pub row_inserted_at: Vec<DateTime<Utc>>,
 }
 
 impl Dataset {
@@ -124,6 +462,7 @@ impl Dataset {
             metadata,
             indices: HashMap::new(),
             lazy_expressions: HashMap::new(),
+            row_inserted_at: Vec::new(),
         }
     }
 
@@ -151,9 +490,22 @@ impl Dataset {
             metadata,
             indices: HashMap::new(),
             lazy_expressions: HashMap::new(),
+            row_inserted_at: Vec::new(),
         })
     }
 
+    /// Remove every row, keeping the schema, indices (emptied) and metadata.
+    /// Returns the number of rows removed.
+    pub fn truncate(&mut self) -> Result<usize, String> {
+        let removed = self.rows.len();
+        self.rows.clear();
+        self.row_inserted_at.clear();
+        self.rebuild_indices()?;
+        let policy = self.metadata.non_finite;
+        self.metadata.analyze(&self.schema, &self.rows, policy);
+        Ok(removed)
+    }
+
     /// Retrieve specific rows by their IDs (indices in the rows vector)
     /// Used for optimized query execution via indices
     pub fn get_rows_by_ids(&self, row_ids: &[usize]) -> Vec<Tuple> {
@@ -168,21 +520,310 @@ impl Dataset {
 
     /// Add a row to the dataset
     pub fn add_row(&mut self, row: Tuple) -> Result<(), String> {
+        self.add_rows(vec![row])
+    }
+
+    /// Append `rows`, folding them into the stats. Every row is
+    /// checked first (including PRIMARY KEY and UNIQUE columns against the
+    /// rows already stored), so nothing is added if one is rejected.
+    pub fn add_rows(&mut self, rows: Vec<Tuple>) -> Result<(), String> {
+        let unique_fields: Vec<Field> = self
+            .schema
+            .fields
+            .iter()
+            .filter(|f| f.is_unique())
+            .cloned()
+            .collect();
+        let mut batch_values = vec![std::collections::HashSet::new(); unique_fields.len()];
+        for row in &rows {
+            if !Arc::ptr_eq(&row.schema, &self.schema) {
+                return Err("Row schema does not match dataset schema".to_string());
+            }
+            for (field, seen) in unique_fields.iter().zip(&mut batch_values) {
+                let value = row.get(&field.name).unwrap_or(&Value::Null);
+                if value.is_null() {
+                    continue;
+                }
+                if !seen.insert(value.to_string())
+                    || self.unique_position(&field.name, value)?.is_some()
+                {
+                    return Err(duplicate_value(field, value));
+                }
+            }
+        }
+
+        // Rows that arrived without add_row count as inserted now
+        let now = Utc::now();
+        self.row_inserted_at.resize(self.rows.len(), now);
+        let first_new = self.rows.len();
+        for row in rows {
+            let row_id = self.rows.len();
+            for (col_name, index) in &mut self.indices {
+                if let Some(value) = row.get(col_name) {
+                    index.add(row_id, value)?;
+                }
+            }
+            self.row_inserted_at.push(now);
+            self.rows.push(row);
+        }
+
+        self.metadata
+            .record_rows(&self.schema, &self.rows[first_new..]);
+        Ok(())
+    }
+
+    /// Insert `row`, or replace the row with the same primary key. Returns
+    /// the position of the written row.
+    pub fn upsert_row(&mut self, row: Tuple) -> Result<usize, String> {
         if !Arc::ptr_eq(&row.schema, &self.schema) {
             return Err("Row schema does not match dataset schema".to_string());
         }
+        if self.schema.primary_key().is_none() {
+            return Err("UPSERT needs a PRIMARY KEY column".to_string());
+        }
+
+        let Some(pos) = self.primary_key_position(&row)? else {
+            self.add_row(row)?;
+            return Ok(self.rows.len() - 1);
+        };
+        // The replacement may not take a UNIQUE value held by another row
+        let unique_fields: Vec<Field> = self
+            .schema
+            .fields
+            .iter()
+            .filter(|f| f.unique && !f.primary_key)
+            .cloned()
+            .collect();
+        for field in &unique_fields {
+            let value = row.get(&field.name).unwrap_or(&Value::Null);
+            if !value.is_null()
+                && self
+                    .unique_position(&field.name, value)?
+                    .is_some_and(|other| other != pos)
+            {
+                return Err(duplicate_value(field, value));
+            }
+        }
+
+        // The key index is unchanged; others only if their column changed
+        let reindex = self
+            .indices
+            .keys()
+            .any(|col| self.rows[pos].get(col) != row.get(col));
+        let now = Utc::now();
+        self.row_inserted_at.resize(self.rows.len(), now);
+        self.row_inserted_at[pos] = now;
+        let old = std::mem::replace(&mut self.rows[pos], row);
+        if reindex {
+            self.rebuild_indices()?;
+        }
+        self.metadata
+            .record_replacement(&self.schema, &old, &self.rows[pos]);
+        Ok(pos)
+    }
+
+    /// Position of the row sharing the primary key of `row`, found through a
+    /// hash index on the key column (created on first use). `None` when the
+    /// key is new or the schema has no primary key.
+    fn primary_key_position(&mut self, row: &Tuple) -> Result<Option<usize>, String> {
+        let Some(key) = self.schema.primary_key() else {
+            return Ok(None);
+        };
+        let column = key.name.clone();
+        let value = row.get(&column).unwrap_or(&Value::Null);
+        self.unique_position(&column, value)
+    }
+
+    /// Position of a row holding `value` in `column`, found through a hash
+    /// index on the column (created on first use)
+    fn unique_position(&mut self, column: &str, value: &Value) -> Result<Option<usize>, String> {
+        if !self.indices.contains_key(column) {
+            let index = Box::new(crate::core::index::hash::HashIndex::new());
+            self.create_index(column.to_string(), index)?;
+        }
+
+        let candidates = self.indices[column].lookup(value)?;
+        // Hash keys are string renderings, so confirm the actual value
+        Ok(candidates
+            .into_iter()
+            .find(|&i| self.rows.get(i).and_then(|r| r.get(column)) == Some(value)))
+    }
+
+    /// Time-to-live from the `ttl` metadata key, in seconds. Zero or an
+    /// unparsable value means no TTL.
+    pub fn ttl(&self) -> Option<chrono::Duration> {
+        let secs: i64 = self.metadata.extra.get(TTL_METADATA_KEY)?.parse().ok()?;
+        (secs > 0).then(|| chrono::Duration::seconds(secs))
+    }
+
+    /// Whether the TTL applies to the whole dataset instead of single rows
+    pub fn ttl_expires_dataset(&self) -> bool {
+        self.metadata
+            .extra
+            .get(TTL_SCOPE_METADATA_KEY)
+            .is_some_and(|s| s.eq_ignore_ascii_case("dataset"))
+    }
+
+    /// True if the dataset has a dataset-scoped TTL and was not written to for that long
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        match self.ttl() {
+            Some(ttl) if self.ttl_expires_dataset() => self.metadata.updated_at + ttl <= now,
+            _ => false,
+        }
+    }
+
+    /// Drop rows inserted more than the row TTL before `now` and rebuild the
+    /// indices. Returns the number of rows removed.
+    pub fn expire_rows(&mut self, now: DateTime<Utc>) -> Result<usize, String> {
+        let ttl = match self.ttl() {
+            Some(ttl) if !self.ttl_expires_dataset() => ttl,
+            _ => return Ok(0),
+        };
+
+        // Rows without a recorded insertion time start their TTL now
+        self.row_inserted_at.resize(self.rows.len(), now);
+        let keep: Vec<bool> = self
+            .row_inserted_at
+            .iter()
+            .map(|inserted| *inserted + ttl > now)
+            .collect();
+        let removed = keep.iter().filter(|k| !**k).count();
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        let mut kept = keep.iter();
+        self.rows.retain(|_| *kept.next().unwrap());
+        let mut kept = keep.iter();
+        self.row_inserted_at.retain(|_| *kept.next().unwrap());
+
+        // Row ids shifted, so every index is rebuilt from scratch
+        self.rebuild_indices()?;
+
+        self.metadata.update_stats(&self.schema, &self.rows);
+        Ok(removed)
+    }
+
+    /// Remove the rows matching `predicate`, rebuilding indices and stats.
+    /// Returns the removed rows.
+    pub fn delete_rows<F>(&mut self, predicate: F) -> Result<Vec<Tuple>, String>
+    where
+        F: Fn(&Tuple) -> bool,
+    {
+        let remove: Vec<bool> = self.rows.iter().map(&predicate).collect();
+        if !remove.contains(&true) {
+            return Ok(Vec::new());
+        }
 
-        let row_id = self.rows.len();
+        self.row_inserted_at.resize(self.rows.len(), Utc::now());
+        let mut flags = remove.iter();
+        self.row_inserted_at.retain(|_| !*flags.next().unwrap());
 
-        // Update indices
-        for (col_name, index) in &mut self.indices {
-            if let Some(value) = row.get(col_name) {
-                index.add(row_id, value)?;
+        let mut removed = Vec::new();
+        for (row, remove) in std::mem::take(&mut self.rows).into_iter().zip(remove) {
+            if remove {
+                removed.push(row);
+            } else {
+                self.rows.push(row);
             }
         }
 
-        self.rows.push(row);
+        // Row ids shifted, so every index is rebuilt from scratch
+        self.rebuild_indices()?;
         self.metadata.update_stats(&self.schema, &self.rows);
+        Ok(removed)
+    }
+
+    /// Set columns of the rows matching `predicate`. Each assignment is
+    /// evaluated against the row before the update and cast to the column
+    /// type. Nothing changes if any assignment fails. Returns the positions
+    /// of the updated rows.
+    pub fn update_rows<F>(
+        &mut self,
+        predicate: F,
+        assignments: &[(String, Expr)],
+    ) -> Result<Vec<usize>, String>
+    where
+        F: Fn(&Tuple) -> bool,
+    {
+        use crate::query::physical::evaluate_expression;
+
+        let mut targets = Vec::with_capacity(assignments.len());
+        for (column, expr) in assignments {
+            let pos = self.column_position(column)?;
+            if self.lazy_expressions.contains_key(column) {
+                return Err(format!("Cannot update lazy column '{}'", column));
+            }
+            targets.push((pos, expr));
+        }
+
+        let mut updates = Vec::new();
+        for (i, row) in self.rows.iter().enumerate() {
+            if !predicate(row) {
+                continue;
+            }
+            let mut values = row.values.clone();
+            for (pos, expr) in &targets {
+                let field = &self.schema.fields[*pos];
+                values[*pos] = evaluate_expression(expr, row)
+                    .cast_to(&field.value_type)
+                    .map_err(|e| format!("Column '{}': {}", field.name, e))?;
+            }
+            updates.push((i, Tuple::new(self.schema.clone(), values)?));
+        }
+        for field in self.schema.fields.iter().filter(|f| f.is_unique()) {
+            let column_pos = self.column_position(&field.name)?;
+            if targets.iter().any(|(pos, _)| *pos == column_pos) {
+                self.check_unique_values(field, &updates)?;
+            }
+        }
+
+        let updated: Vec<usize> = updates.iter().map(|(i, _)| *i).collect();
+        for (i, row) in updates {
+            self.rows[i] = row;
+        }
+        if !updated.is_empty() {
+            self.rebuild_indices()?;
+            self.metadata.update_stats(&self.schema, &self.rows);
+        }
+        Ok(updated)
+    }
+
+    /// Fail if applying `updates` would leave two rows with the same value
+    /// of the PRIMARY KEY or UNIQUE column `field`
+    fn check_unique_values(&self, field: &Field, updates: &[(usize, Tuple)]) -> Result<(), String> {
+        let mut rows: Vec<&Tuple> = self.rows.iter().collect();
+        for (i, row) in updates {
+            rows[*i] = row;
+        }
+        let mut seen = std::collections::HashSet::new();
+        for row in rows {
+            let value = row.get(&field.name).unwrap_or(&Value::Null);
+            if !value.is_null() && !seen.insert(value.to_string()) {
+                return Err(duplicate_value(field, value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rebuild every index from the current rows
+    fn rebuild_indices(&mut self) -> Result<(), String> {
+        for (col_name, index) in self.indices.iter_mut() {
+            let mut rebuilt: Box<dyn Index> = match index.index_type() {
+                crate::core::index::IndexType::Hash => {
+                    Box::new(crate::core::index::hash::HashIndex::new())
+                }
+                crate::core::index::IndexType::Vector => {
+                    Box::new(crate::core::index::vector::VectorIndex::new())
+                }
+            };
+            for (row_id, row) in self.rows.iter().enumerate() {
+                if let Some(value) = row.get(col_name) {
+                    rebuilt.add(row_id, value)?;
+                }
+            }
+            *index = rebuilt;
+        }
         Ok(())
     }
 
@@ -210,6 +851,7 @@ impl Dataset {
             metadata: self.metadata.clone(),
             indices: HashMap::new(), // Indices are not preserved on filter for now
             lazy_expressions: self.lazy_expressions.clone(), // Preserve lazy expressions
+            row_inserted_at: Vec::new(),
         };
 
         new_dataset
@@ -261,6 +903,7 @@ impl Dataset {
             metadata: self.metadata.clone(),
             indices: HashMap::new(),
             lazy_expressions: new_lazy_expressions,
+            row_inserted_at: Vec::new(),
         };
 
         new_dataset
@@ -280,6 +923,27 @@ impl Dataset {
             metadata: self.metadata.clone(),
             indices: HashMap::new(),
             lazy_expressions: self.lazy_expressions.clone(),
+            row_inserted_at: Vec::new(),
+        };
+
+        new_dataset
+            .metadata
+            .update_stats(&self.schema, &new_dataset.rows);
+        new_dataset
+    }
+
+    /// Random subset of the rows, see `Sample`
+    pub fn sample(&self, sample: Sample, rng: &mut Rng) -> Self {
+        let sampled_rows = sample.apply(self.rows.iter().cloned(), rng);
+
+        let mut new_dataset = Self {
+            id: self.id,
+            schema: self.schema.clone(),
+            rows: sampled_rows,
+            metadata: self.metadata.clone(),
+            indices: HashMap::new(),
+            lazy_expressions: self.lazy_expressions.clone(),
+            row_inserted_at: Vec::new(),
         };
 
         new_dataset
@@ -299,6 +963,7 @@ impl Dataset {
             metadata: self.metadata.clone(),
             indices: HashMap::new(),
             lazy_expressions: self.lazy_expressions.clone(),
+            row_inserted_at: Vec::new(),
         };
 
         new_dataset
@@ -335,6 +1000,7 @@ impl Dataset {
             metadata: self.metadata.clone(),
             indices: HashMap::new(),
             lazy_expressions: self.lazy_expressions.clone(),
+            row_inserted_at: Vec::new(),
         })
     }
 
@@ -352,6 +1018,7 @@ impl Dataset {
             metadata: self.metadata.clone(),
             indices: HashMap::new(),
             lazy_expressions: self.lazy_expressions.clone(),
+            row_inserted_at: Vec::new(),
         };
 
         new_dataset
@@ -396,21 +1063,37 @@ impl Dataset {
     pub fn create_index(
         &mut self,
         column_name: String,
-        mut index: Box<dyn Index>,
+        index: Box<dyn Index>,
     ) -> Result<(), String> {
-        if !self.schema_has_field(&column_name) {
+        if let Some(index) = self.populate_index(&column_name, index, |_| true)? {
+            self.indices.insert(column_name, index);
+        }
+        Ok(())
+    }
+
+    /// Fill `index` with the values of a column without attaching it.
+    /// `keep_going` gets the number of rows added after each one; once it
+    /// returns false the build stops and `None` is returned.
+    pub fn populate_index(
+        &self,
+        column_name: &str,
+        mut index: Box<dyn Index>,
+        mut keep_going: impl FnMut(usize) -> bool,
+    ) -> Result<Option<Box<dyn Index>>, String> {
+        if !self.schema_has_field(column_name) {
             return Err(format!("Column '{}' not found in schema", column_name));
         }
 
         // Populate index with existing data
         for (i, row) in self.rows.iter().enumerate() {
-            if let Some(val) = row.get(&column_name) {
+            if let Some(val) = row.get(column_name) {
                 index.add(i, val)?;
             }
+            if !keep_going(i + 1) {
+                return Ok(None);
+            }
         }
-
-        self.indices.insert(column_name, index);
-        Ok(())
+        Ok(Some(index))
     }
 
     /// Get index for a column
@@ -452,6 +1135,8 @@ impl Dataset {
             value_type,
             nullable,
             is_lazy: false,
+            primary_key: false,
+            unique: false,
         });
         let new_schema = Arc::new(Schema::new(new_fields));
 
@@ -471,6 +1156,120 @@ impl Dataset {
         Ok(())
     }
 
+    /// Remove a column, together with its index and lazy expression
+    pub fn drop_column(&mut self, column_name: &str) -> Result<(), String> {
+        let pos = self.column_position(column_name)?;
+        self.check_unmasked(column_name)?;
+        if self.schema.fields.len() == 1 {
+            return Err(format!(
+                "Cannot drop '{}': it is the only column of the dataset",
+                column_name
+            ));
+        }
+
+        let mut new_fields = self.schema.fields.clone();
+        new_fields.remove(pos);
+        let new_schema = Arc::new(Schema::new(new_fields));
+
+        let mut new_rows = Vec::with_capacity(self.rows.len());
+        for row in &self.rows {
+            let mut new_values = row.values.clone();
+            new_values.remove(pos);
+            new_rows.push(Tuple::new(new_schema.clone(), new_values)?);
+        }
+
+        self.schema = new_schema;
+        self.rows = new_rows;
+        self.indices.remove(column_name);
+        self.lazy_expressions.remove(column_name);
+        self.metadata.update_stats(&self.schema, &self.rows);
+        Ok(())
+    }
+
+    /// Rename a column; its index and lazy expression follow it
+    pub fn rename_column(&mut self, old_name: &str, new_name: String) -> Result<(), String> {
+        let pos = self.column_position(old_name)?;
+        self.check_unmasked(old_name)?;
+        if self.schema_has_field(&new_name) {
+            return Err(format!("Column '{}' already exists", new_name));
+        }
+
+        let mut new_fields = self.schema.fields.clone();
+        new_fields[pos].name = new_name.clone();
+        let new_schema = Arc::new(Schema::new(new_fields));
+
+        let mut new_rows = Vec::with_capacity(self.rows.len());
+        for row in &self.rows {
+            new_rows.push(Tuple::new(new_schema.clone(), row.values.clone())?);
+        }
+
+        self.schema = new_schema;
+        self.rows = new_rows;
+        if let Some(index) = self.indices.remove(old_name) {
+            self.indices.insert(new_name.clone(), index);
+        }
+        if let Some(expr) = self.lazy_expressions.remove(old_name) {
+            self.lazy_expressions.insert(new_name, expr);
+        }
+        self.metadata.update_stats(&self.schema, &self.rows);
+        Ok(())
+    }
+
+    /// Change a column's type, casting every stored value. Fails without
+    /// modifying the dataset if any value cannot be cast.
+    pub fn alter_column_type(
+        &mut self,
+        column_name: &str,
+        value_type: ValueType,
+    ) -> Result<(), String> {
+        let pos = self.column_position(column_name)?;
+        if self.lazy_expressions.contains_key(column_name) {
+            return Err(format!(
+                "Cannot change the type of lazy column '{}'",
+                column_name
+            ));
+        }
+
+        let mut new_fields = self.schema.fields.clone();
+        new_fields[pos].value_type = value_type.clone();
+        let new_schema = Arc::new(Schema::new(new_fields));
+
+        let mut new_rows = Vec::with_capacity(self.rows.len());
+        for (i, row) in self.rows.iter().enumerate() {
+            let mut new_values = row.values.clone();
+            new_values[pos] = row.values[pos]
+                .cast_to(&value_type)
+                .map_err(|e| format!("Row {}: {}", i, e))?;
+            new_rows.push(Tuple::new(new_schema.clone(), new_values)?);
+        }
+
+        self.schema = new_schema;
+        self.rows = new_rows;
+        // Indexed values changed type, so lookups need a fresh index
+        self.rebuild_indices()?;
+        self.metadata.update_stats(&self.schema, &self.rows);
+        Ok(())
+    }
+
+    fn column_position(&self, column_name: &str) -> Result<usize, String> {
+        self.schema
+            .fields
+            .iter()
+            .position(|f| f.name == column_name)
+            .ok_or_else(|| format!("Column '{}' not found", column_name))
+    }
+
+    /// Masked columns keep their name, so a policy cannot be lost by accident
+    fn check_unmasked(&self, column_name: &str) -> Result<(), String> {
+        if self.metadata.masking.contains_key(column_name) {
+            return Err(format!(
+                "Column '{}' has a masking policy; remove it first with UNMASK COLUMN",
+                column_name
+            ));
+        }
+        Ok(())
+    }
+
     /// Add a computed column to the dataset
     /// This evaluates an expression for each row and adds the result as a new column
     /// If lazy is true, stores NULL placeholders and evaluates on access
@@ -494,6 +1293,8 @@ impl Dataset {
             value_type: value_type.clone(),
             nullable: lazy, // Lazy columns can have NULL placeholders
             is_lazy: lazy,
+            primary_key: false,
+            unique: false,
         };
         new_fields.push(new_field.clone());
         let new_schema = Arc::new(Schema::new(new_fields));
@@ -582,18 +1383,22 @@ impl Dataset {
 
     /// Materialize all lazy columns (convert to regular columns with computed values)
     pub fn materialize_lazy_columns(&mut self) -> Result<(), String> {
-        let lazy_columns: Vec<String> = self
-            .schema
-            .fields
-            .iter()
-            .filter(|f| f.is_lazy)
-            .map(|f| f.name.clone())
-            .collect();
-
-        if lazy_columns.is_empty() {
+        if !self.schema.fields.iter().any(|f| f.is_lazy) {
             return Ok(()); // Nothing to materialize
         }
+        if let Some((schema, rows)) = self.evaluate_lazy_columns(|_| true)? {
+            self.replace_lazy_columns(schema, rows);
+        }
+        Ok(())
+    }
 
+    /// Schema and rows with every lazy column evaluated, leaving the dataset
+    /// as it is. `keep_going` gets the number of rows evaluated after each
+    /// one; once it returns false evaluation stops and `None` is returned.
+    pub fn evaluate_lazy_columns(
+        &self,
+        mut keep_going: impl FnMut(usize) -> bool,
+    ) -> Result<Option<EvaluatedRows>, String> {
         // Evaluate all lazy columns for all rows
         use crate::query::physical::evaluate_expression;
         let mut new_rows = Vec::with_capacity(self.rows.len());
@@ -612,6 +1417,9 @@ impl Dataset {
             }
 
             new_rows.push(Tuple::new(self.schema.clone(), new_values)?);
+            if !keep_going(new_rows.len()) {
+                return Ok(None);
+            }
         }
 
         // Update schema to mark columns as non-lazy
@@ -621,19 +1429,33 @@ impl Dataset {
                 field.is_lazy = false;
             }
         }
-        let new_schema = Arc::new(Schema::new(new_fields));
-
-        // Update dataset
-        self.rows = new_rows;
-        self.schema = new_schema;
+        Ok(Some((Arc::new(Schema::new(new_fields)), new_rows)))
+    }
 
+    /// Take the schema and rows computed by `evaluate_lazy_columns`
+    pub fn replace_lazy_columns(&mut self, schema: Arc<Schema>, rows: Vec<Tuple>) {
         // Clear lazy expressions (they're now materialized)
-        for col_name in &lazy_columns {
-            self.lazy_expressions.remove(col_name);
+        for field in &self.schema.fields {
+            if field.is_lazy {
+                self.lazy_expressions.remove(&field.name);
+            }
         }
 
+        // Update dataset
+        self.rows = rows;
+        self.schema = schema;
         self.metadata.update_stats(&self.schema, &self.rows);
-        Ok(())
+    }
+}
+
+fn duplicate_value(field: &Field, value: &Value) -> String {
+    if field.primary_key {
+        format!("Duplicate primary key {} in column '{}'", value, field.name)
+    } else {
+        format!(
+            "UNIQUE constraint violated: duplicate value {} in column '{}'",
+            value, field.name
+        )
     }
 }
 
@@ -1,4 +1,4 @@
-use super::{Index, IndexType};
+use super::{Index, IndexSnapshot, IndexType};
 use crate::core::tensor::Tensor;
 use crate::core::value::Value;
 use std::collections::HashMap;
@@ -18,6 +18,11 @@ impl HashIndex {
         }
     }
 
+    /// Restore an index from previously captured entries
+    pub fn from_map(map: HashMap<String, Vec<usize>>) -> Self {
+        Self { map }
+    }
+
     /// Helper to convert Value to a string key for the HashMap
     /// We use String keys because f32 isn't Hash/Eq, and Value derived traits can be tricky
     fn get_key(value: &Value) -> String {
@@ -28,6 +33,9 @@ impl HashIndex {
             Value::Bool(b) => b.to_string(),
             Value::Vector(v) => format!("{:?}", v),
             Value::Matrix(m) => format!("{:?}", m),
+            Value::Timestamp(ts) => ts.to_rfc3339(),
+            Value::Json(j) => j.to_string(),
+            Value::List(_) => value.to_string(),
             Value::Null => "NULL".to_string(),
         }
     }
@@ -58,4 +66,10 @@ impl Index for HashIndex {
             map: self.map.clone(),
         })
     }
+
+    fn snapshot(&self) -> IndexSnapshot {
+        IndexSnapshot::Hash {
+            map: self.map.clone(),
+        }
+    }
 }
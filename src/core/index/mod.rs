@@ -1,9 +1,11 @@
 use crate::core::tensor::Tensor;
 use crate::core::value::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
 /// Types of supported indices
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum IndexType {
     /// Exact match index (hash map based)
     Hash,
@@ -30,6 +32,37 @@ pub trait Index: Send + Sync + Debug {
 
     /// Clone the index box
     fn box_clone(&self) -> Box<dyn Index>;
+
+    /// Capture the index contents in a serializable form
+    fn snapshot(&self) -> IndexSnapshot;
+}
+
+/// Serializable contents of an index, used by the storage layer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum IndexSnapshot {
+    Hash { map: HashMap<String, Vec<usize>> },
+    Vector { vectors: Vec<(usize, Tensor)> },
+}
+
+impl IndexSnapshot {
+    /// Get the type of the captured index
+    pub fn index_type(&self) -> IndexType {
+        match self {
+            IndexSnapshot::Hash { .. } => IndexType::Hash,
+            IndexSnapshot::Vector { .. } => IndexType::Vector,
+        }
+    }
+
+    /// Rebuild a live index from the snapshot without rescanning rows
+    pub fn into_index(self) -> Box<dyn Index> {
+        match self {
+            IndexSnapshot::Hash { map } => Box::new(hash::HashIndex::from_map(map)),
+            IndexSnapshot::Vector { vectors } => {
+                Box::new(vector::VectorIndex::from_vectors(vectors))
+            }
+        }
+    }
 }
 
 impl Clone for Box<dyn Index> {
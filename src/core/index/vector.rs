@@ -1,4 +1,4 @@
-use super::{Index, IndexType};
+use super::{Index, IndexSnapshot, IndexType};
 use crate::core::tensor::Tensor;
 use crate::core::value::Value;
 
@@ -17,6 +17,11 @@ impl VectorIndex {
         }
     }
 
+    /// Restore an index from previously captured embeddings
+    pub fn from_vectors(vectors: Vec<(usize, Tensor)>) -> Self {
+        Self { vectors }
+    }
+
     /// Calculate cosine similarity between two tensors
     fn cosine_similarity(t1: &Tensor, t2: &Tensor) -> Result<f32, String> {
         if t1.shape != t2.shape {
@@ -57,6 +62,9 @@ impl Index for VectorIndex {
             Value::Null => Ok(()),
             Value::Float(_) => Err("Cannot index Float as Vector".to_string()),
             Value::Matrix(_) => Err("Cannot index Matrix as Vector".to_string()),
+            Value::Timestamp(_) => Err("Cannot index Timestamp as Vector".to_string()),
+            Value::Json(_) => Err("Cannot index Json as Vector".to_string()),
+            Value::List(_) => Err("Cannot index List as Vector".to_string()),
         }
     }
 
@@ -88,4 +96,10 @@ impl Index for VectorIndex {
             vectors: self.vectors.clone(),
         })
     }
+
+    fn snapshot(&self) -> IndexSnapshot {
+        IndexSnapshot::Vector {
+            vectors: self.vectors.clone(),
+        }
+    }
 }
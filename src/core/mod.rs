@@ -3,6 +3,7 @@ pub mod config;
 pub mod dataset;
 pub mod dataset_legacy;
 pub mod index;
+pub mod sketch;
 pub mod storage;
 pub mod store;
 pub mod tensor;
@@ -0,0 +1,242 @@
+//! Fixed-size summaries for approximate aggregates: HyperLogLog for distinct
+//! counts and a merging t-digest for quantiles. Both use bounded memory
+//! however many values they see.
+
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+/// Register index bits; 2^12 registers give about 1.6% standard error
+const HLL_PRECISION: u32 = 12;
+
+/// HyperLogLog distinct-count sketch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HyperLogLog {
+    pub fn new() -> Self {
+        Self {
+            registers: vec![0; 1 << HLL_PRECISION],
+        }
+    }
+
+    pub fn add<T: Hash + ?Sized>(&mut self, item: &T) {
+        // SipHash with fixed keys, so estimates are reproducible between runs
+        let mut hasher = std::hash::DefaultHasher::new();
+        item.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let index = (hash >> (64 - HLL_PRECISION)) as usize;
+        let rest = hash << HLL_PRECISION;
+        let rank = (rest.leading_zeros() + 1).min(64 - HLL_PRECISION + 1) as u8;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Fold another sketch in, as if its items had been added here
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for (mine, theirs) in self.registers.iter_mut().zip(&other.registers) {
+            *mine = (*mine).max(*theirs);
+        }
+    }
+
+    /// Estimated number of distinct items added
+    pub fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let raw = alpha * m * m / sum;
+
+        // Linear counting is more accurate while many registers are still empty
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            raw.round() as u64
+        }
+    }
+}
+
+/// Compression of the t-digest; larger keeps more centroids and is more exact
+const TDIGEST_COMPRESSION: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// Merging t-digest quantile sketch. Centroids near the tails stay small,
+/// so extreme quantiles are the most accurate.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct TDigest {
+    centroids: Vec<Centroid>,
+    buffer: Vec<f64>,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a value; NaN is ignored
+    pub fn add(&mut self, x: f64) {
+        if x.is_nan() {
+            return;
+        }
+        if self.is_empty() {
+            (self.min, self.max) = (x, x);
+        } else {
+            self.min = self.min.min(x);
+            self.max = self.max.max(x);
+        }
+        self.buffer.push(x);
+        if self.buffer.len() >= 5 * TDIGEST_COMPRESSION as usize {
+            self.compress();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.centroids.is_empty() && self.buffer.is_empty()
+    }
+
+    /// Merge buffered values into the centroids
+    fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let mut items: Vec<Centroid> = std::mem::take(&mut self.centroids);
+        items.extend(
+            self.buffer
+                .drain(..)
+                .map(|mean| Centroid { mean, weight: 1.0 }),
+        );
+        items.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+
+        // Each centroid spans at most one unit of the scale function
+        // k(q) = compression / 2pi * asin(2q - 1), which is steepest at the tails
+        let k = |q: f64| TDIGEST_COMPRESSION / std::f64::consts::TAU * (2.0 * q - 1.0).asin();
+        let k_inv = |k: f64| ((std::f64::consts::TAU * k / TDIGEST_COMPRESSION).sin() + 1.0) / 2.0;
+
+        let total: f64 = items.iter().map(|c| c.weight).sum();
+        let mut merged = Vec::with_capacity(items.len());
+        let mut current = items[0];
+        let mut before = 0.0;
+        let mut q_limit = k_inv(k(0.0) + 1.0);
+        for next in items.into_iter().skip(1) {
+            if (before + current.weight + next.weight) / total <= q_limit {
+                let weight = current.weight + next.weight;
+                current.mean += (next.mean - current.mean) * next.weight / weight;
+                current.weight = weight;
+            } else {
+                before += current.weight;
+                merged.push(current);
+                current = next;
+                q_limit = k_inv(k(before / total) + 1.0);
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    /// Estimated value at quantile `q` in [0, 1], or `None` when empty
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if !self.buffer.is_empty() {
+            let mut merged = self.clone();
+            merged.compress();
+            return merged.quantile(q);
+        }
+        let centroids = &self.centroids;
+        let (first, last) = (centroids.first()?, centroids.last()?);
+        if centroids.len() == 1 {
+            return Some(first.mean);
+        }
+
+        let total: f64 = centroids.iter().map(|c| c.weight).sum();
+        let target = q.clamp(0.0, 1.0) * total;
+        // Each centroid sits at the middle of the weight it covers
+        if target <= first.weight / 2.0 {
+            let t = target / (first.weight / 2.0);
+            return Some(self.min + (first.mean - self.min) * t);
+        }
+        if target >= total - last.weight / 2.0 {
+            let t = (total - target) / (last.weight / 2.0);
+            return Some(self.max - (self.max - last.mean) * t);
+        }
+
+        let mut center = first.weight / 2.0;
+        for pair in centroids.windows(2) {
+            let next_center = center + (pair[0].weight + pair[1].weight) / 2.0;
+            if target <= next_center {
+                let t = (target - center) / (next_center - center);
+                return Some(pair[0].mean + (pair[1].mean - pair[0].mean) * t);
+            }
+            center = next_center;
+        }
+        Some(last.mean)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hyperloglog_estimate() {
+        let mut hll = HyperLogLog::new();
+        assert_eq!(hll.estimate(), 0);
+        for i in 0..50_000 {
+            hll.add(&(i % 20_000));
+        }
+        let estimate = hll.estimate() as f64;
+        assert!(
+            (estimate - 20_000.0).abs() / 20_000.0 < 0.05,
+            "{}",
+            estimate
+        );
+
+        let mut other = HyperLogLog::new();
+        for i in 20_000..30_000 {
+            other.add(&i);
+        }
+        hll.merge(&other);
+        let estimate = hll.estimate() as f64;
+        assert!(
+            (estimate - 30_000.0).abs() / 30_000.0 < 0.05,
+            "{}",
+            estimate
+        );
+    }
+
+    #[test]
+    fn test_tdigest_quantiles() {
+        let mut digest = TDigest::new();
+        assert_eq!(digest.quantile(0.5), None);
+        // Shuffled 0..=99_999
+        for i in 0..100_000u64 {
+            digest.add(((i * 7_919) % 100_000) as f64);
+        }
+        assert_eq!(digest.quantile(0.0), Some(0.0));
+        assert_eq!(digest.quantile(1.0), Some(99_999.0));
+        for q in [0.01, 0.25, 0.5, 0.9, 0.99] {
+            let estimate = digest.quantile(q).unwrap();
+            assert!(
+                (estimate - q * 99_999.0).abs() < 500.0,
+                "q={} got {}",
+                q,
+                estimate
+            );
+        }
+        assert!(digest.centroids.len() < 500);
+    }
+}
@@ -1,18 +1,30 @@
+use crate::core::config::{ParquetCompression, StorageConfig};
 use crate::core::dataset_legacy::{Dataset, DatasetMetadata};
-use crate::core::tensor::Tensor;
+use crate::core::index::IndexSnapshot;
+use crate::core::tensor::{Tensor, TensorId};
 use crate::core::tuple::{Schema, Tuple};
 use crate::core::value::{Value, ValueType};
-use arrow::array::{Array, ArrayRef, BooleanArray, Float32Array, Int64Array, StringArray};
-use arrow::datatypes::{DataType, Field as ArrowField, Schema as ArrowSchema};
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, Float32Array, Int64Array, StringArray,
+    TimestampMicrosecondArray,
+};
+use arrow::datatypes::{DataType, Field as ArrowField, Schema as ArrowSchema, TimeUnit};
 use arrow::record_batch::RecordBatch;
 use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use parquet::arrow::arrow_writer::ArrowWriter;
-use parquet::file::properties::WriterProperties;
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::{EnabledStatistics, WriterProperties};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
 use thiserror::Error;
 
+pub mod export;
+pub mod npy;
+pub mod object_storage;
+pub mod wal;
+
 #[derive(Error, Debug)]
 pub enum StorageError {
     #[error("IO error: {0}")]
@@ -27,6 +39,12 @@ pub enum StorageError {
     #[error("Arrow error: {0}")]
     Arrow(#[from] arrow::error::ArrowError),
 
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[error("Storage backend error: {0}")]
+    Backend(String),
+
     #[error("Dataset not found: {0}")]
     DatasetNotFound(String),
 
@@ -67,18 +85,83 @@ pub trait StorageEngine {
     fn list_tensors(&self) -> Result<Vec<String>, StorageError>;
 }
 
+/// Rows per Parquet row group when `[storage] parquet_row_group_rows` is not set
+pub const DEFAULT_ROW_GROUP_ROWS: usize = 64 * 1024;
+
+/// How datasets are encoded as Parquet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParquetWriteOptions {
+    /// Rows converted to Arrow and written at a time, one row group each
+    pub row_group_rows: usize,
+    pub compression: ParquetCompression,
+    pub dictionary: bool,
+    /// Column chunk and page statistics (min/max, null count)
+    pub statistics: bool,
+}
+
+impl Default for ParquetWriteOptions {
+    fn default() -> Self {
+        Self {
+            row_group_rows: DEFAULT_ROW_GROUP_ROWS,
+            compression: ParquetCompression::default(),
+            dictionary: true,
+            statistics: true,
+        }
+    }
+}
+
+impl ParquetWriteOptions {
+    pub fn from_config(config: &StorageConfig) -> Self {
+        Self {
+            row_group_rows: config.parquet_row_group_rows.max(1),
+            compression: config.parquet_compression,
+            dictionary: config.parquet_dictionary,
+            statistics: config.parquet_statistics,
+        }
+    }
+
+    fn writer_properties(&self) -> WriterProperties {
+        let compression = match self.compression {
+            ParquetCompression::None => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+            ParquetCompression::Lz4 => Compression::LZ4_RAW,
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        };
+        let statistics = if self.statistics {
+            EnabledStatistics::Page
+        } else {
+            EnabledStatistics::None
+        };
+        WriterProperties::builder()
+            .set_max_row_group_size(self.row_group_rows.max(1))
+            .set_compression(compression)
+            .set_dictionary_enabled(self.dictionary)
+            .set_statistics_enabled(statistics)
+            .build()
+    }
+}
+
 /// Parquet-based storage implementation
 pub struct ParquetStorage {
     base_path: String,
+    options: ParquetWriteOptions,
 }
 
 impl ParquetStorage {
     pub fn new(base_path: impl Into<String>) -> Self {
         Self {
             base_path: base_path.into(),
+            options: ParquetWriteOptions::default(),
         }
     }
 
+    /// Write datasets with `options` instead of the defaults
+    pub fn with_options(mut self, options: ParquetWriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     fn dataset_path(&self, name: &str) -> String {
         format!("{}/datasets/{}.parquet", self.base_path, name)
     }
@@ -87,7 +170,21 @@ impl ParquetStorage {
         format!("{}/datasets/{}.meta.json", self.base_path, name)
     }
 
+    fn indices_path(&self, name: &str) -> String {
+        format!("{}/datasets/{}.indices.json", self.base_path, name)
+    }
+
     fn tensor_path(&self, name: &str) -> String {
+        format!("{}/tensors/{}.npy", self.base_path, name)
+    }
+
+    /// Tensor id, kept beside the `.npy` file since NPY headers have fixed keys
+    fn tensor_meta_path(&self, name: &str) -> String {
+        format!("{}/tensors/{}.meta.json", self.base_path, name)
+    }
+
+    /// Tensors written before the `.npy` layout were stored as JSON
+    fn legacy_tensor_path(&self, name: &str) -> String {
         format!("{}/tensors/{}.json", self.base_path, name)
     }
 
@@ -99,11 +196,129 @@ impl ParquetStorage {
         Ok(())
     }
 
-    /// Convert Dataset to Arrow RecordBatch
-    fn dataset_to_record_batch(&self, dataset: &Dataset) -> Result<RecordBatch, StorageError> {
-        // Build Arrow schema from dataset schema
-        let arrow_fields: Vec<ArrowField> = dataset
-            .schema
+    /// Write a dataset as one standalone Parquet file, without the metadata
+    /// and index files written by `save_dataset`
+    pub fn write_parquet_file(
+        dataset: &Dataset,
+        path: &Path,
+        options: ParquetWriteOptions,
+    ) -> Result<(), StorageError> {
+        let file = fs::File::create(path)?;
+        Self::write_dataset_parquet(dataset, file, options)
+    }
+
+    /// Encode the rows of a dataset as Parquet into `writer`. Rows are
+    /// converted to Arrow `options.row_group_rows` at a time and each chunk
+    /// is flushed as its own row group, so only one chunk is held as arrays.
+    pub(crate) fn write_dataset_parquet<W: std::io::Write + Send>(
+        dataset: &Dataset,
+        writer: W,
+        options: ParquetWriteOptions,
+    ) -> Result<(), StorageError> {
+        let arrow_schema = Self::arrow_schema(&dataset.schema);
+        let props = options.writer_properties();
+        let mut writer = ArrowWriter::try_new(writer, arrow_schema.clone(), Some(props))?;
+        for chunk in dataset.rows.chunks(options.row_group_rows.max(1)) {
+            let record_batch = Self::rows_to_record_batch(&dataset.schema, &arrow_schema, chunk)?;
+            writer.write(&record_batch)?;
+        }
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Decode Parquet data written by `write_dataset_parquet`
+    pub(crate) fn read_dataset_rows<R: parquet::file::reader::ChunkReader + 'static>(
+        reader: R,
+        schema: &Arc<Schema>,
+    ) -> Result<Vec<Tuple>, StorageError> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(reader)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+        let record_batch_reader = builder
+            .with_batch_size(2048)
+            .build()
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+        let mut rows = Vec::new();
+        for batch in record_batch_reader {
+            let batch = batch?;
+            rows.extend(Self::record_batch_to_rows(&batch, schema)?);
+        }
+        Ok(rows)
+    }
+
+    /// Metadata JSON saved next to a dataset. The live schema wins over the
+    /// metadata copy, which ALTERs don't refresh.
+    pub(crate) fn metadata_json(dataset: &Dataset) -> Result<String, StorageError> {
+        let mut metadata = dataset.metadata.clone();
+        metadata.schema = (*dataset.schema).clone();
+        serde_json::to_string_pretty(&metadata)
+            .map_err(|e| StorageError::Serialization(e.to_string()))
+    }
+
+    /// Index snapshots keyed by column, or None when the dataset has no index
+    pub(crate) fn indices_json(dataset: &Dataset) -> Result<Option<String>, StorageError> {
+        if dataset.indices.is_empty() {
+            return Ok(None);
+        }
+        let snapshots: BTreeMap<&String, IndexSnapshot> = dataset
+            .indices
+            .iter()
+            .map(|(col, idx)| (col, idx.snapshot()))
+            .collect();
+        serde_json::to_string(&snapshots)
+            .map(Some)
+            .map_err(|e| StorageError::Serialization(e.to_string()))
+    }
+
+    /// Rebuild a dataset from its metadata, rows and optional index snapshots
+    pub(crate) fn assemble_dataset(
+        name: &str,
+        metadata_json: &str,
+        parquet: impl FnOnce(&Arc<Schema>) -> Result<Vec<Tuple>, StorageError>,
+        indices_json: Option<String>,
+    ) -> Result<Dataset, StorageError> {
+        let metadata: DatasetMetadata = serde_json::from_str(metadata_json)
+            .map_err(|e| StorageError::Serialization(format!("Metadata error: {}", e)))?;
+        // Schema is now in metadata
+        let schema = Arc::new(metadata.schema.clone());
+        let rows = parquet(&schema)?;
+
+        let mut dataset = Dataset::new(
+            crate::core::dataset_legacy::DatasetId(0),
+            schema,
+            Some(name.to_string()),
+        );
+        dataset.rows = rows;
+        dataset.metadata = metadata;
+
+        if let Some(indices_json) = indices_json {
+            let snapshots: BTreeMap<String, IndexSnapshot> = serde_json::from_str(&indices_json)
+                .map_err(|e| StorageError::Serialization(format!("Index error: {}", e)))?;
+            for (col, snapshot) in snapshots {
+                dataset.indices.insert(col, snapshot.into_index());
+            }
+        }
+        Ok(dataset)
+    }
+
+    /// Sidecar JSON holding a tensor's id, since NPY headers have fixed keys
+    pub(crate) fn tensor_meta_json(tensor: &Tensor) -> Result<String, StorageError> {
+        serde_json::to_string(&serde_json::json!({ "id": tensor.id }))
+            .map_err(|e| StorageError::Serialization(e.to_string()))
+    }
+
+    pub(crate) fn apply_tensor_meta(tensor: &mut Tensor, meta_json: &str) -> Result<(), StorageError> {
+        let meta: serde_json::Value = serde_json::from_str(meta_json)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+        if let Some(id) = meta["id"].as_u64() {
+            tensor.id = TensorId(id);
+        }
+        Ok(())
+    }
+
+    /// Arrow schema of the Parquet files written for datasets of `schema`
+    fn arrow_schema(schema: &Schema) -> Arc<ArrowSchema> {
+        let arrow_fields: Vec<ArrowField> = schema
             .fields
             .iter()
             .map(|f| {
@@ -112,20 +327,28 @@ impl ParquetStorage {
                     ValueType::Float => DataType::Float32,
                     ValueType::String => DataType::Utf8,
                     ValueType::Bool => DataType::Boolean,
+                    ValueType::Timestamp => {
+                        DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+                    }
                     _ => DataType::Utf8, // Fallback for complex types (serialize as JSON string)
                 };
                 ArrowField::new(&f.name, data_type, f.nullable)
             })
             .collect();
 
-        let arrow_schema = Arc::new(ArrowSchema::new(arrow_fields));
+        Arc::new(ArrowSchema::new(arrow_fields))
+    }
 
-        // Convert rows to Arrow arrays
+    /// Convert rows of a dataset to an Arrow RecordBatch
+    fn rows_to_record_batch(
+        schema: &Schema,
+        arrow_schema: &Arc<ArrowSchema>,
+        rows: &[Tuple],
+    ) -> Result<RecordBatch, StorageError> {
         let mut arrays: Vec<ArrayRef> = Vec::new();
 
-        for field in &dataset.schema.fields {
-            let column_data: Vec<&Value> = dataset
-                .rows
+        for field in &schema.fields {
+            let column_data: Vec<&Value> = rows
                 .iter()
                 .map(|row| {
                     row.values
@@ -183,6 +406,27 @@ impl ParquetStorage {
                         .collect();
                     Arc::new(BooleanArray::from(values))
                 }
+                ValueType::Timestamp => {
+                    let values: Vec<Option<i64>> = column_data
+                        .iter()
+                        .map(|v| match v {
+                            Value::Timestamp(ts) => Some(ts.timestamp_micros()),
+                            _ => None,
+                        })
+                        .collect();
+                    Arc::new(TimestampMicrosecondArray::from(values).with_timezone("UTC"))
+                }
+                ValueType::Json => {
+                    // The document text itself, readable by other Parquet tools
+                    let values: Vec<Option<String>> = column_data
+                        .iter()
+                        .map(|v| match v {
+                            Value::Json(j) => Some(j.to_string()),
+                            _ => None,
+                        })
+                        .collect();
+                    Arc::new(StringArray::from(values))
+                }
                 _ => {
                     // For complex types (Vector, Matrix), serialize as JSON strings
                     let values: Vec<Option<String>> = column_data
@@ -201,12 +445,11 @@ impl ParquetStorage {
             arrays.push(array);
         }
 
-        RecordBatch::try_new(arrow_schema, arrays).map_err(|e| StorageError::Arrow(e))
+        RecordBatch::try_new(arrow_schema.clone(), arrays).map_err(StorageError::Arrow)
     }
 
     /// Convert Arrow RecordBatch to LINAL Rows
     fn record_batch_to_rows(
-        &self,
         batch: &RecordBatch,
         schema: &Arc<Schema>,
     ) -> Result<Vec<Tuple>, StorageError> {
@@ -223,7 +466,7 @@ impl ParquetStorage {
                 ))
             })?;
 
-            let values = self.arrow_array_to_values(arrow_col, &field.value_type, num_rows)?;
+            let values = Self::arrow_array_to_values(arrow_col, &field.value_type, num_rows)?;
             columns_data.push(values);
         }
 
@@ -242,7 +485,6 @@ impl ParquetStorage {
     }
 
     fn arrow_array_to_values(
-        &self,
         array: &ArrayRef,
         target_type: &ValueType,
         num_rows: usize,
@@ -316,7 +558,25 @@ impl ParquetStorage {
                     })
                     .collect())
             }
-            ValueType::Vector(_) | ValueType::Matrix(_, _) => {
+            ValueType::Timestamp => {
+                let ts_array = array
+                    .as_any()
+                    .downcast_ref::<TimestampMicrosecondArray>()
+                    .ok_or_else(|| {
+                        StorageError::Serialization("Expected TimestampMicrosecondArray".to_string())
+                    })?;
+                Ok((0..num_rows)
+                    .map(|i| {
+                        if ts_array.is_null(i) {
+                            Value::Null
+                        } else {
+                            chrono::DateTime::from_timestamp_micros(ts_array.value(i))
+                                .map_or(Value::Null, Value::Timestamp)
+                        }
+                    })
+                    .collect())
+            }
+            ValueType::Vector(_) | ValueType::Matrix(_, _) | ValueType::List(_) => {
                 let string_array =
                     array
                         .as_any()
@@ -337,6 +597,27 @@ impl ParquetStorage {
                     })
                     .collect())
             }
+            ValueType::Json => {
+                let string_array =
+                    array
+                        .as_any()
+                        .downcast_ref::<StringArray>()
+                        .ok_or_else(|| {
+                            StorageError::Serialization(
+                                "Expected StringArray for JSON".to_string(),
+                            )
+                        })?;
+                Ok((0..num_rows)
+                    .map(|i| {
+                        if string_array.is_null(i) {
+                            Value::Null
+                        } else {
+                            serde_json::from_str(string_array.value(i))
+                                .map_or(Value::Null, Value::Json)
+                        }
+                    })
+                    .collect())
+            }
             ValueType::Null => Ok(vec![Value::Null; num_rows]),
         }
     }
@@ -351,23 +632,21 @@ impl StorageEngine for ParquetStorage {
                 StorageError::Serialization("Dataset must have a name".to_string())
             })?;
 
-        // Convert to RecordBatch
-        let record_batch = self.dataset_to_record_batch(dataset)?;
-
         // Write to Parquet file
-        let data_path = self.dataset_path(dataset_name);
-        let file = fs::File::create(&data_path)?;
-        let props = WriterProperties::builder().build();
-        let mut writer = ArrowWriter::try_new(file, record_batch.schema(), Some(props))?;
-        writer.write(&record_batch)?;
-        writer.close()?;
+        let file = fs::File::create(self.dataset_path(dataset_name))?;
+        Self::write_dataset_parquet(dataset, file, self.options)?;
 
         // Save metadata as JSON
-        // Metadata now includes Schema, which is critical for LOAD
-        let meta_path = self.metadata_path(dataset_name);
-        let metadata_json = serde_json::to_string_pretty(&dataset.metadata)
-            .map_err(|e| StorageError::Serialization(e.to_string()))?;
-        fs::write(&meta_path, metadata_json)?;
+        // Metadata now includes Schema, which is critical for LOAD.
+        fs::write(self.metadata_path(dataset_name), Self::metadata_json(dataset)?)?;
+
+        // Save indices keyed by column, dropping any stale file when none remain
+        let indices_path = self.indices_path(dataset_name);
+        match Self::indices_json(dataset)? {
+            Some(indices_json) => fs::write(&indices_path, indices_json)?,
+            None if Path::new(&indices_path).exists() => fs::remove_file(&indices_path)?,
+            None => {}
+        }
 
         Ok(())
     }
@@ -380,10 +659,8 @@ impl StorageEngine for ParquetStorage {
         }
 
         let metadata_json = fs::read_to_string(&meta_path)?;
-        let metadata: DatasetMetadata = serde_json::from_str(&metadata_json)
-            .map_err(|e| StorageError::Serialization(format!("Metadata error: {}", e)))?;
 
-        // 2. Load Parquet Data
+        // 2. Parquet data
         let data_path = self.dataset_path(name);
         if !Path::new(&data_path).exists() {
             return Err(StorageError::DatasetNotFound(format!(
@@ -392,34 +669,20 @@ impl StorageEngine for ParquetStorage {
             )));
         }
 
-        let file = fs::File::open(&data_path)?;
-        let builder = ParquetRecordBatchReaderBuilder::try_new(file)
-            .map_err(|e| StorageError::Serialization(e.to_string()))?;
-        let record_batch_reader = builder
-            .with_batch_size(2048)
-            .build()
-            .map_err(|e| StorageError::Serialization(e.to_string()))?;
-
-        let mut rows = Vec::new();
-        // Schema is now in metadata
-        let schema = Arc::new(metadata.schema.clone());
-
-        for batch in record_batch_reader {
-            let batch = batch?;
-            let batch_rows = self.record_batch_to_rows(&batch, &schema)?;
-            rows.extend(batch_rows);
-        }
-
-        // 3. Reconstruct Dataset
-        let mut dataset = Dataset::new(
-            crate::core::dataset_legacy::DatasetId(0),
-            schema,
-            Some(name.to_string()),
-        );
-        dataset.rows = rows;
-        dataset.metadata = metadata;
-
-        Ok(dataset)
+        // 3. Indices, if they were persisted
+        let indices_path = self.indices_path(name);
+        let indices_json = if Path::new(&indices_path).exists() {
+            Some(fs::read_to_string(&indices_path)?)
+        } else {
+            None
+        };
+
+        Self::assemble_dataset(
+            name,
+            &metadata_json,
+            |schema| Self::read_dataset_rows(fs::File::open(&data_path)?, schema),
+            indices_json,
+        )
     }
 
     fn dataset_exists(&self, name: &str) -> bool {
@@ -429,13 +692,12 @@ impl StorageEngine for ParquetStorage {
     fn delete_dataset(&self, name: &str) -> Result<(), StorageError> {
         let data_path = self.dataset_path(name);
         let meta_path = self.metadata_path(name);
+        let indices_path = self.indices_path(name);
 
-        if Path::new(&data_path).exists() {
-            fs::remove_file(&data_path)?;
-        }
-
-        if Path::new(&meta_path).exists() {
-            fs::remove_file(&meta_path)?;
+        for path in [&data_path, &meta_path, &indices_path] {
+            if Path::new(path).exists() {
+                fs::remove_file(path)?;
+            }
         }
 
         Ok(())
@@ -463,22 +725,33 @@ impl StorageEngine for ParquetStorage {
     fn save_tensor(&self, name: &str, tensor: &Tensor) -> Result<(), StorageError> {
         self.ensure_directories()?;
 
-        let tensor_path = self.tensor_path(name);
-        let tensor_json = serde_json::to_string_pretty(tensor)
-            .map_err(|e| StorageError::Serialization(e.to_string()))?;
-        fs::write(&tensor_path, tensor_json)?;
+        npy::write_npy(tensor, Path::new(&self.tensor_path(name)))?;
+        fs::write(self.tensor_meta_path(name), Self::tensor_meta_json(tensor)?)?;
+
+        let legacy_path = self.legacy_tensor_path(name);
+        if Path::new(&legacy_path).exists() {
+            fs::remove_file(&legacy_path)?;
+        }
 
         Ok(())
     }
 
     fn load_tensor(&self, name: &str) -> Result<Tensor, StorageError> {
         let tensor_path = self.tensor_path(name);
+        if Path::new(&tensor_path).exists() {
+            let mut tensor = npy::read_npy(Path::new(&tensor_path))?;
+            if let Ok(meta_json) = fs::read_to_string(self.tensor_meta_path(name)) {
+                Self::apply_tensor_meta(&mut tensor, &meta_json)?;
+            }
+            return Ok(tensor);
+        }
 
-        if !Path::new(&tensor_path).exists() {
+        let legacy_path = self.legacy_tensor_path(name);
+        if !Path::new(&legacy_path).exists() {
             return Err(StorageError::TensorNotFound(name.to_string()));
         }
 
-        let tensor_json = fs::read_to_string(&tensor_path)?;
+        let tensor_json = fs::read_to_string(&legacy_path)?;
         let tensor: Tensor = serde_json::from_str(&tensor_json)
             .map_err(|e| StorageError::Serialization(e.to_string()))?;
 
@@ -487,13 +760,18 @@ impl StorageEngine for ParquetStorage {
 
     fn tensor_exists(&self, name: &str) -> bool {
         Path::new(&self.tensor_path(name)).exists()
+            || Path::new(&self.legacy_tensor_path(name)).exists()
     }
 
     fn delete_tensor(&self, name: &str) -> Result<(), StorageError> {
-        let tensor_path = self.tensor_path(name);
-
-        if Path::new(&tensor_path).exists() {
-            fs::remove_file(&tensor_path)?;
+        for tensor_path in [
+            self.tensor_path(name),
+            self.tensor_meta_path(name),
+            self.legacy_tensor_path(name),
+        ] {
+            if Path::new(&tensor_path).exists() {
+                fs::remove_file(&tensor_path)?;
+            }
         }
 
         Ok(())
@@ -508,12 +786,16 @@ impl StorageEngine for ParquetStorage {
         for entry in fs::read_dir(&tensors_dir)? {
             let entry = entry?;
             let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("json") {
+            if matches!(path.extension().and_then(|s| s.to_str()), Some("npy" | "json")) {
                 if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
-                    tensors.push(name.to_string());
+                    if !name.ends_with(".meta") {
+                        tensors.push(name.to_string());
+                    }
                 }
             }
         }
+        tensors.sort();
+        tensors.dedup();
 
         Ok(tensors)
     }
@@ -539,4 +821,82 @@ mod tests {
         // Clean up
         let _ = fs::remove_dir_all(temp_dir);
     }
+
+    #[test]
+    fn test_save_dataset_in_row_groups() {
+        use crate::core::dataset_legacy::DatasetId;
+        use crate::core::tuple::Field;
+
+        let temp_dir = "/tmp/linal_test_storage_row_groups";
+        let _ = fs::remove_dir_all(temp_dir);
+        let storage = ParquetStorage::new(temp_dir).with_options(ParquetWriteOptions {
+            row_group_rows: 2,
+            ..Default::default()
+        });
+
+        let schema = Arc::new(Schema::new(vec![Field::new("id", ValueType::Int)]));
+        let mut dataset = Dataset::new(DatasetId(1), schema.clone(), Some("ids".to_string()));
+        for id in 0..5 {
+            dataset
+                .add_row(Tuple::new(schema.clone(), vec![Value::Int(id)]).unwrap())
+                .unwrap();
+        }
+        storage.save_dataset(&dataset).unwrap();
+
+        let file = fs::File::open(storage.dataset_path("ids")).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 3);
+
+        let loaded = storage.load_dataset("ids").unwrap();
+        let ids: Vec<Value> = loaded.rows.iter().map(|row| row.values[0].clone()).collect();
+        assert_eq!(ids, (0..5).map(Value::Int).collect::<Vec<_>>());
+
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
+    #[test]
+    fn test_parquet_write_options() {
+        use crate::core::dataset_legacy::DatasetId;
+        use crate::core::tuple::Field;
+        use parquet::basic::Encoding;
+
+        let temp_dir = "/tmp/linal_test_storage_write_options";
+        let _ = fs::remove_dir_all(temp_dir);
+        fs::create_dir_all(temp_dir).unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("name", ValueType::String)]));
+        let mut dataset = Dataset::new(DatasetId(1), schema.clone(), Some("names".to_string()));
+        for i in 0..100 {
+            let name = Value::String(format!("name-{}", i % 3));
+            dataset
+                .add_row(Tuple::new(schema.clone(), vec![name]).unwrap())
+                .unwrap();
+        }
+
+        let column = |options: ParquetWriteOptions| {
+            let path = Path::new(temp_dir).join("names.parquet");
+            ParquetStorage::write_parquet_file(&dataset, &path, options).unwrap();
+            let reader =
+                ParquetRecordBatchReaderBuilder::try_new(fs::File::open(path).unwrap()).unwrap();
+            reader.metadata().row_group(0).column(0).clone()
+        };
+
+        // Zstd, dictionary and statistics by default
+        let chunk = column(ParquetWriteOptions::default());
+        assert!(matches!(chunk.compression(), Compression::ZSTD(_)));
+        assert!(chunk.encodings().contains(&Encoding::RLE_DICTIONARY));
+        assert!(chunk.statistics().is_some());
+
+        let chunk = column(ParquetWriteOptions {
+            compression: ParquetCompression::Snappy,
+            dictionary: false,
+            statistics: false,
+            ..Default::default()
+        });
+        assert_eq!(chunk.compression(), Compression::SNAPPY);
+        assert!(!chunk.encodings().contains(&Encoding::RLE_DICTIONARY));
+        assert!(chunk.statistics().is_none());
+
+        let _ = fs::remove_dir_all(temp_dir);
+    }
 }
@@ -0,0 +1,130 @@
+use super::{ParquetStorage, ParquetWriteOptions, StorageError};
+use crate::core::dataset_legacy::Dataset;
+use crate::core::value::Value;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// File format of `EXPORT (query) TO "path" FORMAT parquet|csv|json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Parquet,
+    Csv,
+    /// A JSON array with one object per row
+    Json,
+}
+
+impl ExportFormat {
+    /// Parse a FORMAT name (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "parquet" => Some(Self::Parquet),
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Infer the format from a file extension
+    pub fn from_path(path: &str) -> Option<Self> {
+        Path::new(path)
+            .extension()
+            .and_then(|ext| Self::parse(&ext.to_string_lossy()))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Parquet => "parquet",
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Write all rows of `dataset` to `path`, creating parent directories as needed.
+/// `parquet` applies to Parquet exports only.
+pub fn export_dataset(
+    dataset: &Dataset,
+    path: &Path,
+    format: ExportFormat,
+    parquet: ParquetWriteOptions,
+) -> Result<(), StorageError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    match format {
+        ExportFormat::Parquet => ParquetStorage::write_parquet_file(dataset, path, parquet),
+        ExportFormat::Csv => write_csv(dataset, path),
+        ExportFormat::Json => write_json(dataset, path),
+    }
+}
+
+/// Header row plus one record per row. NULL is an empty cell; vectors and
+/// matrices are JSON arrays, matching what CSV import reads back.
+fn write_csv(dataset: &Dataset, path: &Path) -> Result<(), StorageError> {
+    let csv_err = |e: csv::Error| StorageError::Serialization(e.to_string());
+    let mut writer = csv::Writer::from_path(path).map_err(csv_err)?;
+
+    writer
+        .write_record(dataset.schema.fields.iter().map(|f| f.name.as_str()))
+        .map_err(csv_err)?;
+    for row in &dataset.rows {
+        let cells = row.values.iter().map(|v| match v {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            Value::Vector(_) | Value::Matrix(_) => to_json(v).to_string(),
+            other => other.to_string(),
+        });
+        writer.write_record(cells).map_err(csv_err)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_json(dataset: &Dataset, path: &Path) -> Result<(), StorageError> {
+    let rows: Vec<serde_json::Map<String, serde_json::Value>> = dataset
+        .rows
+        .iter()
+        .map(|row| {
+            dataset
+                .schema
+                .fields
+                .iter()
+                .zip(&row.values)
+                .map(|(field, value)| (field.name.clone(), to_json(value)))
+                .collect()
+        })
+        .collect();
+
+    let mut writer = BufWriter::new(fs::File::create(path)?);
+    serde_json::to_writer(&mut writer, &rows)
+        .map_err(|e| StorageError::Serialization(e.to_string()))?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Plain JSON for a value (the derived serde form is externally tagged)
+fn to_json(value: &Value) -> serde_json::Value {
+    // Going through the shortest decimal form keeps 0.1f32 as 0.1
+    let float = |f: f32| {
+        serde_json::Number::from_f64(f.to_string().parse().unwrap_or(f64::NAN))
+            .map_or(serde_json::Value::Null, serde_json::Value::Number)
+    };
+
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Int(i) => serde_json::Value::from(*i),
+        Value::Float(f) => float(*f),
+        Value::Bool(b) => serde_json::Value::Bool(*b),
+        Value::String(s) => serde_json::Value::String(s.clone()),
+        Value::Timestamp(_) => serde_json::Value::String(value.to_string()),
+        Value::Json(j) => j.clone(),
+        Value::List(items) => items.iter().map(to_json).collect(),
+        Value::Vector(v) => v.iter().map(|f| float(*f)).collect(),
+        Value::Matrix(m) => m
+            .iter()
+            .map(|row| row.iter().map(|f| float(*f)).collect::<serde_json::Value>())
+            .collect(),
+    }
+}
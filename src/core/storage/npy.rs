@@ -0,0 +1,179 @@
+use super::StorageError;
+use crate::core::tensor::{Shape, Tensor, TensorId};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// True if `path` names a NumPy `.npy` file
+pub fn is_npy_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("npy"))
+}
+
+/// Write a tensor as a little-endian float32 `.npy` (format version 1.0)
+pub fn write_npy(tensor: &Tensor, path: &Path) -> Result<(), StorageError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut out = BufWriter::new(fs::File::create(path)?);
+    out.write_all(&encode_npy(tensor)?)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Encode a tensor as the bytes of a float32 `.npy` file
+pub fn encode_npy(tensor: &Tensor) -> Result<Vec<u8>, StorageError> {
+    let shape = match tensor.shape.dims.as_slice() {
+        [] => "()".to_string(),
+        [n] => format!("({},)", n),
+        dims => format!(
+            "({})",
+            dims.iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}",
+        shape
+    );
+    // Magic + version + length + header + '\n' is padded to a multiple of 64
+    let unpadded = MAGIC.len() + 4 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+    let header_len = u16::try_from(header.len())
+        .map_err(|_| StorageError::Serialization("NPY header too long".to_string()))?;
+
+    let mut out = Vec::with_capacity(10 + header.len() + tensor.data.len() * 4);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&[1, 0]);
+    out.extend_from_slice(&header_len.to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    for v in tensor.data.iter() {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    Ok(out)
+}
+
+/// Read a C-ordered `.npy` file of float32, float64, int32 or int64 values.
+/// Values are converted to f32.
+pub fn read_npy(path: &Path) -> Result<Tensor, StorageError> {
+    decode_npy(&fs::read(path)?)
+}
+
+/// Decode the bytes of a `.npy` file, as accepted by `read_npy`
+pub fn decode_npy(bytes: &[u8]) -> Result<Tensor, StorageError> {
+    let invalid = |msg: &str| StorageError::Serialization(format!("Invalid NPY file: {}", msg));
+
+    if bytes.len() < 10 || &bytes[..6] != MAGIC {
+        return Err(invalid("missing magic string"));
+    }
+    let (header_len, header_start) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (
+            u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize,
+            12,
+        ),
+        v => return Err(invalid(&format!("unsupported version {}", v))),
+    };
+    let data_start = header_start + header_len;
+    let header = bytes
+        .get(header_start..data_start)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| invalid("truncated header"))?;
+
+    let descr = header_value(header, "descr")
+        .map(|d| d.trim_matches(|c| c == '\'' || c == '"'))
+        .ok_or_else(|| invalid("missing descr"))?;
+    if header_value(header, "fortran_order") == Some("True") {
+        return Err(invalid("Fortran-ordered arrays are not supported"));
+    }
+    let dims = header_value(header, "shape")
+        .and_then(parse_shape)
+        .ok_or_else(|| invalid("missing or malformed shape"))?;
+
+    let payload = &bytes[data_start..];
+    let data: Vec<f32> = match descr {
+        "<f4" | "=f4" | "|f4" => le_values(payload, f32::from_le_bytes),
+        "<f8" | "=f8" => le_values(payload, |b| f64::from_le_bytes(b) as f32),
+        "<i4" | "=i4" => le_values(payload, |b| i32::from_le_bytes(b) as f32),
+        "<i8" | "=i8" => le_values(payload, |b| i64::from_le_bytes(b) as f32),
+        other => return Err(invalid(&format!("unsupported dtype '{}'", other))),
+    };
+
+    Tensor::new(TensorId(0), Shape::new(dims), data).map_err(|e| invalid(&e))
+}
+
+/// Raw text of `'key': value` in the header dict (tuples kept whole)
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}':", key))? + key.len() + 3;
+    let rest = header[start..].trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')')? + 1
+    } else {
+        rest.find([',', '}'])?
+    };
+    Some(rest[..end].trim())
+}
+
+fn parse_shape(tuple: &str) -> Option<Vec<usize>> {
+    tuple
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse().ok())
+        .collect()
+}
+
+fn le_values<const N: usize>(payload: &[u8], convert: impl Fn([u8; N]) -> f32) -> Vec<f32> {
+    payload
+        .chunks_exact(N)
+        .map(|c| convert(c.try_into().unwrap()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npy_round_trip_and_header_alignment() {
+        let path = std::env::temp_dir().join("linal_npy_unit.npy");
+        let tensor = Tensor::new(
+            TensorId(1),
+            Shape::new(vec![2, 3]),
+            (0..6).map(|i| i as f32).collect(),
+        )
+        .unwrap();
+        write_npy(&tensor, &path).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        assert_eq!(bytes.len(), 10 + header_len + 6 * 4);
+
+        let loaded = read_npy(&path).unwrap();
+        assert_eq!(loaded.shape.dims, vec![2, 3]);
+        assert_eq!(*loaded.data, *tensor.data);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_parse_header_values() {
+        let header = "{'descr': '<f8', 'fortran_order': False, 'shape': (4,), }";
+        assert_eq!(header_value(header, "descr"), Some("'<f8'"));
+        assert_eq!(header_value(header, "fortran_order"), Some("False"));
+        assert_eq!(
+            parse_shape(header_value(header, "shape").unwrap()),
+            Some(vec![4])
+        );
+        assert_eq!(parse_shape("()"), Some(vec![]));
+    }
+}
@@ -0,0 +1,307 @@
+use super::{npy, ParquetStorage, ParquetWriteOptions, StorageEngine, StorageError};
+use crate::core::config::{StorageBackend, StorageConfig};
+use crate::core::dataset_legacy::Dataset;
+use crate::core::tensor::Tensor;
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStore, PutPayload};
+use std::future::Future;
+use std::sync::Arc;
+
+/// Storage on top of an `object_store` backend (local filesystem, S3 or GCS).
+/// Objects use the same layout as `ParquetStorage`, relative to `prefix`.
+pub struct ObjectStoreStorage {
+    store: Arc<dyn ObjectStore>,
+    prefix: ObjectPath,
+    /// Human-readable root, e.g. `s3://bucket/prefix`
+    root: String,
+    options: ParquetWriteOptions,
+}
+
+impl ObjectStoreStorage {
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: &str, root: impl Into<String>) -> Self {
+        Self {
+            store,
+            prefix: ObjectPath::from(prefix),
+            root: root.into(),
+            options: ParquetWriteOptions::default(),
+        }
+    }
+
+    /// Build the backend selected by `storage.backend`, `bucket` and `prefix`
+    pub fn from_config(config: &StorageConfig) -> Result<Self, StorageError> {
+        let bucket = config.bucket.as_deref().ok_or_else(|| {
+            StorageError::Backend("storage.bucket is required for this backend".to_string())
+        })?;
+        let prefix = config.prefix.as_deref().unwrap_or("");
+
+        let (store, scheme): (Arc<dyn ObjectStore>, &str) = match config.backend {
+            StorageBackend::File => {
+                return Err(StorageError::Backend(
+                    "the file backend does not use an object store".to_string(),
+                ))
+            }
+            StorageBackend::Local => {
+                std::fs::create_dir_all(bucket)?;
+                let store = object_store::local::LocalFileSystem::new_with_prefix(bucket)?;
+                (Arc::new(store), "file")
+            }
+            StorageBackend::S3 => (s3_store(bucket)?, "s3"),
+            StorageBackend::Gcs => (gcs_store(bucket)?, "gs"),
+        };
+
+        let mut root = format!("{}://{}", scheme, bucket.trim_end_matches('/'));
+        if !prefix.is_empty() {
+            root = format!("{}/{}", root, prefix.trim_matches('/'));
+        }
+        let mut storage = Self::new(store, prefix, root);
+        storage.options = ParquetWriteOptions::from_config(config);
+        Ok(storage)
+    }
+
+    /// Storage for one database, stored under `{prefix}/{name}`
+    pub fn child(&self, name: &str) -> Self {
+        Self {
+            store: self.store.clone(),
+            prefix: self.prefix.child(name),
+            root: format!("{}/{}", self.root, name),
+            options: self.options,
+        }
+    }
+
+    /// Names of the directories directly under this prefix (one per database)
+    pub fn list_children(&self) -> Result<Vec<String>, StorageError> {
+        let listing = block_on(self.store.list_with_delimiter(Some(&self.prefix)))?;
+        let mut names: Vec<String> = listing
+            .common_prefixes
+            .iter()
+            .filter_map(|p| p.filename().map(str::to_string))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Human-readable location of this storage, used in command output
+    pub fn location(&self) -> &str {
+        &self.root
+    }
+
+    fn object(&self, dir: &str, file: String) -> ObjectPath {
+        self.prefix.child(dir).child(file)
+    }
+
+    fn dataset_path(&self, name: &str) -> ObjectPath {
+        self.object("datasets", format!("{}.parquet", name))
+    }
+
+    fn metadata_path(&self, name: &str) -> ObjectPath {
+        self.object("datasets", format!("{}.meta.json", name))
+    }
+
+    fn indices_path(&self, name: &str) -> ObjectPath {
+        self.object("datasets", format!("{}.indices.json", name))
+    }
+
+    fn tensor_path(&self, name: &str) -> ObjectPath {
+        self.object("tensors", format!("{}.npy", name))
+    }
+
+    fn tensor_meta_path(&self, name: &str) -> ObjectPath {
+        self.object("tensors", format!("{}.meta.json", name))
+    }
+
+    fn put(&self, path: &ObjectPath, bytes: Vec<u8>) -> Result<(), StorageError> {
+        block_on(self.store.put(path, PutPayload::from(bytes)))?;
+        Ok(())
+    }
+
+    /// Object contents, or None if it does not exist
+    fn get(&self, path: &ObjectPath) -> Result<Option<bytes::Bytes>, StorageError> {
+        let result = block_on(async {
+            match self.store.get(path).await {
+                Ok(result) => result.bytes().await.map(Some),
+                Err(object_store::Error::NotFound { .. }) => Ok(None),
+                Err(e) => Err(e),
+            }
+        })?;
+        Ok(result)
+    }
+
+    fn get_string(&self, path: &ObjectPath) -> Result<Option<String>, StorageError> {
+        self.get(path)?
+            .map(|bytes| {
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|e| StorageError::Serialization(e.to_string()))
+            })
+            .transpose()
+    }
+
+    fn exists(&self, path: &ObjectPath) -> bool {
+        block_on(self.store.head(path)).is_ok()
+    }
+
+    fn delete(&self, path: &ObjectPath) -> Result<(), StorageError> {
+        match block_on(self.store.delete(path)) {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// File stems of the objects in `dir` with the given extension
+    fn list_names(&self, dir: &str, extension: &str) -> Result<Vec<String>, StorageError> {
+        let dir = self.prefix.child(dir);
+        let listing = block_on(self.store.list_with_delimiter(Some(&dir)))?;
+        let mut names: Vec<String> = listing
+            .objects
+            .iter()
+            .filter_map(|meta| meta.location.filename()?.strip_suffix(extension))
+            .filter(|name| !name.ends_with(".meta") && !name.ends_with(".indices"))
+            .map(str::to_string)
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+}
+
+impl StorageEngine for ObjectStoreStorage {
+    fn save_dataset(&self, dataset: &Dataset) -> Result<(), StorageError> {
+        let dataset_name =
+            dataset.metadata.name.as_ref().ok_or_else(|| {
+                StorageError::Serialization("Dataset must have a name".to_string())
+            })?;
+
+        let mut parquet = Vec::new();
+        ParquetStorage::write_dataset_parquet(dataset, &mut parquet, self.options)?;
+        self.put(&self.dataset_path(dataset_name), parquet)?;
+        self.put(
+            &self.metadata_path(dataset_name),
+            ParquetStorage::metadata_json(dataset)?.into_bytes(),
+        )?;
+
+        let indices_path = self.indices_path(dataset_name);
+        match ParquetStorage::indices_json(dataset)? {
+            Some(indices_json) => self.put(&indices_path, indices_json.into_bytes()),
+            None => self.delete(&indices_path),
+        }
+    }
+
+    fn load_dataset(&self, name: &str) -> Result<Dataset, StorageError> {
+        let metadata_json = self
+            .get_string(&self.metadata_path(name))?
+            .ok_or_else(|| StorageError::DatasetNotFound(name.to_string()))?;
+        let parquet = self.get(&self.dataset_path(name))?.ok_or_else(|| {
+            StorageError::DatasetNotFound(format!("Data file missing for {}", name))
+        })?;
+        let indices_json = self.get_string(&self.indices_path(name))?;
+
+        ParquetStorage::assemble_dataset(
+            name,
+            &metadata_json,
+            |schema| ParquetStorage::read_dataset_rows(parquet, schema),
+            indices_json,
+        )
+    }
+
+    fn dataset_exists(&self, name: &str) -> bool {
+        self.exists(&self.dataset_path(name))
+    }
+
+    fn delete_dataset(&self, name: &str) -> Result<(), StorageError> {
+        for path in [
+            self.dataset_path(name),
+            self.metadata_path(name),
+            self.indices_path(name),
+        ] {
+            self.delete(&path)?;
+        }
+        Ok(())
+    }
+
+    fn list_datasets(&self) -> Result<Vec<String>, StorageError> {
+        self.list_names("datasets", ".parquet")
+    }
+
+    fn save_tensor(&self, name: &str, tensor: &Tensor) -> Result<(), StorageError> {
+        self.put(&self.tensor_path(name), npy::encode_npy(tensor)?)?;
+        self.put(
+            &self.tensor_meta_path(name),
+            ParquetStorage::tensor_meta_json(tensor)?.into_bytes(),
+        )
+    }
+
+    fn load_tensor(&self, name: &str) -> Result<Tensor, StorageError> {
+        let bytes = self
+            .get(&self.tensor_path(name))?
+            .ok_or_else(|| StorageError::TensorNotFound(name.to_string()))?;
+        let mut tensor = npy::decode_npy(&bytes)?;
+        if let Some(meta_json) = self.get_string(&self.tensor_meta_path(name))? {
+            ParquetStorage::apply_tensor_meta(&mut tensor, &meta_json)?;
+        }
+        Ok(tensor)
+    }
+
+    fn tensor_exists(&self, name: &str) -> bool {
+        self.exists(&self.tensor_path(name))
+    }
+
+    fn delete_tensor(&self, name: &str) -> Result<(), StorageError> {
+        self.delete(&self.tensor_path(name))?;
+        self.delete(&self.tensor_meta_path(name))
+    }
+
+    fn list_tensors(&self) -> Result<Vec<String>, StorageError> {
+        self.list_names("tensors", ".npy")
+    }
+}
+
+#[cfg(feature = "s3")]
+fn s3_store(bucket: &str) -> Result<Arc<dyn ObjectStore>, StorageError> {
+    // Credentials and region come from the usual AWS_* environment variables
+    let store = object_store::aws::AmazonS3Builder::from_env()
+        .with_bucket_name(bucket)
+        .build()?;
+    Ok(Arc::new(store))
+}
+
+#[cfg(not(feature = "s3"))]
+fn s3_store(_bucket: &str) -> Result<Arc<dyn ObjectStore>, StorageError> {
+    Err(StorageError::Backend(
+        "storage.backend = \"s3\" requires building linal with the `s3` feature".to_string(),
+    ))
+}
+
+#[cfg(feature = "gcs")]
+fn gcs_store(bucket: &str) -> Result<Arc<dyn ObjectStore>, StorageError> {
+    // Credentials come from GOOGLE_SERVICE_ACCOUNT / GOOGLE_APPLICATION_CREDENTIALS
+    let store = object_store::gcp::GoogleCloudStorageBuilder::from_env()
+        .with_bucket_name(bucket)
+        .build()?;
+    Ok(Arc::new(store))
+}
+
+#[cfg(not(feature = "gcs"))]
+fn gcs_store(_bucket: &str) -> Result<Arc<dyn ObjectStore>, StorageError> {
+    Err(StorageError::Backend(
+        "storage.backend = \"gcs\" requires building linal with the `gcs` feature".to_string(),
+    ))
+}
+
+/// Run an object store request to completion from synchronous code.
+/// A fresh thread is used so this also works when called inside the server's runtime.
+fn block_on<F>(future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("failed to start object store runtime")
+                    .block_on(future)
+            })
+            .join()
+            .expect("object store request panicked")
+    })
+}
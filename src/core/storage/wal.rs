@@ -0,0 +1,112 @@
+use super::StorageError;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Append-only log of executed mutating DSL commands for one database.
+/// Each record is a JSON-encoded string on its own line.
+pub struct WriteAheadLog {
+    path: PathBuf,
+}
+
+impl WriteAheadLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Log location for a database directory: `{db_dir}/wal.log`
+    pub fn for_database(db_dir: impl AsRef<Path>) -> Self {
+        Self::new(db_dir.as_ref().join("wal.log"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a command and flush it to disk before returning
+    pub fn append(&self, command: &str) -> Result<(), StorageError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let record = serde_json::to_string(command)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", record)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Read all logged commands in order. A missing log is empty.
+    /// A torn final record (crash mid-write) is ignored.
+    pub fn read_all(&self) -> Result<Vec<String>, StorageError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let file = fs::File::open(&self.path)?;
+        let lines: Vec<String> = BufReader::new(file).lines().collect::<Result<_, _>>()?;
+        let last = lines.len().saturating_sub(1);
+
+        let mut commands = Vec::with_capacity(lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<String>(line) {
+                Ok(cmd) => commands.push(cmd),
+                Err(_) if i == last => break,
+                Err(e) => {
+                    return Err(StorageError::Serialization(format!(
+                        "Corrupt WAL record {}: {}",
+                        i + 1,
+                        e
+                    )))
+                }
+            }
+        }
+        Ok(commands)
+    }
+
+    /// Drop all records (after a checkpoint snapshot)
+    pub fn truncate(&self) -> Result<(), StorageError> {
+        if self.path.exists() {
+            fs::File::create(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wal_append_read_truncate() {
+        let temp_dir = "/tmp/linal_test_wal";
+        let _ = fs::remove_dir_all(temp_dir);
+        let wal = WriteAheadLog::for_database(temp_dir);
+
+        assert!(wal.read_all().unwrap().is_empty());
+
+        wal.append("DATASET t COLUMNS (id: Int)").unwrap();
+        wal.append("INSERT INTO t VALUES (1)").unwrap();
+        assert_eq!(
+            wal.read_all().unwrap(),
+            vec!["DATASET t COLUMNS (id: Int)", "INSERT INTO t VALUES (1)"]
+        );
+
+        // Torn tail record is skipped
+        let mut file = OpenOptions::new().append(true).open(wal.path()).unwrap();
+        write!(file, "\"INSERT INTO t VAL").unwrap();
+        assert_eq!(wal.read_all().unwrap().len(), 2);
+
+        wal.truncate().unwrap();
+        assert!(wal.read_all().unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::core::dataset_legacy::{Dataset, DatasetId};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Error types for dataset store operations
 #[derive(Debug)]
@@ -25,11 +26,13 @@ impl std::fmt::Display for DatasetStoreError {
 
 impl std::error::Error for DatasetStoreError {}
 
-/// In-memory storage for datasets
-#[derive(Debug)]
+/// In-memory storage for datasets. Datasets are shared between clones of
+/// the store and copied by the first clone that changes them, so a clone is
+/// a cheap point-in-time snapshot.
+#[derive(Debug, Clone)]
 pub struct DatasetStore {
     next_id: u64,
-    datasets: HashMap<DatasetId, Dataset>,
+    datasets: HashMap<DatasetId, Arc<Dataset>>,
     names: HashMap<String, DatasetId>,
 }
 
@@ -68,7 +71,7 @@ impl DatasetStore {
         dataset.metadata.name = name.clone();
 
         // Insert dataset
-        self.datasets.insert(id, dataset);
+        self.datasets.insert(id, Arc::new(dataset));
 
         // Register name if provided
         if let Some(name_str) = name {
@@ -82,13 +85,16 @@ impl DatasetStore {
     pub fn get(&self, id: DatasetId) -> Result<&Dataset, DatasetStoreError> {
         self.datasets
             .get(&id)
+            .map(Arc::as_ref)
             .ok_or(DatasetStoreError::DatasetNotFound(id))
     }
 
-    /// Get a mutable reference to a dataset by ID
+    /// Get a mutable reference to a dataset by ID, copying it first if a
+    /// snapshot still shares it
     pub fn get_mut(&mut self, id: DatasetId) -> Result<&mut Dataset, DatasetStoreError> {
         self.datasets
             .get_mut(&id)
+            .map(Arc::make_mut)
             .ok_or(DatasetStoreError::DatasetNotFound(id))
     }
 
@@ -100,6 +106,18 @@ impl DatasetStore {
         self.get(*id)
     }
 
+    /// The shared copy of a dataset, which stays as it is when the store
+    /// changes it later
+    pub fn get_shared_by_name(&self, name: &str) -> Result<Arc<Dataset>, DatasetStoreError> {
+        let id = self.names.get(name).ok_or_else(|| {
+            DatasetStoreError::InvalidDataset(format!("Dataset '{}' not found", name))
+        })?;
+        self.datasets
+            .get(id)
+            .cloned()
+            .ok_or(DatasetStoreError::DatasetNotFound(*id))
+    }
+
     /// Get a mutable reference to a dataset by name
     pub fn get_mut_by_name(&mut self, name: &str) -> Result<&mut Dataset, DatasetStoreError> {
         let id = self.names.get(name).copied().ok_or_else(|| {
@@ -120,7 +138,7 @@ impl DatasetStore {
             self.names.remove(name);
         }
 
-        Ok(dataset)
+        Ok(Arc::unwrap_or_clone(dataset))
     }
 
     /// Remove a dataset by name
@@ -131,9 +149,25 @@ impl DatasetStore {
 
         self.datasets
             .remove(&id)
+            .map(Arc::unwrap_or_clone)
             .ok_or(DatasetStoreError::DatasetNotFound(id))
     }
 
+    /// Register the dataset named `from` under `to` instead
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), DatasetStoreError> {
+        if self.names.contains_key(to) {
+            return Err(DatasetStoreError::NameAlreadyExists(to.to_string()));
+        }
+        let id = self.names.remove(from).ok_or_else(|| {
+            DatasetStoreError::InvalidDataset(format!("Dataset '{}' not found", from))
+        })?;
+        self.names.insert(to.to_string(), id);
+        if let Some(dataset) = self.datasets.get_mut(&id) {
+            Arc::make_mut(dataset).metadata.name = Some(to.to_string());
+        }
+        Ok(())
+    }
+
     /// List all dataset IDs
     pub fn list_ids(&self) -> Vec<DatasetId> {
         self.datasets.keys().copied().collect()
@@ -218,6 +252,43 @@ mod tests {
         assert_eq!(store.len(), 0);
     }
 
+    #[test]
+    fn test_dataset_store_rename() {
+        let mut store = DatasetStore::new();
+        for name in ["a", "b"] {
+            let id = store.gen_id();
+            store
+                .insert(create_test_dataset(id), Some(name.to_string()))
+                .unwrap();
+        }
+
+        assert!(store.rename("a", "b").is_err());
+        store.rename("a", "c").unwrap();
+        assert!(store.get_by_name("a").is_err());
+        let renamed = store.get_by_name("c").unwrap();
+        assert_eq!(renamed.metadata.name.as_deref(), Some("c"));
+        assert!(store.rename("a", "d").is_err());
+    }
+
+    #[test]
+    fn test_dataset_store_clone_is_a_snapshot() {
+        let mut store = DatasetStore::new();
+        let id = store.gen_id();
+        store
+            .insert(create_test_dataset(id), Some("test".to_string()))
+            .unwrap();
+
+        let snapshot = store.clone();
+        assert!(std::ptr::eq(
+            store.get(id).unwrap(),
+            snapshot.get(id).unwrap()
+        ));
+
+        store.get_mut(id).unwrap().metadata.version = 7;
+        assert_eq!(store.get(id).unwrap().metadata.version, 7);
+        assert_eq!(snapshot.get(id).unwrap().metadata.version, 1);
+    }
+
     #[test]
     fn test_dataset_store_list() {
         let mut store = DatasetStore::new();
@@ -22,7 +22,7 @@ impl std::fmt::Display for StoreError {
 impl std::error::Error for StoreError {}
 
 /// Motor en memoria: guarda tensores en una lista.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct InMemoryTensorStore {
     next_id: u64,
     tensors: Vec<Tensor>,
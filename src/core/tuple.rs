@@ -13,6 +13,12 @@ pub struct Field {
     pub nullable: bool,
     #[serde(default)]
     pub is_lazy: bool, // True if this column is computed lazily (evaluated on access)
+    /// Key column: values are unique and UPSERT replaces rows by it
+    #[serde(default)]
+    pub primary_key: bool,
+    /// UNIQUE column: no two rows share a value (NULLs may repeat)
+    #[serde(default)]
+    pub unique: bool,
 }
 
 impl Field {
@@ -22,6 +28,8 @@ impl Field {
             value_type,
             nullable: false,
             is_lazy: false,
+            primary_key: false,
+            unique: false,
         }
     }
 
@@ -35,6 +43,22 @@ impl Field {
         self
     }
 
+    pub fn primary_key(mut self) -> Self {
+        self.primary_key = true;
+        self.nullable = false;
+        self
+    }
+
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    /// Whether no two rows may share a value of this column
+    pub fn is_unique(&self) -> bool {
+        self.primary_key || self.unique
+    }
+
     /// Check if a value is compatible with this field
     pub fn is_compatible(&self, value: &Value) -> bool {
         if value.is_null() {
@@ -46,6 +70,10 @@ impl Field {
             (ValueType::Int, ValueType::Int) => true,
             (ValueType::String, ValueType::String) => true,
             (ValueType::Bool, ValueType::Bool) => true,
+            (ValueType::Timestamp, ValueType::Timestamp) => true,
+            (ValueType::Json, ValueType::Json) => true,
+            // Every element must have the list's element type (or be NULL)
+            (ValueType::List(_), ValueType::List(_)) => value.matches_type(&self.value_type),
             (ValueType::Vector(expected_dim), ValueType::Vector(actual_dim)) => {
                 expected_dim == &actual_dim
             }
@@ -84,6 +112,11 @@ impl Schema {
             .and_then(|&idx| self.fields.get(idx))
     }
 
+    /// The primary key column, if the schema has one
+    pub fn primary_key(&self) -> Option<&Field> {
+        self.fields.iter().find(|f| f.primary_key)
+    }
+
     /// Get field index by name
     pub fn get_field_index(&self, name: &str) -> Option<usize> {
         self.field_indices.get(name).copied()
@@ -109,6 +142,12 @@ impl Schema {
         }
 
         for (i, (field, value)) in self.fields.iter().zip(values.iter()).enumerate() {
+            if value.is_null() && !field.nullable {
+                return Err(format!(
+                    "NOT NULL constraint violated: column '{}' cannot be NULL",
+                    field.name
+                ));
+            }
             if !field.is_compatible(value) {
                 return Err(format!(
                     "Type mismatch at field '{}' (index {}): expected {}, got {}",
@@ -2,6 +2,8 @@
 
 //use super::tensor::Tensor;
 //use crate::core::tensor::Shape;
+use crate::utils::parsing::{escape_string, parse_int};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -16,13 +18,28 @@ pub enum Value {
     Bool(bool),
     Vector(Vec<f32>),      // Embedding vector
     Matrix(Vec<Vec<f32>>), // Matrix (2D Tensor)
+    Timestamp(DateTime<Utc>),
+    Json(serde_json::Value), // Semi-structured document
+    List(Vec<Value>),        // Variable-length list of scalars
     Null,
 }
 
+/// Bits identifying a float for equality and hashing: `-0.0` equals `0.0`
+/// and every NaN equals every other, so such keys group and dedupe together
+fn float_key(f: f32) -> u32 {
+    if f == 0.0 {
+        0
+    } else if f.is_nan() {
+        f32::NAN.to_bits()
+    } else {
+        f.to_bits()
+    }
+}
+
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::Float(a), Value::Float(b)) => float_key(*a) == float_key(*b),
             (Value::Int(a), Value::Int(b)) => a == b,
             (Value::String(a), Value::String(b)) => a == b,
             (Value::Bool(a), Value::Bool(b)) => a == b,
@@ -30,7 +47,7 @@ impl PartialEq for Value {
                 if a.len() != b.len() {
                     return false;
                 }
-                a.iter().zip(b).all(|(x, y)| x.to_bits() == y.to_bits())
+                a.iter().zip(b).all(|(x, y)| float_key(*x) == float_key(*y))
             }
             (Value::Matrix(a), Value::Matrix(b)) => {
                 if a.len() != b.len() {
@@ -43,13 +60,16 @@ impl PartialEq for Value {
                     if !a[i]
                         .iter()
                         .zip(&b[i])
-                        .all(|(x, y)| x.to_bits() == y.to_bits())
+                        .all(|(x, y)| float_key(*x) == float_key(*y))
                     {
                         return false;
                     }
                 }
                 true
             }
+            (Value::Timestamp(a), Value::Timestamp(b)) => a == b,
+            (Value::Json(a), Value::Json(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
             (Value::Null, Value::Null) => true,
             _ => false,
         }
@@ -62,14 +82,14 @@ impl std::hash::Hash for Value {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         std::mem::discriminant(self).hash(state);
         match self {
-            Value::Float(v) => v.to_bits().hash(state),
+            Value::Float(v) => float_key(*v).hash(state),
             Value::Int(v) => v.hash(state),
             Value::String(v) => v.hash(state),
             Value::Bool(v) => v.hash(state),
             Value::Vector(v) => {
                 v.len().hash(state);
                 for f in v {
-                    f.to_bits().hash(state);
+                    float_key(*f).hash(state);
                 }
             }
             Value::Matrix(m) => {
@@ -79,10 +99,14 @@ impl std::hash::Hash for Value {
                 }
                 for row in m {
                     for f in row {
-                        f.to_bits().hash(state);
+                        float_key(*f).hash(state);
                     }
                 }
             }
+            Value::Timestamp(v) => v.hash(state),
+            // Objects are key-sorted, so equal documents print the same
+            Value::Json(v) => v.to_string().hash(state),
+            Value::List(v) => v.hash(state),
             Value::Null => {}
         }
     }
@@ -97,6 +121,9 @@ pub enum ValueType {
     Bool,
     Vector(usize),        // Vector with fixed dimension
     Matrix(usize, usize), // Matrix (rows, cols)
+    Timestamp,
+    Json,
+    List(Box<ValueType>), // List of any length with elements of one type
     Null,
 }
 
@@ -116,6 +143,16 @@ impl Value {
                     ValueType::Matrix(m.len(), m[0].len())
                 }
             }
+            Value::Timestamp(_) => ValueType::Timestamp,
+            Value::Json(_) => ValueType::Json,
+            // Typed by the first element that is not NULL
+            Value::List(items) => ValueType::List(Box::new(
+                items
+                    .iter()
+                    .map(Value::value_type)
+                    .find(|t| *t != ValueType::Null)
+                    .unwrap_or(ValueType::Null),
+            )),
             Value::Null => ValueType::Null,
         }
     }
@@ -127,6 +164,16 @@ impl Value {
         matches!(self, Value::Null)
     }
 
+    /// Whether this is a NaN or infinite float, or a vector or matrix holding one
+    pub fn is_non_finite(&self) -> bool {
+        match self {
+            Value::Float(f) => !f.is_finite(),
+            Value::Vector(v) => v.iter().any(|x| !x.is_finite()),
+            Value::Matrix(m) => m.iter().flatten().any(|x| !x.is_finite()),
+            _ => false,
+        }
+    }
+
     /// Try to convert to f32
     pub fn as_float(&self) -> Option<f32> {
         match self {
@@ -161,6 +208,45 @@ impl Value {
         }
     }
 
+    /// Try to get timestamp
+    pub fn as_timestamp(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Value::Timestamp(ts) => Some(*ts),
+            _ => None,
+        }
+    }
+
+    /// Try to get a JSON document reference
+    pub fn as_json(&self) -> Option<&serde_json::Value> {
+        match self {
+            Value::Json(j) => Some(j),
+            _ => None,
+        }
+    }
+
+    /// The scalar a JSON number, string, boolean or null stands for; `None`
+    /// for arrays and objects
+    pub fn from_json_scalar(json: &serde_json::Value) -> Option<Value> {
+        match json {
+            serde_json::Value::Null => Some(Value::Null),
+            serde_json::Value::Bool(b) => Some(Value::Bool(*b)),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Some(Value::Int(i)),
+                None => n.as_f64().map(|f| Value::Float(f as f32)),
+            },
+            serde_json::Value::String(s) => Some(Value::String(s.clone())),
+            _ => None,
+        }
+    }
+
+    /// Try to get the elements of a list
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
     /// Try to get vector reference
     pub fn as_vector(&self) -> Option<&[f32]> {
         match self {
@@ -169,6 +255,116 @@ impl Value {
         }
     }
 
+    /// Implicit coercion, applied without a CAST wherever a value meets a
+    /// column or operand of another type: inserts, comparisons, CASE /
+    /// COALESCE / WHERE branches and computed columns. `None` where only an
+    /// explicit `CAST` (see [`Value::cast_to`]) converts:
+    ///
+    /// | from \ to | Int  | Float | String | Bool | Timestamp | Json |
+    /// |-----------|------|-------|--------|------|-----------|------|
+    /// | Int       | =    | yes   | CAST   | CAST | CAST      | CAST |
+    /// | Float     | CAST | =     | CAST   | CAST | no        | CAST |
+    /// | String    | CAST | CAST  | =      | CAST | yes       | yes  |
+    /// | Bool      | CAST | CAST  | CAST   | =    | no        | CAST |
+    /// | Timestamp | CAST | no    | CAST   | no   | =         | no   |
+    /// | Json      | CAST | CAST  | CAST   | CAST | no        | =    |
+    ///
+    /// NULL coerces to every type. A list coerces element by element to
+    /// another list type, and a vector becomes a list of floats (or of ints
+    /// when every component is whole).
+    pub fn coerce_to(&self, target: &ValueType) -> Option<Value> {
+        match (self, target) {
+            (Value::Null, _) => Some(Value::Null),
+            (value, target) if value.matches_type(target) => Some(value.clone()),
+            (Value::Int(i), ValueType::Float) => Some(Value::Float(*i as f32)),
+            (Value::String(s), ValueType::Timestamp) => {
+                crate::utils::parsing::parse_timestamp(s.trim()).map(Value::Timestamp)
+            }
+            (Value::String(s), ValueType::Json) => serde_json::from_str(s).ok().map(Value::Json),
+            (Value::List(items), ValueType::List(elem)) => items
+                .iter()
+                .map(|item| item.coerce_to(elem))
+                .collect::<Option<_>>()
+                .map(Value::List),
+            (Value::Vector(v), ValueType::List(elem)) => match **elem {
+                ValueType::Float => Some(Value::List(v.iter().map(|f| Value::Float(*f)).collect())),
+                ValueType::Int if v.iter().all(|f| f.fract() == 0.0) => Some(Value::List(
+                    v.iter().map(|f| Value::Int(*f as i64)).collect(),
+                )),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Explicit conversion to `target`, for `CAST` and `ALTER COLUMN ... TYPE`.
+    /// NULL stays NULL; strings are parsed and any scalar can become a string.
+    pub fn cast_to(&self, target: &ValueType) -> Result<Value, String> {
+        let cast = match (self, target) {
+            (Value::Null, _) => Some(Value::Null),
+            (value, target) if value.matches_type(target) => Some(value.clone()),
+            (Value::Int(i), ValueType::Float) => Some(Value::Float(*i as f32)),
+            // i64::MAX as f32 rounds up to 2^63, which is already out of range
+            (Value::Float(f), ValueType::Int) if f.is_finite() => {
+                if *f < i64::MIN as f32 || *f >= i64::MAX as f32 {
+                    return Err(format!("Float {} is out of range for Int", f));
+                }
+                Some(Value::Int(f.trunc() as i64))
+            }
+            (Value::Bool(b), ValueType::Int) => Some(Value::Int(*b as i64)),
+            (Value::Bool(b), ValueType::Float) => Some(Value::Float(*b as i64 as f32)),
+            (Value::Int(i), ValueType::Bool) => Some(Value::Bool(*i != 0)),
+            (Value::Float(f), ValueType::Bool) => Some(Value::Bool(*f != 0.0)),
+            (Value::String(s), ValueType::Int) => match parse_int(s.trim()) {
+                Some(parsed) => return parsed.map(Value::Int),
+                None => None,
+            },
+            (Value::String(s), ValueType::Float) => s.trim().parse().ok().map(Value::Float),
+            (Value::String(s), ValueType::Bool) => match s.trim().to_lowercase().as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            (Value::String(s), ValueType::Timestamp) => {
+                crate::utils::parsing::parse_timestamp(s.trim()).map(Value::Timestamp)
+            }
+            // Whole seconds since the Unix epoch
+            (Value::Int(i), ValueType::Timestamp) => {
+                DateTime::from_timestamp(*i, 0).map(Value::Timestamp)
+            }
+            (Value::Timestamp(ts), ValueType::Int) => Some(Value::Int(ts.timestamp())),
+            (Value::Timestamp(_), ValueType::String) => Some(Value::String(self.to_string())),
+            (Value::Int(_) | Value::Float(_) | Value::Bool(_), ValueType::String) => {
+                Some(Value::String(self.to_string()))
+            }
+            (Value::String(s), ValueType::Json) => serde_json::from_str(s).ok().map(Value::Json),
+            (Value::Int(i), ValueType::Json) => Some(Value::Json((*i).into())),
+            (Value::Float(f), ValueType::Json) => Some(Value::Json((*f as f64).into())),
+            (Value::Bool(b), ValueType::Json) => Some(Value::Json((*b).into())),
+            // A JSON string becomes its contents, anything else its text
+            (Value::Json(serde_json::Value::String(s)), ValueType::String) => {
+                Some(Value::String(s.clone()))
+            }
+            (Value::Json(j), ValueType::String) => Some(Value::String(j.to_string())),
+            (Value::List(_), ValueType::String) => Some(Value::String(self.to_string())),
+            (Value::List(items), ValueType::List(elem)) => {
+                return items
+                    .iter()
+                    .map(|item| item.cast_to(elem))
+                    .collect::<Result<_, _>>()
+                    .map(Value::List);
+            }
+            (Value::Json(j), ValueType::Int | ValueType::Float | ValueType::Bool) => {
+                return match Value::from_json_scalar(j) {
+                    Some(scalar) => scalar.cast_to(target),
+                    None => Err(format!("Cannot cast {} to {}", self, target)),
+                };
+            }
+            _ => None,
+        };
+        cast.ok_or_else(|| format!("Cannot cast {} to {}", self, target))
+    }
+
     /// Compare values (for sorting and filtering)
     pub fn compare(&self, other: &Value) -> Option<std::cmp::Ordering> {
         use std::cmp::Ordering;
@@ -178,16 +374,73 @@ impl Value {
             (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
             (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
             (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::Timestamp(a), Value::Timestamp(b)) => Some(a.cmp(b)),
             (Value::Null, Value::Null) => Some(Ordering::Equal),
             (Value::Null, _) => Some(Ordering::Less),
             (_, Value::Null) => Some(Ordering::Greater),
             // Cross-type numeric comparison
             (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f32)),
             (Value::Int(a), Value::Float(b)) => (*a as f32).partial_cmp(b),
+            (Value::Json(a), Value::Json(b)) if a == b => Some(Ordering::Equal),
+            // Element by element, then the shorter list first
+            (Value::List(a), Value::List(b)) => {
+                for (x, y) in a.iter().zip(b) {
+                    match x.compare(y)? {
+                        Ordering::Equal => {}
+                        other => return Some(other),
+                    }
+                }
+                Some(a.len().cmp(&b.len()))
+            }
+            // JSON scalars compare as the value they hold
+            (Value::Json(a), b) => Value::from_json_scalar(a)
+                .filter(|a| !matches!(a, Value::Null))
+                .and_then(|a| a.compare(b)),
+            (a, Value::Json(b)) => Value::from_json_scalar(b)
+                .filter(|b| !matches!(b, Value::Null))
+                .and_then(|b| a.compare(&b)),
             _ => None, // Vectors and Matrices not comparable for sorting currently
         }
     }
 
+    /// Total order for reproducible output: `compare` where it is defined,
+    /// vectors and matrices element by element, anything else by type
+    pub fn total_cmp(&self, other: &Value) -> std::cmp::Ordering {
+        fn floats(a: &[f32], b: &[f32]) -> std::cmp::Ordering {
+            a.iter()
+                .zip(b)
+                .map(|(x, y)| x.total_cmp(y))
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len()))
+        }
+        fn rank(v: &Value) -> u8 {
+            match v {
+                Value::Null => 0,
+                Value::Bool(_) => 1,
+                Value::Int(_) | Value::Float(_) => 2,
+                Value::Timestamp(_) => 3,
+                Value::String(_) => 4,
+                Value::Vector(_) => 5,
+                Value::Matrix(_) => 6,
+                Value::Json(_) => 7,
+                Value::List(_) => 8,
+            }
+        }
+
+        match (self, other) {
+            (Value::Vector(a), Value::Vector(b)) => floats(a, b),
+            (Value::Matrix(a), Value::Matrix(b)) => a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| floats(x, y))
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            _ => self
+                .compare(other)
+                .unwrap_or_else(|| rank(self).cmp(&rank(other))),
+        }
+    }
+
     /// Check if this value matches the given type
     pub fn matches_type(&self, value_type: &ValueType) -> bool {
         match (self, value_type) {
@@ -195,6 +448,11 @@ impl Value {
             (Value::Int(_), ValueType::Int) => true,
             (Value::String(_), ValueType::String) => true,
             (Value::Bool(_), ValueType::Bool) => true,
+            (Value::Timestamp(_), ValueType::Timestamp) => true,
+            (Value::Json(_), ValueType::Json) => true,
+            (Value::List(items), ValueType::List(elem)) => {
+                items.iter().all(|item| item.matches_type(elem))
+            }
             (Value::Vector(v), ValueType::Vector(dim)) => v.len() == *dim,
             (Value::Matrix(m), ValueType::Matrix(r, c)) => {
                 m.len() == *r && (m.is_empty() || m[0].len() == *c)
@@ -210,7 +468,7 @@ impl fmt::Display for Value {
         match self {
             Value::Float(v) => write!(f, "{}", v),
             Value::Int(v) => write!(f, "{}", v),
-            Value::String(v) => write!(f, "\"{}\"", v),
+            Value::String(v) => write!(f, "\"{}\"", escape_string(v)),
             Value::Bool(v) => write!(f, "{}", v),
             Value::Vector(v) => {
                 write!(f, "[")?;
@@ -239,11 +497,51 @@ impl fmt::Display for Value {
                 }
                 write!(f, "]")
             }
+            Value::Timestamp(ts) => write!(
+                f,
+                "{}",
+                ts.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+            ),
+            Value::Json(v) => write!(f, "{}", v),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
             Value::Null => write!(f, "NULL"),
         }
     }
 }
 
+impl ValueType {
+    /// The type operands of `self` and `other` both coerce to implicitly
+    /// (see [`Value::coerce_to`]), if any: Int and Float meet at Float, a
+    /// String meets a Timestamp or Json at that type and NULL meets anything
+    pub fn common_type(&self, other: &ValueType) -> Option<ValueType> {
+        match (self, other) {
+            (a, b) if a == b => Some(a.clone()),
+            (ValueType::Null, t) | (t, ValueType::Null) => Some(t.clone()),
+            (ValueType::Int, ValueType::Float) | (ValueType::Float, ValueType::Int) => {
+                Some(ValueType::Float)
+            }
+            (ValueType::String, ValueType::Timestamp)
+            | (ValueType::Timestamp, ValueType::String) => Some(ValueType::Timestamp),
+            (ValueType::String, ValueType::Json) | (ValueType::Json, ValueType::String) => {
+                Some(ValueType::Json)
+            }
+            (ValueType::List(a), ValueType::List(b)) => {
+                a.common_type(b).map(|t| ValueType::List(Box::new(t)))
+            }
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for ValueType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -253,6 +551,9 @@ impl fmt::Display for ValueType {
             ValueType::Bool => write!(f, "BOOL"),
             ValueType::Vector(dim) => write!(f, "VECTOR[{}]", dim),
             ValueType::Matrix(r, c) => write!(f, "MATRIX[{}, {}]", r, c),
+            ValueType::Timestamp => write!(f, "TIMESTAMP"),
+            ValueType::Json => write!(f, "JSON"),
+            ValueType::List(elem) => write!(f, "LIST<{}>", elem),
             ValueType::Null => write!(f, "NULL"),
         }
     }
@@ -278,5 +579,35 @@ mod tests {
         assert_eq!(Value::Null.value_type(), ValueType::Null);
     }
 
+    #[test]
+    fn test_implicit_coercion_matrix() {
+        assert_eq!(
+            Value::Int(2).coerce_to(&ValueType::Float),
+            Some(Value::Float(2.0))
+        );
+        assert_eq!(Value::Null.coerce_to(&ValueType::Bool), Some(Value::Null));
+        assert!(Value::String("2024-01-02".into())
+            .coerce_to(&ValueType::Timestamp)
+            .is_some());
+        // Lossy or parsing conversions need an explicit CAST
+        assert_eq!(Value::Float(2.5).coerce_to(&ValueType::Int), None);
+        assert_eq!(Value::String("7".into()).coerce_to(&ValueType::Int), None);
+        assert_eq!(Value::Bool(true).coerce_to(&ValueType::Int), None);
+        assert_eq!(
+            Value::String("7".into()).cast_to(&ValueType::Int),
+            Ok(Value::Int(7))
+        );
+
+        assert_eq!(
+            ValueType::Int.common_type(&ValueType::Float),
+            Some(ValueType::Float)
+        );
+        assert_eq!(
+            ValueType::Null.common_type(&ValueType::String),
+            Some(ValueType::String)
+        );
+        assert_eq!(ValueType::Bool.common_type(&ValueType::Int), None);
+    }
+
     // ...
 }
@@ -3,8 +3,20 @@ use crate::engine::EngineError;
 /// Errores del lenguaje de alto nivel (DSL)
 #[derive(Debug)]
 pub enum DslError {
-    Parse { line: usize, msg: String },
-    Engine { line: usize, source: EngineError },
+    Parse {
+        line: usize,
+        msg: String,
+    },
+    Engine {
+        line: usize,
+        source: EngineError,
+    },
+    /// Shape mismatch reported with the expression that caused it
+    ShapeMismatch {
+        line: usize,
+        expr: String,
+        source: Box<EngineError>,
+    },
 }
 
 impl std::fmt::Display for DslError {
@@ -16,6 +28,9 @@ impl std::fmt::Display for DslError {
             DslError::Engine { line, source } => {
                 write!(f, "[line {}] Engine error: {}", line, source)
             }
+            DslError::ShapeMismatch { line, expr, source } => {
+                write!(f, "[line {}] In `{}`: {}", line, expr, source)
+            }
         }
     }
 }
@@ -0,0 +1,234 @@
+use crate::core::tuple::{Schema, Tuple};
+use crate::core::value::{Value, ValueType};
+use crate::dsl::handlers::persistence::auto_persist_dataset;
+use crate::dsl::{DslError, DslOutput};
+use crate::engine::TensorDb;
+
+/// Rows sent per COPY command by streaming clients (the REPL and
+/// `POST /datasets/{name}/rows`); each batch is logged to the WAL on its own
+pub const COPY_BATCH_ROWS: usize = 1000;
+
+/// Row encoding of a COPY stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// One CSV record per line, values in schema column order
+    Csv,
+    /// One JSON object per line, keyed by column name
+    Json,
+}
+
+impl CopyFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(CopyFormat::Csv),
+            "json" | "ndjson" => Some(CopyFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CopyFormat::Csv => "csv",
+            CopyFormat::Json => "json",
+        }
+    }
+}
+
+/// Header line of `COPY name FROM STDIN [FORMAT csv | json]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyStatement {
+    pub dataset: String,
+    pub format: CopyFormat,
+}
+
+impl CopyStatement {
+    pub fn new(dataset: impl Into<String>, format: CopyFormat) -> Self {
+        Self {
+            dataset: dataset.into(),
+            format,
+        }
+    }
+
+    pub fn parse(header: &str, line_no: usize) -> Result<Self, DslError> {
+        let usage = || DslError::Parse {
+            line: line_no,
+            msg: "Expected: COPY dataset FROM STDIN [FORMAT csv | json]".to_string(),
+        };
+
+        let rest = header.trim().strip_prefix("COPY ").ok_or_else(usage)?;
+        let tokens: Vec<&str> = rest.split_whitespace().collect();
+        let format = match tokens.as_slice() {
+            [_, "FROM", "STDIN"] => CopyFormat::Csv,
+            [_, "FROM", "STDIN", "FORMAT", format] => {
+                CopyFormat::parse(format).ok_or_else(|| DslError::Parse {
+                    line: line_no,
+                    msg: format!("Unknown COPY format '{}' (expected csv or json)", format),
+                })?
+            }
+            _ => return Err(usage()),
+        };
+        Ok(Self::new(tokens[0], format))
+    }
+
+    /// The command carrying `rows`: this header followed by one row per line
+    pub fn command<'a>(&self, rows: impl IntoIterator<Item = &'a str>) -> String {
+        let mut command = format!(
+            "COPY {} FROM STDIN FORMAT {}",
+            self.dataset,
+            self.format.name()
+        );
+        for row in rows {
+            command.push('\n');
+            command.push_str(row);
+        }
+        command
+    }
+}
+
+/// Handle `COPY name FROM STDIN [FORMAT csv | json]` with the rows on the
+/// following lines. Blank lines are skipped; one bad row rejects the batch.
+pub fn handle_copy(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
+    let (header, body) = line.split_once('\n').unwrap_or((line, ""));
+    let statement = CopyStatement::parse(header, line_no)?;
+
+    let schema = db
+        .get_dataset(&statement.dataset)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?
+        .schema
+        .clone();
+
+    let mut tuples = Vec::new();
+    for (row_no, row) in body.lines().enumerate() {
+        let row = row.trim_end_matches('\r');
+        if row.trim().is_empty() {
+            continue;
+        }
+        let values = match statement.format {
+            CopyFormat::Csv => parse_csv_row(row, &schema),
+            CopyFormat::Json => parse_json_row(row, &schema),
+        };
+        let tuple = values
+            .and_then(|values| Tuple::new(schema.clone(), values))
+            .map_err(|e| DslError::Parse {
+                line: line_no,
+                msg: format!("COPY row {}: {}", row_no + 1, e),
+            })?;
+        tuples.push(tuple);
+    }
+
+    let count = tuples.len();
+    db.insert_rows(&statement.dataset, tuples)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+    auto_persist_dataset(db, &statement.dataset, line_no)?;
+
+    Ok(DslOutput::Affected {
+        rows: count,
+        op: "COPY".to_string(),
+    })
+}
+
+fn parse_csv_row(row: &str, schema: &Schema) -> Result<Vec<Value>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(row.as_bytes());
+    let record = match reader.records().next() {
+        Some(record) => record.map_err(|e| e.to_string())?,
+        None => csv::StringRecord::new(),
+    };
+    if record.len() != schema.len() {
+        return Err(format!(
+            "expected {} values, found {}",
+            schema.len(),
+            record.len()
+        ));
+    }
+
+    record
+        .iter()
+        .zip(&schema.fields)
+        .map(|(cell, field)| {
+            let cell = cell.trim();
+            if cell.is_empty() {
+                return Ok(Value::Null);
+            }
+            match field.value_type {
+                // Vectors and matrices are JSON arrays, as EXPORT writes them
+                ValueType::Vector(_) | ValueType::Matrix(_, _) => {
+                    let json = serde_json::from_str(cell)
+                        .map_err(|e| format!("column '{}': {}", field.name, e))?;
+                    json_value(&json, &field.value_type, &field.name)
+                }
+                _ => Value::String(cell.to_string())
+                    .cast_to(&field.value_type)
+                    .map_err(|e| format!("column '{}': {}", field.name, e)),
+            }
+        })
+        .collect()
+}
+
+fn parse_json_row(row: &str, schema: &Schema) -> Result<Vec<Value>, String> {
+    let object: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(row).map_err(|e| e.to_string())?;
+    if let Some(unknown) = object.keys().find(|k| schema.get_field(k).is_none()) {
+        return Err(format!("unknown column '{}'", unknown));
+    }
+
+    schema
+        .fields
+        .iter()
+        .map(|field| match object.get(&field.name) {
+            None => Ok(Value::Null),
+            Some(json) => json_value(json, &field.value_type, &field.name),
+        })
+        .collect()
+}
+
+fn json_value(
+    json: &serde_json::Value,
+    value_type: &ValueType,
+    column: &str,
+) -> Result<Value, String> {
+    use serde_json::Value as Json;
+
+    let floats = |items: &[Json]| -> Option<Vec<f32>> {
+        items.iter().map(|v| v.as_f64().map(|f| f as f32)).collect()
+    };
+    let value = match json {
+        Json::Null => Some(Value::Null),
+        Json::Bool(b) => Some(Value::Bool(*b)),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => Some(Value::Int(i)),
+            None => n.as_f64().map(|f| Value::Float(f as f32)),
+        },
+        Json::String(s) => Some(Value::String(s.clone())),
+        Json::Array(items) => match value_type {
+            ValueType::Matrix(_, _) => items
+                .iter()
+                .map(|row| row.as_array().and_then(|r| floats(r)))
+                .collect::<Option<Vec<_>>>()
+                .map(Value::Matrix),
+            _ => floats(items).map(Value::Vector),
+        },
+        Json::Object(_) => None,
+    };
+    let value = value.ok_or_else(|| format!("column '{}': unsupported value {}", column, json))?;
+
+    match (&value, value_type) {
+        (Value::Vector(v), ValueType::Vector(dim)) if v.len() != *dim => Err(format!(
+            "column '{}': expected {} components, found {}",
+            column,
+            dim,
+            v.len()
+        )),
+        (Value::Vector(_) | Value::Matrix(_), _) => Ok(value),
+        _ => value
+            .cast_to(value_type)
+            .map_err(|e| format!("column '{}': {}", column, e)),
+    }
+}
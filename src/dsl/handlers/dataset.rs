@@ -1,8 +1,15 @@
+use crate::core::dataset_legacy::Sample;
 use crate::core::tuple::{Field, Schema, Tuple};
 use crate::core::value::{Value, ValueType};
-use crate::engine::TensorDb;
+use crate::engine::{EngineError, JobTask, TensorDb};
+use crate::utils::parsing::{
+    parse_int, parse_string_literal, parse_timestamp, unquoted_char_indices, QuoteState,
+};
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::dsl::handlers::persistence::{auto_persist_dataset, auto_persist_drop};
+use crate::dsl::handlers::returning::{returning_table, split_returning};
 use crate::dsl::{DslError, DslOutput};
 
 /// DATASET name COLUMNS (col1: TYPE1, col2: TYPE2, ...)
@@ -13,18 +20,238 @@ pub fn handle_dataset(
     line: &str,
     line_no: usize,
 ) -> Result<DslOutput, DslError> {
-    if line.contains(" COLUMNS ") {
-        handle_dataset_creation(db, line, line_no)
-    } else if line.contains(" FROM ") {
-        handle_dataset_query(db, line, line_no)
-    } else if line.contains(" ADD COLUMN ") {
-        handle_add_column(db, line, line_no)
+    match parser::parse_statement(line, line_no)? {
+        Statement::DatasetQuery(query) => execute_dataset_query(db, &query, line_no),
+        Statement::Command { command, line } => match command {
+            Command::CreateDataset => handle_dataset_creation(db, &line, line_no),
+            Command::AddColumn => handle_add_column(db, &line, line_no),
+            Command::AlterColumn => handle_alter_column(db, &line, line_no),
+            Command::SetTtl => handle_set_ttl(db, &line, line_no),
+            Command::MaskColumn => handle_mask_column(db, &line, line_no),
+            _ => Err(DslError::Parse {
+                line: line_no,
+                msg: format!("Not a DATASET command: {}", line),
+            }),
+        },
+        _ => Err(DslError::Parse {
+            line: line_no,
+            msg: format!("Not a DATASET command: {}", line),
+        }),
+    }
+}
+
+/// DATASET name SET TTL <seconds> [ROWS | DATASET]
+/// ROWS (default) expires each row <seconds> after it was inserted; DATASET
+/// drops the whole dataset once it has not been written to for that long.
+/// A TTL of 0 or NONE removes it.
+fn handle_set_ttl(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
+    use crate::core::dataset_legacy::{TTL_METADATA_KEY, TTL_SCOPE_METADATA_KEY};
+
+    let syntax_err = || DslError::Parse {
+        line: line_no,
+        msg: "Expected: DATASET name SET TTL <seconds> [ROWS | DATASET]".into(),
+    };
+    let rest = line.strip_prefix("DATASET ").unwrap();
+    let (name, spec) = rest.split_once(" SET TTL ").ok_or_else(syntax_err)?;
+    let name = name.trim();
+    let mut parts = spec.split_whitespace();
+    let secs = match parts.next().ok_or_else(syntax_err)? {
+        "NONE" => 0,
+        n => n.parse::<u64>().map_err(|_| syntax_err())?,
+    };
+    let scope = match parts.next() {
+        None | Some("ROWS") => "rows",
+        Some("DATASET") => "dataset",
+        Some(_) => return Err(syntax_err()),
+    };
+    if parts.next().is_some() {
+        return Err(syntax_err());
+    }
+
+    let dataset = db.get_dataset_mut(name).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    let message = if secs == 0 {
+        dataset.metadata.extra.remove(TTL_METADATA_KEY);
+        dataset.metadata.extra.remove(TTL_SCOPE_METADATA_KEY);
+        format!("Removed TTL of dataset '{}'", name)
     } else {
-        Err(DslError::Parse {
+        dataset
+            .metadata
+            .extra
+            .insert(TTL_METADATA_KEY.to_string(), secs.to_string());
+        dataset
+            .metadata
+            .extra
+            .insert(TTL_SCOPE_METADATA_KEY.to_string(), scope.to_string());
+        format!("Set TTL of dataset '{}' to {}s ({})", name, secs, scope)
+    };
+
+    auto_persist_dataset(db, name, line_no)?;
+    Ok(DslOutput::Message(message))
+}
+
+/// DATASET name MASK COLUMN col USING expr [EXCEPT ROLES role, ...]
+/// Callers with a role see `expr` instead of the column's values, unless
+/// their role is exempt. UNMASK COLUMN removes the policy.
+/// Callers with a role need an admin role, or one the column's current
+/// policy exempts, to change it.
+fn handle_mask_column(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let statement = parser::parse_mask_statement(line, line_no)?;
+    let parse_err = |msg: String| DslError::Parse { line: line_no, msg };
+    if let Some(role) = db.role() {
+        let exempt = db
+            .get_dataset(&statement.dataset)
+            .ok()
+            .and_then(|ds| ds.metadata.masking.get(&statement.column))
+            .is_some_and(|policy| !policy.applies_to(Some(role)));
+        if !exempt && !db.config.security.is_admin(role) {
+            return Err(DslError::Engine {
+                line: line_no,
+                source: EngineError::InvalidOp(format!(
+                    "Role '{}' may not change the masking of column '{}' of dataset '{}'",
+                    role, statement.column, statement.dataset
+                )),
+            });
+        }
+    }
+    let dataset = db
+        .get_dataset_mut(&statement.dataset)
+        .map_err(|e| DslError::Engine {
             line: line_no,
-            msg: "Expected DATASET ... COLUMNS ... or DATASET ... FROM ... or DATASET ... ADD COLUMN ...".into(),
-        })
+            source: e,
+        })?;
+    if dataset.schema.get_field_index(&statement.column).is_none() {
+        return Err(parse_err(format!(
+            "Column '{}' not found in dataset '{}'",
+            statement.column, statement.dataset
+        )));
     }
+
+    let message = match statement.policy {
+        Some(policy) => {
+            let expr = parser::parse_expression(&policy.expression, line_no)?;
+            if let Some(unknown) = expr
+                .columns()
+                .into_iter()
+                .find(|c| dataset.schema.get_field_index(c).is_none())
+            {
+                return Err(parse_err(format!(
+                    "Masking expression reads unknown column '{}'",
+                    unknown
+                )));
+            }
+            let message = format!(
+                "Masked column '{}' of dataset '{}' with {}",
+                statement.column, statement.dataset, policy.expression
+            );
+            dataset
+                .metadata
+                .masking
+                .insert(statement.column.clone(), policy);
+            message
+        }
+        None => {
+            if dataset.metadata.masking.remove(&statement.column).is_none() {
+                return Err(parse_err(format!(
+                    "Column '{}' of dataset '{}' is not masked",
+                    statement.column, statement.dataset
+                )));
+            }
+            format!(
+                "Removed masking of column '{}' of dataset '{}'",
+                statement.column, statement.dataset
+            )
+        }
+    };
+
+    auto_persist_dataset(db, &statement.dataset, line_no)?;
+    Ok(DslOutput::Message(message))
+}
+
+/// DROP DATASET name
+pub fn handle_drop_dataset(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let name = single_dataset_name(line, "DROP DATASET", line_no)?;
+    let rows = db.drop_dataset(name).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    auto_persist_drop(db, name, line_no)?;
+    Ok(DslOutput::Message(format!(
+        "Dropped dataset '{}' ({} rows)",
+        name, rows
+    )))
+}
+
+/// RENAME DATASET old TO new
+pub fn handle_rename_dataset(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let rest = line.trim_start_matches("RENAME DATASET").trim();
+    let (from, to) = match rest.split_whitespace().collect::<Vec<_>>()[..] {
+        [from, kw, to] if kw.eq_ignore_ascii_case("TO") => (from, to),
+        _ => {
+            return Err(DslError::Parse {
+                line: line_no,
+                msg: "Expected: RENAME DATASET <name> TO <new_name>".into(),
+            })
+        }
+    };
+    db.rename_dataset(from, to).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    auto_persist_drop(db, from, line_no)?;
+    auto_persist_dataset(db, to, line_no)?;
+    Ok(DslOutput::Message(format!(
+        "Renamed dataset '{}' to '{}'",
+        from, to
+    )))
+}
+
+/// TRUNCATE DATASET name
+pub fn handle_truncate_dataset(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let name = single_dataset_name(line, "TRUNCATE DATASET", line_no)?;
+    let rows = db.truncate_dataset(name).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    auto_persist_dataset(db, name, line_no)?;
+    Ok(DslOutput::Affected {
+        rows,
+        op: "TRUNCATE".into(),
+    })
+}
+
+/// The dataset name following `keywords`, the only argument of the command
+fn single_dataset_name<'a>(
+    line: &'a str,
+    keywords: &str,
+    line_no: usize,
+) -> Result<&'a str, DslError> {
+    let name = line.trim_start_matches(keywords).trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: format!("Expected: {} <name>", keywords),
+        });
+    }
+    Ok(name)
 }
 
 fn handle_dataset_creation(
@@ -55,36 +282,36 @@ fn handle_dataset_creation(
             line: line_no,
             source: e,
         })?;
+    auto_persist_dataset(db, &name, line_no)?;
 
     Ok(DslOutput::Message(format!("Created dataset: {}", name)))
 }
 
-use crate::query::logical::{Expr, LogicalPlan};
-use crate::query::planner::Planner;
+use crate::dsl::parser::{
+    self, Command, DatasetQuery, DeleteStatement, FromClause, InSubquery, JoinClause, JoinSource,
+    QueryClauses, SelectStatement, Statement, UpdateStatement, VersionSpec,
+};
+use crate::query::logical::{Expr, LogicalPlan, ScalarFunction};
 
 /// DATASET target FROM source [FILTER col > val] [SELECT col1, col2] [ORDER BY col [DESC]] [LIMIT n]
-fn handle_dataset_query(
+pub fn execute_dataset_query(
     db: &mut TensorDb,
-    line: &str,
+    query: &DatasetQuery,
     line_no: usize,
 ) -> Result<DslOutput, DslError> {
-    let (target_name, current_plan) = build_dataset_query_plan(db, line, line_no)?;
+    let current_plan = plan_dataset_query(db, query, line_no)?;
+    let target_name = &query.target;
 
     // Plan & Execute
-    let planner = Planner::new(db);
-    let physical_plan =
-        planner
-            .create_physical_plan(&current_plan)
-            .map_err(|e| DslError::Engine {
-                line: line_no,
-                source: e,
-            })?;
-
-    let result_rows = physical_plan.execute(db).map_err(|e| DslError::Engine {
-        line: line_no,
-        source: e,
-    })?;
-    let result_schema = physical_plan.schema();
+    let result = db
+        .execute_plan(&current_plan)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+    let result_schema = result.schema;
+    let result_rows = result.rows;
+    let lineage = result.metadata.lineage;
 
     // Create target dataset
     db.create_dataset(target_name.to_string(), result_schema)
@@ -95,225 +322,402 @@ fn handle_dataset_query(
 
     // Insert rows into target
     let target_ds = db
-        .get_dataset_mut(&target_name)
+        .get_dataset_mut(target_name)
         .map_err(|e| DslError::Engine {
             line: line_no,
             source: e,
         })?;
     target_ds.rows = result_rows;
+    target_ds.metadata.lineage = lineage;
     // Update metadata/stats
     target_ds
         .metadata
         .update_stats(&target_ds.schema, &target_ds.rows);
+    auto_persist_dataset(db, target_name, line_no)?;
 
     Ok(DslOutput::None)
 }
 
 /// SELECT ... FROM ...
 pub fn handle_select(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
-    let working_plan = build_select_query_plan(db, line, line_no)?;
+    let select = parser::parse_select(line, line_no)?;
+    execute_select(db, &select, line_no)
+}
 
-    // Execution
-    let planner = Planner::new(db);
-    let physical_plan =
-        planner
-            .create_physical_plan(&working_plan)
-            .map_err(|e| DslError::Engine {
-                line: line_no,
-                source: e,
-            })?;
-    let result_rows = physical_plan.execute(db).map_err(|e| DslError::Engine {
-        line: line_no,
-        source: e,
-    })?;
+/// Plan and run a parsed SELECT
+pub fn execute_select(
+    db: &mut TensorDb,
+    select: &SelectStatement,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let working_plan = plan_select(db, select, line_no)?;
 
-    // Construct Dataset for Output
-    let result_schema = physical_plan.schema();
-    let ds = crate::core::dataset_legacy::Dataset::with_rows(
-        crate::core::dataset_legacy::DatasetId(0),
-        result_schema.clone(),
-        result_rows.clone(),
-        Some("Query Result".into()),
-    )
-    .map_err(|e| DslError::Parse {
-        line: line_no,
-        msg: e,
-    })?;
+    let ds = db
+        .execute_plan(&working_plan)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
 
     Ok(DslOutput::Table(ds))
 }
 
+/// Build the plan of a SELECT, optionally preceded by common table expressions:
+/// WITH name AS (SELECT ...)[, name2 AS (SELECT ...)] SELECT ... FROM name ...
 pub fn build_select_query_plan(
     db: &mut TensorDb,
     line: &str,
     line_no: usize,
 ) -> Result<LogicalPlan, DslError> {
-    // Parse: SELECT col1, col2, ... FROM source [FILTER ...] [GROUP BY ...]
+    let select = parser::parse_select(line, line_no)?;
+    plan_select(db, &select, line_no)
+}
 
-    // Find FROM
-    let from_idx = line.find(" FROM ").ok_or_else(|| DslError::Parse {
-        line: line_no,
-        msg: "Expected SELECT ... FROM source ...".into(),
-    })?;
+/// Plan each CTE body inline (later CTEs may reference earlier ones), then the main SELECT
+pub fn plan_select(
+    db: &mut TensorDb,
+    select: &SelectStatement,
+    line_no: usize,
+) -> Result<LogicalPlan, DslError> {
+    let mut ctes = HashMap::new();
+    for (name, body) in &select.ctes {
+        let plan = build_select_plan(db, body, line_no, &ctes)?;
+        ctes.insert(name.clone(), plan);
+    }
+    build_select_plan(db, select, line_no, &ctes)
+}
 
-    // cols part: "SELECT col1, ..."
-    let cols_part = line[..from_idx].trim();
-    // rest part: "source [FILTER ...]"
-    let rest_part = line[from_idx + 6..].trim(); // skip " FROM "
+/// Byte index of the parenthesis closing the one `s` starts with (quote-aware)
+pub(crate) fn find_matching_paren(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, ch) in unquoted_char_indices(s) {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
 
-    // Extract source name (first word of rest_part)
-    let parts: Vec<&str> = rest_part.splitn(2, ' ').collect();
-    let source_name = parts[0];
-    let clauses_str = if parts.len() > 1 { parts[1] } else { "" };
+fn build_select_plan(
+    db: &mut TensorDb,
+    select: &SelectStatement,
+    line_no: usize,
+    ctes: &HashMap<String, LogicalPlan>,
+) -> Result<LogicalPlan, DslError> {
+    // A parenthesized subquery or a CTE is planned inline in place of the Scan
+    let (working_plan, source_schema) = match &select.from {
+        FromClause::Subquery { query, .. } => {
+            let plan = build_select_plan(db, query, line_no, ctes)?;
+            let schema = plan.schema();
+            (plan, schema)
+        }
+        FromClause::Join { first, joins } => {
+            let plan = plan_join(db, first, joins, line_no, ctes)?;
+            let schema = plan.schema();
+            (plan, schema)
+        }
+        FromClause::Source { name, version } => match (ctes.get(name), version) {
+            (Some(cte_plan), None) => (cte_plan.clone(), cte_plan.schema()),
+            _ => {
+                // Time travel: FROM name VERSION n / FROM name AS OF '<timestamp>'
+                let dataset_name = versioned_source(db, name, version.as_ref(), line_no)?;
+                let source_ds = db
+                    .get_dataset(&dataset_name)
+                    .map_err(|e| DslError::Engine {
+                        line: line_no,
+                        source: e,
+                    })?;
+                let source_schema = source_ds.schema.clone();
+                let scan = LogicalPlan::Scan {
+                    dataset_name,
+                    schema: source_schema.clone(),
+                };
+                (scan, source_schema)
+            }
+        },
+    };
 
-    // Build Plan
-    let source_ds = db.get_dataset(source_name).map_err(|e| DslError::Engine {
-        line: line_no,
-        source: e,
-    })?;
-    let source_schema = source_ds.schema.clone();
+    let mut clauses = select.clauses.clone();
+    let mut exprs = select.items.clone();
 
-    let mut working_plan = LogicalPlan::Scan {
-        dataset_name: source_name.to_string(),
-        schema: source_schema.clone(),
-    };
+    if matches!(working_plan, LogicalPlan::Join { .. }) {
+        qualify_query_columns(&mut clauses, &mut exprs, &source_schema)
+            .map_err(|msg| DslError::Parse { line: line_no, msg })?;
+    }
+    // Columns of a derived table may be qualified by its alias
+    if let FromClause::Subquery {
+        alias: Some(alias), ..
+    } = &select.from
+    {
+        let prefix = format!("{}.", alias);
+        rename_query_columns(&mut clauses, &mut exprs, &|name| {
+            Ok(name.strip_prefix(&prefix).unwrap_or(name).to_string())
+        })
+        .map_err(|msg| DslError::Parse { line: line_no, msg })?;
+    }
 
-    let mut pending_group_by: Option<Vec<Expr>> = None;
-    let mut remaining_clauses = clauses_str.to_string();
-    let keywords = ["FILTER", "WHERE", "ORDER BY", "LIMIT", "GROUP BY", "HAVING"];
+    let working_plan = sample_source(working_plan, clauses.sample.take());
+    let subqueries = std::mem::take(&mut clauses.in_subqueries);
+    let working_plan = plan_in_subqueries(db, working_plan, subqueries, line_no, ctes)?;
+    build_clause_plan(working_plan, &source_schema, clauses, Some(exprs), line_no)
+}
 
-    // We process clauses from `clauses_str`
-    while !remaining_clauses.is_empty() {
-        let clauses_trimmed = remaining_clauses.trim();
-        if clauses_trimmed.is_empty() {
-            break;
+/// `FROM a [[AS] x] [ASOF] [INNER | LEFT [OUTER]] JOIN b [[AS] y] ON x.col = y.col [JOIN ...]`
+/// Sources are datasets or CTEs; a source without an alias is qualified by
+/// its own name.
+fn plan_join(
+    db: &TensorDb,
+    first: &JoinSource,
+    joins: &[JoinClause],
+    line_no: usize,
+    ctes: &HashMap<String, LogicalPlan>,
+) -> Result<LogicalPlan, DslError> {
+    let parse_err = |msg: String| DslError::Parse { line: line_no, msg };
+    let source_plan = |name: &str| -> Result<LogicalPlan, DslError> {
+        if let Some(plan) = ctes.get(name) {
+            return Ok(plan.clone());
         }
+        let ds = db.get_dataset(name).map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+        Ok(LogicalPlan::Scan {
+            dataset_name: name.to_string(),
+            schema: ds.schema.clone(),
+        })
+    };
 
-        if clauses_trimmed.starts_with("FILTER ") || clauses_trimmed.starts_with("WHERE ") {
-            let kw = if clauses_trimmed.starts_with("WHERE ") {
-                "WHERE"
-            } else {
-                "FILTER"
-            };
-            let (cond_str, rem) = split_clause(clauses_trimmed, kw, &keywords);
-            let cond_string = cond_str.to_string();
-            remaining_clauses = rem.to_string();
-            let (col, op, val) = parse_filter_condition(&cond_string, line_no)?;
-            working_plan = LogicalPlan::Filter {
-                input: Box::new(working_plan),
-                predicate: Expr::BinaryExpr {
-                    left: Box::new(Expr::Column(col)),
-                    op,
-                    right: Box::new(Expr::Literal(val)),
-                },
-            };
-        } else if clauses_trimmed.starts_with("GROUP BY ") {
-            let (group_str, rem) = split_clause(clauses_trimmed, "GROUP BY", &keywords);
-            let group_string = group_str.to_string();
-            remaining_clauses = rem.to_string();
-            let cols: Vec<String> = group_string
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .collect();
-            let exprs: Vec<Expr> = cols.into_iter().map(Expr::Column).collect();
-            pending_group_by = Some(exprs);
-        } else if clauses_trimmed.starts_with("HAVING ") {
-            let (cond_str, rem) = split_clause(clauses_trimmed, "HAVING", &keywords);
-            let cond_string = cond_str.to_string();
-            remaining_clauses = rem.to_string();
-            let (col, op, val) = parse_filter_condition(&cond_string, line_no)?;
-
-            working_plan = LogicalPlan::Filter {
-                input: Box::new(working_plan),
-                predicate: Expr::BinaryExpr {
-                    left: Box::new(Expr::Column(col)),
-                    op,
-                    right: Box::new(Expr::Literal(val)),
-                },
-            };
-        } else if clauses_trimmed.starts_with("limit ") || clauses_trimmed.starts_with("LIMIT ") {
-            let (limit_str, rem) = split_clause(clauses_trimmed, "LIMIT", &keywords);
-            let limit_string = limit_str.to_string();
-            remaining_clauses = rem.to_string();
-            let n: usize = limit_string.parse().map_err(|_| DslError::Parse {
-                line: line_no,
-                msg: "Invalid limit".into(),
-            })?;
-            working_plan = LogicalPlan::Limit {
-                input: Box::new(working_plan),
-                n,
-            };
-        } else {
-            if clauses_trimmed.starts_with("ORDER BY ") {
-                let (order_str, rem) = split_clause(clauses_trimmed, "ORDER BY", &keywords);
-                let order_string = order_str.to_string();
-                remaining_clauses = rem.to_string();
-                let parts: Vec<&str> = order_string.split_whitespace().collect();
-                let col = parts[0].to_string();
-                let desc = parts.len() > 1 && parts[1].eq_ignore_ascii_case("DESC");
-                working_plan = LogicalPlan::Sort {
-                    input: Box::new(working_plan),
-                    column: col,
-                    ascending: !desc,
-                };
-            } else {
-                return Err(DslError::Parse {
-                    line: line_no,
-                    msg: format!("Unknown clause in SELECT: {}", clauses_trimmed),
-                });
+    let mut plan = source_plan(&first.name)?;
+    for join in joins {
+        let mut joined = LogicalPlan::Join {
+            left: Box::new(plan),
+            right: Box::new(source_plan(&join.source.name)?),
+            left_alias: first.alias.clone(),
+            right_alias: join.source.alias.clone(),
+            left_key: String::new(),
+            right_key: String::new(),
+            join_type: join.join_type,
+            asof: None,
+            similarity: None,
+        };
+        let schema = joined.schema();
+        let resolve = |column: &str| -> Result<(String, usize), DslError> {
+            let resolved = resolve_column(&schema, column).map_err(parse_err)?;
+            let idx = schema
+                .get_field_index(&resolved)
+                .ok_or_else(|| parse_err(format!("Unknown JOIN column: {}", column)))?;
+            Ok((resolved, idx))
+        };
+        let (lhs, lhs_idx) = resolve(&join.on.0)?;
+        let (rhs, rhs_idx) = resolve(&join.on.1)?;
+
+        // Either side of the condition may name the joined source
+        let left_width = match &joined {
+            LogicalPlan::Join { left, .. } => left.schema().len(),
+            _ => unreachable!(),
+        };
+        let (key_l, key_r) = match (lhs_idx < left_width, rhs_idx < left_width) {
+            (true, false) => (lhs, rhs),
+            (false, true) => (rhs, lhs),
+            _ => {
+                return Err(parse_err(format!(
+                    "JOIN condition must compare a column of {} with one of the sources before it",
+                    join.source.alias
+                )))
+            }
+        };
+        // ASOF: the left row's time must be the later one
+        let asof_columns = match &join.asof {
+            Some((later, earlier)) => {
+                let (later, later_idx) = resolve(later)?;
+                let (earlier, earlier_idx) = resolve(earlier)?;
+                if later_idx >= left_width || earlier_idx < left_width {
+                    return Err(parse_err(format!(
+                        "ASOF JOIN condition must be <left time> >= <{} time>: {} >= {}",
+                        join.source.alias, later, earlier
+                    )));
+                }
+                Some((later, earlier))
+            }
+            None => None,
+        };
+        // Similarity: both keys are vectors of one dimension
+        if join.similarity.is_some() {
+            let key_type = |name: &str| schema.get_field(name).map(|f| f.value_type.clone());
+            match (key_type(&key_l), key_type(&key_r)) {
+                (Some(ValueType::Vector(l)), Some(ValueType::Vector(r))) if l == r => {}
+                (l, r) => return Err(parse_err(format!(
+                    "SIMILARITY JOIN needs two vector columns of one dimension: {} is {}, {} is {}",
+                    key_l,
+                    l.map_or("unknown".to_string(), |t| t.to_string()),
+                    key_r,
+                    r.map_or("unknown".to_string(), |t| t.to_string())
+                ))),
             }
         }
-    }
-
-    // Finally apply Projection/Aggregation from the initial SELECT `cols_part`
-    let select_exprs_str = cols_part.trim_start_matches("SELECT ").trim();
-    let exprs = parse_select_items(select_exprs_str, line_no)?;
+        if let LogicalPlan::Join {
+            left_key,
+            right_key,
+            asof,
+            similarity,
+            ..
+        } = &mut joined
+        {
+            *left_key = key_l;
+            *right_key = key_r;
+            *asof = asof_columns;
+            *similarity = join.similarity;
+        }
 
-    // Check for Aggregates
-    let has_aggr = exprs
+        plan = joined;
+    }
+    Ok(plan)
+}
+/// Resolve a column of a joined row: qualified names are used as is, and an
+/// unqualified name must belong to exactly one source
+fn resolve_column(schema: &Schema, name: &str) -> Result<String, String> {
+    if name == "*" || schema.get_field(name).is_some() {
+        return Ok(name.to_string());
+    }
+    let suffix = format!(".{}", name);
+    let matches: Vec<&str> = schema
+        .fields
         .iter()
-        .any(|e| matches!(e, Expr::AggregateExpr { .. }));
+        .map(|f| f.name.as_str())
+        .filter(|n| n.ends_with(&suffix))
+        .collect();
+    match matches.as_slice() {
+        [only] => Ok(only.to_string()),
+        [] => Ok(name.to_string()),
+        _ => Err(format!(
+            "Ambiguous column '{}' (could be {})",
+            name,
+            matches.join(", ")
+        )),
+    }
+}
 
-    if pending_group_by.is_some() || has_aggr {
-        let group_expr = pending_group_by.unwrap_or_default();
-        let actual_aggs: Vec<Expr> = exprs
-            .into_iter()
-            .filter(|e| matches!(e, Expr::AggregateExpr { .. }))
-            .collect();
+/// Rewrite the unqualified column names of a join query to `alias.column`
+fn qualify_query_columns(
+    clauses: &mut QueryClauses,
+    select: &mut [Expr],
+    schema: &Schema,
+) -> Result<(), String> {
+    rename_query_columns(clauses, select, &|name| resolve_column(schema, name))
+}
 
-        working_plan = LogicalPlan::Aggregate {
-            input: Box::new(working_plan),
-            group_expr,
-            aggr_expr: actual_aggs,
-        };
-    } else {
-        // Simple Projection with Wildcard Expansion support
-        let mut cols = Vec::new();
-        for e in &exprs {
-            if let Expr::Column(c) = e {
-                if c == "*" {
-                    // Expand wildcard
-                    for field in &source_schema.fields {
-                        cols.push(field.name.clone());
-                    }
-                } else {
-                    cols.push(c.clone());
+/// Apply `rename` to every column a query's filters, group keys, SELECT
+/// list and ORDER BY refer to
+fn rename_query_columns(
+    clauses: &mut QueryClauses,
+    select: &mut [Expr],
+    rename: &dyn Fn(&str) -> Result<String, String>,
+) -> Result<(), String> {
+    fn walk(
+        expr: &mut Expr,
+        rename: &dyn Fn(&str) -> Result<String, String>,
+    ) -> Result<(), String> {
+        match expr {
+            Expr::Column(name) => *name = rename(name)?,
+            Expr::Literal(_) => {}
+            Expr::BinaryExpr { left, right, .. } => {
+                walk(left, rename)?;
+                walk(right, rename)?;
+            }
+            Expr::AggregateExpr { expr, .. } => walk(expr, rename)?,
+            Expr::ScalarFunction { args, .. } => {
+                for arg in args {
+                    walk(arg, rename)?;
                 }
-            } else {
-                return Err(DslError::Parse {
-                    line: line_no,
-                    msg: "Only columns or Aggregates supported".into(),
-                });
             }
+            Expr::WindowFunction {
+                args,
+                partition_by,
+                order_by,
+                ..
+            } => {
+                for arg in args {
+                    walk(arg, rename)?;
+                }
+                for column in partition_by {
+                    *column = rename(column)?;
+                }
+                *order_by = rename(order_by)?;
+            }
+            Expr::Alias { expr, .. } => walk(expr, rename)?,
         }
+        Ok(())
+    }
+
+    for expr in clauses
+        .filters
+        .iter_mut()
+        .chain(clauses.in_subqueries.iter_mut().map(|s| &mut s.expr))
+        .chain(clauses.group_by.iter_mut().flatten())
+        .chain(select.iter_mut())
+    {
+        walk(expr, rename)?;
+    }
+    if let Some((column, _)) = &mut clauses.order_by {
+        *column = rename(column)?;
+    }
+    Ok(())
+}
 
-        working_plan = LogicalPlan::Project {
-            input: Box::new(working_plan),
-            columns: cols,
+/// Wrap `plan` in a semi-join per `expr IN (SELECT ...)` condition, or an
+/// anti-join for NOT IN. Each subquery is planned like a FROM subquery.
+fn plan_in_subqueries(
+    db: &mut TensorDb,
+    mut plan: LogicalPlan,
+    subqueries: Vec<InSubquery>,
+    line_no: usize,
+    ctes: &HashMap<String, LogicalPlan>,
+) -> Result<LogicalPlan, DslError> {
+    for subquery in subqueries {
+        let subquery_plan = build_select_plan(db, &subquery.query, line_no, ctes)?;
+        let width = subquery_plan.schema().len();
+        if width != 1 {
+            return Err(DslError::Parse {
+                line: line_no,
+                msg: format!("Subquery in IN must return one column, got {}", width),
+            });
+        }
+        plan = LogicalPlan::SemiJoin {
+            input: Box::new(plan),
+            subquery: Box::new(subquery_plan),
+            expr: subquery.expr,
+            anti: subquery.negated,
         };
     }
+    Ok(plan)
+}
 
-    Ok(working_plan)
+/// Dataset to scan for `FROM source [VERSION n | AS OF ts]`: `source@n` for a specific version
+fn versioned_source(
+    db: &TensorDb,
+    source: &str,
+    version: Option<&VersionSpec>,
+    line_no: usize,
+) -> Result<String, DslError> {
+    let version = match version {
+        None => return Ok(source.to_string()),
+        Some(VersionSpec::Number(version)) => *version,
+        Some(VersionSpec::AsOf(ts)) => {
+            db.dataset_version_at(source, *ts)
+                .map_err(|e| DslError::Engine {
+                    line: line_no,
+                    source: e,
+                })?
+        }
+    };
+    Ok(format!("{}@{}", source, version))
 }
 
 pub fn build_dataset_query_plan(
@@ -321,252 +725,421 @@ pub fn build_dataset_query_plan(
     line: &str,
     line_no: usize,
 ) -> Result<(String, LogicalPlan), DslError> {
-    let rest = line.trim_start_matches("DATASET").trim();
+    let query = parser::parse_dataset_query(line, line_no)?;
+    let plan = plan_dataset_query(db, &query, line_no)?;
+    Ok((query.target, plan))
+}
 
-    // Split into target and FROM source...
-    let parts: Vec<&str> = rest.splitn(2, " FROM ").collect();
-    if parts.len() != 2 {
+fn plan_dataset_query(
+    db: &mut TensorDb,
+    query: &DatasetQuery,
+    line_no: usize,
+) -> Result<LogicalPlan, DslError> {
+    // Get source dataset schema for validation
+    let source_ds = db
+        .get_dataset(&query.source)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+    let source_schema = source_ds.schema.clone();
+
+    // Initial Plan: Scan
+    let scan = LogicalPlan::Scan {
+        dataset_name: query.source.clone(),
+        schema: source_schema.clone(),
+    };
+
+    let mut clauses = query.clauses.clone();
+    let select = clauses.select.take();
+    let plan = sample_source(scan, clauses.sample.take());
+    let subqueries = std::mem::take(&mut clauses.in_subqueries);
+    let plan = plan_in_subqueries(db, plan, subqueries, line_no, &HashMap::new())?;
+    build_clause_plan(plan, &source_schema, clauses, select, line_no)
+}
+
+/// SAMPLE picks from the source rows, before any condition is applied
+fn sample_source(source: LogicalPlan, sample: Option<Sample>) -> LogicalPlan {
+    match sample {
+        Some(sample) => LogicalPlan::Sample {
+            input: Box::new(source),
+            sample,
+        },
+        None => source,
+    }
+}
+
+/// Build the plan for a query's clauses in canonical order, independent of
+/// their textual order: filter -> group -> having -> order -> limit -> projection
+pub(crate) fn build_clause_plan(
+    input: LogicalPlan,
+    source_schema: &Schema,
+    clauses: QueryClauses,
+    select: Option<Vec<Expr>>,
+    line_no: usize,
+) -> Result<LogicalPlan, DslError> {
+    let mut plan = input;
+    for predicate in clauses.filters {
+        if contains_unnest(&predicate) {
+            return Err(DslError::Parse {
+                line: line_no,
+                msg: "UNNEST is only allowed as a SELECT item".into(),
+            });
+        }
+        plan = LogicalPlan::Filter {
+            input: Box::new(plan),
+            predicate,
+        };
+    }
+    let (mut plan, select) = plan_unnest(plan, select, line_no)?;
+
+    let has_aggr = select
+        .iter()
+        .flatten()
+        .any(|e| matches!(e.unaliased(), Expr::AggregateExpr { .. }));
+
+    if clauses.group_by.is_some() || has_aggr {
+        // Non-aggregates (Columns) are assumed to be group keys, so the
+        // schema (keys + aggs) matches execution (keys + accumulators)
+        let select = select.unwrap_or_default();
+        let mut aggr_expr: Vec<Expr> = select
+            .iter()
+            .map(Expr::unaliased)
+            .filter(|e| matches!(e, Expr::AggregateExpr { .. }))
+            .cloned()
+            .collect();
+        let aggr_count = aggr_expr.len();
+        // HAVING runs on the aggregated rows; aggregates only it uses are
+        // computed as extra columns and dropped afterwards
+        let having: Vec<Expr> = clauses
+            .having
+            .into_iter()
+            .map(|predicate| having_columns(predicate, &select, &mut aggr_expr))
+            .collect();
+        let hidden = aggr_expr.len() - aggr_count;
+        plan = LogicalPlan::Aggregate {
+            input: Box::new(plan),
+            group_expr: group_keys(clauses.group_by.unwrap_or_default(), &select, source_schema),
+            rollup: clauses.rollup,
+            aggr_expr,
+        };
+        for predicate in having {
+            plan = LogicalPlan::Filter {
+                input: Box::new(plan),
+                predicate,
+            };
+        }
+        if hidden > 0 || select.iter().any(|e| matches!(e, Expr::Alias { .. })) {
+            plan = rename_aggregate_output(plan, &select, aggr_count, hidden);
+        }
+        return Ok(apply_order_and_limit(plan, clauses.order_by, clauses.limit));
+    }
+
+    if !clauses.having.is_empty() {
         return Err(DslError::Parse {
             line: line_no,
-            msg: "Expected: DATASET target FROM source ...".into(),
+            msg: "HAVING requires GROUP BY or an aggregate in SELECT".into(),
         });
     }
+    // DISTINCT dedupes the projected rows, and an ORDER BY on an `AS` name
+    // needs the projected column, so ORDER BY / LIMIT go on top of them
+    let orders_by_alias = clauses.order_by.as_ref().is_some_and(|(column, _)| {
+        select.iter().flatten().any(|e| {
+            matches!(e, Expr::Alias { name, .. } if name == column)
+                && source_schema.get_field(column).is_none()
+        })
+    });
+    let distinct = clauses.distinct && select.is_some();
+    if !distinct && !orders_by_alias {
+        plan = apply_order_and_limit(plan, clauses.order_by.clone(), clauses.limit);
+    }
 
-    let target_name = parts[0].trim().to_string();
-    let query_part = parts[1].trim();
-
-    let keywords = [
-        "FILTER", "SELECT", "ORDER BY", "LIMIT", "GROUP BY", "HAVING",
-    ];
-    let mut first_keyword_idx = None;
+    let Some(select) = select else {
+        return Ok(plan);
+    };
 
-    for &kw in &keywords {
-        if let Some(idx) = query_part.find(kw) {
-            // Ensure matches whole word
-            if idx > 0 && !query_part[idx - 1..].starts_with(' ') {
-                continue; // part of another word
+    // Projection with Wildcard Expansion support; function columns are
+    // computed by a Window node first and then projected by name, other
+    // expressions are evaluated by the projection itself
+    let mut exprs = Vec::new();
+    let mut window_exprs = Vec::new();
+    for e in &select {
+        match e.unaliased() {
+            Expr::Column(c) if c == "*" => {
+                // Expand wildcard
+                for field in &source_schema.fields {
+                    exprs.push(Expr::Column(field.name.clone()));
+                }
             }
-            if first_keyword_idx.map_or(true, |curr| idx < curr) {
-                first_keyword_idx = Some(idx);
+            func @ (Expr::ScalarFunction { .. } | Expr::WindowFunction { .. }) => {
+                let column = Expr::Column(func.output_name());
+                exprs.push(match e {
+                    Expr::Alias { name, .. } => Expr::Alias {
+                        expr: Box::new(column),
+                        name: name.clone(),
+                    },
+                    _ => column,
+                });
+                window_exprs.push(func.clone());
             }
+            _ => exprs.push(e.clone()),
         }
     }
 
-    let (source_name, mut clauses_str) = if let Some(idx) = first_keyword_idx {
-        (query_part[..idx].trim(), &query_part[idx..])
-    } else {
-        (query_part.trim(), "")
+    if !window_exprs.is_empty() {
+        plan = insert_window(plan, window_exprs);
+    }
+
+    let plan = LogicalPlan::Project {
+        input: Box::new(plan),
+        exprs,
     };
+    if orders_by_alias && !distinct {
+        return Ok(apply_order_and_limit(plan, clauses.order_by, clauses.limit));
+    }
+    if distinct {
+        let plan = LogicalPlan::Distinct {
+            input: Box::new(plan),
+        };
+        return Ok(apply_order_and_limit(plan, clauses.order_by, clauses.limit));
+    }
+    Ok(plan)
+}
 
-    // Get source dataset schema for validation
-    let source_ds = db.get_dataset(source_name).map_err(|e| DslError::Engine {
+/// Expand the `UNNEST(list)` SELECT item, if any, into an Unnest node
+/// under the rest of the query; the item then reads the element column
+fn plan_unnest(
+    plan: LogicalPlan,
+    select: Option<Vec<Expr>>,
+    line_no: usize,
+) -> Result<(LogicalPlan, Option<Vec<Expr>>), DslError> {
+    let err = |msg: &str| DslError::Parse {
         line: line_no,
-        source: e,
-    })?;
-    let source_schema = source_ds.schema.clone();
-
-    // Initial Plan: Scan
-    let mut current_plan = LogicalPlan::Scan {
-        dataset_name: source_name.to_string(),
-        schema: source_schema.clone(),
+        msg: msg.into(),
     };
-
-    // Process clauses
-    let mut pending_group_by: Option<Vec<Expr>> = None;
-    while !clauses_str.is_empty() {
-        let clauses_trimmed = clauses_str.trim();
-
-        if clauses_trimmed.starts_with("FILTER ") {
-            let (cond_str, remaining) = split_clause(clauses_trimmed, "FILTER", &keywords);
-            clauses_str = remaining;
-
-            // Parse condition: col > val
-            let (col, op, val) = parse_filter_condition(cond_str, line_no)?;
-
-            current_plan = LogicalPlan::Filter {
-                input: Box::new(current_plan),
-                predicate: Expr::BinaryExpr {
-                    left: Box::new(Expr::Column(col)),
-                    op,
-                    right: Box::new(Expr::Literal(val)),
-                },
-            };
-        } else if clauses_trimmed.starts_with("GROUP BY ") {
-            let (group_str, remaining) = split_clause(clauses_trimmed, "GROUP BY", &keywords);
-            clauses_str = remaining;
-
-            let cols: Vec<String> = group_str.split(',').map(|s| s.trim().to_string()).collect();
-            let exprs: Vec<Expr> = cols.into_iter().map(Expr::Column).collect();
-            pending_group_by = Some(exprs);
-        } else if clauses_trimmed.starts_with("SELECT ") {
-            let (cols_str, remaining) = split_clause(clauses_trimmed, "SELECT", &keywords);
-            clauses_str = remaining;
-
-            // New parse function for expressions
-            let exprs = parse_select_items(cols_str, line_no)?;
-
-            // Check if we need Aggregate or Project
-            let has_aggr = exprs
-                .iter()
-                .any(|e| matches!(e, Expr::AggregateExpr { .. }));
-
-            if pending_group_by.is_some() || has_aggr {
-                // Must be Aggregate
-                let group_expr = pending_group_by.take().unwrap_or_default();
-
-                // Filter aggr_expr to strictly include AggregateExprs
-                // Non-aggregates (Columns) are assumed to be Group Keys or ignored for now.
-                // This ensures Schema (Keys + Aggs) matches Execution (Keys + Accs).
-                let actual_aggs: Vec<Expr> = exprs
-                    .into_iter()
-                    .filter(|e| matches!(e, Expr::AggregateExpr { .. }))
-                    .collect();
-
-                // If it's a global aggregation (no group by), group_expr is empty.
-                // We construct Aggregate plan.
-                current_plan = LogicalPlan::Aggregate {
-                    input: Box::new(current_plan),
-                    group_expr,
-                    aggr_expr: actual_aggs,
-                };
-            } else {
-                // Simple Projection (backward compat)
-                // Convert Expr::Column back to String
-                let cols: Vec<String> = exprs
-                    .iter()
-                    .map(|e| {
-                        if let Expr::Column(c) = e {
-                            Ok(c.clone())
-                        } else {
-                            // Projecting literals or unsupported exprs in Project?
-                            // Current LogicalPlan::Project only supports Columns.
-                            // If we have literal, we can't map to Project yet.
-                            // But parse_select_items only parses Col or AggFunc(Col).
-                            // So it should be fine.
-                            Err(DslError::Parse {
-                                line: line_no,
-                                msg: "Only columns supported in simple SELECT (Project)".into(),
-                            })
-                        }
-                    })
-                    .collect::<Result<_, _>>()?;
-
-                current_plan = LogicalPlan::Project {
-                    input: Box::new(current_plan),
-                    columns: cols,
-                };
-            }
-        } else if clauses_trimmed.starts_with("HAVING ") {
-            // HAVING comes after aggregation
-            let (cond_str, remaining) = split_clause(clauses_trimmed, "HAVING", &keywords);
-            clauses_str = remaining;
-
-            // Parse condition like filter
-            // But strictly it should match an output of Aggregation.
-            // For simplicity, reuse parse_filter_condition and wrap in Filter
-            // Because HAVING is just a Filter on the output of Aggregate.
-            let (col, op, val) = parse_filter_condition(cond_str, line_no)?;
-
-            current_plan = LogicalPlan::Filter {
-                input: Box::new(current_plan),
-                predicate: Expr::BinaryExpr {
-                    left: Box::new(Expr::Column(col)),
-                    op,
-                    right: Box::new(Expr::Literal(val)),
-                },
-            };
-        } else if clauses_trimmed.starts_with("ORDER BY ") {
-            let (order_str, remaining) = split_clause(clauses_trimmed, "ORDER BY", &keywords);
-            clauses_str = remaining;
-
-            let parts: Vec<&str> = order_str.split_whitespace().collect();
-            if parts.is_empty() {
-                return Err(DslError::Parse {
-                    line: line_no,
-                    msg: "Empty ORDER BY clause".into(),
-                });
+    let Some(mut select) = select else {
+        return Ok((plan, None));
+    };
+    let is_unnest = |e: &Expr| {
+        matches!(
+            e.unaliased(),
+            Expr::ScalarFunction {
+                func: ScalarFunction::Unnest,
+                ..
             }
-            let col_name = parts[0].to_string();
-            let ascending = if parts.len() > 1 && parts[1] == "DESC" {
-                false
-            } else {
-                true
-            };
+        )
+    };
+    let mut unnested = select.iter().filter(|e| is_unnest(e));
+    let Some(item) = unnested.next() else {
+        if select.iter().any(contains_unnest) {
+            return Err(err("UNNEST is only allowed as a SELECT item"));
+        }
+        return Ok((plan, Some(select)));
+    };
+    if unnested.next().is_some() {
+        return Err(err("Only one UNNEST per SELECT is supported"));
+    }
+    let expr = item.unaliased().clone();
+    if select.iter().any(|e| !is_unnest(e) && contains_unnest(e))
+        || matches!(&expr, Expr::ScalarFunction { args, .. } if args.iter().any(contains_unnest))
+    {
+        return Err(err("UNNEST is only allowed as a SELECT item"));
+    }
 
-            current_plan = LogicalPlan::Sort {
-                input: Box::new(current_plan),
-                column: col_name,
-                ascending,
-            };
-        } else if clauses_trimmed.starts_with("LIMIT ") {
-            let (limit_str, remaining) = split_clause(clauses_trimmed, "LIMIT", &keywords);
-            clauses_str = remaining;
+    let column = Expr::Column(expr.output_name());
+    for e in select.iter_mut().filter(|e| is_unnest(e)) {
+        *e = match e {
+            Expr::Alias { name, .. } => Expr::Alias {
+                expr: Box::new(column.clone()),
+                name: name.clone(),
+            },
+            _ => column.clone(),
+        };
+    }
+    let plan = LogicalPlan::Unnest {
+        input: Box::new(plan),
+        expr,
+    };
+    Ok((plan, Some(select)))
+}
 
-            let n: usize = limit_str.trim().parse().map_err(|_| DslError::Parse {
-                line: line_no,
-                msg: format!("Invalid LIMIT: {}", limit_str),
-            })?;
+/// Whether `expr` calls UNNEST anywhere
+fn contains_unnest(expr: &Expr) -> bool {
+    match expr {
+        Expr::ScalarFunction {
+            func: ScalarFunction::Unnest,
+            ..
+        } => true,
+        Expr::ScalarFunction { args, .. } | Expr::WindowFunction { args, .. } => {
+            args.iter().any(contains_unnest)
+        }
+        Expr::BinaryExpr { left, right, .. } => contains_unnest(left) || contains_unnest(right),
+        Expr::AggregateExpr { expr, .. } | Expr::Alias { expr, .. } => contains_unnest(expr),
+        Expr::Column(_) | Expr::Literal(_) => false,
+    }
+}
 
-            current_plan = LogicalPlan::Limit {
-                input: Box::new(current_plan),
-                n,
+/// Rewrite a HAVING predicate over the output of the Aggregate node:
+/// aggregate calls and the `AS` names of SELECT items become the columns
+/// holding them. Aggregates missing from `aggr_expr` are appended to it.
+fn having_columns(predicate: Expr, select: &[Expr], aggr_expr: &mut Vec<Expr>) -> Expr {
+    match predicate {
+        Expr::AggregateExpr { func, expr } => {
+            let same = |e: &Expr| {
+                matches!(e, Expr::AggregateExpr { func: f, expr: x }
+                    if *f == func && x.output_name() == expr.output_name())
             };
-        } else {
-            return Err(DslError::Parse {
-                line: line_no,
-                msg: format!("Unexpected clause: {}", clauses_str),
+            if !aggr_expr.iter().any(same) {
+                aggr_expr.push(Expr::AggregateExpr {
+                    func: func.clone(),
+                    expr: expr.clone(),
+                });
+            }
+            Expr::Column(func.column_name(&expr))
+        }
+        Expr::Column(name) => {
+            let aliased = select.iter().find_map(|e| match e {
+                Expr::Alias { expr, name: alias } if *alias == name => Some(expr.as_ref().clone()),
+                _ => None,
             });
+            match aliased {
+                Some(expr @ (Expr::AggregateExpr { .. } | Expr::Column(_))) => {
+                    having_columns(expr, select, aggr_expr)
+                }
+                _ => Expr::Column(name),
+            }
         }
+        Expr::BinaryExpr { left, op, right } => Expr::BinaryExpr {
+            left: Box::new(having_columns(*left, select, aggr_expr)),
+            op,
+            right: Box::new(having_columns(*right, select, aggr_expr)),
+        },
+        Expr::ScalarFunction { func, args } => Expr::ScalarFunction {
+            func,
+            args: args
+                .into_iter()
+                .map(|arg| having_columns(arg, select, aggr_expr))
+                .collect(),
+        },
+        other => other,
     }
+}
 
-    Ok((target_name, current_plan))
+/// GROUP BY keys for the Aggregate node: a name that is not a source column
+/// but a SELECT alias (`SELECT id % 10 AS bucket ... GROUP BY bucket`)
+/// groups by the aliased expression
+fn group_keys(keys: Vec<Expr>, select: &[Expr], source_schema: &Schema) -> Vec<Expr> {
+    keys.into_iter()
+        .map(|key| match key {
+            Expr::Column(name) if source_schema.get_field(&name).is_none() => select
+                .iter()
+                .find_map(|e| match e {
+                    Expr::Alias { expr, name: alias } if *alias == name => {
+                        Some(expr.as_ref().clone())
+                    }
+                    _ => None,
+                })
+                .unwrap_or(Expr::Column(name)),
+            other => other,
+        })
+        .collect()
 }
 
-fn split_clause<'a>(s: &'a str, current_kw: &str, all_kws: &[&str]) -> (&'a str, &'a str) {
-    let content_start = current_kw.len();
-    let remaining_s = &s[content_start..];
-
-    // Find next keyword
-    let mut next_kw_idx = None;
-    for &kw in all_kws {
-        if let Some(idx) = remaining_s.find(kw) {
-            // ensure word boundary roughly (space before)
-            if idx > 0 && remaining_s.as_bytes()[idx - 1] == b' ' {
-                if next_kw_idx.map_or(true, |curr| idx < curr) {
-                    next_kw_idx = Some(idx);
-                }
+/// Project the output of an Aggregate node, renaming the group keys and
+/// aggregates that have an `AS` name in `select` and dropping the last
+/// `hidden` aggregates (the ones only HAVING uses)
+fn rename_aggregate_output(
+    plan: LogicalPlan,
+    select: &[Expr],
+    aggr_count: usize,
+    hidden: usize,
+) -> LogicalPlan {
+    let aggr_aliases: Vec<Option<&String>> = select
+        .iter()
+        .filter(|e| matches!(e.unaliased(), Expr::AggregateExpr { .. }))
+        .map(|e| match e {
+            Expr::Alias { name, .. } => Some(name),
+            _ => None,
+        })
+        .collect();
+    let key_alias = |key: &str| {
+        select.iter().find_map(|e| match e {
+            Expr::Alias { expr, name } if expr.output_name() == key => Some(name),
+            _ => None,
+        })
+    };
+
+    let schema = plan.schema();
+    let visible = schema.len() - hidden;
+    let key_count = visible - aggr_count;
+    let exprs = schema.fields[..visible]
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let column = Expr::Column(field.name.clone());
+            let alias = if i < key_count {
+                key_alias(&field.name)
+            } else {
+                aggr_aliases[i - key_count]
+            };
+            match alias {
+                Some(name) => Expr::Alias {
+                    expr: Box::new(column),
+                    name: name.clone(),
+                },
+                None => column,
             }
-        }
+        })
+        .collect();
+    LogicalPlan::Project {
+        input: Box::new(plan),
+        exprs,
     }
+}
 
-    if let Some(idx) = next_kw_idx {
-        (&remaining_s[..idx].trim(), &remaining_s[idx..])
-    } else {
-        (remaining_s.trim(), "")
+fn apply_order_and_limit(
+    mut plan: LogicalPlan,
+    order_by: Option<(String, bool)>,
+    limit: Option<usize>,
+) -> LogicalPlan {
+    if let Some((column, ascending)) = order_by {
+        plan = LogicalPlan::Sort {
+            input: Box::new(plan),
+            column,
+            ascending,
+        };
+    }
+    if let Some(n) = limit {
+        plan = LogicalPlan::Limit {
+            input: Box::new(plan),
+            n,
+        };
     }
+    plan
 }
 
-fn parse_filter_condition(s: &str, line_no: usize) -> Result<(String, String, Value), DslError> {
-    // col > val
-    // Split by operators: >=, <=, >, <, =, !=
-    // Order matters (longest first)
-    let ops = [">=", "<=", "!=", "=", ">", "<"];
-
-    for op in ops {
-        if let Some(idx) = s.find(op) {
-            let col = s[..idx].trim().to_string();
-            let val_str = s[idx + op.len()..].trim();
-            // Parse value (try float, int, string - naive inference or use context?)
-            // parse_single_value assumes generic.
-            let val = parse_single_value(val_str, line_no)?;
-            return Ok((col, op.to_string(), val));
-        }
+/// Window functions see the whole filtered input, so the Window node goes
+/// below any trailing ORDER BY / LIMIT.
+fn insert_window(plan: LogicalPlan, window_expr: Vec<Expr>) -> LogicalPlan {
+    match plan {
+        LogicalPlan::Sort {
+            input,
+            column,
+            ascending,
+        } => LogicalPlan::Sort {
+            input: Box::new(insert_window(*input, window_expr)),
+            column,
+            ascending,
+        },
+        LogicalPlan::Limit { input, n } => LogicalPlan::Limit {
+            input: Box::new(insert_window(*input, window_expr)),
+            n,
+        },
+        other => LogicalPlan::Window {
+            input: Box::new(other),
+            window_expr,
+        },
     }
-
-    Err(DslError::Parse {
-        line: line_no,
-        msg: format!("Invalid filter condition: {}", s),
-    })
 }
 
 // ... existing code ...
@@ -589,18 +1162,6 @@ fn parse_column_definitions(columns_str: &str, line_no: usize) -> Result<Vec<Fie
         });
     }
 
-    // Split into comma arguments
-    // Ensure we stripped outer parens if they exist
-    let columns_str = columns_str.trim();
-    let inner = if columns_str.starts_with('(') && columns_str.ends_with(')') {
-        &columns_str[1..columns_str.len() - 1]
-    } else {
-        columns_str
-    };
-
-    println!("DEBUG: columns_str='{}'", columns_str);
-    println!("DEBUG: inner='{}'", inner);
-
     let mut fields = Vec::new();
 
     // Split by comma, respecting parentheses for types like Matrix(R, C)
@@ -618,23 +1179,72 @@ fn parse_column_definitions(columns_str: &str, line_no: usize) -> Result<Vec<Fie
         }
 
         let col_name = parts[0].trim();
-        let type_str = parts[1].trim();
+        // Trailing constraints in any order: PRIMARY KEY, UNIQUE, NOT NULL
+        // (columns are NOT NULL unless their type ends with `?`)
+        let mut type_str = parts[1].trim();
+        let (mut primary_key, mut unique, mut not_null) = (false, false, false);
+        loop {
+            let upper = type_str.to_uppercase();
+            let Some(constraint) = ["PRIMARY KEY", "UNIQUE", "NOT NULL"]
+                .into_iter()
+                .find(|c| upper.ends_with(c))
+            else {
+                break;
+            };
+            primary_key |= constraint == "PRIMARY KEY";
+            unique |= constraint == "UNIQUE";
+            not_null |= constraint == "NOT NULL";
+            type_str = type_str[..type_str.len() - constraint.len()].trim_end();
+        }
+
+        let (type_str, nullable) = match type_str.strip_suffix('?') {
+            Some(type_str) => (type_str.trim_end(), true),
+            None => (type_str, false),
+        };
+        if nullable && (primary_key || not_null) {
+            let constraint = if primary_key {
+                "PRIMARY KEY"
+            } else {
+                "NOT NULL"
+            };
+            return Err(DslError::Parse {
+                line: line_no,
+                msg: format!(
+                    "Column '{}' is nullable and cannot be {}",
+                    col_name, constraint
+                ),
+            });
+        }
 
         let value_type = parse_value_type(type_str, line_no)?;
-        fields.push(Field::new(col_name, value_type));
+        let field = Field::new(col_name, value_type);
+        let field = if nullable { field.nullable() } else { field };
+        let field = if unique { field.unique() } else { field };
+        if !primary_key {
+            fields.push(field);
+        } else if fields.iter().any(|f: &Field| f.primary_key) {
+            return Err(DslError::Parse {
+                line: line_no,
+                msg: "Only one PRIMARY KEY column is supported".into(),
+            });
+        } else {
+            fields.push(field.primary_key());
+        }
     }
 
     Ok(fields)
 }
 
 /// Parse a value type from string
-fn split_args(s: &str) -> Vec<String> {
+pub(crate) fn split_args(s: &str) -> Vec<String> {
     let mut args = Vec::new();
     let mut current = String::new();
     let mut depth = 0;
+    let mut quotes = QuoteState::default();
 
     for ch in s.chars() {
         match ch {
+            _ if quotes.step(ch) => current.push(ch),
             '(' | '[' => {
                 depth += 1;
                 current.push(ch);
@@ -666,6 +1276,24 @@ fn parse_value_type(type_str: &str, line_no: usize) -> Result<ValueType, DslErro
         Ok(ValueType::String)
     } else if upper == "BOOL" {
         Ok(ValueType::Bool)
+    } else if upper == "TIMESTAMP" {
+        Ok(ValueType::Timestamp)
+    } else if upper == "JSON" {
+        Ok(ValueType::Json)
+    } else if upper.starts_with("LIST(") && upper.ends_with(')') {
+        // LIST(<scalar type>)
+        let elem = parse_value_type(type_str[5..type_str.len() - 1].trim(), line_no)?;
+        match elem {
+            ValueType::Int
+            | ValueType::Float
+            | ValueType::String
+            | ValueType::Bool
+            | ValueType::Timestamp => Ok(ValueType::List(Box::new(elem))),
+            other => Err(DslError::Parse {
+                line: line_no,
+                msg: format!("List elements must be a scalar type, got {}", other),
+            }),
+        }
     } else if upper.starts_with("VECTOR") {
         // Expected format: VECTOR(N)
         let start = upper.find('(');
@@ -731,10 +1359,30 @@ fn parse_value_type(type_str: &str, line_no: usize) -> Result<ValueType, DslErro
 pub fn parse_single_value(s: &str, line_no: usize) -> Result<Value, DslError> {
     let s = s.trim();
 
-    // String (quoted)
-    if s.starts_with('"') && s.ends_with('"') {
-        let content = &s[1..s.len() - 1];
-        return Ok(Value::String(content.to_string()));
+    // String (quoted, with escapes)
+    if let Some(literal) = parse_string_literal(s) {
+        return literal
+            .map(Value::String)
+            .map_err(|msg| DslError::Parse { line: line_no, msg });
+    }
+
+    // TIMESTAMP "2024-01-31T12:00:00Z"
+    if s.get(..9)
+        .is_some_and(|k| k.eq_ignore_ascii_case("TIMESTAMP"))
+    {
+        if let Some(Ok(text)) = parse_string_literal(s[9..].trim_start()) {
+            return parse_timestamp(&text)
+                .map(Value::Timestamp)
+                .ok_or_else(|| DslError::Parse {
+                    line: line_no,
+                    msg: format!("Invalid timestamp: \"{}\"", text),
+                });
+        }
+    }
+
+    // NULL; Tuple::new rejects it in a column that is not nullable
+    if s.eq_ignore_ascii_case("NULL") {
+        return Ok(Value::Null);
     }
 
     // Boolean
@@ -778,39 +1426,95 @@ pub fn parse_single_value(s: &str, line_no: usize) -> Result<Value, DslError> {
             return Ok(Value::Matrix(matrix));
         }
 
-        let mut floats = Vec::with_capacity(parts.len());
-        for p in parts {
-            if p.is_empty() {
-                continue;
-            }
-            let f = p.parse::<f32>().map_err(|_| DslError::Parse {
+        let parts: Vec<String> = parts.into_iter().filter(|p| !p.is_empty()).collect();
+        if let Ok(floats) = parts.iter().map(|p| p.parse::<f32>()).collect() {
+            return Ok(Value::Vector(floats));
+        }
+        // Anything but numbers makes a list, e.g. ["a", "b"]
+        let items = parts
+            .iter()
+            .map(|p| parse_single_value(p, line_no))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| DslError::Parse {
                 line: line_no,
-                msg: format!("Invalid vector element: {}", p),
+                msg: format!("Invalid vector or list: {}", s),
             })?;
-            floats.push(f);
+        if items
+            .iter()
+            .any(|v| matches!(v, Value::Vector(_) | Value::List(_)))
+        {
+            return Err(DslError::Parse {
+                line: line_no,
+                msg: format!("Lists cannot be nested: {}", s),
+            });
         }
-        return Ok(Value::Vector(floats));
+        return Ok(Value::List(items));
     }
 
     // Int
-    s.parse::<i64>()
-        .map(Value::Int)
-        .map_err(|_| DslError::Parse {
+    match parse_int(s) {
+        Some(parsed) => parsed
+            .map(Value::Int)
+            .map_err(|msg| DslError::Parse { line: line_no, msg }),
+        None => Err(DslError::Parse {
             line: line_no,
             msg: format!("Invalid value: {}", s),
+        }),
+    }
+}
+
+/// `[v1, v2, ...]` for a `LIST(elem)` column: each element is parsed and
+/// coerced to `elem`
+fn parse_list_value(s: &str, elem: &ValueType, line_no: usize) -> Result<Value, DslError> {
+    let s = s.trim();
+    let Some(content) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) else {
+        return parse_single_value(s, line_no);
+    };
+    split_args(content)
+        .iter()
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let value = parse_single_value(p, line_no)?;
+            value.coerce_to(elem).ok_or_else(|| DslError::Parse {
+                line: line_no,
+                msg: format!("Invalid {} list element: {}", elem, p),
+            })
         })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Value::List)
 }
 
-/// INSERT INTO dataset_name VALUES (val1, val2, ...)
+/// INSERT INTO dataset_name VALUES (val1, val2, ...)[, (val1, val2, ...) ...]
 pub fn handle_insert(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
-    let rest = line.trim_start_matches("INSERT INTO").trim();
+    write_rows(db, line, line_no, false)
+}
+
+/// UPSERT INTO dataset_name VALUES (val1, val2, ...)[, (...) ...]
+/// Replaces the row with the same primary key, or inserts a new one.
+pub fn handle_upsert(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
+    write_rows(db, line, line_no, true)
+}
+
+fn write_rows(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+    upsert: bool,
+) -> Result<DslOutput, DslError> {
+    let op = if upsert { "UPSERT" } else { "INSERT" };
+    let (line, returning) = split_returning(line, line_no)?;
+    let rest = line.trim_start_matches(op).trim_start();
+    let rest = rest.trim_start_matches("INTO").trim();
 
     // Split into dataset_name and values part
     let parts: Vec<&str> = rest.splitn(2, "VALUES").collect();
     if parts.len() != 2 {
         return Err(DslError::Parse {
             line: line_no,
-            msg: "Expected: INSERT INTO dataset_name VALUES (val1, val2, ...)".into(),
+            msg: format!(
+                "Expected: {} INTO dataset_name VALUES (val1, val2, ...)",
+                op
+            ),
         });
     }
 
@@ -823,21 +1527,192 @@ pub fn handle_insert(db: &mut TensorDb, line: &str, line_no: usize) -> Result<Ds
         source: e,
     })?;
     let schema = dataset.schema.clone();
+    let next_row = dataset.rows.len();
+
+    // Parse every row first so a bad one leaves the dataset untouched
+    let mut tuples = Vec::new();
+    for row_str in split_value_rows(values_str, line_no)? {
+        let values = parse_tuple_values(row_str, &schema, line_no)?;
+        let tuple = Tuple::new(schema.clone(), values).map_err(|e| DslError::Parse {
+            line: line_no,
+            msg: e,
+        })?;
+        tuples.push(tuple);
+    }
+
+    // Columns are checked up front so an unknown name leaves the dataset untouched
+    if let Some(columns) = &returning {
+        returning_table(dataset_name, &schema, &[], columns, line_no)?;
+    }
 
-    // Parse values
-    let values = parse_tuple_values(values_str, &schema, line_no)?;
-    let tuple = Tuple::new(schema.clone(), values).map_err(|e| DslError::Parse {
+    let count = tuples.len();
+    let written: Vec<usize> = if upsert {
+        tuples
+            .into_iter()
+            .map(|tuple| db.upsert_row(dataset_name, tuple))
+            .collect()
+    } else {
+        db.insert_rows(dataset_name, tuples)
+            .map(|_| (next_row..next_row + count).collect())
+    }
+    .map_err(|e| DslError::Engine {
         line: line_no,
-        msg: e,
+        source: e,
     })?;
+    auto_persist_dataset(db, dataset_name, line_no)?;
+
+    match returning {
+        Some(columns) => {
+            let dataset = db.get_dataset(dataset_name).map_err(|e| DslError::Engine {
+                line: line_no,
+                source: e,
+            })?;
+            let rows: Vec<Tuple> = written.iter().map(|&i| dataset.rows[i].clone()).collect();
+            let table = returning_table(dataset_name, &schema, &rows, &columns, line_no)?;
+            Ok(DslOutput::Table(table))
+        }
+        None => Ok(DslOutput::Affected {
+            rows: count,
+            op: op.to_string(),
+        }),
+    }
+}
+
+/// Split `(..), (..), ...` into one string per row. A bare value list
+/// without parentheses is a single row.
+fn split_value_rows(values_str: &str, line_no: usize) -> Result<Vec<&str>, DslError> {
+    let mut rest = values_str.trim();
+    if !rest.starts_with('(') {
+        return Ok(vec![rest]);
+    }
+
+    let mut rows = Vec::new();
+    loop {
+        let end = find_matching_paren(rest).ok_or_else(|| DslError::Parse {
+            line: line_no,
+            msg: "Unbalanced parentheses in VALUES".into(),
+        })?;
+        rows.push(&rest[..=end]);
+        rest = rest[end + 1..].trim_start();
+        if rest.is_empty() {
+            return Ok(rows);
+        }
+        rest = match rest.strip_prefix(',') {
+            Some(next) if next.trim_start().starts_with('(') => next.trim_start(),
+            _ => {
+                return Err(DslError::Parse {
+                    line: line_no,
+                    msg: "Expected ', (' between rows in VALUES".into(),
+                })
+            }
+        };
+    }
+}
+
+/// DELETE FROM <name> [WHERE <condition>] [RETURNING col, ...]
+pub fn handle_delete(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
+    match parser::parse_statement(line, line_no)? {
+        Statement::Delete(delete) => execute_delete(db, &delete, line_no),
+        _ => Err(DslError::Parse {
+            line: line_no,
+            msg: "Expected: DELETE FROM <name> [WHERE <condition>]".into(),
+        }),
+    }
+}
+
+pub fn execute_delete(
+    db: &mut TensorDb,
+    delete: &DeleteStatement,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let dataset_name = delete.dataset.as_str();
+    let schema = db
+        .get_dataset(dataset_name)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?
+        .schema
+        .clone();
+    if let Some(columns) = &delete.returning {
+        returning_table(dataset_name, &schema, &[], columns, line_no)?;
+    }
 
-    db.insert_row(dataset_name, tuple)
+    let removed = db
+        .delete_rows(dataset_name, delete.predicate.as_ref())
         .map_err(|e| DslError::Engine {
             line: line_no,
             source: e,
         })?;
+    auto_persist_dataset(db, dataset_name, line_no)?;
 
-    Ok(DslOutput::None)
+    match &delete.returning {
+        Some(columns) => Ok(DslOutput::Table(returning_table(
+            dataset_name,
+            &schema,
+            &removed,
+            columns,
+            line_no,
+        )?)),
+        None => Ok(DslOutput::Affected {
+            rows: removed.len(),
+            op: "DELETE".to_string(),
+        }),
+    }
+}
+
+/// UPDATE <name> SET <col> = <expr>[, ...] [WHERE <condition>] [RETURNING col, ...]
+pub fn handle_update(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
+    match parser::parse_statement(line, line_no)? {
+        Statement::Update(update) => execute_update(db, &update, line_no),
+        _ => Err(DslError::Parse {
+            line: line_no,
+            msg: "Expected: UPDATE <name> SET <col> = <expr>, ... [WHERE <condition>]".into(),
+        }),
+    }
+}
+
+pub fn execute_update(
+    db: &mut TensorDb,
+    update: &UpdateStatement,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let dataset_name = update.dataset.as_str();
+    let schema = db
+        .get_dataset(dataset_name)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?
+        .schema
+        .clone();
+    if let Some(columns) = &update.returning {
+        returning_table(dataset_name, &schema, &[], columns, line_no)?;
+    }
+
+    let updated = db
+        .update_rows(dataset_name, &update.assignments, update.predicate.as_ref())
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+    auto_persist_dataset(db, dataset_name, line_no)?;
+
+    match &update.returning {
+        Some(columns) => {
+            let dataset = db.get_dataset(dataset_name).map_err(|e| DslError::Engine {
+                line: line_no,
+                source: e,
+            })?;
+            let rows: Vec<Tuple> = updated.iter().map(|&i| dataset.rows[i].clone()).collect();
+            let table = returning_table(dataset_name, &schema, &rows, columns, line_no)?;
+            Ok(DslOutput::Table(table))
+        }
+        None => Ok(DslOutput::Affected {
+            rows: updated.len(),
+            op: "UPDATE".to_string(),
+        }),
+    }
 }
 
 /// Parse tuple values from: (val1, val2, ...)
@@ -862,26 +1737,33 @@ fn parse_tuple_values(
 
     let mut values = Vec::new();
     let mut current = String::new();
-    let mut in_string = false;
+    let mut quotes = QuoteState::default();
     let mut depth = 0;
 
+    // List columns read their literal element by element, so `[1, 2]`
+    // keeps exact Ints instead of becoming a Float vector
+    let parse_value = |text: &str, index: usize| match schema.fields.get(index) {
+        Some(field) => match &field.value_type {
+            ValueType::List(elem) => parse_list_value(text, elem, line_no),
+            _ => parse_single_value(text, line_no),
+        },
+        None => parse_single_value(text, line_no),
+    };
+
     // Parse values, handling strings and nested structures
     for ch in inner.chars() {
         match ch {
-            '"' => {
-                in_string = !in_string;
-                current.push(ch);
-            }
-            '[' | '(' if !in_string => {
+            _ if quotes.step(ch) => current.push(ch),
+            '[' | '(' => {
                 depth += 1;
                 current.push(ch);
             }
-            ']' | ')' if !in_string => {
+            ']' | ')' => {
                 depth -= 1;
                 current.push(ch);
             }
-            ',' if !in_string && depth == 0 => {
-                values.push(parse_single_value(&current.trim(), line_no)?);
+            ',' if depth == 0 => {
+                values.push(parse_value(current.trim(), values.len())?);
                 current.clear();
             }
             _ => {
@@ -892,7 +1774,7 @@ fn parse_tuple_values(
 
     // Don't forget the last value
     if !current.trim().is_empty() {
-        values.push(parse_single_value(&current.trim(), line_no)?);
+        values.push(parse_value(current.trim(), values.len())?);
     }
 
     // Validate count matches schema
@@ -903,6 +1785,26 @@ fn parse_tuple_values(
         });
     }
 
+    // Implicit coercions, e.g. Int literals in Float columns and ISO-8601
+    // strings in Timestamp columns
+    for (value, field) in values.iter_mut().zip(&schema.fields) {
+        if value.matches_type(&field.value_type) {
+            continue;
+        }
+        match value.coerce_to(&field.value_type) {
+            Some(coerced) => *value = coerced,
+            None if field.value_type == ValueType::Timestamp
+                && matches!(value, Value::String(_)) =>
+            {
+                return Err(DslError::Parse {
+                    line: line_no,
+                    msg: format!("Invalid timestamp for column '{}': {}", field.name, value),
+                })
+            }
+            None => {}
+        }
+    }
+
     Ok(values)
 }
 
@@ -933,10 +1835,11 @@ fn handle_add_column(db: &mut TensorDb, line: &str, line_no: usize) -> Result<Ds
         })?;
 
         // Check for LAZY keyword
-        let is_lazy = column_spec.to_uppercase().contains("LAZY");
+        // ASCII-only uppercasing keeps byte offsets valid for slicing column_spec
+        let is_lazy = column_spec.to_ascii_uppercase().contains("LAZY");
         let expression_part = if is_lazy {
             // Remove LAZY keyword from expression part
-            let upper = column_spec.to_uppercase();
+            let upper = column_spec.to_ascii_uppercase();
             let lazy_pos = upper.find("LAZY").unwrap();
             column_spec[eq_idx + 1..lazy_pos].trim()
         } else {
@@ -953,7 +1856,7 @@ fn handle_add_column(db: &mut TensorDb, line: &str, line_no: usize) -> Result<Ds
         }
 
         // Parse the expression
-        let expr = parse_expression(expression_part, line_no)?;
+        let expr = parser::parse_expression(expression_part, line_no)?;
 
         // Get dataset
         let dataset = db.get_dataset(dataset_name).map_err(|e| DslError::Engine {
@@ -987,6 +1890,7 @@ fn handle_add_column(db: &mut TensorDb, line: &str, line_no: usize) -> Result<Ds
                 line: line_no,
                 source: e,
             })?;
+            auto_persist_dataset(db, dataset_name, line_no)?;
 
             Ok(DslOutput::Message(format!(
                 "Added lazy computed column '{}' to dataset '{}'",
@@ -1024,6 +1928,7 @@ fn handle_add_column(db: &mut TensorDb, line: &str, line_no: usize) -> Result<Ds
                 line: line_no,
                 source: e,
             })?;
+            auto_persist_dataset(db, dataset_name, line_no)?;
 
             Ok(DslOutput::Message(format!(
                 "Added computed column '{}' to dataset '{}'",
@@ -1077,6 +1982,9 @@ fn handle_add_column(db: &mut TensorDb, line: &str, line_no: usize) -> Result<Ds
                     ValueType::Bool => Value::Bool(false),
                     ValueType::Vector(dim) => Value::Vector(vec![0.0; dim]),
                     ValueType::Matrix(r, c) => Value::Matrix(vec![vec![0.0; c]; r]),
+                    ValueType::Timestamp => Value::Timestamp(chrono::DateTime::UNIX_EPOCH),
+                    ValueType::Json => Value::Json(serde_json::Value::Null),
+                    ValueType::List(_) => Value::List(Vec::new()),
                     ValueType::Null => Value::Null,
                 }
             }
@@ -1094,6 +2002,7 @@ fn handle_add_column(db: &mut TensorDb, line: &str, line_no: usize) -> Result<Ds
             line: line_no,
             source: e,
         })?;
+        auto_persist_dataset(db, dataset_name, line_no)?;
 
         Ok(DslOutput::Message(format!(
             "Added column '{}' to dataset '{}'",
@@ -1102,163 +2011,112 @@ fn handle_add_column(db: &mut TensorDb, line: &str, line_no: usize) -> Result<Ds
     }
 }
 
-fn parse_select_items(s: &str, line_no: usize) -> Result<Vec<Expr>, DslError> {
-    let s = s.trim();
-    if s.is_empty() {
-        return Err(DslError::Parse {
-            line: line_no,
-            msg: "Empty SELECT clause".into(),
-        });
-    }
-
-    let parts = split_args(s);
-    let mut exprs = Vec::new();
-
-    use crate::query::logical::{AggregateFunction, Expr};
-
-    for part in parts {
-        let part = part.trim();
-        if part == "*" {
-            exprs.push(Expr::Column("*".to_string()));
-            continue;
-        }
-        // Check for function call: FUNC(col)
-        // Check if it looks like Func Call (starts with Name + '(' and ends with ')')
-        // Be careful not to match (a+b) as function call.
-        if let Some(idx) = part.find('(') {
-            let possible_func = part[..idx].trim().to_uppercase();
-            // Validate if it is a known function
-            let func = match possible_func.as_str() {
-                "SUM" => Some(AggregateFunction::Sum),
-                "AVG" => Some(AggregateFunction::Avg),
-                "COUNT" => Some(AggregateFunction::Count),
-                "MIN" => Some(AggregateFunction::Min),
-                "MAX" => Some(AggregateFunction::Max),
-                _ => None,
-            };
-
-            if let Some(f) = func {
-                if part.ends_with(')') {
-                    let content = &part[idx + 1..part.len() - 1].trim();
-                    // Inner expr
-                    let inner = if *content == "*" {
-                        Expr::Literal(Value::Int(1))
-                    } else {
-                        parse_expression(content, line_no)?
-                    };
-
-                    exprs.push(Expr::AggregateExpr {
-                        func: f,
-                        expr: Box::new(inner),
-                    });
-                    continue;
-                }
-            }
-        }
-
-        // If not aggregation function, parse as expression
-        exprs.push(parse_expression(part, line_no)?);
-    }
-    Ok(exprs)
-}
-
-fn parse_expression(s: &str, line_no: usize) -> Result<Expr, DslError> {
-    parse_expr_add_sub(s, line_no)
-}
-
-fn parse_expr_add_sub(s: &str, line_no: usize) -> Result<Expr, DslError> {
-    let chars: Vec<char> = s.chars().collect();
-    let mut i = chars.len();
-    let mut depth = 0;
-    let mut last_op_idx = None;
-    let mut last_op = ' ';
-
-    while i > 0 {
-        i -= 1;
-        let c = chars[i];
-        if c == ')' {
-            depth += 1;
-        } else if c == '(' {
-            depth -= 1;
-        } else if depth == 0 && (c == '+' || c == '-') {
-            last_op_idx = Some(i);
-            last_op = c;
-            break;
-        }
-    }
-
-    if let Some(idx) = last_op_idx {
-        let left_str = s[..idx].trim();
-        let right_str = s[idx + 1..].trim();
+/// Handle schema changes of existing columns:
+///   DATASET <name> DROP COLUMN <col>
+///   DATASET <name> RENAME COLUMN <old> TO <new>
+///   DATASET <name> ALTER COLUMN <col> TYPE <type>   (values are cast)
+fn handle_alter_column(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let rest = line.trim_start_matches("DATASET").trim();
+    let (dataset_name, action) = rest.split_once(' ').ok_or_else(|| DslError::Parse {
+        line: line_no,
+        msg: "Expected: DATASET <name> DROP|RENAME|ALTER COLUMN ...".into(),
+    })?;
+    let action = action.trim();
+    let engine_err = |e| DslError::Engine {
+        line: line_no,
+        source: e,
+    };
 
-        // Check if left_str is empty? (Unary ops not supported yet like -5)
-        // If left is empty, it's unary?
-        if left_str.is_empty() {
+    let message = if let Some(column) = action.strip_prefix("DROP COLUMN ") {
+        let column = column.trim();
+        db.alter_dataset_drop_column(dataset_name, column)
+            .map_err(engine_err)?;
+        format!(
+            "Dropped column '{}' from dataset '{}'",
+            column, dataset_name
+        )
+    } else if let Some(spec) = action.strip_prefix("RENAME COLUMN ") {
+        let (old_name, new_name) = spec.split_once(" TO ").ok_or_else(|| DslError::Parse {
+            line: line_no,
+            msg: "Expected: DATASET <name> RENAME COLUMN <old> TO <new>".into(),
+        })?;
+        let (old_name, new_name) = (old_name.trim(), new_name.trim());
+        if new_name.is_empty() {
             return Err(DslError::Parse {
                 line: line_no,
-                msg: "Unary operators not supported yet".into(),
+                msg: "Column name cannot be empty".into(),
             });
         }
-
-        let left = parse_expr_add_sub(left_str, line_no)?;
-        let right = parse_term_mul_div(right_str, line_no)?;
-
-        return Ok(Expr::BinaryExpr {
-            left: Box::new(left),
-            op: last_op.to_string(),
-            right: Box::new(right),
-        });
-    }
-
-    parse_term_mul_div(s, line_no)
-}
-
-fn parse_term_mul_div(s: &str, line_no: usize) -> Result<Expr, DslError> {
-    let chars: Vec<char> = s.chars().collect();
-    let mut i = chars.len();
-    let mut depth = 0;
-    let mut last_op_idx = None;
-    let mut last_op = ' ';
-
-    while i > 0 {
-        i -= 1;
-        let c = chars[i];
-        if c == ')' {
-            depth += 1;
-        } else if c == '(' {
-            depth -= 1;
-        } else if depth == 0 && (c == '*' || c == '/') {
-            last_op_idx = Some(i);
-            last_op = c;
-            break;
-        }
-    }
-
-    if let Some(idx) = last_op_idx {
-        let left_str = s[..idx].trim();
-        let right_str = s[idx + 1..].trim();
-
-        let left = parse_term_mul_div(left_str, line_no)?;
-        let right = parse_factor(right_str, line_no)?;
-
-        return Ok(Expr::BinaryExpr {
-            left: Box::new(left),
-            op: last_op.to_string(),
-            right: Box::new(right),
+        db.alter_dataset_rename_column(dataset_name, old_name, new_name.to_string())
+            .map_err(engine_err)?;
+        format!(
+            "Renamed column '{}' to '{}' in dataset '{}'",
+            old_name, new_name, dataset_name
+        )
+    } else if let Some(spec) = action.strip_prefix("ALTER COLUMN ") {
+        let (column, type_str) = spec.split_once(" TYPE ").ok_or_else(|| DslError::Parse {
+            line: line_no,
+            msg: "Expected: DATASET <name> ALTER COLUMN <col> TYPE <type>".into(),
+        })?;
+        let column = column.trim();
+        let value_type = parse_value_type(type_str.trim(), line_no)?;
+        db.alter_dataset_column_type(dataset_name, column, value_type.clone())
+            .map_err(engine_err)?;
+        format!(
+            "Changed type of column '{}' in dataset '{}' to {}",
+            column, dataset_name, value_type
+        )
+    } else {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: "Expected: DATASET <name> DROP|RENAME|ALTER COLUMN ...".into(),
         });
-    }
+    };
+    auto_persist_dataset(db, dataset_name, line_no)?;
 
-    parse_factor(s, line_no)
+    Ok(DslOutput::Message(message))
 }
 
 /// Handle MATERIALIZE command
-/// MATERIALIZE <dataset>.<column> or MATERIALIZE <dataset>
+/// MATERIALIZE <dataset>.<column> or MATERIALIZE <dataset>, either followed
+/// by ASYNC to evaluate the columns in a background job
 pub fn handle_materialize(
     db: &mut TensorDb,
     line: &str,
     line_no: usize,
 ) -> Result<DslOutput, DslError> {
-    let rest = line.trim_start_matches("MATERIALIZE").trim();
+    let mut rest = line.trim_start_matches("MATERIALIZE").trim();
+    if parser::is_async(rest) {
+        rest = rest[..rest.len() - "ASYNC".len()].trim_end();
+        let dataset_name = rest.split('.').next().unwrap_or(rest).trim();
+        let lazy = db
+            .get_dataset(dataset_name)
+            .map_err(|e| DslError::Engine {
+                line: line_no,
+                source: e,
+            })?
+            .schema
+            .fields
+            .iter()
+            .any(|f| f.is_lazy);
+        // WAL replay, and datasets without lazy columns, need no job
+        if lazy && !db.replaying_wal {
+            let id = db
+                .start_job(dataset_name, line, JobTask::Materialize)
+                .map_err(|e| DslError::Engine {
+                    line: line_no,
+                    source: e,
+                })?;
+            return Ok(DslOutput::Message(format!(
+                "Started job {}: materializing lazy columns in dataset '{}'",
+                id, dataset_name
+            )));
+        }
+    }
 
     // Check if it's dataset.column or just dataset
     if rest.contains('.') {
@@ -1294,20 +2152,6 @@ pub fn handle_materialize(
     }
 }
 
-fn parse_factor(s: &str, line_no: usize) -> Result<Expr, DslError> {
-    let s = s.trim();
-    if s.starts_with('(') && s.ends_with(')') {
-        return parse_expression(&s[1..s.len() - 1], line_no);
-    }
-
-    if let Ok(val) = parse_single_value(s, line_no) {
-        Ok(Expr::Literal(val))
-    } else {
-        // Assume column.
-        Ok(Expr::Column(s.to_string()))
-    }
-}
-
 pub fn handle_add_tensor_column(
     db: &mut TensorDb,
     line: &str,
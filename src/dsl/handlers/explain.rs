@@ -1,7 +1,15 @@
 use super::dataset::build_dataset_query_plan;
+use crate::dsl::parser::{classify, Command};
 use crate::dsl::{DslError, DslOutput};
 use crate::engine::TensorDb;
+use crate::query::lineage::plan_lineage;
+use crate::query::logical::LogicalPlan;
+use crate::query::masking::mask_plan;
+use crate::query::optimizer::optimize;
 use crate::query::planner::Planner;
+use crate::query::profile::QueryProfile;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 pub fn handle_explain(
     db: &mut TensorDb,
@@ -9,16 +17,21 @@ pub fn handle_explain(
     line_no: usize,
 ) -> Result<DslOutput, DslError> {
     let rest = line.trim_start_matches("EXPLAIN").trim();
-    let query_line = if rest.to_uppercase().starts_with("PLAN ") {
+    let analyze = rest.to_ascii_uppercase().starts_with("ANALYZE ");
+    let lineage = rest.to_ascii_uppercase().starts_with("LINEAGE ");
+    let query_line = if rest.to_ascii_uppercase().starts_with("PLAN ") {
         rest[5..].trim()
+    } else if analyze || lineage {
+        rest[8..].trim()
     } else {
         rest
     };
 
-    let logical_plan = if query_line.starts_with("DATASET ") {
+    let command = classify(query_line, line_no)?.map(|(command, _)| command);
+    let logical_plan = if command == Some(Command::DatasetQuery) {
         let (_, plan) = build_dataset_query_plan(db, query_line, line_no)?;
         plan
-    } else if query_line.starts_with("SEARCH ") {
+    } else if command == Some(Command::Search) {
         // Need to parse SEARCH args carefully again or duplicate parsing logic?
         // Reuse handle_search parsing logic?
         // handle_search does: parse parts -> build_search_plan
@@ -34,7 +47,7 @@ pub fn handle_explain(
         // } else if query_line.starts_with("SEARCH ") {
         let (_, plan) = super::search::build_search_query_plan(db, query_line, line_no)?;
         plan
-    } else if query_line.starts_with("SELECT ") {
+    } else if command == Some(Command::Select) {
         super::dataset::build_select_query_plan(db, query_line, line_no)?
     } else {
         return Err(DslError::Parse {
@@ -43,6 +56,13 @@ pub fn handle_explain(
         });
     };
 
+    let logical_plan = optimize(mask_plan(logical_plan, db));
+    if analyze {
+        return explain_analyze(db, &logical_plan, line_no);
+    }
+    if lineage {
+        return Ok(explain_lineage(db, &logical_plan));
+    }
     let planner = Planner::new(db);
     let physical_plan =
         planner
@@ -66,3 +86,55 @@ pub fn handle_explain(
 
     Ok(DslOutput::Message(output))
 }
+
+/// List the source columns and transformations behind each output column
+fn explain_lineage(db: &TensorDb, logical_plan: &LogicalPlan) -> DslOutput {
+    let mut output = format!(
+        "--- LINEAGE ---\n{:<20} {:<30} {}",
+        "Column", "Sources", "Transforms"
+    );
+    for column in plan_lineage(logical_plan, db) {
+        let sources: Vec<String> = column.sources.iter().map(|s| s.to_string()).collect();
+        let sources = if sources.is_empty() {
+            "(constant)".to_string()
+        } else {
+            sources.join(", ")
+        };
+        output.push_str(&format!(
+            "\n{:<20} {:<30} {}",
+            column.column,
+            sources,
+            column.transforms.join(" -> ")
+        ));
+    }
+    DslOutput::Message(output)
+}
+
+/// Run `logical_plan` and report each operator's wall time, rows and memory.
+/// DATASET queries are executed without creating their target dataset.
+fn explain_analyze(
+    db: &TensorDb,
+    logical_plan: &LogicalPlan,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let engine_err = |e| DslError::Engine {
+        line: line_no,
+        source: e,
+    };
+    let profile = Arc::new(Mutex::new(QueryProfile::default()));
+    let physical_plan = Planner::with_profile(db, profile.clone())
+        .create_physical_plan(logical_plan)
+        .map_err(engine_err)?;
+
+    let start = Instant::now();
+    let rows = physical_plan.execute(db).map_err(engine_err)?;
+    let elapsed = start.elapsed();
+
+    let profile = profile.lock().unwrap();
+    Ok(DslOutput::Message(format!(
+        "--- EXPLAIN ANALYZE ---\n{}\nTotal: {} rows in {:.3}ms",
+        profile.render(),
+        rows.len(),
+        elapsed.as_secs_f64() * 1000.0
+    )))
+}
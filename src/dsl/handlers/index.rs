@@ -1,21 +1,27 @@
 use crate::dsl::error::DslError;
+use crate::dsl::parser::is_async;
 use crate::dsl::DslOutput;
-use crate::engine::TensorDb;
+use crate::engine::{JobTask, TensorDb};
 
 /// Handle CREATE INDEX commands
 /// Syntax:
 /// CREATE INDEX idx_name ON dataset(column)
 /// CREATE VECTOR INDEX idx_name ON dataset(column)
+/// With a trailing ASYNC the index is built by a background job
 pub fn handle_create_index(
     db: &mut TensorDb,
     input: &str,
     line_no: usize,
 ) -> Result<DslOutput, DslError> {
     // Expected formats:
-    // CREATE INDEX idx_name ON dataset(column)
-    // CREATE VECTOR INDEX idx_name ON dataset(column)
+    // CREATE INDEX idx_name ON dataset(column) [ASYNC]
+    // CREATE VECTOR INDEX idx_name ON dataset(column) [ASYNC]
 
-    let parts: Vec<&str> = input.split_whitespace().collect();
+    let mut parts: Vec<&str> = input.split_whitespace().collect();
+    let is_async = is_async(input);
+    if is_async {
+        parts.pop();
+    }
 
     // Check if VECTOR is present
     let is_vector = parts.get(1).map(|s| *s == "VECTOR").unwrap_or(false);
@@ -27,7 +33,8 @@ pub fn handle_create_index(
     if parts.len() <= target_pos || parts[on_keyword_pos] != "ON" {
         return Err(DslError::Parse {
             line: line_no,
-            msg: "Invalid syntax. Expected: CREATE [VECTOR] INDEX name ON dataset(column)".into(),
+            msg: "Invalid syntax. Expected: CREATE [VECTOR] INDEX name ON dataset(column) [ASYNC]"
+                .into(),
         });
     }
 
@@ -58,6 +65,28 @@ pub fn handle_create_index(
         });
     };
 
+    // WAL replay builds the index in place
+    if is_async && !db.replaying_wal {
+        let task = JobTask::Index {
+            column: column_name.to_string(),
+            vector: is_vector,
+        };
+        let id = db
+            .start_job(dataset_name, input, task)
+            .map_err(|e| DslError::Engine {
+                line: line_no,
+                source: e,
+            })?;
+        return Ok(DslOutput::Message(format!(
+            "Started job {}: building {} index '{}' on {}({})",
+            id,
+            if is_vector { "VECTOR" } else { "HASH" },
+            idx_name,
+            dataset_name,
+            column_name
+        )));
+    }
+
     if is_vector {
         db.create_vector_index(dataset_name, column_name)
             .map_err(|e| DslError::Engine {
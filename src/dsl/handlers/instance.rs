@@ -92,3 +92,79 @@ pub fn handle_show_databases(db: &TensorDb) -> Result<DslOutput, DslError> {
 
     Ok(DslOutput::Message(msg))
 }
+
+/// Split `<name> <KEYWORD> "label"` into (name, label)
+fn parse_snapshot_target<'a>(
+    rest: &'a str,
+    keyword: &str,
+    usage: &str,
+    line_no: usize,
+) -> Result<(&'a str, &'a str), DslError> {
+    let err = || DslError::Parse {
+        line: line_no,
+        msg: format!("Expected: {}", usage),
+    };
+    let (name, label) = rest.split_once(keyword).ok_or_else(err)?;
+    let (name, label) = (name.trim(), label.trim().trim_matches('"'));
+    if name.is_empty() || label.is_empty() {
+        return Err(err());
+    }
+    Ok((name, label))
+}
+
+/// Handle SNAPSHOT DATABASE command
+/// Syntax: SNAPSHOT DATABASE db_name AS "label"
+pub fn handle_snapshot_database(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let rest = line.strip_prefix("SNAPSHOT DATABASE ").unwrap();
+    let (name, label) = parse_snapshot_target(
+        rest,
+        " AS ",
+        "SNAPSHOT DATABASE db_name AS \"label\"",
+        line_no,
+    )?;
+
+    let path = db
+        .snapshot_database(name, label)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+
+    Ok(DslOutput::Message(format!(
+        "Snapshot '{}' of database '{}' written to '{}'",
+        label,
+        name,
+        path.display()
+    )))
+}
+
+/// Handle RESTORE DATABASE command
+/// Syntax: RESTORE DATABASE db_name FROM "label"
+pub fn handle_restore_database(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let rest = line.strip_prefix("RESTORE DATABASE ").unwrap();
+    let (name, label) = parse_snapshot_target(
+        rest,
+        " FROM ",
+        "RESTORE DATABASE db_name FROM \"label\"",
+        line_no,
+    )?;
+
+    db.restore_database(name, label)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+
+    Ok(DslOutput::Message(format!(
+        "Database '{}' restored from snapshot '{}'",
+        name, label
+    )))
+}
@@ -1,9 +1,19 @@
+use crate::core::dataset_legacy::{Dataset, DatasetId};
+use crate::core::tuple::{Field, Schema, Tuple};
+use crate::core::value::{Value, ValueType};
 use crate::dsl::{DslError, DslOutput};
 use crate::engine::TensorDb;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
 
 /// SHOW x
 /// SHOW ALL
 /// SHOW ALL DATASETS
+/// SHOW EVICTIONS
+/// SHOW QUERIES
+/// SHOW JOBS
+/// SHOW MACROS
 pub fn handle_show(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
     let rest = line.trim_start_matches("SHOW").trim();
 
@@ -41,6 +51,12 @@ pub fn handle_show(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslO
                 }
             }
         }
+        for evicted in db.evicted_datasets() {
+            output.push_str(&format!(
+                "Dataset: {} (rows: {}, evicted to disk)\n",
+                evicted.name, evicted.rows
+            ));
+        }
         output.push_str("--------------------");
         Ok(DslOutput::Message(output))
     } else if rest == "DATABASES" || rest == "ALL DATABASES" {
@@ -96,6 +112,115 @@ pub fn handle_show(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslO
             }
         }
 
+        Ok(DslOutput::Message(output))
+    } else if rest == "EVICTIONS" {
+        let memory = &db.config.memory;
+        let stats = db.memory_stats();
+        let mut output = String::from("--- EVICTIONS ---\n");
+        if memory.budget_bytes == 0 {
+            output.push_str(&format!(
+                "Resident: {} bytes (no memory budget)\n",
+                stats.resident_bytes
+            ));
+        } else {
+            output.push_str(&format!(
+                "Resident: {} / {} bytes (evicting from {:.0}%)\n",
+                stats.resident_bytes,
+                memory.budget_bytes,
+                memory.evict_at * 100.0
+            ));
+        }
+        output.push_str(&format!(
+            "Evictions: {} ({} bytes), reloads: {} ({} bytes)\n",
+            stats.evictions, stats.bytes_evicted, stats.reloads, stats.bytes_reloaded
+        ));
+        output.push_str(&format!(
+            "{:<20} {:<10} {:<12} {}\n",
+            "Dataset", "Rows", "Bytes", "Evicted at"
+        ));
+        output.push_str(&format!("{:-<70}\n", ""));
+        for evicted in db.evicted_datasets() {
+            output.push_str(&format!(
+                "{:<20} {:<10} {:<12} {}\n",
+                evicted.name,
+                evicted.rows,
+                evicted.bytes,
+                evicted.evicted_at.to_rfc3339()
+            ));
+        }
+        output.push_str("------------------");
+        Ok(DslOutput::Message(output))
+    } else if rest == "QUERIES" {
+        let queries = db.running_queries();
+        let mut output = format!(
+            "--- QUERIES (running {}s or longer) ---\n",
+            db.config.execution.progress_after_secs
+        );
+        output.push_str(&format!(
+            "{:<6} {:<12} {:<10} {:<28} {:<12} {:<12} {}\n",
+            "Id", "Database", "Elapsed", "Operator", "Rows", "Remaining", "Command"
+        ));
+        output.push_str(&format!("{:-<100}\n", ""));
+        for query in queries {
+            let remaining = match (query.rows_remaining, query.estimated_remaining_secs) {
+                (Some(rows), Some(secs)) => format!("{} (~{:.1}s)", rows, secs),
+                (Some(rows), None) => rows.to_string(),
+                _ => "-".to_string(),
+            };
+            output.push_str(&format!(
+                "{:<6} {:<12} {:<10} {:<28} {:<12} {:<12} {}\n",
+                query.id,
+                query.database,
+                format!("{:.1}s", query.elapsed_secs),
+                query.operator.as_deref().unwrap_or("-"),
+                query.rows_processed,
+                remaining,
+                query.command
+            ));
+        }
+        output.push_str("-------------------");
+        Ok(DslOutput::Message(output))
+    } else if rest == "JOBS" {
+        let mut output = String::from("--- JOBS ---\n");
+        output.push_str(&format!(
+            "{:<6} {:<12} {:<10} {:<20} {:<10} {}\n",
+            "Id", "Database", "State", "Rows", "Elapsed", "Command"
+        ));
+        output.push_str(&format!("{:-<90}\n", ""));
+        for job in db.jobs() {
+            let mut rows = format!("{}/{}", job.rows_done, job.rows_total);
+            if job.restarts > 0 {
+                rows.push_str(&format!(" ({} restarts)", job.restarts));
+            }
+            output.push_str(&format!(
+                "{:<6} {:<12} {:<10} {:<20} {:<10} {}",
+                job.id,
+                job.database,
+                job.state.to_string(),
+                rows,
+                format!("{:.1}s", job.elapsed_secs),
+                job.command
+            ));
+            if let Some(error) = &job.error {
+                output.push_str(&format!(" -- {}", error));
+            }
+            output.push('\n');
+        }
+        output.push_str("------------");
+        Ok(DslOutput::Message(output))
+    } else if rest == "MACROS" {
+        let mut macros: Vec<_> = db.active_instance().macros.iter().collect();
+        macros.sort_by(|a, b| a.0.cmp(b.0));
+        let mut output = String::from("--- MACROS ---\n");
+        for (name, definition) in macros {
+            output.push_str(&format!(
+                "{}({}) AS ({})\n",
+                name,
+                definition.params.join(", "),
+                definition.body
+            ));
+        }
+        output.push_str("--------------");
         Ok(DslOutput::Message(output))
     } else if rest.starts_with("SHAPE ") {
         let name = rest.trim_start_matches("SHAPE ").trim();
@@ -152,9 +277,10 @@ pub fn handle_show(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslO
             return Ok(DslOutput::Tensor(t.clone()));
         }
 
-        // Check if it's a dataset
-        if let Ok(dataset) = db.get_dataset(name) {
-            return Ok(DslOutput::Table(dataset.clone()));
+        // Check if it's a dataset; masked columns are shown as a query would
+        if db.get_dataset(name).is_ok() {
+            let dataset = visible_dataset(db, name, line_no)?;
+            return Ok(DslOutput::Table(dataset.into_owned()));
         }
 
         // Check if it's a tensor dataset
@@ -169,3 +295,226 @@ pub fn handle_show(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslO
         });
     }
 }
+
+/// DESCRIBE <dataset>
+///
+/// One row per column: type, nullability, index, non-NULL values and the
+/// dataset's row count
+pub fn handle_describe(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let name = dataset_argument(line, "DESCRIBE", line_no)?;
+    let dataset = visible_dataset(db, name, line_no)?;
+    let indices = db.list_indices();
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("column", ValueType::String),
+        Field::new("type", ValueType::String),
+        Field::new("nullable", ValueType::Bool),
+        Field::new("index", ValueType::String).nullable(),
+        Field::new("non_null", ValueType::Int),
+        Field::new("rows", ValueType::Int),
+    ]));
+    let rows = dataset
+        .schema
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let index = indices
+                .iter()
+                .find(|(ds, column, _)| ds == name && column == &field.name)
+                .map_or(Value::Null, |(_, _, kind)| Value::String(kind.clone()));
+            let non_null = dataset.rows.iter().filter(|r| !r.values[i].is_null());
+            Tuple::new(
+                schema.clone(),
+                vec![
+                    Value::String(field.name.clone()),
+                    Value::String(field.value_type.to_string()),
+                    Value::Bool(field.nullable),
+                    index,
+                    Value::Int(non_null.count() as i64),
+                    Value::Int(dataset.rows.len() as i64),
+                ],
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|msg| DslError::Parse { line: line_no, msg })?;
+
+    table(schema, rows, name, line_no)
+}
+
+/// SUMMARIZE <dataset>
+///
+/// One row per column: non-NULL count, share of NULLs, exact distinct count,
+/// min and max, and for numeric columns the mean and sample standard deviation
+pub fn handle_summarize(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let name = dataset_argument(line, "SUMMARIZE", line_no)?;
+    let dataset = visible_dataset(db, name, line_no)?;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("column", ValueType::String),
+        Field::new("type", ValueType::String),
+        Field::new("count", ValueType::Int),
+        Field::new("null_pct", ValueType::Float),
+        Field::new("distinct", ValueType::Int),
+        Field::new("min", ValueType::String).nullable(),
+        Field::new("max", ValueType::String).nullable(),
+        Field::new("mean", ValueType::Float).nullable(),
+        Field::new("stddev", ValueType::Float).nullable(),
+    ]));
+    let total = dataset.rows.len();
+    let rows = dataset
+        .schema
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let values: Vec<&Value> = dataset
+                .rows
+                .iter()
+                .map(|r| &r.values[i])
+                .filter(|v| !v.is_null())
+                .collect();
+            let distinct: HashSet<&Value> = values.iter().copied().collect();
+            let null_pct = if total == 0 {
+                0.0
+            } else {
+                (total - values.len()) as f32 * 100.0 / total as f32
+            };
+            let (min, max) = min_max(&field.value_type, &values);
+            let (mean, stddev) = mean_stddev(&values);
+            Tuple::new(
+                schema.clone(),
+                vec![
+                    Value::String(field.name.clone()),
+                    Value::String(field.value_type.to_string()),
+                    Value::Int(values.len() as i64),
+                    Value::Float(null_pct),
+                    Value::Int(distinct.len() as i64),
+                    min,
+                    max,
+                    mean,
+                    stddev,
+                ],
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|msg| DslError::Parse { line: line_no, msg })?;
+
+    table(schema, rows, name, line_no)
+}
+
+/// The dataset named after `keyword`, the only argument of the command
+fn dataset_argument<'a>(line: &'a str, keyword: &str, line_no: usize) -> Result<&'a str, DslError> {
+    let name = line[keyword.len()..].trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: format!("Expected: {} <dataset>", keyword),
+        });
+    }
+    Ok(name)
+}
+
+/// Dataset `name` as the caller may see it: with its masking policies
+/// applied when any applies to the session's role
+fn visible_dataset<'a>(
+    db: &'a TensorDb,
+    name: &str,
+    line_no: usize,
+) -> Result<Cow<'a, Dataset>, DslError> {
+    let dataset = db.get_dataset(name).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    if !dataset
+        .metadata
+        .masking
+        .values()
+        .any(|p| p.applies_to(db.role()))
+    {
+        return Ok(Cow::Borrowed(dataset));
+    }
+    let scan = crate::query::logical::LogicalPlan::Scan {
+        dataset_name: name.to_string(),
+        schema: dataset.schema.clone(),
+    };
+    let mut masked = db.execute_plan(&scan).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    masked.metadata.name = Some(name.to_string());
+    Ok(Cow::Owned(masked))
+}
+
+fn table(
+    schema: Arc<Schema>,
+    rows: Vec<Tuple>,
+    name: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    Dataset::with_rows(DatasetId(0), schema, rows, Some(name.to_string()))
+        .map(DslOutput::Table)
+        .map_err(|msg| DslError::Parse { line: line_no, msg })
+}
+
+/// Smallest and largest finite values of an ordered column, as text
+fn min_max(value_type: &ValueType, values: &[&Value]) -> (Value, Value) {
+    if !matches!(
+        value_type,
+        ValueType::Int | ValueType::Float | ValueType::String | ValueType::Timestamp
+    ) {
+        return (Value::Null, Value::Null);
+    }
+    let mut ordered = values.iter().filter(|v| !v.is_non_finite());
+    let Some(first) = ordered.next() else {
+        return (Value::Null, Value::Null);
+    };
+    let (mut min, mut max) = (*first, *first);
+    for value in ordered {
+        if value.compare(min) == Some(std::cmp::Ordering::Less) {
+            min = value;
+        }
+        if value.compare(max) == Some(std::cmp::Ordering::Greater) {
+            max = value;
+        }
+    }
+    let text = |value: &Value| match value {
+        Value::String(s) => Value::String(s.clone()),
+        other => Value::String(other.to_string()),
+    };
+    (text(min), text(max))
+}
+
+/// Mean and sample standard deviation of the finite numbers in `values`;
+/// NULL for non-numeric columns, and the deviation for fewer than two numbers
+fn mean_stddev(values: &[&Value]) -> (Value, Value) {
+    let numbers: Vec<f64> = values
+        .iter()
+        .filter_map(|v| match v {
+            Value::Int(i) => Some(*i as f64),
+            Value::Float(f) if f.is_finite() => Some(f64::from(*f)),
+            _ => None,
+        })
+        .collect();
+    if numbers.is_empty() {
+        return (Value::Null, Value::Null);
+    }
+    let n = numbers.len() as f64;
+    let mean = numbers.iter().sum::<f64>() / n;
+    if numbers.len() < 2 {
+        return (Value::Float(mean as f32), Value::Null);
+    }
+    let variance = numbers.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (
+        Value::Float(mean as f32),
+        Value::Float(variance.sqrt() as f32),
+    )
+}
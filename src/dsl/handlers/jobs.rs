@@ -0,0 +1,21 @@
+use crate::dsl::{DslError, DslOutput};
+use crate::engine::TensorDb;
+
+/// Handle CANCEL JOB command
+/// CANCEL JOB <id>
+pub fn handle_cancel_job(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let rest = line.trim_start_matches("CANCEL JOB").trim();
+    let id: u64 = rest.parse().map_err(|_| DslError::Parse {
+        line: line_no,
+        msg: "Expected: CANCEL JOB <id>".into(),
+    })?;
+    db.cancel_job(id).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    Ok(DslOutput::Message(format!("Cancelled job {}", id)))
+}
@@ -0,0 +1,30 @@
+use crate::dsl::parser::parse_macro_definition;
+use crate::dsl::{DslError, DslOutput};
+use crate::engine::TensorDb;
+
+/// Handle DEFINE MACRO command
+/// Syntax: DEFINE MACRO name(param, ...) AS (statement)
+/// The statement refers to its parameters as $param; `name(arg, ...)` then
+/// runs it with each argument's text in place of its parameter.
+pub fn handle_define_macro(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let rest = line.strip_prefix("DEFINE MACRO").unwrap_or(line).trim();
+    let (name, definition) = parse_macro_definition(rest, line_no)?;
+
+    let redefined = db.active_instance().macros.contains_key(&name);
+    let signature = format!("{}({})", name, definition.params.join(", "));
+    db.define_macro(&name, definition)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+
+    Ok(DslOutput::Message(if redefined {
+        format!("Macro '{}' redefined", signature)
+    } else {
+        format!("Macro '{}' defined", signature)
+    }))
+}
@@ -1,5 +1,6 @@
 use crate::dsl::{DslError, DslOutput};
 use crate::engine::TensorDb;
+use crate::utils::parsing::parse_string_literal;
 
 /// Handle SET DATASET <name> METADATA <key> = <value>
 pub fn handle_set_metadata(
@@ -32,7 +33,11 @@ pub fn handle_set_metadata(
     }
 
     let key = kv[0].trim().to_string();
-    let value = kv[1].trim().trim_matches('"').to_string();
+    let value = kv[1].trim();
+    let value = match parse_string_literal(value) {
+        Some(literal) => literal.map_err(|msg| DslError::Parse { line: line_no, msg })?,
+        None => value.to_string(),
+    };
 
     // Update metadata in DB
     db.set_dataset_metadata(dataset_name, key.clone(), value.clone())
@@ -46,3 +51,28 @@ pub fn handle_set_metadata(
         dataset_name, key, value
     )))
 }
+
+/// Handle ANALYZE <dataset>
+pub fn handle_analyze(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let name = line["ANALYZE".len()..].trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: "Expected: ANALYZE <dataset>".to_string(),
+        });
+    }
+
+    let rows = db.analyze_dataset(name).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+
+    Ok(DslOutput::Message(format!(
+        "Analyzed dataset '{}': {} rows",
+        name, rows
+    )))
+}
@@ -1,15 +1,22 @@
+pub mod copy;
 pub mod dataset;
 pub mod explain;
 pub mod index;
 pub mod instance;
 pub mod introspection;
+pub mod jobs;
+pub mod macros;
 pub mod metadata;
 pub mod operations;
 pub mod persistence;
+pub mod returning;
 pub mod search;
+pub mod sql;
+pub mod stored_query;
 pub mod tensor;
+pub mod transaction;
 
-pub use dataset::{handle_dataset, handle_insert};
+pub use dataset::{handle_dataset, handle_delete, handle_insert, handle_update, handle_upsert};
 pub use instance::{handle_create_database, handle_drop_database, handle_use_database};
 pub use introspection::handle_show;
 pub use operations::handle_let;
@@ -1,16 +1,51 @@
+use crate::dsl::handlers::persistence::auto_persist_dataset;
 use crate::dsl::{DslError, DslOutput};
 use crate::engine::context::ExecutionContext;
-use crate::engine::{BinaryOp, TensorDb, UnaryOp};
+use crate::engine::{BinaryOp, CompareOp, EngineError, TensorDb, UnaryOp};
 
 /// LET c = ADD a b
 /// LET score = CORRELATE a WITH b
 /// LET sim = SIMILARITY a WITH b
 /// LET half = SCALE a BY 0.5
+/// LET total = CUMSUM a
+/// LET smooth = ROLLING_MEAN a WINDOW 3
+/// LET ranked = SORT a DESC
+/// LET best, at = TOPK a 10
+/// LET o = OUTER a b
+/// LET c = COV m POPULATION
+/// LET g = GRAM m CENTERED
+/// LET scores = m @ q
+/// LET mask = v > 0.5
+/// LET big = v[v > 0.5]
+/// LET clipped = WHERE(v > 1, 1, v)
 pub fn handle_let(
     db: &mut TensorDb,
     line: &str,
     line_no: usize,
     ctx: Option<&mut ExecutionContext>,
+) -> Result<DslOutput, DslError> {
+    eval_let(db, line, line_no, ctx).map_err(|e| match e {
+        DslError::Engine {
+            line: err_line,
+            source: source @ EngineError::ShapeMismatch { .. },
+        } => DslError::ShapeMismatch {
+            line: err_line,
+            expr: line
+                .split_once('=')
+                .map_or(line, |(_, rhs)| rhs)
+                .trim()
+                .to_string(),
+            source: Box::new(source),
+        },
+        other => other,
+    })
+}
+
+fn eval_let(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+    ctx: Option<&mut ExecutionContext>,
 ) -> Result<DslOutput, DslError> {
     // If no context provided, create a transient one
     let mut local_ctx;
@@ -74,9 +109,41 @@ pub fn handle_let(
             | "SCALE"
             | "NORMALIZE"
             | "FLATTEN"
+            | "CUMSUM"
+            | "CUMPROD"
+            | "DIFF"
+            | "ROLLING_MEAN"
+            | "SORT"
+            | "ARGSORT"
+            | "TOPK"
+            | "OUTER"
+            | "COV"
+            | "GRAM"
     );
 
+    // Ternary select: WHERE(cond, a, b)
+    if expr.to_ascii_uppercase().starts_with("WHERE(") {
+        return handle_where(db, output_name, expr, line_no, ctx);
+    }
+
     if !is_keyword {
+        // Matrix product: m @ q
+        let is_name = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if let Some((left, right)) = expr
+            .split_once('@')
+            .filter(|(l, r)| is_name(l.trim()) && is_name(r.trim()))
+        {
+            db.eval_matmul(ctx, output_name, left.trim(), right.trim())
+                .map_err(|e| DslError::Engine {
+                    line: line_no,
+                    source: e,
+                })?;
+            return Ok(DslOutput::Message(format!(
+                "Defined variable: {}",
+                output_name
+            )));
+        }
+
         // Check for infix operator: a + b
         if let Some((left, op, right)) = parse_infix_op(expr) {
             return handle_infix_op(db, output_name, left, op, right, line_no, ctx);
@@ -255,6 +322,20 @@ pub fn handle_let(
                     source: e,
                 })
         }
+        "OUTER" => {
+            // OUTER a b
+            if tokens.len() != 3 {
+                return Err(DslError::Parse {
+                    line: line_no,
+                    msg: "Expected: LET x = OUTER a b".into(),
+                });
+            }
+            db.eval_binary(ctx, output_name, tokens[1], tokens[2], BinaryOp::Outer)
+                .map_err(|e| DslError::Engine {
+                    line: line_no,
+                    source: e,
+                })
+        }
         "MATMUL" => {
             // MATMUL a b
             if tokens.len() != 3 {
@@ -348,6 +429,127 @@ pub fn handle_let(
                     source: e,
                 })
         }
+        "CUMSUM" | "CUMPROD" | "DIFF" => {
+            // LET x = CUMSUM a
+            if tokens.len() != 2 {
+                return Err(DslError::Parse {
+                    line: line_no,
+                    msg: format!("Expected: LET x = {} a", tokens[0]),
+                });
+            }
+            let op = match tokens[0] {
+                "CUMSUM" => UnaryOp::CumSum,
+                "CUMPROD" => UnaryOp::CumProd,
+                _ => UnaryOp::Diff,
+            };
+            db.eval_unary(ctx, output_name, tokens[1], op)
+                .map_err(|e| DslError::Engine {
+                    line: line_no,
+                    source: e,
+                })
+        }
+        "ROLLING_MEAN" => {
+            // LET x = ROLLING_MEAN a WINDOW 3
+            if tokens.len() != 4 || tokens[2] != "WINDOW" {
+                return Err(DslError::Parse {
+                    line: line_no,
+                    msg: "Expected: LET x = ROLLING_MEAN a WINDOW <n>".into(),
+                });
+            }
+            let window: usize = tokens[3].parse().map_err(|_| DslError::Parse {
+                line: line_no,
+                msg: format!("Invalid window size: {}", tokens[3]),
+            })?;
+            db.eval_unary(ctx, output_name, tokens[1], UnaryOp::RollingMean(window))
+                .map_err(|e| DslError::Engine {
+                    line: line_no,
+                    source: e,
+                })
+        }
+        "SORT" | "ARGSORT" => {
+            // LET x = SORT a [ASC|DESC]
+            let descending = match tokens.get(2).map(|t| t.to_ascii_uppercase()) {
+                _ if !(2..=3).contains(&tokens.len()) => None,
+                None => Some(false),
+                Some(dir) if dir == "ASC" => Some(false),
+                Some(dir) if dir == "DESC" => Some(true),
+                Some(_) => None,
+            };
+            let Some(descending) = descending else {
+                return Err(DslError::Parse {
+                    line: line_no,
+                    msg: format!("Expected: LET x = {} a [ASC|DESC]", tokens[0]),
+                });
+            };
+            let op = if tokens[0] == "SORT" {
+                UnaryOp::Sort { descending }
+            } else {
+                UnaryOp::ArgSort { descending }
+            };
+            db.eval_unary(ctx, output_name, tokens[1], op)
+                .map_err(|e| DslError::Engine {
+                    line: line_no,
+                    source: e,
+                })
+        }
+        "COV" | "GRAM" => {
+            // LET c = COV m [POPULATION], LET g = GRAM m [CENTERED]
+            let option = if tokens[0] == "COV" {
+                "POPULATION"
+            } else {
+                "CENTERED"
+            };
+            let flag = match tokens.get(2) {
+                _ if !(2..=3).contains(&tokens.len()) => None,
+                None => Some(false),
+                Some(t) if t.eq_ignore_ascii_case(option) => Some(true),
+                Some(_) => None,
+            };
+            let Some(flag) = flag else {
+                return Err(DslError::Parse {
+                    line: line_no,
+                    msg: format!("Expected: LET x = {} m [{}]", tokens[0], option),
+                });
+            };
+            let op = if tokens[0] == "COV" {
+                UnaryOp::Cov { population: flag }
+            } else {
+                UnaryOp::Gram { centered: flag }
+            };
+            db.eval_unary(ctx, output_name, tokens[1], op)
+                .map_err(|e| DslError::Engine {
+                    line: line_no,
+                    source: e,
+                })
+        }
+        "TOPK" => {
+            // LET values[, indices] = TOPK a k
+            if tokens.len() != 3 {
+                return Err(DslError::Parse {
+                    line: line_no,
+                    msg: "Expected: LET values[, indices] = TOPK a <k>".into(),
+                });
+            }
+            let k: usize = tokens[2].parse().map_err(|_| DslError::Parse {
+                line: line_no,
+                msg: format!("Invalid k: {}", tokens[2]),
+            })?;
+            let (values_name, indices_name) = match output_name.split_once(',') {
+                Some((values, indices)) => (values.trim(), Some(indices.trim())),
+                None => (output_name, None),
+            };
+            if values_name.is_empty() || indices_name.is_some_and(str::is_empty) {
+                return Err(DslError::Parse {
+                    line: line_no,
+                    msg: "Missing output name in LET".into(),
+                });
+            }
+            db.eval_topk(values_name, indices_name, tokens[1], k)
+                .map_err(|e| DslError::Engine {
+                    line: line_no,
+                    source: e,
+                })
+        }
         "STACK" => {
             // LET x = STACK a b c ...
             if tokens.len() < 3 {
@@ -372,7 +574,7 @@ pub fn handle_let(
     .map(|_| DslOutput::Message(format!("Defined variable: {}", output_name)))
 }
 
-/// Handle indexing expressions: name[i, j, ...] or name[i:j, *]
+/// Handle indexing expressions: name[i, j, ...], name[i:j, *] or name[::-1, 1::2]
 fn handle_indexing(
     db: &mut TensorDb,
     output_name: &str,
@@ -387,7 +589,7 @@ fn handle_indexing(
         msg: "Expected '[' in indexing expression".into(),
     })?;
 
-    let bracket_end = expr.find(']').ok_or_else(|| DslError::Parse {
+    let bracket_end = expr.rfind(']').ok_or_else(|| DslError::Parse {
         line: line_no,
         msg: "Expected ']' in indexing expression".into(),
     })?;
@@ -402,47 +604,58 @@ fn handle_indexing(
     let tensor_name = expr[..bracket_start].trim();
     let specs_str = &expr[bracket_start + 1..bracket_end];
 
-    // Parse specs: "0, 1" or "0:2, *" or "*, 1"
-    let spec_parts: Vec<&str> = specs_str.split(',').map(|s| s.trim()).collect();
-    let mut specs = Vec::new();
-    let mut all_indices = true; // Track if all specs are simple indices
+    // A comparison or a tensor name selects by boolean mask
+    let is_mask = split_comparison(specs_str).is_some()
+        || specs_str
+            .trim_start()
+            .starts_with(|c: char| c.is_alphabetic() || c == '_');
+    if is_mask {
+        return handle_mask(db, output_name, tensor_name, specs_str, line_no);
+    }
 
-    for part in spec_parts {
+    // Parse specs: "0, 1", "0:2, *", "*, -1" or "::-1, 1:"
+    let mut specs = Vec::new();
+    for part in specs_str.split(',').map(|s| s.trim()) {
         if part == "*" || part == ":" {
             // Wildcard: entire dimension
             specs.push(SliceSpec::All);
-            all_indices = false;
         } else if part.contains(':') {
-            // Range: start:end
-            let range_parts: Vec<&str> = part.split(':').collect();
-            if range_parts.len() != 2 {
+            // Range: start:end or start:end:step, any of them optional
+            let range_parts: Vec<&str> = part.split(':').map(|s| s.trim()).collect();
+            if range_parts.len() > 3 {
                 return Err(DslError::Parse {
                     line: line_no,
                     msg: format!("Invalid range spec: {}", part),
                 });
             }
+            let bound = |text: &str, what: &str| -> Result<Option<isize>, DslError> {
+                if text.is_empty() {
+                    return Ok(None);
+                }
+                text.parse::<isize>()
+                    .map(Some)
+                    .map_err(|_| DslError::Parse {
+                        line: line_no,
+                        msg: format!("Invalid range {}: {}", what, text),
+                    })
+            };
 
-            let start = range_parts[0]
-                .trim()
-                .parse::<usize>()
-                .map_err(|_| DslError::Parse {
-                    line: line_no,
-                    msg: format!("Invalid range start: {}", range_parts[0]),
-                })?;
-
-            let end = range_parts[1]
-                .trim()
-                .parse::<usize>()
-                .map_err(|_| DslError::Parse {
+            let start = bound(range_parts[0], "start")?;
+            let end = bound(range_parts[1], "end")?;
+            let step = match range_parts.get(2) {
+                Some(text) => bound(text, "step")?.unwrap_or(1),
+                None => 1,
+            };
+            if step == 0 {
+                return Err(DslError::Parse {
                     line: line_no,
-                    msg: format!("Invalid range end: {}", range_parts[1]),
-                })?;
-
-            specs.push(SliceSpec::Range(start, end));
-            all_indices = false;
+                    msg: format!("Range step cannot be zero: {}", part),
+                });
+            }
+            specs.push(SliceSpec::Range { start, end, step });
         } else {
-            // Single index
-            let idx = part.parse::<usize>().map_err(|_| DslError::Parse {
+            // Single index, negative counting from the end
+            let idx = part.parse::<isize>().map_err(|_| DslError::Parse {
                 line: line_no,
                 msg: format!("Invalid index: {}", part),
             })?;
@@ -450,32 +663,113 @@ fn handle_indexing(
         }
     }
 
-    // If all specs are simple indices, use eval_index for backwards compatibility
-    if all_indices {
-        let indices: Vec<usize> = specs
-            .iter()
-            .filter_map(|s| {
-                if let SliceSpec::Index(idx) = s {
-                    Some(*idx)
-                } else {
-                    None
-                }
-            })
-            .collect();
+    db.eval_slice(output_name, tensor_name, specs)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
 
-        db.eval_index(output_name, tensor_name, indices)
-            .map_err(|e| DslError::Engine {
-                line: line_no,
-                source: e,
-            })?;
+    Ok(DslOutput::Message(format!(
+        "Defined variable: {}",
+        output_name
+    )))
+}
+
+/// Handle boolean mask selection: name[mask] or name[<comparison>]. On a
+/// dataset the mask has one element per row and the result is a new dataset.
+fn handle_mask(
+    db: &mut TensorDb,
+    output_name: &str,
+    source_name: &str,
+    mask_expr: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let engine_err = |e| DslError::Engine {
+        line: line_no,
+        source: e,
+    };
+
+    let (mask_name, temporary) = match split_comparison(mask_expr) {
+        Some((left, op, right)) => {
+            let temp_name = format!("_tmp_{}_mask", output_name);
+            let mut ctx = ExecutionContext::new();
+            let op = BinaryOp::Compare(op);
+            handle_infix_op(db, &temp_name, left, op, right, line_no, &mut ctx)?;
+            (temp_name, true)
+        }
+        None => {
+            let name = evaluate_operand(db, mask_expr, output_name, "mask", line_no)?;
+            let temporary = name != mask_expr.trim();
+            (name, temporary)
+        }
+    };
+
+    let result = if db.get_dataset(source_name).is_ok() {
+        db.eval_dataset_mask(output_name, source_name, &mask_name)
+            .map(Some)
     } else {
-        // Use eval_slice for advanced indexing
-        db.eval_slice(output_name, tensor_name, specs)
-            .map_err(|e| DslError::Engine {
-                line: line_no,
-                source: e,
-            })?;
+        db.eval_mask(output_name, source_name, &mask_name)
+            .map(|_| None)
+    };
+    if temporary {
+        db.remove_tensor(&mask_name);
+    }
+
+    match result.map_err(engine_err)? {
+        Some(rows) => {
+            auto_persist_dataset(db, output_name, line_no)?;
+            Ok(DslOutput::Message(format!(
+                "Created dataset: {} ({} rows)",
+                output_name, rows
+            )))
+        }
+        None => Ok(DslOutput::Message(format!(
+            "Defined variable: {}",
+            output_name
+        ))),
     }
+}
+
+/// Handle WHERE(cond, a, b): `a` where `cond` holds, `b` elsewhere. Each
+/// argument is a tensor, a number, an infix expression such as a comparison,
+/// or a nested WHERE.
+fn handle_where(
+    db: &mut TensorDb,
+    output_name: &str,
+    expr: &str,
+    line_no: usize,
+    ctx: &mut ExecutionContext,
+) -> Result<DslOutput, DslError> {
+    let usage = || DslError::Parse {
+        line: line_no,
+        msg: "Expected: LET x = WHERE(cond, a, b)".into(),
+    };
+    let inner = expr["WHERE(".len()..]
+        .trim_end()
+        .strip_suffix(')')
+        .ok_or_else(usage)?;
+    let args = split_args(inner);
+    let [cond, then, otherwise] = args.as_slice() else {
+        return Err(usage());
+    };
+
+    let mut operands = Vec::with_capacity(3);
+    for (arg, suffix) in [(cond, "cond"), (then, "then"), (otherwise, "else")] {
+        match where_operand(db, arg, output_name, suffix, line_no, ctx) {
+            Ok(name) => operands.push((name, *arg)),
+            Err(e) => {
+                remove_temporaries(db, &operands);
+                return Err(e);
+            }
+        }
+    }
+
+    let result = db.eval_where(output_name, &operands[0].0, &operands[1].0, &operands[2].0);
+    remove_temporaries(db, &operands);
+    result.map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
 
     Ok(DslOutput::Message(format!(
         "Defined variable: {}",
@@ -483,6 +777,48 @@ fn handle_indexing(
     )))
 }
 
+/// Tensor holding a WHERE argument, computed into a temporary when needed
+fn where_operand(
+    db: &mut TensorDb,
+    arg: &str,
+    base_name: &str,
+    suffix: &str,
+    line_no: usize,
+    ctx: &mut ExecutionContext,
+) -> Result<String, DslError> {
+    let temp_name = format!("_tmp_{}_{}", base_name, suffix);
+    if arg.to_ascii_uppercase().starts_with("WHERE(") {
+        handle_where(db, &temp_name, arg, line_no, ctx)?;
+        return Ok(temp_name);
+    }
+    if arg.parse::<f32>().is_err() {
+        if let Some((left, op, right)) = parse_infix_op(arg) {
+            handle_infix_op(db, &temp_name, left, op, right, line_no, ctx)?;
+            return Ok(temp_name);
+        }
+    }
+    evaluate_operand(db, arg, base_name, suffix, line_no)
+}
+
+/// Split call arguments at the commas outside brackets and parentheses
+fn split_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in args.char_indices() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(args[start..].trim());
+    parts
+}
+
 /// Handle dot notation: name.field
 fn handle_dot_notation(
     db: &mut TensorDb,
@@ -526,6 +862,12 @@ fn handle_dot_notation(
 
 fn parse_infix_op(expr: &str) -> Option<(&str, crate::engine::BinaryOp, &str)> {
     use crate::engine::BinaryOp;
+
+    // Comparisons bind loosest
+    if let Some((left, op, right)) = split_comparison(expr) {
+        return Some((left, BinaryOp::Compare(op), right));
+    }
+
     let ops = [
         ("+", BinaryOp::Add),
         ("-", BinaryOp::Subtract),
@@ -552,6 +894,47 @@ fn parse_infix_op(expr: &str) -> Option<(&str, crate::engine::BinaryOp, &str)> {
     None
 }
 
+/// Split `expr` at its first comparison operator outside brackets
+fn split_comparison(expr: &str) -> Option<(&str, CompareOp, &str)> {
+    // Two-character operators first, so `>=` is not read as `>`
+    let ops = [
+        (">=", CompareOp::Ge),
+        ("<=", CompareOp::Le),
+        ("!=", CompareOp::Ne),
+        ("==", CompareOp::Eq),
+        (">", CompareOp::Gt),
+        ("<", CompareOp::Lt),
+        ("=", CompareOp::Eq),
+    ];
+
+    let mut bracket_level = 0;
+    for (i, c) in expr.char_indices() {
+        if c == '[' {
+            bracket_level += 1;
+        } else if c == ']' {
+            bracket_level -= 1;
+        } else if bracket_level == 0 {
+            for (sym, op) in &ops {
+                if expr[i..].starts_with(sym) {
+                    return Some((expr[..i].trim(), *op, expr[i + sym.len()..].trim()));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Drop the temporaries bound for `(name, operand)` pairs, leaving operands
+/// that named an existing tensor alone
+fn remove_temporaries(db: &mut TensorDb, operands: &[(String, &str)]) {
+    for (name, operand) in operands {
+        if name != operand.trim() {
+            // Column access binds the column's own tensor
+            db.release_tensor(name);
+        }
+    }
+}
+
 fn evaluate_operand(
     db: &mut TensorDb,
     expr: &str,
@@ -612,13 +995,38 @@ fn handle_infix_op(
 
     let left_name = evaluate_operand(db, left, output_name, &format!("L_{}", timestamp), line_no)?;
     let right_name =
-        evaluate_operand(db, right, output_name, &format!("R_{}", timestamp), line_no)?;
+        match evaluate_operand(db, right, output_name, &format!("R_{}", timestamp), line_no) {
+            Ok(name) => name,
+            Err(e) => {
+                remove_temporaries(db, &[(left_name, left)]);
+                return Err(e);
+            }
+        };
 
-    db.eval_binary(ctx, output_name, &left_name, &right_name, op)
-        .map_err(|e| DslError::Engine {
+    let result = db.eval_binary(ctx, output_name, &left_name, &right_name, op);
+    remove_temporaries(db, &[(left_name, left), (right_name, right)]);
+    result.map_err(|e| {
+        // Report the operands as written rather than their temporaries
+        let source = match e {
+            EngineError::ShapeMismatch {
+                left_shape,
+                right_shape,
+                op,
+                ..
+            } => EngineError::ShapeMismatch {
+                left_name: left.trim().to_string(),
+                right_name: right.trim().to_string(),
+                left_shape,
+                right_shape,
+                op,
+            },
+            other => other,
+        };
+        DslError::Engine {
             line: line_no,
-            source: e,
-        })?;
+            source,
+        }
+    })?;
 
     Ok(DslOutput::Message(format!(
         "Defined variable: {}",
@@ -1,10 +1,104 @@
-use crate::core::storage::{ParquetStorage, StorageEngine};
+use crate::core::config::ParquetCompression;
+use crate::core::storage::export::{export_dataset, ExportFormat};
+use crate::core::storage::npy::{is_npy_path, read_npy, write_npy};
+use crate::core::storage::wal::WriteAheadLog;
+use crate::core::storage::{ParquetStorage, ParquetWriteOptions, StorageEngine};
+use crate::core::value::Value;
 use crate::dsl::{DslError, DslOutput};
 use crate::engine::TensorDb;
+use crate::utils::parsing::{parse_int, parse_timestamp};
+
+/// Local directory of the active database: data_dir / active_db (holds the WAL)
+fn default_storage_path(db: &TensorDb) -> String {
+    let mut p = db.config.storage.data_dir.clone();
+    p.push(&db.active_instance().name);
+    p.to_string_lossy().into_owned()
+}
+
+/// Parquet storage at an explicit `path`, or the active database's storage on
+/// the configured backend. Returns the storage and its location for messages.
+fn open_storage(
+    db: &TensorDb,
+    path: Option<String>,
+    line_no: usize,
+) -> Result<(Box<dyn StorageEngine>, String), DslError> {
+    if let Some(path) = path {
+        let options = ParquetWriteOptions::from_config(&db.config.storage);
+        let storage = ParquetStorage::new(&path).with_options(options);
+        return Ok((Box::new(storage), path));
+    }
+    let name = &db.active_instance().name;
+    let storage = db.database_storage(name).map_err(|e| DslError::Parse {
+        line: line_no,
+        msg: format!("Failed to open storage: {}", e),
+    })?;
+    Ok((storage, db.storage_location(name)))
+}
+
+/// Write-through hook for `[storage] auto_persist = true`: flushes the named
+/// dataset to the active database's storage path after a mutating command.
+/// No-op when auto persistence is disabled.
+pub fn auto_persist_dataset(db: &TensorDb, name: &str, line_no: usize) -> Result<(), DslError> {
+    if !db.config.storage.auto_persist {
+        return Ok(());
+    }
+
+    let dataset = db.get_dataset(name).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    let (storage, _) = open_storage(db, None, line_no)?;
+    storage
+        .save_dataset(dataset)
+        .map_err(|e| DslError::Parse {
+            line: line_no,
+            msg: format!("Failed to persist dataset '{}': {}", name, e),
+        })
+}
+
+/// Remove a dropped or renamed dataset from storage (no-op unless auto_persist is on)
+pub fn auto_persist_drop(db: &TensorDb, name: &str, line_no: usize) -> Result<(), DslError> {
+    if !db.config.storage.auto_persist {
+        return Ok(());
+    }
+
+    let (storage, _) = open_storage(db, None, line_no)?;
+    storage.delete_dataset(name).map_err(|e| DslError::Parse {
+        line: line_no,
+        msg: format!("Failed to delete stored dataset '{}': {}", name, e),
+    })
+}
+
+/// Append an executed command to the active database's write-ahead log
+pub fn append_to_wal(db: &TensorDb, line: &str, line_no: usize) -> Result<(), DslError> {
+    WriteAheadLog::for_database(default_storage_path(db))
+        .append(line)
+        .map_err(|e| DslError::Parse {
+            line: line_no,
+            msg: format!("Failed to write WAL: {}", e),
+        })
+}
+
+/// Handle CHECKPOINT command
+/// Snapshots the active database (as SAVE ALL) and truncates its WAL
+pub fn handle_checkpoint(db: &mut TensorDb, line_no: usize) -> Result<DslOutput, DslError> {
+    let path = default_storage_path(db);
+    let saved = handle_save_all(db, "ALL", line_no)?;
+
+    WriteAheadLog::for_database(&path)
+        .truncate()
+        .map_err(|e| DslError::Parse {
+            line: line_no,
+            msg: format!("Failed to truncate WAL: {}", e),
+        })?;
+
+    Ok(DslOutput::Message(format!("Checkpoint complete. {}", saved)))
+}
 
 /// Handle SAVE command
 /// Syntax: SAVE DATASET dataset_name TO "path"
-///         SAVE TENSOR tensor_name TO "path"
+///         SAVE TENSOR tensor_name TO "path"   (or "file.npy")
+///         SAVE ALL [TO "path"]
 pub fn handle_save(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
     let rest = line.strip_prefix("SAVE ").unwrap().trim();
 
@@ -12,14 +106,111 @@ pub fn handle_save(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslO
         handle_save_dataset(db, rest, line_no)
     } else if rest.starts_with("TENSOR ") {
         handle_save_tensor(db, rest, line_no)
+    } else if rest == "ALL" || rest.starts_with("ALL ") {
+        handle_save_all(db, rest, line_no)
     } else {
         Err(DslError::Parse {
             line: line_no,
-            msg: "Expected 'DATASET' or 'TENSOR' after 'SAVE'".to_string(),
+            msg: "Expected 'DATASET', 'TENSOR' or 'ALL' after 'SAVE'".to_string(),
         })
     }
 }
 
+/// SAVE ALL [TO "path"]: persist every dataset and named tensor of the active database
+fn handle_save_all(db: &mut TensorDb, rest: &str, line_no: usize) -> Result<DslOutput, DslError> {
+    let rest = rest.strip_prefix("ALL").unwrap().trim();
+
+    let path = if let Some(p) = rest.strip_prefix("TO ") {
+        Some(p.trim().trim_matches('"').to_string())
+    } else if rest.is_empty() {
+        None
+    } else {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: "Expected: SAVE ALL [TO \"path\"]".to_string(),
+        });
+    };
+
+    let elsewhere = path.is_some();
+    let (storage, path) = open_storage(db, path, line_no)?;
+
+    let mut dataset_names = db.list_dataset_names();
+    dataset_names.sort();
+    for name in &dataset_names {
+        let dataset = db.get_dataset(name).map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+        storage
+            .save_dataset(dataset)
+            .map_err(|e| DslError::Parse {
+                line: line_no,
+                msg: format!("Failed to save dataset '{}': {}", name, e),
+            })?;
+    }
+    // Evicted datasets are already in the database's storage; copy them one at a time
+    if elsewhere {
+        let db_name = db.active_instance().name.clone();
+        for evicted in db.evicted_datasets() {
+            let dataset = db
+                .load_evicted(&db_name, &evicted.name)
+                .map_err(|e| DslError::Engine {
+                    line: line_no,
+                    source: e,
+                })?;
+            storage
+                .save_dataset(&dataset)
+                .map_err(|e| DslError::Parse {
+                    line: line_no,
+                    msg: format!("Failed to save dataset '{}': {}", evicted.name, e),
+                })?;
+        }
+    }
+
+    // Tensor-first datasets are materialized, as in SAVE DATASET
+    let mut view_names = db.active_instance().tensor_datasets.list_names();
+    view_names.sort();
+    for name in &view_names {
+        let dataset = db
+            .materialize_tensor_dataset(name)
+            .map_err(|e| DslError::Engine {
+                line: line_no,
+                source: e,
+            })?;
+        storage
+            .save_dataset(&dataset)
+            .map_err(|e| DslError::Parse {
+                line: line_no,
+                msg: format!("Failed to save dataset '{}': {}", name, e),
+            })?;
+    }
+
+    let mut tensor_names = db.list_names();
+    tensor_names.sort();
+    for name in &tensor_names {
+        let tensor = db
+            .active_instance()
+            .get(name)
+            .map_err(|e| DslError::Engine {
+                line: line_no,
+                source: e,
+            })?;
+        storage
+            .save_tensor(name, tensor)
+            .map_err(|e| DslError::Parse {
+                line: line_no,
+                msg: format!("Failed to save tensor '{}': {}", name, e),
+            })?;
+    }
+
+    Ok(DslOutput::Message(format!(
+        "Saved {} datasets and {} tensors to '{}'",
+        dataset_names.len() + view_names.len(),
+        tensor_names.len(),
+        path
+    )))
+}
+
 fn handle_save_dataset(
     db: &mut TensorDb,
     rest: &str,
@@ -31,12 +222,9 @@ fn handle_save_dataset(
     let (dataset_name, path) = if let Some(idx) = rest.find(" TO ") {
         let name = rest[..idx].trim();
         let p = rest[idx + 4..].trim().trim_matches('"').to_string();
-        (name, p)
+        (name, Some(p))
     } else {
-        // Default path: data_dir / active_db
-        let mut p = db.config.storage.data_dir.clone();
-        p.push(&db.active_instance().name);
-        (rest, p.to_string_lossy().into_owned())
+        (rest, None)
     };
 
     // Get dataset from store using public method
@@ -51,7 +239,7 @@ fn handle_save_dataset(
     };
 
     // Save using storage engine
-    let storage = ParquetStorage::new(&path);
+    let (storage, path) = open_storage(db, path, line_no)?;
     storage
         .save_dataset(&dataset)
         .map_err(|e| DslError::Parse {
@@ -76,12 +264,9 @@ fn handle_save_tensor(
     let (tensor_name, path) = if let Some(idx) = rest.find(" TO ") {
         let name = rest[..idx].trim();
         let p = rest[idx + 4..].trim().trim_matches('"').to_string();
-        (name, p)
+        (name, Some(p))
     } else {
-        // Default path: data_dir / active_db
-        let mut p = db.config.storage.data_dir.clone();
-        p.push(&db.active_instance().name);
-        (rest, p.to_string_lossy().into_owned())
+        (rest, None)
     };
 
     // Get tensor from db
@@ -93,14 +278,18 @@ fn handle_save_tensor(
             source: e,
         })?;
 
-    // Save using storage engine
-    let storage = ParquetStorage::new(&path);
-    storage
-        .save_tensor(tensor_name, tensor)
-        .map_err(|e| DslError::Parse {
-            line: line_no,
-            msg: format!("Failed to save tensor: {}", e),
-        })?;
+    // A ".npy" target is a single file; anything else is a storage directory
+    let (saved, path) = match path {
+        Some(path) if is_npy_path(&path) => (write_npy(tensor, std::path::Path::new(&path)), path),
+        path => {
+            let (storage, path) = open_storage(db, path, line_no)?;
+            (storage.save_tensor(tensor_name, tensor), path)
+        }
+    };
+    saved.map_err(|e| DslError::Parse {
+        line: line_no,
+        msg: format!("Failed to save tensor: {}", e),
+    })?;
 
     Ok(DslOutput::Message(format!(
         "Saved tensor '{}' to '{}'",
@@ -108,9 +297,159 @@ fn handle_save_tensor(
     )))
 }
 
+/// Handle EXPORT command
+/// Syntax: EXPORT (SELECT ...) TO "path" [FORMAT parquet|csv|json]
+///         [COMPRESSION none|snappy|gzip|lz4|zstd] [ROW GROUP n]
+///         [DICTIONARY | NO DICTIONARY] [STATISTICS | NO STATISTICS]
+/// The format defaults to the file extension of `path`. The Parquet options
+/// default to the `[storage] parquet_*` settings.
+pub fn handle_export(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
+    let syntax_err = || DslError::Parse {
+        line: line_no,
+        msg: "Expected: EXPORT (SELECT ...) TO \"path\" [FORMAT parquet|csv|json]".to_string(),
+    };
+
+    let rest = line.strip_prefix("EXPORT ").unwrap().trim();
+    if !rest.starts_with('(') {
+        return Err(syntax_err());
+    }
+    let close = super::dataset::find_matching_paren(rest).ok_or_else(syntax_err)?;
+    let query = rest[1..close].trim();
+    let target = rest[close + 1..]
+        .trim()
+        .strip_prefix("TO ")
+        .ok_or_else(syntax_err)?
+        .trim();
+
+    let (path, rest) = match target.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"').ok_or_else(syntax_err)?,
+        None => target.split_once(' ').unwrap_or((target, "")),
+    };
+    if path.is_empty() {
+        return Err(syntax_err());
+    }
+    let (format, parquet) = parse_export_options(db, rest, line_no)?;
+    let format = match format {
+        Some(format) => format,
+        None => ExportFormat::from_path(path).ok_or_else(|| DslError::Parse {
+            line: line_no,
+            msg: format!("Cannot infer export format of '{}'; add FORMAT", path),
+        })?,
+    };
+    if parquet.is_some() && format != ExportFormat::Parquet {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: format!(
+                "COMPRESSION, ROW GROUP, DICTIONARY and STATISTICS apply to parquet exports, not {}",
+                format.name()
+            ),
+        });
+    }
+    let parquet = parquet.unwrap_or_else(|| ParquetWriteOptions::from_config(&db.config.storage));
+
+    if !query.starts_with("SELECT ") && !query.starts_with("WITH ") {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: "EXPORT only supports SELECT queries".to_string(),
+        });
+    }
+    let dataset = match super::dataset::handle_select(db, query, line_no)? {
+        DslOutput::Table(ds) => ds,
+        _ => unreachable!("SELECT always produces a table"),
+    };
+
+    export_dataset(&dataset, std::path::Path::new(path), format, parquet).map_err(|e| {
+        DslError::Parse {
+            line: line_no,
+            msg: format!("Failed to export to '{}': {}", path, e),
+        }
+    })?;
+
+    Ok(DslOutput::Message(format!(
+        "Exported {} rows to '{}' ({})",
+        dataset.len(),
+        path,
+        format.name()
+    )))
+}
+
+/// The first word of `s` and what follows it
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    s.split_once(char::is_whitespace).unwrap_or((s, ""))
+}
+
+/// Clauses after the EXPORT path: the FORMAT, if given, and the Parquet
+/// options if any of them is given
+fn parse_export_options(
+    db: &TensorDb,
+    mut rest: &str,
+    line_no: usize,
+) -> Result<(Option<ExportFormat>, Option<ParquetWriteOptions>), DslError> {
+    let parse_err = |msg: String| DslError::Parse { line: line_no, msg };
+
+    let mut format = None;
+    let mut parquet: Option<ParquetWriteOptions> = None;
+    let defaults = ParquetWriteOptions::from_config(&db.config.storage);
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        } else if let Some(r) = rest.strip_prefix("FORMAT ") {
+            let (name, r) = split_word(r);
+            format = Some(ExportFormat::parse(name).ok_or_else(|| {
+                parse_err(format!(
+                    "Unknown export format '{}' (parquet, csv or json)",
+                    name
+                ))
+            })?);
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("COMPRESSION ") {
+            let (name, r) = split_word(r);
+            let compression = ParquetCompression::parse(name).ok_or_else(|| {
+                parse_err(format!(
+                    "Unknown compression '{}' (none, snappy, gzip, lz4 or zstd)",
+                    name
+                ))
+            })?;
+            parquet.get_or_insert(defaults).compression = compression;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("ROW GROUP ") {
+            let (rows, r) = split_word(r);
+            parquet.get_or_insert(defaults).row_group_rows = rows
+                .parse::<usize>()
+                .ok()
+                .filter(|rows| *rows > 0)
+                .ok_or_else(|| {
+                    parse_err(format!(
+                        "ROW GROUP expects a positive row count, got '{}'",
+                        rows
+                    ))
+                })?;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("NO DICTIONARY") {
+            parquet.get_or_insert(defaults).dictionary = false;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("DICTIONARY") {
+            parquet.get_or_insert(defaults).dictionary = true;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("NO STATISTICS") {
+            parquet.get_or_insert(defaults).statistics = false;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("STATISTICS") {
+            parquet.get_or_insert(defaults).statistics = true;
+            rest = r;
+        } else {
+            return Err(parse_err(format!("Unexpected EXPORT option '{}'", rest)));
+        }
+    }
+    Ok((format, parquet))
+}
+
 /// Handle LOAD command
 /// Syntax: LOAD DATASET dataset_name FROM "path"
-///         LOAD TENSOR tensor_name FROM "path"
+///         LOAD DATASET dataset_name FROM "file.csv" [DELIMITER ","] [HEADER | NO HEADER]
+///         LOAD TENSOR tensor_name FROM "path"   (or "file.npy")
 pub fn handle_load(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
     let rest = line.strip_prefix("LOAD ").unwrap().trim();
 
@@ -136,24 +475,50 @@ fn handle_load_dataset(
     // Check for " FROM " keyword
     let (dataset_name, path) = if let Some(idx) = rest.find(" FROM ") {
         let name = rest[..idx].trim();
-        let p = rest[idx + 6..].trim().trim_matches('"').to_string();
-        (name, p)
+        (name, Some(rest[idx + 6..].trim().to_string()))
     } else {
-        // Default path: data_dir / active_db
-        let mut p = db.config.storage.data_dir.clone();
-        p.push(&db.active_instance().name);
-        (rest, p.to_string_lossy().into_owned())
+        (rest, None)
     };
 
-    // Load from storage
-    let storage = ParquetStorage::new(&path);
-    let dataset = storage
-        .load_dataset(dataset_name)
-        .map_err(|e| DslError::Parse {
-            line: line_no,
-            msg: format!("Failed to load dataset: {}", e),
-        })?;
+    let csv_source = path.as_deref().filter(|p| is_csv_source(p));
+    let (dataset, source, report) = if let Some(path) = csv_source {
+        let (file, options) = parse_csv_options(path, line_no)?;
+        let (dataset, report) =
+            read_csv_dataset(dataset_name, &file, &options).map_err(|e| DslError::Parse {
+                line: line_no,
+                msg: format!("Failed to load CSV '{}': {}", file, e),
+            })?;
+        (dataset, file, Some(report))
+    } else {
+        // Load from storage
+        let path = path.map(|p| p.trim_matches('"').to_string());
+        let (storage, path) = open_storage(db, path, line_no)?;
+        let dataset = storage
+            .load_dataset(dataset_name)
+            .map_err(|e| DslError::Parse {
+                line: line_no,
+                msg: format!("Failed to load dataset: {}", e),
+            })?;
+        (dataset, path, None)
+    };
+
+    let output = insert_loaded_dataset(db, dataset_name, dataset, &source, line_no)?;
+    Ok(match (output, report) {
+        (DslOutput::Message(msg), Some(report)) => {
+            DslOutput::Message(format!("{}\n{}", msg, report))
+        }
+        (output, _) => output,
+    })
+}
 
+/// Register a dataset read from disk under `dataset_name`, restoring its indices
+fn insert_loaded_dataset(
+    db: &mut TensorDb,
+    dataset_name: &str,
+    dataset: crate::core::dataset_legacy::Dataset,
+    path: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
     // Insert into DB
     // We explicitly insert the dataset. create_dataset usually takes name+schema.
     // But we have a full dataset. We need a way to insert a full dataset or insert it via crate::core::store
@@ -197,10 +562,18 @@ fn handle_load_dataset(
     }
 
     let row_count = dataset.len();
+    let indices = dataset.indices;
 
     // Insert rows
-    for row in dataset.rows {
-        db.insert_row(dataset_name, row)
+    db.insert_rows(dataset_name, dataset.rows)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+
+    // Restore persisted indices (row ids match the insertion order above)
+    for (column, index) in indices {
+        db.restore_index(dataset_name, &column, index)
             .map_err(|e| DslError::Engine {
                 line: line_no,
                 source: e,
@@ -213,6 +586,272 @@ fn handle_load_dataset(
     )))
 }
 
+/// Options of `LOAD DATASET name FROM "file.csv" [DELIMITER ";"] [HEADER | NO HEADER]`
+#[derive(Debug, Default)]
+struct CsvOptions {
+    delimiter: Option<u8>,
+    /// None: detect whether the first row is a header
+    header: Option<bool>,
+}
+
+/// A LOAD source is read as CSV when its file name ends in `.csv`
+fn is_csv_source(source: &str) -> bool {
+    source
+        .trim_start_matches('"')
+        .split('"')
+        .next()
+        .is_some_and(|file| file.to_lowercase().ends_with(".csv"))
+}
+
+fn parse_csv_options(source: &str, line_no: usize) -> Result<(String, CsvOptions), DslError> {
+    let syntax_err = || DslError::Parse {
+        line: line_no,
+        msg: "Expected: LOAD DATASET name FROM \"file.csv\" [DELIMITER \",\"] [HEADER | NO HEADER]"
+            .to_string(),
+    };
+
+    let (file, mut rest) = match source.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"').ok_or_else(syntax_err)?,
+        None => source.split_once(' ').unwrap_or((source, "")),
+    };
+
+    let mut options = CsvOptions::default();
+    loop {
+        rest = rest.trim();
+        if rest.is_empty() {
+            break;
+        } else if let Some(r) = rest.strip_prefix("DELIMITER ") {
+            let r = r.trim_start();
+            let quote = r.chars().next().filter(|c| *c == '"' || *c == '\'');
+            let quote = quote.ok_or_else(syntax_err)?;
+            let (delim, r) = r[1..].split_once(quote).ok_or_else(syntax_err)?;
+            let delim = match delim {
+                "\\t" => b'\t',
+                d if d.len() == 1 => d.as_bytes()[0],
+                _ => {
+                    return Err(DslError::Parse {
+                        line: line_no,
+                        msg: format!("DELIMITER must be a single character, got '{}'", delim),
+                    })
+                }
+            };
+            options.delimiter = Some(delim);
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("NO HEADER") {
+            options.header = Some(false);
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("HEADER") {
+            options.header = Some(true);
+            rest = r;
+        } else {
+            return Err(syntax_err());
+        }
+    }
+
+    Ok((file.to_string(), options))
+}
+
+/// Read a CSV file into a dataset, inferring one of Int, Float, Bool,
+/// Timestamp (ISO 8601), Vector (JSON arrays such as `"[0.1, 0.2]"`) or
+/// String per column. Empty cells become NULL and make the column nullable.
+/// Also returns the inference report shown by LOAD.
+fn read_csv_dataset(
+    name: &str,
+    file: &str,
+    options: &CsvOptions,
+) -> Result<(crate::core::dataset_legacy::Dataset, String), String> {
+    use crate::core::dataset_legacy::{Dataset, DatasetId};
+    use crate::core::tuple::{Field, Schema, Tuple};
+    use std::sync::Arc;
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(options.delimiter.unwrap_or(b','))
+        .from_path(file)
+        .map_err(|e| e.to_string())?;
+
+    let mut records: Vec<Vec<String>> = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        records.push(record.iter().map(|f| f.trim().to_string()).collect());
+    }
+    if records.is_empty() {
+        return Err("file is empty".to_string());
+    }
+
+    let has_header = options
+        .header
+        .unwrap_or_else(|| looks_like_header(&records));
+    let names: Vec<String> = if has_header {
+        records.remove(0)
+    } else {
+        (1..=records[0].len()).map(|i| format!("col{}", i)).collect()
+    };
+
+    let mut report = String::from("Inferred schema:");
+    let mut fields = Vec::with_capacity(names.len());
+    for (i, col) in names.iter().enumerate() {
+        let cells = records.iter().map(|r| r.get(i).map_or("", String::as_str));
+        let inference = infer_csv_column(cells.clone());
+        let mut field = Field::new(col.clone(), inference.value_type);
+        if cells.clone().any(str::is_empty) {
+            field = field.nullable();
+        }
+        report.push_str(&format!("\n  {}: {}", col, field.value_type));
+        if field.nullable {
+            report.push_str(" (nullable)");
+        }
+        if let Some(ambiguity) = inference.ambiguity {
+            report.push_str(&format!(" -- ambiguous: {}", ambiguity));
+        }
+        fields.push(field);
+    }
+    let schema = Arc::new(Schema::new(fields));
+
+    let mut rows = Vec::with_capacity(records.len());
+    for (row_no, record) in records.iter().enumerate() {
+        let values = record
+            .iter()
+            .zip(&schema.fields)
+            .map(|(cell, field)| parse_csv_cell(cell, &field.value_type))
+            .collect();
+        let tuple = Tuple::new(schema.clone(), values)
+            .map_err(|e| format!("row {}: {}", row_no + 1, e))?;
+        rows.push(tuple);
+    }
+
+    let dataset = Dataset::with_rows(DatasetId(0), schema, rows, Some(name.to_string()))?;
+    Ok((dataset, report))
+}
+
+/// The first row is a header when none of its cells parse as a typed value
+/// and every cell is distinct and non-empty
+fn looks_like_header(records: &[Vec<String>]) -> bool {
+    use crate::core::value::ValueType;
+
+    let first = &records[0];
+    let all_text = first
+        .iter()
+        .all(|c| !c.is_empty() && infer_csv_type(std::iter::once(c.as_str())) == ValueType::String);
+    let distinct = first
+        .iter()
+        .enumerate()
+        .all(|(i, c)| !first[..i].contains(c));
+    all_text && distinct
+}
+
+fn infer_csv_type<'a>(cells: impl Iterator<Item = &'a str>) -> crate::core::value::ValueType {
+    infer_csv_column(cells).value_type
+}
+
+/// Type inferred for a CSV column
+struct ColumnInference {
+    value_type: crate::core::value::ValueType,
+    /// Why the column was read as String although some of its cells look typed
+    ambiguity: Option<String>,
+}
+
+fn infer_csv_column<'a>(cells: impl Iterator<Item = &'a str>) -> ColumnInference {
+    use crate::core::value::ValueType;
+
+    let settled = |value_type| ColumnInference {
+        value_type,
+        ambiguity: None,
+    };
+    let ambiguous = |ambiguity: String| ColumnInference {
+        value_type: ValueType::String,
+        ambiguity: Some(ambiguity),
+    };
+
+    let cells: Vec<&str> = cells.filter(|c| !c.is_empty()).collect();
+    if cells.is_empty() {
+        return settled(ValueType::String);
+    }
+    let is_bool = |c: &str| c.eq_ignore_ascii_case("true") || c.eq_ignore_ascii_case("false");
+    let ints: Vec<_> = cells.iter().map(|c| parse_int(c)).collect();
+    let dims: Vec<Option<usize>> = cells
+        .iter()
+        .map(|c| parse_json_vector(c).map(|v| v.len()))
+        .collect();
+
+    if ints.iter().all(|i| matches!(i, Some(Ok(_)))) {
+        settled(ValueType::Int)
+    } else if ints.iter().all(Option::is_some) {
+        // Ids past the i64 range stay exact as text rather than becoming floats
+        ambiguous("integers outside the Int range".to_string())
+    } else if cells.iter().all(|c| c.parse::<f64>().is_ok()) {
+        settled(ValueType::Float)
+    } else if cells.iter().all(|c| is_bool(c)) {
+        settled(ValueType::Bool)
+    } else if cells.iter().all(|c| parse_timestamp(c).is_some()) {
+        settled(ValueType::Timestamp)
+    } else if dims.iter().all(Option::is_some) {
+        let mut distinct: Vec<usize> = dims.into_iter().flatten().collect();
+        distinct.sort_unstable();
+        distinct.dedup();
+        match distinct[..] {
+            [dim] => settled(ValueType::Vector(dim)),
+            _ => ambiguous(format!(
+                "vectors of dimensions {}",
+                distinct
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    } else {
+        // Name the typed reading most cells agree with, and a cell that breaks it
+        let is_number = |c: &str| c.parse::<f64>().is_ok();
+        let readings = [
+            ("numbers", is_number as fn(&str) -> bool),
+            ("BOOL", is_bool),
+            ("TIMESTAMP", |c| parse_timestamp(c).is_some()),
+            ("VECTOR", |c| parse_json_vector(c).is_some()),
+        ];
+        let (label, matches) = readings
+            .iter()
+            .max_by_key(|(_, matches)| cells.iter().filter(|c| matches(c)).count())
+            .unwrap();
+        let count = cells.iter().filter(|c| matches(c)).count();
+        if count == 0 {
+            return settled(ValueType::String);
+        }
+        let other = cells.iter().find(|c| !matches(c)).unwrap();
+        ambiguous(format!(
+            "{} of {} values are {}, but not '{}'",
+            count,
+            cells.len(),
+            label,
+            other
+        ))
+    }
+}
+
+fn parse_json_vector(cell: &str) -> Option<Vec<f32>> {
+    if !cell.starts_with('[') {
+        return None;
+    }
+    serde_json::from_str(cell).ok()
+}
+
+fn parse_csv_cell(cell: &str, value_type: &crate::core::value::ValueType) -> Value {
+    use crate::core::value::ValueType;
+
+    if cell.is_empty() {
+        return Value::Null;
+    }
+    // Types were inferred from these same cells, so parsing cannot fail
+    match value_type {
+        ValueType::Int => Value::Int(cell.parse().unwrap()),
+        ValueType::Float => Value::Float(cell.parse().unwrap()),
+        ValueType::Bool => Value::Bool(cell.eq_ignore_ascii_case("true")),
+        ValueType::Timestamp => Value::Timestamp(parse_timestamp(cell).unwrap()),
+        ValueType::Vector(_) => Value::Vector(parse_json_vector(cell).unwrap()),
+        _ => Value::String(cell.to_string()),
+    }
+}
+
 fn handle_load_tensor(
     db: &mut TensorDb,
     rest: &str,
@@ -224,22 +863,22 @@ fn handle_load_tensor(
     let (tensor_name, path) = if let Some(idx) = rest.find(" FROM ") {
         let name = rest[..idx].trim();
         let p = rest[idx + 6..].trim().trim_matches('"').to_string();
-        (name, p)
+        (name, Some(p))
     } else {
-        // Default path: data_dir / active_db
-        let mut p = db.config.storage.data_dir.clone();
-        p.push(&db.active_instance().name);
-        (rest, p.to_string_lossy().into_owned())
+        (rest, None)
     };
 
-    // Load using storage engine
-    let storage = ParquetStorage::new(&path);
-    let tensor = storage
-        .load_tensor(tensor_name)
-        .map_err(|e| DslError::Parse {
-            line: line_no,
-            msg: format!("Failed to load tensor: {}", e),
-        })?;
+    let (loaded, path) = match path {
+        Some(path) if is_npy_path(&path) => (read_npy(std::path::Path::new(&path)), path),
+        path => {
+            let (storage, path) = open_storage(db, path, line_no)?;
+            (storage.load_tensor(tensor_name), path)
+        }
+    };
+    let tensor = loaded.map_err(|e| DslError::Parse {
+        line: line_no,
+        msg: format!("Failed to load tensor: {}", e),
+    })?;
 
     // Insert into db
     // We create a new tensor with a new ID in the current DB, but reuse shape and data
@@ -284,20 +923,11 @@ fn handle_list_datasets_impl(
 ) -> Result<DslOutput, DslError> {
     let rest = rest.strip_prefix("DATASETS").unwrap().trim();
 
-    let path = if rest.starts_with("FROM ") {
-        rest.strip_prefix("FROM ")
-            .unwrap()
-            .trim()
-            .trim_matches('"')
-            .to_string()
-    } else {
-        // Default path: data_dir / active_db
-        let mut p = _db.config.storage.data_dir.clone();
-        p.push(&_db.active_instance().name);
-        p.to_string_lossy().into_owned()
-    };
+    let path = rest
+        .strip_prefix("FROM ")
+        .map(|p| p.trim().trim_matches('"').to_string());
 
-    let storage = ParquetStorage::new(&path);
+    let (storage, path) = open_storage(_db, path, line_no)?;
     let datasets = storage.list_datasets().map_err(|e| DslError::Parse {
         line: line_no,
         msg: format!("Failed to list datasets: {}", e),
@@ -319,20 +949,11 @@ fn handle_list_tensors_impl(
 ) -> Result<DslOutput, DslError> {
     let rest = rest.strip_prefix("TENSORS").unwrap().trim();
 
-    let path = if rest.starts_with("FROM ") {
-        rest.strip_prefix("FROM ")
-            .unwrap()
-            .trim()
-            .trim_matches('"')
-            .to_string()
-    } else {
-        // Default path: data_dir / active_db
-        let mut p = _db.config.storage.data_dir.clone();
-        p.push(&_db.active_instance().name);
-        p.to_string_lossy().into_owned()
-    };
+    let path = rest
+        .strip_prefix("FROM ")
+        .map(|p| p.trim().trim_matches('"').to_string());
 
-    let storage = ParquetStorage::new(&path);
+    let (storage, path) = open_storage(_db, path, line_no)?;
     let tensors = storage.list_tensors().map_err(|e| DslError::Parse {
         line: line_no,
         msg: format!("Failed to list tensors: {}", e),
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use crate::core::dataset_legacy::{Dataset, DatasetId};
+use crate::core::tuple::{Schema, Tuple};
+use crate::dsl::DslError;
+use crate::utils::parsing::unquoted_char_indices;
+
+/// Split off a trailing `RETURNING col, ...` (parentheses optional, `*` for
+/// every column), returning the remaining line. Occurrences inside string
+/// literals are ignored.
+pub fn split_returning(
+    line: &str,
+    line_no: usize,
+) -> Result<(&str, Option<Vec<String>>), DslError> {
+    const KEYWORD: &str = " RETURNING ";
+    let found = unquoted_char_indices(line)
+        .into_iter()
+        .rev()
+        .find(|&(idx, _)| line[idx..].starts_with(KEYWORD));
+    let Some((idx, _)) = found else {
+        return Ok((line, None));
+    };
+
+    let list = line[idx + KEYWORD.len()..].trim();
+    let list = list
+        .strip_prefix('(')
+        .and_then(|l| l.strip_suffix(')'))
+        .unwrap_or(list);
+    let columns: Vec<String> = list
+        .split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+    if columns.is_empty() {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: "RETURNING needs at least one column".into(),
+        });
+    }
+    Ok((&line[..idx], Some(columns)))
+}
+
+/// Result table with `columns` of `rows` (all of them for `*`), in the requested order
+pub fn returning_table(
+    name: &str,
+    schema: &Schema,
+    rows: &[Tuple],
+    columns: &[String],
+    line_no: usize,
+) -> Result<Dataset, DslError> {
+    let positions: Vec<usize> = if columns.len() == 1 && columns[0] == "*" {
+        (0..schema.len()).collect()
+    } else {
+        columns
+            .iter()
+            .map(|c| {
+                schema
+                    .fields
+                    .iter()
+                    .position(|f| &f.name == c)
+                    .ok_or_else(|| DslError::Parse {
+                        line: line_no,
+                        msg: format!("RETURNING column '{}' not found in '{}'", c, name),
+                    })
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    let projected = Arc::new(Schema::new(
+        positions
+            .iter()
+            .map(|&i| schema.fields[i].clone())
+            .collect(),
+    ));
+    let rows = rows
+        .iter()
+        .map(|row| {
+            let values = positions.iter().map(|&i| row.values[i].clone()).collect();
+            Tuple::new(projected.clone(), values)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|msg| DslError::Parse { line: line_no, msg })?;
+
+    Dataset::with_rows(DatasetId(0), projected, rows, Some(name.to_string()))
+        .map_err(|msg| DslError::Parse { line: line_no, msg })
+}
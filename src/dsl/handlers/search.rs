@@ -2,29 +2,39 @@ use crate::core::value::Value;
 use crate::dsl::{DslError, DslOutput};
 use crate::engine::TensorDb;
 use crate::query::logical::LogicalPlan;
-use crate::query::planner::Planner;
+use crate::utils::parsing::parse_string_literal;
 
 use super::dataset::parse_single_value;
+use super::returning::returning_table;
 
 /// SEARCH target FROM source QUERY vector ON column K=k
 /// SEARCH target FROM source QUERY vector ON column K=k
 /// OR simplified: SEARCH source WHERE column ~= vector LIMIT k
+/// Either form accepts a trailing
+///   RERANK USING SERVICE "name" ON text_column QUERY "query text"
+/// and a final RETURNING (col, ...) that answers with those columns of the hits
 pub fn handle_search(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
+    let (line, returning) = split_returning_clause(line, line_no)?;
     let (target_name, plan) = build_search_query_plan(db, line, line_no)?;
 
     // Execute Plan
-    let planner = Planner::new(db);
-    let physical_plan = planner
-        .create_physical_plan(&plan)
-        .map_err(|e| DslError::Engine {
-            line: line_no,
-            source: e,
-        })?;
-    let result_rows = physical_plan.execute(db).map_err(|e| DslError::Engine {
+    let result = db.execute_plan(&plan).map_err(|e| DslError::Engine {
         line: line_no,
         source: e,
     })?;
-    let result_schema = physical_plan.schema();
+    let returned = match &returning {
+        Some(columns) => Some(returning_table(
+            result.metadata.name.as_deref().unwrap_or("search results"),
+            &result.schema,
+            &result.rows,
+            columns,
+            line_no,
+        )?),
+        None => None,
+    };
+    let result_schema = result.schema;
+    let result_rows = result.rows;
+    let result_count = result_rows.len();
 
     // Create target dataset
     let final_target = target_name.unwrap_or_else(|| "search_results".to_string());
@@ -69,21 +79,57 @@ pub fn handle_search(db: &mut TensorDb, line: &str, line_no: usize) -> Result<Ds
         ds.metadata.update_stats(&ds.schema, &ds.rows);
     }
 
+    if let Some(hits) = returned {
+        return Ok(DslOutput::Table(hits));
+    }
     Ok(DslOutput::Message(format!(
         "Search completed. Found {} results in '{}'.",
-        physical_plan.execute(db).unwrap().len(), // Re-executing is bad but rows moved? No rows cloned?
-        // rows was consumed by line 130?
-        // Wait, physical_plan.execute returns Vec<Tuple>.
-        // I used `result_rows` above.
+        result_count,
         final_target
     )))
 }
 
+/// Split off a trailing `RETURNING (col, ...)`, returning the remaining line
+fn split_returning_clause(
+    line: &str,
+    line_no: usize,
+) -> Result<(&str, Option<Vec<String>>), DslError> {
+    let Some(idx) = line.rfind(" RETURNING ") else {
+        return Ok((line, None));
+    };
+    let list = line[idx + 11..].trim();
+    let columns: Vec<String> = list
+        .strip_prefix('(')
+        .and_then(|l| l.strip_suffix(')'))
+        .ok_or_else(|| DslError::Parse {
+            line: line_no,
+            msg: "Expected: RETURNING (col1, col2, ...)".into(),
+        })?
+        .split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+    if columns.is_empty() {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: "RETURNING needs at least one column".into(),
+        });
+    }
+    Ok((&line[..idx], Some(columns)))
+}
+
 pub fn build_search_query_plan(
     db: &mut TensorDb,
     line: &str,
     line_no: usize,
 ) -> Result<(Option<String>, LogicalPlan), DslError> {
+    let (line, rerank) = match line.find(" RERANK ") {
+        Some(idx) => (
+            &line[..idx],
+            Some(parse_rerank_clause(line[idx + 8..].trim(), line_no)?),
+        ),
+        None => (line, None),
+    };
     let rest = line.trim_start_matches("SEARCH").trim();
 
     // Check syntax: FROM vs WHERE
@@ -160,7 +206,7 @@ pub fn build_search_query_plan(
             query_tensor,
             k,
         );
-        Ok((Some(target_name), plan))
+        Ok((Some(target_name), with_rerank(plan, rerank)))
     } else {
         // Simplified Syntax: SEARCH source WHERE col ~= vector LIMIT k
         // source is rest split by " WHERE "
@@ -232,7 +278,55 @@ pub fn build_search_query_plan(
             query_tensor,
             k,
         );
-        Ok((None, plan))
+        Ok((None, with_rerank(plan, rerank)))
+    }
+}
+
+/// Parsed `RERANK USING SERVICE "name" ON column QUERY "text"`
+struct RerankClause {
+    service: String,
+    column: String,
+    query: String,
+}
+
+fn parse_rerank_clause(s: &str, line_no: usize) -> Result<RerankClause, DslError> {
+    let syntax_error = || DslError::Parse {
+        line: line_no,
+        msg: "Expected: RERANK USING SERVICE \"name\" ON <column> QUERY \"text\"".into(),
+    };
+
+    let rest = s.strip_prefix("USING SERVICE ").ok_or_else(syntax_error)?.trim();
+    let rest = rest.strip_prefix('"').ok_or_else(syntax_error)?;
+    let end = rest.find('"').ok_or_else(syntax_error)?;
+    let service = rest[..end].to_string();
+
+    let rest = rest[end + 1..].trim();
+    let rest = rest.strip_prefix("ON ").ok_or_else(syntax_error)?;
+    let (column, query) = rest.split_once(" QUERY ").ok_or_else(syntax_error)?;
+    let query = match parse_string_literal(query.trim()) {
+        Some(literal) => literal.map_err(|msg| DslError::Parse { line: line_no, msg })?,
+        None => return Err(syntax_error()),
+    };
+    if service.is_empty() {
+        return Err(syntax_error());
+    }
+
+    Ok(RerankClause {
+        service,
+        column: column.trim().to_string(),
+        query,
+    })
+}
+
+fn with_rerank(plan: LogicalPlan, rerank: Option<RerankClause>) -> LogicalPlan {
+    match rerank {
+        Some(r) => LogicalPlan::Rerank {
+            input: Box::new(plan),
+            service: r.service,
+            column: r.column,
+            query: r.query,
+        },
+        None => plan,
     }
 }
 
@@ -0,0 +1,844 @@
+use std::sync::Arc;
+
+use sqlparser::ast as sql;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+
+use crate::core::tuple::{Field, Schema, Tuple};
+use crate::core::value::{Value, ValueType};
+use crate::dsl::handlers::dataset::build_clause_plan;
+use crate::dsl::handlers::persistence::auto_persist_dataset;
+use crate::dsl::handlers::returning::returning_table;
+use crate::dsl::parser::QueryClauses;
+use crate::dsl::{DslError, DslOutput};
+use crate::engine::TensorDb;
+use crate::query::logical::{AggregateFunction, Expr, LogicalPlan, ScalarFunction};
+use crate::utils::parsing::{parse_int, parse_interval, parse_timestamp};
+
+/// Handle SQL command
+/// Syntax: SQL <statement>, where statement is one of
+///   CREATE TABLE name (col TYPE [NOT NULL | PRIMARY KEY], ...)   -- TYPE may be VECTOR(n)
+///   INSERT INTO name [(col, ...)] VALUES (...), (...) [RETURNING col, ... | *]
+///   SELECT cols FROM name [WHERE ...] [GROUP BY ...] [HAVING ...] [ORDER BY col [DESC]] [LIMIT n]
+pub fn handle_sql(db: &mut TensorDb, line: &str, line_no: usize) -> Result<DslOutput, DslError> {
+    let text = line.strip_prefix("SQL ").unwrap_or(line).trim();
+    let mut statements =
+        Parser::parse_sql(&GenericDialect {}, text).map_err(|e| parse_err(line_no, e))?;
+    if statements.len() != 1 {
+        return Err(parse_err(line_no, "Expected exactly one SQL statement"));
+    }
+
+    match statements.remove(0) {
+        sql::Statement::CreateTable(create) => create_table(db, create, line_no),
+        sql::Statement::Insert(insert) => insert_rows(db, insert, line_no),
+        sql::Statement::Query(query) => {
+            let plan = query_plan(db, *query, line_no)?;
+            let ds = db.execute_plan(&plan).map_err(|e| DslError::Engine {
+                line: line_no,
+                source: e,
+            })?;
+            Ok(DslOutput::Table(ds))
+        }
+        other => Err(parse_err(
+            line_no,
+            format!("Unsupported SQL statement: {}", other),
+        )),
+    }
+}
+
+/// True for SQL statements that modify the active database
+pub fn is_mutating_sql(line: &str) -> bool {
+    let Some(text) = line.strip_prefix("SQL ") else {
+        return false;
+    };
+    let upper = text.trim_start().to_uppercase();
+    upper.starts_with("CREATE ") || upper.starts_with("INSERT ")
+}
+
+fn parse_err(line_no: usize, msg: impl ToString) -> DslError {
+    DslError::Parse {
+        line: line_no,
+        msg: msg.to_string(),
+    }
+}
+
+fn table_name(name: &sql::ObjectName, line_no: usize) -> Result<String, DslError> {
+    match name.0.as_slice() {
+        [ident] => Ok(ident.value.clone()),
+        _ => Err(parse_err(
+            line_no,
+            format!("Qualified table names are not supported: {}", name),
+        )),
+    }
+}
+
+fn create_table(
+    db: &mut TensorDb,
+    create: sql::CreateTable,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let name = table_name(&create.name, line_no)?;
+    if create.if_not_exists && db.get_dataset(&name).is_ok() {
+        return Ok(DslOutput::Message(format!(
+            "Dataset '{}' already exists",
+            name
+        )));
+    }
+    if create.columns.is_empty() {
+        return Err(parse_err(
+            line_no,
+            "CREATE TABLE requires at least one column",
+        ));
+    }
+
+    let multi_column = create.constraints.iter().any(|c| match c {
+        sql::TableConstraint::PrimaryKey { columns, .. }
+        | sql::TableConstraint::Unique { columns, .. } => columns.len() > 1,
+        _ => false,
+    });
+    if multi_column {
+        return Err(parse_err(
+            line_no,
+            "Only single-column PRIMARY KEY and UNIQUE constraints are supported",
+        ));
+    }
+
+    let mut fields = Vec::with_capacity(create.columns.len());
+    for column in &create.columns {
+        let value_type = column_type(&column.data_type, line_no)?;
+        // Column options, then single-column table constraints
+        let table_constraint = |primary: bool| {
+            create.constraints.iter().any(|c| match c {
+                sql::TableConstraint::PrimaryKey { columns, .. } => {
+                    primary && columns.len() == 1 && columns[0].value == column.name.value
+                }
+                sql::TableConstraint::Unique { columns, .. } => {
+                    !primary && columns.len() == 1 && columns[0].value == column.name.value
+                }
+                _ => false,
+            })
+        };
+        let primary_key = table_constraint(true)
+            || column.options.iter().any(|o| {
+                matches!(
+                    o.option,
+                    sql::ColumnOption::Unique {
+                        is_primary: true,
+                        ..
+                    }
+                )
+            });
+        let unique = table_constraint(false)
+            || column.options.iter().any(|o| {
+                matches!(
+                    o.option,
+                    sql::ColumnOption::Unique {
+                        is_primary: false,
+                        ..
+                    }
+                )
+            });
+        let not_null = column
+            .options
+            .iter()
+            .any(|o| matches!(o.option, sql::ColumnOption::NotNull));
+        let field = Field::new(column.name.value.clone(), value_type);
+        let field = if unique { field.unique() } else { field };
+        fields.push(if primary_key {
+            field.primary_key()
+        } else if not_null {
+            field
+        } else {
+            field.nullable()
+        });
+    }
+    if fields.iter().filter(|f| f.primary_key).count() > 1 {
+        return Err(parse_err(
+            line_no,
+            "Only one PRIMARY KEY column is supported",
+        ));
+    }
+
+    db.create_dataset(name.clone(), Arc::new(Schema::new(fields)))
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+    auto_persist_dataset(db, &name, line_no)?;
+
+    Ok(DslOutput::Message(format!("Created dataset: {}", name)))
+}
+
+/// Map a SQL column type onto a ValueType. VECTOR(n) is the vector extension.
+fn column_type(data_type: &sql::DataType, line_no: usize) -> Result<ValueType, DslError> {
+    use sql::DataType as T;
+    match data_type {
+        T::Int(_) | T::Integer(_) | T::BigInt(_) | T::SmallInt(_) | T::Int32 | T::Int64 => {
+            Ok(ValueType::Int)
+        }
+        T::Float(_)
+        | T::Real
+        | T::Double
+        | T::DoublePrecision
+        | T::Float4
+        | T::Float8
+        | T::Float32
+        | T::Float64 => Ok(ValueType::Float),
+        T::Text | T::Varchar(_) | T::Char(_) | T::String(_) => Ok(ValueType::String),
+        T::Bool | T::Boolean => Ok(ValueType::Bool),
+        T::Timestamp(_, _) | T::Datetime(_) => Ok(ValueType::Timestamp),
+        T::JSON | T::JSONB => Ok(ValueType::Json),
+        // INT[], ARRAY<TEXT> or ARRAY(INT): a list of scalars
+        T::Array(
+            sql::ArrayElemTypeDef::AngleBracket(elem)
+            | sql::ArrayElemTypeDef::SquareBracket(elem, _)
+            | sql::ArrayElemTypeDef::Parenthesis(elem),
+        ) => match column_type(elem, line_no)? {
+            ValueType::Vector(_) | ValueType::Matrix(_, _) | ValueType::List(_) => Err(parse_err(
+                line_no,
+                format!("Unsupported array element type: {}", elem),
+            )),
+            elem => Ok(ValueType::List(Box::new(elem))),
+        },
+        T::Custom(name, args) if name.to_string().eq_ignore_ascii_case("VECTOR") => {
+            match args.as_slice() {
+                [dim] => dim
+                    .parse()
+                    .map(ValueType::Vector)
+                    .map_err(|_| parse_err(line_no, format!("Invalid VECTOR dimension: {}", dim))),
+                _ => Err(parse_err(line_no, "Expected VECTOR(n)")),
+            }
+        }
+        other => Err(parse_err(
+            line_no,
+            format!("Unsupported column type: {}", other),
+        )),
+    }
+}
+
+fn insert_rows(
+    db: &mut TensorDb,
+    insert: sql::Insert,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let name = table_name(&insert.table_name, line_no)?;
+    let schema = db
+        .get_dataset(&name)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?
+        .schema
+        .clone();
+
+    let rows = match insert.source.map(|q| *q.body) {
+        Some(sql::SetExpr::Values(values)) => values.rows,
+        _ => {
+            return Err(parse_err(
+                line_no,
+                "Expected: INSERT INTO name [(cols)] VALUES (...)",
+            ))
+        }
+    };
+
+    // Position of each schema field within a VALUES row (None -> NULL)
+    let positions: Vec<Option<usize>> = if insert.columns.is_empty() {
+        (0..schema.len()).map(Some).collect()
+    } else {
+        for column in &insert.columns {
+            if schema.get_field(&column.value).is_none() {
+                return Err(parse_err(
+                    line_no,
+                    format!("Unknown column '{}' in dataset '{}'", column.value, name),
+                ));
+            }
+        }
+        schema
+            .fields
+            .iter()
+            .map(|f| insert.columns.iter().position(|c| c.value == f.name))
+            .collect()
+    };
+    let returning = match &insert.returning {
+        Some(items) => Some(returning_columns(items, line_no)?),
+        None => None,
+    };
+    if let Some(columns) = &returning {
+        returning_table(&name, &schema, &[], columns, line_no)?;
+    }
+
+    let width = if insert.columns.is_empty() {
+        schema.len()
+    } else {
+        insert.columns.len()
+    };
+
+    // Build every tuple first so a bad row leaves the dataset untouched
+    let mut tuples = Vec::with_capacity(rows.len());
+    for row in &rows {
+        if row.len() != width {
+            return Err(parse_err(
+                line_no,
+                format!("Expected {} values, got {}", width, row.len()),
+            ));
+        }
+        let mut values = Vec::with_capacity(schema.len());
+        for (field, pos) in schema.fields.iter().zip(&positions) {
+            let value = match pos {
+                Some(i) => literal_value(&row[*i], line_no)?,
+                None => Value::Null,
+            };
+            values.push(coerce(value, &field.value_type));
+        }
+        tuples.push(Tuple::new(schema.clone(), values).map_err(|e| parse_err(line_no, e))?);
+    }
+
+    let count = tuples.len();
+    db.insert_rows(&name, tuples)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+    auto_persist_dataset(db, &name, line_no)?;
+
+    if let Some(columns) = returning {
+        let dataset = db.get_dataset(&name).map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?;
+        let inserted = &dataset.rows[dataset.rows.len() - count..];
+        let table = returning_table(&name, &schema, inserted, &columns, line_no)?;
+        return Ok(DslOutput::Table(table));
+    }
+
+    Ok(DslOutput::Affected {
+        rows: count,
+        op: "INSERT".to_string(),
+    })
+}
+
+/// Column names of a RETURNING list; `*` stands for every column
+fn returning_columns(items: &[sql::SelectItem], line_no: usize) -> Result<Vec<String>, DslError> {
+    items
+        .iter()
+        .map(|item| match item {
+            sql::SelectItem::Wildcard(_) => Ok("*".to_string()),
+            sql::SelectItem::UnnamedExpr(sql::Expr::Identifier(ident)) => Ok(ident.value.clone()),
+            other => Err(parse_err(
+                line_no,
+                format!("RETURNING only supports column names, got: {}", other),
+            )),
+        })
+        .collect()
+}
+
+/// Implicit coercion to the column type (`Value::coerce_to`), e.g. integer
+/// literals for Float columns; anything else is left for the type check
+fn coerce(value: Value, target: &ValueType) -> Value {
+    value.coerce_to(target).unwrap_or(value)
+}
+
+fn literal_value(expr: &sql::Expr, line_no: usize) -> Result<Value, DslError> {
+    match expr {
+        sql::Expr::Value(sql::Value::Number(n, _)) => match parse_int(n) {
+            Some(parsed) => parsed
+                .map(Value::Int)
+                .map_err(|msg| parse_err(line_no, msg)),
+            None => n
+                .parse::<f32>()
+                .map(Value::Float)
+                .map_err(|_| parse_err(line_no, format!("Invalid number: {}", n))),
+        },
+        sql::Expr::Value(sql::Value::SingleQuotedString(s)) => Ok(Value::String(s.clone())),
+        sql::Expr::Value(sql::Value::Boolean(b)) => Ok(Value::Bool(*b)),
+        sql::Expr::Value(sql::Value::Null) => Ok(Value::Null),
+        sql::Expr::UnaryOp {
+            op: sql::UnaryOperator::Minus,
+            expr,
+        } => match literal_value(expr, line_no)? {
+            Value::Int(i) => Ok(Value::Int(-i)),
+            Value::Float(f) => Ok(Value::Float(-f)),
+            _ => Err(parse_err(line_no, format!("Invalid literal: {}", expr))),
+        },
+        sql::Expr::Nested(inner) => literal_value(inner, line_no),
+        sql::Expr::TypedString {
+            data_type: sql::DataType::Timestamp(_, _) | sql::DataType::Datetime(_),
+            value,
+        } => parse_timestamp(value)
+            .map(Value::Timestamp)
+            .ok_or_else(|| parse_err(line_no, format!("Invalid timestamp: '{}'", value))),
+        // Intervals are an Int of seconds
+        sql::Expr::Interval(interval) if interval.last_field.is_none() => {
+            let text = match literal_value(&interval.value, line_no)? {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            let text = match &interval.leading_field {
+                Some(unit) => format!("{} {}", text, unit),
+                None => text,
+            };
+            parse_interval(&text)
+                .map(Value::Int)
+                .map_err(|msg| parse_err(line_no, msg))
+        }
+        // A vector when every element is a number, otherwise a list
+        sql::Expr::Array(array) => {
+            let elems = array
+                .elem
+                .iter()
+                .map(|elem| literal_value(elem, line_no))
+                .collect::<Result<Vec<_>, _>>()?;
+            let floats: Option<Vec<f32>> = elems
+                .iter()
+                .map(|elem| match elem {
+                    Value::Int(i) => Some(*i as f32),
+                    Value::Float(f) => Some(*f),
+                    _ => None,
+                })
+                .collect();
+            if let Some(floats) = floats.filter(|f| !f.is_empty()) {
+                return Ok(Value::Vector(floats));
+            }
+            if elems
+                .iter()
+                .any(|e| matches!(e, Value::Vector(_) | Value::List(_)))
+            {
+                return Err(parse_err(
+                    line_no,
+                    format!("Nested arrays are not supported: {}", expr),
+                ));
+            }
+            Ok(Value::List(elems))
+        }
+        other => Err(parse_err(
+            line_no,
+            format!("Expected a literal, got: {}", other),
+        )),
+    }
+}
+
+fn query_plan(db: &TensorDb, query: sql::Query, line_no: usize) -> Result<LogicalPlan, DslError> {
+    if query.with.is_some() || query.offset.is_some() || query.fetch.is_some() {
+        return Err(parse_err(
+            line_no,
+            "WITH, OFFSET and FETCH are not supported in SQL mode",
+        ));
+    }
+    let select = match *query.body {
+        sql::SetExpr::Select(select) => *select,
+        other => return Err(parse_err(line_no, format!("Unsupported query: {}", other))),
+    };
+    let distinct = match &select.distinct {
+        None => false,
+        Some(sql::Distinct::Distinct) => true,
+        Some(sql::Distinct::On(_)) => {
+            return Err(parse_err(line_no, "SELECT DISTINCT ON is not supported"))
+        }
+    };
+
+    let source = match select.from.as_slice() {
+        [sql::TableWithJoins { relation, joins }] if joins.is_empty() => match relation {
+            sql::TableFactor::Table { name, .. } => table_name(name, line_no)?,
+            other => {
+                return Err(parse_err(
+                    line_no,
+                    format!("Unsupported FROM source: {}", other),
+                ))
+            }
+        },
+        _ => return Err(parse_err(line_no, "Expected a single table in FROM")),
+    };
+    let source_schema = db
+        .get_dataset(&source)
+        .map_err(|e| DslError::Engine {
+            line: line_no,
+            source: e,
+        })?
+        .schema
+        .clone();
+    let scan = LogicalPlan::Scan {
+        dataset_name: source,
+        schema: source_schema.clone(),
+    };
+
+    let mut exprs = Vec::with_capacity(select.projection.len());
+    for item in &select.projection {
+        match item {
+            sql::SelectItem::Wildcard(_) => exprs.push(Expr::Column("*".to_string())),
+            sql::SelectItem::UnnamedExpr(e) => exprs.push(convert_expr(e, line_no)?),
+            sql::SelectItem::ExprWithAlias { expr, alias } => exprs.push(Expr::Alias {
+                expr: Box::new(convert_expr(expr, line_no)?),
+                name: alias.value.clone(),
+            }),
+            other => {
+                return Err(parse_err(
+                    line_no,
+                    format!("Unsupported select item: {}", other),
+                ))
+            }
+        }
+    }
+
+    let mut clauses = QueryClauses {
+        distinct,
+        ..Default::default()
+    };
+    if let Some(selection) = &select.selection {
+        conjuncts(selection, line_no, &mut clauses.filters)?;
+    }
+    if let Some(having) = &select.having {
+        conjuncts(having, line_no, &mut clauses.having)?;
+    }
+    if let sql::GroupByExpr::Expressions(group_by, modifiers) = &select.group_by {
+        clauses.rollup = modifiers.contains(&sql::GroupByWithModifier::Rollup);
+        // GROUP BY ROLLUP(a, b) is GROUP BY a, b WITH ROLLUP
+        let group_by = match group_by.as_slice() {
+            [sql::Expr::Rollup(sets)] => {
+                clauses.rollup = true;
+                sets.iter()
+                    .map(|set| match set.as_slice() {
+                        [key] => Ok(key.clone()),
+                        _ => Err(parse_err(line_no, "ROLLUP takes one expression per key")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            keys => keys.to_vec(),
+        };
+        if !group_by.is_empty() {
+            clauses.group_by = Some(
+                group_by
+                    .iter()
+                    .map(|e| convert_expr(e, line_no))
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+    }
+    if let Some(order_by) = &query.order_by {
+        match order_by.exprs.as_slice() {
+            [] => {}
+            [item] => {
+                let column = aggregate_columns(convert_expr(&item.expr, line_no)?).output_name();
+                clauses.order_by = Some((column, item.asc.unwrap_or(true)));
+            }
+            _ => return Err(parse_err(line_no, "ORDER BY supports a single column")),
+        }
+    }
+    if let Some(limit) = &query.limit {
+        clauses.limit = match literal_value(limit, line_no)? {
+            Value::Int(n) if n >= 0 => Some(n as usize),
+            _ => return Err(parse_err(line_no, format!("Invalid LIMIT: {}", limit))),
+        };
+    }
+
+    build_clause_plan(scan, &source_schema, clauses, Some(exprs), line_no)
+}
+
+/// Split a predicate on AND into separate filters
+fn conjuncts(expr: &sql::Expr, line_no: usize, out: &mut Vec<Expr>) -> Result<(), DslError> {
+    match expr {
+        sql::Expr::BinaryOp {
+            left,
+            op: sql::BinaryOperator::And,
+            right,
+        } => {
+            conjuncts(left, line_no, out)?;
+            conjuncts(right, line_no, out)
+        }
+        sql::Expr::Nested(inner) => conjuncts(inner, line_no, out),
+        other => {
+            out.push(convert_expr(other, line_no)?);
+            Ok(())
+        }
+    }
+}
+
+fn convert_expr(expr: &sql::Expr, line_no: usize) -> Result<Expr, DslError> {
+    match expr {
+        sql::Expr::Identifier(ident) => Ok(Expr::Column(ident.value.clone())),
+        sql::Expr::Nested(inner) => convert_expr(inner, line_no),
+        sql::Expr::BinaryOp {
+            left,
+            op: op @ (sql::BinaryOperator::Arrow | sql::BinaryOperator::LongArrow),
+            right,
+        } => Ok(Expr::ScalarFunction {
+            func: if *op == sql::BinaryOperator::Arrow {
+                ScalarFunction::JsonGet
+            } else {
+                ScalarFunction::JsonGetText
+            },
+            args: vec![convert_expr(left, line_no)?, convert_expr(right, line_no)?],
+        }),
+        sql::Expr::BinaryOp { left, op, right } => {
+            let op = match op {
+                sql::BinaryOperator::Eq => "=",
+                sql::BinaryOperator::NotEq => "!=",
+                sql::BinaryOperator::Gt => ">",
+                sql::BinaryOperator::Lt => "<",
+                sql::BinaryOperator::GtEq => ">=",
+                sql::BinaryOperator::LtEq => "<=",
+                sql::BinaryOperator::Plus => "+",
+                sql::BinaryOperator::Minus => "-",
+                sql::BinaryOperator::Multiply => "*",
+                sql::BinaryOperator::Divide => "/",
+                sql::BinaryOperator::Modulo => "%",
+                sql::BinaryOperator::And => "AND",
+                sql::BinaryOperator::Or => "OR",
+                other => {
+                    return Err(parse_err(
+                        line_no,
+                        format!("Unsupported operator: {}", other),
+                    ))
+                }
+            };
+            Ok(Expr::BinaryExpr {
+                left: Box::new(convert_expr(left, line_no)?),
+                op: op.to_string(),
+                right: Box::new(convert_expr(right, line_no)?),
+            })
+        }
+        sql::Expr::UnaryOp {
+            op: sql::UnaryOperator::Not,
+            expr,
+        } => Ok(convert_expr(expr, line_no)?.negate()),
+        sql::Expr::Subscript { expr, subscript } => match subscript.as_ref() {
+            sql::Subscript::Index { index } => Ok(Expr::ScalarFunction {
+                func: ScalarFunction::ElementAt,
+                args: vec![convert_expr(expr, line_no)?, convert_expr(index, line_no)?],
+            }),
+            other => Err(parse_err(
+                line_no,
+                format!("Unsupported subscript: {}", other),
+            )),
+        },
+        sql::Expr::InList {
+            expr,
+            list,
+            negated,
+        } => {
+            let lhs = convert_expr(expr, line_no)?;
+            let mut alternatives = list
+                .iter()
+                .map(|item| Ok(binary(lhs.clone(), "=", convert_expr(item, line_no)?)));
+            let first = alternatives
+                .next()
+                .ok_or_else(|| parse_err(line_no, "Empty IN list"))??;
+            let any = alternatives.try_fold(first, |left, right: Result<Expr, DslError>| {
+                Ok::<_, DslError>(binary(left, "OR", right?))
+            })?;
+            Ok(if *negated { any.negate() } else { any })
+        }
+        sql::Expr::Between {
+            expr,
+            negated,
+            low,
+            high,
+        } => {
+            let lhs = convert_expr(expr, line_no)?;
+            let between = binary(
+                binary(lhs.clone(), ">=", convert_expr(low, line_no)?),
+                "AND",
+                binary(lhs, "<=", convert_expr(high, line_no)?),
+            );
+            Ok(if *negated { between.negate() } else { between })
+        }
+        sql::Expr::Like {
+            negated,
+            any: false,
+            expr,
+            pattern,
+            escape_char: None,
+        } => {
+            let like = binary(
+                convert_expr(expr, line_no)?,
+                "LIKE",
+                convert_expr(pattern, line_no)?,
+            );
+            Ok(if *negated { like.negate() } else { like })
+        }
+        sql::Expr::IsNull(inner) => Ok(binary(
+            convert_expr(inner, line_no)?,
+            "IS",
+            Expr::Literal(Value::Null),
+        )),
+        sql::Expr::IsNotNull(inner) => Ok(binary(
+            convert_expr(inner, line_no)?,
+            "IS NOT",
+            Expr::Literal(Value::Null),
+        )),
+        sql::Expr::Cast {
+            expr, data_type, ..
+        } => Ok(Expr::ScalarFunction {
+            func: ScalarFunction::Cast(column_type(data_type, line_no)?),
+            args: vec![convert_expr(expr, line_no)?],
+        }),
+        sql::Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            let operand = operand
+                .as_ref()
+                .map(|e| convert_expr(e, line_no))
+                .transpose()?;
+            let mut args = Vec::new();
+            for (condition, result) in conditions.iter().zip(results) {
+                let condition = convert_expr(condition, line_no)?;
+                args.push(match &operand {
+                    Some(lhs) => binary(lhs.clone(), "=", condition),
+                    None => condition,
+                });
+                args.push(convert_expr(result, line_no)?);
+            }
+            args.push(match else_result {
+                Some(e) => convert_expr(e, line_no)?,
+                None => Expr::Literal(Value::Null),
+            });
+            Ok(Expr::ScalarFunction {
+                func: ScalarFunction::Case,
+                args,
+            })
+        }
+        sql::Expr::Function(function) => {
+            let name = function.name.to_string().to_uppercase();
+            match name.as_str() {
+                "COALESCE" | "IFNULL" | "ELEMENT_AT" | "ARRAY_CONTAINS" | "UNNEST" => {
+                    convert_scalar(function, &name, line_no)
+                }
+                _ => convert_aggregate(function, line_no),
+            }
+        }
+        other => literal_value(other, line_no).map(Expr::Literal),
+    }
+}
+
+fn binary(left: Expr, op: &str, right: Expr) -> Expr {
+    Expr::BinaryExpr {
+        left: Box::new(left),
+        op: op.to_string(),
+        right: Box::new(right),
+    }
+}
+
+/// COALESCE(a, b, ...) and its two-argument form IFNULL(a, b), and the
+/// list functions ELEMENT_AT(list, i), ARRAY_CONTAINS(list, v) and UNNEST(list)
+fn convert_scalar(function: &sql::Function, name: &str, line_no: usize) -> Result<Expr, DslError> {
+    let list = match &function.args {
+        sql::FunctionArguments::List(list) => list.args.as_slice(),
+        _ => &[],
+    };
+    let (func, arity_ok) = match name {
+        "IFNULL" => (ScalarFunction::Coalesce, list.len() == 2),
+        "ELEMENT_AT" => (ScalarFunction::ElementAt, list.len() == 2),
+        "ARRAY_CONTAINS" => (ScalarFunction::ArrayContains, list.len() == 2),
+        "UNNEST" => (ScalarFunction::Unnest, list.len() == 1),
+        _ => (ScalarFunction::Coalesce, !list.is_empty()),
+    };
+    if !arity_ok {
+        return Err(parse_err(
+            line_no,
+            format!("Wrong number of arguments to {}", name),
+        ));
+    }
+    let args = list
+        .iter()
+        .map(|arg| match arg {
+            sql::FunctionArg::Unnamed(sql::FunctionArgExpr::Expr(e)) => convert_expr(e, line_no),
+            other => Err(parse_err(
+                line_no,
+                format!("Unsupported argument: {}", other),
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Expr::ScalarFunction { func, args })
+}
+
+/// HAVING and ORDER BY run after the Aggregate node, where aggregates are plain columns
+fn aggregate_columns(expr: Expr) -> Expr {
+    match expr {
+        Expr::AggregateExpr { func, expr } => {
+            // Same naming as the Aggregate node's output schema
+            let arg = match *expr {
+                Expr::Column(name) => name,
+                _ => "val".to_string(),
+            };
+            Expr::Column(func.output_name(&arg))
+        }
+        Expr::BinaryExpr { left, op, right } => Expr::BinaryExpr {
+            left: Box::new(aggregate_columns(*left)),
+            op,
+            right: Box::new(aggregate_columns(*right)),
+        },
+        other => other,
+    }
+}
+
+/// COUNT/SUM/AVG/MIN/MAX, VARIANCE/STDDEV/MEDIAN over a column or `*`,
+/// COUNT(DISTINCT col), PERCENTILE(col, p) and the approximate
+/// APPROX_COUNT_DISTINCT(col) and APPROX_QUANTILE(col, p)
+fn convert_aggregate(function: &sql::Function, line_no: usize) -> Result<Expr, DslError> {
+    let name = function.name.to_string().to_uppercase();
+    let mut func = match name.as_str() {
+        "PERCENTILE" => AggregateFunction::Percentile(0.0),
+        "APPROX_QUANTILE" => AggregateFunction::ApproxQuantile(0.0),
+        _ => AggregateFunction::from_name(&name)
+            .ok_or_else(|| parse_err(line_no, format!("Unsupported function: {}", name)))?,
+    };
+
+    let list = match &function.args {
+        sql::FunctionArguments::List(list) => list,
+        _ => return Err(parse_err(line_no, format!("{} expects one argument", name))),
+    };
+    if let Some(sql::DuplicateTreatment::Distinct) = list.duplicate_treatment {
+        if func != AggregateFunction::Count {
+            return Err(parse_err(
+                line_no,
+                format!("DISTINCT is only supported in COUNT, not {}", name),
+            ));
+        }
+        func = AggregateFunction::CountDistinct;
+    }
+    let arg = match (&mut func, list.args.as_slice()) {
+        (
+            AggregateFunction::Percentile(p) | AggregateFunction::ApproxQuantile(p),
+            [arg, fraction],
+        ) => {
+            *p = match fraction {
+                sql::FunctionArg::Unnamed(sql::FunctionArgExpr::Expr(e)) => {
+                    match convert_expr(e, line_no)? {
+                        Expr::Literal(v) => v.as_float().filter(|p| (0.0..=1.0).contains(p)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+            .ok_or_else(|| {
+                parse_err(
+                    line_no,
+                    format!("{} expects a fraction between 0 and 1", name),
+                )
+            })?;
+            arg
+        }
+        (AggregateFunction::Percentile(_) | AggregateFunction::ApproxQuantile(_), _) => {
+            return Err(parse_err(line_no, format!("Expected: {}(column, p)", name)))
+        }
+        (_, [arg]) => arg,
+        _ => return Err(parse_err(line_no, format!("{} expects one argument", name))),
+    };
+    let inner = match arg {
+        sql::FunctionArg::Unnamed(sql::FunctionArgExpr::Wildcard) => Expr::Literal(Value::Int(1)),
+        sql::FunctionArg::Unnamed(sql::FunctionArgExpr::Expr(e)) => convert_expr(e, line_no)?,
+        other => {
+            return Err(parse_err(
+                line_no,
+                format!("Unsupported argument: {}", other),
+            ))
+        }
+    };
+
+    Ok(Expr::AggregateExpr {
+        func,
+        expr: Box::new(inner),
+    })
+}
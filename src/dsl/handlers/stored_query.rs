@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use crate::dsl::handlers::dataset::{handle_select, split_args};
+use crate::dsl::{DslError, DslOutput};
+use crate::engine::TensorDb;
+use crate::utils::parsing::{parse_string_literal, QuoteState};
+
+/// Handle CREATE QUERY command
+/// Syntax: CREATE QUERY name AS SELECT ... (may reference parameters as $param)
+pub fn handle_create_query(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let rest = line.strip_prefix("CREATE QUERY ").unwrap().trim();
+    let (name, body) = rest.split_once(" AS ").ok_or_else(|| DslError::Parse {
+        line: line_no,
+        msg: "Expected: CREATE QUERY name AS SELECT ...".to_string(),
+    })?;
+    let (name, body) = (name.trim(), body.trim());
+
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: format!("Invalid query name '{}'", name),
+        });
+    }
+    if !body.starts_with("SELECT ") && !body.starts_with("WITH ") {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: "Stored queries must be SELECT statements".to_string(),
+        });
+    }
+
+    db.create_query(name, body).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+
+    let params = query_parameters(body);
+    let msg = if params.is_empty() {
+        format!("Query '{}' created", name)
+    } else {
+        format!(
+            "Query '{}' created (parameters: {})",
+            name,
+            params
+                .iter()
+                .map(|p| format!("${}", p))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    Ok(DslOutput::Message(msg))
+}
+
+/// Handle RUN QUERY command
+/// Syntax: RUN QUERY name [WITH ($param = value, ...)]
+pub fn handle_run_query(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+) -> Result<DslOutput, DslError> {
+    let rest = line.strip_prefix("RUN QUERY ").unwrap().trim();
+    let (name, args) = match rest.split_once(" WITH ") {
+        Some((name, args)) => (name.trim(), Some(args.trim())),
+        None => (rest, None),
+    };
+
+    let mut params = HashMap::new();
+    if let Some(args) = args {
+        let inner = args
+            .strip_prefix('(')
+            .and_then(|a| a.strip_suffix(')'))
+            .ok_or_else(|| DslError::Parse {
+                line: line_no,
+                msg: "Expected: RUN QUERY name WITH ($param = value, ...)".to_string(),
+            })?;
+        for arg in split_args(inner) {
+            let (key, value) = arg.split_once('=').ok_or_else(|| DslError::Parse {
+                line: line_no,
+                msg: format!("Expected '$param = value', got '{}'", arg),
+            })?;
+            let key = key
+                .trim()
+                .strip_prefix('$')
+                .ok_or_else(|| DslError::Parse {
+                    line: line_no,
+                    msg: format!("Parameter names must start with '$': '{}'", key.trim()),
+                })?;
+            let value = value.trim();
+            if !is_literal(value) {
+                return Err(DslError::Parse {
+                    line: line_no,
+                    msg: format!(
+                        "Value of ${} must be a number, string or boolean literal",
+                        key
+                    ),
+                });
+            }
+            params.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    let body = db.get_query(name).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    let bound = bind_parameters(body, &params, line_no)?;
+
+    handle_select(db, &bound, line_no)
+}
+
+fn is_literal(value: &str) -> bool {
+    matches!(parse_string_literal(value), Some(Ok(_)))
+        || value == "true"
+        || value == "false"
+        || value.parse::<f64>().is_ok()
+}
+
+/// Names of the `$param` placeholders of a query body, in order of first use
+pub(crate) fn query_parameters(body: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    scan_parameters(body, |name| {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        String::new()
+    });
+    names
+}
+
+/// Substitute every `$param` outside string literals with its bound value
+fn bind_parameters(
+    body: &str,
+    params: &HashMap<String, String>,
+    line_no: usize,
+) -> Result<String, DslError> {
+    let mut missing = None;
+    let bound = scan_parameters(body, |name| match params.get(name) {
+        Some(value) => value.clone(),
+        None => {
+            missing.get_or_insert_with(|| name.to_string());
+            String::new()
+        }
+    });
+
+    match missing {
+        Some(name) => Err(DslError::Parse {
+            line: line_no,
+            msg: format!("Missing value for parameter ${}", name),
+        }),
+        None => Ok(bound),
+    }
+}
+
+/// Rebuild `body`, replacing each `$ident` (outside quotes) with `replace(ident)`
+pub(crate) fn scan_parameters(body: &str, mut replace: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    let mut quotes = QuoteState::default();
+
+    while let Some(ch) = chars.next() {
+        if quotes.step(ch) || ch != '$' {
+            out.push(ch);
+            continue;
+        }
+
+        let mut name = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_alphanumeric() || c == '_' {
+                name.push(c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if name.is_empty() {
+            out.push('$');
+        } else {
+            out.push_str(&replace(&name));
+        }
+    }
+    out
+}
@@ -0,0 +1,50 @@
+use crate::dsl::handlers::persistence::{append_to_wal, auto_persist_dataset, auto_persist_drop};
+use crate::dsl::{DslError, DslOutput};
+use crate::engine::TensorDb;
+
+/// Handle BEGIN command
+pub fn handle_begin(db: &mut TensorDb, line_no: usize) -> Result<DslOutput, DslError> {
+    db.begin_transaction().map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    Ok(DslOutput::Message("Transaction started".to_string()))
+}
+
+/// Handle COMMIT command
+/// Writes the statements held back since BEGIN to the WAL
+pub fn handle_commit(db: &mut TensorDb, line_no: usize) -> Result<DslOutput, DslError> {
+    let statements = db.commit_transaction().map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    if db.config.storage.wal && !db.replaying_wal {
+        for statement in &statements {
+            append_to_wal(db, statement, line_no)?;
+        }
+    }
+    Ok(DslOutput::Message(format!(
+        "Transaction committed ({} statements)",
+        statements.len()
+    )))
+}
+
+/// Handle ROLLBACK command
+/// With auto_persist on, stored datasets are brought back in line too
+pub fn handle_rollback(db: &mut TensorDb, line_no: usize) -> Result<DslOutput, DslError> {
+    let report = db.rollback_transaction().map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+    for name in &report.dropped {
+        auto_persist_drop(db, name, line_no)?;
+    }
+    for name in &report.restored {
+        auto_persist_dataset(db, name, line_no)?;
+    }
+    Ok(DslOutput::Message(format!(
+        "Transaction rolled back ({} datasets restored, {} dropped)",
+        report.restored.len(),
+        report.dropped.len()
+    )))
+}
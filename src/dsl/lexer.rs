@@ -0,0 +1,158 @@
+//! Tokenizer for DSL statements. String literals become single tokens, so
+//! keywords, commas and parentheses inside them never split a statement.
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+/// Kind of a lexical token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Identifier or keyword; keywords are matched case-insensitively
+    Word,
+    /// Unsigned integer or decimal literal (signs are operators)
+    Number,
+    /// `"..."` literal with its quotes; escapes are decoded by the parser
+    String,
+    /// `'...'` literal with its quotes (timestamps in `AS OF`)
+    QuotedText,
+    /// `$name` parameter of a stored query
+    Param,
+    /// Operator or punctuation, e.g. `(`, `,`, `>=`
+    Symbol,
+}
+
+/// A token and the source text it was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offset of `text` in the statement
+    pub offset: usize,
+}
+
+impl Token<'_> {
+    /// Byte offset just past the token
+    pub fn end(&self) -> usize {
+        self.offset + self.text.len()
+    }
+
+    /// Whether this is the keyword `kw` (case-insensitive)
+    pub fn is_keyword(&self, kw: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(kw)
+    }
+
+    pub fn is_symbol(&self, sym: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == sym
+    }
+}
+
+const TWO_CHAR_SYMBOLS: [&str; 5] = [">=", "<=", "!=", "<>", "->"];
+
+/// Split `src` into tokens. Fails only on an unterminated quoted literal.
+pub fn tokenize(src: &str) -> Result<Vec<Token<'_>>, String> {
+    Lexer::new(src).collect()
+}
+
+/// Lazily yields the tokens of a statement, so a command can be identified
+/// from its first words without lexing the rest of the line
+pub struct Lexer<'a> {
+    src: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            chars: src.char_indices().peekable(),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chars = &mut self.chars;
+        while chars.next_if(|&(_, c)| c.is_whitespace()).is_some() {}
+        let &(start, ch) = chars.peek()?;
+
+        let kind = if ch == '"' || ch == '\'' {
+            chars.next();
+            let mut escaped = false;
+            let closed = chars.by_ref().any(|(_, c)| {
+                if escaped {
+                    escaped = false;
+                    false
+                } else {
+                    escaped = ch == '"' && c == '\\';
+                    c == ch
+                }
+            });
+            if !closed {
+                return Some(Err(format!(
+                    "Unterminated string literal: {}",
+                    &self.src[start..]
+                )));
+            }
+            if ch == '"' {
+                TokenKind::String
+            } else {
+                TokenKind::QuotedText
+            }
+        } else if ch.is_ascii_digit() {
+            take_while(chars, |c| c.is_ascii_digit());
+            // A fraction needs a digit after the point, so `1.` stays `1` `.`
+            let mut ahead = chars.clone();
+            if ahead.next().is_some_and(|(_, c)| c == '.')
+                && ahead.next().is_some_and(|(_, c)| c.is_ascii_digit())
+            {
+                chars.next();
+                take_while(chars, |c| c.is_ascii_digit());
+            }
+            let mut ahead = chars.clone();
+            if ahead.next().is_some_and(|(_, c)| c == 'e' || c == 'E') {
+                ahead.next_if(|&(_, c)| c == '+' || c == '-');
+                if ahead.peek().is_some_and(|&(_, c)| c.is_ascii_digit()) {
+                    *chars = ahead;
+                    take_while(chars, |c| c.is_ascii_digit());
+                }
+            }
+            TokenKind::Number
+        } else if is_word_char(ch) {
+            take_while(chars, is_word_char);
+            TokenKind::Word
+        } else if ch == '$' {
+            chars.next();
+            take_while(chars, is_word_char);
+            TokenKind::Param
+        } else {
+            chars.next();
+            if let Some(&(i, next)) = chars.peek() {
+                if TWO_CHAR_SYMBOLS.contains(&&self.src[start..i + next.len_utf8()]) {
+                    chars.next();
+                    // `->>` extends `->`
+                    if &self.src[start..i + 1] == "->" {
+                        chars.next_if(|&(_, c)| c == '>');
+                    }
+                }
+            }
+            TokenKind::Symbol
+        };
+
+        let end = chars.peek().map_or(self.src.len(), |&(i, _)| i);
+        Some(Ok(Token {
+            kind,
+            text: &self.src[start..end],
+            offset: start,
+        }))
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn take_while(chars: &mut Peekable<CharIndices<'_>>, pred: impl Fn(char) -> bool) {
+    while chars.next_if(|&(_, c)| pred(c)).is_some() {}
+}
@@ -1,13 +1,16 @@
 pub mod error;
 pub mod handlers;
-// pub mod parser; // Not used currently, logic is in handlers/parsing logic
+pub mod lexer;
+pub mod parser;
 
 pub use error::DslError;
 
 use crate::core::dataset_legacy::Dataset;
 use crate::core::tensor::Tensor;
 use crate::engine::TensorDb;
+use crate::utils::parsing::unquoted_char_indices;
 use handlers::{handle_define, handle_let, handle_show};
+use parser::{Command, Statement};
 use serde::Serialize;
 
 #[derive(Debug, Clone, Serialize)]
@@ -17,6 +20,8 @@ pub enum DslOutput {
     Table(Dataset),
     TensorTable(crate::core::dataset::Dataset, Vec<String>),
     Tensor(Tensor),
+    /// Result of a data-modifying statement: rows touched and the statement kind (e.g. "INSERT")
+    Affected { rows: usize, op: String },
 }
 
 use std::fmt;
@@ -64,12 +69,36 @@ impl fmt::Display for DslOutput {
                 Ok(())
             }
             DslOutput::Tensor(t) => write!(f, "Tensor: {:?} values: {:?}", t.shape, t.data), // simplified
+            DslOutput::Affected { rows, op } => write!(f, "{}: {} row(s) affected", op, rows),
         }
     }
 }
 
 /// Ejecuta un script completo (varias líneas) sobre un TensorDb
 pub fn execute_script(db: &mut TensorDb, script: &str) -> Result<(), DslError> {
+    for (line_no, cmd) in split_statements(script)? {
+        let output = match execute_line(db, &cmd, line_no) {
+            Ok(output) => output,
+            Err(e) => {
+                // A failed statement undoes the whole open transaction
+                if db.in_transaction() {
+                    let _ = handlers::transaction::handle_rollback(db, line_no);
+                }
+                return Err(e);
+            }
+        };
+        if !matches!(output, DslOutput::None) {
+            println!("{}", output);
+        }
+    }
+    Ok(())
+}
+
+/// Split a script into statements paired with their starting line.
+/// Blank lines and comments between statements are skipped; a statement
+/// continues across lines until its parentheses balance.
+pub fn split_statements(script: &str) -> Result<Vec<(usize, String)>, DslError> {
+    let mut statements = Vec::new();
     let mut current_cmd = String::new();
     let mut start_line = 0;
     let mut paren_balance = 0;
@@ -90,8 +119,8 @@ pub fn execute_script(db: &mut TensorDb, script: &str) -> Result<(), DslError> {
         }
         current_cmd.push_str(line);
 
-        // Update balance
-        for c in line.chars() {
+        // Update balance (parentheses inside string literals do not count)
+        for (_, c) in unquoted_char_indices(line) {
             if c == '(' {
                 paren_balance += 1;
             } else if c == ')' {
@@ -101,17 +130,8 @@ pub fn execute_script(db: &mut TensorDb, script: &str) -> Result<(), DslError> {
 
         // Check if command is complete
         // Heuristic: balance is 0.
-        // Note: This might be fragile if strings contain parens, but MVP.
         if paren_balance == 0 {
-            match execute_line(db, &current_cmd, start_line) {
-                Ok(output) => {
-                    if !matches!(output, DslOutput::None) {
-                        println!("{}", output);
-                    }
-                }
-                Err(e) => return Err(e),
-            }
-            current_cmd.clear();
+            statements.push((start_line, std::mem::take(&mut current_cmd)));
         }
     }
 
@@ -123,7 +143,7 @@ pub fn execute_script(db: &mut TensorDb, script: &str) -> Result<(), DslError> {
         });
     }
 
-    Ok(())
+    Ok(statements)
 }
 
 /// Ejecuta una sola línea de DSL
@@ -131,6 +151,11 @@ pub fn execute_line(db: &mut TensorDb, line: &str, line_no: usize) -> Result<Dsl
     execute_line_with_context(db, line, line_no, None)
 }
 
+/// Execute a single SQL statement (CREATE TABLE, INSERT or SELECT)
+pub fn execute_sql(db: &mut TensorDb, sql: &str, line_no: usize) -> Result<DslOutput, DslError> {
+    execute_line(db, &format!("SQL {}", sql.trim()), line_no)
+}
+
 /// Execute a single DSL line with an optional execution context
 pub fn execute_line_with_context(
     db: &mut TensorDb,
@@ -138,87 +163,169 @@ pub fn execute_line_with_context(
     line_no: usize,
     ctx: Option<&mut crate::engine::context::ExecutionContext>,
 ) -> Result<DslOutput, DslError> {
-    if line.starts_with("DEFINE ") {
-        handle_define(db, line, line_no)
-    } else if line.starts_with("VECTOR ") {
-        handlers::tensor::handle_vector(db, line, line_no)
-    } else if line.starts_with("MATRIX ") {
-        handlers::tensor::handle_matrix(db, line, line_no)
-    } else if line.starts_with("LET ") {
-        handle_let(db, line, line_no, ctx)
-    } else if line.starts_with("SHOW ") {
-        handle_show(db, line, line_no)
-    } else if line.starts_with("SELECT ") {
-        handlers::dataset::handle_select(db, line, line_no)
-    } else if line.starts_with("DATASET ") {
-        handlers::dataset::handle_dataset(db, line, line_no)
-    } else if line.starts_with("INSERT INTO ") {
-        handlers::dataset::handle_insert(db, line, line_no)
-    } else if line.starts_with("SEARCH ") {
-        handlers::search::handle_search(db, line, line_no)
-    } else if line.starts_with("EXPLAIN ") {
-        // Added EXPLAIN routing
-        handlers::explain::handle_explain(db, line, line_no)
-    } else if line.starts_with("MATERIALIZE ") {
-        handlers::dataset::handle_materialize(db, line, line_no)
-    } else if line.contains(".add_column(") {
-        handlers::dataset::handle_add_tensor_column(db, line, line_no)
-    } else if line.starts_with("CREATE ") {
-        // Check for CREATE DATABASE
-        if line.starts_with("CREATE DATABASE ") {
-            handlers::instance::handle_create_database(db, line, line_no)
-        } else if line.contains("INDEX ") {
-            handlers::index::handle_create_index(db, line, line_no)
-        } else {
-            Err(DslError::Parse {
-                line: line_no,
-                msg: format!("Unsupported CREATE command: {}", line),
-            })
+    // A macro call runs, and is logged, as the statement it expands to
+    let expanded = parser::expand_macros(line, &db.active_instance().macros, line_no)?;
+    let line = expanded.as_deref().unwrap_or(line);
+
+    // Datasets evicted under memory pressure come back before the statement runs
+    let named = db.touch_datasets(line).map_err(|e| DslError::Engine {
+        line: line_no,
+        source: e,
+    })?;
+
+    // Only the leading keywords decide how a statement is logged, so a
+    // statement that fails to parse is still audited
+    let command = parser::classify(line, line_no)
+        .ok()
+        .flatten()
+        .map(|(command, _)| command);
+
+    // Background jobs finished since the previous statement come first,
+    // unless it is there to cancel one of them
+    if command != Some(Command::CancelJob) {
+        let installed = db.install_finished_jobs();
+        if db.config.storage.wal && !db.replaying_wal {
+            for command in &installed {
+                handlers::persistence::append_to_wal(db, command, line_no)?;
+            }
+        }
+    }
+    let audited = !db.replaying_wal && command.is_some_and(|c| c.is_audited(line));
+    let before = audited.then(|| (db.active_instance().name.clone(), db.dataset_shapes()));
+    let mutating = command.is_some_and(|c| c.is_mutating(line));
+    let fingerprints =
+        mutating.then(|| (db.active_instance().name.clone(), db.dataset_fingerprints()));
+    // An open transaction only takes writes from the session that began it
+    let owned = if mutating {
+        db.check_transaction_session()
+    } else {
+        Ok(())
+    };
+    if mutating && owned.is_ok() {
+        db.copy_on_write(line);
+    }
+
+    // COPY rows follow the header line and are not shown by SHOW QUERIES
+    let header = line.split('\n').next().unwrap_or(line);
+    let registered = db.begin_query(header);
+    let database = db.active_instance().name.clone();
+    let started = std::time::Instant::now();
+    let result = owned
+        .map_err(|source| DslError::Engine {
+            line: line_no,
+            source,
+        })
+        .and_then(|()| dispatch_line(db, line, line_no, ctx));
+    if registered {
+        db.end_query();
+        if !db.replaying_wal {
+            db.notify_query_executed(&crate::engine::QueryEvent {
+                database: &database,
+                command: header,
+                elapsed: started.elapsed(),
+                rows_returned: match &result {
+                    Ok(DslOutput::Table(ds)) => Some(ds.rows.len()),
+                    _ => None,
+                },
+                error: result.as_ref().err().map(|e| e.to_string()),
+            });
+        }
+    }
+    if let (Ok(_), Some((database, fingerprints))) = (&result, fingerprints) {
+        // Commands that switch databases (LOAD/RESTORE DATABASE) start no versions
+        if database == db.active_instance().name {
+            db.commit_dataset_versions(&fingerprints);
+        }
+    }
+    if let Some((database, shapes)) = before {
+        let error = result.as_ref().err().map(|e| e.to_string());
+        // COPY rows follow the header line; the WAL keeps them, the audit log does not
+        let command = line.split('\n').next().unwrap_or(line);
+        db.record_audit(&database, command, &shapes, error);
+    }
+    db.enforce_memory_budget(&named);
+    let output = result?;
+
+    // Inside a transaction the WAL only sees statements once COMMIT runs
+    if db.config.storage.wal && !db.replaying_wal && mutating && !db.defer_wal(line) {
+        handlers::persistence::append_to_wal(db, line, line_no)?;
+    }
+
+    Ok(output)
+}
+
+fn dispatch_line(
+    db: &mut TensorDb,
+    line: &str,
+    line_no: usize,
+    ctx: Option<&mut crate::engine::context::ExecutionContext>,
+) -> Result<DslOutput, DslError> {
+    let (command, line) = match parser::parse_statement(line, line_no)? {
+        Statement::Empty => return Ok(DslOutput::None),
+        Statement::Select(select) => {
+            return handlers::dataset::execute_select(db, &select, line_no)
         }
-    } else if line.starts_with("ALTER ") {
-        let line = line.strip_prefix("ALTER ").unwrap();
-        if line.starts_with("DATASET ") {
-            handlers::dataset::handle_dataset(db, line, line_no)
-        } else {
-            Err(DslError::Parse {
-                line: line_no,
-                msg: format!("Unsupported ALTER command: {}", line),
-            })
+        Statement::DatasetQuery(query) => {
+            return handlers::dataset::execute_dataset_query(db, &query, line_no)
         }
-    } else if line.starts_with("USE ") {
-        handlers::instance::handle_use_database(db, line, line_no)
-    } else if line.starts_with("DROP ") {
-        if line.starts_with("DROP DATABASE ") {
-            handlers::instance::handle_drop_database(db, line, line_no)
-        } else {
-            Err(DslError::Parse {
-                line: line_no,
-                msg: format!("Unsupported DROP command: {}", line),
-            })
+        Statement::Update(update) => {
+            return handlers::dataset::execute_update(db, &update, line_no)
         }
-    } else if line.starts_with("SET ") {
-        if line.starts_with("SET DATASET ") {
-            handlers::metadata::handle_set_metadata(db, line, line_no)
-        } else {
-            Err(DslError::Parse {
-                line: line_no,
-                msg: format!("Unsupported SET command: {}", line),
-            })
+        Statement::Delete(delete) => {
+            return handlers::dataset::execute_delete(db, &delete, line_no)
         }
-    } else if line.starts_with("SAVE ") {
-        handlers::persistence::handle_save(db, line, line_no)
-    } else if line.starts_with("LOAD ") {
-        handlers::persistence::handle_load(db, line, line_no)
-    } else if line.starts_with("LIST DATASETS") || line.starts_with("LIST TENSORS") {
-        handlers::persistence::handle_list_datasets(db, line, line_no)
-    } else {
-        // Comment or empty? handled in script, but for single line exec check too
-        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
-            return Ok(DslOutput::None);
+        Statement::Command { command, line } => (command, line),
+    };
+    let line = line.as_str();
+
+    match command {
+        Command::Define => handle_define(db, line, line_no),
+        Command::DefineMacro => handlers::macros::handle_define_macro(db, line, line_no),
+        Command::Vector => handlers::tensor::handle_vector(db, line, line_no),
+        Command::Matrix => handlers::tensor::handle_matrix(db, line, line_no),
+        Command::Let => handle_let(db, line, line_no, ctx),
+        Command::Show => handle_show(db, line, line_no),
+        Command::CreateDataset
+        | Command::AddColumn
+        | Command::AlterColumn
+        | Command::SetTtl
+        | Command::MaskColumn => handlers::dataset::handle_dataset(db, line, line_no),
+        Command::Insert => handlers::dataset::handle_insert(db, line, line_no),
+        Command::Upsert => handlers::dataset::handle_upsert(db, line, line_no),
+        Command::Copy => handlers::copy::handle_copy(db, line, line_no),
+        Command::Search => handlers::search::handle_search(db, line, line_no),
+        Command::Explain => handlers::explain::handle_explain(db, line, line_no),
+        Command::Materialize => handlers::dataset::handle_materialize(db, line, line_no),
+        Command::AddTensorColumn => handlers::dataset::handle_add_tensor_column(db, line, line_no),
+        Command::CreateDatabase => handlers::instance::handle_create_database(db, line, line_no),
+        Command::CreateQuery => handlers::stored_query::handle_create_query(db, line, line_no),
+        Command::CreateIndex => handlers::index::handle_create_index(db, line, line_no),
+        Command::Use => handlers::instance::handle_use_database(db, line, line_no),
+        Command::DropDatabase => handlers::instance::handle_drop_database(db, line, line_no),
+        Command::DropDataset => handlers::dataset::handle_drop_dataset(db, line, line_no),
+        Command::RenameDataset => handlers::dataset::handle_rename_dataset(db, line, line_no),
+        Command::TruncateDataset => handlers::dataset::handle_truncate_dataset(db, line, line_no),
+        Command::SetMetadata => handlers::metadata::handle_set_metadata(db, line, line_no),
+        Command::RunQuery => handlers::stored_query::handle_run_query(db, line, line_no),
+        Command::SnapshotDatabase => {
+            handlers::instance::handle_snapshot_database(db, line, line_no)
+        }
+        Command::RestoreDatabase => handlers::instance::handle_restore_database(db, line, line_no),
+        Command::Checkpoint => handlers::persistence::handle_checkpoint(db, line_no),
+        Command::Sql => handlers::sql::handle_sql(db, line, line_no),
+        Command::Export => handlers::persistence::handle_export(db, line, line_no),
+        Command::Save => handlers::persistence::handle_save(db, line, line_no),
+        Command::Load => handlers::persistence::handle_load(db, line, line_no),
+        Command::List => handlers::persistence::handle_list_datasets(db, line, line_no),
+        Command::Analyze => handlers::metadata::handle_analyze(db, line, line_no),
+        Command::Describe => handlers::introspection::handle_describe(db, line, line_no),
+        Command::Summarize => handlers::introspection::handle_summarize(db, line, line_no),
+        Command::Begin => handlers::transaction::handle_begin(db, line_no),
+        Command::Commit => handlers::transaction::handle_commit(db, line_no),
+        Command::Rollback => handlers::transaction::handle_rollback(db, line_no),
+        Command::CancelJob => handlers::jobs::handle_cancel_job(db, line, line_no),
+        Command::Select | Command::DatasetQuery | Command::Update | Command::Delete => {
+            unreachable!("queries parse into their own statements")
         }
-        Err(DslError::Parse {
-            line: line_no,
-            msg: format!("Unknown command: {}", line),
-        })
     }
 }
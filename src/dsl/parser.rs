@@ -0,0 +1,2496 @@
+//! Recursive-descent parser for DSL statements.
+//!
+//! Queries (SELECT, DATASET ... FROM, UPDATE, DELETE) are parsed into a
+//! syntax tree that the handlers plan and execute. Every other command is
+//! identified by its leading keywords and parses the rest of its line itself.
+//! Keywords are case-insensitive; names and string literals are not.
+
+use super::lexer::{tokenize, Lexer, Token, TokenKind};
+use super::DslError;
+use crate::core::dataset_legacy::{MaskingPolicy, Sample};
+use crate::core::value::{Value, ValueType};
+use crate::dsl::handlers::dataset::parse_single_value;
+use crate::dsl::handlers::stored_query::{query_parameters, scan_parameters};
+use crate::query::logical::{
+    AggregateFunction, Expr, JoinType, ScalarFunction, SimilarityThreshold, WindowFrame,
+    WindowFunction,
+};
+use crate::utils::parsing::{parse_interval, parse_timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What a statement does, decided by its leading keywords
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Select,
+    DatasetQuery,
+    Update,
+    Delete,
+    Define,
+    DefineMacro,
+    Vector,
+    Matrix,
+    Let,
+    Show,
+    CreateDataset,
+    AddColumn,
+    AlterColumn,
+    SetTtl,
+    MaskColumn,
+    Insert,
+    Upsert,
+    Copy,
+    Search,
+    Explain,
+    Materialize,
+    AddTensorColumn,
+    CreateDatabase,
+    CreateQuery,
+    CreateIndex,
+    DropDatabase,
+    Use,
+    SetMetadata,
+    RunQuery,
+    SnapshotDatabase,
+    RestoreDatabase,
+    Checkpoint,
+    Sql,
+    Export,
+    Save,
+    Load,
+    List,
+    Analyze,
+    Describe,
+    Summarize,
+    DropDataset,
+    RenameDataset,
+    TruncateDataset,
+    Begin,
+    Commit,
+    Rollback,
+    CancelJob,
+}
+
+impl Command {
+    /// Leading keywords the handler expects, in canonical upper case
+    fn keywords(self) -> &'static str {
+        match self {
+            Command::Select | Command::AddTensorColumn => "",
+            Command::DatasetQuery
+            | Command::CreateDataset
+            | Command::AddColumn
+            | Command::AlterColumn
+            | Command::SetTtl
+            | Command::MaskColumn => "DATASET",
+            Command::Update => "UPDATE",
+            Command::Delete => "DELETE FROM",
+            Command::Define => "DEFINE",
+            Command::DefineMacro => "DEFINE MACRO",
+            Command::Vector => "VECTOR",
+            Command::Matrix => "MATRIX",
+            Command::Let => "LET",
+            Command::Show => "SHOW",
+            Command::Insert => "INSERT INTO",
+            Command::Upsert => "UPSERT INTO",
+            Command::Copy => "COPY",
+            Command::Search => "SEARCH",
+            Command::Explain => "EXPLAIN",
+            Command::Materialize => "MATERIALIZE",
+            Command::CreateDatabase => "CREATE DATABASE",
+            Command::CreateQuery => "CREATE QUERY",
+            Command::CreateIndex => "CREATE",
+            Command::DropDatabase => "DROP DATABASE",
+            Command::DropDataset => "DROP DATASET",
+            Command::RenameDataset => "RENAME DATASET",
+            Command::TruncateDataset => "TRUNCATE DATASET",
+            Command::Use => "USE",
+            Command::SetMetadata => "SET DATASET",
+            Command::RunQuery => "RUN QUERY",
+            Command::SnapshotDatabase => "SNAPSHOT DATABASE",
+            Command::RestoreDatabase => "RESTORE DATABASE",
+            Command::Checkpoint => "CHECKPOINT",
+            Command::Sql => "SQL",
+            Command::Export => "EXPORT",
+            Command::Save => "SAVE",
+            Command::Load => "LOAD",
+            Command::List => "LIST",
+            Command::Analyze => "ANALYZE",
+            Command::Describe => "DESCRIBE",
+            Command::Summarize => "SUMMARIZE",
+            Command::Begin => "BEGIN",
+            Command::Commit => "COMMIT",
+            Command::Rollback => "ROLLBACK",
+            Command::CancelJob => "CANCEL JOB",
+        }
+    }
+
+    /// Commands that change the state of the active database and must be
+    /// logged to the WAL. Databases and stored queries are persisted by their
+    /// own catalogs; SQL statements are checked by the SQL frontend.
+    pub fn is_mutating(self, line: &str) -> bool {
+        if self == Command::Sql {
+            return crate::dsl::handlers::sql::is_mutating_sql(line);
+        }
+        // A background job is logged once its result is installed
+        if matches!(self, Command::CreateIndex | Command::Materialize) && is_async(line) {
+            return false;
+        }
+        !matches!(
+            self,
+            Command::Select
+                | Command::Show
+                | Command::Explain
+                | Command::CreateDatabase
+                | Command::CreateQuery
+                | Command::DefineMacro
+                | Command::DropDatabase
+                | Command::Use
+                | Command::RunQuery
+                | Command::SnapshotDatabase
+                | Command::Checkpoint
+                | Command::Sql
+                | Command::Export
+                | Command::Save
+                | Command::List
+                | Command::Analyze
+                | Command::Describe
+                | Command::Summarize
+                | Command::Begin
+                | Command::Commit
+                | Command::Rollback
+                | Command::CancelJob
+        )
+    }
+
+    /// Queries that only read the active database, so they can run on a
+    /// snapshot of it while writers go on (see `server::sessions`)
+    pub fn is_read_only(self, line: &str) -> bool {
+        match self {
+            Command::Sql => !crate::dsl::handlers::sql::is_mutating_sql(line),
+            _ => matches!(
+                self,
+                Command::Select
+                    | Command::Show
+                    | Command::Explain
+                    | Command::Describe
+                    | Command::Summarize
+            ),
+        }
+    }
+
+    /// Commands recorded in `system.audit_log`: everything logged to the WAL
+    /// plus database, stored query and macro DDL
+    pub fn is_audited(self, line: &str) -> bool {
+        self.is_mutating(line)
+            || matches!(
+                self,
+                Command::CreateDatabase
+                    | Command::DropDatabase
+                    | Command::CreateQuery
+                    | Command::DefineMacro
+                    | Command::CreateIndex
+                    | Command::Materialize
+            )
+    }
+}
+
+/// A parsed statement
+#[derive(Debug, Clone)]
+pub enum Statement {
+    /// Blank line or comment
+    Empty,
+    Select(SelectStatement),
+    DatasetQuery(DatasetQuery),
+    Update(UpdateStatement),
+    Delete(DeleteStatement),
+    /// Any other command, with its leading keywords in canonical upper case
+    /// so the handler can parse the rest of `line`
+    Command {
+        command: Command,
+        line: String,
+    },
+}
+
+/// `[WITH name AS (SELECT ...), ...] SELECT items FROM source clauses`
+#[derive(Debug, Clone)]
+pub struct SelectStatement {
+    pub ctes: Vec<(String, SelectStatement)>,
+    pub items: Vec<Expr>,
+    pub from: FromClause,
+    pub clauses: QueryClauses,
+}
+
+#[derive(Debug, Clone)]
+pub enum FromClause {
+    /// A dataset or CTE, optionally at a past version
+    Source {
+        name: String,
+        version: Option<VersionSpec>,
+    },
+    /// `(SELECT ...) [[AS] alias]`; columns may be qualified by the alias
+    Subquery {
+        query: Box<SelectStatement>,
+        alias: Option<String>,
+    },
+    /// `a [[AS] x] JOIN b [[AS] y] ON x.col = y.col [JOIN ...]`, or
+    /// `ASOF [LEFT] JOIN b ON x.col = y.col AND x.ts >= y.ts`
+    Join {
+        first: JoinSource,
+        joins: Vec<JoinClause>,
+    },
+}
+
+/// `VERSION n` or `AS OF '<timestamp>'`
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionSpec {
+    Number(u32),
+    AsOf(chrono::DateTime<chrono::Utc>),
+}
+
+/// A dataset or CTE in a join; without an alias it is qualified by its name
+#[derive(Debug, Clone)]
+pub struct JoinSource {
+    pub name: String,
+    pub alias: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct JoinClause {
+    pub join_type: JoinType,
+    pub source: JoinSource,
+    /// Columns of `ON lhs = rhs`, as written
+    pub on: (String, String),
+    /// ASOF JOIN: columns of `AND later >= earlier`, as written, with `<=`
+    /// conditions turned around
+    pub asof: Option<(String, String)>,
+    /// `ON SIMILARITY(lhs, rhs) > threshold`: `on` holds the vector columns
+    pub similarity: Option<SimilarityThreshold>,
+}
+
+/// `DATASET target FROM source clauses`
+#[derive(Debug, Clone)]
+pub struct DatasetQuery {
+    pub target: String,
+    pub source: String,
+    pub clauses: QueryClauses,
+}
+
+/// `UPDATE name SET col = expr, ... [WHERE predicate] [RETURNING cols]`
+#[derive(Debug, Clone)]
+pub struct UpdateStatement {
+    pub dataset: String,
+    pub assignments: Vec<(String, Expr)>,
+    pub predicate: Option<Expr>,
+    pub returning: Option<Vec<String>>,
+}
+
+/// `DELETE FROM name [WHERE predicate] [RETURNING cols]`
+#[derive(Debug, Clone)]
+pub struct DeleteStatement {
+    pub dataset: String,
+    pub predicate: Option<Expr>,
+    pub returning: Option<Vec<String>>,
+}
+
+/// Clauses of a SELECT or DATASET ... FROM query, in whatever order they were written
+#[derive(Debug, Clone, Default)]
+pub struct QueryClauses {
+    pub filters: Vec<Expr>,
+    pub group_by: Option<Vec<Expr>>,
+    /// `GROUP BY ... WITH ROLLUP`: add subtotal and grand-total rows
+    pub rollup: bool,
+    pub having: Vec<Expr>,
+    pub select: Option<Vec<Expr>>,
+    /// `SELECT DISTINCT`: drop duplicate result rows
+    pub distinct: bool,
+    pub order_by: Option<(String, bool)>,
+    pub limit: Option<usize>,
+    /// `SAMPLE n PERCENT` / `SAMPLE n ROWS`: a random subset of the source rows
+    pub sample: Option<Sample>,
+    /// `expr [NOT] IN (SELECT ...)` conditions of FILTER/WHERE
+    pub in_subqueries: Vec<InSubquery>,
+}
+
+/// `expr [NOT] IN (SELECT col FROM ...)`, a top-level (AND-ed) filter condition
+#[derive(Debug, Clone)]
+pub struct InSubquery {
+    pub expr: Expr,
+    pub query: SelectStatement,
+    pub negated: bool,
+}
+
+/// Commands named by their first two words
+const TWO_WORD: [(&str, &str, Command); 10] = [
+    ("INSERT", "INTO", Command::Insert),
+    ("UPSERT", "INTO", Command::Upsert),
+    ("DELETE", "FROM", Command::Delete),
+    ("RUN", "QUERY", Command::RunQuery),
+    ("SNAPSHOT", "DATABASE", Command::SnapshotDatabase),
+    ("RESTORE", "DATABASE", Command::RestoreDatabase),
+    ("DEFINE", "MACRO", Command::DefineMacro),
+    ("RENAME", "DATASET", Command::RenameDataset),
+    ("TRUNCATE", "DATASET", Command::TruncateDataset),
+    ("CANCEL", "JOB", Command::CancelJob),
+];
+
+/// Commands named by their first word
+const ONE_WORD: [(&str, Command); 23] = [
+    ("DEFINE", Command::Define),
+    ("VECTOR", Command::Vector),
+    ("MATRIX", Command::Matrix),
+    ("LET", Command::Let),
+    ("SHOW", Command::Show),
+    ("UPDATE", Command::Update),
+    ("COPY", Command::Copy),
+    ("SEARCH", Command::Search),
+    ("EXPLAIN", Command::Explain),
+    ("MATERIALIZE", Command::Materialize),
+    ("USE", Command::Use),
+    ("CHECKPOINT", Command::Checkpoint),
+    ("SQL", Command::Sql),
+    ("EXPORT", Command::Export),
+    ("SAVE", Command::Save),
+    ("LOAD", Command::Load),
+    ("LIST", Command::List),
+    ("ANALYZE", Command::Analyze),
+    ("DESCRIBE", Command::Describe),
+    ("SUMMARIZE", Command::Summarize),
+    ("BEGIN", Command::Begin),
+    ("COMMIT", Command::Commit),
+    ("ROLLBACK", Command::Rollback),
+];
+
+/// Leading words of the commands matched before the keyword tables
+const LEADING_KEYWORDS: [&str; 7] = [
+    "SELECT", "WITH", "DATASET", "ALTER", "CREATE", "DROP", "SET",
+];
+
+/// Whether `word` starts a built-in command, so a macro cannot shadow it
+fn is_command_word(word: &str) -> bool {
+    LEADING_KEYWORDS
+        .iter()
+        .chain(TWO_WORD.iter().map(|(first, _, _)| first))
+        .chain(ONE_WORD.iter().map(|(first, _)| first))
+        .any(|kw| kw.eq_ignore_ascii_case(word))
+}
+
+/// Whether `line` ends with `ASYNC`, which runs CREATE INDEX and MATERIALIZE
+/// as a background job (see `engine::jobs`)
+pub fn is_async(line: &str) -> bool {
+    line.split_whitespace()
+        .last()
+        .is_some_and(|word| word.eq_ignore_ascii_case("ASYNC"))
+}
+
+/// Identify the command of `line` from its leading keywords, returning it
+/// with the byte offset where those keywords end. `None` for blank lines
+/// and comments. Only the leading words are lexed.
+pub fn classify(line: &str, line_no: usize) -> Result<Option<(Command, usize)>, DslError> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+        return Ok(None);
+    }
+    let unknown = || DslError::Parse {
+        line: line_no,
+        msg: format!("Unknown command: {}", line),
+    };
+
+    let mut lexer = Lexer::new(line);
+    let mut head: Vec<Token> = Vec::new();
+    // Enough words for `ALTER DATASET name ADD` and `a.add_column(`
+    for _ in 0..5 {
+        match lexer.next() {
+            Some(Ok(token)) => head.push(token),
+            Some(Err(_)) | None => break,
+        }
+    }
+    let kw = |i: usize, word: &str| head.get(i).is_some_and(|t| t.is_keyword(word));
+    let end_of = |i: usize| head[i].end();
+
+    let command = if head.len() >= 4
+        && head[0].kind == TokenKind::Word
+        && head[1].is_symbol(".")
+        && kw(2, "add_column")
+        && head[3].is_symbol("(")
+    {
+        (Command::AddTensorColumn, 0)
+    } else if kw(0, "SELECT") || kw(0, "WITH") {
+        (Command::Select, 0)
+    } else if kw(0, "DATASET") || (kw(0, "ALTER") && kw(1, "DATASET")) {
+        let name = if kw(0, "ALTER") { 2 } else { 1 };
+        let command = dataset_command(&head, name).ok_or_else(|| DslError::Parse {
+            line: line_no,
+            msg: "Expected DATASET ... COLUMNS ... or DATASET ... FROM ... or DATASET ... ADD/DROP/RENAME/ALTER COLUMN ... or DATASET ... SET TTL ... or DATASET ... MASK/UNMASK COLUMN ...".into(),
+        })?;
+        (command, end_of(name - 1))
+    } else if kw(0, "ALTER") {
+        return Err(DslError::Parse {
+            line: line_no,
+            msg: format!("Unsupported ALTER command: {}", line),
+        });
+    } else if kw(0, "CREATE") {
+        if kw(1, "DATABASE") {
+            (Command::CreateDatabase, end_of(1))
+        } else if kw(1, "QUERY") {
+            (Command::CreateQuery, end_of(1))
+        } else if kw(1, "INDEX") || (kw(1, "VECTOR") && kw(2, "INDEX")) {
+            (Command::CreateIndex, end_of(0))
+        } else {
+            return Err(DslError::Parse {
+                line: line_no,
+                msg: format!("Unsupported CREATE command: {}", line),
+            });
+        }
+    } else if kw(0, "DROP") {
+        if kw(1, "DATABASE") {
+            (Command::DropDatabase, end_of(1))
+        } else if kw(1, "DATASET") {
+            (Command::DropDataset, end_of(1))
+        } else {
+            return Err(DslError::Parse {
+                line: line_no,
+                msg: format!("Unsupported DROP command: {}", line),
+            });
+        }
+    } else if kw(0, "SET") {
+        if !kw(1, "DATASET") {
+            return Err(DslError::Parse {
+                line: line_no,
+                msg: format!("Unsupported SET command: {}", line),
+            });
+        }
+        (Command::SetMetadata, end_of(1))
+    } else if let Some(&(_, _, command)) = TWO_WORD.iter().find(|(a, b, _)| kw(0, a) && kw(1, b)) {
+        (command, end_of(1))
+    } else if let Some(&(_, command)) = ONE_WORD.iter().find(|(a, _)| kw(0, a)) {
+        (command, end_of(0))
+    } else {
+        return Err(unknown());
+    };
+
+    // CHECKPOINT and the transaction commands stand alone; every other
+    // command takes arguments
+    let standalone = matches!(
+        command.0,
+        Command::Checkpoint | Command::Begin | Command::Commit | Command::Rollback
+    );
+    let has_args = !line[command.1..].trim().is_empty() || command.0 == Command::Select;
+    if has_args == standalone {
+        return Err(unknown());
+    }
+    Ok(Some(command))
+}
+
+/// DATASET form from the word after the dataset name at `head[name]`
+fn dataset_command(head: &[Token], name: usize) -> Option<Command> {
+    if head.get(name)?.kind != TokenKind::Word {
+        return None;
+    }
+    let next = head.get(name + 1)?;
+    [
+        ("COLUMNS", Command::CreateDataset),
+        ("FROM", Command::DatasetQuery),
+        ("ADD", Command::AddColumn),
+        ("DROP", Command::AlterColumn),
+        ("RENAME", Command::AlterColumn),
+        ("ALTER", Command::AlterColumn),
+        ("SET", Command::SetTtl),
+        ("MASK", Command::MaskColumn),
+        ("UNMASK", Command::MaskColumn),
+    ]
+    .iter()
+    .find(|(kw, _)| next.is_keyword(kw))
+    .map(|&(_, command)| command)
+}
+
+/// Parse a whole statement
+pub fn parse_statement(line: &str, line_no: usize) -> Result<Statement, DslError> {
+    let line = line.trim();
+    let Some((command, head_end)) = classify(line, line_no)? else {
+        return Ok(Statement::Empty);
+    };
+
+    match command {
+        Command::Select => Ok(Statement::Select(parse_select(line, line_no)?)),
+        Command::DatasetQuery => {
+            let mut p = Parser::new(&line[head_end..], line_no)?;
+            let query = p.dataset_query()?;
+            p.expect_end()?;
+            Ok(Statement::DatasetQuery(query))
+        }
+        Command::Update => {
+            let mut p = Parser::new(&line[head_end..], line_no)?;
+            let update = p.update()?;
+            p.expect_end()?;
+            Ok(Statement::Update(update))
+        }
+        Command::Delete => {
+            let mut p = Parser::new(&line[head_end..], line_no)?;
+            let delete = p.delete()?;
+            p.expect_end()?;
+            Ok(Statement::Delete(delete))
+        }
+        _ => {
+            let keywords = command.keywords();
+            let rest = match keywords {
+                "DATASET" => canonical_dataset_command(&line[head_end..]),
+                _ => line[head_end..].trim_start().to_string(),
+            };
+            let line = match (keywords.is_empty(), rest.is_empty()) {
+                (true, _) => rest.to_string(),
+                (false, true) => keywords.to_string(),
+                (false, false) => format!("{} {}", keywords, rest),
+            };
+            Ok(Statement::Command { command, line })
+        }
+    }
+}
+
+/// `name verb ...` of a DATASET command with the verb (`COLUMNS`, `ADD COLUMN`,
+/// `SET TTL`, ...) in upper case, so handlers can split on it
+fn canonical_dataset_command(rest: &str) -> String {
+    let tokens: Vec<Token> = Lexer::new(rest).take(3).map_while(Result::ok).collect();
+    let mut end = match tokens.get(1) {
+        Some(verb) => verb.end(),
+        None => return rest.trim_start().to_string(),
+    };
+    let second = match tokens[1].text.to_ascii_uppercase().as_str() {
+        "ADD" | "DROP" | "RENAME" | "ALTER" => "COLUMN",
+        "SET" => "TTL",
+        _ => "",
+    };
+    if tokens
+        .get(2)
+        .is_some_and(|t| !second.is_empty() && t.is_keyword(second))
+    {
+        end = tokens[2].end();
+    }
+    let verb_start = tokens[1].offset;
+    format!(
+        "{}{}{}",
+        rest[..verb_start].trim_start(),
+        rest[verb_start..end].to_ascii_uppercase(),
+        &rest[end..]
+    )
+}
+
+/// Longest chain of macros whose bodies call further macros
+const MAX_MACRO_DEPTH: usize = 16;
+
+/// A macro made by `DEFINE MACRO name(params) AS (statement)`. Each `$param`
+/// of the body stands for the source text of the matching argument.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    pub params: Vec<String>,
+    pub body: String,
+}
+
+/// Parse `name[(param, ...)] AS (statement)`, the rest of a DEFINE MACRO command
+pub fn parse_macro_definition(text: &str, line_no: usize) -> Result<(String, Macro), DslError> {
+    const USAGE: &str = "Expected: DEFINE MACRO name(param, ...) AS (statement)";
+    let mut p = Parser::new(text, line_no)?;
+    let name = match p.advance() {
+        Some(t) if t.kind == TokenKind::Word => t.text.to_string(),
+        _ => return Err(p.error(USAGE)),
+    };
+    if is_command_word(&name) {
+        return Err(p.error(format!("Macro name '{}' is a command keyword", name)));
+    }
+
+    let mut params: Vec<String> = Vec::new();
+    if p.at_symbol("(") {
+        for param in p.parenthesized_list(USAGE)? {
+            if param.is_empty() || !param.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(p.error(format!("Invalid macro parameter '{}'", param)));
+            }
+            if params.iter().any(|q| q == param) {
+                return Err(p.error(format!("Duplicate macro parameter '{}'", param)));
+            }
+            params.push(param.to_string());
+        }
+    }
+    p.expect_keyword("AS", USAGE)?;
+    let body = p.rest().trim();
+    if !p.at_symbol("(") {
+        return Err(p.error(USAGE));
+    }
+    p.parenthesized_list(USAGE)?;
+    p.expect_end()?;
+    let body = body[1..body.len() - 1].trim().to_string();
+    if body.is_empty() {
+        return Err(p.error(USAGE));
+    }
+    if let Some(unknown) = query_parameters(&body)
+        .into_iter()
+        .find(|name| !params.contains(name))
+    {
+        return Err(p.error(format!(
+            "Macro '{}' has no parameter '{}' (parameters: {})",
+            name,
+            unknown,
+            params.join(", ")
+        )));
+    }
+    Ok((name, Macro { params, body }))
+}
+
+/// `DATASET name MASK COLUMN col USING expr [EXCEPT ROLES role, ...]`, or
+/// `DATASET name UNMASK COLUMN col` (no policy)
+#[derive(Debug, Clone)]
+pub struct MaskStatement {
+    pub dataset: String,
+    pub column: String,
+    pub policy: Option<MaskingPolicy>,
+}
+
+/// Parse a DATASET ... MASK/UNMASK COLUMN command
+pub fn parse_mask_statement(text: &str, line_no: usize) -> Result<MaskStatement, DslError> {
+    const USAGE: &str = "Expected: DATASET name MASK COLUMN col USING expr [EXCEPT ROLES role, ...] or DATASET name UNMASK COLUMN col";
+    let mut p = Parser::new(text, line_no)?;
+    p.expect_keyword("DATASET", USAGE)?;
+    let dataset = p.name("a dataset name")?;
+    let mask = p.eat_keyword("MASK");
+    if !mask {
+        p.expect_keyword("UNMASK", USAGE)?;
+    }
+    p.expect_keyword("COLUMN", USAGE)?;
+    let column = p.name("a column name")?;
+
+    let policy = if mask {
+        p.expect_keyword("USING", USAGE)?;
+        let start = p.pos;
+        p.additive()?;
+        let expression = p.text_since(start).trim().to_string();
+        let mut exempt_roles = Vec::new();
+        if p.eat_keywords(&["EXCEPT", "ROLES"]) || p.eat_keywords(&["EXCEPT", "ROLE"]) {
+            loop {
+                exempt_roles.push(p.name("a role name")?);
+                if !p.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        Some(MaskingPolicy {
+            expression,
+            exempt_roles,
+        })
+    } else {
+        None
+    };
+    p.expect_end()?;
+    Ok(MaskStatement {
+        dataset,
+        column,
+        policy,
+    })
+}
+
+/// Expand a statement that calls a macro, `name(arg, ...)` or a bare `name`
+/// for a macro without parameters, into the macro's body with the arguments
+/// in place of its parameters. The body may itself call a macro. `None` when
+/// the statement is not a macro call.
+pub fn expand_macros(
+    line: &str,
+    macros: &HashMap<String, Macro>,
+    line_no: usize,
+) -> Result<Option<String>, DslError> {
+    if macros.is_empty() {
+        return Ok(None);
+    }
+    let mut expanded: Option<String> = None;
+    for _ in 0..MAX_MACRO_DEPTH {
+        let current = expanded.as_deref().unwrap_or(line);
+        match expand_macro_call(current, macros, line_no)? {
+            Some(next) => expanded = Some(next),
+            None => return Ok(expanded),
+        }
+    }
+    Err(DslError::Parse {
+        line: line_no,
+        msg: format!(
+            "Macro expansion exceeds {} levels (recursive macro?)",
+            MAX_MACRO_DEPTH
+        ),
+    })
+}
+
+fn expand_macro_call(
+    line: &str,
+    macros: &HashMap<String, Macro>,
+    line_no: usize,
+) -> Result<Option<String>, DslError> {
+    let line = line.trim();
+    let definition = match Lexer::new(line).next() {
+        Some(Ok(first)) if first.kind == TokenKind::Word => macros.get(first.text),
+        _ => None,
+    };
+    let Some(definition) = definition else {
+        return Ok(None);
+    };
+
+    let mut p = Parser::new(line, line_no)?;
+    let name = p.advance().map_or("", |t| t.text);
+    let args = if p.at_end() {
+        Vec::new()
+    } else if p.at_symbol("(") {
+        p.parenthesized_list(&format!("call of macro '{}'", name))?
+    } else {
+        // `name.add_column(...)` and the like are not calls
+        return Ok(None);
+    };
+    p.expect_end()?;
+    if args.len() != definition.params.len() {
+        return Err(p.error(format!(
+            "Macro '{}' expects {} argument(s), got {}",
+            name,
+            definition.params.len(),
+            args.len()
+        )));
+    }
+
+    let bound: HashMap<&str, &str> = definition
+        .params
+        .iter()
+        .map(String::as_str)
+        .zip(args)
+        .collect();
+    Ok(Some(scan_parameters(&definition.body, |param| {
+        bound
+            .get(param)
+            .map_or_else(|| format!("${}", param), |arg| arg.to_string())
+    })))
+}
+
+/// Parse a SELECT statement, with or without leading CTEs
+pub fn parse_select(text: &str, line_no: usize) -> Result<SelectStatement, DslError> {
+    let mut p = Parser::new(text, line_no)?;
+    let select = p.select_with_ctes()?;
+    p.expect_end()?;
+    Ok(select)
+}
+
+/// Parse `DATASET target FROM source clauses`
+pub fn parse_dataset_query(text: &str, line_no: usize) -> Result<DatasetQuery, DslError> {
+    let mut p = Parser::new(text, line_no)?;
+    p.expect_keyword("DATASET", "Expected: DATASET target FROM source ...")?;
+    let query = p.dataset_query()?;
+    p.expect_end()?;
+    Ok(query)
+}
+
+/// Parse a scalar expression such as `price * qty + 1`
+pub fn parse_expression(text: &str, line_no: usize) -> Result<Expr, DslError> {
+    let mut p = Parser::new(text, line_no)?;
+    let expr = p.additive()?;
+    p.expect_end()?;
+    Ok(expr)
+}
+
+/// Parse a FILTER/WHERE/HAVING predicate
+pub fn parse_predicate(text: &str, line_no: usize) -> Result<Expr, DslError> {
+    let mut p = Parser::new(text, line_no)?;
+    let expr = p.predicate()?;
+    p.expect_end()?;
+    Ok(expr)
+}
+
+/// Top-level `AND`s become separate filters, so the one next to the scan
+/// can still be answered from an index
+pub fn split_conjuncts(expr: Expr, out: &mut Vec<Expr>) {
+    match expr {
+        Expr::BinaryExpr { left, op, right } if op == "AND" => {
+            split_conjuncts(*left, out);
+            split_conjuncts(*right, out);
+        }
+        other => out.push(other),
+    }
+}
+
+fn binary_expr(left: Expr, op: &str, right: Expr) -> Expr {
+    Expr::BinaryExpr {
+        left: Box::new(left),
+        op: op.to_string(),
+        right: Box::new(right),
+    }
+}
+
+/// Operator of the placeholder condition `expr IN SUBQUERY <index>` that
+/// stands for `expr IN (SELECT ...)` until its clause takes the subquery;
+/// negated, it reads `NOT IN SUBQUERY`
+const IN_SUBQUERY: &str = "IN SUBQUERY";
+
+/// Words that start a query clause
+const CLAUSE_KEYWORDS: [&str; 9] = [
+    "FILTER",
+    "WHERE",
+    "ORDER",
+    "LIMIT",
+    "SAMPLE",
+    "GROUP",
+    "HAVING",
+    "SELECT",
+    "RETURNING",
+];
+
+/// Words that end a join source; anything else after the source name is its alias
+const JOIN_SOURCE_END: [&str; 12] = [
+    "JOIN", "INNER", "LEFT", "ASOF", "ON", "FILTER", "WHERE", "ORDER", "LIMIT", "SAMPLE", "GROUP",
+    "HAVING",
+];
+
+const COMPARISON_OPS: [&str; 7] = [">=", "<=", "!=", "<>", "=", ">", "<"];
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    line_no: usize,
+    /// `IN (SELECT ...)` subqueries, taken by the clause that owns them. A
+    /// condition refers to one by index (see `IN_SUBQUERY`).
+    subqueries: Vec<Option<SelectStatement>>,
+    /// Inside HAVING, where aggregate calls are operands of conditions
+    in_having: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str, line_no: usize) -> Result<Self, DslError> {
+        let tokens = tokenize(src).map_err(|msg| DslError::Parse { line: line_no, msg })?;
+        Ok(Self {
+            src,
+            tokens,
+            pos: 0,
+            line_no,
+            subqueries: Vec::new(),
+            in_having: false,
+        })
+    }
+
+    fn error(&self, msg: impl Into<String>) -> DslError {
+        DslError::Parse {
+            line: self.line_no,
+            msg: msg.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn advance(&mut self) -> Option<Token<'a>> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    /// Source text from the current token on
+    fn rest(&self) -> &'a str {
+        self.peek().map_or("", |t| &self.src[t.offset..])
+    }
+
+    /// Source text of the tokens in `start..self.pos`
+    fn text_since(&self, start: usize) -> &'a str {
+        match (
+            self.tokens.get(start),
+            self.tokens.get(self.pos.wrapping_sub(1)),
+        ) {
+            (Some(first), Some(last)) if self.pos > start => &self.src[first.offset..last.end()],
+            _ => "",
+        }
+    }
+
+    fn at_keyword(&self, kw: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_keyword(kw))
+    }
+
+    fn at_keyword_in(&self, kws: &[&str]) -> bool {
+        kws.iter().any(|kw| self.at_keyword(kw))
+    }
+
+    fn at_symbol(&self, sym: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_symbol(sym))
+    }
+
+    fn eat_keyword(&mut self, kw: &str) -> bool {
+        let found = self.at_keyword(kw);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Consume the keywords `kws` only if all of them come next
+    fn eat_keywords(&mut self, kws: &[&str]) -> bool {
+        let found = kws
+            .iter()
+            .enumerate()
+            .all(|(i, kw)| self.peek_at(i).is_some_and(|t| t.is_keyword(kw)));
+        if found {
+            self.pos += kws.len();
+        }
+        found
+    }
+
+    fn eat_symbol(&mut self, sym: &str) -> bool {
+        let found = self.at_symbol(sym);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, kw: &str, msg: &str) -> Result<(), DslError> {
+        if self.eat_keyword(kw) {
+            Ok(())
+        } else {
+            Err(self.error(msg))
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), DslError> {
+        if self.peek().is_some() {
+            return Err(self.error(format!("Unexpected input: {}", self.rest())));
+        }
+        // Subqueries nobody took sit under OR/NOT or outside a query's filters
+        if self.subqueries.iter().any(Option::is_some) {
+            return Err(self.error(
+                "IN (SELECT ...) is only supported as a FILTER/WHERE condition combined with AND",
+            ));
+        }
+        Ok(())
+    }
+
+    /// `( ... )`: the source text between the parentheses, split at the
+    /// commas that are not nested in brackets; `()` is an empty list
+    fn parenthesized_list(&mut self, what: &str) -> Result<Vec<&'a str>, DslError> {
+        if !self.eat_symbol("(") {
+            return Err(self.error(format!("Expected '(' in {}", what)));
+        }
+        let mut items = Vec::new();
+        let mut depth = 0;
+        let mut start = self.pos;
+        loop {
+            let Some(token) = self.peek().copied() else {
+                return Err(self.error(format!("Unclosed '(' in {}", what)));
+            };
+            if token.is_symbol("(") || token.is_symbol("[") {
+                depth += 1;
+            } else if depth > 0 && (token.is_symbol(")") || token.is_symbol("]")) {
+                depth -= 1;
+            } else if depth == 0 && (token.is_symbol(",") || token.is_symbol(")")) {
+                let item = self.text_since(start);
+                if !(token.is_symbol(")") && items.is_empty() && item.is_empty()) {
+                    items.push(item);
+                }
+                self.pos += 1;
+                if token.is_symbol(")") {
+                    return Ok(items);
+                }
+                start = self.pos;
+                continue;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// `word[.word...]`, e.g. a dataset, `alias.column` or `system.audit_log`
+    fn name(&mut self, what: &str) -> Result<String, DslError> {
+        let start = self.pos;
+        match self.advance() {
+            Some(t) if t.kind == TokenKind::Word => {}
+            _ => {
+                self.pos = start;
+                return Err(self.error(format!(
+                    "Expected {}, found: {}",
+                    what,
+                    self.rest_or_end()
+                )));
+            }
+        }
+        while self.at_symbol(".") && self.peek_at(1).is_some_and(|t| t.kind == TokenKind::Word) {
+            self.pos += 2;
+        }
+        Ok(self.text_since(start).to_string())
+    }
+
+    fn rest_or_end(&self) -> &'a str {
+        if self.at_end() {
+            "end of input"
+        } else {
+            self.rest()
+        }
+    }
+
+    // ----- Queries -----
+
+    fn select_with_ctes(&mut self) -> Result<SelectStatement, DslError> {
+        let mut ctes: Vec<(String, SelectStatement)> = Vec::new();
+        if self.eat_keyword("WITH") {
+            let syntax_err = |p: &Self, msg: &str| {
+                p.error(format!(
+                    "{} (expected: WITH name AS (SELECT ...) SELECT ...)",
+                    msg
+                ))
+            };
+            loop {
+                let name = match self.advance() {
+                    Some(t) if t.kind == TokenKind::Word => t.text.to_string(),
+                    other => {
+                        let name = other.map_or("", |t| t.text);
+                        return Err(syntax_err(self, &format!("Invalid CTE name '{}'", name)));
+                    }
+                };
+                if !self.eat_keyword("AS") {
+                    return Err(syntax_err(self, "Missing AS in WITH clause"));
+                }
+                if ctes.iter().any(|(n, _)| *n == name) {
+                    return Err(syntax_err(self, &format!("Duplicate CTE name '{}'", name)));
+                }
+                if !self.eat_symbol("(") {
+                    return Err(syntax_err(
+                        self,
+                        &format!("CTE '{}' must be parenthesized", name),
+                    ));
+                }
+                if !self.at_keyword("SELECT") {
+                    return Err(syntax_err(
+                        self,
+                        &format!("CTE '{}' must be a SELECT", name),
+                    ));
+                }
+                let body = self.select()?;
+                if !self.eat_symbol(")") {
+                    return Err(syntax_err(
+                        self,
+                        &format!("Unclosed parenthesis in CTE '{}'", name),
+                    ));
+                }
+                ctes.push((name, body));
+
+                if self.eat_symbol(",") {
+                    continue;
+                }
+                if self.at_keyword("SELECT") {
+                    break;
+                }
+                return Err(syntax_err(self, "Expected ',' or SELECT after CTE"));
+            }
+        }
+        let mut select = self.select()?;
+        select.ctes = ctes;
+        Ok(select)
+    }
+
+    /// `SELECT items FROM source clauses`, stopping before a closing `)`
+    fn select(&mut self) -> Result<SelectStatement, DslError> {
+        self.expect_keyword("SELECT", "Expected SELECT")?;
+        let distinct = self.eat_keyword("DISTINCT");
+        if self.at_keyword("FROM") {
+            return Err(self.error("Empty SELECT clause"));
+        }
+        let items = self.select_items()?;
+        if !self.eat_keyword("FROM") {
+            return Err(self.error("Expected SELECT ... FROM source ..."));
+        }
+
+        let from = if self.eat_symbol("(") {
+            if !self.at_keyword("SELECT") {
+                return Err(self.error("Subquery in FROM must be a SELECT"));
+            }
+            let subquery = self.select()?;
+            if !self.eat_symbol(")") {
+                return Err(self.error("Unclosed parenthesis in FROM subquery"));
+            }
+            // Optional alias: FROM (SELECT ...) [AS] name
+            let alias = if self.eat_keyword("AS")
+                || self.peek().is_some_and(|t| {
+                    t.kind == TokenKind::Word && !self.at_keyword_in(&CLAUSE_KEYWORDS)
+                }) {
+                Some(self.name("subquery alias")?)
+            } else {
+                None
+            };
+            FromClause::Subquery {
+                query: Box::new(subquery),
+                alias,
+            }
+        } else {
+            self.source_clause()?
+        };
+
+        let mut clauses = self.clauses(false)?;
+        clauses.distinct = distinct;
+        Ok(SelectStatement {
+            ctes: Vec::new(),
+            items,
+            from,
+            clauses,
+        })
+    }
+
+    fn source_clause(&mut self) -> Result<FromClause, DslError> {
+        let name = self.source_name()?;
+
+        if self.eat_keyword("VERSION") {
+            let version = self
+                .advance()
+                .filter(|t| t.kind == TokenKind::Number)
+                .and_then(|t| t.text.parse::<u32>().ok())
+                .ok_or_else(|| {
+                    self.error(format!(
+                        "Invalid dataset version: {}",
+                        self.text_since(self.pos - 1)
+                    ))
+                })?;
+            return Ok(FromClause::Source {
+                name,
+                version: Some(VersionSpec::Number(version)),
+            });
+        }
+        if self.eat_keywords(&["AS", "OF"]) {
+            let ts = self.timestamp()?;
+            return Ok(FromClause::Source {
+                name,
+                version: Some(VersionSpec::AsOf(ts)),
+            });
+        }
+
+        // A join starts after the first source and its optional alias
+        let alias_len = if self.at_keyword("AS") {
+            2
+        } else if self.peek().is_some_and(|t| {
+            t.kind == TokenKind::Word && !JOIN_SOURCE_END.iter().any(|kw| t.is_keyword(kw))
+        }) {
+            1
+        } else {
+            0
+        };
+        let joins_next = self.peek_at(alias_len).is_some_and(|t| {
+            ["JOIN", "INNER", "LEFT", "ASOF"]
+                .iter()
+                .any(|kw| t.is_keyword(kw))
+        });
+        if !joins_next {
+            return Ok(FromClause::Source {
+                name,
+                version: None,
+            });
+        }
+
+        let first = self.join_source(name)?;
+        let mut joins: Vec<JoinClause> = Vec::new();
+        loop {
+            let asof = self.eat_keyword("ASOF");
+            let join_type = if self.eat_keyword("JOIN") || self.eat_keywords(&["INNER", "JOIN"]) {
+                JoinType::Inner
+            } else if self.eat_keywords(&["LEFT", "JOIN"])
+                || self.eat_keywords(&["LEFT", "OUTER", "JOIN"])
+            {
+                JoinType::Left
+            } else if asof || self.at_keyword("INNER") || self.at_keyword("LEFT") {
+                return Err(self.error(format!("Expected JOIN: {}", self.rest())));
+            } else {
+                break;
+            };
+
+            let name = self.source_name()?;
+            let source = self.join_source(name)?;
+            let duplicate = std::iter::once(&first)
+                .chain(joins.iter().map(|j| &j.source))
+                .any(|s| s.alias == source.alias);
+            if duplicate {
+                return Err(self.error(format!(
+                    "Duplicate table alias '{}' in JOIN (use: JOIN {} other_alias ...)",
+                    source.alias, source.name
+                )));
+            }
+            if !self.eat_keyword("ON") {
+                return Err(self.error(format!("Expected ON condition after JOIN {}", source.name)));
+            }
+
+            let similarity_call = ["SIMILARITY", "COSINE_SIM"]
+                .iter()
+                .any(|kw| self.at_keyword(kw))
+                && self.peek_at(1).is_some_and(|t| t.is_symbol("("));
+            if similarity_call {
+                if asof {
+                    return Err(self.error("ASOF JOIN cannot use a SIMILARITY condition"));
+                }
+                let (on, threshold) = self.similarity_condition()?;
+                joins.push(JoinClause {
+                    join_type,
+                    source,
+                    on,
+                    asof: None,
+                    similarity: Some(threshold),
+                });
+                continue;
+            }
+
+            let start = self.pos;
+            let lhs = self.name("a column in the JOIN condition");
+            let eq = self.eat_symbol("=");
+            let rhs = self.name("a column in the JOIN condition");
+            let (Ok(lhs), true, Ok(rhs)) = (lhs, eq, rhs) else {
+                self.pos = start;
+                let condition = self.join_condition_text();
+                return Err(self.error(format!(
+                    "JOIN condition must be an equality of two columns: {}",
+                    condition
+                )));
+            };
+            let asof = if asof {
+                Some(self.asof_condition()?)
+            } else {
+                None
+            };
+            joins.push(JoinClause {
+                join_type,
+                source,
+                on: (lhs, rhs),
+                asof,
+                similarity: None,
+            });
+        }
+        Ok(FromClause::Join { first, joins })
+    }
+
+    /// `SIMILARITY(a, b) > threshold` (or `>=`) of a similarity join
+    fn similarity_condition(
+        &mut self,
+    ) -> Result<((String, String), SimilarityThreshold), DslError> {
+        let start = self.pos;
+        self.pos += 2;
+        let lhs = self.name("a vector column in SIMILARITY");
+        let comma = self.eat_symbol(",");
+        let rhs = self.name("a vector column in SIMILARITY");
+        let close = self.eat_symbol(")");
+        let op = [">=", ">"].into_iter().find(|op| self.eat_symbol(op));
+        let negative = self.eat_symbol("-");
+        let min = self
+            .peek()
+            .filter(|t| t.kind == TokenKind::Number)
+            .and_then(|t| t.text.parse::<f32>().ok());
+        match (lhs, comma, rhs, close, op, min) {
+            (Ok(lhs), true, Ok(rhs), true, Some(op), Some(min)) => {
+                self.pos += 1;
+                Ok((
+                    (lhs, rhs),
+                    SimilarityThreshold {
+                        min: if negative { -min } else { min },
+                        inclusive: op == ">=",
+                    },
+                ))
+            }
+            _ => {
+                self.pos = start;
+                let condition = self.join_condition_text();
+                Err(self.error(format!(
+                    "Similarity JOIN needs ON SIMILARITY(left_vector, right_vector) > threshold, got: {}",
+                    condition
+                )))
+            }
+        }
+    }
+
+    /// `AND a >= b` (or `b <= a`) after the key equality of an ASOF JOIN,
+    /// as `(a, b)`
+    fn asof_condition(&mut self) -> Result<(String, String), DslError> {
+        let start = self.pos;
+        let and = self.eat_keyword("AND");
+        let lhs = self.name("a column in the ASOF condition");
+        let op = [">=", "<="].into_iter().find(|op| self.eat_symbol(op));
+        let rhs = self.name("a column in the ASOF condition");
+        match (and, lhs, op, rhs) {
+            (true, Ok(lhs), Some(">="), Ok(rhs)) => Ok((lhs, rhs)),
+            (true, Ok(lhs), Some(_), Ok(rhs)) => Ok((rhs, lhs)),
+            _ => {
+                self.pos = start;
+                let condition = self.join_condition_text();
+                Err(self.error(format!(
+                    "ASOF JOIN needs ON key = key AND left_time >= right_time, got: {}",
+                    condition
+                )))
+            }
+        }
+    }
+
+    /// Text of a malformed ON condition, for the error message
+    fn join_condition_text(&mut self) -> &'a str {
+        let start = self.pos;
+        while !self.at_end() && !self.at_keyword_in(&JOIN_SOURCE_END) {
+            self.pos += 1;
+        }
+        self.text_since(start)
+    }
+
+    /// `name [[AS] alias]` of a join source
+    fn join_source(&mut self, name: String) -> Result<JoinSource, DslError> {
+        let alias = if self.eat_keyword("AS")
+            || self.peek().is_some_and(|t| {
+                t.kind == TokenKind::Word && !JOIN_SOURCE_END.iter().any(|kw| t.is_keyword(kw))
+            }) {
+            self.name("table alias")?
+        } else {
+            name.clone()
+        };
+        Ok(JoinSource { name, alias })
+    }
+
+    /// Dataset or CTE name; `name@n` refers to version `n` of a dataset
+    fn source_name(&mut self) -> Result<String, DslError> {
+        let start = self.pos;
+        self.name("a dataset name")?;
+        if self.at_symbol("@") && self.peek_at(1).is_some_and(|t| t.kind == TokenKind::Number) {
+            self.pos += 2;
+        }
+        Ok(self.text_since(start).to_string())
+    }
+
+    /// Timestamp of `AS OF`: quoted, or a single unquoted word
+    fn timestamp(&mut self) -> Result<chrono::DateTime<chrono::Utc>, DslError> {
+        let ts = match self.peek().copied() {
+            Some(t) if matches!(t.kind, TokenKind::String | TokenKind::QuotedText) => {
+                self.pos += 1;
+                &t.text[1..t.text.len() - 1]
+            }
+            Some(t) => {
+                // Everything up to the next whitespace
+                let start = t.offset;
+                let end = self.src[start..]
+                    .find(char::is_whitespace)
+                    .map_or(self.src.len(), |i| start + i);
+                while self.peek().is_some_and(|t| t.offset < end) {
+                    self.pos += 1;
+                }
+                &self.src[start..end]
+            }
+            None => "",
+        };
+        parse_timestamp(ts).ok_or_else(|| {
+            self.error(format!(
+                "Invalid AS OF timestamp '{}' (expected RFC 3339, e.g. 2024-01-31T12:00:00Z)",
+                ts
+            ))
+        })
+    }
+
+    fn dataset_query(&mut self) -> Result<DatasetQuery, DslError> {
+        let target = self.name("a target dataset name")?;
+        self.expect_keyword("FROM", "Expected: DATASET target FROM source ...")?;
+        let source = self.source_name()?;
+        let clauses = self.clauses(true)?;
+        Ok(DatasetQuery {
+            target,
+            source,
+            clauses,
+        })
+    }
+
+    /// Query clauses in any order. Repeated FILTER/WHERE/HAVING clauses are
+    /// combined; any other repeated clause is an error. Stops at the end or
+    /// before a `)` that closes a subquery.
+    fn clauses(&mut self, allow_select: bool) -> Result<QueryClauses, DslError> {
+        let mut clauses = QueryClauses::default();
+
+        while !self.at_end() && !self.at_symbol(")") {
+            if self.eat_keyword("FILTER") || self.eat_keyword("WHERE") {
+                let mut conditions = Vec::new();
+                split_conjuncts(self.predicate()?, &mut conditions);
+                for condition in conditions {
+                    match condition {
+                        Expr::BinaryExpr { left, op, right } if op.ends_with(IN_SUBQUERY) => {
+                            let Expr::Literal(Value::Int(idx)) = *right else {
+                                unreachable!("subquery conditions hold their index");
+                            };
+                            clauses.in_subqueries.push(InSubquery {
+                                expr: *left,
+                                query: self.subqueries[idx as usize].take().expect("taken once"),
+                                negated: op.starts_with("NOT"),
+                            });
+                        }
+                        other => clauses.filters.push(other),
+                    }
+                }
+            } else if self.eat_keyword("HAVING") {
+                self.in_having = true;
+                let predicate = self.predicate();
+                self.in_having = false;
+                clauses.having.push(predicate?);
+            } else if self.eat_keywords(&["GROUP", "BY"]) {
+                // `ROLLUP(a, b)` is the same as `a, b WITH ROLLUP`
+                let rollup_call =
+                    self.at_keyword("ROLLUP") && self.peek_at(1).is_some_and(|t| t.is_symbol("("));
+                if rollup_call {
+                    self.pos += 2;
+                }
+                let mut keys = Vec::new();
+                loop {
+                    // A column, a SELECT alias or an expression such as `id % 10`
+                    keys.push(self.additive()?);
+                    if !self.eat_symbol(",") {
+                        break;
+                    }
+                }
+                if rollup_call && !self.eat_symbol(")") {
+                    return Err(self.error("Expected: GROUP BY ROLLUP(key, ...)"));
+                }
+                set_clause(&mut clauses.group_by, keys, "GROUP BY", self)?;
+                clauses.rollup = rollup_call || self.eat_keywords(&["WITH", "ROLLUP"]);
+            } else if allow_select && self.eat_keyword("SELECT") {
+                clauses.distinct = self.eat_keyword("DISTINCT");
+                let items = self.select_items()?;
+                set_clause(&mut clauses.select, items, "SELECT", self)?;
+            } else if self.eat_keywords(&["ORDER", "BY"]) {
+                if self.at_end() || self.at_keyword_in(&CLAUSE_KEYWORDS) {
+                    return Err(self.error("Empty ORDER BY clause"));
+                }
+                let start = self.pos;
+                self.additive()?;
+                let column = self.text_since(start).to_string();
+                let ascending = !self.eat_keyword("DESC");
+                if ascending {
+                    self.eat_keyword("ASC");
+                }
+                set_clause(&mut clauses.order_by, (column, ascending), "ORDER BY", self)?;
+            } else if self.eat_keyword("LIMIT") {
+                let n = self
+                    .peek()
+                    .filter(|t| t.kind == TokenKind::Number)
+                    .and_then(|t| t.text.parse::<usize>().ok())
+                    .ok_or_else(|| self.error(format!("Invalid LIMIT: {}", self.rest())))?;
+                self.pos += 1;
+                set_clause(&mut clauses.limit, n, "LIMIT", self)?;
+            } else if self.eat_keyword("SAMPLE") {
+                let sample = self.sample()?;
+                set_clause(&mut clauses.sample, sample, "SAMPLE", self)?;
+            } else {
+                return Err(self.error(format!("Unknown clause: {}", self.rest())));
+            }
+        }
+
+        Ok(clauses)
+    }
+
+    /// `n PERCENT` (0 to 100, fractions allowed) or `n ROWS`
+    fn sample(&mut self) -> Result<Sample, DslError> {
+        let syntax_err = |p: &Self| {
+            p.error(format!(
+                "Invalid SAMPLE: {} (expected: SAMPLE n PERCENT or SAMPLE n ROWS)",
+                p.rest_or_end()
+            ))
+        };
+        let n = match self.peek() {
+            Some(t) if t.kind == TokenKind::Number => t.text,
+            _ => return Err(syntax_err(self)),
+        };
+        self.pos += 1;
+        let sample = if self.eat_keyword("PERCENT") {
+            n.parse::<f64>()
+                .ok()
+                .filter(|p| (0.0..=100.0).contains(p))
+                .map(Sample::Percent)
+        } else if self.eat_keyword("ROWS") {
+            n.parse::<usize>().ok().map(Sample::Rows)
+        } else {
+            self.pos -= 1;
+            return Err(syntax_err(self));
+        };
+        sample.ok_or_else(|| self.error(format!("Invalid SAMPLE size: {}", n)))
+    }
+
+    /// Comma-separated SELECT items: `*`, aggregates, window functions and
+    /// expressions, each optionally named with `AS name`
+    fn select_items(&mut self) -> Result<Vec<Expr>, DslError> {
+        if self.at_end() {
+            return Err(self.error("Empty SELECT clause"));
+        }
+        let mut items = Vec::new();
+        loop {
+            if self.eat_symbol("*") {
+                items.push(Expr::Column("*".to_string()));
+            } else {
+                let item = self.select_item()?;
+                items.push(if self.eat_keyword("AS") {
+                    Expr::Alias {
+                        expr: Box::new(item),
+                        name: self.name("a column name after AS")?,
+                    }
+                } else {
+                    item
+                });
+            }
+            if !self.eat_symbol(",") {
+                return Ok(items);
+            }
+        }
+    }
+
+    /// An aggregate call standing alone, or any expression, optionally
+    /// followed by `IS [NOT] NULL`
+    fn select_item(&mut self) -> Result<Expr, DslError> {
+        let start = self.pos;
+        if let Some(aggregate) = self.aggregate_call()? {
+            if self.at_item_end() {
+                return Ok(aggregate);
+            }
+            self.pos = start;
+        }
+        let expr = self.additive()?;
+        if self.at_keyword("IS") {
+            return self.null_test(expr);
+        }
+        Ok(expr)
+    }
+
+    /// `IS [NOT] NULL` after `lhs`
+    fn null_test(&mut self, lhs: Expr) -> Result<Expr, DslError> {
+        self.expect_keyword("IS", "Expected: expr IS [NOT] NULL")?;
+        let op = if self.eat_keyword("NOT") {
+            "IS NOT"
+        } else {
+            "IS"
+        };
+        self.expect_keyword("NULL", "Expected: expr IS [NOT] NULL")?;
+        Ok(binary_expr(lhs, op, Expr::Literal(Value::Null)))
+    }
+
+    /// `FUNC([DISTINCT] expr)`, `COUNT(*)` or `PERCENTILE(expr, p)`, or `None`
+    /// with nothing consumed when the next tokens are not an aggregate call
+    fn aggregate_call(&mut self) -> Result<Option<Expr>, DslError> {
+        let start = self.pos;
+        let func = self.peek().and_then(|t| {
+            if t.kind != TokenKind::Word || !self.peek_at(1).is_some_and(|n| n.is_symbol("(")) {
+                return None;
+            }
+            match t.text.to_uppercase().as_str() {
+                "PERCENTILE" => Some(AggregateFunction::Percentile(0.0)),
+                "APPROX_QUANTILE" => Some(AggregateFunction::ApproxQuantile(0.0)),
+                name => AggregateFunction::from_name(name),
+            }
+        });
+        if let Some(mut func) = func {
+            self.pos += 2;
+            if func == AggregateFunction::Count && self.eat_keyword("DISTINCT") {
+                func = AggregateFunction::CountDistinct;
+            }
+            let inner = if self.at_symbol("*") && self.peek_at(1).is_some_and(|t| t.is_symbol(")"))
+            {
+                self.pos += 1;
+                Expr::Literal(Value::Int(1))
+            } else {
+                self.additive()?
+            };
+            let name = func.name();
+            if let AggregateFunction::Percentile(p) | AggregateFunction::ApproxQuantile(p) =
+                &mut func
+            {
+                *p = self.percentile_fraction(name)?;
+            }
+            if self.eat_symbol(")") {
+                return Ok(Some(Expr::AggregateExpr {
+                    func,
+                    expr: Box::new(inner),
+                }));
+            }
+            self.pos = start;
+        }
+        Ok(None)
+    }
+
+    /// `, p` closing PERCENTILE(expr, p) or APPROX_QUANTILE(expr, p), with p in [0, 1]
+    fn percentile_fraction(&mut self, name: &str) -> Result<f32, DslError> {
+        let usage = format!("Expected: {}(expr, p) with p between 0 and 1", name);
+        if !self.eat_symbol(",") {
+            return Err(self.error(usage));
+        }
+        let p = self
+            .peek()
+            .filter(|t| t.kind == TokenKind::Number)
+            .and_then(|t| t.text.parse::<f32>().ok())
+            .filter(|p| (0.0..=1.0).contains(p))
+            .ok_or_else(|| self.error(usage))?;
+        self.pos += 1;
+        Ok(p)
+    }
+
+    fn at_item_end(&self) -> bool {
+        self.at_end()
+            || self.at_symbol(",")
+            || self.at_symbol(")")
+            || self.at_keyword("AS")
+            || self.at_keyword("FROM")
+            || self.at_keyword_in(&CLAUSE_KEYWORDS)
+    }
+
+    fn update(&mut self) -> Result<UpdateStatement, DslError> {
+        let usage = "Expected: UPDATE <name> SET <col> = <expr>, ... [WHERE <condition>]";
+        let dataset = self.name("a dataset name").map_err(|_| self.error(usage))?;
+        self.expect_keyword("SET", usage)?;
+        if self.at_end() || self.at_keyword("WHERE") || self.at_keyword("RETURNING") {
+            return Err(self.error("UPDATE needs at least one assignment"));
+        }
+
+        let mut assignments = Vec::new();
+        loop {
+            let start = self.pos;
+            let column = self.name("a column name");
+            if !(column.is_ok() && self.eat_symbol("=")) {
+                self.pos = start;
+                while !self.at_end() && !self.at_symbol(",") && !self.at_keyword("WHERE") {
+                    self.pos += 1;
+                }
+                return Err(self.error(format!(
+                    "Expected <col> = <expr>, got: {}",
+                    self.text_since(start)
+                )));
+            }
+            assignments.push((column?, self.additive()?));
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+
+        let predicate = self.where_clause()?;
+        let returning = self.returning()?;
+        Ok(UpdateStatement {
+            dataset,
+            assignments,
+            predicate,
+            returning,
+        })
+    }
+
+    fn delete(&mut self) -> Result<DeleteStatement, DslError> {
+        let dataset = self.name("a dataset name")?;
+        let predicate = self.where_clause()?;
+        let returning = self.returning()?;
+        Ok(DeleteStatement {
+            dataset,
+            predicate,
+            returning,
+        })
+    }
+
+    fn where_clause(&mut self) -> Result<Option<Expr>, DslError> {
+        if self.eat_keyword("WHERE") {
+            Ok(Some(self.predicate()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// `RETURNING col, ...` (parentheses optional, `*` for every column)
+    fn returning(&mut self) -> Result<Option<Vec<String>>, DslError> {
+        if !self.eat_keyword("RETURNING") {
+            return Ok(None);
+        }
+        let parenthesized = self.eat_symbol("(");
+        let mut columns = Vec::new();
+        while !self.at_end() && !self.at_symbol(")") {
+            if self.eat_symbol("*") {
+                columns.push("*".to_string());
+            } else {
+                columns.push(self.name("a RETURNING column")?);
+            }
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+        if parenthesized && !self.eat_symbol(")") {
+            return Err(self.error("Unclosed parenthesis in RETURNING"));
+        }
+        if columns.is_empty() {
+            return Err(self.error("RETURNING needs at least one column"));
+        }
+        Ok(Some(columns))
+    }
+
+    // ----- Predicates -----
+
+    /// Comparisons combined with `AND`, `OR`, `NOT` and parentheses.
+    /// `OR` binds loosest, then `AND`, then `NOT`.
+    fn predicate(&mut self) -> Result<Expr, DslError> {
+        let mut expr = self.conjunction()?;
+        while self.eat_keyword("OR") {
+            expr = binary_expr(expr, "OR", self.conjunction()?);
+        }
+        Ok(expr)
+    }
+
+    fn conjunction(&mut self) -> Result<Expr, DslError> {
+        let mut expr = self.negation()?;
+        while self.eat_keyword("AND") {
+            expr = binary_expr(expr, "AND", self.negation()?);
+        }
+        Ok(expr)
+    }
+
+    fn negation(&mut self) -> Result<Expr, DslError> {
+        if self.eat_keyword("NOT") {
+            return Ok(self.negation()?.negate());
+        }
+        // `(a OR b)` groups a predicate; `(a + b) > c` starts a condition
+        if self.at_symbol("(") {
+            let start = self.pos;
+            self.pos += 1;
+            if let Ok(inner) = self.predicate() {
+                if self.eat_symbol(")") && !self.at_operator() {
+                    return Ok(inner);
+                }
+            }
+            self.pos = start;
+        }
+        self.condition()
+    }
+
+    /// Whether the next token continues an expression or condition
+    fn at_operator(&self) -> bool {
+        self.peek().is_some_and(|t| {
+            (t.kind == TokenKind::Symbol
+                && (COMPARISON_OPS.contains(&t.text)
+                    || "+-*/%".contains(t.text)
+                    || t.text == "->"
+                    || t.text == "->>"))
+                || ["BETWEEN", "IN", "LIKE", "NOT", "IS"]
+                    .iter()
+                    .any(|kw| t.is_keyword(kw))
+        })
+    }
+
+    /// One condition: `expr op expr`, `expr [NOT] BETWEEN low AND high`,
+    /// `expr [NOT] IN (v1, v2, ...)`, `expr [NOT] LIKE "pattern"`,
+    /// `expr IS [NOT] NULL`, a boolean function such as
+    /// REGEXP_MATCH(col, "pattern"), or a bare Bool column.
+    fn condition(&mut self) -> Result<Expr, DslError> {
+        let start = self.pos;
+        let lhs = self.additive()?;
+        if self.at_keyword("IS") {
+            return self.null_test(lhs);
+        }
+
+        let negated = self.peek().is_some_and(|t| t.is_keyword("NOT"))
+            && self
+                .peek_at(1)
+                .is_some_and(|t| ["BETWEEN", "IN", "LIKE"].iter().any(|kw| t.is_keyword(kw)));
+        if negated {
+            self.pos += 1;
+        }
+
+        let expr = if self.eat_keyword("BETWEEN") {
+            let low = self.additive()?;
+            if !self.eat_keyword("AND") {
+                return Err(self.error(format!(
+                    "Expected BETWEEN low AND high: {}",
+                    self.text_since(start)
+                )));
+            }
+            let high = self.additive()?;
+            binary_expr(
+                binary_expr(lhs.clone(), ">=", low),
+                "AND",
+                binary_expr(lhs, "<=", high),
+            )
+        } else if self.at_keyword("IN")
+            && self.peek_at(1).is_some_and(|t| t.is_symbol("("))
+            && self.peek_at(2).is_some_and(|t| t.is_keyword("SELECT"))
+        {
+            self.pos += 2;
+            let subquery = self.select()?;
+            if !self.eat_symbol(")") {
+                return Err(self.error("Unclosed parenthesis in IN subquery"));
+            }
+            // The clause that owns the condition takes the subquery by index
+            self.subqueries.push(Some(subquery));
+            let idx = Value::Int(self.subqueries.len() as i64 - 1);
+            binary_expr(lhs, IN_SUBQUERY, Expr::Literal(idx))
+        } else if self.eat_keyword("IN") {
+            if !self.eat_symbol("(") {
+                return Err(self.error(format!(
+                    "Expected IN (value, ...): {}",
+                    self.text_since(start)
+                )));
+            }
+            if self.eat_symbol(")") {
+                return Err(self.error(format!("Empty IN list: {}", self.text_since(start))));
+            }
+            let mut expr = binary_expr(lhs.clone(), "=", self.additive()?);
+            while self.eat_symbol(",") {
+                expr = binary_expr(expr, "OR", binary_expr(lhs.clone(), "=", self.additive()?));
+            }
+            if !self.eat_symbol(")") {
+                return Err(self.error(format!(
+                    "Expected IN (value, ...): {}",
+                    self.text_since(start)
+                )));
+            }
+            expr
+        } else if self.eat_keyword("LIKE") {
+            let pattern = match self.peek().copied() {
+                Some(t) if t.kind == TokenKind::String => {
+                    self.pos += 1;
+                    parse_single_value(t.text, self.line_no)?
+                }
+                _ => {
+                    self.pos = (self.pos + 1).min(self.tokens.len());
+                    return Err(self.error(format!(
+                        "LIKE expects a string pattern: {}",
+                        self.text_since(start)
+                    )));
+                }
+            };
+            binary_expr(lhs, "LIKE", Expr::Literal(pattern))
+        } else if let Some(op) = self
+            .peek()
+            .filter(|t| t.kind == TokenKind::Symbol && COMPARISON_OPS.contains(&t.text))
+            .map(|t| if t.text == "<>" { "!=" } else { t.text })
+        {
+            self.pos += 1;
+            let rhs = self.additive()?;
+            return Ok(binary_expr(lhs, op, rhs));
+        } else {
+            return match lhs {
+                // A bare column tests a Bool: `active` means `active = true`
+                Expr::Column(_) => Ok(binary_expr(lhs, "=", Expr::Literal(Value::Bool(true)))),
+                Expr::ScalarFunction {
+                    func: ScalarFunction::RegexpMatch | ScalarFunction::ArrayContains,
+                    ..
+                } => Ok(lhs),
+                _ => Err(self.error(format!(
+                    "Invalid filter condition: {}",
+                    self.text_since(start)
+                ))),
+            };
+        };
+        Ok(if negated { expr.negate() } else { expr })
+    }
+
+    // ----- Expressions -----
+
+    /// `term (('+' | '-') term)*`
+    fn additive(&mut self) -> Result<Expr, DslError> {
+        let mut expr = self.term()?;
+        while let Some(op) = ["+", "-"].into_iter().find(|op| self.at_symbol(op)) {
+            self.pos += 1;
+            expr = binary_expr(expr, op, self.term()?);
+        }
+        Ok(expr)
+    }
+
+    /// `factor (('*' | '/' | '%') factor)*`
+    fn term(&mut self) -> Result<Expr, DslError> {
+        let mut expr = self.factor()?;
+        while let Some(op) = ["*", "/", "%"].into_iter().find(|op| self.at_symbol(op)) {
+            self.pos += 1;
+            expr = binary_expr(expr, op, self.factor()?);
+        }
+        Ok(expr)
+    }
+
+    /// `primary (('->' | '->>') key | '[' index ']')*`: JSON path steps,
+    /// each a quoted object field or an array index (`->` yields JSON,
+    /// `->>` text), and 1-based list subscripts.
+    fn factor(&mut self) -> Result<Expr, DslError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat_symbol("[") {
+                let index = self.additive()?;
+                if !self.eat_symbol("]") {
+                    return Err(self.error(format!("Expected ']': {}", self.rest_or_end())));
+                }
+                expr = Expr::ScalarFunction {
+                    func: ScalarFunction::ElementAt,
+                    args: vec![expr, index],
+                };
+                continue;
+            }
+            let Some(op) = ["->", "->>"].into_iter().find(|op| self.at_symbol(op)) else {
+                break;
+            };
+            self.pos += 1;
+            let key = match self.advance() {
+                Some(t) if matches!(t.kind, TokenKind::String | TokenKind::QuotedText) => {
+                    Value::String(t.text[1..t.text.len() - 1].to_string())
+                }
+                Some(t) if t.kind == TokenKind::Number => Value::Int(
+                    t.text
+                        .parse()
+                        .map_err(|_| self.error(format!("Invalid JSON array index: {}", t.text)))?,
+                ),
+                _ => {
+                    return Err(self.error(format!(
+                        "Expected a quoted key or an array index after {}",
+                        op
+                    )))
+                }
+            };
+            let func = if op == "->" {
+                ScalarFunction::JsonGet
+            } else {
+                ScalarFunction::JsonGetText
+            };
+            expr = Expr::ScalarFunction {
+                func,
+                args: vec![expr, Expr::Literal(key)],
+            };
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, DslError> {
+        let Some(token) = self.peek().copied() else {
+            return Err(self.error("Unexpected end of expression"));
+        };
+        match token.kind {
+            TokenKind::Number | TokenKind::String => {
+                self.pos += 1;
+                Ok(Expr::Literal(parse_single_value(token.text, self.line_no)?))
+            }
+            TokenKind::Word => self.word_factor(),
+            TokenKind::Param => Err(self.error(format!("Unbound parameter {}", token.text))),
+            TokenKind::QuotedText => Err(self.error(format!(
+                "Invalid value: {} (strings use double quotes)",
+                token.text
+            ))),
+            TokenKind::Symbol => match token.text {
+                "(" => {
+                    self.pos += 1;
+                    let inner = self.additive()?;
+                    if !self.eat_symbol(")") {
+                        return Err(self.error(format!("Expected ')': {}", self.rest_or_end())));
+                    }
+                    Ok(inner)
+                }
+                "[" => {
+                    let start = self.pos;
+                    self.skip_brackets()?;
+                    let literal = self.text_since(start);
+                    Ok(Expr::Literal(parse_single_value(literal, self.line_no)?))
+                }
+                "-" if self.peek_at(1).is_some_and(|t| t.kind == TokenKind::Number) => {
+                    // Negative literals keep the full i64 range
+                    let number = self.tokens[self.pos + 1].text;
+                    self.pos += 2;
+                    let value = parse_single_value(&format!("-{}", number), self.line_no)?;
+                    Ok(Expr::Literal(value))
+                }
+                "-" => Err(self.error("Unary operators not supported yet")),
+                _ => Err(self.error(format!("Unexpected '{}' in expression", token.text))),
+            },
+        }
+    }
+
+    /// `TIMESTAMP "..."` or `INTERVAL "..."` (an Int of seconds); `None`
+    /// when the keyword is not followed by a quoted string, so it names a column
+    fn typed_literal(&mut self, keyword: &Token) -> Result<Option<Value>, DslError> {
+        let Some(quoted) = self
+            .peek_at(1)
+            .filter(|t| matches!(t.kind, TokenKind::String | TokenKind::QuotedText))
+            .copied()
+        else {
+            return Ok(None);
+        };
+        self.pos += 2;
+        let text = &quoted.text[1..quoted.text.len() - 1];
+        let value = if keyword.is_keyword("TIMESTAMP") {
+            parse_timestamp(text)
+                .map(Value::Timestamp)
+                .ok_or_else(|| self.error(format!("Invalid timestamp: '{}'", text)))?
+        } else {
+            Value::Int(parse_interval(text).map_err(|msg| self.error(msg))?)
+        };
+        Ok(Some(value))
+    }
+
+    /// `CASE WHEN cond THEN value ... [ELSE value] END`, or the simple form
+    /// `CASE expr WHEN v THEN value ... END` comparing `expr = v`. Without
+    /// ELSE the result is NULL when no condition holds.
+    fn case_expr(&mut self) -> Result<Expr, DslError> {
+        let usage = "Expected: CASE WHEN <condition> THEN <value> ... [ELSE <value>] END";
+        self.expect_keyword("CASE", usage)?;
+        let operand = if self.at_keyword("WHEN") {
+            None
+        } else {
+            Some(self.additive()?)
+        };
+        let mut args = Vec::new();
+        while self.eat_keyword("WHEN") {
+            let condition = match &operand {
+                Some(lhs) => binary_expr(lhs.clone(), "=", self.additive()?),
+                None => self.predicate()?,
+            };
+            self.expect_keyword("THEN", usage)?;
+            args.push(condition);
+            args.push(self.additive()?);
+        }
+        if args.is_empty() {
+            return Err(self.error(usage));
+        }
+        args.push(if self.eat_keyword("ELSE") {
+            self.additive()?
+        } else {
+            Expr::Literal(Value::Null)
+        });
+        self.expect_keyword("END", usage)?;
+        Ok(Expr::ScalarFunction {
+            func: ScalarFunction::Case,
+            args,
+        })
+    }
+
+    /// `CAST(expr AS type)` to INT, FLOAT, STRING, BOOL, TIMESTAMP or JSON
+    fn cast_expr(&mut self) -> Result<Expr, DslError> {
+        let usage = "Expected: CAST(expr AS INT | FLOAT | STRING | BOOL | TIMESTAMP | JSON)";
+        self.pos += 2;
+        let expr = self.additive()?;
+        self.expect_keyword("AS", usage)?;
+        let target = match self.advance().map(|t| t.text.to_uppercase()).as_deref() {
+            Some("INT" | "INTEGER" | "BIGINT") => ValueType::Int,
+            Some("FLOAT" | "DOUBLE" | "REAL") => ValueType::Float,
+            Some("STRING" | "TEXT" | "VARCHAR") => ValueType::String,
+            Some("BOOL" | "BOOLEAN") => ValueType::Bool,
+            Some("TIMESTAMP") => ValueType::Timestamp,
+            Some("JSON") => ValueType::Json,
+            _ => return Err(self.error(usage)),
+        };
+        if !self.eat_symbol(")") {
+            return Err(self.error(usage));
+        }
+        Ok(Expr::ScalarFunction {
+            func: ScalarFunction::Cast(target),
+            args: vec![expr],
+        })
+    }
+
+    /// Skip a balanced `[...]` literal
+    fn skip_brackets(&mut self) -> Result<(), DslError> {
+        let mut depth = 0;
+        while let Some(token) = self.advance() {
+            if token.is_symbol("[") {
+                depth += 1;
+            } else if token.is_symbol("]") {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            }
+        }
+        Err(self.error("Unclosed '[' in literal"))
+    }
+
+    /// A keyword literal, a function call or a column
+    fn word_factor(&mut self) -> Result<Expr, DslError> {
+        if self.in_having {
+            if let Some(aggregate) = self.aggregate_call()? {
+                return Ok(aggregate);
+            }
+        }
+        let token = self.tokens[self.pos];
+        if token.is_keyword("CASE") {
+            return self.case_expr();
+        }
+        if token.is_keyword("CAST") && self.peek_at(1).is_some_and(|t| t.is_symbol("(")) {
+            return self.cast_expr();
+        }
+        if !self.peek_at(1).is_some_and(|t| t.is_symbol("(")) {
+            if token.is_keyword("true") || token.is_keyword("false") {
+                self.pos += 1;
+                return Ok(Expr::Literal(Value::Bool(token.is_keyword("true"))));
+            }
+            if token.is_keyword("NULL") {
+                self.pos += 1;
+                return Ok(Expr::Literal(Value::Null));
+            }
+            if token.is_keyword("TIMESTAMP") || token.is_keyword("INTERVAL") {
+                if let Some(literal) = self.typed_literal(&token)? {
+                    return Ok(Expr::Literal(literal));
+                }
+            }
+            return Ok(Expr::Column(self.name("a column")?));
+        }
+
+        let start = self.pos;
+        self.pos += 2;
+        let name = token.text.to_uppercase();
+        let mut args = Vec::new();
+        let star = self.at_symbol("*") && self.peek_at(1).is_some_and(|t| t.is_symbol(")"));
+        if star {
+            self.pos += 1;
+        } else if !self.at_symbol(")") {
+            loop {
+                // The first argument of WHERE is a condition
+                args.push(if name == "WHERE" && args.is_empty() {
+                    self.predicate()?
+                } else {
+                    self.additive()?
+                });
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        if !self.eat_symbol(")") {
+            return Err(self.error(format!(
+                "Expected ')' to close {}(: {}",
+                token.text,
+                self.rest_or_end()
+            )));
+        }
+
+        if self.eat_keyword("OVER") {
+            return self.window_function(&name, args);
+        }
+        let func = match name.as_str() {
+            "WIDTH_BUCKET" => ScalarFunction::WidthBucket,
+            "REGEXP_MATCH" => ScalarFunction::RegexpMatch,
+            "REGEXP_EXTRACT" => ScalarFunction::RegexpExtract,
+            "WHERE" => ScalarFunction::Where,
+            "CUMSUM" => ScalarFunction::CumSum,
+            "CUMPROD" => ScalarFunction::CumProd,
+            "DIFF" => ScalarFunction::Diff,
+            "ROLLING_MEAN" => ScalarFunction::RollingMean,
+            "COSINE_SIM" => ScalarFunction::CosineSim,
+            "DOT" => ScalarFunction::Dot,
+            "L2_DIST" => ScalarFunction::L2Dist,
+            "NORM" => ScalarFunction::Norm,
+            "UPPER" => ScalarFunction::Upper,
+            "LOWER" => ScalarFunction::Lower,
+            "LENGTH" => ScalarFunction::Length,
+            "SUBSTR" => ScalarFunction::Substr,
+            "CONCAT" => ScalarFunction::Concat,
+            "ABS" => ScalarFunction::Abs,
+            "ROUND" => ScalarFunction::Round,
+            "FLOOR" => ScalarFunction::Floor,
+            "SQRT" => ScalarFunction::Sqrt,
+            "LOG" => ScalarFunction::Log,
+            "EXP" => ScalarFunction::Exp,
+            "NOW" => ScalarFunction::Now,
+            "DATE_TRUNC" => ScalarFunction::DateTrunc,
+            "COALESCE" | "IFNULL" => ScalarFunction::Coalesce,
+            "JSON_GET" => ScalarFunction::JsonGet,
+            "JSON_GET_TEXT" => ScalarFunction::JsonGetText,
+            "ELEMENT_AT" => ScalarFunction::ElementAt,
+            "ARRAY_CONTAINS" => ScalarFunction::ArrayContains,
+            "UNNEST" => ScalarFunction::Unnest,
+            "HASH" => ScalarFunction::Hash,
+            "MASK_EMAIL" => ScalarFunction::MaskEmail,
+            "TRUNCATE_IP" => ScalarFunction::TruncateIp,
+            // Other calls (e.g. aggregates inside expressions) name a column
+            _ => return Ok(Expr::Column(self.text_since(start).to_string())),
+        };
+        if star {
+            return Err(self.error(format!("{}(*) is not supported", name)));
+        }
+        // IFNULL(a, b) is COALESCE with exactly two arguments
+        if name == "IFNULL" && args.len() != 2 {
+            return Err(self.error("Expected: IFNULL(expr, fallback)"));
+        }
+        self.check_scalar_args(&func, &args)?;
+        Ok(Expr::ScalarFunction { func, args })
+    }
+
+    fn check_scalar_args(&self, func: &ScalarFunction, args: &[Expr]) -> Result<(), DslError> {
+        match func {
+            ScalarFunction::WidthBucket => {
+                if args.len() != 4 {
+                    return Err(self.error("Expected: WIDTH_BUCKET(expr, min, max, count)"));
+                }
+            }
+            ScalarFunction::RegexpMatch => {
+                if args.len() != 2 {
+                    return Err(self.error("Expected: REGEXP_MATCH(expr, \"pattern\")"));
+                }
+                self.check_regex_pattern(&args[1])?;
+            }
+            ScalarFunction::RegexpExtract => {
+                if args.len() != 3 || !matches!(args[2], Expr::Literal(Value::Int(g)) if g >= 0) {
+                    return Err(self.error("Expected: REGEXP_EXTRACT(expr, \"pattern\", group)"));
+                }
+                self.check_regex_pattern(&args[1])?;
+            }
+            ScalarFunction::Where => {
+                if args.len() != 3 {
+                    return Err(self.error("Expected: WHERE(condition, then, else)"));
+                }
+            }
+            ScalarFunction::CosineSim | ScalarFunction::Dot | ScalarFunction::L2Dist => {
+                if args.len() != 2 {
+                    return Err(self.error(format!("Expected: {}(vector, vector)", func.name())));
+                }
+            }
+            ScalarFunction::CumSum
+            | ScalarFunction::CumProd
+            | ScalarFunction::Diff
+            | ScalarFunction::Norm => {
+                if args.len() != 1 {
+                    return Err(self.error(format!("Expected: {}(vector)", func.name())));
+                }
+            }
+            ScalarFunction::Upper
+            | ScalarFunction::Lower
+            | ScalarFunction::Length
+            | ScalarFunction::MaskEmail
+            | ScalarFunction::TruncateIp => {
+                if args.len() != 1 {
+                    return Err(self.error(format!("Expected: {}(string)", func.name())));
+                }
+            }
+            ScalarFunction::Hash => {
+                if !(1..=2).contains(&args.len()) {
+                    return Err(self.error("Expected: HASH(expr [, salt])"));
+                }
+            }
+            ScalarFunction::Substr => {
+                if !(2..=3).contains(&args.len()) {
+                    return Err(self.error("Expected: SUBSTR(string, start [, length])"));
+                }
+            }
+            ScalarFunction::Concat => {
+                if args.is_empty() {
+                    return Err(self.error("Expected: CONCAT(expr, ...)"));
+                }
+            }
+            ScalarFunction::Round => {
+                if !(1..=2).contains(&args.len()) {
+                    return Err(self.error("Expected: ROUND(number [, digits])"));
+                }
+            }
+            ScalarFunction::Abs
+            | ScalarFunction::Floor
+            | ScalarFunction::Sqrt
+            | ScalarFunction::Log
+            | ScalarFunction::Exp => {
+                if args.len() != 1 {
+                    return Err(self.error(format!("Expected: {}(number)", func.name())));
+                }
+            }
+            ScalarFunction::Coalesce => {
+                if args.is_empty() {
+                    return Err(self.error("Expected: COALESCE(expr, ...)"));
+                }
+            }
+            ScalarFunction::JsonGet | ScalarFunction::JsonGetText => {
+                if args.len() != 2 {
+                    return Err(self.error(format!("Expected: {}(json, key)", func.name())));
+                }
+            }
+            ScalarFunction::ElementAt => {
+                if args.len() != 2 {
+                    return Err(self.error("Expected: ELEMENT_AT(list, index)"));
+                }
+            }
+            ScalarFunction::ArrayContains => {
+                if args.len() != 2 {
+                    return Err(self.error("Expected: ARRAY_CONTAINS(list, value)"));
+                }
+            }
+            ScalarFunction::Unnest => {
+                if args.len() != 1 {
+                    return Err(self.error("Expected: UNNEST(list)"));
+                }
+            }
+            // Built by case_expr and cast_expr, which check their own shape
+            ScalarFunction::Case | ScalarFunction::Cast(_) => {}
+            ScalarFunction::Now => {
+                if !args.is_empty() {
+                    return Err(self.error("Expected: NOW()"));
+                }
+            }
+            ScalarFunction::DateTrunc => {
+                const UNITS: [&str; 7] =
+                    ["second", "minute", "hour", "day", "week", "month", "year"];
+                let unit = match args {
+                    [Expr::Literal(Value::String(unit)), _] => unit.to_lowercase(),
+                    _ => return Err(self.error("Expected: DATE_TRUNC(\"unit\", timestamp)")),
+                };
+                if !UNITS.contains(&unit.as_str()) {
+                    return Err(self.error(format!(
+                        "Unknown DATE_TRUNC unit '{}' (expected one of: {})",
+                        unit,
+                        UNITS.join(", ")
+                    )));
+                }
+            }
+            ScalarFunction::RollingMean => {
+                if args.len() != 2 || !matches!(args[1], Expr::Literal(Value::Int(w)) if w > 0) {
+                    return Err(self.error("Expected: ROLLING_MEAN(vector, window)"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Literal patterns are compiled up front so typos surface as parse errors
+    fn check_regex_pattern(&self, pattern: &Expr) -> Result<(), DslError> {
+        if let Expr::Literal(Value::String(p)) = pattern {
+            crate::query::physical::cached_regex(p)
+                .map_err(|e| self.error(format!("Invalid regex pattern \"{}\": {}", p, e)))?;
+        }
+        Ok(())
+    }
+
+    /// `NTILE(n) OVER ([PARTITION BY col, ...] ORDER BY col [ASC|DESC])` and
+    /// the other window functions; aggregates also take a `ROWS` frame. The
+    /// call has been read up to and including OVER
+    fn window_function(&mut self, name: &str, args: Vec<Expr>) -> Result<Expr, DslError> {
+        let aggregate = AggregateFunction::from_name(name);
+        let func = match name {
+            "NTILE" => WindowFunction::Ntile,
+            "PERCENT_RANK" => WindowFunction::PercentRank,
+            "ROW_NUMBER" => WindowFunction::RowNumber,
+            "RANK" => WindowFunction::Rank,
+            "DENSE_RANK" => WindowFunction::DenseRank,
+            "LAG" => WindowFunction::Lag,
+            "LEAD" => WindowFunction::Lead,
+            _ => match aggregate {
+                Some(agg) => WindowFunction::Aggregate(agg, WindowFrame::Running),
+                None => return Err(self.error(format!("Unknown window function: {}", name))),
+            },
+        };
+        match &func {
+            WindowFunction::Ntile => {
+                if !matches!(args.as_slice(), [Expr::Literal(Value::Int(n))] if *n > 0) {
+                    return Err(self.error("NTILE expects a positive integer: NTILE(n)"));
+                }
+            }
+            WindowFunction::PercentRank
+            | WindowFunction::RowNumber
+            | WindowFunction::Rank
+            | WindowFunction::DenseRank => {
+                if !args.is_empty() {
+                    return Err(self.error(format!(
+                        "{} takes no arguments: {}()",
+                        func.name(),
+                        func.name()
+                    )));
+                }
+            }
+            WindowFunction::Lag | WindowFunction::Lead => {
+                let offset_ok = args
+                    .get(1)
+                    .is_none_or(|a| matches!(a, Expr::Literal(Value::Int(n)) if *n >= 0));
+                if args.is_empty() || args.len() > 3 || !offset_ok {
+                    return Err(self.error(format!(
+                        "Expected: {}(expr [, offset [, default]])",
+                        func.name()
+                    )));
+                }
+            }
+            WindowFunction::Aggregate(agg, _) => {
+                // COUNT(*) arrives without arguments
+                let arity_ok =
+                    args.len() == 1 || (args.is_empty() && *agg == AggregateFunction::Count);
+                if !arity_ok {
+                    return Err(self.error(format!("Expected: {}(expr) OVER (...)", func.name())));
+                }
+            }
+        }
+
+        let usage = "Expected OVER ([PARTITION BY <column>, ...] ORDER BY <column> [DESC])";
+        let parenthesized = self.eat_symbol("(");
+        let mut partition_by = Vec::new();
+        if self.eat_keywords(&["PARTITION", "BY"]) {
+            loop {
+                partition_by.push(self.name("a column")?);
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        if !self.eat_keywords(&["ORDER", "BY"]) {
+            return Err(self.error(usage));
+        }
+        let order_by = self.name("a column")?;
+        let ascending = !self.eat_keyword("DESC");
+        if ascending {
+            self.eat_keyword("ASC");
+        }
+        let func = match func {
+            WindowFunction::Aggregate(agg, _) if self.eat_keyword("ROWS") => {
+                WindowFunction::Aggregate(agg, self.window_frame()?)
+            }
+            other => other,
+        };
+        if parenthesized && !self.eat_symbol(")") {
+            return Err(self.error(format!("Invalid window ORDER BY: {}", self.rest_or_end())));
+        }
+
+        Ok(Expr::WindowFunction {
+            func,
+            args,
+            partition_by,
+            order_by,
+            ascending,
+        })
+    }
+
+    /// After ROWS: `BETWEEN <start> AND <end>`, or `<start>` alone (ending at
+    /// the current row). Bounds are `UNBOUNDED PRECEDING|FOLLOWING`,
+    /// `n PRECEDING|FOLLOWING` and `CURRENT ROW`
+    fn window_frame(&mut self) -> Result<WindowFrame, DslError> {
+        let between = self.eat_keyword("BETWEEN");
+        let preceding = self.frame_bound("PRECEDING")?;
+        let following = if between {
+            self.expect_keyword("AND", "Expected: ROWS BETWEEN <start> AND <end>")?;
+            self.frame_bound("FOLLOWING")?
+        } else {
+            Some(0)
+        };
+        Ok(WindowFrame::Rows {
+            preceding,
+            following,
+        })
+    }
+
+    /// One frame bound on the `direction` side of the current row; `None` is UNBOUNDED
+    fn frame_bound(&mut self, direction: &str) -> Result<Option<usize>, DslError> {
+        if self.eat_keywords(&["CURRENT", "ROW"]) {
+            return Ok(Some(0));
+        }
+        let bound = if self.eat_keyword("UNBOUNDED") {
+            None
+        } else {
+            let n = self
+                .peek()
+                .filter(|t| t.kind == TokenKind::Number)
+                .and_then(|t| t.text.parse::<usize>().ok());
+            if n.is_some() {
+                self.pos += 1;
+            }
+            Some(n.ok_or_else(|| {
+                self.error(format!(
+                    "Invalid window frame bound: {}",
+                    self.rest_or_end()
+                ))
+            })?)
+        };
+        if !self.eat_keyword(direction) {
+            return Err(self.error(format!(
+                "Expected {} in window frame: {}",
+                direction,
+                self.rest_or_end()
+            )));
+        }
+        Ok(bound)
+    }
+}
+
+fn set_clause<T>(
+    slot: &mut Option<T>,
+    value: T,
+    kw: &str,
+    parser: &Parser,
+) -> Result<(), DslError> {
+    if slot.is_some() {
+        return Err(parser.error(format!("Duplicate {} clause", kw)));
+    }
+    *slot = Some(value);
+    Ok(())
+}
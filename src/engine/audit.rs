@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::dataset_legacy::{Dataset, DatasetId};
+use crate::core::tuple::{Field, Schema, Tuple};
+use crate::core::value::{Value, ValueType};
+
+/// Name under which the audit trail can be queried (read-only)
+pub const AUDIT_LOG_DATASET: &str = "system.audit_log";
+
+/// One mutating command, as recorded in the audit trail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// RFC 3339 UTC timestamp
+    pub timestamp: String,
+    /// Who ran the command: masked API key, "anonymous" or "local"
+    pub actor: String,
+    pub database: String,
+    pub command: String,
+    /// Datasets whose rows or columns changed
+    pub datasets: Vec<String>,
+    pub rows_before: i64,
+    pub rows_after: i64,
+    /// Error message of a failed command
+    pub error: Option<String>,
+}
+
+/// Row and column count of every dataset, taken before and after a command
+pub type DatasetShapes = BTreeMap<String, (usize, usize)>;
+
+impl AuditEntry {
+    /// Build an entry from the dataset shapes before and after the command
+    pub fn from_shapes(
+        actor: &str,
+        database: &str,
+        command: &str,
+        before: &DatasetShapes,
+        after: &DatasetShapes,
+        error: Option<String>,
+    ) -> Self {
+        let mut datasets = Vec::new();
+        let (mut rows_before, mut rows_after) = (0, 0);
+        for name in before
+            .keys()
+            .chain(after.keys().filter(|k| !before.contains_key(*k)))
+        {
+            let (old, new) = (before.get(name), after.get(name));
+            if old != new {
+                datasets.push(name.clone());
+                rows_before += old.map_or(0, |s| s.0 as i64);
+                rows_after += new.map_or(0, |s| s.0 as i64);
+            }
+        }
+
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            actor: actor.to_string(),
+            database: database.to_string(),
+            command: command.to_string(),
+            datasets,
+            rows_before,
+            rows_after,
+            error,
+        }
+    }
+}
+
+/// Mask an API key so the audit trail identifies it without storing the secret
+pub fn mask_api_key(key: &str) -> String {
+    let prefix: String = key.chars().take(4).collect();
+    if key.chars().count() > 8 {
+        format!("key:{}****", prefix)
+    } else {
+        "key:****".to_string()
+    }
+}
+
+/// In-memory audit trail, optionally mirrored to a JSON-lines file
+#[derive(Debug)]
+pub struct AuditLog {
+    dataset: Dataset,
+    path: Option<PathBuf>,
+    /// Entries recorded on a session copy, handed back by `merge`
+    pending: Option<Vec<AuditEntry>>,
+}
+
+impl AuditLog {
+    /// Create the log, reloading previous entries from `path` if given
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let mut log = Self {
+            dataset: Dataset::new(
+                DatasetId(0),
+                Arc::new(audit_schema()),
+                Some(AUDIT_LOG_DATASET.to_string()),
+            ),
+            path: None,
+            pending: None,
+        };
+
+        if let Some(path) = &path {
+            if let Ok(file) = fs::File::open(path) {
+                for line in BufReader::new(file).lines().map_while(Result::ok) {
+                    match serde_json::from_str::<AuditEntry>(&line) {
+                        Ok(entry) => log.push_row(&entry),
+                        Err(e) => eprintln!("Warning: Skipping malformed audit record: {}", e),
+                    }
+                }
+            }
+        }
+        log.path = path;
+        log
+    }
+
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+
+    /// Copy for a database session; its new entries are not written to the
+    /// file but kept until the session is merged back
+    pub fn session_copy(&self) -> Self {
+        Self {
+            dataset: self.dataset.clone(),
+            path: None,
+            pending: Some(Vec::new()),
+        }
+    }
+
+    /// Record the entries of a session copy made by `session_copy`
+    pub fn merge(&mut self, session: AuditLog) {
+        for entry in session.pending.unwrap_or_default() {
+            self.record(entry);
+        }
+    }
+
+    pub fn record(&mut self, entry: AuditEntry) {
+        if let Some(pending) = &mut self.pending {
+            pending.push(entry.clone());
+        }
+        if let Some(path) = &self.path {
+            if let Err(e) = append_record(path, &entry) {
+                eprintln!("Warning: Failed to write audit record: {}", e);
+            }
+        }
+        self.push_row(&entry);
+    }
+
+    fn push_row(&mut self, entry: &AuditEntry) {
+        let values = vec![
+            Value::String(entry.timestamp.clone()),
+            Value::String(entry.actor.clone()),
+            Value::String(entry.database.clone()),
+            Value::String(entry.command.clone()),
+            Value::String(entry.datasets.join(",")),
+            Value::Int(entry.rows_before),
+            Value::Int(entry.rows_after),
+            Value::Int(entry.rows_after - entry.rows_before),
+            Value::Bool(entry.error.is_none()),
+            entry.error.clone().map_or(Value::Null, Value::String),
+        ];
+        let tuple = Tuple::new(self.dataset.schema.clone(), values)
+            .expect("audit row matches audit schema");
+        let _ = self.dataset.add_row(tuple);
+    }
+}
+
+fn audit_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("timestamp", ValueType::String),
+        Field::new("actor", ValueType::String),
+        Field::new("database", ValueType::String),
+        Field::new("command", ValueType::String),
+        Field::new("datasets", ValueType::String),
+        Field::new("rows_before", ValueType::Int),
+        Field::new("rows_after", ValueType::Int),
+        Field::new("row_delta", ValueType::Int),
+        Field::new("success", ValueType::Bool),
+        Field::new("error", ValueType::String).nullable(),
+    ])
+}
+
+fn append_record(path: &Path, entry: &AuditEntry) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let record = serde_json::to_string(entry)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", record)
+}
@@ -1,20 +1,107 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
+
+use crate::core::config::NonFinitePolicy;
 use crate::core::dataset_legacy::{Dataset, DatasetId};
 use crate::core::store::{DatasetStore, InMemoryTensorStore};
 use crate::core::tensor::{Shape, Tensor, TensorId};
 use crate::core::tuple::{Schema, Tuple};
 
+use super::audit::{AuditEntry, AuditLog, DatasetShapes, AUDIT_LOG_DATASET};
 use super::error::EngineError;
+use super::jobs::{JobOutput, JobRegistry, JobStatus, JobTask};
+use super::memory::{EvictedDataset, EvictionState, MemoryStats};
 use super::operations::{BinaryOp, TensorKind, UnaryOp};
+use super::plugin::EnginePlugin;
+use super::progress::{QueryGuard, QueryRegistry, QueryStatus};
+use super::transaction::{RollbackReport, Transaction};
 use crate::engine::context::ExecutionContext;
 
-struct NameEntry {
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NameEntry {
     id: TensorId,
     kind: TensorKind,
 }
 
+/// Outcome of one TTL sweep (`TensorDb::reap_expired`)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExpiryReport {
+    pub rows_removed: usize,
+    /// (database, dataset) pairs dropped because their dataset-scoped TTL elapsed
+    pub datasets_dropped: Vec<(String, String)>,
+}
+
+/// Work done by the physical operators of the last executed command(s),
+/// reset with `TensorDb::reset_execution_stats`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionStats {
+    /// Rows read from datasets by scans and index lookups
+    pub rows_scanned: usize,
+    /// Whether a hash or vector index served any of the scans
+    pub index_used: bool,
+}
+
+/// Committed state of a database that read-only commands run against while
+/// writers go on (see `server::sessions`). Datasets and tensor data are
+/// shared with the database, which copies a dataset the first time it
+/// changes it after the snapshot was taken.
+pub struct DatabaseSnapshot {
+    instance: DatabaseInstance,
+}
+
+impl Clone for DatabaseSnapshot {
+    fn clone(&self) -> Self {
+        Self {
+            instance: self.instance.snapshot(),
+        }
+    }
+}
+
+/// Point-in-time copy of a database's datasets and tensors, taken while it is
+/// locked and written out afterwards (see `TensorDb::fork_database`)
+#[derive(Debug, Clone)]
+pub struct DatabaseFork {
+    pub name: String,
+    /// Datasets, tensor-first ones materialized as in SAVE ALL
+    pub datasets: Vec<Dataset>,
+    pub tensors: Vec<(String, Tensor)>,
+}
+
+impl DatabaseFork {
+    /// Write every dataset (with indices) and tensor under `dir` in the
+    /// `ParquetStorage` layout
+    pub fn save(&self, dir: &std::path::Path) -> Result<(), crate::core::storage::StorageError> {
+        use crate::core::storage::{ParquetStorage, StorageEngine};
+
+        let storage = ParquetStorage::new(dir.to_string_lossy().into_owned());
+        for dataset in &self.datasets {
+            storage.save_dataset(dataset)?;
+        }
+        for (name, tensor) in &self.tensors {
+            storage.save_tensor(name, tensor)?;
+        }
+        Ok(())
+    }
+}
+
+/// Last update time, row count and column count of a dataset, which change
+/// whenever its rows or schema do
+pub type DatasetFingerprint = (DateTime<Utc>, usize, usize);
+
+/// Fingerprint of every dataset, used to detect which datasets a command
+/// changed (see `commit_dataset_versions`)
+pub type DatasetFingerprints = BTreeMap<String, DatasetFingerprint>;
+
+fn fingerprint(dataset: &Dataset) -> DatasetFingerprint {
+    (
+        dataset.metadata.updated_at,
+        dataset.rows.len(),
+        dataset.schema.len(),
+    )
+}
+
 /// Individual database instance containing its own stores and name mappings
 pub struct DatabaseInstance {
     pub name: String,
@@ -24,6 +111,16 @@ pub struct DatabaseInstance {
     pub tensor_datasets: crate::core::dataset::DatasetRegistry,
     pub dataset_vars: HashMap<String, String>,
     pub backend: Box<dyn crate::core::backend::ComputeBackend>,
+    /// Named, parameterizable queries (CREATE QUERY / RUN QUERY)
+    pub stored_queries: HashMap<String, String>,
+    /// Statement macros (DEFINE MACRO), expanded before a statement is parsed
+    pub macros: HashMap<String, crate::dsl::parser::Macro>,
+    /// Retained snapshots per dataset, oldest first (`[versioning] retention`)
+    dataset_versions: HashMap<String, VecDeque<Arc<Dataset>>>,
+    /// Recency, sizes and evicted datasets for the `[memory]` budget
+    eviction: EvictionState,
+    /// Undo log of the open `BEGIN` ... `COMMIT` transaction
+    transaction: Option<Transaction>,
 }
 
 impl DatabaseInstance {
@@ -36,11 +133,35 @@ impl DatabaseInstance {
             tensor_datasets: crate::core::dataset::DatasetRegistry::new(),
             dataset_vars: HashMap::new(),
             backend: Box::new(crate::core::backend::CpuBackend::new()),
+            stored_queries: HashMap::new(),
+            macros: HashMap::new(),
+            dataset_versions: HashMap::new(),
+            eviction: EvictionState::default(),
+            transaction: None,
         }
     }
 
     // ... all existing methods of the old TensorDb ...
 
+    /// Copy sharing datasets, retained versions and tensor data with this
+    /// instance; the open transaction stays behind
+    fn snapshot(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            store: self.store.clone(),
+            names: self.names.clone(),
+            dataset_store: self.dataset_store.clone(),
+            tensor_datasets: self.tensor_datasets.clone(),
+            dataset_vars: self.dataset_vars.clone(),
+            backend: Box::new(crate::core::backend::CpuBackend::new()),
+            stored_queries: self.stored_queries.clone(),
+            macros: self.macros.clone(),
+            dataset_versions: self.dataset_versions.clone(),
+            eviction: self.eviction.clone(),
+            transaction: None,
+        }
+    }
+
     pub fn set_dataset_metadata(
         &mut self,
         name: &str,
@@ -69,6 +190,23 @@ impl DatabaseInstance {
         }
     }
 
+    /// Unbind `name`, dropping its tensor unless another name or a column of
+    /// a tensor-first dataset still refers to it
+    pub fn release_tensor(&mut self, name: &str) {
+        let Some(entry) = self.names.remove(name) else {
+            return;
+        };
+        let shared = self.names.values().any(|other| other.id == entry.id)
+            || self.tensor_datasets.list_names().iter().any(|ds_name| {
+                self.tensor_datasets
+                    .get(ds_name)
+                    .is_some_and(|ds| ds.columns.values().any(|&id| id == entry.id))
+            });
+        if !shared {
+            self.store.remove(entry.id);
+        }
+    }
+
     pub fn register_tensor_dataset(&mut self, ds: crate::core::dataset::Dataset) {
         let _ = self.tensor_datasets.register(ds);
     }
@@ -282,6 +420,36 @@ pub struct TensorDb {
     pub config: crate::core::config::EngineConfig,
     databases: HashMap<String, DatabaseInstance>,
     active_db: String,
+    /// Set while the WAL is being replayed so replayed commands are not logged again
+    pub(crate) replaying_wal: bool,
+    audit_log: AuditLog,
+    /// Identity recorded in the audit log for the commands being executed
+    audit_actor: String,
+    /// Client session the commands belong to. Only the session that began a
+    /// transaction may write to, commit or roll back that transaction.
+    session_id: Option<String>,
+    /// Role of the caller, which decides the columns masked in query
+    /// results; `None` (local sessions) sees raw values
+    role: Option<String>,
+    /// Filled by physical operators, which only get `&TensorDb`
+    execution_stats: std::sync::Mutex<ExecutionStats>,
+    /// Generators handed out so far; each gets its own stream of the seed.
+    /// Shared with sessions so they keep drawing distinct streams.
+    rng_streams: Arc<std::sync::atomic::AtomicU64>,
+    /// Commands in flight, shared with sessions so SHOW QUERIES sees them all
+    queries: Arc<QueryRegistry>,
+    /// Registration of the command being executed, advanced by scan operators
+    active_query: Option<QueryGuard>,
+    /// Extensions notified of dataset and query events, shared with sessions
+    plugins: Arc<[Arc<dyn EnginePlugin>]>,
+    /// Databases lent to a session by `detach_session`; an empty instance
+    /// holds their place until `attach_session`
+    detached: std::collections::HashSet<String>,
+    /// Background jobs, shared with sessions so SHOW JOBS sees them all
+    jobs: Arc<JobRegistry>,
+    /// Session over a `DatabaseSnapshot`, whose changes are discarded; it
+    /// leaves finished jobs for the database itself to install
+    snapshot_session: bool,
 }
 
 impl TensorDb {
@@ -291,6 +459,15 @@ impl TensorDb {
     }
 
     pub fn with_config(config: crate::core::config::EngineConfig) -> Self {
+        Self::with_plugins(config, Vec::new())
+    }
+
+    /// Engine whose `plugins` see every event from the start, including the
+    /// commands replayed from the WAL while it recovers
+    pub fn with_plugins(
+        config: crate::core::config::EngineConfig,
+        plugins: Vec<Arc<dyn EnginePlugin>>,
+    ) -> Self {
         let default_name = config.storage.default_db.clone();
         let mut dbs = HashMap::new();
         dbs.insert(
@@ -298,10 +475,28 @@ impl TensorDb {
             DatabaseInstance::new(default_name.clone()),
         );
 
+        let audit_path = config
+            .audit
+            .persist
+            .then(|| config.storage.data_dir.join("audit_log.jsonl"));
+        let jobs = JobRegistry::new(config.execution.finished_jobs_kept);
         let mut db = Self {
             databases: dbs,
             active_db: default_name,
             config,
+            replaying_wal: false,
+            audit_log: AuditLog::new(audit_path),
+            audit_actor: "local".to_string(),
+            session_id: None,
+            role: None,
+            execution_stats: std::sync::Mutex::new(ExecutionStats::default()),
+            rng_streams: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            queries: Arc::new(QueryRegistry::default()),
+            active_query: None,
+            plugins: plugins.into(),
+            detached: std::collections::HashSet::new(),
+            jobs: Arc::new(jobs),
+            snapshot_session: false,
         };
 
         // Try to recover existing databases
@@ -310,9 +505,78 @@ impl TensorDb {
         db
     }
 
+    /// Dataset and tensor storage of database `db_name` on the configured backend
+    pub fn database_storage(
+        &self,
+        db_name: &str,
+    ) -> Result<Box<dyn crate::core::storage::StorageEngine>, crate::core::storage::StorageError>
+    {
+        use crate::core::config::StorageBackend;
+        use crate::core::storage::object_storage::ObjectStoreStorage;
+        use crate::core::storage::{ParquetStorage, ParquetWriteOptions};
+
+        Ok(match self.config.storage.backend {
+            StorageBackend::File => Box::new(
+                ParquetStorage::new(
+                    self.config
+                        .storage
+                        .data_dir
+                        .join(db_name)
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+                .with_options(ParquetWriteOptions::from_config(&self.config.storage)),
+            ),
+            _ => Box::new(ObjectStoreStorage::from_config(&self.config.storage)?.child(db_name)),
+        })
+    }
+
+    /// Where `database_storage(db_name)` keeps its data, for messages
+    pub fn storage_location(&self, db_name: &str) -> String {
+        use crate::core::config::StorageBackend;
+        use crate::core::storage::object_storage::ObjectStoreStorage;
+
+        match self.config.storage.backend {
+            StorageBackend::File => self
+                .config
+                .storage
+                .data_dir
+                .join(db_name)
+                .to_string_lossy()
+                .into_owned(),
+            _ => match ObjectStoreStorage::from_config(&self.config.storage) {
+                Ok(storage) => storage.child(db_name).location().to_string(),
+                Err(_) => db_name.to_string(),
+            },
+        }
+    }
+
     fn recover_databases(&mut self) -> Result<(), EngineError> {
+        use crate::core::config::StorageBackend;
+        use crate::core::storage::object_storage::ObjectStoreStorage;
+
+        // Databases persisted on a remote backend have no directory in data_dir
+        if self.config.storage.backend != StorageBackend::File && self.config.storage.auto_persist
+        {
+            let children = ObjectStoreStorage::from_config(&self.config.storage)
+                .and_then(|storage| storage.list_children());
+            match children {
+                Ok(names) => {
+                    for db_name in names {
+                        self.databases
+                            .entry(db_name.clone())
+                            .or_insert_with(|| DatabaseInstance::new(db_name));
+                    }
+                }
+                Err(e) => eprintln!("Warning: Failed to list databases in storage: {}", e),
+            }
+        }
+
         let data_dir = &self.config.storage.data_dir;
         if !data_dir.exists() {
+            if self.config.storage.auto_persist {
+                self.recover_datasets();
+            }
             return Ok(());
         }
 
@@ -330,9 +594,233 @@ impl TensorDb {
                 }
             }
         }
+
+        self.recover_query_catalogs();
+
+        // Restore the last snapshot, then re-apply commands logged since
+        if self.config.storage.auto_persist || self.config.storage.wal {
+            self.recover_datasets();
+        }
+        if self.config.storage.wal {
+            self.replay_wal();
+        }
         Ok(())
     }
 
+    /// Re-execute logged commands of every database on top of its snapshot
+    fn replay_wal(&mut self) {
+        use crate::core::storage::wal::WriteAheadLog;
+
+        let previous_active = self.active_db.clone();
+        let mut db_names: Vec<String> = self.databases.keys().cloned().collect();
+        db_names.sort();
+
+        self.replaying_wal = true;
+        for db_name in db_names {
+            let wal = WriteAheadLog::for_database(self.config.storage.data_dir.join(&db_name));
+            let commands = match wal.read_all() {
+                Ok(commands) => commands,
+                Err(e) => {
+                    eprintln!("Warning: Failed to read WAL of database '{}': {}", db_name, e);
+                    continue;
+                }
+            };
+
+            self.active_db = db_name.clone();
+            for (i, command) in commands.iter().enumerate() {
+                if let Err(e) = crate::dsl::execute_line(self, command, i + 1) {
+                    eprintln!(
+                        "Warning: Failed to replay WAL record {} of database '{}': {}",
+                        i + 1,
+                        db_name,
+                        e
+                    );
+                }
+            }
+        }
+        self.replaying_wal = false;
+        self.active_db = previous_active;
+    }
+
+    /// Reload persisted datasets of every known database (auto_persist mode).
+    /// With the WAL enabled, checkpointed tensors are reloaded as well.
+    fn recover_datasets(&mut self) {
+        use crate::core::config::StorageBackend;
+
+        let mut db_names: Vec<String> = self.databases.keys().cloned().collect();
+        db_names.sort();
+        for db_name in db_names {
+            if self.config.storage.backend == StorageBackend::File
+                && !self.config.storage.data_dir.join(&db_name).exists()
+            {
+                continue;
+            }
+            let storage = match self.database_storage(&db_name) {
+                Ok(storage) => storage,
+                Err(e) => {
+                    eprintln!(
+                        "Warning: Failed to open storage of database '{}': {}",
+                        db_name, e
+                    );
+                    continue;
+                }
+            };
+            let instance = self
+                .databases
+                .get_mut(&db_name)
+                .expect("database listed above");
+            let names = match storage.list_datasets() {
+                Ok(names) => names,
+                Err(_) => continue,
+            };
+            for name in names {
+                let restored = storage
+                    .load_dataset(&name)
+                    .map_err(|e| e.to_string())
+                    .and_then(|ds| instance.restore_dataset(ds).map_err(|e| e.to_string()));
+                if let Err(e) = restored {
+                    eprintln!(
+                        "Warning: Failed to recover dataset '{}' in database '{}': {}",
+                        name, db_name, e
+                    );
+                }
+            }
+
+            if !self.config.storage.wal {
+                continue;
+            }
+            for name in storage.list_tensors().unwrap_or_default() {
+                let restored = storage
+                    .load_tensor(&name)
+                    .map_err(|e| e.to_string())
+                    .and_then(|t| {
+                        instance
+                            .insert_named(name.clone(), t.shape.clone(), (*t.data).clone())
+                            .map_err(|e| e.to_string())
+                    });
+                if let Err(e) = restored {
+                    eprintln!(
+                        "Warning: Failed to recover tensor '{}' in database '{}': {}",
+                        name, db_name, e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Register a named query in the active database's catalog and persist
+    /// the catalog to data_dir/<db>/queries.json
+    pub fn create_query(&mut self, name: &str, body: &str) -> Result<(), EngineError> {
+        let instance = self.active_instance_mut();
+        if instance.stored_queries.contains_key(name) {
+            return Err(EngineError::InvalidOp(format!(
+                "Query '{}' already exists",
+                name
+            )));
+        }
+        instance
+            .stored_queries
+            .insert(name.to_string(), body.to_string());
+
+        let db_name = self.active_db.clone();
+        let queries = &self.databases[&db_name].stored_queries;
+        if let Err(e) = self.save_catalog(&db_name, "queries.json", queries) {
+            self.active_instance_mut().stored_queries.remove(name);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Get the body of a named query from the active database's catalog
+    pub fn get_query(&self, name: &str) -> Result<&str, EngineError> {
+        self.active_instance()
+            .stored_queries
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| EngineError::InvalidOp(format!("Query '{}' not found", name)))
+    }
+
+    /// Define (or redefine) a macro in the active database and persist the
+    /// database's macros to data_dir/<db>/macros.json
+    pub fn define_macro(
+        &mut self,
+        name: &str,
+        definition: crate::dsl::parser::Macro,
+    ) -> Result<(), EngineError> {
+        let previous = self
+            .active_instance_mut()
+            .macros
+            .insert(name.to_string(), definition);
+
+        let db_name = self.active_db.clone();
+        let macros = &self.databases[&db_name].macros;
+        if let Err(e) = self.save_catalog(&db_name, "macros.json", macros) {
+            let macros = &mut self.active_instance_mut().macros;
+            match previous {
+                Some(previous) => macros.insert(name.to_string(), previous),
+                None => macros.remove(name),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn catalog_path(&self, db_name: &str, file: &str) -> std::path::PathBuf {
+        self.config.storage.data_dir.join(db_name).join(file)
+    }
+
+    /// Write `entries`, sorted by name, to data_dir/<db_name>/<file>
+    fn save_catalog<T: serde::Serialize>(
+        &self,
+        db_name: &str,
+        file: &str,
+        entries: &HashMap<String, T>,
+    ) -> Result<(), EngineError> {
+        let entries: BTreeMap<_, _> = entries.iter().collect();
+        let path = self.catalog_path(db_name, file);
+        let write = || -> Result<(), Box<dyn std::error::Error>> {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, serde_json::to_string_pretty(&entries)?)?;
+            Ok(())
+        };
+        write().map_err(|e| EngineError::InvalidOp(format!("Failed to persist {}: {}", file, e)))
+    }
+
+    /// Load the persisted query catalogs and macros of all discovered databases
+    fn recover_query_catalogs(&mut self) {
+        let names: Vec<String> = self.databases.keys().cloned().collect();
+        for db_name in names {
+            let read = |file: &str| std::fs::read_to_string(self.catalog_path(&db_name, file));
+            let queries = read("queries.json")
+                .ok()
+                .map(|content| serde_json::from_str::<HashMap<String, String>>(&content));
+            let macros = read("macros.json").ok().map(|content| {
+                serde_json::from_str::<HashMap<String, crate::dsl::parser::Macro>>(&content)
+            });
+            let Some(instance) = self.databases.get_mut(&db_name) else {
+                continue;
+            };
+            match queries {
+                Some(Ok(queries)) => instance.stored_queries = queries,
+                Some(Err(e)) => eprintln!(
+                    "Warning: Failed to load query catalog of database '{}': {}",
+                    db_name, e
+                ),
+                None => {}
+            }
+            match macros {
+                Some(Ok(macros)) => instance.macros = macros,
+                Some(Err(e)) => eprintln!(
+                    "Warning: Failed to load macros of database '{}': {}",
+                    db_name, e
+                ),
+                None => {}
+            }
+        }
+    }
+
     /// Get reference to the active database
     pub fn active_instance(&self) -> &DatabaseInstance {
         self.databases
@@ -372,6 +860,36 @@ impl TensorDb {
         Ok(())
     }
 
+    /// Name of the active database
+    pub fn active_database(&self) -> &str {
+        &self.active_db
+    }
+
+    /// Run `f` against database `name`, then switch back to the previously
+    /// active one, so nothing `f` does (not even USE DATABASE) changes the
+    /// database later callers run against
+    pub fn with_database<T>(
+        &mut self,
+        name: &str,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> Result<T, EngineError> {
+        if !self.databases.contains_key(name) {
+            return Err(EngineError::InvalidOp(format!(
+                "Database '{}' not found",
+                name
+            )));
+        }
+        let previous = std::mem::replace(&mut self.active_db, name.to_string());
+        let result = f(self);
+        // `f` may have dropped the previously active database
+        self.active_db = if self.databases.contains_key(&previous) {
+            previous
+        } else {
+            self.config.storage.default_db.clone()
+        };
+        Ok(result)
+    }
+
     /// Drop a database
     pub fn drop_database(&mut self, name: &str) -> Result<(), EngineError> {
         if name == "default" {
@@ -389,14 +907,198 @@ impl TensorDb {
             self.active_db = "default".to_string();
         }
         self.databases.remove(name);
+        // A session still running on it is discarded when attached
+        self.detached.remove(name);
         Ok(())
     }
 
+    /// Move database `name` into an engine of its own, so commands on it run
+    /// without holding this one. The catalog keeps listing the database, but
+    /// it stays empty here until the session is returned with `attach_session`.
+    pub fn detach_session(&mut self, name: &str) -> Result<TensorDb, EngineError> {
+        if self.detached.contains(name) {
+            return Err(EngineError::InvalidOp(format!(
+                "Database '{}' is already in use by another session",
+                name
+            )));
+        }
+        let instance = self.databases.get_mut(name).ok_or_else(|| {
+            EngineError::InvalidOp(format!("Database '{}' not found", name))
+        })?;
+        let instance = std::mem::replace(instance, DatabaseInstance::new(name.to_string()));
+        self.detached.insert(name.to_string());
+
+        Ok(self.session(instance))
+    }
+
+    /// Snapshot of the committed state of database `name` for readers. `None`
+    /// when it does not exist, is lent to a session, or has an open
+    /// transaction whose changes must stay private.
+    pub fn read_snapshot(&self, name: &str) -> Option<DatabaseSnapshot> {
+        if self.detached.contains(name) {
+            return None;
+        }
+        let instance = self.databases.get(name)?;
+        if instance.transaction.is_some() {
+            return None;
+        }
+        Some(DatabaseSnapshot {
+            instance: instance.snapshot(),
+        })
+    }
+
+    /// Session running read-only commands on `snapshot`. Nothing it does is
+    /// written back: it never evicts datasets, and is dropped once done.
+    pub fn snapshot_session(&self, snapshot: DatabaseSnapshot) -> TensorDb {
+        let mut session = self.session(snapshot.instance);
+        session.config.memory.budget_bytes = 0;
+        session.snapshot_session = true;
+        session
+    }
+
+    /// Session holding just `instance`, sharing this engine's settings,
+    /// query registry and plugins
+    fn session(&self, instance: DatabaseInstance) -> TensorDb {
+        let name = instance.name.clone();
+        TensorDb {
+            config: self.config.clone(),
+            databases: HashMap::from([(name.clone(), instance)]),
+            active_db: name,
+            replaying_wal: false,
+            audit_log: self.audit_log.session_copy(),
+            audit_actor: self.audit_actor.clone(),
+            session_id: self.session_id.clone(),
+            role: self.role.clone(),
+            execution_stats: std::sync::Mutex::new(ExecutionStats::default()),
+            rng_streams: self.rng_streams.clone(),
+            queries: self.queries.clone(),
+            active_query: None,
+            plugins: self.plugins.clone(),
+            detached: std::collections::HashSet::new(),
+            jobs: self.jobs.clone(),
+            snapshot_session: false,
+        }
+    }
+
+    /// Put back the database of a session made by `detach_session` and
+    /// record its audit entries. A database dropped meanwhile stays dropped.
+    pub fn attach_session(&mut self, mut session: TensorDb) {
+        self.audit_log.merge(session.audit_log);
+        let name = session.active_db;
+        if let Some(instance) = session.databases.remove(&name) {
+            if self.detached.remove(&name) {
+                self.databases.insert(name, instance);
+            }
+        }
+    }
+
     /// List all databases
     pub fn list_databases(&self) -> Vec<String> {
         self.databases.keys().cloned().collect()
     }
 
+    /// Directory holding snapshot `label` of database `name`: data_dir/name/snapshots/label
+    pub fn snapshot_path(&self, name: &str, label: &str) -> std::path::PathBuf {
+        self.config
+            .storage
+            .data_dir
+            .join(name)
+            .join("snapshots")
+            .join(label)
+    }
+
+    /// Serialize every dataset (with indices) and named tensor of a database
+    /// into its snapshot directory, replacing an older snapshot with the same label
+    pub fn snapshot_database(
+        &self,
+        name: &str,
+        label: &str,
+    ) -> Result<std::path::PathBuf, EngineError> {
+        validate_snapshot_label(label)?;
+        let fork = self.fork_database(name)?;
+
+        let path = self.snapshot_path(name, label);
+        if path.exists() {
+            std::fs::remove_dir_all(&path).map_err(|e| {
+                EngineError::InvalidOp(format!("Failed to replace snapshot '{}': {}", label, e))
+            })?;
+        }
+        fork.save(&path).map_err(|e| {
+            EngineError::InvalidOp(format!("Failed to write snapshot '{}': {}", label, e))
+        })?;
+
+        Ok(path)
+    }
+
+    /// Copy the datasets and tensors of database `name`, so they can be
+    /// written out without holding the database
+    pub fn fork_database(&self, name: &str) -> Result<DatabaseFork, EngineError> {
+        let instance = self.databases.get(name).ok_or_else(|| {
+            EngineError::InvalidOp(format!("Database '{}' not found", name))
+        })?;
+
+        let mut datasets = Vec::new();
+        for ds_name in instance.list_dataset_names() {
+            datasets.push(instance.get_dataset(&ds_name)?.clone());
+        }
+        for ds_name in instance.eviction.evicted.keys() {
+            datasets.push(self.load_evicted(name, ds_name)?);
+        }
+        // Tensor-first datasets are stored materialized, as in SAVE ALL
+        for ds_name in instance.tensor_datasets.list_names() {
+            datasets.push(instance.materialize_tensor_dataset(&ds_name)?);
+        }
+        let mut tensors = Vec::new();
+        for tensor_name in instance.list_names() {
+            let tensor = instance.get(&tensor_name)?.clone();
+            tensors.push((tensor_name, tensor));
+        }
+
+        Ok(DatabaseFork {
+            name: name.to_string(),
+            datasets,
+            tensors,
+        })
+    }
+
+    /// Replace the contents of a database with a snapshot taken by `snapshot_database`.
+    /// The database is created if it does not exist yet.
+    pub fn restore_database(&mut self, name: &str, label: &str) -> Result<(), EngineError> {
+        use crate::core::storage::{ParquetStorage, StorageEngine};
+
+        validate_snapshot_label(label)?;
+        let path = self.snapshot_path(name, label);
+        if !path.is_dir() {
+            return Err(EngineError::InvalidOp(format!(
+                "Snapshot '{}' of database '{}' not found",
+                label, name
+            )));
+        }
+        let storage = ParquetStorage::new(path.to_string_lossy().into_owned());
+        let storage_err = |e: crate::core::storage::StorageError| {
+            EngineError::InvalidOp(format!("Failed to read snapshot '{}': {}", label, e))
+        };
+
+        // Build the new instance fully before swapping it in, so a failed
+        // restore leaves the current state untouched
+        let mut instance = DatabaseInstance::new(name.to_string());
+        for ds_name in storage.list_datasets().map_err(storage_err)? {
+            instance.restore_dataset(storage.load_dataset(&ds_name).map_err(storage_err)?)?;
+        }
+        for tensor_name in storage.list_tensors().map_err(storage_err)? {
+            let tensor = storage.load_tensor(&tensor_name).map_err(storage_err)?;
+            instance.insert_named(tensor_name, tensor.shape.clone(), (*tensor.data).clone())?;
+        }
+
+        // The query catalog and macros are not part of the snapshot and are kept as is
+        if let Some(current) = self.databases.get_mut(name) {
+            instance.stored_queries = std::mem::take(&mut current.stored_queries);
+            instance.macros = std::mem::take(&mut current.macros);
+        }
+        self.databases.insert(name.to_string(), instance);
+        Ok(())
+    }
+
     // Delegate methods to active instance
     pub fn insert_named(
         &mut self,
@@ -466,6 +1168,37 @@ impl TensorDb {
         self.active_instance_mut().remove_tensor(name)
     }
 
+    /// Unbind `name` in the active database, keeping its tensor while
+    /// something else still refers to it
+    pub fn release_tensor(&mut self, name: &str) {
+        self.active_instance_mut().release_tensor(name)
+    }
+
+    /// Run `eval`, which binds `output_name` in the active database. Under
+    /// `[execution] non_finite = "error"` a result holding NaN or Inf is
+    /// discarded and the name keeps its previous tensor.
+    fn eval_checked(
+        &mut self,
+        output_name: String,
+        eval: impl FnOnce(&mut DatabaseInstance, String) -> Result<(), EngineError>,
+    ) -> Result<(), EngineError> {
+        let check = self.config.execution.non_finite == NonFinitePolicy::Error;
+        let instance = self.active_instance_mut();
+        let previous = instance.names.get(&output_name).copied();
+        eval(instance, output_name.clone())?;
+        if !check || instance.get(&output_name)?.data_ref().iter().all(|x| x.is_finite()) {
+            return Ok(());
+        }
+        instance.remove_tensor(&output_name);
+        if let Some(previous) = previous {
+            instance.names.insert(output_name.clone(), previous);
+        }
+        Err(EngineError::InvalidOp(format!(
+            "Result '{}' contains NaN or Inf (non_finite = \"error\")",
+            output_name
+        )))
+    }
+
     pub fn eval_unary(
         &mut self,
         ctx: &mut ExecutionContext,
@@ -473,8 +1206,9 @@ impl TensorDb {
         input_name: &str,
         op: UnaryOp,
     ) -> Result<(), EngineError> {
-        self.active_instance_mut()
-            .eval_unary(ctx, output_name, input_name, op)
+        self.eval_checked(output_name.into(), |db, output_name| {
+            db.eval_unary(ctx, output_name, input_name, op)
+        })
     }
 
     pub fn eval_binary(
@@ -485,8 +1219,9 @@ impl TensorDb {
         right_name: &str,
         op: BinaryOp,
     ) -> Result<(), EngineError> {
-        self.active_instance_mut()
-            .eval_binary(ctx, output_name, left_name, right_name, op)
+        self.eval_checked(output_name.into(), |db, output_name| {
+            db.eval_binary(ctx, output_name, left_name, right_name, op)
+        })
     }
 
     pub fn list_names(&self) -> Vec<String> {
@@ -500,8 +1235,9 @@ impl TensorDb {
         left_name: &str,
         right_name: &str,
     ) -> Result<(), EngineError> {
-        self.active_instance_mut()
-            .eval_matmul(ctx, output_name, left_name, right_name)
+        self.eval_checked(output_name.into(), |db, output_name| {
+            db.eval_matmul(ctx, output_name, left_name, right_name)
+        })
     }
 
     pub fn eval_reshape(
@@ -531,11 +1267,108 @@ impl TensorDb {
         name: String,
         schema: Arc<Schema>,
     ) -> Result<DatasetId, EngineError> {
-        self.active_instance_mut().create_dataset(name, schema)
+        let id = self
+            .active_instance_mut()
+            .create_dataset(name.clone(), schema)?;
+        if !self.plugins.is_empty() {
+            let dataset = self.active_instance().get_dataset(&name)?;
+            for plugin in self.plugins.iter() {
+                plugin.on_dataset_created(&self.active_db, dataset);
+            }
+        }
+        Ok(id)
     }
 
+    /// Dataset by name. `name@n` refers to version `n` of a dataset, which
+    /// must be the current one or still retained.
     pub fn get_dataset(&self, name: &str) -> Result<&Dataset, EngineError> {
-        self.active_instance().get_dataset(name)
+        if name == AUDIT_LOG_DATASET {
+            return Ok(self.audit_log.dataset());
+        }
+        if let Some((base, version)) = name.rsplit_once('@') {
+            if let Ok(version) = version.parse::<u32>() {
+                return self.active_instance().get_dataset_version(base, version);
+            }
+        }
+        let instance = self.active_instance();
+        instance.get_dataset(name).map_err(|e| {
+            if instance.eviction.evicted.contains_key(name) {
+                EngineError::InvalidOp(format!(
+                    "Dataset '{}' was evicted to disk under memory pressure; \
+                     it is reloaded by the next statement that names it",
+                    name
+                ))
+            } else {
+                e
+            }
+        })
+    }
+
+    /// Version of dataset `name` that was current at `ts`
+    pub fn dataset_version_at(&self, name: &str, ts: DateTime<Utc>) -> Result<u32, EngineError> {
+        self.active_instance().dataset_version_at(name, ts)
+    }
+
+    /// Fingerprint of every dataset in the active database, taken before a
+    /// mutating command and passed to `commit_dataset_versions` afterwards
+    pub fn dataset_fingerprints(&self) -> DatasetFingerprints {
+        let instance = self.active_instance();
+        instance
+            .list_dataset_names()
+            .into_iter()
+            .filter_map(|name| {
+                let ds = instance.get_dataset(&name).ok()?;
+                Some((name, fingerprint(ds)))
+            })
+            .collect()
+    }
+
+    /// Bump the version of every dataset changed since `before` and retain a
+    /// snapshot of the new state, keeping `[versioning] retention` per dataset.
+    /// Their column statistics are redone under `[execution] non_finite`.
+    pub fn commit_dataset_versions(&mut self, before: &DatasetFingerprints) {
+        let retention = self.config.versioning.retention;
+        let non_finite = self.config.execution.non_finite;
+        let after = self.dataset_fingerprints();
+        let instance = self.active_instance_mut();
+
+        for (name, fingerprint) in &after {
+            let previous = before.get(name);
+            if previous == Some(fingerprint) {
+                continue;
+            }
+            let Ok(dataset) = instance.dataset_store.get_mut_by_name(name) else {
+                continue;
+            };
+            if previous.is_some() {
+                dataset.metadata.version += 1;
+            }
+            // Stats are kept up to date incrementally under the policy they
+            // were last computed with; only a change of policy needs a rescan
+            if dataset.metadata.non_finite != non_finite {
+                dataset
+                    .metadata
+                    .update_column_stats(&dataset.schema, &dataset.rows, non_finite);
+            }
+            let snapshot = (retention > 0).then(|| version_snapshot(dataset));
+
+            let history = instance.dataset_versions.entry(name.clone()).or_default();
+            if previous.is_none() {
+                // A new (or re-created) dataset starts a fresh history
+                history.clear();
+            }
+            if let Some(snapshot) = snapshot {
+                history.push_back(Arc::new(snapshot));
+                while history.len() > retention {
+                    history.pop_front();
+                }
+            }
+        }
+        // Evicted datasets keep their history for when they are reloaded
+        let evicted = &instance.eviction.evicted;
+        instance
+            .dataset_versions
+            .retain(|name, _| after.contains_key(name) || evicted.contains_key(name));
     }
 
     pub fn get_dataset_mut(&mut self, name: &str) -> Result<&mut Dataset, EngineError> {
@@ -543,13 +1376,565 @@ impl TensorDb {
     }
 
     pub fn insert_row(&mut self, dataset_name: &str, tuple: Tuple) -> Result<(), EngineError> {
-        self.active_instance_mut().insert_row(dataset_name, tuple)
+        self.active_instance_mut().insert_row(dataset_name, tuple)?;
+        self.notify_rows_inserted(dataset_name, |rows| &rows[rows.len() - 1..])
+    }
+
+    /// Append several rows at once; indices and stats are refreshed once
+    pub fn insert_rows(
+        &mut self,
+        dataset_name: &str,
+        tuples: Vec<Tuple>,
+    ) -> Result<(), EngineError> {
+        let count = tuples.len();
+        self.active_instance_mut()
+            .insert_rows(dataset_name, tuples)?;
+        self.notify_rows_inserted(dataset_name, |rows| &rows[rows.len() - count..])
+    }
+
+    /// Insert a row or replace the one with the same primary key. Returns its position.
+    pub fn upsert_row(&mut self, dataset_name: &str, tuple: Tuple) -> Result<usize, EngineError> {
+        let position = self
+            .active_instance_mut()
+            .upsert_row(dataset_name, tuple)?;
+        self.notify_rows_inserted(dataset_name, |rows| &rows[position..=position])?;
+        Ok(position)
+    }
+
+    /// Hand the rows picked by `written` from a dataset to the plugins
+    fn notify_rows_inserted(
+        &self,
+        dataset_name: &str,
+        written: impl FnOnce(&[Tuple]) -> &[Tuple],
+    ) -> Result<(), EngineError> {
+        if self.plugins.is_empty() {
+            return Ok(());
+        }
+        let rows = written(&self.get_dataset(dataset_name)?.rows);
+        for plugin in self.plugins.iter() {
+            plugin.on_rows_inserted(&self.active_db, dataset_name, rows);
+        }
+        Ok(())
+    }
+
+    /// Report a finished top-level command to the plugins
+    pub fn notify_query_executed(&self, event: &super::plugin::QueryEvent<'_>) {
+        for plugin in self.plugins.iter() {
+            plugin.on_query_executed(event);
+        }
+    }
+
+    /// Names of the registered plugins, in registration order
+    pub fn plugin_names(&self) -> Vec<String> {
+        self.plugins.iter().map(|p| p.name().to_string()).collect()
     }
 
     pub fn list_dataset_names(&self) -> Vec<String> {
         self.active_instance().list_dataset_names()
     }
 
+    /// Run one TTL sweep over every database (see `reap_expired_at`)
+    pub fn reap_expired(&mut self) -> ExpiryReport {
+        self.reap_expired_at(chrono::Utc::now())
+    }
+
+    /// Drop expired rows and datasets of every database as of `now`. With
+    /// auto_persist the storage copy is updated as well.
+    pub fn reap_expired_at(&mut self, now: chrono::DateTime<chrono::Utc>) -> ExpiryReport {
+        let mut report = ExpiryReport::default();
+        let mut db_names: Vec<String> = self.databases.keys().cloned().collect();
+        db_names.sort();
+
+        for db_name in db_names {
+            let instance = self.databases.get_mut(&db_name).expect("listed above");
+            let (changed, dropped, rows_removed) = instance.expire_datasets(now);
+            report.rows_removed += rows_removed;
+
+            if self.config.storage.auto_persist && !(changed.is_empty() && dropped.is_empty()) {
+                self.persist_expiry(&db_name, &changed, &dropped);
+            }
+            report
+                .datasets_dropped
+                .extend(dropped.into_iter().map(|ds| (db_name.clone(), ds)));
+        }
+        report
+    }
+
+    fn persist_expiry(&self, db_name: &str, changed: &[String], dropped: &[String]) {
+        let storage = match self.database_storage(db_name) {
+            Ok(storage) => storage,
+            Err(e) => {
+                eprintln!("Warning: Failed to open storage of database '{}': {}", db_name, e);
+                return;
+            }
+        };
+        let instance = &self.databases[db_name];
+        for name in changed {
+            if let Ok(dataset) = instance.get_dataset(name) {
+                if let Err(e) = storage.save_dataset(dataset) {
+                    eprintln!("Warning: Failed to persist expired dataset '{}': {}", name, e);
+                }
+            }
+        }
+        for name in dropped {
+            if let Err(e) = storage.delete_dataset(name) {
+                eprintln!("Warning: Failed to delete expired dataset '{}': {}", name, e);
+            }
+        }
+    }
+
+    /// Mark the datasets of the active database that `line` names, directly
+    /// or through a stored query, as used, reloading those that were evicted.
+    /// Returns their names.
+    pub fn touch_datasets(&mut self, line: &str) -> Result<Vec<String>, EngineError> {
+        let instance = self.active_instance();
+        if self.config.memory.budget_bytes == 0 && instance.eviction.evicted.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (live, evicted) = instance.referenced_datasets(line);
+        for name in &evicted {
+            self.reload_dataset(name)?;
+        }
+        let instance = self.active_instance_mut();
+        for name in live.iter().chain(&evicted) {
+            instance.eviction.touch(name);
+        }
+        Ok(live.into_iter().chain(evicted).collect())
+    }
+
+    /// Before a mutating statement runs inside a transaction, copy the
+    /// datasets it names into the undo log (see `engine::transaction`)
+    pub fn copy_on_write(&mut self, line: &str) {
+        self.active_instance_mut().copy_on_write(line);
+    }
+
+    /// Open a transaction on the active database, owned by the current session
+    pub fn begin_transaction(&mut self) -> Result<(), EngineError> {
+        let session = self.session_id.clone();
+        self.active_instance_mut().begin_transaction(session)
+    }
+
+    /// Close the open transaction of the active database, keeping its
+    /// changes. Returns the statements it held back from the WAL.
+    pub fn commit_transaction(&mut self) -> Result<Vec<String>, EngineError> {
+        self.check_transaction_session()?;
+        self.active_instance_mut().commit_transaction()
+    }
+
+    /// Undo every change made since `BEGIN` on the active database
+    pub fn rollback_transaction(&mut self) -> Result<RollbackReport, EngineError> {
+        self.check_transaction_session()?;
+        self.active_instance_mut().rollback_transaction()
+    }
+
+    /// Whether the active database has an open transaction
+    pub fn in_transaction(&self) -> bool {
+        self.active_instance().transaction.is_some()
+    }
+
+    /// Session that began the open transaction of the active database
+    pub fn transaction_session(&self) -> Option<&str> {
+        self.active_instance()
+            .transaction
+            .as_ref()?
+            .session
+            .as_deref()
+    }
+
+    /// Fails when the active database has a transaction open that another
+    /// session began: its changes would land in that session's undo log
+    pub fn check_transaction_session(&self) -> Result<(), EngineError> {
+        let instance = self.active_instance();
+        match &instance.transaction {
+            Some(transaction) if transaction.session != self.session_id => {
+                Err(EngineError::InvalidOp(format!(
+                    "Database '{}' has a transaction open by another session",
+                    instance.name
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Hold a WAL entry back until the open transaction commits. Returns
+    /// false when no transaction is open and the entry should be written now.
+    pub fn defer_wal(&mut self, line: &str) -> bool {
+        match &mut self.active_instance_mut().transaction {
+            Some(transaction) => {
+                transaction.wal.push(line.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Spill least recently used datasets to storage until the estimated size
+    /// of those in memory is back under `[memory] evict_at` of the budget.
+    /// Datasets of the active database in `protected` stay. Nothing is evicted
+    /// unless auto_persist is on, since storage must be able to reload them.
+    pub fn enforce_memory_budget(&mut self, protected: &[String]) {
+        let memory = &self.config.memory;
+        if memory.budget_bytes == 0 {
+            return;
+        }
+        let limit = (memory.budget_bytes as f64 * memory.evict_at) as usize;
+
+        let mut resident = 0;
+        let mut candidates = Vec::new();
+        for (db_name, instance) in self.databases.iter_mut() {
+            let names = instance.dataset_store.list_names();
+            instance.eviction.retain(&names);
+            let mut db_resident = 0;
+            for name in names {
+                let Ok(dataset) = instance.dataset_store.get_by_name(&name) else {
+                    continue;
+                };
+                let bytes = instance.eviction.size_of(&name, dataset);
+                db_resident += bytes;
+                // A rollback puts datasets back in memory, so none is spilled mid-transaction
+                if instance.transaction.is_some() {
+                    continue;
+                }
+                if *db_name != self.active_db || !protected.contains(&name) {
+                    let last_used = instance.eviction.last_used(&name);
+                    candidates.push((last_used, db_name.clone(), name, bytes));
+                }
+            }
+            instance.eviction.stats.resident_bytes = db_resident;
+            resident += db_resident;
+        }
+        if resident <= limit || !self.config.storage.auto_persist {
+            return;
+        }
+
+        candidates.sort();
+        for (_, db_name, name, bytes) in candidates {
+            if resident <= limit {
+                break;
+            }
+            match self.evict_dataset(&db_name, &name, bytes) {
+                Ok(()) => resident -= bytes,
+                Err(e) => eprintln!(
+                    "Warning: Failed to evict dataset '{}' of database '{}': {}",
+                    name, db_name, e
+                ),
+            }
+        }
+    }
+
+    /// Write dataset `name` of database `db_name` to storage and drop it from memory
+    fn evict_dataset(&mut self, db_name: &str, name: &str, bytes: usize) -> Result<(), EngineError> {
+        let storage = self
+            .database_storage(db_name)
+            .map_err(|e| EngineError::InvalidOp(e.to_string()))?;
+        let instance = self
+            .databases
+            .get_mut(db_name)
+            .ok_or_else(|| EngineError::InvalidOp(format!("Database '{}' not found", db_name)))?;
+        let dataset = instance.get_dataset(name)?;
+        storage
+            .save_dataset(dataset)
+            .map_err(|e| EngineError::InvalidOp(e.to_string()))?;
+
+        let dataset = instance.dataset_store.remove_by_name(name)?;
+        let stats = &mut instance.eviction.stats;
+        stats.resident_bytes = stats.resident_bytes.saturating_sub(bytes);
+        instance.eviction.record_eviction(EvictedDataset {
+            name: name.to_string(),
+            rows: dataset.rows.len(),
+            bytes,
+            evicted_at: Utc::now(),
+        });
+        Ok(())
+    }
+
+    /// Bring evicted dataset `name` of the active database back into memory
+    fn reload_dataset(&mut self, name: &str) -> Result<(), EngineError> {
+        let db_name = self.active_db.clone();
+        let dataset = self.load_evicted(&db_name, name)?;
+        let instance = self.active_instance_mut();
+        instance.restore_dataset(dataset)?;
+        instance.eviction.record_reload(name);
+        Ok(())
+    }
+
+    /// Read evicted dataset `name` of database `db_name` back from storage
+    pub fn load_evicted(&self, db_name: &str, name: &str) -> Result<Dataset, EngineError> {
+        self.database_storage(db_name)
+            .and_then(|storage| storage.load_dataset(name))
+            .map_err(|e| {
+                EngineError::InvalidOp(format!(
+                    "Failed to reload evicted dataset '{}': {}",
+                    name, e
+                ))
+            })
+    }
+
+    /// Datasets of the active database currently evicted to storage, by name
+    pub fn evicted_datasets(&self) -> Vec<EvictedDataset> {
+        let instance = self.active_instance();
+        instance.eviction.evicted.values().cloned().collect()
+    }
+
+    /// Eviction counters and resident size, summed over every database
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.databases
+            .values()
+            .fold(MemoryStats::default(), |mut total, instance| {
+                let stats = &instance.eviction.stats;
+                total.resident_bytes += stats.resident_bytes;
+                total.evictions += stats.evictions;
+                total.bytes_evicted += stats.bytes_evicted;
+                total.reloads += stats.reloads;
+                total.bytes_reloaded += stats.bytes_reloaded;
+                total
+            })
+    }
+
+    /// Counters accumulated since the last `reset_execution_stats`
+    pub fn execution_stats(&self) -> ExecutionStats {
+        *self.execution_stats.lock().unwrap()
+    }
+
+    pub fn reset_execution_stats(&self) {
+        *self.execution_stats.lock().unwrap() = ExecutionStats::default();
+    }
+
+    /// Generator for random operators. In deterministic mode the n-th call
+    /// returns the same sequence on every run with the same `[execution] seed`.
+    pub fn rng(&self) -> crate::utils::rng::Rng {
+        use crate::utils::rng::Rng;
+        let stream = self
+            .rng_streams
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        if self.config.execution.deterministic {
+            // Decorrelate streams by mixing the stream number through the generator
+            Rng::new(Rng::new(stream).next_u64() ^ self.config.execution.seed)
+        } else {
+            Rng::from_entropy()
+        }
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.config.execution.deterministic
+    }
+
+    /// Called by scan operators for every dataset they read
+    pub(crate) fn record_scan(&self, rows: usize, index_used: bool) {
+        let mut stats = self.execution_stats.lock().unwrap();
+        stats.rows_scanned += rows;
+        stats.index_used |= index_used;
+    }
+
+    /// Register `command` as running until `end_query`. Commands started by
+    /// another command (stored queries, scripts) report under the outer one
+    /// and are not registered, which returns false.
+    pub fn begin_query(&mut self, command: &str) -> bool {
+        if self.active_query.is_some() {
+            return false;
+        }
+        self.active_query = Some(self.queries.begin(&self.active_db, command));
+        true
+    }
+
+    pub fn end_query(&mut self) {
+        self.active_query = None;
+    }
+
+    /// Commands of this engine and its sessions that have run for at least
+    /// `[execution] progress_after_secs`, oldest first
+    pub fn running_queries(&self) -> Vec<QueryStatus> {
+        let min_elapsed =
+            std::time::Duration::from_secs(self.config.execution.progress_after_secs);
+        let own = self.active_query.as_ref().map(|q| q.progress().id);
+        self.queries
+            .running(min_elapsed)
+            .into_iter()
+            .filter(|query| Some(query.id) != own)
+            .collect()
+    }
+
+    /// Start `task` on a snapshot of dataset `dataset_name` of the active
+    /// database in the background; `command` is logged to the WAL once the
+    /// result is installed. Returns the job id.
+    pub fn start_job(
+        &mut self,
+        dataset_name: &str,
+        command: &str,
+        task: JobTask,
+    ) -> Result<u64, EngineError> {
+        let instance = self.active_instance();
+        if instance.transaction.is_some() {
+            return Err(EngineError::InvalidOp(
+                "ASYNC jobs cannot be started inside a transaction".to_string(),
+            ));
+        }
+        let source = instance
+            .dataset_store
+            .get_shared_by_name(dataset_name)
+            .map_err(|_| EngineError::DatasetNotFound(dataset_name.to_string()))?;
+        if let JobTask::Index { column, .. } = &task {
+            if source.schema.get_field(column).is_none() {
+                return Err(EngineError::InvalidOp(format!(
+                    "Column '{}' not found in schema",
+                    column
+                )));
+            }
+        }
+        let fingerprint = fingerprint(&source);
+        Ok(self.jobs.submit(
+            &instance.name,
+            dataset_name,
+            command,
+            task,
+            source,
+            fingerprint,
+        ))
+    }
+
+    /// Install the results of the finished jobs of the active database.
+    /// A job whose dataset changed since its snapshot starts over instead,
+    /// and one whose dataset is gone fails. Returns the commands of the
+    /// installed jobs, for the WAL.
+    pub fn install_finished_jobs(&mut self) -> Vec<String> {
+        let name = self.active_db.clone();
+        if self.snapshot_session || self.detached.contains(&name) {
+            return Vec::new();
+        }
+        let jobs = self.jobs.clone();
+        let instance = self.active_instance_mut();
+        // A rollback could not undo the installation
+        if instance.transaction.is_some() {
+            return Vec::new();
+        }
+        let mut installed = Vec::new();
+        // Jobs on evicted datasets wait until they are loaded again
+        let ready = jobs.take_ready(&name, |dataset| {
+            !instance.eviction.evicted.contains_key(dataset)
+        });
+        for job in ready {
+            let Ok(current) = instance.dataset_store.get_by_name(&job.dataset) else {
+                jobs.fail(job.id, format!("Dataset '{}' no longer exists", job.dataset));
+                continue;
+            };
+            let current_fingerprint = fingerprint(current);
+            if current_fingerprint != job.fingerprint {
+                if let Ok(source) = instance.dataset_store.get_shared_by_name(&job.dataset) {
+                    jobs.restart(job.id, source, current_fingerprint);
+                }
+                continue;
+            }
+            let Ok(dataset) = instance.dataset_store.get_mut_by_name(&job.dataset) else {
+                continue;
+            };
+            match job.output {
+                JobOutput::Index { column, index } => {
+                    dataset.indices.insert(column, index);
+                }
+                JobOutput::Materialized { schema, rows } => {
+                    dataset.replace_lazy_columns(schema, rows)
+                }
+            }
+            jobs.complete(job.id);
+            installed.push(job.command);
+        }
+        installed
+    }
+
+    /// Whether jobs of database `name` wait for a command to install them
+    pub fn has_finished_jobs(&self, name: &str) -> bool {
+        self.jobs.has_ready(name)
+    }
+
+    /// Every background job of this engine and its sessions, oldest first
+    pub fn jobs(&self) -> Vec<JobStatus> {
+        self.jobs.list()
+    }
+
+    /// Stop background job `id`, discarding its result
+    pub fn cancel_job(&self, id: u64) -> Result<(), EngineError> {
+        self.jobs.cancel(id).map_err(EngineError::InvalidOp)
+    }
+
+    /// Registry of running commands, readable without locking any database
+    pub fn query_registry(&self) -> Arc<QueryRegistry> {
+        self.queries.clone()
+    }
+
+    /// Called by scan operators before reading `rows` rows
+    pub(crate) fn start_operator(&self, operator: String, rows: usize) {
+        if let Some(query) = &self.active_query {
+            query.progress().start_operator(operator, rows);
+        }
+    }
+
+    /// Called by scan operators as they read rows
+    pub(crate) fn advance_operator(&self, rows: usize) {
+        if let Some(query) = &self.active_query {
+            query.progress().advance(rows);
+        }
+    }
+
+    /// Set who is recorded in the audit log for subsequent commands
+    pub fn set_audit_actor(&mut self, actor: impl Into<String>) {
+        self.audit_actor = actor.into();
+    }
+
+    /// Set the client session subsequent commands belong to
+    pub fn set_session_id(&mut self, session: Option<String>) {
+        self.session_id = session;
+    }
+
+    /// Set the role masking policies are checked against for subsequent commands
+    pub fn set_role(&mut self, role: Option<String>) {
+        self.role = role;
+    }
+
+    pub fn role(&self) -> Option<&str> {
+        self.role.as_deref()
+    }
+
+    /// Row and column count of every dataset in the active database
+    pub fn dataset_shapes(&self) -> DatasetShapes {
+        self.list_dataset_names()
+            .into_iter()
+            .filter_map(|name| {
+                let shape = self
+                    .get_dataset(&name)
+                    .ok()
+                    .map(|ds| (ds.rows.len(), ds.schema.len()))?;
+                Some((name, shape))
+            })
+            .collect()
+    }
+
+    /// Append a command to the audit log. `before` is the result of
+    /// `dataset_shapes()` taken before the command ran.
+    pub fn record_audit(
+        &mut self,
+        database: &str,
+        command: &str,
+        before: &DatasetShapes,
+        error: Option<String>,
+    ) {
+        // Datasets of another database are unrelated to the command's effect
+        let after = if database == self.active_db {
+            self.dataset_shapes()
+        } else {
+            before.clone()
+        };
+        let entry = AuditEntry::from_shapes(
+            &self.audit_actor,
+            database,
+            command,
+            before,
+            &after,
+            error,
+        );
+        self.audit_log.record(entry);
+    }
+
     pub fn alter_dataset_add_column(
         &mut self,
         dataset_name: &str,
@@ -587,11 +1972,62 @@ impl TensorDb {
             )
     }
 
+    pub fn alter_dataset_drop_column(
+        &mut self,
+        dataset_name: &str,
+        column_name: &str,
+    ) -> Result<(), EngineError> {
+        self.active_instance_mut()
+            .alter_dataset_drop_column(dataset_name, column_name)
+    }
+
+    pub fn alter_dataset_rename_column(
+        &mut self,
+        dataset_name: &str,
+        old_name: &str,
+        new_name: String,
+    ) -> Result<(), EngineError> {
+        self.active_instance_mut()
+            .alter_dataset_rename_column(dataset_name, old_name, new_name)
+    }
+
+    pub fn alter_dataset_column_type(
+        &mut self,
+        dataset_name: &str,
+        column_name: &str,
+        value_type: crate::core::value::ValueType,
+    ) -> Result<(), EngineError> {
+        self.active_instance_mut()
+            .alter_dataset_column_type(dataset_name, column_name, value_type)
+    }
+
     pub fn materialize_lazy_columns(&mut self, dataset_name: &str) -> Result<(), EngineError> {
         self.active_instance_mut()
             .materialize_lazy_columns(dataset_name)
     }
 
+    /// Remove the rows matching `predicate` (all rows if None); returns them
+    pub fn delete_rows(
+        &mut self,
+        dataset_name: &str,
+        predicate: Option<&crate::query::logical::Expr>,
+    ) -> Result<Vec<Tuple>, EngineError> {
+        self.active_instance_mut()
+            .delete_rows(dataset_name, predicate)
+    }
+
+    /// Apply `assignments` to the rows matching `predicate` (all rows if None);
+    /// returns the positions of the updated rows
+    pub fn update_rows(
+        &mut self,
+        dataset_name: &str,
+        assignments: &[(String, crate::query::logical::Expr)],
+        predicate: Option<&crate::query::logical::Expr>,
+    ) -> Result<Vec<usize>, EngineError> {
+        self.active_instance_mut()
+            .update_rows(dataset_name, assignments, predicate)
+    }
+
     pub fn eval_index(
         &mut self,
         output_name: impl Into<String>,
@@ -612,6 +2048,49 @@ impl TensorDb {
             .eval_slice(output_name, tensor_name, specs)
     }
 
+    pub fn eval_mask(
+        &mut self,
+        output_name: impl Into<String>,
+        tensor_name: &str,
+        mask_name: &str,
+    ) -> Result<(), EngineError> {
+        self.active_instance_mut()
+            .eval_mask(output_name, tensor_name, mask_name)
+    }
+
+    pub fn eval_dataset_mask(
+        &mut self,
+        output_name: &str,
+        dataset_name: &str,
+        mask_name: &str,
+    ) -> Result<usize, EngineError> {
+        self.active_instance_mut()
+            .eval_dataset_mask(output_name, dataset_name, mask_name)
+    }
+
+    pub fn eval_where(
+        &mut self,
+        output_name: impl Into<String>,
+        cond_name: &str,
+        then_name: &str,
+        else_name: &str,
+    ) -> Result<(), EngineError> {
+        self.eval_checked(output_name.into(), |db, output_name| {
+            db.eval_where(output_name, cond_name, then_name, else_name)
+        })
+    }
+
+    pub fn eval_topk(
+        &mut self,
+        values_name: impl Into<String>,
+        indices_name: Option<&str>,
+        input_name: &str,
+        k: usize,
+    ) -> Result<(), EngineError> {
+        self.active_instance_mut()
+            .eval_topk(values_name, indices_name, input_name, k)
+    }
+
     pub fn eval_field_access(
         &mut self,
         output_name: impl Into<String>,
@@ -650,6 +2129,16 @@ impl TensorDb {
             .create_vector_index(dataset_name, column_name)
     }
 
+    pub fn restore_index(
+        &mut self,
+        dataset_name: &str,
+        column_name: &str,
+        index: Box<dyn crate::core::index::Index>,
+    ) -> Result<(), EngineError> {
+        self.active_instance_mut()
+            .restore_index(dataset_name, column_name, index)
+    }
+
     pub fn list_indices(&self) -> Vec<(String, String, String)> {
         self.active_instance().list_indices()
     }
@@ -664,6 +2153,65 @@ impl TensorDb {
             .set_dataset_metadata(name, key, value)
     }
 
+    /// Drop dataset `name` of the active database. Returns the number of rows it held.
+    pub fn drop_dataset(&mut self, name: &str) -> Result<usize, EngineError> {
+        let dataset = self.active_instance_mut().drop_dataset(name)?;
+        Ok(dataset.rows.len())
+    }
+
+    /// Rename dataset `from` of the active database to `to`
+    pub fn rename_dataset(&mut self, from: &str, to: &str) -> Result<(), EngineError> {
+        self.active_instance_mut().rename_dataset(from, to)
+    }
+
+    /// Remove every row of dataset `name`, emptying its indices. Returns the
+    /// number of rows removed.
+    pub fn truncate_dataset(&mut self, name: &str) -> Result<usize, EngineError> {
+        self.get_dataset_mut(name)?
+            .truncate()
+            .map_err(EngineError::InvalidOp)
+    }
+
+    /// Recompute the column statistics of `name` from all of its rows,
+    /// tightening the bounds left loose by in-place updates and rebuilding
+    /// the distinct counts and histograms. Returns the number of rows scanned.
+    pub fn analyze_dataset(&mut self, name: &str) -> Result<usize, EngineError> {
+        let non_finite = self.config.execution.non_finite;
+        let dataset = self.get_dataset_mut(name)?;
+        dataset
+            .metadata
+            .analyze(&dataset.schema, &dataset.rows, non_finite);
+        Ok(dataset.rows.len())
+    }
+
+    /// Plan and execute a logical plan against the active database, without
+    /// going through the DSL. Returns the result rows as an unregistered dataset.
+    pub fn execute_plan(
+        &self,
+        plan: &crate::query::logical::LogicalPlan,
+    ) -> Result<Dataset, EngineError> {
+        let plan = crate::query::masking::mask_plan(plan.clone(), self);
+        let plan = crate::query::optimizer::optimize(plan);
+        let planner = crate::query::planner::Planner::new(self);
+        let physical_plan = planner.create_physical_plan(&plan)?;
+        let rows = physical_plan.execute(self)?;
+
+        // Rows may come straight from storage (e.g. index lookups) and carry
+        // an equal but distinct schema Arc, so `Dataset::with_rows` is too strict.
+        // Operators may widen a column at execution (Int overflow promoted to
+        // Float), so the rows' schema wins when there are any.
+        let schema = rows
+            .first()
+            .map_or_else(|| physical_plan.schema(), |row| row.schema.clone());
+        let mut result = Dataset::new(DatasetId(0), schema, Some("Query Result".into()));
+        result
+            .metadata
+            .update_stats_with(&result.schema, &rows, self.config.execution.non_finite);
+        result.metadata.lineage = crate::query::lineage::plan_lineage(&plan, self);
+        result.rows = rows;
+        Ok(result)
+    }
+
     /// Execute a DSL command with an execution context for resource management
     /// This is an opt-in API that provides arena allocation and automatic cleanup
     pub fn execute_with_context(
@@ -696,6 +2244,17 @@ impl TensorDb {
     }
 }
 
+/// Snapshot labels become directory names, so path separators are rejected
+fn validate_snapshot_label(label: &str) -> Result<(), EngineError> {
+    if label.is_empty() || label == "." || label == ".." || label.contains(['/', '\\']) {
+        return Err(EngineError::InvalidOp(format!(
+            "Invalid snapshot name '{}'",
+            label
+        )));
+    }
+    Ok(())
+}
+
 impl DatabaseInstance {
     /// Inserta un tensor y lo asocia a un nombre (modo NORMAL por defecto)
     pub fn insert_named(
@@ -768,6 +2327,27 @@ impl DatabaseInstance {
                 .backend
                 .flatten(ctx, &in_tensor, new_id)
                 .map_err(EngineError::InvalidOp)?,
+            UnaryOp::CumSum => {
+                super::kernels::cumsum(&in_tensor, new_id).map_err(EngineError::InvalidOp)?
+            }
+            UnaryOp::CumProd => {
+                super::kernels::cumprod(&in_tensor, new_id).map_err(EngineError::InvalidOp)?
+            }
+            UnaryOp::Diff => {
+                super::kernels::diff(&in_tensor, new_id).map_err(EngineError::InvalidOp)?
+            }
+            UnaryOp::RollingMean(window) => super::kernels::rolling_mean(&in_tensor, window, new_id)
+                .map_err(EngineError::InvalidOp)?,
+            UnaryOp::Sort { descending } => super::kernels::sort(&in_tensor, descending, new_id)
+                .map_err(EngineError::InvalidOp)?,
+            UnaryOp::ArgSort { descending } => {
+                super::kernels::argsort(&in_tensor, descending, new_id)
+                    .map_err(EngineError::InvalidOp)?
+            }
+            UnaryOp::Cov { population } => super::kernels::cov(&in_tensor, population, new_id)
+                .map_err(EngineError::InvalidOp)?,
+            UnaryOp::Gram { centered } => super::kernels::gram(&in_tensor, centered, new_id)
+                .map_err(EngineError::InvalidOp)?,
         };
 
         let out_id = self.store.insert_existing_tensor(result)?;
@@ -794,6 +2374,9 @@ impl DatabaseInstance {
         let (b_ref, kind_b) = self.get_with_kind(right_name)?;
         let a = a_ref.clone();
         let b = b_ref.clone();
+        if !op.accepts_shapes(&a.shape.dims, &b.shape.dims) {
+            return Err(shape_mismatch(op.keyword(), left_name, &a, right_name, &b));
+        }
         let new_id = self.store.gen_id_internal();
 
         // Si alguno es STRICT, el resultado también es STRICT.
@@ -846,6 +2429,12 @@ impl DatabaseInstance {
                 let data = vec![value];
                 Tensor::new(new_id, shape, data).map_err(EngineError::InvalidOp)?
             }
+            BinaryOp::Compare(cmp) => {
+                super::kernels::compare(&a, &b, cmp, new_id).map_err(EngineError::InvalidOp)?
+            }
+            BinaryOp::Outer => {
+                super::kernels::outer(&a, &b, new_id).map_err(EngineError::InvalidOp)?
+            }
         };
 
         let out_id = self.store.insert_existing_tensor(result_tensor)?;
@@ -876,12 +2465,20 @@ impl DatabaseInstance {
         let (b_ref, kind_b) = self.get_with_kind(right_name)?;
         let a = a_ref.clone();
         let b = b_ref.clone();
+        let (dims_a, dims_b) = (&a.shape.dims, &b.shape.dims);
+        if dims_a.len() != 2 || !matches!(dims_b.len(), 1 | 2) || dims_a[1] != dims_b[0] {
+            return Err(shape_mismatch("MATMUL", left_name, &a, right_name, &b));
+        }
         let new_id = self.store.gen_id_internal();
 
-        let result = self
-            .backend
-            .matmul(ctx, &a, &b, new_id)
-            .map_err(EngineError::InvalidOp)?;
+        // A matrix times a vector scores every row against it
+        let result = if dims_b.len() == 1 {
+            super::kernels::matvec(&a, &b, new_id).map_err(EngineError::InvalidOp)?
+        } else {
+            self.backend
+                .matmul(ctx, &a, &b, new_id)
+                .map_err(EngineError::InvalidOp)?
+        };
 
         let out_kind = match (kind_a, kind_b) {
             (TensorKind::Strict, _) | (_, TensorKind::Strict) => TensorKind::Strict,
@@ -976,6 +2573,189 @@ impl DatabaseInstance {
             .map_err(EngineError::from)
     }
 
+    /// Apply dataset TTLs: drop datasets whose dataset-scoped TTL elapsed and
+    /// expired rows of the others. Returns the names of the changed datasets
+    /// and the dropped ones, plus the number of rows removed.
+    pub fn expire_datasets(
+        &mut self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> (Vec<String>, Vec<String>, usize) {
+        let (mut changed, mut dropped, mut rows_removed) = (Vec::new(), Vec::new(), 0);
+        let mut names = self.dataset_store.list_names();
+        names.sort();
+        for name in names {
+            let Ok(dataset) = self.dataset_store.get_mut_by_name(&name) else {
+                continue;
+            };
+            if dataset.is_expired(now) {
+                let _ = self.dataset_store.remove_by_name(&name);
+                dropped.push(name);
+                continue;
+            }
+            match dataset.expire_rows(now) {
+                Ok(0) => {}
+                Ok(n) => {
+                    rows_removed += n;
+                    changed.push(name);
+                }
+                Err(e) => eprintln!("Warning: Failed to expire rows of '{}': {}", name, e),
+            }
+        }
+        (changed, dropped, rows_removed)
+    }
+
+    /// Remove dataset `name` with its indices, retained versions and the
+    /// variables bound to it
+    pub fn drop_dataset(&mut self, name: &str) -> Result<Dataset, EngineError> {
+        let dataset = self
+            .dataset_store
+            .remove_by_name(name)
+            .map_err(|_| EngineError::DatasetNotFound(name.to_string()))?;
+        self.dataset_versions.remove(name);
+        self.dataset_vars.retain(|_, target| target != name);
+        Ok(dataset)
+    }
+
+    /// Register dataset `from` as `to`, retargeting the variables bound to
+    /// it. Versions retained under the old name are dropped: the renamed
+    /// dataset starts a new history at its current version.
+    pub fn rename_dataset(&mut self, from: &str, to: &str) -> Result<(), EngineError> {
+        self.get_dataset(from)?;
+        self.dataset_store.rename(from, to)?;
+        self.dataset_versions.remove(from);
+        for target in self.dataset_vars.values_mut() {
+            if target == from {
+                *target = to.to_string();
+            }
+        }
+        Ok(())
+    }
+
+    /// Words of `line`, and of the stored queries it names, that are datasets
+    /// of this database: those in memory and those evicted to storage
+    fn referenced_datasets(&self, line: &str) -> (Vec<String>, Vec<String>) {
+        let identifiers = |text: &str| -> Vec<String> {
+            text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .filter(|word| !word.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let mut words = identifiers(line);
+        let bodies: Vec<String> = words
+            .iter()
+            .filter_map(|word| self.stored_queries.get(word).cloned())
+            .collect();
+        words.extend(bodies.iter().flat_map(|body| identifiers(body)));
+        words.sort();
+        words.dedup();
+        words
+            .into_iter()
+            .filter(|word| {
+                self.dataset_store.get_by_name(word).is_ok()
+                    || self.eviction.evicted.contains_key(word)
+            })
+            .partition(|word| self.dataset_store.get_by_name(word).is_ok())
+    }
+
+    fn copy_on_write(&mut self, line: &str) {
+        if self.transaction.is_none() {
+            return;
+        }
+        let (live, _) = self.referenced_datasets(line);
+        let Some(transaction) = self.transaction.as_mut() else {
+            return;
+        };
+        for name in live {
+            if let Ok(dataset) = self.dataset_store.get_by_name(&name) {
+                transaction.copy_on_write(&name, dataset);
+            }
+        }
+    }
+
+    fn begin_transaction(&mut self, session: Option<String>) -> Result<(), EngineError> {
+        if self.transaction.is_some() {
+            return Err(EngineError::InvalidOp(format!(
+                "A transaction is already open on database '{}'",
+                self.name
+            )));
+        }
+        self.transaction = Some(Transaction::begin(
+            session,
+            self.dataset_store.list_names().into_iter().collect(),
+            self.names.clone(),
+            self.dataset_vars.clone(),
+        ));
+        Ok(())
+    }
+
+    fn commit_transaction(&mut self) -> Result<Vec<String>, EngineError> {
+        let transaction = self
+            .transaction
+            .take()
+            .ok_or_else(|| self.no_transaction())?;
+        Ok(transaction.wal)
+    }
+
+    fn rollback_transaction(&mut self) -> Result<RollbackReport, EngineError> {
+        let transaction = self
+            .transaction
+            .take()
+            .ok_or_else(|| self.no_transaction())?;
+        let mut report = RollbackReport::default();
+
+        let mut names = self.dataset_store.list_names();
+        names.sort();
+        for name in names {
+            if !transaction.existing.contains(&name) {
+                let _ = self.dataset_store.remove_by_name(&name);
+                self.dataset_versions.remove(&name);
+                report.dropped.push(name);
+            }
+        }
+        let mut originals: Vec<_> = transaction.originals.into_iter().collect();
+        originals.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, original) in originals {
+            let _ = self.dataset_store.remove_by_name(&name);
+            // Versions committed inside the transaction are forgotten too
+            let version = original.metadata.version;
+            if let Some(history) = self.dataset_versions.get_mut(&name) {
+                history.retain(|snapshot| snapshot.metadata.version <= version);
+            }
+            self.restore_dataset(original)?;
+            report.restored.push(name);
+        }
+
+        // Tensors bound since BEGIN are released
+        let kept: std::collections::HashSet<TensorId> =
+            transaction.names.values().map(|entry| entry.id).collect();
+        for entry in self.names.values() {
+            if !kept.contains(&entry.id) {
+                self.store.remove(entry.id);
+            }
+        }
+        self.names = transaction.names;
+        self.dataset_vars = transaction.dataset_vars;
+        Ok(report)
+    }
+
+    fn no_transaction(&self) -> EngineError {
+        EngineError::InvalidOp(format!(
+            "No transaction is open on database '{}'",
+            self.name
+        ))
+    }
+
+    /// Register a fully-built dataset (e.g. loaded from storage) under its metadata name
+    pub fn restore_dataset(&mut self, mut dataset: Dataset) -> Result<DatasetId, EngineError> {
+        let name = dataset.metadata.name.clone().ok_or_else(|| {
+            EngineError::InvalidOp("Cannot restore a dataset without a name".to_string())
+        })?;
+        dataset.id = self.dataset_store.gen_id();
+        self.dataset_store
+            .insert(dataset, Some(name))
+            .map_err(EngineError::from)
+    }
+
     /// Get dataset by name
     pub fn get_dataset(&self, name: &str) -> Result<&Dataset, EngineError> {
         self.dataset_store
@@ -983,6 +2763,47 @@ impl DatabaseInstance {
             .map_err(|_| EngineError::DatasetNotFound(name.to_string()))
     }
 
+    /// Version `version` of a dataset: the live dataset if it is current,
+    /// otherwise a retained snapshot
+    pub fn get_dataset_version(&self, name: &str, version: u32) -> Result<&Dataset, EngineError> {
+        let current = self.get_dataset(name)?;
+        if current.metadata.version == version {
+            return Ok(current);
+        }
+        self.dataset_versions
+            .get(name)
+            .and_then(|history| history.iter().find(|ds| ds.metadata.version == version))
+            .map(Arc::as_ref)
+            .ok_or_else(|| {
+                EngineError::InvalidOp(format!(
+                    "Version {} of dataset '{}' is not retained (current version is {})",
+                    version, name, current.metadata.version
+                ))
+            })
+    }
+
+    /// Latest retained version of a dataset last changed at or before `ts`
+    pub fn dataset_version_at(&self, name: &str, ts: DateTime<Utc>) -> Result<u32, EngineError> {
+        let current = self.get_dataset(name)?;
+        if current.metadata.updated_at <= ts {
+            return Ok(current.metadata.version);
+        }
+        self.dataset_versions
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|ds| ds.metadata.updated_at <= ts)
+            .map(|ds| ds.metadata.version)
+            .max()
+            .ok_or_else(|| {
+                EngineError::InvalidOp(format!(
+                    "No retained version of dataset '{}' as of {}",
+                    name,
+                    ts.to_rfc3339()
+                ))
+            })
+    }
+
     /// Get mutable dataset by name
     pub fn get_dataset_mut(&mut self, name: &str) -> Result<&mut Dataset, EngineError> {
         self.dataset_store
@@ -998,6 +2819,22 @@ impl DatabaseInstance {
             .map_err(|e| EngineError::InvalidOp(e))
     }
 
+    /// Append several rows; none are added if one is rejected
+    pub fn insert_rows(
+        &mut self,
+        dataset_name: &str,
+        tuples: Vec<Tuple>,
+    ) -> Result<(), EngineError> {
+        let dataset = self.get_dataset_mut(dataset_name)?;
+        dataset.add_rows(tuples).map_err(EngineError::InvalidOp)
+    }
+
+    /// Insert a row or replace the one with the same primary key
+    pub fn upsert_row(&mut self, dataset_name: &str, tuple: Tuple) -> Result<usize, EngineError> {
+        let dataset = self.get_dataset_mut(dataset_name)?;
+        dataset.upsert_row(tuple).map_err(EngineError::InvalidOp)
+    }
+
     /// List all dataset names
     pub fn list_dataset_names(&self) -> Vec<String> {
         self.dataset_store.list_names()
@@ -1034,6 +2871,76 @@ impl DatabaseInstance {
             .map_err(|e| EngineError::InvalidOp(e))
     }
 
+    /// Remove a column from an existing dataset
+    pub fn alter_dataset_drop_column(
+        &mut self,
+        dataset_name: &str,
+        column_name: &str,
+    ) -> Result<(), EngineError> {
+        let dataset = self.get_dataset_mut(dataset_name)?;
+        dataset
+            .drop_column(column_name)
+            .map_err(EngineError::InvalidOp)
+    }
+
+    /// Rename a column of an existing dataset
+    pub fn alter_dataset_rename_column(
+        &mut self,
+        dataset_name: &str,
+        old_name: &str,
+        new_name: String,
+    ) -> Result<(), EngineError> {
+        let dataset = self.get_dataset_mut(dataset_name)?;
+        dataset
+            .rename_column(old_name, new_name)
+            .map_err(EngineError::InvalidOp)
+    }
+
+    /// Change the type of a column, casting its values
+    pub fn alter_dataset_column_type(
+        &mut self,
+        dataset_name: &str,
+        column_name: &str,
+        value_type: crate::core::value::ValueType,
+    ) -> Result<(), EngineError> {
+        let dataset = self.get_dataset_mut(dataset_name)?;
+        dataset
+            .alter_column_type(column_name, value_type)
+            .map_err(EngineError::InvalidOp)
+    }
+
+    /// Remove the rows of a dataset matching `predicate`
+    pub fn delete_rows(
+        &mut self,
+        dataset_name: &str,
+        predicate: Option<&crate::query::logical::Expr>,
+    ) -> Result<Vec<Tuple>, EngineError> {
+        use crate::query::planner::evaluate_expr;
+
+        let dataset = self.get_dataset_mut(dataset_name)?;
+        dataset
+            .delete_rows(|row| predicate.is_none_or(|p| evaluate_expr(p, row)))
+            .map_err(EngineError::InvalidOp)
+    }
+
+    /// Update the rows of a dataset matching `predicate`
+    pub fn update_rows(
+        &mut self,
+        dataset_name: &str,
+        assignments: &[(String, crate::query::logical::Expr)],
+        predicate: Option<&crate::query::logical::Expr>,
+    ) -> Result<Vec<usize>, EngineError> {
+        use crate::query::planner::evaluate_expr;
+
+        let dataset = self.get_dataset_mut(dataset_name)?;
+        dataset
+            .update_rows(
+                |row| predicate.is_none_or(|p| evaluate_expr(p, row)),
+                assignments,
+            )
+            .map_err(EngineError::InvalidOp)
+    }
+
     /// Materialize lazy columns in a dataset
     pub fn materialize_lazy_columns(&mut self, dataset_name: &str) -> Result<(), EngineError> {
         let dataset = self.get_dataset_mut(dataset_name)?;
@@ -1082,6 +2989,112 @@ impl DatabaseInstance {
         Ok(())
     }
 
+    /// Boolean mask selection: output = tensor[mask]
+    pub fn eval_mask(
+        &mut self,
+        output_name: impl Into<String>,
+        tensor_name: &str,
+        mask_name: &str,
+    ) -> Result<(), EngineError> {
+        let new_id = self.store.gen_id_internal();
+        let (tensor, kind) = self.get_with_kind(tensor_name)?;
+        let mask = self.get(mask_name)?;
+
+        let result = super::kernels::mask_select(tensor, mask, new_id)
+            .map_err(EngineError::InvalidOp)?;
+
+        let out_id = self.store.insert_existing_tensor(result)?;
+        self.names
+            .insert(output_name.into(), NameEntry { id: out_id, kind });
+        Ok(())
+    }
+
+    /// `output_name = WHERE(cond, then, else)`, element by element
+    pub fn eval_where(
+        &mut self,
+        output_name: impl Into<String>,
+        cond_name: &str,
+        then_name: &str,
+        else_name: &str,
+    ) -> Result<(), EngineError> {
+        let new_id = self.store.gen_id_internal();
+        let cond = self.get(cond_name)?;
+        let (then, kind_then) = self.get_with_kind(then_name)?;
+        let (otherwise, kind_else) = self.get_with_kind(else_name)?;
+        let kind = match (kind_then, kind_else) {
+            (TensorKind::Strict, _) | (_, TensorKind::Strict) => TensorKind::Strict,
+            _ => TensorKind::Normal,
+        };
+
+        let result = super::kernels::where_select(cond, then, otherwise, new_id)
+            .map_err(EngineError::InvalidOp)?;
+
+        let out_id = self.store.insert_existing_tensor(result)?;
+        self.names
+            .insert(output_name.into(), NameEntry { id: out_id, kind });
+        Ok(())
+    }
+
+    /// `values_name = TOPK input k`, with the positions of the values in
+    /// `indices_name` when given
+    pub fn eval_topk(
+        &mut self,
+        values_name: impl Into<String>,
+        indices_name: Option<&str>,
+        input_name: &str,
+        k: usize,
+    ) -> Result<(), EngineError> {
+        let values_id = self.store.gen_id_internal();
+        let indices_id = self.store.gen_id_internal();
+        let (input, kind) = self.get_with_kind(input_name)?;
+        let (values, indices) = super::kernels::topk(input, k, values_id, indices_id)
+            .map_err(EngineError::InvalidOp)?;
+
+        let out_id = self.store.insert_existing_tensor(values)?;
+        self.names
+            .insert(values_name.into(), NameEntry { id: out_id, kind });
+        if let Some(indices_name) = indices_name {
+            let out_id = self.store.insert_existing_tensor(indices)?;
+            self.names
+                .insert(indices_name.to_string(), NameEntry { id: out_id, kind });
+        }
+        Ok(())
+    }
+
+    /// New dataset `output_name` with the rows of `dataset_name` selected by
+    /// a vector mask with one element per row. Returns the number of rows kept.
+    pub fn eval_dataset_mask(
+        &mut self,
+        output_name: &str,
+        dataset_name: &str,
+        mask_name: &str,
+    ) -> Result<usize, EngineError> {
+        let mask = self.get(mask_name)?;
+        let source = self.get_dataset(dataset_name)?;
+        if mask.shape.dims != [source.rows.len()] {
+            return Err(EngineError::InvalidOp(format!(
+                "Mask of shape {:?} does not match the {} rows of dataset '{}'",
+                mask.shape.dims,
+                source.rows.len(),
+                dataset_name
+            )));
+        }
+        let rows: Vec<Tuple> = source
+            .rows
+            .iter()
+            .zip(mask.data_ref())
+            .filter(|(_, &m)| super::kernels::is_selected(m))
+            .map(|(row, _)| row.clone())
+            .collect();
+        let schema = source.schema.clone();
+
+        self.create_dataset(output_name.to_string(), schema)?;
+        let target = self.get_dataset_mut(output_name)?;
+        target.rows = rows;
+        target.metadata.update_stats(&target.schema, &target.rows);
+        Ok(target.rows.len())
+    }
+
     /// Access a tuple field: output = tuple.field
     /// Returns the field value as a scalar tensor
     pub fn eval_field_access(
@@ -1234,6 +3247,24 @@ impl DatabaseInstance {
             .map_err(|e| EngineError::InvalidOp(e))
     }
 
+    /// Attach an already-populated index (e.g. restored from storage) to a dataset column
+    pub fn restore_index(
+        &mut self,
+        dataset_name: &str,
+        column_name: &str,
+        index: Box<dyn crate::core::index::Index>,
+    ) -> Result<(), EngineError> {
+        let dataset = self.get_dataset_mut(dataset_name)?;
+        if !dataset.schema.fields.iter().any(|f| f.name == column_name) {
+            return Err(EngineError::InvalidOp(format!(
+                "Column '{}' not found in schema",
+                column_name
+            )));
+        }
+        dataset.indices.insert(column_name.to_string(), index);
+        Ok(())
+    }
+
     /// Get all indices info
     pub fn list_indices(&self) -> Vec<(String, String, String)> {
         let mut result = Vec::new();
@@ -1251,3 +3282,34 @@ impl DatabaseInstance {
         result
     }
 }
+
+/// Read-only copy of a dataset kept for time travel. Indices are dropped:
+/// queries against old versions scan the rows.
+fn version_snapshot(dataset: &Dataset) -> Dataset {
+    Dataset {
+        id: dataset.id,
+        schema: dataset.schema.clone(),
+        rows: dataset.rows.clone(),
+        metadata: dataset.metadata.clone(),
+        indices: HashMap::new(),
+        lazy_expressions: dataset.lazy_expressions.clone(),
+        row_inserted_at: Vec::new(),
+    }
+}
+
+/// Typed error for operands `a` and `b` that `op` cannot combine
+fn shape_mismatch(
+    op: &'static str,
+    left_name: &str,
+    a: &Tensor,
+    right_name: &str,
+    b: &Tensor,
+) -> EngineError {
+    EngineError::ShapeMismatch {
+        left_name: left_name.to_string(),
+        right_name: right_name.to_string(),
+        left_shape: a.shape.dims.clone(),
+        right_shape: b.shape.dims.clone(),
+        op,
+    }
+}
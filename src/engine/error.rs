@@ -7,6 +7,14 @@ pub enum EngineError {
     InvalidOp(String),
     DatasetError(DatasetStoreError),
     DatasetNotFound(String),
+    /// Operands of a binary op whose shapes the kernel cannot combine
+    ShapeMismatch {
+        left_name: String,
+        right_name: String,
+        left_shape: Vec<usize>,
+        right_shape: Vec<usize>,
+        op: &'static str,
+    },
 }
 
 impl From<StoreError> for EngineError {
@@ -29,6 +37,17 @@ impl std::fmt::Display for EngineError {
             EngineError::InvalidOp(msg) => write!(f, "Invalid operation: {}", msg),
             EngineError::DatasetError(e) => write!(f, "Dataset error: {}", e),
             EngineError::DatasetNotFound(name) => write!(f, "Dataset not found: {}", name),
+            EngineError::ShapeMismatch {
+                left_name,
+                right_name,
+                left_shape,
+                right_shape,
+                op,
+            } => write!(
+                f,
+                "Shape mismatch in {}: '{}' has shape {:?} but '{}' has shape {:?}",
+                op, left_name, left_shape, right_name, right_shape
+            ),
         }
     }
 }
@@ -0,0 +1,384 @@
+//! Background jobs (`CREATE INDEX ... ASYNC`, `MATERIALIZE ... ASYNC`). A
+//! job builds its result from a snapshot of its dataset on a thread of its
+//! own, so the database stays free for other commands meanwhile. The next
+//! command run on the database installs the result, provided the dataset is
+//! unchanged since the snapshot; otherwise the job starts over on the
+//! current rows. SHOW JOBS lists the jobs and CANCEL JOB stops one.
+//! Only the latest `[execution] finished_jobs_kept` finished jobs are kept.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::core::dataset_legacy::Dataset;
+use crate::core::index::hash::HashIndex;
+use crate::core::index::vector::VectorIndex;
+use crate::core::index::Index;
+use crate::core::tuple::{Schema, Tuple};
+
+use super::db::DatasetFingerprint;
+
+/// What a job computes from its dataset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobTask {
+    /// Build a hash (or vector) index on `column`
+    Index { column: String, vector: bool },
+    /// Evaluate every lazy column into stored values
+    Materialize,
+}
+
+impl JobTask {
+    /// Compute the result from `dataset`, or `None` once `keep_going`
+    /// (given the rows done so far) returns false
+    fn run(
+        &self,
+        dataset: &Dataset,
+        keep_going: impl FnMut(usize) -> bool,
+    ) -> Result<Option<JobOutput>, String> {
+        match self {
+            JobTask::Index { column, vector } => {
+                let index: Box<dyn Index> = if *vector {
+                    Box::new(VectorIndex::new())
+                } else {
+                    Box::new(HashIndex::new())
+                };
+                Ok(dataset
+                    .populate_index(column, index, keep_going)?
+                    .map(|index| JobOutput::Index {
+                        column: column.clone(),
+                        index,
+                    }))
+            }
+            JobTask::Materialize => Ok(dataset
+                .evaluate_lazy_columns(keep_going)?
+                .map(|(schema, rows)| JobOutput::Materialized { schema, rows })),
+        }
+    }
+}
+
+/// Result of a job, waiting to be installed into its dataset
+#[derive(Debug)]
+pub(crate) enum JobOutput {
+    Index {
+        column: String,
+        index: Box<dyn Index>,
+    },
+    Materialized {
+        schema: Arc<Schema>,
+        rows: Vec<Tuple>,
+    },
+}
+
+/// Where a job stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    /// Working on its snapshot of the dataset
+    Running,
+    /// Finished; installed by the next command on its database
+    Ready,
+    /// Installed into its dataset
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    /// Whether the job is over, successfully or not
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Done | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            JobState::Running => "running",
+            JobState::Ready => "ready",
+            JobState::Done => "done",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Snapshot of a job, as listed by SHOW JOBS
+#[derive(Debug, Clone)]
+pub struct JobStatus {
+    pub id: u64,
+    pub database: String,
+    pub dataset: String,
+    pub command: String,
+    pub state: JobState,
+    pub rows_done: usize,
+    pub rows_total: usize,
+    /// Times the job started over because its dataset changed
+    pub restarts: u32,
+    pub elapsed_secs: f64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug)]
+struct Job {
+    database: String,
+    dataset: String,
+    /// The statement, appended to the WAL once the result is installed
+    command: String,
+    task: JobTask,
+    state: JobState,
+    error: Option<String>,
+    /// Dataset state the current attempt works on
+    fingerprint: DatasetFingerprint,
+    rows_done: Arc<AtomicUsize>,
+    rows_total: usize,
+    cancel: Arc<AtomicBool>,
+    restarts: u32,
+    started: Instant,
+    /// How long the job ran, once it finished
+    elapsed_secs: Option<f64>,
+    output: Option<JobOutput>,
+}
+
+impl Job {
+    fn status(&self, id: u64) -> JobStatus {
+        JobStatus {
+            id,
+            database: self.database.clone(),
+            dataset: self.dataset.clone(),
+            command: self.command.clone(),
+            state: self.state,
+            rows_done: self.rows_done.load(Ordering::Relaxed),
+            rows_total: self.rows_total,
+            restarts: self.restarts,
+            elapsed_secs: self
+                .elapsed_secs
+                .unwrap_or_else(|| self.started.elapsed().as_secs_f64()),
+            error: self.error.clone(),
+        }
+    }
+
+    fn finish(&mut self, state: JobState, error: Option<String>) {
+        self.state = state;
+        self.error = error;
+        self.output = None;
+        self.elapsed_secs = Some(self.started.elapsed().as_secs_f64());
+    }
+}
+
+/// A finished job handed out for installation by `JobRegistry::take_ready`
+#[derive(Debug)]
+pub(crate) struct ReadyJob {
+    pub id: u64,
+    pub dataset: String,
+    pub command: String,
+    pub fingerprint: DatasetFingerprint,
+    pub output: JobOutput,
+}
+
+/// Jobs of an engine, shared with the sessions it lends databases to so
+/// SHOW JOBS and CANCEL JOB reach them all. Finished jobs stay listed until
+/// `finished_kept` newer ones have finished.
+#[derive(Debug)]
+pub struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<BTreeMap<u64, Job>>,
+    finished_kept: usize,
+}
+
+impl JobRegistry {
+    pub fn new(finished_kept: usize) -> Self {
+        Self {
+            next_id: AtomicU64::new(0),
+            jobs: Mutex::new(BTreeMap::new()),
+            finished_kept,
+        }
+    }
+
+    /// Forget the oldest finished jobs past `finished_kept`
+    fn prune(&self, jobs: &mut BTreeMap<u64, Job>) {
+        let finished: Vec<u64> = jobs
+            .iter()
+            .filter(|(_, job)| job.state.is_finished())
+            .map(|(id, _)| *id)
+            .collect();
+        let excess = finished.len().saturating_sub(self.finished_kept);
+        for id in &finished[..excess] {
+            jobs.remove(id);
+        }
+    }
+
+    /// Start `task` on `source`, a snapshot of dataset `dataset` of `database`
+    pub(crate) fn submit(
+        self: &Arc<Self>,
+        database: &str,
+        dataset: &str,
+        command: &str,
+        task: JobTask,
+        source: Arc<Dataset>,
+        fingerprint: DatasetFingerprint,
+    ) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let job = Job {
+            database: database.to_string(),
+            dataset: dataset.to_string(),
+            command: command.to_string(),
+            task,
+            state: JobState::Running,
+            error: None,
+            fingerprint,
+            rows_done: Arc::new(AtomicUsize::new(0)),
+            rows_total: source.rows.len(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            restarts: 0,
+            started: Instant::now(),
+            elapsed_secs: None,
+            output: None,
+        };
+        self.jobs.lock().unwrap().insert(id, job);
+        self.spawn(id, source);
+        id
+    }
+
+    /// Run the current attempt of job `id` on `source` in a thread of its own
+    fn spawn(self: &Arc<Self>, id: u64, source: Arc<Dataset>) {
+        let (task, rows_done, cancel) = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let job = jobs.get_mut(&id).expect("job registered before it runs");
+            // Each attempt gets its own flag, so a superseded one stops too
+            job.cancel = Arc::new(AtomicBool::new(false));
+            job.rows_done = Arc::new(AtomicUsize::new(0));
+            job.rows_total = source.rows.len();
+            (job.task.clone(), job.rows_done.clone(), job.cancel.clone())
+        };
+        let registry = self.clone();
+        std::thread::spawn(move || {
+            let result = task.run(&source, |done| {
+                rows_done.store(done, Ordering::Relaxed);
+                !cancel.load(Ordering::Relaxed)
+            });
+            drop(source);
+            let mut jobs = registry.jobs.lock().unwrap();
+            let Some(job) = jobs.get_mut(&id) else {
+                return;
+            };
+            if !Arc::ptr_eq(&job.cancel, &cancel) || job.state != JobState::Running {
+                return;
+            }
+            match result {
+                Ok(Some(output)) => {
+                    job.state = JobState::Ready;
+                    job.output = Some(output);
+                }
+                Ok(None) => job.finish(JobState::Cancelled, None),
+                Err(e) => job.finish(JobState::Failed, Some(e)),
+            }
+            registry.prune(&mut jobs);
+        });
+    }
+
+    /// Every job, oldest first
+    pub fn list(&self) -> Vec<JobStatus> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, job)| job.status(*id))
+            .collect()
+    }
+
+    /// Stop job `id`. A result not installed yet is discarded.
+    pub fn cancel(&self, id: u64) -> Result<(), String> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs
+            .get_mut(&id)
+            .ok_or_else(|| format!("Job {} not found", id))?;
+        match job.state {
+            JobState::Running | JobState::Ready => {
+                job.cancel.store(true, Ordering::Relaxed);
+                job.finish(JobState::Cancelled, None);
+                self.prune(&mut jobs);
+                Ok(())
+            }
+            state => Err(format!("Job {} is already {}", id, state)),
+        }
+    }
+
+    /// Whether jobs of `database` wait to be installed
+    pub(crate) fn has_ready(&self, database: &str) -> bool {
+        self.jobs
+            .lock()
+            .unwrap()
+            .values()
+            .any(|job| job.state == JobState::Ready && job.database == database)
+    }
+
+    /// Take the results of the finished jobs of `database` whose dataset
+    /// passes `installable`, oldest first. Each must be settled with
+    /// `complete`, `fail` or `restart`.
+    pub(crate) fn take_ready(
+        &self,
+        database: &str,
+        installable: impl Fn(&str) -> bool,
+    ) -> Vec<ReadyJob> {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.iter_mut()
+            .filter(|(_, job)| job.state == JobState::Ready && job.database == database)
+            .filter(|(_, job)| installable(&job.dataset))
+            .filter_map(|(id, job)| {
+                let output = job.output.take()?;
+                job.state = JobState::Running;
+                Some(ReadyJob {
+                    id: *id,
+                    dataset: job.dataset.clone(),
+                    command: job.command.clone(),
+                    fingerprint: job.fingerprint,
+                    output,
+                })
+            })
+            .collect()
+    }
+
+    /// Job `id` was installed into its dataset
+    pub(crate) fn complete(&self, id: u64) {
+        self.settle(id, JobState::Done, None);
+    }
+
+    pub(crate) fn fail(&self, id: u64, error: String) {
+        self.settle(id, JobState::Failed, Some(error));
+    }
+
+    fn settle(&self, id: u64, state: JobState, error: Option<String>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.get_mut(&id) {
+            job.finish(state, error);
+            self.prune(&mut jobs);
+        }
+    }
+
+    /// Start job `id` over on `source`, the current state of its dataset
+    pub(crate) fn restart(
+        self: &Arc<Self>,
+        id: u64,
+        source: Arc<Dataset>,
+        fingerprint: DatasetFingerprint,
+    ) {
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            let Some(job) = jobs
+                .get_mut(&id)
+                .filter(|job| job.state == JobState::Running)
+            else {
+                // Cancelled while it was being installed
+                return;
+            };
+            job.fingerprint = fingerprint;
+            job.restarts += 1;
+        }
+        self.spawn(id, source);
+    }
+}
@@ -1,6 +1,7 @@
 // src/engine/kernels.rs
 
 use crate::core::tensor::{Shape, Tensor, TensorId};
+use crate::engine::operations::CompareOp;
 
 /// Estrategia para combinar dos tensores en una operación elemento a elemento.
 /// Soporta:
@@ -330,6 +331,284 @@ pub fn flatten(a: &Tensor, new_id: TensorId) -> Result<Tensor, String> {
     Tensor::new(new_id, shape, a.data_ref().to_vec())
 }
 
+/// Running sum of a series
+pub fn cumsum_series(xs: &[f32]) -> Vec<f32> {
+    xs.iter()
+        .scan(0.0f32, |acc, &x| {
+            *acc += x;
+            Some(*acc)
+        })
+        .collect()
+}
+
+/// Running product of a series
+pub fn cumprod_series(xs: &[f32]) -> Vec<f32> {
+    xs.iter()
+        .scan(1.0f32, |acc, &x| {
+            *acc *= x;
+            Some(*acc)
+        })
+        .collect()
+}
+
+/// First differences `x[i + 1] - x[i]`, one shorter than the series
+pub fn diff_series(xs: &[f32]) -> Vec<f32> {
+    xs.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+/// Mean of every full window of `window` consecutive values, so the result
+/// has `len - window + 1` values (none when the window is longer)
+pub fn rolling_mean_series(xs: &[f32], window: usize) -> Vec<f32> {
+    if window == 0 || window > xs.len() {
+        return Vec::new();
+    }
+    // Slide a running sum, kept in f64 so long series do not drift
+    let mut sum: f64 = xs[..window].iter().map(|&x| x as f64).sum();
+    let mut out = Vec::with_capacity(xs.len() - window + 1);
+    out.push((sum / window as f64) as f32);
+    for i in window..xs.len() {
+        sum += xs[i] as f64 - xs[i - window] as f64;
+        out.push((sum / window as f64) as f32);
+    }
+    out
+}
+
+/// Apply `series` to every run of values along the last axis; each run
+/// becomes `out_len` values
+fn map_last_axis(
+    a: &Tensor,
+    out_len: usize,
+    new_id: TensorId,
+    series: impl Fn(&[f32]) -> Vec<f32>,
+) -> Result<Tensor, String> {
+    let Some(&len) = a.shape.dims.last() else {
+        return Err("Series operations need a tensor of rank 1 or more".into());
+    };
+    let mut data = Vec::with_capacity(a.shape.num_elements() / len.max(1) * out_len);
+    if len > 0 {
+        for run in a.data_ref().chunks(len) {
+            data.extend(series(run));
+        }
+    }
+    let mut dims = a.shape.dims.clone();
+    *dims.last_mut().unwrap() = out_len;
+    Tensor::new(new_id, Shape::new(dims), data)
+}
+
+/// Cumulative sum along the last axis
+pub fn cumsum(a: &Tensor, new_id: TensorId) -> Result<Tensor, String> {
+    let len = a.shape.dims.last().copied().unwrap_or(0);
+    map_last_axis(a, len, new_id, cumsum_series)
+}
+
+/// Cumulative product along the last axis
+pub fn cumprod(a: &Tensor, new_id: TensorId) -> Result<Tensor, String> {
+    let len = a.shape.dims.last().copied().unwrap_or(0);
+    map_last_axis(a, len, new_id, cumprod_series)
+}
+
+/// First differences along the last axis, which shrinks by one
+pub fn diff(a: &Tensor, new_id: TensorId) -> Result<Tensor, String> {
+    let len = a.shape.dims.last().copied().unwrap_or(0);
+    map_last_axis(a, len.saturating_sub(1), new_id, diff_series)
+}
+
+/// Rolling mean over `window` values along the last axis, which shrinks to
+/// the number of full windows
+pub fn rolling_mean(a: &Tensor, window: usize, new_id: TensorId) -> Result<Tensor, String> {
+    let len = a.shape.dims.last().copied().unwrap_or(0);
+    if window == 0 || window > len {
+        return Err(format!(
+            "Rolling window {} must be between 1 and the last dimension ({})",
+            window, len
+        ));
+    }
+    map_last_axis(a, len - window + 1, new_id, |run| {
+        rolling_mean_series(run, window)
+    })
+}
+
+/// Batched matrix-vector product: `[n, d] x [d] -> [n]`, one dot product per row
+pub fn matvec(a: &Tensor, v: &Tensor, new_id: TensorId) -> Result<Tensor, String> {
+    let (n, d) = match (a.shape.dims.as_slice(), v.shape.dims.as_slice()) {
+        (&[n, d], &[len]) if d == len => (n, d),
+        _ => {
+            return Err(format!(
+                "matvec expects [n, d] and [d], got {:?} and {:?}",
+                a.shape.dims, v.shape.dims
+            ))
+        }
+    };
+    let v = v.data_ref();
+    let data = if d == 0 {
+        vec![0.0; n]
+    } else {
+        a.data_ref()
+            .chunks(d)
+            .map(|row| row.iter().zip(v).map(|(x, y)| x * y).sum())
+            .collect()
+    };
+    Tensor::new(new_id, Shape::new(vec![n]), data)
+}
+
+/// Outer product of two vectors: `[n] x [m] -> [n, m]`
+pub fn outer(a: &Tensor, b: &Tensor, new_id: TensorId) -> Result<Tensor, String> {
+    if a.shape.rank() != 1 || b.shape.rank() != 1 {
+        return Err("OUTER expects two rank-1 tensors (vectors)".into());
+    }
+    let (a_data, b_data) = (a.data_ref(), b.data_ref());
+    let data = a_data
+        .iter()
+        .flat_map(|x| b_data.iter().map(move |y| x * y))
+        .collect();
+    Tensor::new(new_id, Shape::new(vec![a_data.len(), b_data.len()]), data)
+}
+
+/// Rows and columns of a matrix, for the helpers below
+fn matrix_dims(a: &Tensor, op: &str) -> Result<(usize, usize), String> {
+    match a.shape.dims.as_slice() {
+        &[n, d] => Ok((n, d)),
+        _ => Err(format!("{} expects a rank-2 tensor (matrix)", op)),
+    }
+}
+
+/// Data of an `[n, d]` matrix with each column's mean subtracted
+fn centered_columns(a: &Tensor, n: usize, d: usize) -> Vec<f32> {
+    let data = a.data_ref();
+    let mut means = vec![0.0f64; d];
+    for row in data.chunks(d.max(1)).take(n) {
+        for (m, &x) in means.iter_mut().zip(row) {
+            *m += x as f64;
+        }
+    }
+    means.iter_mut().for_each(|m| *m /= n.max(1) as f64);
+    data.iter()
+        .enumerate()
+        .map(|(i, &x)| (x as f64 - means[i % d]) as f32)
+        .collect()
+}
+
+/// Symmetric `[size, size]` matrix from `entry(i, j)`, computing each pair once
+fn symmetric(
+    size: usize,
+    new_id: TensorId,
+    entry: impl Fn(usize, usize) -> f32,
+) -> Result<Tensor, String> {
+    let mut data = vec![0.0; size * size];
+    for i in 0..size {
+        for j in i..size {
+            let value = entry(i, j);
+            data[i * size + j] = value;
+            data[j * size + i] = value;
+        }
+    }
+    Tensor::new(new_id, Shape::new(vec![size, size]), data)
+}
+
+/// Covariance of the columns of `[n, d]` (rows are observations) as `[d, d]`.
+/// Divides by `n - 1`, or by `n` for the population covariance.
+pub fn cov(a: &Tensor, population: bool, new_id: TensorId) -> Result<Tensor, String> {
+    let (n, d) = matrix_dims(a, "COV")?;
+    let divisor = if population { n } else { n.saturating_sub(1) };
+    if divisor == 0 {
+        return Err(format!("COV needs at least {} rows", n + 1));
+    }
+    let x = centered_columns(a, n, d);
+    symmetric(d, new_id, |i, j| {
+        let sum: f64 = (0..n)
+            .map(|r| x[r * d + i] as f64 * x[r * d + j] as f64)
+            .sum();
+        (sum / divisor as f64) as f32
+    })
+}
+
+/// Gram matrix of the rows of `[n, d]`: `[n, n]` of pairwise dot products,
+/// optionally after centering the columns
+pub fn gram(a: &Tensor, centered: bool, new_id: TensorId) -> Result<Tensor, String> {
+    let (n, d) = matrix_dims(a, "GRAM")?;
+    let x = if centered {
+        centered_columns(a, n, d)
+    } else {
+        a.data_ref().to_vec()
+    };
+    symmetric(n, new_id, |i, j| {
+        x[i * d..(i + 1) * d]
+            .iter()
+            .zip(&x[j * d..(j + 1) * d])
+            .map(|(p, q)| p * q)
+            .sum()
+    })
+}
+
+/// Order of `a` and `b` for sorting: NaN goes last in either direction
+fn sort_order(a: f32, b: f32, descending: bool) -> std::cmp::Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => std::cmp::Ordering::Equal,
+        (true, false) => std::cmp::Ordering::Greater,
+        (false, true) => std::cmp::Ordering::Less,
+        _ if descending => b.total_cmp(&a),
+        _ => a.total_cmp(&b),
+    }
+}
+
+/// Positions of `xs` in sorted order; ties keep their original order
+pub fn argsort_series(xs: &[f32], descending: bool) -> Vec<usize> {
+    let mut idx: Vec<usize> = (0..xs.len()).collect();
+    idx.sort_by(|&i, &j| sort_order(xs[i], xs[j], descending));
+    idx
+}
+
+/// Values sorted along the last axis
+pub fn sort(a: &Tensor, descending: bool, new_id: TensorId) -> Result<Tensor, String> {
+    let len = a.shape.dims.last().copied().unwrap_or(0);
+    map_last_axis(a, len, new_id, |run| {
+        argsort_series(run, descending)
+            .into_iter()
+            .map(|i| run[i])
+            .collect()
+    })
+}
+
+/// Positions that sort each run along the last axis, as floats
+pub fn argsort(a: &Tensor, descending: bool, new_id: TensorId) -> Result<Tensor, String> {
+    let len = a.shape.dims.last().copied().unwrap_or(0);
+    map_last_axis(a, len, new_id, |run| {
+        argsort_series(run, descending)
+            .into_iter()
+            .map(|i| i as f32)
+            .collect()
+    })
+}
+
+/// The `k` largest values along the last axis, largest first, and their
+/// positions in the run
+pub fn topk(
+    a: &Tensor,
+    k: usize,
+    values_id: TensorId,
+    indices_id: TensorId,
+) -> Result<(Tensor, Tensor), String> {
+    let len = a.shape.dims.last().copied().unwrap_or(0);
+    if k == 0 || k > len {
+        return Err(format!(
+            "TOPK k = {} must be between 1 and the last dimension ({})",
+            k, len
+        ));
+    }
+    let top = |run: &[f32]| -> Vec<usize> {
+        let mut idx = argsort_series(run, true);
+        idx.truncate(k);
+        idx
+    };
+    let values = map_last_axis(a, k, values_id, |run| {
+        top(run).into_iter().map(|i| run[i]).collect()
+    })?;
+    let indices = map_last_axis(a, k, indices_id, |run| {
+        top(run).into_iter().map(|i| i as f32).collect()
+    })?;
+    Ok((values, indices))
+}
+
 /// Slice a tensor along a dimension
 /// Returns a new tensor with elements from start (inclusive) to end (exclusive)
 pub fn slice(
@@ -444,18 +723,71 @@ pub fn index_to_scalar(a: &Tensor, indices: &[usize], new_id: TensorId) -> Resul
 }
 
 /// Slice specification for a single dimension
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SliceSpec {
-    /// Single index: reduces dimension
-    Index(usize),
-    /// Range: start..end (inclusive start, exclusive end)
-    Range(usize, usize),
+    /// Single index, negative counting from the end: reduces dimension
+    Index(isize),
+    /// `start:end:step` as in NumPy. Negative bounds count from the end,
+    /// omitted ones (`None`) span the dimension in the direction of `step`,
+    /// and bounds past either end are clamped.
+    Range {
+        start: Option<isize>,
+        end: Option<isize>,
+        step: isize,
+    },
     /// Wildcard: entire dimension
     All,
 }
 
-/// Multi-dimensional slicing
-/// Supports: m[0, *], m[0:2, :], m[*, 1], etc.
+impl SliceSpec {
+    /// Positions selected along a dimension of size `len`, in output order
+    pub fn positions(&self, len: usize) -> Result<Vec<usize>, String> {
+        let len = len as isize;
+        match *self {
+            SliceSpec::All => Ok((0..len as usize).collect()),
+            SliceSpec::Index(idx) => {
+                let resolved = if idx < 0 { idx + len } else { idx };
+                if !(0..len).contains(&resolved) {
+                    return Err(format!(
+                        "Index {} out of bounds for dimension of size {}",
+                        idx, len
+                    ));
+                }
+                Ok(vec![resolved as usize])
+            }
+            SliceSpec::Range { start, end, step } => {
+                if step == 0 {
+                    return Err("Slice step cannot be zero".to_string());
+                }
+                // Going backwards the bounds range over -1..len-1, where -1
+                // stands for "before the first element"
+                let (lowest, highest) = if step > 0 { (0, len) } else { (-1, len - 1) };
+                let resolve = |bound: Option<isize>, default: isize| match bound {
+                    None => default,
+                    Some(b) if b < 0 => (b + len).clamp(lowest, highest),
+                    Some(b) => b.clamp(lowest, highest),
+                };
+                let (start, end) = if step > 0 {
+                    (resolve(start, 0), resolve(end, len))
+                } else {
+                    (resolve(start, len - 1), resolve(end, -1))
+                };
+
+                let mut positions = Vec::new();
+                let mut i = start;
+                while (step > 0 && i < end) || (step < 0 && i > end) {
+                    positions.push(i as usize);
+                    i += step;
+                }
+                Ok(positions)
+            }
+        }
+    }
+}
+
+/// Multi-dimensional slicing with one spec per dimension, e.g. `m[0, *]`,
+/// `m[0:2, :]`, `v[-1]` or `m[::-1, 1::2]`. Indexed dimensions are dropped
+/// from the result; indexing every dimension yields a scalar.
 pub fn slice_multi(a: &Tensor, specs: &[SliceSpec], new_id: TensorId) -> Result<Tensor, String> {
     if specs.len() != a.shape.rank() {
         return Err(format!(
@@ -465,100 +797,167 @@ pub fn slice_multi(a: &Tensor, specs: &[SliceSpec], new_id: TensorId) -> Result<
         ));
     }
 
-    // For rank-1 (vectors)
-    if a.shape.rank() == 1 {
-        match &specs[0] {
-            SliceSpec::Index(idx) => {
-                // Single element -> scalar
-                index_to_scalar(a, &[*idx], new_id)
-            }
-            SliceSpec::Range(start, end) => {
-                // Range -> vector slice
-                slice(a, 0, *start, *end, new_id)
-            }
-            SliceSpec::All => {
-                // Entire vector -> copy
-                Ok(a.clone())
-            }
-        }
+    let selections = specs
+        .iter()
+        .zip(&a.shape.dims)
+        .enumerate()
+        .map(|(dim, (spec, &len))| {
+            spec.positions(len)
+                .map_err(|e| format!("{} (dimension {})", e, dim))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let dims: Vec<usize> = specs
+        .iter()
+        .zip(&selections)
+        .filter(|(spec, _)| !matches!(spec, SliceSpec::Index(_)))
+        .map(|(_, positions)| positions.len())
+        .collect();
+
+    // Row-major strides of the source
+    let mut strides = vec![1; a.shape.rank()];
+    for dim in (0..a.shape.rank().saturating_sub(1)).rev() {
+        strides[dim] = strides[dim + 1] * a.shape.dims[dim + 1];
     }
-    // For rank-2 (matrices)
-    else if a.shape.rank() == 2 {
-        let rows = a.shape.dims[0];
-        let cols = a.shape.dims[1];
-
-        match (&specs[0], &specs[1]) {
-            // Single element: m[i, j]
-            (SliceSpec::Index(i), SliceSpec::Index(j)) => index_to_scalar(a, &[*i, *j], new_id),
-            // Row slice: m[i, *] or m[i, :]
-            (SliceSpec::Index(i), SliceSpec::All) => {
-                // Extract row as vector
-                let mut data = Vec::with_capacity(cols);
-                for j in 0..cols {
-                    data.push(a.data_ref()[i * cols + j]);
-                }
-                let shape = Shape::new(vec![cols]);
-                Tensor::new(new_id, shape, data)
-            }
-            // Column slice: m[*, j] or m[:, j]
-            (SliceSpec::All, SliceSpec::Index(j)) => {
-                // Extract column as vector
-                let mut data = Vec::with_capacity(rows);
-                for i in 0..rows {
-                    data.push(a.data_ref()[i * cols + j]);
-                }
-                let shape = Shape::new(vec![rows]);
-                Tensor::new(new_id, shape, data)
-            }
-            // Row range: m[i:k, *]
-            (SliceSpec::Range(start, end), SliceSpec::All) => slice(a, 0, *start, *end, new_id),
-            // Column range: m[*, j:k]
-            (SliceSpec::All, SliceSpec::Range(start, end)) => slice(a, 1, *start, *end, new_id),
-            // Submatrix: m[i:k, j:l]
-            (SliceSpec::Range(row_start, row_end), SliceSpec::Range(col_start, col_end)) => {
-                let new_rows = row_end - row_start;
-                let new_cols = col_end - col_start;
-                let mut data = Vec::with_capacity(new_rows * new_cols);
-
-                for i in *row_start..*row_end {
-                    for j in *col_start..*col_end {
-                        data.push(a.data_ref()[i * cols + j]);
-                    }
-                }
 
-                let shape = Shape::new(vec![new_rows, new_cols]);
-                Tensor::new(new_id, shape, data)
-            }
-            // Full matrix: m[*, *]
-            (SliceSpec::All, SliceSpec::All) => Ok(a.clone()),
-            // Mixed cases with ranges
-            (SliceSpec::Index(i), SliceSpec::Range(start, end)) => {
-                // Row i, columns start:end -> vector
-                let new_cols = end - start;
-                let mut data = Vec::with_capacity(new_cols);
-                for j in *start..*end {
-                    data.push(a.data_ref()[i * cols + j]);
+    let total: usize = selections.iter().map(Vec::len).product();
+    let source = a.data_ref();
+    let mut data = Vec::with_capacity(total);
+    if total > 0 {
+        // Odometer over the selected positions, last dimension fastest
+        let mut cursor = vec![0; selections.len()];
+        'outer: loop {
+            let offset: usize = cursor
+                .iter()
+                .zip(&selections)
+                .zip(&strides)
+                .map(|((&c, positions), stride)| positions[c] * stride)
+                .sum();
+            data.push(source[offset]);
+
+            for dim in (0..cursor.len()).rev() {
+                cursor[dim] += 1;
+                if cursor[dim] < selections[dim].len() {
+                    continue 'outer;
                 }
-                let shape = Shape::new(vec![new_cols]);
-                Tensor::new(new_id, shape, data)
-            }
-            (SliceSpec::Range(start, end), SliceSpec::Index(j)) => {
-                // Rows start:end, column j -> vector
-                let new_rows = end - start;
-                let mut data = Vec::with_capacity(new_rows);
-                for i in *start..*end {
-                    data.push(a.data_ref()[i * cols + j]);
-                }
-                let shape = Shape::new(vec![new_rows]);
-                Tensor::new(new_id, shape, data)
+                cursor[dim] = 0;
             }
+            break;
         }
+    }
+
+    Tensor::new(new_id, Shape::new(dims), data)
+}
+
+/// Element-wise comparison: 1.0 where `a op b` holds, 0.0 elsewhere. Shapes
+/// must match, or either side may be a scalar.
+pub fn compare(a: &Tensor, b: &Tensor, op: CompareOp, new_id: TensorId) -> Result<Tensor, String> {
+    let (x, y) = (a.data_ref(), b.data_ref());
+    let mask = |holds: bool| if holds { 1.0 } else { 0.0 };
+    let (shape, data): (Shape, Vec<f32>) = if a.shape == b.shape {
+        let data = x
+            .iter()
+            .zip(y)
+            .map(|(&p, &q)| mask(op.holds(p, q)))
+            .collect();
+        (a.shape.clone(), data)
+    } else if b.shape.rank() == 0 {
+        let data = x.iter().map(|&p| mask(op.holds(p, y[0]))).collect();
+        (a.shape.clone(), data)
+    } else if a.shape.rank() == 0 {
+        let data = y.iter().map(|&q| mask(op.holds(x[0], q))).collect();
+        (b.shape.clone(), data)
     } else {
-        Err(format!(
-            "Multi-dimensional slicing not yet implemented for rank-{} tensors",
-            a.shape.rank()
-        ))
+        return Err(format!(
+            "Cannot compare tensors of shapes {:?} and {:?}",
+            a.shape.dims, b.shape.dims
+        ));
+    };
+    Tensor::new(new_id, shape, data)
+}
+
+/// Whether a mask element selects its position (non-zero, not NaN)
+pub fn is_selected(mask_value: f32) -> bool {
+    mask_value != 0.0 && !mask_value.is_nan()
+}
+
+/// Boolean mask selection, as `a[mask]` in NumPy. A mask of the same shape
+/// as `a` picks elements into a vector, in row-major order; a vector mask as
+/// long as the first dimension picks whole rows (sub-tensors).
+pub fn mask_select(a: &Tensor, mask: &Tensor, new_id: TensorId) -> Result<Tensor, String> {
+    let data = a.data_ref();
+    let selected = mask.data_ref().iter().map(|&m| is_selected(m));
+    if mask.shape == a.shape {
+        let picked: Vec<f32> = data
+            .iter()
+            .zip(selected)
+            .filter(|(_, keep)| *keep)
+            .map(|(&v, _)| v)
+            .collect();
+        return Tensor::new(new_id, Shape::new(vec![picked.len()]), picked);
     }
+    if mask.shape.rank() == 1 && a.shape.rank() > 1 && mask.shape.dims[0] == a.shape.dims[0] {
+        let row_len: usize = a.shape.dims[1..].iter().product();
+        let mut picked = Vec::new();
+        let mut rows = 0;
+        for (row, keep) in selected.enumerate() {
+            if keep {
+                picked.extend_from_slice(&data[row * row_len..(row + 1) * row_len]);
+                rows += 1;
+            }
+        }
+        let mut dims = a.shape.dims.clone();
+        dims[0] = rows;
+        return Tensor::new(new_id, Shape::new(dims), picked);
+    }
+    Err(format!(
+        "Mask of shape {:?} does not match tensor of shape {:?}",
+        mask.shape.dims, a.shape.dims
+    ))
+}
+
+/// Element-wise ternary select, as `np.where(cond, a, b)`: `a` where `cond`
+/// is selected (non-zero, not NaN), `b` elsewhere. The non-scalar operands
+/// must share one shape; scalars are broadcast.
+pub fn where_select(
+    cond: &Tensor,
+    a: &Tensor,
+    b: &Tensor,
+    new_id: TensorId,
+) -> Result<Tensor, String> {
+    let operands = [cond, a, b];
+    let shape = operands
+        .iter()
+        .map(|t| &t.shape)
+        .find(|s| s.rank() > 0)
+        .cloned()
+        .unwrap_or_else(|| Shape::new(vec![]));
+    if let Some(t) = operands
+        .iter()
+        .find(|t| t.shape.rank() > 0 && t.shape != shape)
+    {
+        return Err(format!(
+            "WHERE operands must share one shape or be scalars: {:?} and {:?}",
+            shape.dims, t.shape.dims
+        ));
+    }
+    let at = |t: &Tensor, i: usize| {
+        let data = t.data_ref();
+        if t.shape.rank() == 0 {
+            data[0]
+        } else {
+            data[i]
+        }
+    };
+    let data = (0..shape.num_elements())
+        .map(|i| {
+            if is_selected(at(cond, i)) {
+                at(a, i)
+            } else {
+                at(b, i)
+            }
+        })
+        .collect();
+    Tensor::new(new_id, shape, data)
 }
 
 /// Stack a list of tensors along a new axis (0 for now)
@@ -0,0 +1,131 @@
+//! Soft memory budget (`[memory]`). Datasets are charged their estimated
+//! in-memory size; when the total passes the budget's threshold, the least
+//! recently used ones are written to storage and dropped from memory, then
+//! reloaded by the next statement that names them.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::{DateTime, Utc};
+
+use crate::core::dataset_legacy::Dataset;
+use crate::core::tuple::Tuple;
+use crate::core::value::Value;
+
+/// Process-wide access clock, so recency compares across databases
+static ACCESS_CLOCK: AtomicU64 = AtomicU64::new(1);
+
+fn tick() -> u64 {
+    ACCESS_CLOCK.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Last update time, row count and column count of a dataset
+type Fingerprint = (DateTime<Utc>, usize, usize);
+
+/// A dataset written to storage and dropped from memory
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvictedDataset {
+    pub name: String,
+    pub rows: usize,
+    /// Estimated in-memory size when it was evicted
+    pub bytes: usize,
+    pub evicted_at: DateTime<Utc>,
+}
+
+/// Eviction counters, summed over databases by `TensorDb::memory_stats`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Estimated size of the datasets held in memory
+    pub resident_bytes: usize,
+    pub evictions: usize,
+    pub bytes_evicted: usize,
+    pub reloads: usize,
+    pub bytes_reloaded: usize,
+}
+
+/// Per-database bookkeeping; it moves with the database into sessions
+#[derive(Debug, Default, Clone)]
+pub struct EvictionState {
+    /// Access clock of the last statement that named each dataset
+    last_used: HashMap<String, u64>,
+    /// Estimated size per dataset, valid while its (update time, rows, columns) hold
+    sizes: HashMap<String, (Fingerprint, usize)>,
+    pub(crate) evicted: BTreeMap<String, EvictedDataset>,
+    pub(crate) stats: MemoryStats,
+}
+
+impl EvictionState {
+    /// Mark `name` as used now
+    pub fn touch(&mut self, name: &str) {
+        self.last_used.insert(name.to_string(), tick());
+    }
+
+    /// Access clock of `name`; datasets no statement named yet (e.g. recovered
+    /// ones) are the coldest
+    pub fn last_used(&self, name: &str) -> u64 {
+        self.last_used.get(name).copied().unwrap_or(0)
+    }
+
+    /// Estimated size of `dataset`, recomputed only when it changed
+    pub fn size_of(&mut self, name: &str, dataset: &Dataset) -> usize {
+        let fingerprint = (
+            dataset.metadata.updated_at,
+            dataset.rows.len(),
+            dataset.schema.len(),
+        );
+        match self.sizes.get(name) {
+            Some((cached, bytes)) if *cached == fingerprint => *bytes,
+            _ => {
+                let bytes = estimated_size(dataset);
+                self.sizes.insert(name.to_string(), (fingerprint, bytes));
+                bytes
+            }
+        }
+    }
+
+    /// Forget the sizes and recency of datasets no longer in `live`
+    pub fn retain(&mut self, live: &[String]) {
+        self.sizes.retain(|name, _| live.contains(name));
+        self.last_used
+            .retain(|name, _| live.contains(name) || self.evicted.contains_key(name));
+    }
+
+    pub fn record_eviction(&mut self, evicted: EvictedDataset) {
+        self.stats.evictions += 1;
+        self.stats.bytes_evicted += evicted.bytes;
+        self.sizes.remove(&evicted.name);
+        self.evicted.insert(evicted.name.clone(), evicted);
+    }
+
+    pub fn record_reload(&mut self, name: &str) {
+        if let Some(evicted) = self.evicted.remove(name) {
+            self.stats.reloads += 1;
+            self.stats.bytes_reloaded += evicted.bytes;
+        }
+    }
+}
+
+/// Approximate heap footprint of a dataset's rows
+pub fn estimated_size(dataset: &Dataset) -> usize {
+    rows_size(&dataset.rows)
+}
+
+/// Approximate heap footprint of `rows`
+pub fn rows_size(rows: &[Tuple]) -> usize {
+    rows.iter()
+        .map(|row| std::mem::size_of::<Tuple>() + row.values.iter().map(value_size).sum::<usize>())
+        .sum()
+}
+
+fn value_size(value: &Value) -> usize {
+    let heap = match value {
+        Value::String(s) => s.capacity(),
+        Value::Vector(v) => v.capacity() * std::mem::size_of::<f32>(),
+        Value::Matrix(m) => m
+            .iter()
+            .map(|r| std::mem::size_of::<Vec<f32>>() + r.capacity() * std::mem::size_of::<f32>())
+            .sum(),
+        _ => 0,
+    };
+    std::mem::size_of::<Value>() + heap
+}
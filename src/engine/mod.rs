@@ -1,10 +1,24 @@
+pub mod audit;
 pub mod context;
 pub mod db;
 pub mod error;
 pub mod executor;
+pub mod jobs;
 pub mod kernels;
+pub mod memory;
 pub mod operations;
+pub mod plugin;
+pub mod progress;
+pub mod seed;
+pub mod transaction;
 
-pub use db::TensorDb;
+pub use db::{
+    DatabaseFork, DatabaseSnapshot, DatasetFingerprints, ExecutionStats, ExpiryReport, TensorDb,
+};
 pub use error::EngineError;
-pub use operations::{BinaryOp, TensorKind, UnaryOp};
+pub use jobs::{JobRegistry, JobState, JobStatus, JobTask};
+pub use memory::{EvictedDataset, MemoryStats};
+pub use operations::{BinaryOp, CompareOp, TensorKind, UnaryOp};
+pub use plugin::{EnginePlugin, QueryEvent};
+pub use progress::{QueryRegistry, QueryStatus};
+pub use transaction::RollbackReport;
@@ -15,6 +15,80 @@ pub enum BinaryOp {
     Similarity,
     /// DISTANCE a TO b -> distancia L2 (rank-1)
     Distance,
+    /// a > b, a = b, ... -> mask of 1.0 where it holds and 0.0 elsewhere
+    Compare(CompareOp),
+    /// OUTER a b -> [len(a), len(b)] matrix of products (rank-1)
+    Outer,
+}
+
+/// Element-wise comparisons producing mask tensors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl CompareOp {
+    /// Operator as written in the DSL
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "!=",
+        }
+    }
+
+    pub fn holds(&self, a: f32, b: f32) -> bool {
+        match self {
+            CompareOp::Gt => a > b,
+            CompareOp::Ge => a >= b,
+            CompareOp::Lt => a < b,
+            CompareOp::Le => a <= b,
+            CompareOp::Eq => a == b,
+            CompareOp::Ne => a != b,
+        }
+    }
+}
+
+impl BinaryOp {
+    /// DSL keyword for this operation, used in error messages
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "ADD",
+            BinaryOp::Subtract => "SUBTRACT",
+            BinaryOp::Multiply => "MULTIPLY",
+            BinaryOp::Divide => "DIVIDE",
+            BinaryOp::Correlate => "CORRELATE",
+            BinaryOp::Similarity => "SIMILARITY",
+            BinaryOp::Distance => "DISTANCE",
+            BinaryOp::Compare(op) => op.symbol(),
+            BinaryOp::Outer => "OUTER",
+        }
+    }
+
+    /// Whether the kernels accept operands of these shapes. Element-wise ops
+    /// take equal shapes, a scalar on either side or two vectors (padded);
+    /// the vector ops need two vectors of the same length. Comparisons take
+    /// equal shapes or a scalar on either side; OUTER takes any two vectors.
+    pub fn accepts_shapes(&self, a: &[usize], b: &[usize]) -> bool {
+        match self {
+            BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide => {
+                a == b || a.is_empty() || b.is_empty() || (a.len() == 1 && b.len() == 1)
+            }
+            BinaryOp::Correlate | BinaryOp::Similarity | BinaryOp::Distance => {
+                a.len() == 1 && a == b
+            }
+            BinaryOp::Compare(_) => a == b || a.is_empty() || b.is_empty(),
+            BinaryOp::Outer => a.len() == 1 && b.len() == 1,
+        }
+    }
 }
 
 /// Operaciones unarias
@@ -28,6 +102,22 @@ pub enum UnaryOp {
     Transpose,
     /// FLATTEN a (flatten to 1D)
     Flatten,
+    /// CUMSUM a (running sum along the last axis)
+    CumSum,
+    /// CUMPROD a (running product along the last axis)
+    CumProd,
+    /// DIFF a (first differences along the last axis)
+    Diff,
+    /// ROLLING_MEAN a WINDOW n (mean of each full window along the last axis)
+    RollingMean(usize),
+    /// SORT a [DESC] (sorted values along the last axis)
+    Sort { descending: bool },
+    /// ARGSORT a [DESC] (sorting positions along the last axis)
+    ArgSort { descending: bool },
+    /// COV m [POPULATION] (column covariance of a matrix)
+    Cov { population: bool },
+    /// GRAM m [CENTERED] (pairwise dot products of a matrix's rows)
+    Gram { centered: bool },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
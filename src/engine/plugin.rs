@@ -0,0 +1,35 @@
+//! Extension hooks. Downstream crates implement `EnginePlugin` and pass it
+//! to `TensorDb::with_plugins` to add indexing, caching or audit behavior
+//! without patching the engine.
+
+use crate::core::dataset_legacy::Dataset;
+use crate::core::tuple::Tuple;
+use std::time::Duration;
+
+/// Observer of dataset and query events. Every hook defaults to doing
+/// nothing; hooks run synchronously on the thread executing the command.
+pub trait EnginePlugin: Send + Sync {
+    /// Name listed by `TensorDb::plugin_names`
+    fn name(&self) -> &str;
+
+    /// A dataset was created in `database`
+    fn on_dataset_created(&self, _database: &str, _dataset: &Dataset) {}
+
+    /// `rows` were appended to (or upserted into) `dataset`
+    fn on_rows_inserted(&self, _database: &str, _dataset: &str, _rows: &[Tuple]) {}
+
+    /// A top-level command finished, successfully or not
+    fn on_query_executed(&self, _event: &QueryEvent<'_>) {}
+}
+
+/// A command as seen by `EnginePlugin::on_query_executed`
+#[derive(Debug, Clone)]
+pub struct QueryEvent<'a> {
+    /// Database the command started on
+    pub database: &'a str,
+    pub command: &'a str,
+    pub elapsed: Duration,
+    /// Rows of the result table (None for other outputs and failures)
+    pub rows_returned: Option<usize>,
+    pub error: Option<String>,
+}
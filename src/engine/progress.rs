@@ -0,0 +1,146 @@
+//! Progress of the commands being executed, reported by SHOW QUERIES and
+//! `GET /queries` so a slow query can be told apart from a hung one
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Commands in flight, shared by a `TensorDb` and the sessions it lends
+/// databases to
+#[derive(Debug, Default)]
+pub struct QueryRegistry {
+    next_id: AtomicU64,
+    running: Mutex<BTreeMap<u64, Arc<QueryProgress>>>,
+}
+
+impl QueryRegistry {
+    /// Register a command; it is listed until the returned guard is dropped
+    pub fn begin(self: &Arc<Self>, database: &str, command: &str) -> QueryGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let progress = Arc::new(QueryProgress::new(id, database, command));
+        self.running.lock().unwrap().insert(id, progress.clone());
+        QueryGuard {
+            registry: self.clone(),
+            progress,
+        }
+    }
+
+    /// Commands running for at least `min_elapsed`, oldest first
+    pub fn running(&self, min_elapsed: Duration) -> Vec<QueryStatus> {
+        let running: Vec<_> = self.running.lock().unwrap().values().cloned().collect();
+        running
+            .iter()
+            .filter(|query| query.started.elapsed() >= min_elapsed)
+            .map(|query| query.status())
+            .collect()
+    }
+}
+
+/// Keeps a command listed in its `QueryRegistry`
+#[derive(Debug)]
+pub struct QueryGuard {
+    registry: Arc<QueryRegistry>,
+    progress: Arc<QueryProgress>,
+}
+
+impl QueryGuard {
+    pub fn progress(&self) -> &Arc<QueryProgress> {
+        &self.progress
+    }
+}
+
+impl Drop for QueryGuard {
+    fn drop(&mut self) {
+        self.registry
+            .running
+            .lock()
+            .unwrap()
+            .remove(&self.progress.id);
+    }
+}
+
+/// Counters of one running command, advanced by its scan operators
+#[derive(Debug)]
+pub struct QueryProgress {
+    pub id: u64,
+    pub database: String,
+    pub command: String,
+    started: Instant,
+    /// Rows read by every operator of the command so far
+    rows_processed: AtomicUsize,
+    /// Operator currently reading rows and when it started
+    operator: Mutex<Option<(String, Instant)>>,
+    operator_rows_done: AtomicUsize,
+    operator_rows_total: AtomicUsize,
+}
+
+impl QueryProgress {
+    fn new(id: u64, database: &str, command: &str) -> Self {
+        Self {
+            id,
+            database: database.to_string(),
+            command: command.to_string(),
+            started: Instant::now(),
+            rows_processed: AtomicUsize::new(0),
+            operator: Mutex::new(None),
+            operator_rows_done: AtomicUsize::new(0),
+            operator_rows_total: AtomicUsize::new(0),
+        }
+    }
+
+    /// `operator` starts reading `rows_total` rows
+    pub fn start_operator(&self, operator: String, rows_total: usize) {
+        *self.operator.lock().unwrap() = Some((operator, Instant::now()));
+        self.operator_rows_done.store(0, Ordering::Relaxed);
+        self.operator_rows_total
+            .store(rows_total, Ordering::Relaxed);
+    }
+
+    /// The current operator read `rows` more rows
+    pub fn advance(&self, rows: usize) {
+        self.rows_processed.fetch_add(rows, Ordering::Relaxed);
+        self.operator_rows_done.fetch_add(rows, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> QueryStatus {
+        let operator = self.operator.lock().unwrap().clone();
+        let done = self.operator_rows_done.load(Ordering::Relaxed);
+        let total = self.operator_rows_total.load(Ordering::Relaxed);
+        let rows_remaining = operator.as_ref().map(|_| total.saturating_sub(done));
+        // Extrapolate the current operator's rate over the rows it has left
+        let estimated_remaining_secs = match (&operator, rows_remaining) {
+            (Some((_, started)), Some(remaining)) if done > 0 => {
+                Some(started.elapsed().as_secs_f64() / done as f64 * remaining as f64)
+            }
+            _ => None,
+        };
+        QueryStatus {
+            id: self.id,
+            database: self.database.clone(),
+            command: self.command.clone(),
+            elapsed_secs: self.started.elapsed().as_secs_f64(),
+            operator: operator.map(|(name, _)| name),
+            rows_processed: self.rows_processed.load(Ordering::Relaxed),
+            rows_remaining,
+            estimated_remaining_secs,
+        }
+    }
+}
+
+/// Snapshot of a running command
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryStatus {
+    pub id: u64,
+    pub database: String,
+    pub command: String,
+    pub elapsed_secs: f64,
+    /// Operator reading rows right now (None before the first scan starts)
+    pub operator: Option<String>,
+    pub rows_processed: usize,
+    /// Rows the current operator has yet to read
+    pub rows_remaining: Option<usize>,
+    /// Time the current operator needs for its remaining rows at its rate so far
+    pub estimated_remaining_secs: Option<f64>,
+}
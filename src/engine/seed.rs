@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use crate::core::storage::{ParquetStorage, StorageEngine};
+use crate::dsl::{execute_line, split_statements};
+
+use super::db::TensorDb;
+use super::error::EngineError;
+
+/// Whether `dir` does not exist or has nothing in it
+pub fn is_empty_dir(dir: &Path) -> bool {
+    std::fs::read_dir(dir).map_or(true, |mut entries| entries.next().is_none())
+}
+
+/// Apply the `[seed]` section when the data directory is still empty: load
+/// its Parquet directories, then run its scripts, all on the default database.
+/// Returns whether anything was seeded.
+pub fn seed_empty_data_dir(db: &mut TensorDb) -> Result<bool, EngineError> {
+    let seed = db.config.seed.clone();
+    if (seed.parquet.is_empty() && seed.scripts.is_empty())
+        || !is_empty_dir(&db.config.storage.data_dir)
+    {
+        return Ok(false);
+    }
+
+    let default_db = db.config.storage.default_db.clone();
+    for dir in &seed.parquet {
+        db.with_database(&default_db, |db| load_parquet_dir(db, dir))??;
+    }
+    for script in &seed.scripts {
+        db.with_database(&default_db, |db| run_script(db, script))??;
+    }
+    Ok(true)
+}
+
+/// LOAD every dataset and tensor saved in `dir`
+fn load_parquet_dir(db: &mut TensorDb, dir: &Path) -> Result<(), EngineError> {
+    let fail = |e: String| {
+        EngineError::InvalidOp(format!("Failed to seed from '{}': {}", dir.display(), e))
+    };
+    if !dir.is_dir() {
+        return Err(fail("not a directory".to_string()));
+    }
+
+    let storage = ParquetStorage::new(dir.to_string_lossy().into_owned());
+    let datasets = storage.list_datasets().map_err(|e| fail(e.to_string()))?;
+    let tensors = storage.list_tensors().map_err(|e| fail(e.to_string()))?;
+    let commands = datasets
+        .iter()
+        .map(|name| format!("LOAD DATASET {} FROM \"{}\"", name, dir.display()))
+        .chain(
+            tensors
+                .iter()
+                .map(|name| format!("LOAD TENSOR {} FROM \"{}\"", name, dir.display())),
+        );
+    for command in commands {
+        execute_line(db, &command, 1).map_err(|e| fail(e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn run_script(db: &mut TensorDb, path: &Path) -> Result<(), EngineError> {
+    let fail = |e: String| {
+        EngineError::InvalidOp(format!("Seed script '{}' failed: {}", path.display(), e))
+    };
+    let script = std::fs::read_to_string(path).map_err(|e| fail(e.to_string()))?;
+    for (line_no, command) in split_statements(&script).map_err(|e| fail(e.to_string()))? {
+        execute_line(db, &command, line_no).map_err(|e| fail(e.to_string()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+//! Multi-statement transactions (`BEGIN` / `COMMIT` / `ROLLBACK`). While a
+//! transaction is open its database keeps an undo log: each dataset is copied
+//! the first time a mutating statement names it, so `ROLLBACK` can put back
+//! the copies, drop the datasets created since `BEGIN` and restore the tensor
+//! bindings. Statements bound for the WAL are held back until `COMMIT`.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::core::dataset_legacy::Dataset;
+
+use super::db::NameEntry;
+
+/// Undo log of the open transaction of a database
+#[derive(Debug)]
+pub struct Transaction {
+    /// Client session that began the transaction, the only one allowed to
+    /// write to it, commit it or roll it back
+    pub(crate) session: Option<String>,
+    /// Datasets that existed at `BEGIN`; any other one is dropped on rollback
+    pub(crate) existing: HashSet<String>,
+    /// Each dataset as it was before the transaction first wrote to it
+    pub(crate) originals: HashMap<String, Dataset>,
+    /// Tensor bindings at `BEGIN`
+    pub(crate) names: HashMap<String, NameEntry>,
+    /// Dataset variables at `BEGIN`
+    pub(crate) dataset_vars: HashMap<String, String>,
+    /// Mutating statements to append to the WAL on `COMMIT`
+    pub(crate) wal: Vec<String>,
+}
+
+impl Transaction {
+    pub(crate) fn begin(
+        session: Option<String>,
+        existing: HashSet<String>,
+        names: HashMap<String, NameEntry>,
+        dataset_vars: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            session,
+            existing,
+            originals: HashMap::new(),
+            names,
+            dataset_vars,
+            wal: Vec::new(),
+        }
+    }
+
+    /// Keep a copy of `dataset` unless one was taken already or the dataset
+    /// was created by this transaction
+    pub(crate) fn copy_on_write(&mut self, name: &str, dataset: &Dataset) {
+        if self.existing.contains(name) && !self.originals.contains_key(name) {
+            self.originals.insert(name.to_string(), dataset.clone());
+        }
+    }
+}
+
+/// Datasets a rollback changed, so their stored copies can follow
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RollbackReport {
+    /// Datasets put back as they were at `BEGIN`
+    pub restored: Vec<String>,
+    /// Datasets created by the transaction and dropped again
+    pub dropped: Vec<String>,
+}
@@ -20,7 +20,7 @@ pub use core::value;
 
 // Re-exports para tener una API limpia desde fuera del crate
 pub use dataset::{Dataset as TensorDataset, DatasetRegistry, DatasetSchema};
-pub use dataset_legacy::{ColumnStats, Dataset, DatasetId, DatasetMetadata};
+pub use dataset_legacy::{ColumnStats, Dataset, DatasetId, DatasetMetadata, Histogram, Sample};
 pub use dataset_store::{DatasetStore, DatasetStoreError};
 pub use dsl::{execute_line, execute_script, DslError};
 pub use engine::{BinaryOp, EngineError, TensorDb, TensorKind, UnaryOp};
@@ -1,12 +1,15 @@
 use clap::{Parser, Subcommand};
 use colored::*;
-use linal::dsl::{execute_line, DslOutput};
+use linal::dsl::handlers::copy::{CopyStatement, COPY_BATCH_ROWS};
+use linal::dsl::{execute_line, DslError, DslOutput};
+use linal::engine::seed::seed_empty_data_dir;
 use linal::engine::TensorDb;
 use linal::server::start_server;
+use linal::utils::parsing::unquoted_char_indices;
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 use std::fs;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, RwLock};
 use toon_format::encode_default;
 
 #[derive(Parser)]
@@ -46,12 +49,18 @@ enum Commands {
     },
     /// Initialize a new LINAL project structure
     Init,
-    /// Load a Parquet file directly into a dataset
+    /// Load a Parquet or CSV file directly into a dataset
     Load {
-        /// Path to the parquet file
+        /// Path to the parquet or .csv file
         file: String,
         /// Target dataset name
         dataset: String,
+        /// Field delimiter for CSV files (default ',')
+        #[arg(long)]
+        delimiter: Option<char>,
+        /// Treat the first CSV row as data instead of detecting a header
+        #[arg(long)]
+        no_header: bool,
     },
 }
 
@@ -85,7 +94,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
                 current_cmd.push_str(line);
 
-                for c in line.chars() {
+                for (_, c) in unquoted_char_indices(line) {
                     if c == '(' {
                         paren_balance += 1;
                     } else if c == ')' {
@@ -124,15 +133,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
         Some(Commands::Server { port }) | Some(Commands::Serve { port }) => {
-            // Need Arc<Mutex<TensorDb>>
-            let db_arc = Arc::new(Mutex::new(db));
+            match seed_empty_data_dir(&mut db) {
+                Ok(true) => println!("Seeded empty data directory from [seed]"),
+                Ok(false) => {}
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            // Need Arc<RwLock<TensorDb>>
+            let db_arc = Arc::new(RwLock::new(db));
             start_server(db_arc, port).await;
         }
         Some(Commands::Init) => {
             handle_init()?;
         }
-        Some(Commands::Load { file, dataset }) => {
-            handle_load(&mut db, &file, &dataset)?;
+        Some(Commands::Load {
+            file,
+            dataset,
+            delimiter,
+            no_header,
+        }) => {
+            handle_load(&mut db, &file, &dataset, delimiter, no_header)?;
         }
         Some(Commands::Repl { format }) => {
             run_repl(db, format == "toon")?;
@@ -157,6 +179,15 @@ fn handle_init() -> Result<(), Box<dyn std::error::Error>> {
         let default_config = r#"[storage]
 data_dir = "./data"
 default_db = "default"
+auto_persist = false
+wal = false
+backend = "file"
+
+[audit]
+persist = false
+
+[versioning]
+retention = 0
 "#;
         fs::write(config_path, default_config)?;
         println!("Created default configuration: {}", config_path.green());
@@ -178,8 +209,16 @@ fn handle_load(
     db: &mut TensorDb,
     file: &str,
     dataset: &str,
+    delimiter: Option<char>,
+    no_header: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let command = format!("LOAD DATASET {} FROM \"{}\"", dataset, file);
+    let mut command = format!("LOAD DATASET {} FROM \"{}\"", dataset, file);
+    if let Some(d) = delimiter {
+        command.push_str(&format!(" DELIMITER \"{}\"", d.escape_default()));
+    }
+    if no_header {
+        command.push_str(" NO HEADER");
+    }
     match execute_line(db, &command, 1) {
         Ok(output) => {
             println!("{}", output.to_string().green());
@@ -233,7 +272,7 @@ fn run_repl(mut db: TensorDb, use_toon: bool) -> Result<(), Box<dyn std::error::
                 }
                 current_cmd.push_str(trimmed);
 
-                for c in trimmed.chars() {
+                for (_, c) in unquoted_char_indices(trimmed) {
                     if c == '(' {
                         paren_balance += 1;
                     } else if c == ')' {
@@ -241,7 +280,16 @@ fn run_repl(mut db: TensorDb, use_toon: bool) -> Result<(), Box<dyn std::error::
                     }
                 }
 
-                if paren_balance == 0 {
+                if paren_balance == 0 && current_cmd.starts_with("COPY ") {
+                    match CopyStatement::parse(&current_cmd, 1) {
+                        Ok(statement) => match copy_from_stdin(&mut rl, &mut db, &statement) {
+                            Ok(rows) => println!("COPY: {} row(s) affected", rows),
+                            Err(e) => eprintln!("{}: {}", "Error".red(), e),
+                        },
+                        Err(e) => eprintln!("{}: {}", "Error".red(), e),
+                    }
+                    current_cmd.clear();
+                } else if paren_balance == 0 {
                     match execute_line(&mut db, &current_cmd, 1) {
                         Ok(output) => {
                             if !matches!(output, DslOutput::None) {
@@ -280,3 +328,46 @@ fn run_repl(mut db: TensorDb, use_toon: bool) -> Result<(), Box<dyn std::error::
     let _ = rl.save_history(history_path);
     Ok(())
 }
+
+/// Read rows for `COPY ... FROM STDIN` until a line with just `\.` (or EOF),
+/// ingesting them `COPY_BATCH_ROWS` at a time. Returns the rows ingested.
+fn copy_from_stdin(
+    rl: &mut DefaultEditor,
+    db: &mut TensorDb,
+    statement: &CopyStatement,
+) -> Result<usize, DslError> {
+    println!("Enter rows, one per line. End with \\. on a line by itself.");
+    let mut batch: Vec<String> = Vec::new();
+    let mut ingested = 0;
+
+    let flush = |db: &mut TensorDb, batch: &mut Vec<String>| -> Result<usize, DslError> {
+        let output = execute_line(db, &statement.command(batch.iter().map(String::as_str)), 1);
+        batch.clear();
+        match output? {
+            DslOutput::Affected { rows, .. } => Ok(rows),
+            _ => Ok(0),
+        }
+    };
+
+    loop {
+        match rl.readline("copy> ") {
+            Ok(line) if line.trim() == "\\." => break,
+            Ok(line) => {
+                batch.push(line);
+                if batch.len() == COPY_BATCH_ROWS {
+                    ingested += flush(db, &mut batch)?;
+                }
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(ReadlineError::Interrupted) => {
+                println!("Interrupted");
+                return Ok(ingested);
+            }
+            Err(err) => {
+                println!("Error: {:?}", err);
+                break;
+            }
+        }
+    }
+    Ok(ingested + flush(db, &mut batch)?)
+}
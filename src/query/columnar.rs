@@ -0,0 +1,505 @@
+//! Columnar batches and vectorized kernels. Large inputs are transposed into
+//! one typed vector per referenced column, so filters and global aggregates
+//! run as tight loops instead of evaluating an `Expr` against every `Tuple`.
+//! The kernels reproduce the row-at-a-time results exactly, NULLs included.
+//! Low-cardinality String columns are dictionary-encoded, so comparisons are
+//! evaluated once per distinct value and GROUP BY hashes integer codes.
+
+use crate::core::tuple::{Schema, Tuple};
+use crate::core::value::{Value, ValueType};
+use crate::query::logical::{AggregateFunction, Expr};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A String column is dictionary-encoded when it has at most one distinct
+/// value per this many rows; other String columns stay on the row path
+pub const MIN_ROWS_PER_DICTIONARY_ENTRY: usize = 2;
+
+/// One column of a batch. `valid[i]` is false where row `i` is NULL.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnChunk {
+    Int {
+        values: Vec<i64>,
+        valid: Vec<bool>,
+    },
+    Float {
+        values: Vec<f32>,
+        valid: Vec<bool>,
+    },
+    Bool {
+        values: Vec<bool>,
+        valid: Vec<bool>,
+    },
+    /// Strings as indices into `dictionary`, which holds each distinct value
+    /// once in order of first appearance and is shared by clones of the chunk
+    Dictionary {
+        codes: Vec<u32>,
+        dictionary: Arc<[String]>,
+        valid: Vec<bool>,
+    },
+}
+
+impl ColumnChunk {
+    /// Column `idx` of `rows`, or `None` when it holds a value of another type
+    pub fn from_rows(rows: &[Tuple], idx: usize, value_type: &ValueType) -> Option<Self> {
+        let valid: Vec<bool> = rows.iter().map(|r| !r.values[idx].is_null()).collect();
+        let chunk = match value_type {
+            ValueType::Int => ColumnChunk::Int {
+                values: column(rows, idx, |v| match v {
+                    Value::Int(i) => Some(*i),
+                    _ => None,
+                })?,
+                valid,
+            },
+            ValueType::Float => ColumnChunk::Float {
+                values: column(rows, idx, |v| match v {
+                    Value::Float(f) => Some(*f),
+                    _ => None,
+                })?,
+                valid,
+            },
+            ValueType::Bool => ColumnChunk::Bool {
+                values: column(rows, idx, |v| match v {
+                    Value::Bool(b) => Some(*b),
+                    _ => None,
+                })?,
+                valid,
+            },
+            ValueType::String => return Self::encode_strings(rows, idx),
+            _ => return None,
+        };
+        Some(chunk)
+    }
+
+    /// Dictionary encoding of String column `idx`, or `None` when it holds
+    /// another type or too many distinct values
+    fn encode_strings(rows: &[Tuple], idx: usize) -> Option<Self> {
+        let max_entries = rows.len() / MIN_ROWS_PER_DICTIONARY_ENTRY;
+        let mut lookup: HashMap<&str, u32> = HashMap::new();
+        let mut dictionary: Vec<String> = Vec::new();
+        let mut codes = Vec::with_capacity(rows.len());
+        let mut valid = Vec::with_capacity(rows.len());
+        for row in rows {
+            match &row.values[idx] {
+                Value::Null => {
+                    codes.push(0);
+                    valid.push(false);
+                }
+                Value::String(s) => {
+                    let next = dictionary.len() as u32;
+                    let code = *lookup.entry(s.as_str()).or_insert(next);
+                    if code == next {
+                        if dictionary.len() == max_entries {
+                            return None;
+                        }
+                        dictionary.push(s.clone());
+                    }
+                    codes.push(code);
+                    valid.push(true);
+                }
+                _ => return None,
+            }
+        }
+        Some(ColumnChunk::Dictionary {
+            codes,
+            dictionary: dictionary.into(),
+            valid,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.valid().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn valid(&self) -> &[bool] {
+        match self {
+            ColumnChunk::Int { valid, .. }
+            | ColumnChunk::Float { valid, .. }
+            | ColumnChunk::Bool { valid, .. }
+            | ColumnChunk::Dictionary { valid, .. } => valid,
+        }
+    }
+}
+
+/// Values of one column, with NULLs as the type's default
+fn column<T: Default>(
+    rows: &[Tuple],
+    idx: usize,
+    get: impl Fn(&Value) -> Option<T>,
+) -> Option<Vec<T>> {
+    rows.iter()
+        .map(|r| match &r.values[idx] {
+            Value::Null => Some(T::default()),
+            v => get(v),
+        })
+        .collect()
+}
+
+/// The referenced columns of a run of rows
+#[derive(Debug, Clone, Default)]
+pub struct ColumnBatch {
+    pub columns: HashMap<String, ColumnChunk>,
+    pub len: usize,
+}
+
+impl ColumnBatch {
+    /// Transpose the `names` columns of `rows`. `None` when a column is
+    /// missing or holds values the kernels cannot handle.
+    pub fn from_rows(rows: &[Tuple], schema: &Schema, names: &[String]) -> Option<Self> {
+        let mut columns = HashMap::with_capacity(names.len());
+        for name in names {
+            let idx = schema.get_field_index(name)?;
+            let chunk = ColumnChunk::from_rows(rows, idx, &schema.fields[idx].value_type)?;
+            columns.insert(name.clone(), chunk);
+        }
+        Some(Self {
+            columns,
+            len: rows.len(),
+        })
+    }
+
+    /// Whether a Float column holds NaN or Inf in a non-NULL row
+    pub fn has_non_finite(&self) -> bool {
+        self.columns.values().any(|chunk| match chunk {
+            ColumnChunk::Float { values, valid } => values
+                .iter()
+                .zip(valid)
+                .any(|(x, valid)| *valid && !x.is_finite()),
+            _ => false,
+        })
+    }
+}
+
+fn column_type<'a>(schema: &'a Schema, name: &str) -> Option<&'a ValueType> {
+    schema.get_field(name).map(|f| &f.value_type)
+}
+
+/// Whether `filter_mask` handles `predicate`: ANDs and ORs of comparisons
+/// between an Int, Float, Bool or String column and a literal, and LIKE on
+/// a String column
+pub fn can_vectorize_filter(predicate: &Expr, schema: &Schema) -> bool {
+    match predicate {
+        Expr::BinaryExpr { left, op, right } if op == "AND" || op == "OR" => {
+            can_vectorize_filter(left, schema) && can_vectorize_filter(right, schema)
+        }
+        Expr::BinaryExpr { .. } => {
+            comparison(predicate).is_some_and(|(col, op, _)| match column_type(schema, col) {
+                Some(ValueType::String) => true,
+                Some(ValueType::Int | ValueType::Float | ValueType::Bool) => !op.contains("LIKE"),
+                _ => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+/// `column op literal`, with a literal on the left flipped to the right
+fn comparison(expr: &Expr) -> Option<(&str, &str, &Value)> {
+    let Expr::BinaryExpr { left, op, right } = expr else {
+        return None;
+    };
+    let flipped = match op.as_str() {
+        "=" | "!=" => op.as_str(),
+        // The pattern must be on the right
+        "LIKE" | "NOT LIKE" => {
+            return match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(col), Expr::Literal(lit)) => Some((col, op, lit)),
+                _ => None,
+            }
+        }
+        ">" => "<",
+        "<" => ">",
+        ">=" => "<=",
+        "<=" => ">=",
+        _ => return None,
+    };
+    match (left.as_ref(), right.as_ref()) {
+        (Expr::Column(col), Expr::Literal(lit)) => Some((col, op, lit)),
+        (Expr::Literal(lit), Expr::Column(col)) => Some((col, flipped, lit)),
+        _ => None,
+    }
+}
+
+/// Same truth table as the row evaluator for an ordering of two non-NULL values
+fn matches_op(op: &str, ord: Option<Ordering>) -> bool {
+    match op {
+        "=" => ord == Some(Ordering::Equal),
+        "!=" => ord.is_some_and(|o| o != Ordering::Equal),
+        ">" => ord == Some(Ordering::Greater),
+        "<" => ord == Some(Ordering::Less),
+        ">=" => matches!(ord, Some(Ordering::Greater | Ordering::Equal)),
+        "<=" => matches!(ord, Some(Ordering::Less | Ordering::Equal)),
+        _ => false,
+    }
+}
+
+/// Rows of `batch` that satisfy `predicate`, or `None` if it is not vectorizable
+pub fn filter_mask(predicate: &Expr, batch: &ColumnBatch) -> Option<Vec<bool>> {
+    if let Expr::BinaryExpr { left, op, right } = predicate {
+        if op == "AND" || op == "OR" {
+            let mut mask = filter_mask(left, batch)?;
+            let other = filter_mask(right, batch)?;
+            for (m, o) in mask.iter_mut().zip(other) {
+                if op == "AND" {
+                    *m &= o;
+                } else {
+                    *m |= o;
+                }
+            }
+            return Some(mask);
+        }
+    }
+
+    let (col, op, lit) = comparison(predicate)?;
+    let chunk = batch.columns.get(col)?;
+    // A comparison with NULL is unknown, which a filter drops
+    let select = |valid: &[bool], hit: &dyn Fn(usize) -> bool| -> Vec<bool> {
+        (0..valid.len()).map(|i| valid[i] && hit(i)).collect()
+    };
+
+    let mask = match (chunk, lit) {
+        (ColumnChunk::Int { values, valid }, Value::Int(x)) => {
+            select(valid, &|i| matches_op(op, Some(values[i].cmp(x))))
+        }
+        (ColumnChunk::Int { values, valid }, Value::Float(x)) => select(valid, &|i| {
+            matches_op(op, (values[i] as f32).partial_cmp(x))
+        }),
+        (ColumnChunk::Float { values, valid }, Value::Float(x)) => {
+            select(valid, &|i| matches_op(op, values[i].partial_cmp(x)))
+        }
+        (ColumnChunk::Float { values, valid }, Value::Int(x)) => select(valid, &|i| {
+            matches_op(op, values[i].partial_cmp(&(*x as f32)))
+        }),
+        (ColumnChunk::Bool { values, valid }, Value::Bool(x)) => {
+            select(valid, &|i| matches_op(op, Some(values[i].cmp(x))))
+        }
+        // Each distinct string is compared once, then rows look up their code
+        (
+            ColumnChunk::Dictionary {
+                codes,
+                dictionary,
+                valid,
+            },
+            Value::String(x),
+        ) => {
+            let hits: Vec<bool> = dictionary
+                .iter()
+                .map(|s| match op {
+                    "LIKE" => crate::query::planner::like_match(s, x),
+                    "NOT LIKE" => !crate::query::planner::like_match(s, x),
+                    _ => matches_op(op, Some(s.as_str().cmp(x))),
+                })
+                .collect();
+            select(valid, &|i| hits[codes[i] as usize])
+        }
+        // Strings may still compare with other literals after coercion
+        (ColumnChunk::Dictionary { .. }, _) => return None,
+        // Incomparable types and NULL literals never match
+        (chunk, _) => vec![false; chunk.len()],
+    };
+    Some(mask)
+}
+
+/// Groups of `rows` by the values of `keys`: the key values of each group in
+/// order of first appearance, and the group of every row. `None` unless every
+/// key is a dictionary-encoded String column, whose codes are then hashed
+/// instead of the strings.
+pub fn dictionary_groups(keys: &[Expr], rows: &[Tuple]) -> Option<(Vec<Vec<Value>>, Vec<usize>)> {
+    let schema = &rows.first()?.schema;
+    let mut columns = Vec::with_capacity(keys.len());
+    for key in keys {
+        let Expr::Column(name) = key else {
+            return None;
+        };
+        let idx = schema.get_field_index(name)?;
+        if schema.fields[idx].value_type != ValueType::String {
+            return None;
+        }
+        match ColumnChunk::encode_strings(rows, idx)? {
+            ColumnChunk::Dictionary {
+                codes,
+                dictionary,
+                valid,
+            } => columns.push((codes, dictionary, valid)),
+            _ => return None,
+        }
+    }
+
+    // One mixed-radix number per row; the last digit of each key stands for NULL
+    let mut radix = Vec::with_capacity(columns.len());
+    let mut span: u64 = 1;
+    for (_, dictionary, _) in &columns {
+        radix.push(span);
+        span = span.checked_mul(dictionary.len() as u64 + 1)?;
+    }
+    let mut group_of_code: HashMap<u64, usize> = HashMap::new();
+    let mut group_keys = Vec::new();
+    let mut group_of_row = Vec::with_capacity(rows.len());
+    for row in 0..rows.len() {
+        let code: u64 = columns
+            .iter()
+            .zip(&radix)
+            .map(|((codes, dictionary, valid), radix)| {
+                let digit = if valid[row] {
+                    codes[row] as usize
+                } else {
+                    dictionary.len()
+                };
+                digit as u64 * radix
+            })
+            .sum();
+        let group = *group_of_code.entry(code).or_insert_with(|| {
+            group_keys.push(
+                columns
+                    .iter()
+                    .map(|(codes, dictionary, valid)| {
+                        if valid[row] {
+                            Value::String(dictionary[codes[row] as usize].clone())
+                        } else {
+                            Value::Null
+                        }
+                    })
+                    .collect(),
+            );
+            group_keys.len() - 1
+        });
+        group_of_row.push(group);
+    }
+    Some((group_keys, group_of_row))
+}
+
+/// Whether `aggregate_batch` handles `aggr_expr`: COUNT, SUM, MIN, MAX and
+/// AVG of Int or Float columns, and COUNT(*)
+pub fn can_vectorize_aggregate(aggr_expr: &[Expr], schema: &Schema) -> bool {
+    !aggr_expr.is_empty()
+        && aggr_expr.iter().all(|expr| match expr {
+            Expr::AggregateExpr {
+                func: AggregateFunction::Count,
+                expr,
+            } => match expr.as_ref() {
+                Expr::Literal(_) => true,
+                Expr::Column(name) => schema.get_field(name).is_some(),
+                _ => false,
+            },
+            Expr::AggregateExpr { func, expr } => {
+                matches!(
+                    func,
+                    AggregateFunction::Sum
+                        | AggregateFunction::Min
+                        | AggregateFunction::Max
+                        | AggregateFunction::Avg
+                ) && matches!(expr.as_ref(), Expr::Column(name) if schema
+                    .get_field(name)
+                    .is_some_and(|f| matches!(f.value_type, ValueType::Int | ValueType::Float)))
+            }
+            _ => false,
+        })
+}
+
+/// One output value per aggregate over the whole batch, or `None` if an
+/// aggregate is not vectorizable. `batch` must not be empty.
+pub fn aggregate_batch(aggr_expr: &[Expr], batch: &ColumnBatch) -> Option<Vec<Value>> {
+    aggr_expr
+        .iter()
+        .map(|expr| {
+            let Expr::AggregateExpr { func, expr } = expr else {
+                return None;
+            };
+            if *func == AggregateFunction::Count {
+                // COUNT counts every row, as the row-based aggregate does
+                return Some(Value::Int(batch.len as i64));
+            }
+            let Expr::Column(name) = expr.as_ref() else {
+                return None;
+            };
+            match batch.columns.get(name)? {
+                ColumnChunk::Int { values, valid } => int_aggregate(func, values, valid),
+                ColumnChunk::Float { values, valid } => float_aggregate(func, values, valid),
+                ColumnChunk::Bool { .. } | ColumnChunk::Dictionary { .. } => None,
+            }
+        })
+        .collect()
+}
+
+fn int_aggregate(func: &AggregateFunction, values: &[i64], valid: &[bool]) -> Option<Value> {
+    let present = || {
+        values
+            .iter()
+            .zip(valid)
+            .filter(|(_, v)| **v)
+            .map(|(x, _)| *x)
+    };
+    Some(match func {
+        AggregateFunction::Sum => {
+            let mut sum = Value::Int(0);
+            for x in present() {
+                sum = match sum {
+                    // Promote to Float rather than wrap on overflow
+                    Value::Int(s) => match s.checked_add(x) {
+                        Some(total) => Value::Int(total),
+                        None => Value::Float(s as f32 + x as f32),
+                    },
+                    Value::Float(s) => Value::Float(s + x as f32),
+                    other => other,
+                };
+            }
+            sum
+        }
+        AggregateFunction::Min => present().min().map_or(Value::Null, Value::Int),
+        AggregateFunction::Max => present().max().map_or(Value::Null, Value::Int),
+        AggregateFunction::Avg => {
+            // NULLs count towards the divisor, as in the row-based aggregate
+            let sum = present().fold(0.0f32, |s, x| s + x as f32);
+            Value::Float(sum / values.len() as f32)
+        }
+        _ => return None,
+    })
+}
+
+fn float_aggregate(func: &AggregateFunction, values: &[f32], valid: &[bool]) -> Option<Value> {
+    let present = || {
+        values
+            .iter()
+            .zip(valid)
+            .filter(|(_, v)| **v)
+            .map(|(x, _)| *x)
+    };
+    // Keep the first extreme value, and a leading NaN, like the row comparisons
+    let extreme = |wanted: Ordering| {
+        present()
+            .reduce(|acc, x| {
+                if x.partial_cmp(&acc) == Some(wanted) {
+                    x
+                } else {
+                    acc
+                }
+            })
+            .map_or(Value::Null, Value::Float)
+    };
+    Some(match func {
+        AggregateFunction::Sum => {
+            let mut any = false;
+            let sum = present().fold(0.0f32, |s, x| {
+                any = true;
+                s + x
+            });
+            if any {
+                Value::Float(sum)
+            } else {
+                Value::Int(0)
+            }
+        }
+        AggregateFunction::Min => extreme(Ordering::Less),
+        AggregateFunction::Max => extreme(Ordering::Greater),
+        AggregateFunction::Avg => {
+            let sum = present().fold(0.0f32, |s, x| s + x);
+            Value::Float(sum / values.len() as f32)
+        }
+        _ => return None,
+    })
+}
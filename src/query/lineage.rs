@@ -0,0 +1,222 @@
+//! Column-level lineage: for every output column of a query, the columns of
+//! stored datasets its values come from and the expressions applied on the
+//! way. Datasets created from a query keep the lineage of their columns, so
+//! provenance carries through chains of derived datasets.
+
+use crate::engine::TensorDb;
+use crate::query::logical::{qualified_name, Expr, LogicalPlan, RERANK_SCORE_COLUMN};
+use serde::{Deserialize, Serialize};
+
+/// A column of a stored dataset
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SourceColumn {
+    pub dataset: String,
+    pub column: String,
+}
+
+impl std::fmt::Display for SourceColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.dataset, self.column)
+    }
+}
+
+/// Provenance of one output column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnLineage {
+    pub column: String,
+    /// Source columns the values are computed from; empty for constants
+    pub sources: Vec<SourceColumn>,
+    /// Expressions applied to the sources, innermost first; empty when the
+    /// column is a copy of its source
+    pub transforms: Vec<String>,
+}
+
+impl ColumnLineage {
+    fn source(column: &str, dataset: &str, source_column: &str) -> Self {
+        Self {
+            column: column.to_string(),
+            sources: vec![SourceColumn {
+                dataset: dataset.to_string(),
+                column: source_column.to_string(),
+            }],
+            transforms: Vec::new(),
+        }
+    }
+
+    fn renamed(&self, column: String) -> Self {
+        Self {
+            column,
+            ..self.clone()
+        }
+    }
+}
+
+/// Lineage of the output columns of `plan`, in output order
+pub fn plan_lineage(plan: &LogicalPlan, db: &TensorDb) -> Vec<ColumnLineage> {
+    match plan {
+        LogicalPlan::Scan {
+            dataset_name,
+            schema,
+        } => {
+            let dataset = db.get_dataset(dataset_name).ok();
+            let stored = |column: &str| {
+                dataset?
+                    .metadata
+                    .lineage
+                    .iter()
+                    .find(|lineage| lineage.column == column)
+            };
+            let mut lineage: Vec<ColumnLineage> = schema
+                .fields
+                .iter()
+                .map(|field| match stored(&field.name) {
+                    // A derived dataset answers for the sources it was built from
+                    Some(stored) => stored.clone(),
+                    None => ColumnLineage::source(&field.name, dataset_name, &field.name),
+                })
+                .collect();
+            // Computed columns read the dataset's other columns
+            if let Some(dataset) = dataset {
+                for (column, expr) in &dataset.lazy_expressions {
+                    let derived = derive(column.clone(), expr, &lineage);
+                    if let Some(slot) = lineage.iter_mut().find(|l| &l.column == column) {
+                        *slot = derived;
+                    }
+                }
+            }
+            lineage
+        }
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Sample { input, .. }
+        | LogicalPlan::Distinct { input }
+        | LogicalPlan::VectorSearch { input, .. }
+        | LogicalPlan::SemiJoin { input, .. } => plan_lineage(input, db),
+        LogicalPlan::Project { input, exprs } => {
+            let input = plan_lineage(input, db);
+            exprs
+                .iter()
+                .map(|expr| derive(expr.output_name(), expr, &input))
+                .collect()
+        }
+        LogicalPlan::Aggregate {
+            input,
+            group_expr,
+            aggr_expr,
+            ..
+        } => {
+            let input = plan_lineage(input, db);
+            let groups = group_expr
+                .iter()
+                .map(|expr| derive(expr.output_name(), expr, &input));
+            let aggregates = aggr_expr.iter().filter_map(|expr| match expr {
+                Expr::AggregateExpr { func, expr: inner } => {
+                    Some(derive(func.column_name(inner), expr, &input))
+                }
+                _ => None,
+            });
+            groups.chain(aggregates).collect()
+        }
+        LogicalPlan::Window { input, window_expr } => {
+            let mut lineage = plan_lineage(input, db);
+            let computed: Vec<_> = window_expr
+                .iter()
+                .map(|expr| derive(expr.output_name(), expr, &lineage))
+                .collect();
+            lineage.extend(computed);
+            lineage
+        }
+        LogicalPlan::Unnest { input, expr } => {
+            let mut lineage = plan_lineage(input, db);
+            let element = derive(expr.output_name(), expr, &lineage);
+            lineage.push(element);
+            lineage
+        }
+        LogicalPlan::Rerank {
+            input,
+            service,
+            column,
+            ..
+        } => {
+            let mut lineage = plan_lineage(input, db);
+            let mut score = derive(
+                RERANK_SCORE_COLUMN.to_string(),
+                &Expr::Column(column.clone()),
+                &lineage,
+            );
+            score
+                .transforms
+                .push(format!("RERANK({}) USING {}", column, service));
+            lineage.push(score);
+            lineage
+        }
+        LogicalPlan::Join {
+            left,
+            right,
+            left_alias,
+            right_alias,
+            ..
+        } => {
+            let qualify = |alias: &str, lineage: Vec<ColumnLineage>| -> Vec<ColumnLineage> {
+                lineage
+                    .into_iter()
+                    .map(|l| {
+                        let column = qualified_name(alias, &l.column);
+                        l.renamed(column)
+                    })
+                    .collect()
+            };
+            let mut lineage = qualify(left_alias, plan_lineage(left, db));
+            lineage.extend(qualify(right_alias, plan_lineage(right, db)));
+            lineage
+        }
+    }
+}
+
+/// Lineage of the column `column` computed by `expr` over columns with `input` lineage
+fn derive(column: String, expr: &Expr, input: &[ColumnLineage]) -> ColumnLineage {
+    // A plain column reference passes its lineage on under its new name
+    if let Expr::Column(name) = expr.unaliased() {
+        if let Some(lineage) = find(input, name) {
+            return lineage.renamed(column);
+        }
+    }
+
+    let mut lineage = ColumnLineage {
+        column,
+        sources: Vec::new(),
+        transforms: Vec::new(),
+    };
+    for name in expr.columns() {
+        let Some(read) = find(input, &name) else {
+            continue;
+        };
+        for source in &read.sources {
+            if !lineage.sources.contains(source) {
+                lineage.sources.push(source.clone());
+            }
+        }
+        for transform in &read.transforms {
+            if !lineage.transforms.contains(transform) {
+                lineage.transforms.push(transform.clone());
+            }
+        }
+    }
+    lineage.transforms.push(expr.unaliased().output_name());
+    lineage
+}
+
+/// The input column `name` refers to: an exact match, else the only joined
+/// column `alias.name`
+fn find<'a>(input: &'a [ColumnLineage], name: &str) -> Option<&'a ColumnLineage> {
+    input.iter().find(|l| l.column == name).or_else(|| {
+        let mut qualified = input.iter().filter(|l| {
+            l.column
+                .rsplit_once('.')
+                .is_some_and(|(_, column)| column == name)
+        });
+        let first = qualified.next()?;
+        qualified.next().is_none().then_some(first)
+    })
+}
@@ -1,3 +1,4 @@
+use crate::core::dataset_legacy::Sample;
 use crate::core::tensor::Tensor;
 use crate::core::tuple::Schema;
 use crate::core::value::Value;
@@ -21,6 +22,192 @@ pub enum Expr {
         func: AggregateFunction,
         expr: Box<Expr>,
     },
+    /// Scalar function evaluated per row (e.g. WIDTH_BUCKET(score, 0, 100, 10))
+    ScalarFunction {
+        func: ScalarFunction,
+        args: Vec<Expr>,
+    },
+    /// Window function evaluated over the ordered rows of each partition
+    /// (e.g. ROW_NUMBER() OVER (PARTITION BY cat ORDER BY ts))
+    WindowFunction {
+        func: WindowFunction,
+        args: Vec<Expr>,
+        partition_by: Vec<String>,
+        order_by: String,
+        ascending: bool,
+    },
+    /// `expr AS name` in a SELECT list
+    Alias { expr: Box<Expr>, name: String },
+}
+
+impl Expr {
+    /// Name used for the output column produced by this expression
+    pub fn output_name(&self) -> String {
+        match self {
+            Expr::Column(name) => name.clone(),
+            Expr::Literal(val) => val.to_string(),
+            Expr::BinaryExpr { left, op, right } => {
+                format!("{} {} {}", left.output_name(), op, right.output_name())
+            }
+            Expr::AggregateExpr { func, expr } => func.output_name(&expr.output_name()),
+            Expr::ScalarFunction {
+                func: ScalarFunction::Case,
+                args,
+            } => {
+                let branches = args.chunks_exact(2);
+                let otherwise = branches.remainder();
+                let mut name = "CASE".to_string();
+                for pair in branches {
+                    name += &format!(
+                        " WHEN {} THEN {}",
+                        pair[0].output_name(),
+                        pair[1].output_name()
+                    );
+                }
+                if let [e] = otherwise {
+                    if !matches!(e, Expr::Literal(Value::Null)) {
+                        name += &format!(" ELSE {}", e.output_name());
+                    }
+                }
+                name + " END"
+            }
+            Expr::ScalarFunction {
+                func: ScalarFunction::Cast(target),
+                args,
+            } => format!("CAST({} AS {})", join_output_names(args), target),
+            Expr::ScalarFunction {
+                func: func @ (ScalarFunction::JsonGet | ScalarFunction::JsonGetText),
+                args,
+            } if args.len() == 2 => {
+                let arrow = if *func == ScalarFunction::JsonGet {
+                    "->"
+                } else {
+                    "->>"
+                };
+                match &args[1] {
+                    Expr::Literal(Value::String(key)) => {
+                        format!("{}{}'{}'", args[0].output_name(), arrow, key)
+                    }
+                    key => format!("{}{}{}", args[0].output_name(), arrow, key.output_name()),
+                }
+            }
+            Expr::ScalarFunction {
+                func: ScalarFunction::ElementAt,
+                args,
+            } if args.len() == 2 => {
+                format!("{}[{}]", args[0].output_name(), args[1].output_name())
+            }
+            Expr::ScalarFunction { func, args } => {
+                format!("{}({})", func.name(), join_output_names(args))
+            }
+            Expr::WindowFunction { func, args, .. } => match func {
+                WindowFunction::Aggregate(AggregateFunction::Count, _) if args.is_empty() => {
+                    "COUNT(*)".to_string()
+                }
+                _ => format!("{}({})", func.name(), join_output_names(args)),
+            },
+            Expr::Alias { name, .. } => name.clone(),
+        }
+    }
+
+    /// The expression without its `AS` name
+    pub fn unaliased(&self) -> &Expr {
+        match self {
+            Expr::Alias { expr, .. } => expr,
+            other => other,
+        }
+    }
+
+    /// Names of the columns this expression reads, in first-use order
+    pub fn columns(&self) -> Vec<String> {
+        fn walk(expr: &Expr, out: &mut Vec<String>) {
+            let mut add = |name: &String| {
+                if !out.contains(name) {
+                    out.push(name.clone());
+                }
+            };
+            match expr {
+                Expr::Column(name) => add(name),
+                Expr::Literal(_) => {}
+                Expr::BinaryExpr { left, right, .. } => {
+                    walk(left, out);
+                    walk(right, out);
+                }
+                Expr::AggregateExpr { expr, .. } | Expr::Alias { expr, .. } => walk(expr, out),
+                Expr::ScalarFunction { args, .. } => args.iter().for_each(|a| walk(a, out)),
+                Expr::WindowFunction {
+                    args,
+                    partition_by,
+                    order_by,
+                    ..
+                } => {
+                    partition_by.iter().for_each(&mut add);
+                    if !order_by.is_empty() {
+                        add(order_by);
+                    }
+                    args.iter().for_each(|a| walk(a, out));
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(self, &mut out);
+        out
+    }
+}
+
+impl Expr {
+    /// Logical negation of a predicate, pushed down to the comparisons:
+    /// De Morgan for AND/OR and the inverse operator otherwise
+    pub fn negate(self) -> Expr {
+        let inverse = match &self {
+            Expr::BinaryExpr { op, .. } => match op.as_str() {
+                "AND" => Some("OR"),
+                "OR" => Some("AND"),
+                "=" => Some("!="),
+                "!=" => Some("="),
+                "<" => Some(">="),
+                ">=" => Some("<"),
+                ">" => Some("<="),
+                "<=" => Some(">"),
+                "LIKE" => Some("NOT LIKE"),
+                "NOT LIKE" => Some("LIKE"),
+                "IS" => Some("IS NOT"),
+                "IS NOT" => Some("IS"),
+                // Placeholder for `IN (SELECT ...)` while parsing
+                "IN SUBQUERY" => Some("NOT IN SUBQUERY"),
+                "NOT IN SUBQUERY" => Some("IN SUBQUERY"),
+                _ => None,
+            },
+            _ => None,
+        };
+        match (self, inverse) {
+            (Expr::BinaryExpr { left, op, right }, Some(inverse)) => {
+                let (left, right) = if op == "AND" || op == "OR" {
+                    (Box::new(left.negate()), Box::new(right.negate()))
+                } else {
+                    (left, right)
+                };
+                Expr::BinaryExpr {
+                    left,
+                    op: inverse.to_string(),
+                    right,
+                }
+            }
+            // Boolean functions such as REGEXP_MATCH
+            (other, _) => Expr::BinaryExpr {
+                left: Box::new(other),
+                op: "=".to_string(),
+                right: Box::new(Expr::Literal(Value::Bool(false))),
+            },
+        }
+    }
+}
+
+fn join_output_names(args: &[Expr]) -> String {
+    args.iter()
+        .map(|a| a.output_name())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -30,6 +217,299 @@ pub enum AggregateFunction {
     Count,
     Min,
     Max,
+    /// Sample variance (n - 1 denominator), NULL for fewer than two values
+    Variance,
+    /// Sample standard deviation
+    Stddev,
+    Median,
+    /// PERCENTILE(expr, p): continuous percentile, interpolating between values
+    Percentile(f32),
+    /// COUNT(DISTINCT expr)
+    CountDistinct,
+    /// APPROX_COUNT_DISTINCT(expr): HyperLogLog estimate of COUNT(DISTINCT)
+    ApproxCountDistinct,
+    /// APPROX_QUANTILE(expr, q): t-digest estimate of PERCENTILE
+    ApproxQuantile(f32),
+}
+
+impl AggregateFunction {
+    /// Functions called as `NAME(expr)`; PERCENTILE, APPROX_QUANTILE and
+    /// COUNT(DISTINCT) need more syntax
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "SUM" => Some(AggregateFunction::Sum),
+            "AVG" => Some(AggregateFunction::Avg),
+            "COUNT" => Some(AggregateFunction::Count),
+            "MIN" => Some(AggregateFunction::Min),
+            "MAX" => Some(AggregateFunction::Max),
+            "VARIANCE" | "VAR_SAMP" => Some(AggregateFunction::Variance),
+            "STDDEV" | "STDDEV_SAMP" => Some(AggregateFunction::Stddev),
+            "MEDIAN" => Some(AggregateFunction::Median),
+            "APPROX_COUNT_DISTINCT" => Some(AggregateFunction::ApproxCountDistinct),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateFunction::Sum => "SUM",
+            AggregateFunction::Avg => "AVG",
+            AggregateFunction::Count | AggregateFunction::CountDistinct => "COUNT",
+            AggregateFunction::Min => "MIN",
+            AggregateFunction::Max => "MAX",
+            AggregateFunction::Variance => "VARIANCE",
+            AggregateFunction::Stddev => "STDDEV",
+            AggregateFunction::Median => "MEDIAN",
+            AggregateFunction::Percentile(_) => "PERCENTILE",
+            AggregateFunction::ApproxCountDistinct => "APPROX_COUNT_DISTINCT",
+            AggregateFunction::ApproxQuantile(_) => "APPROX_QUANTILE",
+        }
+    }
+
+    /// Column name of this aggregate over an argument named `arg`
+    pub fn output_name(&self, arg: &str) -> String {
+        match self {
+            AggregateFunction::CountDistinct => format!("COUNT(DISTINCT {})", arg),
+            AggregateFunction::Percentile(p) => format!("PERCENTILE({}, {})", arg, p),
+            AggregateFunction::ApproxQuantile(q) => format!("APPROX_QUANTILE({}, {})", arg, q),
+            _ => format!("{}({})", self.name(), arg),
+        }
+    }
+
+    /// Name of this aggregate's column in an Aggregate node's output
+    pub fn column_name(&self, arg: &Expr) -> String {
+        match arg {
+            Expr::Column(name) => self.output_name(name),
+            _ => self.output_name("val"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScalarFunction {
+    /// WIDTH_BUCKET(expr, min, max, count) -> bucket number in 0..=count+1
+    WidthBucket,
+    /// REGEXP_MATCH(expr, pattern) -> true if the pattern matches anywhere in the string
+    RegexpMatch,
+    /// REGEXP_EXTRACT(expr, pattern, group) -> captured group, NULL when there is no match
+    RegexpExtract,
+    /// WHERE(cond, a, b) -> `a` where the condition holds, `b` otherwise (NULL included)
+    Where,
+    /// CUMSUM(vector) -> running sum of the vector's elements
+    CumSum,
+    /// CUMPROD(vector) -> running product of the vector's elements
+    CumProd,
+    /// DIFF(vector) -> first differences, one element shorter
+    Diff,
+    /// ROLLING_MEAN(vector, window) -> mean of each full window of elements
+    RollingMean,
+    /// COSINE_SIM(a, b) -> cosine similarity of two vectors of the same length
+    CosineSim,
+    /// DOT(a, b) -> dot product of two vectors of the same length
+    Dot,
+    /// L2_DIST(a, b) -> Euclidean distance between two vectors of the same length
+    L2Dist,
+    /// NORM(vector) -> L2 norm
+    Norm,
+    /// UPPER(string) -> the string in upper case
+    Upper,
+    /// LOWER(string) -> the string in lower case
+    Lower,
+    /// LENGTH(string) -> number of characters (elements for a vector)
+    Length,
+    /// SUBSTR(string, start [, length]) -> characters from the 1-based `start`
+    Substr,
+    /// CONCAT(a, b, ...) -> the arguments as text, NULLs skipped
+    Concat,
+    /// ABS(number) -> absolute value
+    Abs,
+    /// ROUND(number [, digits]) -> rounded half away from zero
+    Round,
+    /// FLOOR(number) -> largest whole number not above it
+    Floor,
+    /// SQRT(number) -> square root, NULL for negative numbers
+    Sqrt,
+    /// LOG(number) -> natural logarithm, NULL unless positive
+    Log,
+    /// EXP(number) -> e raised to the number
+    Exp,
+    /// NOW() -> the current time as a timestamp
+    Now,
+    /// DATE_TRUNC(unit, timestamp) -> the timestamp truncated to the unit
+    DateTrunc,
+    /// COALESCE(a, b, ...) -> the first argument that is not NULL
+    /// (IFNULL(a, b) is the two-argument form)
+    Coalesce,
+    /// CASE WHEN c1 THEN v1 [WHEN c2 THEN v2 ...] ELSE e END -> the value of
+    /// the first condition that holds, else `e`; args are `[c1, v1, c2, v2, ..., e]`
+    Case,
+    /// CAST(expr AS type) -> explicit conversion (`Value::cast_to`), NULL
+    /// when the value does not convert
+    Cast(crate::core::value::ValueType),
+    /// JSON_GET(json, key) or `json->key` -> the field (String key) or array
+    /// element (Int key) as JSON, NULL when absent
+    JsonGet,
+    /// JSON_GET_TEXT(json, key) or `json->>key` -> the same as text: a JSON
+    /// string's contents, anything else its JSON form
+    JsonGetText,
+    /// ELEMENT_AT(list, index) or `list[index]` -> the element at a 1-based
+    /// index, NULL when out of range
+    ElementAt,
+    /// ARRAY_CONTAINS(list, value) -> true if any element equals the value
+    ArrayContains,
+    /// UNNEST(list) -> one row per element; only valid as a top-level SELECT item
+    Unnest,
+    /// HASH(expr [, salt]) -> hex SHA-256 of the salt followed by the value's text
+    Hash,
+    /// MASK_EMAIL(string) -> the first character of the local part and the
+    /// domain, e.g. `a***@example.com`
+    MaskEmail,
+    /// TRUNCATE_IP(string) -> the address with its host bits zeroed (/24 for
+    /// IPv4, /48 for IPv6), NULL when it does not parse
+    TruncateIp,
+}
+
+impl ScalarFunction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScalarFunction::WidthBucket => "WIDTH_BUCKET",
+            ScalarFunction::RegexpMatch => "REGEXP_MATCH",
+            ScalarFunction::RegexpExtract => "REGEXP_EXTRACT",
+            ScalarFunction::Where => "WHERE",
+            ScalarFunction::CumSum => "CUMSUM",
+            ScalarFunction::CumProd => "CUMPROD",
+            ScalarFunction::Diff => "DIFF",
+            ScalarFunction::RollingMean => "ROLLING_MEAN",
+            ScalarFunction::CosineSim => "COSINE_SIM",
+            ScalarFunction::Dot => "DOT",
+            ScalarFunction::L2Dist => "L2_DIST",
+            ScalarFunction::Norm => "NORM",
+            ScalarFunction::Upper => "UPPER",
+            ScalarFunction::Lower => "LOWER",
+            ScalarFunction::Length => "LENGTH",
+            ScalarFunction::Substr => "SUBSTR",
+            ScalarFunction::Concat => "CONCAT",
+            ScalarFunction::Abs => "ABS",
+            ScalarFunction::Round => "ROUND",
+            ScalarFunction::Floor => "FLOOR",
+            ScalarFunction::Sqrt => "SQRT",
+            ScalarFunction::Log => "LOG",
+            ScalarFunction::Exp => "EXP",
+            ScalarFunction::Now => "NOW",
+            ScalarFunction::DateTrunc => "DATE_TRUNC",
+            ScalarFunction::Coalesce => "COALESCE",
+            ScalarFunction::Case => "CASE",
+            ScalarFunction::Cast(_) => "CAST",
+            ScalarFunction::JsonGet => "JSON_GET",
+            ScalarFunction::JsonGetText => "JSON_GET_TEXT",
+            ScalarFunction::ElementAt => "ELEMENT_AT",
+            ScalarFunction::ArrayContains => "ARRAY_CONTAINS",
+            ScalarFunction::Unnest => "UNNEST",
+            ScalarFunction::Hash => "HASH",
+            ScalarFunction::MaskEmail => "MASK_EMAIL",
+            ScalarFunction::TruncateIp => "TRUNCATE_IP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowFunction {
+    /// NTILE(n): splits the ordered rows into n buckets numbered 1..=n
+    Ntile,
+    /// PERCENT_RANK(): (rank - 1) / (rows - 1), in [0, 1]
+    PercentRank,
+    /// ROW_NUMBER(): 1, 2, 3, ... in window order; ties are numbered in input order
+    RowNumber,
+    /// RANK(): ties share a rank and leave a gap after them (1, 1, 3)
+    Rank,
+    /// DENSE_RANK(): ties share a rank without gaps (1, 1, 2)
+    DenseRank,
+    /// LAG(expr [, offset [, default]]): value `offset` rows earlier in the partition
+    Lag,
+    /// LEAD(expr [, offset [, default]]): value `offset` rows later in the partition
+    Lead,
+    /// SUM/AVG/COUNT/MIN/MAX over a frame of rows around the current one
+    Aggregate(AggregateFunction, WindowFrame),
+}
+
+impl WindowFunction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindowFunction::Ntile => "NTILE",
+            WindowFunction::PercentRank => "PERCENT_RANK",
+            WindowFunction::RowNumber => "ROW_NUMBER",
+            WindowFunction::Rank => "RANK",
+            WindowFunction::DenseRank => "DENSE_RANK",
+            WindowFunction::Lag => "LAG",
+            WindowFunction::Lead => "LEAD",
+            WindowFunction::Aggregate(func, _) => func.name(),
+        }
+    }
+}
+
+/// Rows a window aggregate covers, relative to the current row in window order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowFrame {
+    /// No ROWS clause: partition start through the current row and its
+    /// ORDER BY ties (a running aggregate)
+    Running,
+    /// `ROWS BETWEEN <preceding> AND <following>`; `None` is UNBOUNDED and
+    /// `Some(0)` CURRENT ROW
+    Rows {
+        preceding: Option<usize>,
+        following: Option<usize>,
+    },
+}
+
+/// Column appended by a Rerank node
+pub const RERANK_SCORE_COLUMN: &str = "rerank_score";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinType {
+    /// Only rows with a match on both sides
+    Inner,
+    /// Every left row; right columns are NULL when nothing matches
+    Left,
+}
+
+/// Condition of a similarity join: `SIMILARITY(l, r) > min`, or `>=`
+/// when `inclusive`, on the cosine similarity of two vector columns
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimilarityThreshold {
+    pub min: f32,
+    pub inclusive: bool,
+}
+
+impl SimilarityThreshold {
+    pub fn accepts(&self, score: f32) -> bool {
+        score > self.min || (self.inclusive && score == self.min)
+    }
+}
+
+impl std::fmt::Display for SimilarityThreshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = if self.inclusive { ">=" } else { ">" };
+        write!(f, "{} {}", op, self.min)
+    }
+}
+
+impl JoinType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JoinType::Inner => "INNER",
+            JoinType::Left => "LEFT",
+        }
+    }
+}
+
+/// Output name of `column` from the join input aliased `alias`. Columns
+/// that are already qualified (from a nested join) keep their name.
+pub fn qualified_name(alias: &str, column: &str) -> String {
+    if column.contains('.') {
+        column.to_string()
+    } else {
+        format!("{}.{}", alias, column)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -44,10 +524,11 @@ pub enum LogicalPlan {
         input: Box<LogicalPlan>,
         predicate: Expr,
     },
-    /// Projection (Select columns)
+    /// Projection: one output column per expression, named by `output_name`
+    /// (plain columns are passed through, anything else is evaluated per row)
     Project {
         input: Box<LogicalPlan>,
-        columns: Vec<String>,
+        exprs: Vec<Expr>,
     },
     /// Vector Search (K-NN)
     VectorSearch {
@@ -64,56 +545,108 @@ pub enum LogicalPlan {
     },
     /// Limit rows
     Limit { input: Box<LogicalPlan>, n: usize },
+    /// Random subset of rows
+    Sample {
+        input: Box<LogicalPlan>,
+        sample: Sample,
+    },
+    /// Remove duplicate rows, keeping the first occurrence of each
+    Distinct { input: Box<LogicalPlan> },
     /// Aggregate rows
     Aggregate {
         input: Box<LogicalPlan>,
         group_expr: Vec<Expr>,
         aggr_expr: Vec<Expr>,
+        /// Also aggregate each prefix of `group_expr` (subtotals, then a
+        /// grand total), with the keys left out set to NULL
+        rollup: bool,
+    },
+    /// Append computed columns (window and scalar functions) to every input row
+    Window {
+        input: Box<LogicalPlan>,
+        window_expr: Vec<Expr>,
+    },
+    /// Reorder rows by scores from an external rerank service
+    Rerank {
+        input: Box<LogicalPlan>,
+        service: String,
+        column: String,
+        query: String,
+    },
+    /// Rows of `input` whose `expr` is (or, for an anti-join, is not) among
+    /// the values of the single column of `subquery`: `expr [NOT] IN (SELECT ...)`
+    SemiJoin {
+        input: Box<LogicalPlan>,
+        subquery: Box<LogicalPlan>,
+        expr: Expr,
+        anti: bool,
+    },
+    /// Equi-join (or similarity join) of two inputs. Output columns are the
+    /// left then the right columns, named `alias.column`; the keys use those
+    /// output names.
+    Join {
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+        left_alias: String,
+        right_alias: String,
+        left_key: String,
+        right_key: String,
+        join_type: JoinType,
+        /// ASOF JOIN on `(left time, right time)` output names: each left
+        /// row meets only the latest right row of its key at or before it
+        asof: Option<(String, String)>,
+        /// Similarity join: the keys are vector columns, and each left row
+        /// meets every right row whose cosine similarity passes the threshold
+        similarity: Option<SimilarityThreshold>,
     },
+    /// One row per element of the list `expr` (an `UNNEST(...)` call),
+    /// with the element appended as a column named by `output_name`;
+    /// rows whose list is NULL or empty are dropped
+    Unnest { input: Box<LogicalPlan>, expr: Expr },
 }
 
 impl LogicalPlan {
     pub fn schema(&self) -> Arc<Schema> {
         match self {
             LogicalPlan::Scan { schema, .. } => schema.clone(),
-            LogicalPlan::Filter { input, .. } => input.schema(),
-            LogicalPlan::Project { input, columns } => {
+            LogicalPlan::Filter { input, .. } | LogicalPlan::SemiJoin { input, .. } => {
+                input.schema()
+            }
+            LogicalPlan::Project { input, exprs } => {
                 let input_schema = input.schema();
                 // Construct new schema from selected columns
                 // This is a simplification; normally we'd validate here or during construction
-                let fields = columns
+                let fields = exprs
                     .iter()
-                    .filter_map(|name| input_schema.get_field(name).cloned())
+                    .filter_map(|expr| projected_field(expr, &input_schema))
                     .collect();
                 Arc::new(Schema::new(fields))
             }
             LogicalPlan::VectorSearch { input, .. } => input.schema(),
             LogicalPlan::Sort { input, .. } => input.schema(),
             LogicalPlan::Limit { input, .. } => input.schema(),
+            LogicalPlan::Sample { input, .. } => input.schema(),
+            LogicalPlan::Distinct { input } => input.schema(),
             LogicalPlan::Aggregate {
                 input,
                 group_expr,
                 aggr_expr,
+                rollup,
             } => {
                 // Schema consists of Group keys + Aggregation results
                 let mut fields = Vec::new();
-                // 1. Group keys
+                // 1. Group keys, named by their expression text
                 let input_schema = input.schema();
                 for expr in group_expr {
-                    if let Expr::Column(name) = expr {
-                        let typ = infer_expr_type_full(expr, &input_schema);
-                        fields.push(crate::core::tuple::Field::new(name, typ));
-                    }
+                    let typ = infer_expr_type_full(expr, &input_schema);
+                    let field = crate::core::tuple::Field::new(expr.output_name(), typ);
+                    // Subtotal rows leave keys NULL
+                    fields.push(if *rollup { field.nullable() } else { field });
                 }
                 // 2. Aggregates
                 for expr in aggr_expr {
                     if let Expr::AggregateExpr { func, expr: inner } = expr {
-                        let col_name = match inner.as_ref() {
-                            Expr::Column(n) => n.clone(),
-                            _ => "val".to_string(),
-                        };
-                        let name =
-                            format!("{}({})", format!("{:?}", func).to_uppercase(), col_name);
+                        let name = func.column_name(inner);
                         let mut typ = crate::core::value::ValueType::Int; // Default
 
                         // Infer for SUM/MIN/MAX if inner is likely Vector (not perfect, but MVP)
@@ -128,21 +661,131 @@ impl LogicalPlan {
                                 let input_schema = input.schema();
                                 typ = infer_expr_type_full(inner.as_ref(), &input_schema);
                             }
-                            super::logical::AggregateFunction::Avg => {
+                            super::logical::AggregateFunction::Avg
+                            | super::logical::AggregateFunction::Variance
+                            | super::logical::AggregateFunction::Stddev
+                            | super::logical::AggregateFunction::Median
+                            | super::logical::AggregateFunction::Percentile(_)
+                            | super::logical::AggregateFunction::ApproxQuantile(_) => {
                                 typ = crate::core::value::ValueType::Float;
                             }
                             _ => {}
                         }
 
-                        fields.push(crate::core::tuple::Field::new(&name, typ));
+                        let field = crate::core::tuple::Field::new(&name, typ);
+                        // NULL for too few values
+                        fields.push(match func {
+                            AggregateFunction::Variance
+                            | AggregateFunction::Stddev
+                            | AggregateFunction::Median
+                            | AggregateFunction::Percentile(_)
+                            | AggregateFunction::ApproxQuantile(_) => field.nullable(),
+                            _ => field,
+                        });
                     }
                 }
                 Arc::new(Schema::new(fields))
             }
+            LogicalPlan::Rerank { input, .. } => {
+                let mut fields = input.schema().fields.clone();
+                fields.push(crate::core::tuple::Field::new(
+                    RERANK_SCORE_COLUMN,
+                    crate::core::value::ValueType::Float,
+                ));
+                Arc::new(Schema::new(fields))
+            }
+            LogicalPlan::Window { input, window_expr } => {
+                let input_schema = input.schema();
+                let mut fields = input_schema.fields.clone();
+                for expr in window_expr {
+                    let typ = infer_expr_type_full(expr, &input_schema);
+                    fields.push(crate::core::tuple::Field::new(expr.output_name(), typ).nullable());
+                }
+                Arc::new(Schema::new(fields))
+            }
+            LogicalPlan::Unnest { input, expr } => {
+                let input_schema = input.schema();
+                let mut fields = input_schema.fields.clone();
+                let typ = infer_expr_type_full(expr, &input_schema);
+                fields.push(crate::core::tuple::Field::new(expr.output_name(), typ).nullable());
+                Arc::new(Schema::new(fields))
+            }
+            LogicalPlan::Join {
+                left,
+                right,
+                left_alias,
+                right_alias,
+                join_type,
+                ..
+            } => {
+                let qualify = |alias: &str, field: &crate::core::tuple::Field| {
+                    let mut field = field.clone();
+                    field.name = qualified_name(alias, &field.name);
+                    // Keys are not unique across a join, and the input scans
+                    // have already evaluated lazy columns
+                    field.primary_key = false;
+                    field.is_lazy = false;
+                    field
+                };
+                let mut fields: Vec<_> = left
+                    .schema()
+                    .fields
+                    .iter()
+                    .map(|f| qualify(left_alias, f))
+                    .collect();
+                for field in &right.schema().fields {
+                    let field = qualify(right_alias, field);
+                    fields.push(match join_type {
+                        JoinType::Inner => field,
+                        JoinType::Left => field.nullable(),
+                    });
+                }
+                Arc::new(Schema::new(fields))
+            }
         }
     }
 }
 
+/// Output field of a projected expression: the input field (renamed by
+/// `AS`) for a column, an inferred nullable field for anything computed
+pub(crate) fn projected_field(expr: &Expr, input: &Schema) -> Option<crate::core::tuple::Field> {
+    match expr.unaliased() {
+        Expr::Column(name) => input.get_field(name).map(|field| {
+            let mut field = field.clone();
+            field.name = expr.output_name();
+            field
+        }),
+        other => Some(
+            crate::core::tuple::Field::new(expr.output_name(), infer_expr_type_full(other, input))
+                .nullable(),
+        ),
+    }
+}
+
+/// Type of an expression that yields one of `branches`: the type they all
+/// coerce to, or the first branch's type when there is none
+fn common_type<'a>(
+    branches: impl Iterator<Item = &'a Expr>,
+    schema: &Schema,
+) -> crate::core::value::ValueType {
+    use crate::core::value::ValueType;
+
+    branches
+        .map(|branch| infer_expr_type_full(branch, schema))
+        .fold(ValueType::Null, |acc, t| acc.common_type(&t).unwrap_or(acc))
+}
+
+/// Type of one element of a list (or vector) of `container` type
+fn element_type(container: &crate::core::value::ValueType) -> crate::core::value::ValueType {
+    use crate::core::value::ValueType;
+
+    match container {
+        ValueType::List(elem) => (**elem).clone(),
+        ValueType::Vector(_) => ValueType::Float,
+        _ => ValueType::Null,
+    }
+}
+
 // Helper to fix BinaryExpr destructuring in infer_expr_type
 fn infer_expr_type_full(expr: &Expr, schema: &Schema) -> crate::core::value::ValueType {
     use crate::core::value::ValueType;
@@ -152,11 +795,32 @@ fn infer_expr_type_full(expr: &Expr, schema: &Schema) -> crate::core::value::Val
             .map(|f| f.value_type.clone())
             .unwrap_or(ValueType::Null),
         Expr::Literal(val) => val.value_type(),
-        Expr::BinaryExpr { left, right, .. } => {
+        Expr::BinaryExpr { op, .. }
+            if matches!(
+                op.as_str(),
+                "=" | "!="
+                    | "<"
+                    | ">"
+                    | "<="
+                    | ">="
+                    | "LIKE"
+                    | "NOT LIKE"
+                    | "AND"
+                    | "OR"
+                    | "IS"
+                    | "IS NOT"
+            ) =>
+        {
+            ValueType::Bool
+        }
+        Expr::BinaryExpr { left, op, right } => {
             let l = infer_expr_type_full(left, schema);
             let r = infer_expr_type_full(right, schema);
 
             match (l, r) {
+                // The difference of two timestamps is in seconds
+                (ValueType::Timestamp, ValueType::Timestamp) if op == "-" => ValueType::Int,
+                (ValueType::Timestamp, _) | (_, ValueType::Timestamp) => ValueType::Timestamp,
                 (ValueType::Matrix(r, c), _) => ValueType::Matrix(r, c),
                 (_, ValueType::Matrix(r, c)) => ValueType::Matrix(r, c),
                 (ValueType::Vector(d), _) => ValueType::Vector(d),
@@ -167,5 +831,93 @@ fn infer_expr_type_full(expr: &Expr, schema: &Schema) -> crate::core::value::Val
             }
         }
         Expr::AggregateExpr { .. } => ValueType::Int, // Nested aggregations? Should not happen in logical plan simple exprs
+        Expr::ScalarFunction { func, args } => match func {
+            ScalarFunction::WidthBucket => ValueType::Int,
+            ScalarFunction::RegexpMatch => ValueType::Bool,
+            ScalarFunction::RegexpExtract => ValueType::String,
+            ScalarFunction::CosineSim
+            | ScalarFunction::Dot
+            | ScalarFunction::L2Dist
+            | ScalarFunction::Norm
+            | ScalarFunction::Sqrt
+            | ScalarFunction::Log
+            | ScalarFunction::Exp => ValueType::Float,
+            ScalarFunction::Upper
+            | ScalarFunction::Lower
+            | ScalarFunction::Substr
+            | ScalarFunction::Concat
+            | ScalarFunction::Hash
+            | ScalarFunction::MaskEmail
+            | ScalarFunction::TruncateIp => ValueType::String,
+            ScalarFunction::Length => ValueType::Int,
+            ScalarFunction::Now | ScalarFunction::DateTrunc => ValueType::Timestamp,
+            ScalarFunction::Abs | ScalarFunction::Round | ScalarFunction::Floor => {
+                match infer_expr_type_full(&args[0], schema) {
+                    ValueType::Int => ValueType::Int,
+                    _ => ValueType::Float,
+                }
+            }
+            ScalarFunction::Coalesce => common_type(args.iter(), schema),
+            // Types of the THEN values and the ELSE value
+            ScalarFunction::Case => common_type(
+                args.iter()
+                    .enumerate()
+                    .filter(|(i, _)| i % 2 == 1 || *i == args.len() - 1)
+                    .map(|(_, arg)| arg),
+                schema,
+            ),
+            ScalarFunction::Where => common_type(args[1..].iter(), schema),
+            ScalarFunction::Cast(target) => target.clone(),
+            ScalarFunction::JsonGet => ValueType::Json,
+            ScalarFunction::JsonGetText => ValueType::String,
+            ScalarFunction::ArrayContains => ValueType::Bool,
+            ScalarFunction::ElementAt | ScalarFunction::Unnest => {
+                element_type(&infer_expr_type_full(&args[0], schema))
+            }
+            ScalarFunction::CumSum
+            | ScalarFunction::CumProd
+            | ScalarFunction::Diff
+            | ScalarFunction::RollingMean => match infer_expr_type_full(&args[0], schema) {
+                ValueType::Vector(dim) => ValueType::Vector(match (func, args.get(1)) {
+                    (ScalarFunction::Diff, _) => dim.saturating_sub(1),
+                    (ScalarFunction::RollingMean, Some(Expr::Literal(Value::Int(w)))) => {
+                        (dim + 1).saturating_sub(*w as usize)
+                    }
+                    _ => dim,
+                }),
+                other => other,
+            },
+        },
+        Expr::WindowFunction { func, args, .. } => match func {
+            WindowFunction::Ntile
+            | WindowFunction::RowNumber
+            | WindowFunction::Rank
+            | WindowFunction::DenseRank
+            | WindowFunction::Aggregate(
+                AggregateFunction::Count
+                | AggregateFunction::CountDistinct
+                | AggregateFunction::ApproxCountDistinct,
+                _,
+            ) => ValueType::Int,
+            WindowFunction::PercentRank
+            | WindowFunction::Aggregate(
+                AggregateFunction::Avg
+                | AggregateFunction::Variance
+                | AggregateFunction::Stddev
+                | AggregateFunction::Median
+                | AggregateFunction::Percentile(_)
+                | AggregateFunction::ApproxQuantile(_),
+                _,
+            ) => ValueType::Float,
+            WindowFunction::Lag
+            | WindowFunction::Lead
+            | WindowFunction::Aggregate(
+                AggregateFunction::Sum | AggregateFunction::Min | AggregateFunction::Max,
+                _,
+            ) => args
+                .first()
+                .map_or(ValueType::Float, |arg| infer_expr_type_full(arg, schema)),
+        },
+        Expr::Alias { expr, .. } => infer_expr_type_full(expr, schema),
     }
 }
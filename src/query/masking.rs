@@ -0,0 +1,64 @@
+//! Column masking: scans of datasets with masking policies are replaced by a
+//! projection that shows each masked column as its policy's expression, so
+//! every operator above the scan (filters included) only sees masked values.
+
+use crate::core::tuple::Schema;
+use crate::core::value::Value;
+use crate::engine::TensorDb;
+use crate::query::logical::{Expr, LogicalPlan};
+use crate::query::optimizer::map_inputs;
+
+/// `plan` with the masking policies that apply to the caller's role in place
+pub fn mask_plan(plan: LogicalPlan, db: &TensorDb) -> LogicalPlan {
+    if db.role().is_none() {
+        return plan;
+    }
+    let masked = match &plan {
+        LogicalPlan::Scan {
+            dataset_name,
+            schema,
+        } => masking_projection(dataset_name, schema, db),
+        // Vector search runs on the scan itself; its results are masked
+        LogicalPlan::VectorSearch { input, .. } => match input.as_ref() {
+            LogicalPlan::Scan {
+                dataset_name,
+                schema,
+            } => masking_projection(dataset_name, schema, db),
+            _ => None,
+        },
+        _ => None,
+    };
+    match masked {
+        Some(exprs) => LogicalPlan::Project {
+            input: Box::new(plan),
+            exprs,
+        },
+        None => map_inputs(plan, |input| mask_plan(input, db)),
+    }
+}
+
+/// One expression per column of `schema`: the column itself, or the policy
+/// expression named after it. `None` when no policy applies to the caller.
+fn masking_projection(dataset_name: &str, schema: &Schema, db: &TensorDb) -> Option<Vec<Expr>> {
+    let policies = &db.get_dataset(dataset_name).ok()?.metadata.masking;
+    if !policies.values().any(|p| p.applies_to(db.role())) {
+        return None;
+    }
+    let exprs = schema
+        .fields
+        .iter()
+        .map(|field| match policies.get(&field.name) {
+            Some(policy) if policy.applies_to(db.role()) => Expr::Alias {
+                // Policies are checked when they are set; one that no longer
+                // parses hides the column altogether
+                expr: Box::new(
+                    crate::dsl::parser::parse_expression(&policy.expression, 0)
+                        .unwrap_or(Expr::Literal(Value::Null)),
+                ),
+                name: field.name.clone(),
+            },
+            _ => Expr::Column(field.name.clone()),
+        })
+        .collect();
+    Some(exprs)
+}
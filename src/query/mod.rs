@@ -1,3 +1,9 @@
+pub mod columnar;
+pub mod lineage;
 pub mod logical;
+pub mod masking;
+pub mod optimizer;
 pub mod physical;
 pub mod planner;
+pub mod profile;
+pub mod rerank;
@@ -0,0 +1,432 @@
+//! Rule-based rewrites of logical plans, applied before physical planning:
+//! constant folding, filter pushdown below projections, removal of no-op
+//! projections and column pruning at scans. None of them changes results.
+
+use crate::core::tuple::{Schema, Tuple};
+use crate::core::value::Value;
+use crate::query::logical::{Expr, LogicalPlan};
+use crate::query::physical::evaluate_expression;
+use crate::query::planner::evaluate_expr;
+use std::sync::Arc;
+
+/// Apply every rewrite to `plan`
+pub fn optimize(plan: LogicalPlan) -> LogicalPlan {
+    let plan = fold_constants(plan);
+    let plan = push_down_filters(plan);
+    let plan = remove_noop_projections(plan);
+    prune_columns(plan, None)
+}
+
+/// Rebuild `plan` with `f` applied to each of its inputs
+pub(crate) fn map_inputs(
+    plan: LogicalPlan,
+    mut f: impl FnMut(LogicalPlan) -> LogicalPlan,
+) -> LogicalPlan {
+    let mut apply = |input: Box<LogicalPlan>| Box::new(f(*input));
+    match plan {
+        LogicalPlan::Scan { .. } => plan,
+        LogicalPlan::Filter { input, predicate } => LogicalPlan::Filter {
+            input: apply(input),
+            predicate,
+        },
+        LogicalPlan::Project { input, exprs } => LogicalPlan::Project {
+            input: apply(input),
+            exprs,
+        },
+        LogicalPlan::VectorSearch {
+            input,
+            column,
+            query,
+            k,
+        } => LogicalPlan::VectorSearch {
+            input: apply(input),
+            column,
+            query,
+            k,
+        },
+        LogicalPlan::Sort {
+            input,
+            column,
+            ascending,
+        } => LogicalPlan::Sort {
+            input: apply(input),
+            column,
+            ascending,
+        },
+        LogicalPlan::Limit { input, n } => LogicalPlan::Limit {
+            input: apply(input),
+            n,
+        },
+        LogicalPlan::Sample { input, sample } => LogicalPlan::Sample {
+            input: apply(input),
+            sample,
+        },
+        LogicalPlan::Distinct { input } => LogicalPlan::Distinct {
+            input: apply(input),
+        },
+        LogicalPlan::Aggregate {
+            input,
+            group_expr,
+            aggr_expr,
+            rollup,
+        } => LogicalPlan::Aggregate {
+            input: apply(input),
+            group_expr,
+            aggr_expr,
+            rollup,
+        },
+        LogicalPlan::Window { input, window_expr } => LogicalPlan::Window {
+            input: apply(input),
+            window_expr,
+        },
+        LogicalPlan::Rerank {
+            input,
+            service,
+            column,
+            query,
+        } => LogicalPlan::Rerank {
+            input: apply(input),
+            service,
+            column,
+            query,
+        },
+        LogicalPlan::SemiJoin {
+            input,
+            subquery,
+            expr,
+            anti,
+        } => LogicalPlan::SemiJoin {
+            input: apply(input),
+            subquery: apply(subquery),
+            expr,
+            anti,
+        },
+        LogicalPlan::Join {
+            left,
+            right,
+            left_alias,
+            right_alias,
+            left_key,
+            right_key,
+            join_type,
+            asof,
+            similarity,
+        } => LogicalPlan::Join {
+            left: apply(left),
+            right: apply(right),
+            left_alias,
+            right_alias,
+            left_key,
+            right_key,
+            join_type,
+            asof,
+            similarity,
+        },
+        LogicalPlan::Unnest { input, expr } => LogicalPlan::Unnest {
+            input: apply(input),
+            expr,
+        },
+    }
+}
+
+/// Row without columns, for evaluating expressions made of literals
+fn empty_row() -> Tuple {
+    Tuple::new(Arc::new(Schema::new(vec![])), vec![]).expect("empty row")
+}
+
+/// Fold literal-only expressions in filters and projections. Filters that
+/// fold to TRUE are dropped; projected columns keep their original names.
+/// Aggregates are left alone, since their output names quote their arguments.
+fn fold_constants(plan: LogicalPlan) -> LogicalPlan {
+    match map_inputs(plan, fold_constants) {
+        LogicalPlan::Filter { input, predicate } => match fold_predicate(predicate) {
+            Expr::Literal(Value::Bool(true)) => *input,
+            predicate => LogicalPlan::Filter { input, predicate },
+        },
+        LogicalPlan::Project { input, exprs } => {
+            let exprs = exprs
+                .into_iter()
+                .map(|expr| match expr {
+                    Expr::Alias { expr, name } => Expr::Alias {
+                        expr: Box::new(fold_value(*expr)),
+                        name,
+                    },
+                    expr => {
+                        let name = expr.output_name();
+                        let folded = fold_value(expr);
+                        if folded.output_name() == name {
+                            folded
+                        } else {
+                            Expr::Alias {
+                                expr: Box::new(folded),
+                                name,
+                            }
+                        }
+                    }
+                })
+                .collect();
+            LogicalPlan::Project { input, exprs }
+        }
+        other => other,
+    }
+}
+
+/// Arithmetic on literals becomes its result
+fn fold_value(expr: Expr) -> Expr {
+    match expr {
+        Expr::BinaryExpr { left, op, right } => {
+            let folded = Expr::BinaryExpr {
+                left: Box::new(fold_value(*left)),
+                op,
+                right: Box::new(fold_value(*right)),
+            };
+            match &folded {
+                Expr::BinaryExpr { left, op, right }
+                    if matches!(op.as_str(), "+" | "-" | "*" | "/" | "%")
+                        && matches!(left.as_ref(), Expr::Literal(_))
+                        && matches!(right.as_ref(), Expr::Literal(_)) =>
+                {
+                    Expr::Literal(evaluate_expression(&folded, &empty_row()))
+                }
+                _ => folded,
+            }
+        }
+        Expr::ScalarFunction { func, args } => Expr::ScalarFunction {
+            func,
+            args: args.into_iter().map(fold_value).collect(),
+        },
+        Expr::Alias { expr, name } => Expr::Alias {
+            expr: Box::new(fold_value(*expr)),
+            name,
+        },
+        other => other,
+    }
+}
+
+/// Comparisons of literals become TRUE or FALSE, which then simplify AND and OR
+fn fold_predicate(expr: Expr) -> Expr {
+    let Expr::BinaryExpr { left, op, right } = expr else {
+        return expr;
+    };
+    let is_bool = |e: &Expr, b: bool| matches!(e, Expr::Literal(Value::Bool(v)) if *v == b);
+
+    if op == "AND" || op == "OR" {
+        let (left, right) = (fold_predicate(*left), fold_predicate(*right));
+        // The absorbing value of the operator decides the result, its identity drops out
+        let absorbing = op == "OR";
+        return if is_bool(&left, absorbing) || is_bool(&right, absorbing) {
+            Expr::Literal(Value::Bool(absorbing))
+        } else if is_bool(&left, !absorbing) {
+            right
+        } else if is_bool(&right, !absorbing) {
+            left
+        } else {
+            Expr::BinaryExpr {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            }
+        };
+    }
+
+    let folded = Expr::BinaryExpr {
+        left: Box::new(fold_value(*left)),
+        op,
+        right: Box::new(fold_value(*right)),
+    };
+    match &folded {
+        Expr::BinaryExpr { left, right, .. }
+            if matches!(left.as_ref(), Expr::Literal(_))
+                && matches!(right.as_ref(), Expr::Literal(_)) =>
+        {
+            Expr::Literal(Value::Bool(evaluate_expr(&folded, &empty_row())))
+        }
+        _ => folded,
+    }
+}
+
+/// Move filters below semi-joins and below projections that only pass or
+/// rename the columns they test, so they can reach (and use indexes on) the scan
+fn push_down_filters(plan: LogicalPlan) -> LogicalPlan {
+    match map_inputs(plan, push_down_filters) {
+        LogicalPlan::Filter { input, predicate } => match *input {
+            LogicalPlan::Project {
+                input: project_input,
+                exprs,
+            } => match through_projection(&predicate, &exprs) {
+                Some(pushed) => LogicalPlan::Project {
+                    input: Box::new(push_down_filters(LogicalPlan::Filter {
+                        input: project_input,
+                        predicate: pushed,
+                    })),
+                    exprs,
+                },
+                None => LogicalPlan::Filter {
+                    input: Box::new(LogicalPlan::Project {
+                        input: project_input,
+                        exprs,
+                    }),
+                    predicate,
+                },
+            },
+            // A semi-join only drops rows, so filters can run first
+            LogicalPlan::SemiJoin {
+                input,
+                subquery,
+                expr,
+                anti,
+            } => LogicalPlan::SemiJoin {
+                input: Box::new(push_down_filters(LogicalPlan::Filter { input, predicate })),
+                subquery,
+                expr,
+                anti,
+            },
+            input => LogicalPlan::Filter {
+                input: Box::new(input),
+                predicate,
+            },
+        },
+        other => other,
+    }
+}
+
+/// `predicate` over the input of a projection of `exprs`, or `None` when
+/// it reads a column the projection computes
+fn through_projection(predicate: &Expr, exprs: &[Expr]) -> Option<Expr> {
+    let source = |name: &str| -> Option<String> {
+        let mut matches = exprs.iter().filter(|e| e.output_name() == name);
+        match (matches.next()?.unaliased(), matches.next()) {
+            (Expr::Column(source), None) => Some(source.clone()),
+            _ => None,
+        }
+    };
+    rename_columns(predicate, &source)
+}
+
+fn rename_columns(expr: &Expr, source: &dyn Fn(&str) -> Option<String>) -> Option<Expr> {
+    Some(match expr {
+        Expr::Column(name) => Expr::Column(source(name)?),
+        Expr::Literal(_) => expr.clone(),
+        Expr::BinaryExpr { left, op, right } => Expr::BinaryExpr {
+            left: Box::new(rename_columns(left, source)?),
+            op: op.clone(),
+            right: Box::new(rename_columns(right, source)?),
+        },
+        Expr::ScalarFunction { func, args } => Expr::ScalarFunction {
+            func: func.clone(),
+            args: args
+                .iter()
+                .map(|a| rename_columns(a, source))
+                .collect::<Option<_>>()?,
+        },
+        Expr::Alias { .. } | Expr::AggregateExpr { .. } | Expr::WindowFunction { .. } => {
+            return None
+        }
+    })
+}
+
+/// Drop projections that pass every input column through unchanged, in order
+fn remove_noop_projections(plan: LogicalPlan) -> LogicalPlan {
+    match map_inputs(plan, remove_noop_projections) {
+        LogicalPlan::Project { input, exprs } => {
+            let input_schema = input.schema();
+            let noop = exprs.len() == input_schema.fields.len()
+                && exprs.iter().zip(&input_schema.fields).all(|(expr, field)| {
+                    expr.output_name() == field.name
+                        && matches!(expr.unaliased(), Expr::Column(c) if *c == field.name)
+                });
+            if noop {
+                *input
+            } else {
+                LogicalPlan::Project { input, exprs }
+            }
+        }
+        other => other,
+    }
+}
+
+/// Narrow scans to the columns the plan above them reads. `required` is
+/// `None` when every column of `plan`'s output is needed.
+fn prune_columns(plan: LogicalPlan, required: Option<Vec<String>>) -> LogicalPlan {
+    let with = |required: &Option<Vec<String>>, columns: Vec<String>| {
+        required.clone().map(|mut required| {
+            required.extend(columns);
+            required
+        })
+    };
+    match plan {
+        LogicalPlan::Scan {
+            dataset_name,
+            schema,
+        } => {
+            let schema = match required {
+                Some(required) if !required.iter().any(|c| c == "*") => {
+                    let fields: Vec<_> = schema
+                        .fields
+                        .iter()
+                        .filter(|f| required.contains(&f.name))
+                        .cloned()
+                        .collect();
+                    if fields.len() < schema.fields.len() {
+                        Arc::new(Schema::new(fields))
+                    } else {
+                        schema
+                    }
+                }
+                _ => schema,
+            };
+            LogicalPlan::Scan {
+                dataset_name,
+                schema,
+            }
+        }
+        LogicalPlan::Filter { input, predicate } => LogicalPlan::Filter {
+            input: Box::new(prune_columns(*input, with(&required, predicate.columns()))),
+            predicate,
+        },
+        LogicalPlan::Project { input, exprs } => {
+            let columns = exprs.iter().flat_map(Expr::columns).collect();
+            LogicalPlan::Project {
+                input: Box::new(prune_columns(*input, Some(columns))),
+                exprs,
+            }
+        }
+        LogicalPlan::Sort {
+            input,
+            column,
+            ascending,
+        } => LogicalPlan::Sort {
+            input: Box::new(prune_columns(*input, with(&required, vec![column.clone()]))),
+            column,
+            ascending,
+        },
+        LogicalPlan::Limit { input, n } => LogicalPlan::Limit {
+            input: Box::new(prune_columns(*input, required)),
+            n,
+        },
+        LogicalPlan::Sample { input, sample } => LogicalPlan::Sample {
+            input: Box::new(prune_columns(*input, required)),
+            sample,
+        },
+        LogicalPlan::Aggregate {
+            input,
+            group_expr,
+            aggr_expr,
+            rollup,
+        } => {
+            let columns = group_expr
+                .iter()
+                .chain(&aggr_expr)
+                .flat_map(Expr::columns)
+                .collect();
+            LogicalPlan::Aggregate {
+                input: Box::new(prune_columns(*input, Some(columns))),
+                group_expr,
+                aggr_expr,
+                rollup,
+            }
+        }
+        // DISTINCT compares whole rows; the others keep every input column
+        other => map_inputs(other, |input| prune_columns(input, None)),
+    }
+}
@@ -1,7 +1,10 @@
+use crate::core::dataset_legacy::Sample;
 use crate::core::tuple::{Schema, Tuple};
 use crate::engine::EngineError;
 use crate::engine::TensorDb;
-use std::sync::Arc;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
 
 /// Helper function to evaluate lazy columns in a row
 fn evaluate_lazy_columns_in_row(
@@ -22,6 +25,39 @@ fn evaluate_lazy_columns_in_row(
     Tuple::new(dataset.schema.clone(), evaluated_values).map_err(|e| EngineError::InvalidOp(e))
 }
 
+/// Positions in the dataset of the columns of a pruned scan `schema`; `None`
+/// when the scan reads every column
+fn scan_columns(dataset_schema: &Schema, schema: &Schema) -> Option<Vec<usize>> {
+    if schema.len() == dataset_schema.len() {
+        return None;
+    }
+    Some(
+        schema
+            .fields
+            .iter()
+            .filter_map(|f| dataset_schema.get_field_index(&f.name))
+            .collect(),
+    )
+}
+
+/// A scanned row with lazy columns evaluated, narrowed to `schema` when the
+/// optimizer pruned columns from the scan
+fn scan_row(
+    dataset: &crate::core::dataset_legacy::Dataset,
+    row: &Tuple,
+    columns: &Option<Vec<usize>>,
+    schema: &Arc<Schema>,
+) -> Result<Tuple, EngineError> {
+    let row = evaluate_lazy_columns_in_row(dataset, row)?;
+    match columns {
+        None => Ok(row),
+        Some(columns) => {
+            let values = columns.iter().map(|&i| row.values[i].clone()).collect();
+            Tuple::new(schema.clone(), values).map_err(EngineError::InvalidOp)
+        }
+    }
+}
+
 /// Trait for physical execution plan nodes
 pub trait PhysicalPlan: Send + Sync + std::fmt::Debug {
     /// Get the schema of the output
@@ -29,8 +65,21 @@ pub trait PhysicalPlan: Send + Sync + std::fmt::Debug {
 
     /// Execute the plan and return the result rows
     fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError>;
+
+    /// Operator name shown by EXPLAIN ANALYZE
+    fn name(&self) -> String {
+        let type_name = std::any::type_name::<Self>();
+        type_name
+            .rsplit("::")
+            .next()
+            .unwrap_or(type_name)
+            .to_string()
+    }
 }
 
+/// Rows a scan reads between two progress updates (see SHOW QUERIES)
+const PROGRESS_BATCH_ROWS: usize = 1024;
+
 /// Sequential Scan Executor
 #[derive(Debug)]
 pub struct SeqScanExec {
@@ -43,12 +92,22 @@ impl PhysicalPlan for SeqScanExec {
         self.schema.clone()
     }
 
+    fn name(&self) -> String {
+        format!("SeqScanExec({})", self.dataset_name)
+    }
+
     fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
         let dataset = db.get_dataset(&self.dataset_name)?;
+        db.record_scan(dataset.rows.len(), false);
+        db.start_operator(self.name(), dataset.rows.len());
         // Clone all rows and evaluate lazy columns
+        let columns = scan_columns(&dataset.schema, &self.schema);
         let mut rows = Vec::with_capacity(dataset.rows.len());
-        for row in &dataset.rows {
-            rows.push(evaluate_lazy_columns_in_row(&dataset, row)?);
+        for batch in dataset.rows.chunks(PROGRESS_BATCH_ROWS) {
+            for row in batch {
+                rows.push(scan_row(dataset, row, &columns, &self.schema)?);
+            }
+            db.advance_operator(batch.len());
         }
         Ok(rows)
     }
@@ -84,6 +143,41 @@ impl PhysicalPlan for FilterExec {
     }
 }
 
+/// Vectorized Filter Executor: evaluates the predicate over columnar batches
+/// of the referenced columns, falling back to row evaluation when a column
+/// holds values of an unexpected type
+#[derive(Debug)]
+pub struct ColumnarFilterExec {
+    pub input: Box<dyn PhysicalPlan>,
+    pub predicate: crate::query::logical::Expr,
+}
+
+impl PhysicalPlan for ColumnarFilterExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        use crate::query::columnar::{filter_mask, ColumnBatch};
+
+        let rows = self.input.execute(db)?;
+        let columns = self.predicate.columns();
+        let mask = ColumnBatch::from_rows(&rows, &self.schema(), &columns)
+            .and_then(|batch| filter_mask(&self.predicate, &batch));
+        let Some(mask) = mask else {
+            return Ok(rows
+                .into_iter()
+                .filter(|row| crate::query::planner::evaluate_expr(&self.predicate, row))
+                .collect());
+        };
+        Ok(rows
+            .into_iter()
+            .zip(mask)
+            .filter_map(|(row, keep)| keep.then_some(row))
+            .collect())
+    }
+}
+
 /// Index Scan Executor (Optimization)
 #[derive(Debug)]
 pub struct IndexScanExec {
@@ -98,6 +192,10 @@ impl PhysicalPlan for IndexScanExec {
         self.schema.clone()
     }
 
+    fn name(&self) -> String {
+        format!("IndexScanExec({}.{})", self.dataset_name, self.column)
+    }
+
     fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
         let dataset = db.get_dataset(&self.dataset_name)?;
 
@@ -109,10 +207,12 @@ impl PhysicalPlan for IndexScanExec {
         let row_ids = index
             .lookup(&self.value)
             .map_err(|e| EngineError::InvalidOp(e))?;
+        db.record_scan(row_ids.len(), true);
 
+        let columns = scan_columns(&dataset.schema, &self.schema);
         let mut evaluated_rows = Vec::new();
         for row in dataset.get_rows_by_ids(&row_ids) {
-            evaluated_rows.push(evaluate_lazy_columns_in_row(&dataset, &row)?);
+            evaluated_rows.push(scan_row(dataset, &row, &columns, &self.schema)?);
         }
         Ok(evaluated_rows)
     }
@@ -133,6 +233,13 @@ impl PhysicalPlan for VectorSearchExec {
         self.schema.clone()
     }
 
+    fn name(&self) -> String {
+        format!(
+            "VectorSearchExec({}.{}, k={})",
+            self.dataset_name, self.column, self.k
+        )
+    }
+
     fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
         let dataset = db.get_dataset(&self.dataset_name)?;
         let index = dataset.get_index(&self.column).ok_or_else(|| {
@@ -153,6 +260,8 @@ impl PhysicalPlan for VectorSearchExec {
             .search(&self.query, self.k)
             .map_err(|e| EngineError::InvalidOp(e))?;
         let row_ids: Vec<usize> = results.iter().map(|(id, _)| *id).collect();
+        // The vector index compares the query against every indexed row
+        db.record_scan(dataset.rows.len(), true);
 
         let mut evaluated_rows = Vec::new();
         for row in dataset.get_rows_by_ids(&row_ids) {
@@ -162,12 +271,67 @@ impl PhysicalPlan for VectorSearchExec {
     }
 }
 
+/// Rerank Executor: scores the input rows with an external service and
+/// returns them best-first with a `rerank_score` column appended
+#[derive(Debug)]
+pub struct RerankExec {
+    pub input: Box<dyn PhysicalPlan>,
+    pub service: crate::core::config::RerankServiceConfig,
+    pub column: String,
+    pub query: String,
+    pub schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for RerankExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.schema.clone()
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        use crate::core::value::Value;
+
+        let input_rows = self.input.execute(db)?;
+        let documents: Vec<String> = input_rows
+            .iter()
+            .map(|row| match row.get(&self.column) {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Null) | None => String::new(),
+                Some(other) => other.to_string(),
+            })
+            .collect();
+
+        let scores = crate::query::rerank::request_scores(&self.service, &self.query, &documents)
+            .map_err(EngineError::InvalidOp)?;
+
+        let mut scored: Vec<(f32, Tuple)> = scores.into_iter().zip(input_rows).collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        scored
+            .into_iter()
+            .map(|(score, row)| {
+                let mut values = row.values;
+                values.push(Value::Float(score));
+                Tuple::new(self.schema.clone(), values).map_err(EngineError::InvalidOp)
+            })
+            .collect()
+    }
+}
+
 /// Projection Executor
 #[derive(Debug)]
 pub struct ProjectionExec {
     pub input: Box<dyn PhysicalPlan>,
     pub output_schema: Arc<Schema>,
-    pub column_indices: Vec<usize>,
+    pub columns: Vec<ProjectedColumn>,
+}
+
+/// Source of one projected column
+#[derive(Debug)]
+pub enum ProjectedColumn {
+    /// Copied from the input column at this index
+    Input(usize),
+    /// Evaluated per row, e.g. `price * quantity`
+    Computed(crate::query::logical::Expr),
 }
 
 impl PhysicalPlan for ProjectionExec {
@@ -177,20 +341,40 @@ impl PhysicalPlan for ProjectionExec {
 
     fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
         let input_rows = self.input.execute(db)?;
-        let mut output_rows = Vec::with_capacity(input_rows.len());
 
-        for row in input_rows {
-            let new_values: Vec<_> = self
-                .column_indices
-                .iter()
-                .map(|&idx| row.values[idx].clone())
-                .collect();
-            output_rows.push(
-                Tuple::new(self.output_schema.clone(), new_values)
-                    .map_err(|e| EngineError::InvalidOp(e))?,
-            );
-        }
-        Ok(output_rows)
+        // Input columns widened at execution (Int overflow promoted to Float)
+        // carry their actual type through
+        let output_schema = match input_rows.first() {
+            Some(row) if *row.schema != *self.input.schema() => Arc::new(Schema::new(
+                self.columns
+                    .iter()
+                    .zip(&self.output_schema.fields)
+                    .map(|(column, field)| match column {
+                        ProjectedColumn::Input(idx) => {
+                            let mut widened = row.schema.fields[*idx].clone();
+                            widened.name = field.name.clone();
+                            widened
+                        }
+                        ProjectedColumn::Computed(_) => field.clone(),
+                    })
+                    .collect(),
+            )),
+            _ => self.output_schema.clone(),
+        };
+
+        let output_rows = input_rows
+            .iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .map(|column| match column {
+                        ProjectedColumn::Input(idx) => row.values[*idx].clone(),
+                        ProjectedColumn::Computed(expr) => evaluate_expression(expr, row),
+                    })
+                    .collect()
+            })
+            .collect();
+        tuples_with_promotion(&output_schema, output_rows)
     }
 }
 
@@ -212,6 +396,43 @@ impl PhysicalPlan for LimitExec {
     }
 }
 
+/// Sample Executor: a random subset of the input rows, see `Sample`
+#[derive(Debug)]
+pub struct SampleExec {
+    pub input: Box<dyn PhysicalPlan>,
+    pub sample: Sample,
+}
+
+impl PhysicalPlan for SampleExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        let rows = self.input.execute(db)?;
+        Ok(self.sample.apply(rows, &mut db.rng()))
+    }
+}
+
+/// Distinct Executor: hash-based deduplication of whole rows, in input order
+#[derive(Debug)]
+pub struct DistinctExec {
+    pub input: Box<dyn PhysicalPlan>,
+}
+
+impl PhysicalPlan for DistinctExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        let mut rows = self.input.execute(db)?;
+        let mut seen = std::collections::HashSet::with_capacity(rows.len());
+        rows.retain(|row| seen.insert(row.values.clone()));
+        Ok(rows)
+    }
+}
+
 /// Sort Executor
 #[derive(Debug)]
 pub struct SortExec {
@@ -248,209 +469,728 @@ impl PhysicalPlan for SortExec {
     }
 }
 
-/// Aggregation Executor
+/// Top-K Executor: ORDER BY followed by LIMIT k in one pass, keeping only the
+/// best `k` rows in a bounded heap instead of sorting the whole input. Ties
+/// keep input order, as with the stable sort of SortExec.
 #[derive(Debug)]
-pub struct AggregateExec {
+pub struct TopKExec {
     pub input: Box<dyn PhysicalPlan>,
-    pub group_expr: Vec<crate::query::logical::Expr>,
-    pub aggr_expr: Vec<crate::query::logical::Expr>,
-    pub schema: Arc<Schema>,
+    pub column: String,
+    pub ascending: bool,
+    pub k: usize,
 }
 
-impl PhysicalPlan for AggregateExec {
+/// A candidate row; orders by sort key, then input position, so the heap's
+/// top is the row that leaves first
+struct TopKEntry {
+    key: crate::core::value::Value,
+    pos: usize,
+    ascending: bool,
+    row: Tuple,
+}
+
+impl Ord for TopKEntry {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let ord = self
+            .key
+            .compare(&other.key)
+            .unwrap_or(std::cmp::Ordering::Equal);
+        let ord = if self.ascending { ord } else { ord.reverse() };
+        ord.then(self.pos.cmp(&other.pos))
+    }
+}
+
+impl PartialOrd for TopKEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TopKEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for TopKEntry {}
+
+impl PhysicalPlan for TopKExec {
     fn schema(&self) -> Arc<Schema> {
-        self.schema.clone()
+        self.input.schema()
     }
 
     fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
         let rows = self.input.execute(db)?;
+        let col_idx = self.schema().get_field_index(&self.column).ok_or_else(|| {
+            EngineError::InvalidOp(format!("Column not found for sorting: {}", self.column))
+        })?;
+        if self.k == 0 {
+            return Ok(Vec::new());
+        }
 
-        // If no rows and no group by, return empty result set
-        // (Aggregations on empty sets typically return no rows, not NULL rows)
-        if rows.is_empty() {
-            return Ok(vec![]);
+        let mut heap = std::collections::BinaryHeap::with_capacity(self.k.min(rows.len()));
+        for (pos, row) in rows.into_iter().enumerate() {
+            let entry = TopKEntry {
+                key: row.values[col_idx].clone(),
+                pos,
+                ascending: self.ascending,
+                row,
+            };
+            if heap.len() < self.k {
+                heap.push(entry);
+            } else if let Some(mut worst) = heap.peek_mut() {
+                if entry < *worst {
+                    *worst = entry;
+                }
+            }
+        }
+        Ok(heap.into_sorted_vec().into_iter().map(|e| e.row).collect())
+    }
+}
+
+/// How a hash join spreads its work, chosen by the planner from the
+/// estimated row counts of its inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinStrategy {
+    /// One hash table on the small side (the left one when `build_left`),
+    /// probed by every row of the other side
+    Broadcast { build_left: bool },
+    /// Both sides hash-partitioned on the key, partition pairs joined in parallel
+    Partitioned { partitions: usize },
+}
+
+impl std::fmt::Display for JoinStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JoinStrategy::Broadcast { build_left: true } => write!(f, "broadcast left"),
+            JoinStrategy::Broadcast { build_left: false } => write!(f, "broadcast right"),
+            JoinStrategy::Partitioned { partitions } => write!(f, "partitioned x{}", partitions),
         }
+    }
+}
 
-        // If no group by, global aggregation (1 group)
-        // If group by, hash aggregation
+/// Hash Join Executor. Whatever the strategy, rows come out in left row
+/// order, with the matches of a left row in right row order. NULL keys
+/// never match.
+#[derive(Debug)]
+pub struct HashJoinExec {
+    pub left: Box<dyn PhysicalPlan>,
+    pub right: Box<dyn PhysicalPlan>,
+    /// Key column positions within the left and right input rows
+    pub left_key: usize,
+    pub right_key: usize,
+    pub join_type: crate::query::logical::JoinType,
+    pub strategy: JoinStrategy,
+    pub schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for HashJoinExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.schema.clone()
+    }
 
+    fn name(&self) -> String {
+        format!("HashJoinExec({})", self.strategy)
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
         use crate::core::value::Value;
-        use std::collections::HashMap;
+        use crate::query::logical::JoinType;
 
-        // Map GroupKey -> Accumulators
-        // GroupKey is Vec<Value>
-        type GroupKey = Vec<Value>;
-        type Accumulators = Vec<Value>; // Accumulator state for SUM, COUNT, MIN, MAX
+        let left_rows = self.left.execute(db)?;
+        let right_rows = self.right.execute(db)?;
+        let keys = |rows: &[Tuple], col: usize| -> Vec<(usize, Value)> {
+            rows.iter()
+                .enumerate()
+                .filter_map(|(i, row)| join_key(&row.values[col]).map(|key| (i, key)))
+                .collect()
+        };
+        let left_keys = keys(&left_rows, self.left_key);
+        let right_keys = keys(&right_rows, self.right_key);
 
-        // Separate tracking for AVG: (sum, count) pairs for each AVG aggregate
-        // Indexed by position in aggr_expr
-        type AvgAccumulators = Vec<(Value, usize)>; // (sum, count) for AVG
+        let mut pairs = match self.strategy {
+            JoinStrategy::Broadcast { build_left } => {
+                join_pairs(&left_keys, &right_keys, build_left)
+            }
+            JoinStrategy::Partitioned { partitions } => {
+                partitioned_join_pairs(left_keys, right_keys, partitions.max(1))
+            }
+        };
+        pairs.sort_unstable();
 
-        let mut groups: HashMap<GroupKey, (Accumulators, AvgAccumulators)> = HashMap::new();
+        let right_width = self.right.schema().len();
+        let mut pairs = pairs.into_iter().peekable();
+        let mut output_rows = Vec::new();
+        for (l, row) in left_rows.into_iter().enumerate() {
+            let mut matched = false;
+            while let Some((_, r)) = pairs.next_if(|&(pl, _)| pl == l) {
+                matched = true;
+                let mut values = row.values.clone();
+                values.extend(right_rows[r].values.iter().cloned());
+                output_rows
+                    .push(Tuple::new(self.schema.clone(), values).map_err(EngineError::InvalidOp)?);
+            }
+            if !matched && self.join_type == JoinType::Left {
+                let mut values = row.values;
+                values.extend(std::iter::repeat_n(Value::Null, right_width));
+                output_rows
+                    .push(Tuple::new(self.schema.clone(), values).map_err(EngineError::InvalidOp)?);
+            }
+        }
+        Ok(output_rows)
+    }
+}
 
-        // 1. Initialize groups
-        // Iterate rows
-        for row in rows {
-            // Eval group key
-            let key: GroupKey = self
-                .group_expr
-                .iter()
-                .map(|expr| evaluate_expression(expr, &row))
-                .collect();
+/// As-of join: each left row meets the right row of the same key with the
+/// latest time at or before its own (the last such row on ties). Left row
+/// order is kept; NULL keys and times never match.
+#[derive(Debug)]
+pub struct AsOfJoinExec {
+    pub left: Box<dyn PhysicalPlan>,
+    pub right: Box<dyn PhysicalPlan>,
+    /// Key and time column positions within the left and right input rows
+    pub left_key: usize,
+    pub right_key: usize,
+    pub left_time: usize,
+    pub right_time: usize,
+    pub join_type: crate::query::logical::JoinType,
+    pub schema: Arc<Schema>,
+}
 
-            let (accs, avg_accs) = groups.entry(key).or_insert_with(|| {
-                // Init accumulators
-                let mut regular_accs = Vec::new();
-                let mut avg_accumulators = Vec::new();
+impl PhysicalPlan for AsOfJoinExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.schema.clone()
+    }
 
-                for expr in &self.aggr_expr {
-                    match expr {
-                        crate::query::logical::Expr::AggregateExpr { func, expr: inner } => {
-                            match func {
-                                crate::query::logical::AggregateFunction::Count => {
-                                    regular_accs.push(Value::Int(0));
-                                    avg_accumulators.push((Value::Null, 0)); // Placeholder
-                                }
-                                crate::query::logical::AggregateFunction::Sum => {
-                                    let val = evaluate_expression(inner, &row);
-                                    if let Value::Vector(v) = val {
-                                        regular_accs.push(Value::Vector(vec![0.0; v.len()]));
-                                    } else if let Value::Matrix(m) = val {
-                                        // Zero matrix
-                                        if m.is_empty() {
-                                            regular_accs.push(Value::Matrix(vec![]));
-                                        } else {
-                                            let r = m.len();
-                                            let c = m[0].len();
-                                            regular_accs.push(Value::Matrix(vec![vec![0.0; c]; r]));
-                                        }
-                                    } else {
-                                        regular_accs.push(Value::Int(0));
-                                    }
-                                    avg_accumulators.push((Value::Null, 0)); // Placeholder
-                                }
-                                crate::query::logical::AggregateFunction::Min => {
-                                    regular_accs.push(Value::Null);
-                                    avg_accumulators.push((Value::Null, 0)); // Placeholder
-                                }
-                                crate::query::logical::AggregateFunction::Max => {
-                                    regular_accs.push(Value::Null);
-                                    avg_accumulators.push((Value::Null, 0)); // Placeholder
-                                }
-                                crate::query::logical::AggregateFunction::Avg => {
-                                    // For AVG, initialize sum based on first value type
-                                    let val = evaluate_expression(inner, &row);
-                                    let initial_sum = if let Value::Vector(v) = val {
-                                        Value::Vector(vec![0.0; v.len()])
-                                    } else if let Value::Matrix(m) = val {
-                                        if m.is_empty() {
-                                            Value::Matrix(vec![])
-                                        } else {
-                                            let r = m.len();
-                                            let c = m[0].len();
-                                            Value::Matrix(vec![vec![0.0; c]; r])
-                                        }
-                                    } else {
-                                        Value::Float(0.0)
-                                    };
-                                    avg_accumulators.push((initial_sum, 0));
-                                    regular_accs.push(Value::Null); // Placeholder, will be replaced with computed avg
-                                }
-                            }
-                        }
-                        _ => {
-                            regular_accs.push(Value::Null);
-                            avg_accumulators.push((Value::Null, 0));
-                        }
-                    }
-                }
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        use crate::core::value::Value;
+        use crate::query::logical::JoinType;
 
-                (regular_accs, avg_accumulators)
+        let right_rows = self.right.execute(db)?;
+        let mut timelines: HashMap<Value, Vec<usize>> = HashMap::new();
+        for (i, row) in right_rows.iter().enumerate() {
+            if row.values[self.right_time].is_null() {
+                continue;
+            }
+            if let Some(key) = join_key(&row.values[self.right_key]) {
+                timelines.entry(key).or_default().push(i);
+            }
+        }
+        // Stable, so rows with equal times stay in input order
+        for timeline in timelines.values_mut() {
+            timeline.sort_by(|&a, &b| {
+                right_rows[a].values[self.right_time]
+                    .total_cmp(&right_rows[b].values[self.right_time])
             });
+        }
 
-            // Update accumulators
-            for (i, expr) in self.aggr_expr.iter().enumerate() {
-                if let crate::query::logical::Expr::AggregateExpr {
-                    func,
-                    expr: inner_expr,
-                } = expr
-                {
-                    // Eval inner expr
-                    let val = evaluate_expression(inner_expr, &row);
-
-                    match func {
-                        crate::query::logical::AggregateFunction::Count => {
-                            if let Value::Int(c) = accs[i] {
-                                accs[i] = Value::Int(c + 1);
-                            }
-                        }
-                        crate::query::logical::AggregateFunction::Sum => {
-                            match (&mut accs[i], &val) {
-                                (Value::Int(ref mut sum), Value::Int(v)) => *sum += v,
-                                (Value::Float(ref mut sum), Value::Float(v)) => *sum += v,
-                                (Value::Int(sum), Value::Float(v)) => {
-                                    let new_val = *sum as f32 + v;
-                                    accs[i] = Value::Float(new_val);
-                                }
-                                (Value::Float(ref mut sum), Value::Int(v)) => *sum += *v as f32,
-                                (Value::Vector(sum_vec), Value::Vector(v)) => {
-                                    if sum_vec.len() == v.len() {
-                                        for (opt, val) in sum_vec.iter_mut().zip(v.iter()) {
-                                            *opt += val;
-                                        }
-                                    }
-                                }
-                                (Value::Matrix(sum_mat), Value::Matrix(v)) => {
-                                    // Element-wise sum
-                                    if sum_mat.len() == v.len()
-                                        && !sum_mat.is_empty()
-                                        && sum_mat[0].len() == v[0].len()
-                                    {
-                                        for i in 0..sum_mat.len() {
-                                            for j in 0..sum_mat[i].len() {
-                                                sum_mat[i][j] += v[i][j];
-                                            }
-                                        }
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-                        crate::query::logical::AggregateFunction::Avg => {
-                            // Track sum and count for AVG
-                            let (sum_ref, count_ref) = &mut avg_accs[i];
-                            *count_ref += 1;
-
-                            // Add to sum - need to handle type conversions
-                            match sum_ref {
-                                Value::Float(ref mut sum) => match &val {
-                                    Value::Int(v) => *sum += *v as f32,
-                                    Value::Float(v) => *sum += v,
-                                    _ => {}
-                                },
-                                Value::Int(ref mut sum) => {
-                                    match &val {
-                                        Value::Int(v) => {
-                                            // Convert to Float for precision
-                                            *sum_ref = Value::Float(*sum as f32 + *v as f32);
-                                        }
-                                        Value::Float(v) => {
-                                            *sum_ref = Value::Float(*sum as f32 + v);
+        let right_width = self.right.schema().len();
+        let mut output_rows = Vec::new();
+        for row in self.left.execute(db)? {
+            let time = &row.values[self.left_time];
+            let matched = join_key(&row.values[self.left_key])
+                .filter(|_| !time.is_null())
+                .and_then(|key| timelines.get(&key))
+                .and_then(|timeline| {
+                    let after = timeline.partition_point(|&i| {
+                        compare_values(&right_rows[i].values[self.right_time], "<=", time)
+                            == Value::Bool(true)
+                    });
+                    after.checked_sub(1).map(|pos| timeline[pos])
+                });
+            let mut values = row.values;
+            match matched {
+                Some(i) => values.extend(right_rows[i].values.iter().cloned()),
+                None if self.join_type == JoinType::Left => {
+                    values.extend(std::iter::repeat_n(Value::Null, right_width))
+                }
+                None => continue,
+            }
+            output_rows
+                .push(Tuple::new(self.schema.clone(), values).map_err(EngineError::InvalidOp)?);
+        }
+        Ok(output_rows)
+    }
+}
+
+/// Similarity Join Executor: pairs each left row with every right row whose
+/// vector is similar enough, best match first. When the right side scans a
+/// dataset with a vector index on its key, the index generates the
+/// candidates instead of a scan of the right rows per left row.
+#[derive(Debug)]
+pub struct SimilarityJoinExec {
+    pub left: Box<dyn PhysicalPlan>,
+    pub right: Box<dyn PhysicalPlan>,
+    /// Vector column positions within the left and right input rows
+    pub left_key: usize,
+    pub right_key: usize,
+    /// `(dataset, column)` of the vector index answering for the right side
+    pub right_index: Option<(String, String)>,
+    pub threshold: crate::query::logical::SimilarityThreshold,
+    pub join_type: crate::query::logical::JoinType,
+    pub schema: Arc<Schema>,
+}
+
+impl SimilarityJoinExec {
+    /// `(right row, score)` matches of `query` through the vector index
+    fn index_matches(
+        &self,
+        db: &TensorDb,
+        dataset_name: &str,
+        column: &str,
+        query: &[f32],
+    ) -> Result<Vec<(Tuple, f32)>, EngineError> {
+        let dataset = db.get_dataset(dataset_name)?;
+        let index = dataset
+            .get_index(column)
+            .filter(|index| index.index_type() == crate::core::index::IndexType::Vector)
+            .ok_or_else(|| {
+                EngineError::InvalidOp(format!("Vector index not found on column '{}'", column))
+            })?;
+        // Best first, so the matches are a prefix of the ranking
+        let ranked = index
+            .search(&vector_tensor(query), dataset.rows.len())
+            .map_err(EngineError::InvalidOp)?;
+        db.record_scan(dataset.rows.len(), true);
+
+        let schema = self.right.schema();
+        let columns = scan_columns(&dataset.schema, &schema);
+        ranked
+            .into_iter()
+            .take_while(|(_, score)| self.threshold.accepts(*score))
+            .filter_map(|(id, score)| dataset.rows.get(id).map(|row| (row, score)))
+            .map(|(row, score)| Ok((scan_row(dataset, row, &columns, &schema)?, score)))
+            .collect()
+    }
+}
+
+impl PhysicalPlan for SimilarityJoinExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.schema.clone()
+    }
+
+    fn name(&self) -> String {
+        let candidates = if self.right_index.is_some() {
+            "vector index"
+        } else {
+            "nested loop"
+        };
+        format!("SimilarityJoinExec({}, {})", self.threshold, candidates)
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        use crate::core::value::Value;
+        use crate::query::logical::JoinType;
+
+        let right_rows = match self.right_index {
+            Some(_) => Vec::new(),
+            None => self.right.execute(db)?,
+        };
+        let right_width = self.right.schema().len();
+        let mut output_rows = Vec::new();
+        for row in self.left.execute(db)? {
+            let mut matches: Vec<(Tuple, f32)> =
+                match (&row.values[self.left_key], &self.right_index) {
+                    (Value::Vector(query), Some((dataset, column))) => {
+                        self.index_matches(db, dataset, column, query)?
+                    }
+                    (Value::Vector(query), None) => {
+                        let query = vector_tensor(query);
+                        right_rows
+                            .iter()
+                            .filter_map(|right| match &right.values[self.right_key] {
+                                Value::Vector(v) => crate::engine::kernels::cosine_similarity_1d(
+                                    &query,
+                                    &vector_tensor(v),
+                                )
+                                .ok()
+                                .filter(|score| self.threshold.accepts(*score))
+                                .map(|score| (right.clone(), score)),
+                                _ => None,
+                            })
+                            .collect()
+                    }
+                    _ => Vec::new(),
+                };
+            // Stable, so equal scores keep the right side's order
+            matches.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+            if matches.is_empty() && self.join_type == JoinType::Left {
+                let mut values = row.values;
+                values.extend(std::iter::repeat_n(Value::Null, right_width));
+                output_rows
+                    .push(Tuple::new(self.schema.clone(), values).map_err(EngineError::InvalidOp)?);
+                continue;
+            }
+            for (right, _) in matches {
+                let mut values = row.values.clone();
+                values.extend(right.values);
+                output_rows
+                    .push(Tuple::new(self.schema.clone(), values).map_err(EngineError::InvalidOp)?);
+            }
+        }
+        Ok(output_rows)
+    }
+}
+
+/// `(left, right)` row index pairs of equal keys, hashing the left side
+/// when `build_left` and the right side otherwise
+fn join_pairs(
+    left: &[(usize, crate::core::value::Value)],
+    right: &[(usize, crate::core::value::Value)],
+    build_left: bool,
+) -> Vec<(usize, usize)> {
+    let (build, probe) = if build_left {
+        (left, right)
+    } else {
+        (right, left)
+    };
+    let mut table: HashMap<&crate::core::value::Value, Vec<usize>> = HashMap::new();
+    for (i, key) in build {
+        table.entry(key).or_default().push(*i);
+    }
+    let mut pairs = Vec::new();
+    for (i, key) in probe {
+        for &j in table.get(key).into_iter().flatten() {
+            pairs.push(if build_left { (j, *i) } else { (*i, j) });
+        }
+    }
+    pairs
+}
+
+/// Join pairs computed per hash partition of the keys, one thread each
+fn partitioned_join_pairs(
+    left: Vec<(usize, crate::core::value::Value)>,
+    right: Vec<(usize, crate::core::value::Value)>,
+    partitions: usize,
+) -> Vec<(usize, usize)> {
+    use std::hash::{Hash, Hasher};
+    let split = |keys: Vec<(usize, crate::core::value::Value)>| {
+        let mut parts = vec![Vec::new(); partitions];
+        for (i, key) in keys {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            key.hash(&mut hasher);
+            parts[(hasher.finish() % partitions as u64) as usize].push((i, key));
+        }
+        parts
+    };
+    let left_parts = split(left);
+    let right_parts = split(right);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = left_parts
+            .iter()
+            .zip(&right_parts)
+            .map(|(l, r)| scope.spawn(move || join_pairs(l, r, false)))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("join partition panicked"))
+            .collect()
+    })
+}
+
+/// Semi-join (anti-join when `anti`) of the input rows against the values
+/// of the single column of `subquery`, as `expr [NOT] IN (SELECT ...)`.
+/// A NULL `expr` never qualifies, and NOT IN keeps nothing when the
+/// subquery returns a NULL, as in SQL.
+#[derive(Debug)]
+pub struct SemiJoinExec {
+    pub input: Box<dyn PhysicalPlan>,
+    pub subquery: Box<dyn PhysicalPlan>,
+    pub expr: crate::query::logical::Expr,
+    pub anti: bool,
+}
+
+impl PhysicalPlan for SemiJoinExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        let mut has_null = false;
+        let mut values = std::collections::HashSet::new();
+        for row in self.subquery.execute(db)? {
+            match join_key(&row.values[0]) {
+                Some(key) => {
+                    values.insert(key);
+                }
+                None => has_null = true,
+            }
+        }
+        if self.anti && has_null {
+            return Ok(Vec::new());
+        }
+
+        let mut rows = self.input.execute(db)?;
+        rows.retain(
+            |row| match join_key(&evaluate_expression(&self.expr, row)) {
+                Some(key) => values.contains(&key) != self.anti,
+                None => false,
+            },
+        );
+        Ok(rows)
+    }
+}
+
+/// Hash key of a join column value: integral floats hash like the equal
+/// Int so `1 = 1.0` matches, and NULL has no key
+fn join_key(value: &crate::core::value::Value) -> Option<crate::core::value::Value> {
+    use crate::core::value::Value;
+    match value {
+        Value::Null => None,
+        Value::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f32 => {
+            Some(Value::Int(*f as i64))
+        }
+        other => Some(other.clone()),
+    }
+}
+
+/// Aggregation Executor
+#[derive(Debug)]
+pub struct AggregateExec {
+    pub input: Box<dyn PhysicalPlan>,
+    pub group_expr: Vec<crate::query::logical::Expr>,
+    pub aggr_expr: Vec<crate::query::logical::Expr>,
+    /// Append subtotal rows for each prefix of the keys and a grand total
+    pub rollup: bool,
+    pub schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for AggregateExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.schema.clone()
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        let rows = self.input.execute(db)?;
+        if !self.rollup {
+            return Self::aggregate_rows(&self.group_expr, &self.aggr_expr, &self.schema, rows, db);
+        }
+        // Keys past the prefix become a NULL literal, which puts every row
+        // of the prefix group in one group
+        use crate::query::logical::Expr;
+        let mut output = Vec::new();
+        for kept in (0..=self.group_expr.len()).rev() {
+            let keys: Vec<Expr> = self
+                .group_expr
+                .iter()
+                .enumerate()
+                .map(|(i, key)| {
+                    if i < kept {
+                        key.clone()
+                    } else {
+                        Expr::Literal(crate::core::value::Value::Null)
+                    }
+                })
+                .collect();
+            output.extend(Self::aggregate_rows(
+                &keys,
+                &self.aggr_expr,
+                &self.schema,
+                rows.clone(),
+                db,
+            )?);
+        }
+        Ok(output)
+    }
+}
+
+impl AggregateExec {
+    /// Hash aggregation of materialized rows
+    fn aggregate_rows(
+        group_expr: &[crate::query::logical::Expr],
+        aggr_expr: &[crate::query::logical::Expr],
+        schema: &Arc<Schema>,
+        rows: Vec<Tuple>,
+        db: &TensorDb,
+    ) -> Result<Vec<Tuple>, EngineError> {
+        // If no rows and no group by, return empty result set
+        // (Aggregations on empty sets typically return no rows, not NULL rows)
+        if rows.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // If no group by, global aggregation (1 group)
+        // If group by, hash aggregation
+
+        use crate::core::config::NonFinitePolicy;
+        use crate::core::value::Value;
+        let non_finite = db.config.execution.non_finite;
+        use std::collections::HashMap;
+
+        // Map GroupKey -> Accumulators
+        // GroupKey is Vec<Value>
+        type GroupKey = Vec<Value>;
+        type Accumulators = Vec<Value>; // Accumulator state for SUM, COUNT, MIN, MAX
+
+        // Separate tracking for AVG: (sum, count) pairs for each AVG aggregate
+        // Indexed by position in aggr_expr
+        type AvgAccumulators = Vec<(Value, usize)>; // (sum, count) for AVG
+
+        let mut groups: HashMap<GroupKey, (Accumulators, AvgAccumulators, Vec<ScalarAccumulator>)> =
+            HashMap::new();
+
+        // Large inputs grouped by low-cardinality String columns are split by
+        // dictionary code up front, so each group's key is hashed only once
+        let large = rows.len() >= db.config.execution.columnar_threshold;
+        let dictionary = large
+            .then(|| crate::query::columnar::dictionary_groups(group_expr, &rows))
+            .flatten();
+        let runs: Vec<(GroupKey, Vec<Tuple>)> = match dictionary {
+            Some((keys, group_of_row)) => {
+                let mut runs: Vec<_> = keys.into_iter().map(|key| (key, Vec::new())).collect();
+                for (row, group) in rows.into_iter().zip(group_of_row) {
+                    runs[group].1.push(row);
+                }
+                runs
+            }
+            None => rows
+                .into_iter()
+                .map(|row| {
+                    let key: GroupKey = group_expr
+                        .iter()
+                        .map(|expr| evaluate_expression(expr, &row))
+                        .collect();
+                    (key, vec![row])
+                })
+                .collect(),
+        };
+
+        // 1. Initialize groups
+        for (key, run) in runs {
+            let row = &run[0];
+            let (accs, avg_accs, stats) = groups.entry(key).or_insert_with(|| {
+                // Init accumulators
+                let mut regular_accs = Vec::new();
+                let mut avg_accumulators = Vec::new();
+                let mut stat_accumulators = Vec::new();
+
+                for expr in aggr_expr {
+                    match expr {
+                        crate::query::logical::Expr::AggregateExpr { func, expr: inner } => {
+                            match func {
+                                crate::query::logical::AggregateFunction::Count => {
+                                    regular_accs.push(Value::Int(0));
+                                    avg_accumulators.push((Value::Null, 0)); // Placeholder
+                                }
+                                crate::query::logical::AggregateFunction::Sum => {
+                                    let val = evaluate_expression(inner, row);
+                                    if let Value::Vector(v) = val {
+                                        regular_accs.push(Value::Vector(vec![0.0; v.len()]));
+                                    } else if let Value::Matrix(m) = val {
+                                        // Zero matrix
+                                        if m.is_empty() {
+                                            regular_accs.push(Value::Matrix(vec![]));
+                                        } else {
+                                            let r = m.len();
+                                            let c = m[0].len();
+                                            regular_accs.push(Value::Matrix(vec![vec![0.0; c]; r]));
                                         }
-                                        _ => {}
+                                    } else {
+                                        regular_accs.push(Value::Int(0));
                                     }
+                                    avg_accumulators.push((Value::Null, 0)); // Placeholder
+                                }
+                                crate::query::logical::AggregateFunction::Min => {
+                                    regular_accs.push(Value::Null);
+                                    avg_accumulators.push((Value::Null, 0)); // Placeholder
+                                }
+                                crate::query::logical::AggregateFunction::Max => {
+                                    regular_accs.push(Value::Null);
+                                    avg_accumulators.push((Value::Null, 0)); // Placeholder
                                 }
-                                Value::Vector(ref mut sum_vec) => {
-                                    if let Value::Vector(v) = &val {
+                                crate::query::logical::AggregateFunction::Avg => {
+                                    // For AVG, initialize sum based on first value type
+                                    let val = evaluate_expression(inner, row);
+                                    let initial_sum = if let Value::Vector(v) = val {
+                                        Value::Vector(vec![0.0; v.len()])
+                                    } else if let Value::Matrix(m) = val {
+                                        if m.is_empty() {
+                                            Value::Matrix(vec![])
+                                        } else {
+                                            let r = m.len();
+                                            let c = m[0].len();
+                                            Value::Matrix(vec![vec![0.0; c]; r])
+                                        }
+                                    } else {
+                                        Value::Float(0.0)
+                                    };
+                                    avg_accumulators.push((initial_sum, 0));
+                                    regular_accs.push(Value::Null); // Placeholder, will be replaced with computed avg
+                                }
+                                _ => {
+                                    // Statistical aggregates live in the scalar accumulator
+                                    regular_accs.push(Value::Null);
+                                    avg_accumulators.push((Value::Null, 0));
+                                }
+                            }
+                            stat_accumulators.push(ScalarAccumulator::new(func));
+                        }
+                        _ => {
+                            regular_accs.push(Value::Null);
+                            avg_accumulators.push((Value::Null, 0));
+                            stat_accumulators.push(ScalarAccumulator::default());
+                        }
+                    }
+                }
+
+                (regular_accs, avg_accumulators, stat_accumulators)
+            });
+
+            // Update accumulators
+            for row in &run {
+                for (i, expr) in aggr_expr.iter().enumerate() {
+                    if let crate::query::logical::Expr::AggregateExpr {
+                        func,
+                        expr: inner_expr,
+                    } = expr
+                    {
+                        // Eval inner expr
+                        let val = evaluate_expression(inner_expr, row);
+                        if val.is_non_finite() {
+                            match non_finite {
+                                NonFinitePolicy::Propagate => {}
+                                // Left out entirely, so AVG does not count it either
+                                NonFinitePolicy::Skip => continue,
+                                NonFinitePolicy::Error => {
+                                    return Err(EngineError::InvalidOp(format!(
+                                        "{} met a non-finite value: {}",
+                                        expr.output_name(),
+                                        val
+                                    )))
+                                }
+                            }
+                        }
+
+                        match func {
+                            crate::query::logical::AggregateFunction::Count => {
+                                if let Value::Int(c) = accs[i] {
+                                    accs[i] = Value::Int(c + 1);
+                                }
+                            }
+                            crate::query::logical::AggregateFunction::Sum => {
+                                match (&mut accs[i], &val) {
+                                    (Value::Int(sum), Value::Int(v)) => {
+                                        // Promote to Float rather than wrap on overflow
+                                        accs[i] = match sum.checked_add(*v) {
+                                            Some(total) => Value::Int(total),
+                                            None => Value::Float(*sum as f32 + *v as f32),
+                                        };
+                                    }
+                                    (Value::Float(ref mut sum), Value::Float(v)) => *sum += v,
+                                    (Value::Int(sum), Value::Float(v)) => {
+                                        let new_val = *sum as f32 + v;
+                                        accs[i] = Value::Float(new_val);
+                                    }
+                                    (Value::Float(ref mut sum), Value::Int(v)) => *sum += *v as f32,
+                                    (Value::Vector(sum_vec), Value::Vector(v)) => {
                                         if sum_vec.len() == v.len() {
-                                            for (s, val) in sum_vec.iter_mut().zip(v.iter()) {
-                                                *s += val;
+                                            for (opt, val) in sum_vec.iter_mut().zip(v.iter()) {
+                                                *opt += val;
                                             }
                                         }
                                     }
-                                }
-                                Value::Matrix(ref mut sum_mat) => {
-                                    if let Value::Matrix(v) = &val {
+                                    (Value::Matrix(sum_mat), Value::Matrix(v)) => {
                                         // Element-wise sum
                                         if sum_mat.len() == v.len()
                                             && !sum_mat.is_empty()
@@ -463,117 +1203,1193 @@ impl PhysicalPlan for AggregateExec {
                                             }
                                         }
                                     }
+                                    _ => {}
                                 }
-                                _ => {
-                                    // Initialize with first value
-                                    *sum_ref = val.clone();
+                            }
+                            crate::query::logical::AggregateFunction::Avg => {
+                                // Track sum and count for AVG
+                                let (sum_ref, count_ref) = &mut avg_accs[i];
+                                *count_ref += 1;
+
+                                // Add to sum - need to handle type conversions
+                                match sum_ref {
+                                    Value::Float(ref mut sum) => match &val {
+                                        Value::Int(v) => *sum += *v as f32,
+                                        Value::Float(v) => *sum += v,
+                                        _ => {}
+                                    },
+                                    Value::Int(ref mut sum) => {
+                                        match &val {
+                                            Value::Int(v) => {
+                                                // Convert to Float for precision
+                                                *sum_ref = Value::Float(*sum as f32 + *v as f32);
+                                            }
+                                            Value::Float(v) => {
+                                                *sum_ref = Value::Float(*sum as f32 + v);
+                                            }
+                                            _ => {}
+                                        }
+                                    }
+                                    Value::Vector(ref mut sum_vec) => {
+                                        if let Value::Vector(v) = &val {
+                                            if sum_vec.len() == v.len() {
+                                                for (s, val) in sum_vec.iter_mut().zip(v.iter()) {
+                                                    *s += val;
+                                                }
+                                            }
+                                        }
+                                    }
+                                    Value::Matrix(ref mut sum_mat) => {
+                                        if let Value::Matrix(v) = &val {
+                                            // Element-wise sum
+                                            if sum_mat.len() == v.len()
+                                                && !sum_mat.is_empty()
+                                                && sum_mat[0].len() == v[0].len()
+                                            {
+                                                for i in 0..sum_mat.len() {
+                                                    for j in 0..sum_mat[i].len() {
+                                                        sum_mat[i][j] += v[i][j];
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                    _ => {
+                                        // Initialize with first value
+                                        *sum_ref = val.clone();
+                                    }
                                 }
                             }
-                        }
-                        crate::query::logical::AggregateFunction::Max => {
-                            match (&mut accs[i], &val) {
-                                (Value::Null, _) => accs[i] = val.clone(),
-                                (current, v) if !v.is_null() => {
-                                    // Handle Vector element-wise MAX? Or Magnitude?
-                                    // User said "element-wise aggregation".
-                                    // MAX([1, 5], [2, 3]) -> [2, 5].
-                                    match (current, v) {
+                            crate::query::logical::AggregateFunction::Max => {
+                                match (&mut accs[i], &val) {
+                                    (Value::Null, _) => accs[i] = val.clone(),
+                                    (current, v) if !v.is_null() => {
+                                        // Handle Vector element-wise MAX? Or Magnitude?
+                                        // User said "element-wise aggregation".
+                                        // MAX([1, 5], [2, 3]) -> [2, 5].
+                                        match (current, v) {
+                                            (Value::Vector(curr_vec), Value::Vector(v_vec)) => {
+                                                if curr_vec.len() == v_vec.len() {
+                                                    for (c, n) in
+                                                        curr_vec.iter_mut().zip(v_vec.iter())
+                                                    {
+                                                        if *n > *c {
+                                                            *c = *n;
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            (c, n) => {
+                                                if let Some(std::cmp::Ordering::Greater) =
+                                                    n.compare(c)
+                                                {
+                                                    *c = n.clone();
+                                                }
+                                            }
+                                        }
+                                    }
+                                    _ => {}
+                                }
+                            }
+                            crate::query::logical::AggregateFunction::Min => {
+                                match (&mut accs[i], &val) {
+                                    (Value::Null, _) => accs[i] = val.clone(),
+                                    (current, v) if !v.is_null() => match (current, v) {
                                         (Value::Vector(curr_vec), Value::Vector(v_vec)) => {
                                             if curr_vec.len() == v_vec.len() {
                                                 for (c, n) in curr_vec.iter_mut().zip(v_vec.iter())
                                                 {
-                                                    if *n > *c {
+                                                    if *n < *c {
                                                         *c = *n;
                                                     }
                                                 }
                                             }
                                         }
                                         (c, n) => {
-                                            if let Some(std::cmp::Ordering::Greater) = n.compare(c)
-                                            {
+                                            if let Some(std::cmp::Ordering::Less) = n.compare(c) {
                                                 *c = n.clone();
                                             }
                                         }
-                                    }
+                                    },
+                                    _ => {}
                                 }
-                                _ => {}
                             }
+                            _ => stats[i].add(&val),
                         }
-                        crate::query::logical::AggregateFunction::Min => {
-                            match (&mut accs[i], &val) {
-                                (Value::Null, _) => accs[i] = val.clone(),
-                                (current, v) if !v.is_null() => match (current, v) {
-                                    (Value::Vector(curr_vec), Value::Vector(v_vec)) => {
-                                        if curr_vec.len() == v_vec.len() {
-                                            for (c, n) in curr_vec.iter_mut().zip(v_vec.iter()) {
-                                                if *n < *c {
-                                                    *c = *n;
-                                                }
-                                            }
-                                        }
-                                    }
-                                    (c, n) => {
-                                        if let Some(std::cmp::Ordering::Less) = n.compare(c) {
-                                            *c = n.clone();
-                                        }
-                                    }
-                                },
-                                _ => {}
-                            }
+                    }
+                }
+            }
+        }
+
+        // Output rows - compute AVG from sum/count before outputting
+        let mut output_rows = Vec::with_capacity(groups.len());
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        if db.is_deterministic() {
+            // HashMap order varies between runs; sort groups by key instead
+            groups.sort_by(|(a, _), (b, _)| {
+                a.iter()
+                    .zip(b)
+                    .map(|(x, y)| x.total_cmp(y))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+        for (key, (accs, avg_accs, stats)) in groups {
+            let mut values = key; // Group keys first
+
+            // Build final accumulator values, computing AVG where needed
+            let mut final_accs = Vec::new();
+            for (i, expr) in aggr_expr.iter().enumerate() {
+                if let crate::query::logical::Expr::AggregateExpr { func, .. } = expr {
+                    if matches!(func, crate::query::logical::AggregateFunction::Avg) {
+                        // Compute average: sum / count
+                        let (sum, count) = &avg_accs[i];
+                        if *count > 0 {
+                            let avg = match sum {
+                                Value::Float(s) => Value::Float(*s / *count as f32),
+                                Value::Int(s) => Value::Float(*s as f32 / *count as f32),
+                                Value::Vector(v) => {
+                                    Value::Vector(v.iter().map(|x| x / *count as f32).collect())
+                                }
+                                Value::Matrix(m) => Value::Matrix(
+                                    m.iter()
+                                        .map(|row| row.iter().map(|x| x / *count as f32).collect())
+                                        .collect(),
+                                ),
+                                _ => Value::Null,
+                            };
+                            final_accs.push(avg);
+                        } else {
+                            final_accs.push(Value::Null);
                         }
+                    } else if ScalarAccumulator::is_statistical(func) {
+                        final_accs.push(stats[i].finish(func));
+                    } else {
+                        final_accs.push(accs[i].clone());
+                    }
+                } else {
+                    final_accs.push(accs[i].clone());
+                }
+            }
+
+            values.extend(final_accs); // Then aggregates
+            output_rows.push(values);
+        }
+
+        check_finite_aggregates(schema, &output_rows, group_expr.len(), db)?;
+        tuples_with_promotion(schema, output_rows)
+    }
+}
+
+/// Vectorized global aggregation (no GROUP BY) over a columnar batch of the
+/// aggregated columns
+#[derive(Debug)]
+pub struct ColumnarAggregateExec {
+    pub input: Box<dyn PhysicalPlan>,
+    pub aggr_expr: Vec<crate::query::logical::Expr>,
+    pub schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for ColumnarAggregateExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.schema.clone()
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        use crate::core::config::NonFinitePolicy;
+        use crate::query::columnar::{aggregate_batch, ColumnBatch};
+
+        let rows = self.input.execute(db)?;
+        if rows.is_empty() {
+            return Ok(vec![]);
+        }
+        // COUNT never reads its argument, so any column type may be counted
+        let mut columns = Vec::new();
+        let read = self.aggr_expr.iter().filter(|expr| {
+            !matches!(
+                expr,
+                crate::query::logical::Expr::AggregateExpr {
+                    func: crate::query::logical::AggregateFunction::Count,
+                    ..
+                }
+            )
+        });
+        for expr in read {
+            for name in expr.columns() {
+                if !columns.contains(&name) {
+                    columns.push(name);
+                }
+            }
+        }
+        // The kernels propagate NaN and Inf; other policies take the row path
+        let propagate = db.config.execution.non_finite == NonFinitePolicy::Propagate;
+        let values = ColumnBatch::from_rows(&rows, &self.input.schema(), &columns)
+            .filter(|batch| propagate || !batch.has_non_finite())
+            .and_then(|batch| aggregate_batch(&self.aggr_expr, &batch));
+        match values {
+            Some(values) => {
+                let values = vec![values];
+                check_finite_aggregates(&self.schema, &values, 0, db)?;
+                tuples_with_promotion(&self.schema, values)
+            }
+            None => AggregateExec::aggregate_rows(&[], &self.aggr_expr, &self.schema, rows, db),
+        }
+    }
+}
+
+/// Under `[execution] non_finite = "error"`, fail when an aggregate (the
+/// columns after the `keys` group keys) overflowed to Inf or came out NaN
+fn check_finite_aggregates(
+    schema: &Schema,
+    rows: &[Vec<crate::core::value::Value>],
+    keys: usize,
+    db: &TensorDb,
+) -> Result<(), EngineError> {
+    if db.config.execution.non_finite != crate::core::config::NonFinitePolicy::Error {
+        return Ok(());
+    }
+    for row in rows {
+        if let Some(i) = (keys..row.len()).find(|&i| row[i].is_non_finite()) {
+            return Err(EngineError::InvalidOp(format!(
+                "{} is not finite: {}",
+                schema.fields[i].name, row[i]
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Build output tuples, widening Int columns to Float when checked Int
+/// arithmetic overflowed and promoted some of their values. Other values are
+/// implicitly coerced to their column's type (`Value::coerce_to`).
+fn tuples_with_promotion(
+    schema: &Arc<Schema>,
+    rows: Vec<Vec<crate::core::value::Value>>,
+) -> Result<Vec<Tuple>, EngineError> {
+    use crate::core::value::{Value, ValueType};
+
+    let promoted: Vec<usize> = (0..schema.len())
+        .filter(|&i| {
+            schema.fields[i].value_type == ValueType::Int
+                && rows.iter().any(|r| matches!(r[i], Value::Float(_)))
+        })
+        .collect();
+
+    let schema = if promoted.is_empty() {
+        schema.clone()
+    } else {
+        let mut fields = schema.fields.clone();
+        for &i in &promoted {
+            fields[i].value_type = ValueType::Float;
+        }
+        Arc::new(Schema::new(fields))
+    };
+
+    rows.into_iter()
+        .map(|mut values| {
+            for (value, field) in values.iter_mut().zip(&schema.fields) {
+                if !value.matches_type(&field.value_type) {
+                    if let Some(coerced) = value.coerce_to(&field.value_type) {
+                        *value = coerced;
+                    }
+                }
+            }
+            Tuple::new(schema.clone(), values).map_err(EngineError::InvalidOp)
+        })
+        .collect()
+}
+
+/// Window Executor: appends one computed column per window expression
+#[derive(Debug)]
+pub struct WindowExec {
+    pub input: Box<dyn PhysicalPlan>,
+    pub window_expr: Vec<crate::query::logical::Expr>,
+    pub schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for WindowExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.schema.clone()
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        use crate::query::logical::Expr;
+
+        let rows = self.input.execute(db)?;
+        let input_schema = self.input.schema();
+
+        // One output column per expression, in input row order
+        let mut columns = Vec::with_capacity(self.window_expr.len());
+        for expr in &self.window_expr {
+            let column = match expr {
+                Expr::WindowFunction {
+                    func,
+                    args,
+                    partition_by,
+                    order_by,
+                    ascending,
+                } => {
+                    let col_idx = input_schema.get_field_index(order_by).ok_or_else(|| {
+                        EngineError::InvalidOp(format!(
+                            "Column not found for window ORDER BY: {}",
+                            order_by
+                        ))
+                    })?;
+                    let partition_cols = partition_by
+                        .iter()
+                        .map(|name| {
+                            input_schema.get_field_index(name).ok_or_else(|| {
+                                EngineError::InvalidOp(format!(
+                                    "Column not found for window PARTITION BY: {}",
+                                    name
+                                ))
+                            })
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let mut column = vec![crate::core::value::Value::Null; rows.len()];
+                    for partition in partitions(&rows, &partition_cols) {
+                        evaluate_window_function(
+                            func,
+                            args,
+                            &rows,
+                            partition,
+                            col_idx,
+                            *ascending,
+                            &mut column,
+                        )?;
+                    }
+                    column
+                }
+                other => rows
+                    .iter()
+                    .map(|row| evaluate_expression(other, row))
+                    .collect(),
+            };
+            columns.push(column);
+        }
+
+        let mut output_rows = Vec::with_capacity(rows.len());
+        for (i, row) in rows.into_iter().enumerate() {
+            let mut values = row.values;
+            for column in &columns {
+                values.push(column[i].clone());
+            }
+            output_rows.push(values);
+        }
+        tuples_with_promotion(&self.schema, output_rows)
+    }
+}
+
+/// Unnest Executor: repeats each input row once per element of its list,
+/// with the element appended
+#[derive(Debug)]
+pub struct UnnestExec {
+    pub input: Box<dyn PhysicalPlan>,
+    /// The `UNNEST(list)` call
+    pub expr: crate::query::logical::Expr,
+    pub schema: Arc<Schema>,
+}
+
+impl PhysicalPlan for UnnestExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.schema.clone()
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        use crate::core::value::Value;
+        use crate::query::logical::Expr;
+
+        let list_expr = match &self.expr {
+            Expr::ScalarFunction { args, .. } if args.len() == 1 => &args[0],
+            other => {
+                return Err(EngineError::InvalidOp(format!(
+                    "Expected UNNEST(list), got {}",
+                    other.output_name()
+                )))
+            }
+        };
+
+        let mut output_rows = Vec::new();
+        for row in self.input.execute(db)? {
+            let elements = match evaluate_expression(list_expr, &row) {
+                Value::List(items) => items,
+                Value::Vector(v) => v.into_iter().map(Value::Float).collect(),
+                Value::Null => continue,
+                other => {
+                    return Err(EngineError::InvalidOp(format!(
+                        "UNNEST expects a list, got {}",
+                        other.value_type()
+                    )))
+                }
+            };
+            for element in elements {
+                let mut values = row.values.clone();
+                values.push(element);
+                output_rows.push(values);
+            }
+        }
+        tuples_with_promotion(&self.schema, output_rows)
+    }
+}
+
+/// Row positions grouped by the values of `cols`, partitions in order of
+/// first appearance
+fn partitions(rows: &[Tuple], cols: &[usize]) -> Vec<Vec<usize>> {
+    if cols.is_empty() {
+        return vec![(0..rows.len()).collect()];
+    }
+    let mut index: HashMap<Vec<crate::core::value::Value>, usize> = HashMap::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let key = cols.iter().map(|&c| row.values[c].clone()).collect();
+        let slot = *index.entry(key).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[slot].push(i);
+    }
+    groups
+}
+
+/// Compute a window function over the rows of one partition (`order` holds
+/// their positions), ordered by the column at `col_idx`. Writes each row's
+/// value to `result` at its original position.
+fn evaluate_window_function(
+    func: &crate::query::logical::WindowFunction,
+    args: &[crate::query::logical::Expr],
+    rows: &[Tuple],
+    mut order: Vec<usize>,
+    col_idx: usize,
+    ascending: bool,
+    result: &mut [crate::core::value::Value],
+) -> Result<(), EngineError> {
+    use crate::core::value::Value;
+    use crate::query::logical::{Expr, WindowFrame, WindowFunction};
+    use std::cmp::Ordering;
+
+    let compare = |a: usize, b: usize| -> Ordering {
+        let cmp = rows[a].values[col_idx]
+            .compare(&rows[b].values[col_idx])
+            .unwrap_or(Ordering::Equal);
+        if ascending {
+            cmp
+        } else {
+            cmp.reverse()
+        }
+    };
+
+    // Stable sort of row positions, so ties keep input order
+    order.sort_by(|&a, &b| compare(a, b));
+    let total = order.len();
+    // Position of the first row of each run of ORDER BY ties
+    let peer_start = |pos: usize| pos > 0 && compare(order[pos - 1], order[pos]) != Ordering::Equal;
+
+    match func {
+        WindowFunction::Ntile => {
+            let buckets = match args.first() {
+                Some(Expr::Literal(Value::Int(n))) if *n > 0 => *n as usize,
+                _ => {
+                    return Err(EngineError::InvalidOp(
+                        "NTILE expects a positive integer bucket count".into(),
+                    ))
+                }
+            };
+            // The first (total % buckets) buckets get one extra row
+            let base = total / buckets;
+            let extra = total % buckets;
+            let mut bucket = 1;
+            let mut filled = 0;
+            for &row_idx in &order {
+                let capacity = if bucket <= extra { base + 1 } else { base };
+                if filled == capacity {
+                    bucket += 1;
+                    filled = 0;
+                }
+                result[row_idx] = Value::Int(bucket as i64);
+                filled += 1;
+            }
+        }
+        WindowFunction::PercentRank | WindowFunction::Rank => {
+            let mut rank = 1;
+            for (pos, &row_idx) in order.iter().enumerate() {
+                if peer_start(pos) {
+                    rank = pos + 1;
+                }
+                result[row_idx] = if *func == WindowFunction::Rank {
+                    Value::Int(rank as i64)
+                } else if total > 1 {
+                    Value::Float((rank - 1) as f32 / (total - 1) as f32)
+                } else {
+                    Value::Float(0.0)
+                };
+            }
+        }
+        WindowFunction::RowNumber => {
+            for (pos, &row_idx) in order.iter().enumerate() {
+                result[row_idx] = Value::Int(pos as i64 + 1);
+            }
+        }
+        WindowFunction::DenseRank => {
+            let mut rank = 1;
+            for (pos, &row_idx) in order.iter().enumerate() {
+                if peer_start(pos) {
+                    rank += 1;
+                }
+                result[row_idx] = Value::Int(rank);
+            }
+        }
+        WindowFunction::Lag | WindowFunction::Lead => {
+            let (Some(expr), offset) = (args.first(), args.get(1)) else {
+                return Err(EngineError::InvalidOp(format!(
+                    "{} expects an expression",
+                    func.name()
+                )));
+            };
+            let offset = match offset {
+                None => 1,
+                Some(Expr::Literal(Value::Int(n))) if *n >= 0 => *n as usize,
+                Some(_) => {
+                    return Err(EngineError::InvalidOp(format!(
+                        "{} offset must be a non-negative integer",
+                        func.name()
+                    )))
+                }
+            };
+            for (pos, &row_idx) in order.iter().enumerate() {
+                let source = if *func == WindowFunction::Lag {
+                    pos.checked_sub(offset)
+                } else {
+                    Some(pos + offset).filter(|&p| p < total)
+                };
+                result[row_idx] = match (source, args.get(2)) {
+                    (Some(p), _) => evaluate_expression(expr, &rows[order[p]]),
+                    (None, Some(default)) => evaluate_expression(default, &rows[row_idx]),
+                    (None, None) => Value::Null,
+                };
+            }
+        }
+        WindowFunction::Aggregate(agg, frame) => {
+            let values: Vec<Value> = match args.first() {
+                Some(expr) => order
+                    .iter()
+                    .map(|&i| evaluate_expression(expr, &rows[i]))
+                    .collect(),
+                // COUNT(*)
+                None => vec![Value::Int(1); total],
+            };
+            // Last position of each row's run of ties, for the running frame
+            let mut peer_end = vec![total.saturating_sub(1); total];
+            for pos in (0..total.saturating_sub(1)).rev() {
+                if peer_start(pos + 1) {
+                    peer_end[pos] = pos;
+                } else {
+                    peer_end[pos] = peer_end[pos + 1];
+                }
+            }
+
+            // Frames that keep their start and grow (running totals) are
+            // extended incrementally; others are recomputed
+            let mut acc = ScalarAccumulator::new(agg);
+            let mut covered: Option<(usize, usize)> = None;
+            for (pos, &row_idx) in order.iter().enumerate() {
+                let (lo, hi) = match frame {
+                    WindowFrame::Running => (0, peer_end[pos]),
+                    WindowFrame::Rows {
+                        preceding,
+                        following,
+                    } => (
+                        preceding.map_or(0, |p| pos.saturating_sub(p)),
+                        following.map_or(total - 1, |f| (pos + f).min(total - 1)),
+                    ),
+                };
+                let from = match covered {
+                    Some((start, end)) if start == lo && end <= hi => end + 1,
+                    _ => {
+                        acc = ScalarAccumulator::new(agg);
+                        lo
                     }
+                };
+                for value in &values[from..=hi] {
+                    acc.add(value);
                 }
+                covered = Some((lo, hi));
+                result[row_idx] = acc.finish(agg);
             }
         }
+    }
 
-        // Output rows - compute AVG from sum/count before outputting
-        let mut output_rows = Vec::new();
-        for (key, (accs, avg_accs)) in groups {
-            let mut values = key; // Group keys first
+    Ok(())
+}
 
-            // Build final accumulator values, computing AVG where needed
-            let mut final_accs = Vec::new();
-            for (i, expr) in self.aggr_expr.iter().enumerate() {
-                if let crate::query::logical::Expr::AggregateExpr { func, .. } = expr {
-                    if matches!(func, crate::query::logical::AggregateFunction::Avg) {
-                        // Compute average: sum / count
-                        let (sum, count) = &avg_accs[i];
-                        if *count > 0 {
-                            let avg = match sum {
-                                Value::Float(s) => Value::Float(*s / *count as f32),
-                                Value::Int(s) => Value::Float(*s as f32 / *count as f32),
-                                Value::Vector(v) => {
-                                    Value::Vector(v.iter().map(|x| x / *count as f32).collect())
-                                }
-                                Value::Matrix(m) => Value::Matrix(
-                                    m.iter()
-                                        .map(|row| row.iter().map(|x| x / *count as f32).collect())
-                                        .collect(),
-                                ),
-                                _ => Value::Null,
-                            };
-                            final_accs.push(avg);
-                        } else {
-                            final_accs.push(Value::Null);
-                        }
-                    } else {
-                        final_accs.push(accs[i].clone());
-                    }
-                } else {
-                    final_accs.push(accs[i].clone());
+/// Running state of an aggregate over scalar values; NULLs are skipped.
+/// Used for window frames and for the statistical GROUP BY aggregates.
+#[derive(Default)]
+struct ScalarAccumulator {
+    count: usize,
+    int_sum: Option<i64>,
+    float_sum: f32,
+    is_float: bool,
+    min: Option<crate::core::value::Value>,
+    max: Option<crate::core::value::Value>,
+    /// Welford's running mean and sum of squared deviations of the numeric values
+    numeric: usize,
+    mean: f64,
+    m2: f64,
+    /// Kept only for MEDIAN / PERCENTILE
+    values: Option<Vec<f64>>,
+    /// Kept only for COUNT(DISTINCT)
+    distinct: Option<std::collections::HashSet<crate::core::value::Value>>,
+    /// Kept only for APPROX_COUNT_DISTINCT
+    hll: Option<crate::core::sketch::HyperLogLog>,
+    /// Kept only for APPROX_QUANTILE
+    digest: Option<crate::core::sketch::TDigest>,
+}
+
+impl ScalarAccumulator {
+    fn new(func: &crate::query::logical::AggregateFunction) -> Self {
+        use crate::query::logical::AggregateFunction;
+
+        let mut acc = Self::default();
+        match func {
+            AggregateFunction::Median | AggregateFunction::Percentile(_) => {
+                acc.values = Some(Vec::new())
+            }
+            AggregateFunction::CountDistinct => acc.distinct = Some(Default::default()),
+            AggregateFunction::ApproxCountDistinct => acc.hll = Some(Default::default()),
+            AggregateFunction::ApproxQuantile(_) => acc.digest = Some(Default::default()),
+            _ => {}
+        }
+        acc
+    }
+
+    /// Aggregates that AggregateExec computes with this accumulator
+    fn is_statistical(func: &crate::query::logical::AggregateFunction) -> bool {
+        use crate::query::logical::AggregateFunction;
+
+        matches!(
+            func,
+            AggregateFunction::Variance
+                | AggregateFunction::Stddev
+                | AggregateFunction::Median
+                | AggregateFunction::Percentile(_)
+                | AggregateFunction::CountDistinct
+                | AggregateFunction::ApproxCountDistinct
+                | AggregateFunction::ApproxQuantile(_)
+        )
+    }
+
+    fn add(&mut self, value: &crate::core::value::Value) {
+        use crate::core::value::Value;
+        use std::cmp::Ordering;
+
+        if value.is_null() {
+            return;
+        }
+        self.count += 1;
+        if let Some(distinct) = &mut self.distinct {
+            distinct.insert(value.clone());
+        }
+        if let Some(hll) = &mut self.hll {
+            hll.add(value);
+        }
+        if let Some(x) = value.as_float() {
+            let x = x as f64;
+            self.numeric += 1;
+            let delta = x - self.mean;
+            self.mean += delta / self.numeric as f64;
+            self.m2 += delta * (x - self.mean);
+            if let Some(values) = &mut self.values {
+                values.push(x);
+            }
+            if let Some(digest) = &mut self.digest {
+                digest.add(x);
+            }
+        }
+        match value {
+            Value::Int(v) if !self.is_float => {
+                // Promote to Float rather than wrap on overflow
+                match self.int_sum.unwrap_or(0).checked_add(*v) {
+                    Some(sum) => self.int_sum = Some(sum),
+                    None => self.is_float = true,
                 }
+                self.float_sum += *v as f32;
+            }
+            _ => {
+                self.is_float = true;
+                self.float_sum += value.as_float().unwrap_or(0.0);
             }
+        }
+        if self
+            .min
+            .as_ref()
+            .is_none_or(|m| value.compare(m) == Some(Ordering::Less))
+        {
+            self.min = Some(value.clone());
+        }
+        if self
+            .max
+            .as_ref()
+            .is_none_or(|m| value.compare(m) == Some(Ordering::Greater))
+        {
+            self.max = Some(value.clone());
+        }
+    }
 
-            values.extend(final_accs); // Then aggregates
-            output_rows.push(
-                Tuple::new(self.schema.clone(), values).map_err(|e| EngineError::InvalidOp(e))?,
-            );
+    fn finish(&self, func: &crate::query::logical::AggregateFunction) -> crate::core::value::Value {
+        use crate::core::value::Value;
+        use crate::query::logical::AggregateFunction;
+
+        match func {
+            AggregateFunction::Count => Value::Int(self.count as i64),
+            AggregateFunction::CountDistinct => {
+                Value::Int(self.distinct.as_ref().map_or(0, |d| d.len()) as i64)
+            }
+            AggregateFunction::ApproxCountDistinct => {
+                Value::Int(self.hll.as_ref().map_or(0, |h| h.estimate()) as i64)
+            }
+            _ if self.count == 0 => Value::Null,
+            AggregateFunction::Sum => match self.int_sum {
+                Some(sum) if !self.is_float => Value::Int(sum),
+                _ => Value::Float(self.float_sum),
+            },
+            AggregateFunction::Avg => Value::Float(self.float_sum / self.count as f32),
+            AggregateFunction::Min => self.min.clone().unwrap_or(Value::Null),
+            AggregateFunction::Max => self.max.clone().unwrap_or(Value::Null),
+            AggregateFunction::Variance | AggregateFunction::Stddev if self.numeric < 2 => {
+                Value::Null
+            }
+            AggregateFunction::Variance => {
+                Value::Float((self.m2 / (self.numeric - 1) as f64) as f32)
+            }
+            AggregateFunction::Stddev => {
+                Value::Float((self.m2 / (self.numeric - 1) as f64).sqrt() as f32)
+            }
+            AggregateFunction::Median => self.percentile(0.5),
+            AggregateFunction::Percentile(p) => self.percentile(*p as f64),
+            AggregateFunction::ApproxQuantile(q) => self
+                .digest
+                .as_ref()
+                .and_then(|d| d.quantile(*q as f64))
+                .map_or(Value::Null, |x| Value::Float(x as f32)),
         }
+    }
 
-        Ok(output_rows)
+    /// Linear interpolation between the two values closest to rank p * (n - 1)
+    fn percentile(&self, p: f64) -> crate::core::value::Value {
+        use crate::core::value::Value;
+
+        let mut values = match &self.values {
+            Some(values) if !values.is_empty() => values.clone(),
+            _ => return Value::Null,
+        };
+        values.sort_by(f64::total_cmp);
+        let rank = p.clamp(0.0, 1.0) * (values.len() - 1) as f64;
+        let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+        let value = values[lo] + (values[hi] - values[lo]) * (rank - lo as f64);
+        Value::Float(value as f32)
+    }
+}
+
+/// WHERE(cond, a, b): `a` where the condition holds and `b` where it is false
+/// or NULL, coerced to the type both branches share so the column keeps one type
+fn where_select(
+    args: &[crate::query::logical::Expr],
+    row: &crate::core::tuple::Tuple,
+) -> crate::core::value::Value {
+    use crate::core::value::Value;
+
+    if args.len() != 3 {
+        return Value::Null;
+    }
+    let (chosen, other) = if crate::query::planner::evaluate_expr(&args[0], row) {
+        (&args[1], &args[2])
+    } else {
+        (&args[2], &args[1])
+    };
+    let value = evaluate_expression(chosen, row);
+    let common = value
+        .value_type()
+        .common_type(&evaluate_expression(other, row).value_type());
+    common.and_then(|t| value.coerce_to(&t)).unwrap_or(value)
+}
+
+/// COALESCE(a, b, ...): the first argument that is not NULL
+fn coalesce(values: Vec<crate::core::value::Value>) -> crate::core::value::Value {
+    values
+        .into_iter()
+        .find(|v| !v.is_null())
+        .unwrap_or(crate::core::value::Value::Null)
+}
+
+/// CASE over evaluated `[c1, v1, c2, v2, ..., else]`: the value after the
+/// first condition that is true (not false or NULL), else the last value.
+/// As in WHERE, it is coerced to the type all branches share.
+fn case_select(mut values: Vec<crate::core::value::Value>) -> crate::core::value::Value {
+    use crate::core::value::{Value, ValueType};
+
+    let otherwise = values.pop().unwrap_or(Value::Null);
+    let common = values
+        .chunks_exact(2)
+        .map(|pair| &pair[1])
+        .chain([&otherwise])
+        .try_fold(ValueType::Null, |acc, v| acc.common_type(&v.value_type()));
+    let chosen = values
+        .chunks_exact(2)
+        .find(|pair| pair[0] == Value::Bool(true))
+        .map_or(otherwise, |pair| pair[1].clone());
+    common.and_then(|t| chosen.coerce_to(&t)).unwrap_or(chosen)
+}
+
+/// Apply a series kernel to a Vector argument; anything else gives NULL
+fn map_vector(
+    args: &[crate::core::value::Value],
+    series: impl Fn(&[f32]) -> Vec<f32>,
+) -> crate::core::value::Value {
+    use crate::core::value::Value;
+
+    match args.first() {
+        Some(Value::Vector(v)) => Value::Vector(series(v)),
+        _ => Value::Null,
+    }
+}
+
+/// A vector value as a rank-1 tensor, for the tensor kernels
+fn vector_tensor(v: &[f32]) -> crate::core::tensor::Tensor {
+    use crate::core::tensor::{Shape, Tensor, TensorId};
+
+    Tensor::new(TensorId(0), Shape::new(vec![v.len()]), v.to_vec()).expect("rank-1 shape")
+}
+
+/// Apply a two-vector kernel; NULL when an argument is not a vector or the
+/// kernel rejects them (different lengths, zero norm)
+fn vector_pair(
+    args: &[crate::core::value::Value],
+    kernel: fn(&crate::core::tensor::Tensor, &crate::core::tensor::Tensor) -> Result<f32, String>,
+) -> crate::core::value::Value {
+    use crate::core::value::Value;
+
+    match (args.first(), args.get(1)) {
+        (Some(Value::Vector(a)), Some(Value::Vector(b))) => {
+            kernel(&vector_tensor(a), &vector_tensor(b)).map_or(Value::Null, Value::Float)
+        }
+        _ => Value::Null,
+    }
+}
+
+/// A numeric argument through `f`; NULL when it is not a number or `f`
+/// rejects it
+fn float_function(
+    args: &[crate::core::value::Value],
+    f: impl Fn(f32) -> Option<f32>,
+) -> crate::core::value::Value {
+    use crate::core::value::Value;
+
+    match args.first().and_then(|v| v.as_float()) {
+        Some(x) => f(x).map_or(Value::Null, Value::Float),
+        None => Value::Null,
+    }
+}
+
+/// SUBSTR(string, start [, length]), counting characters from 1. A start
+/// before 1 shortens the length as in SQL.
+fn substr(args: &[crate::core::value::Value]) -> crate::core::value::Value {
+    use crate::core::value::Value;
+
+    let (Some(Value::String(s)), Some(Value::Int(start))) = (args.first(), args.get(1)) else {
+        return Value::Null;
+    };
+    let end = match args.get(2) {
+        None => i64::MAX,
+        Some(Value::Int(len)) if *len >= 0 => start.saturating_add(*len),
+        _ => return Value::Null,
+    };
+    let from = (*start).max(1);
+    let taken = end.saturating_sub(from).max(0);
+    Value::String(
+        s.chars()
+            .skip((from - 1) as usize)
+            .take(usize::try_from(taken).unwrap_or(usize::MAX))
+            .collect(),
+    )
+}
+
+/// HASH(expr [, salt]): the same value and salt always give the same digest,
+/// so hashed columns still join and group
+fn hash_value(args: &[crate::core::value::Value]) -> crate::core::value::Value {
+    use crate::core::value::Value;
+    use sha2::{Digest, Sha256};
+
+    let text = |value: &Value| match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let (Some(value), salt) = (args.first(), args.get(1)) else {
+        return Value::Null;
+    };
+    if value.is_null() || salt.is_some_and(Value::is_null) {
+        return Value::Null;
+    }
+    let mut hasher = Sha256::new();
+    if let Some(salt) = salt {
+        hasher.update(text(salt).as_bytes());
+    }
+    hasher.update(text(value).as_bytes());
+    Value::String(
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    )
+}
+
+/// MASK_EMAIL: `alice@example.com` -> `a***@example.com`; a string without
+/// a domain is masked entirely
+fn mask_email(email: &str) -> String {
+    match email.rsplit_once('@') {
+        Some((local, domain)) if !local.is_empty() => {
+            let first: String = local.chars().take(1).collect();
+            format!("{}***@{}", first, domain)
+        }
+        _ => "***".to_string(),
+    }
+}
+
+/// TRUNCATE_IP: `192.168.1.77` -> `192.168.1.0`, `2001:db8:85a3::7334` -> `2001:db8:85a3::`
+fn truncate_ip(address: &str) -> Option<String> {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    Some(match address.trim().parse::<IpAddr>().ok()? {
+        IpAddr::V4(ip) => Ipv4Addr::from(u32::from(ip) & 0xFFFF_FF00).to_string(),
+        IpAddr::V6(ip) => Ipv6Addr::from(u128::from(ip) & !((1u128 << 80) - 1)).to_string(),
+    })
+}
+
+/// ROUND(number [, digits]); integers are already whole
+fn round(args: &[crate::core::value::Value]) -> crate::core::value::Value {
+    use crate::core::value::Value;
+
+    let digits = match args.get(1) {
+        None => 0,
+        Some(Value::Int(d)) => (*d).clamp(-30, 30) as i32,
+        _ => return Value::Null,
+    };
+    match args.first() {
+        Some(Value::Int(i)) if digits >= 0 => Value::Int(*i),
+        Some(Value::Int(i)) => {
+            let scale = 10f64.powi(-digits);
+            Value::Int(((*i as f64 / scale).round() * scale) as i64)
+        }
+        Some(v) => match v.as_float() {
+            Some(x) => {
+                let scale = 10f64.powi(digits);
+                Value::Float(((x as f64 * scale).round() / scale) as f32)
+            }
+            None => Value::Null,
+        },
+        None => Value::Null,
+    }
+}
+
+/// WIDTH_BUCKET(value, min, max, count)
+/// Values below `min` fall in bucket 0 and values at or above `max` in bucket count + 1.
+fn width_bucket(args: &[crate::core::value::Value]) -> crate::core::value::Value {
+    use crate::core::value::Value;
+
+    if args.len() != 4 {
+        return Value::Null;
+    }
+    let (value, min, max, count) = match (
+        args[0].as_float(),
+        args[1].as_float(),
+        args[2].as_float(),
+        args[3].as_int(),
+    ) {
+        (Some(v), Some(lo), Some(hi), Some(n)) if n > 0 && hi > lo => (v, lo, hi, n),
+        _ => return Value::Null,
+    };
+
+    if value < min {
+        Value::Int(0)
+    } else if value >= max {
+        Value::Int(count + 1)
+    } else {
+        let bucket = ((value - min) / (max - min) * count as f32).floor() as i64 + 1;
+        Value::Int(bucket.min(count))
+    }
+}
+
+/// Upper bound on cached patterns; the cache is simply reset when exceeded.
+const REGEX_CACHE_CAPACITY: usize = 256;
+
+/// Compile a regex pattern, reusing previously compiled patterns so that
+/// row-by-row evaluation does not recompile the same pattern.
+pub(crate) fn cached_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Regex>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+
+    if let Some(re) = cache.lock().unwrap().get(pattern) {
+        return Ok(re.clone());
+    }
+
+    let re = Regex::new(pattern)?;
+    let mut cache = cache.lock().unwrap();
+    if cache.len() >= REGEX_CACHE_CAPACITY {
+        cache.clear();
+    }
+    cache.insert(pattern.to_string(), re.clone());
+    Ok(re)
+}
+
+/// REGEXP_MATCH(value, pattern)
+/// NULL inputs (or non-string arguments) yield NULL.
+fn regexp_match(args: &[crate::core::value::Value]) -> crate::core::value::Value {
+    use crate::core::value::Value;
+
+    match args {
+        [Value::String(text), Value::String(pattern)] => match cached_regex(pattern) {
+            Ok(re) => Value::Bool(re.is_match(text)),
+            Err(_) => Value::Null,
+        },
+        _ => Value::Null,
+    }
+}
+
+/// REGEXP_EXTRACT(value, pattern, group)
+/// Group 0 is the whole match; a missing match or group yields NULL.
+fn regexp_extract(args: &[crate::core::value::Value]) -> crate::core::value::Value {
+    use crate::core::value::Value;
+
+    let (text, pattern, group) = match args {
+        [Value::String(text), Value::String(pattern), Value::Int(group)] if *group >= 0 => {
+            (text, pattern, *group as usize)
+        }
+        _ => return Value::Null,
+    };
+    let re = match cached_regex(pattern) {
+        Ok(re) => re,
+        Err(_) => return Value::Null,
+    };
+
+    re.captures(text)
+        .and_then(|caps| caps.get(group))
+        .map(|m| Value::String(m.as_str().to_string()))
+        .unwrap_or(Value::Null)
+}
+
+/// SQL comparison: NULL (unknown) when either side is NULL or the two
+/// values do not compare, even after implicit coercion to a common type
+/// (e.g. a string against a timestamp), otherwise a Bool
+pub(crate) fn compare_values(
+    l: &crate::core::value::Value,
+    op: &str,
+    r: &crate::core::value::Value,
+) -> crate::core::value::Value {
+    use crate::core::value::Value;
+    use std::cmp::Ordering;
+
+    if l.is_null() || r.is_null() {
+        return Value::Null;
+    }
+    if op == "LIKE" || op == "NOT LIKE" {
+        return match (l, r) {
+            (Value::String(s), Value::String(p)) => {
+                Value::Bool(crate::query::planner::like_match(s, p) == (op == "LIKE"))
+            }
+            _ => Value::Null,
+        };
+    }
+    let ord = l.compare(r).or_else(|| {
+        let common = l.value_type().common_type(&r.value_type())?;
+        l.coerce_to(&common)?.compare(&r.coerce_to(&common)?)
+    });
+    let Some(ord) = ord else {
+        return Value::Null;
+    };
+    match op {
+        "=" => Value::Bool(ord == Ordering::Equal),
+        "!=" => Value::Bool(ord != Ordering::Equal),
+        ">" => Value::Bool(ord == Ordering::Greater),
+        "<" => Value::Bool(ord == Ordering::Less),
+        ">=" => Value::Bool(ord != Ordering::Less),
+        "<=" => Value::Bool(ord != Ordering::Greater),
+        _ => Value::Null,
     }
 }
 
+/// Field (String key) or array element (Int key) of a JSON document
+fn json_get(values: &[crate::core::value::Value]) -> Option<&serde_json::Value> {
+    use crate::core::value::Value;
+    match values {
+        [Value::Json(doc), Value::String(key)] => doc.get(key.as_str()),
+        [Value::Json(doc), Value::Int(i)] => usize::try_from(*i).ok().and_then(|i| doc.get(i)),
+        _ => None,
+    }
+}
+
+/// Element of a list (or vector) at a 1-based index, NULL when out of range
+fn element_at(values: &[crate::core::value::Value]) -> crate::core::value::Value {
+    use crate::core::value::Value;
+    let position = |i: i64, len: usize| usize::try_from(i - 1).ok().filter(|i| *i < len);
+    match values {
+        [Value::List(items), Value::Int(i)] => {
+            position(*i, items.len()).map_or(Value::Null, |i| items[i].clone())
+        }
+        [Value::Vector(v), Value::Int(i)] => {
+            position(*i, v.len()).map_or(Value::Null, |i| Value::Float(v[i]))
+        }
+        _ => Value::Null,
+    }
+}
+
+/// Int arithmetic that promotes to Float instead of wrapping on overflow.
+/// Division or remainder by zero is NULL.
+fn int_arithmetic(l: i64, op: &str, r: i64) -> crate::core::value::Value {
+    use crate::core::value::Value;
+    let (checked, approx) = match op {
+        "+" => (l.checked_add(r), l as f32 + r as f32),
+        "-" => (l.checked_sub(r), l as f32 - r as f32),
+        "*" => (l.checked_mul(r), l as f32 * r as f32),
+        "/" if r != 0 => (l.checked_div(r), l as f32 / r as f32),
+        "%" if r != 0 => (l.checked_rem(r), l as f32 % r as f32),
+        _ => return Value::Null,
+    };
+    checked.map_or(Value::Float(approx), Value::Int)
+}
+
+/// `ts` moved by a number of seconds; NULL when it leaves chrono's range
+fn shift_timestamp(ts: chrono::DateTime<chrono::Utc>, seconds: i64) -> crate::core::value::Value {
+    use crate::core::value::Value;
+    chrono::TimeDelta::try_seconds(seconds)
+        .and_then(|delta| ts.checked_add_signed(delta))
+        .map_or(Value::Null, Value::Timestamp)
+}
+
+/// DATE_TRUNC(unit, timestamp) for units from second to year
+fn date_trunc(args: &[crate::core::value::Value]) -> crate::core::value::Value {
+    use crate::core::value::Value;
+    use chrono::{Datelike, Timelike};
+
+    let (Some(Value::String(unit)), Some(Value::Timestamp(ts))) = (args.first(), args.get(1))
+    else {
+        return Value::Null;
+    };
+    let date = ts.date_naive();
+    let day = date.and_hms_opt(0, 0, 0);
+    let truncated = match unit.to_lowercase().as_str() {
+        "second" => ts.with_nanosecond(0).map(|t| t.naive_utc()),
+        "minute" => date.and_hms_opt(ts.hour(), ts.minute(), 0),
+        "hour" => date.and_hms_opt(ts.hour(), 0, 0),
+        "day" => day,
+        // Weeks start on Monday
+        "week" => day.map(|d| d - chrono::Days::new(ts.weekday().num_days_from_monday() as u64)),
+        "month" => date.with_day(1).and_then(|d| d.and_hms_opt(0, 0, 0)),
+        "year" => date.with_ordinal(1).and_then(|d| d.and_hms_opt(0, 0, 0)),
+        _ => None,
+    };
+    truncated.map_or(Value::Null, |t| Value::Timestamp(t.and_utc()))
+}
+
 pub fn evaluate_expression(
     expr: &crate::query::logical::Expr,
     row: &crate::core::tuple::Tuple,
@@ -584,27 +2400,50 @@ pub fn evaluate_expression(
         crate::query::logical::Expr::Literal(val) => val.clone(),
         crate::query::logical::Expr::BinaryExpr { left, op, right } => {
             let left_val = evaluate_expression(left, row);
+            match op.as_str() {
+                // Three-valued logic: FALSE decides AND and TRUE decides OR,
+                // even when the other side is NULL
+                "AND" | "OR" => {
+                    let decisive = Value::Bool(op == "OR");
+                    if left_val == decisive {
+                        return decisive;
+                    }
+                    let right_val = evaluate_expression(right, row);
+                    return match (left_val, right_val) {
+                        (_, r) if r == decisive => decisive,
+                        (Value::Bool(_), Value::Bool(b)) => Value::Bool(b),
+                        _ => Value::Null,
+                    };
+                }
+                "IS" => return Value::Bool(left_val.is_null()),
+                "IS NOT" => return Value::Bool(!left_val.is_null()),
+                "=" | "!=" | "<" | ">" | "<=" | ">=" | "LIKE" | "NOT LIKE" => {
+                    return compare_values(&left_val, op, &evaluate_expression(right, row));
+                }
+                _ => {}
+            }
             let right_val = evaluate_expression(right, row);
 
             match (left_val, right_val) {
-                (Value::Int(l), Value::Int(r)) => match op.as_str() {
-                    "+" => Value::Int(l + r),
-                    "-" => Value::Int(l - r),
-                    "*" => Value::Int(l * r),
-                    "/" => {
-                        if r != 0 {
-                            Value::Int(l / r)
-                        } else {
-                            Value::Null
-                        }
-                    }
+                (Value::Int(l), Value::Int(r)) => int_arithmetic(l, op, r),
+                // Intervals are whole seconds
+                (Value::Timestamp(t), Value::Int(s)) => match op.as_str() {
+                    "+" => shift_timestamp(t, s),
+                    "-" => s
+                        .checked_neg()
+                        .map_or(Value::Null, |s| shift_timestamp(t, s)),
                     _ => Value::Null,
                 },
+                (Value::Int(s), Value::Timestamp(t)) if op == "+" => shift_timestamp(t, s),
+                (Value::Timestamp(l), Value::Timestamp(r)) if op == "-" => {
+                    Value::Int((l - r).num_seconds())
+                }
                 (Value::Float(l), Value::Float(r)) => match op.as_str() {
                     "+" => Value::Float(l + r),
                     "-" => Value::Float(l - r),
                     "*" => Value::Float(l * r),
                     "/" => Value::Float(l / r),
+                    "%" => Value::Float(l % r),
                     _ => Value::Null,
                 },
                 (Value::Int(l), Value::Float(r)) => {
@@ -614,6 +2453,7 @@ pub fn evaluate_expression(
                         "-" => Value::Float(l - r),
                         "*" => Value::Float(l * r),
                         "/" => Value::Float(l / r),
+                        "%" => Value::Float(l % r),
                         _ => Value::Null,
                     }
                 }
@@ -624,6 +2464,7 @@ pub fn evaluate_expression(
                         "-" => Value::Float(l - r),
                         "*" => Value::Float(l * r),
                         "/" => Value::Float(l / r),
+                        "%" => Value::Float(l % r),
                         _ => Value::Null,
                     }
                 }
@@ -693,6 +2534,133 @@ pub fn evaluate_expression(
                 _ => Value::Null,
             }
         }
+        crate::query::logical::Expr::ScalarFunction { func, args } => {
+            let values: Vec<Value> = args.iter().map(|a| evaluate_expression(a, row)).collect();
+            match func {
+                crate::query::logical::ScalarFunction::WidthBucket => width_bucket(&values),
+                crate::query::logical::ScalarFunction::RegexpMatch => regexp_match(&values),
+                crate::query::logical::ScalarFunction::RegexpExtract => regexp_extract(&values),
+                crate::query::logical::ScalarFunction::Where => where_select(args, row),
+                crate::query::logical::ScalarFunction::CumSum => {
+                    map_vector(&values, crate::engine::kernels::cumsum_series)
+                }
+                crate::query::logical::ScalarFunction::CumProd => {
+                    map_vector(&values, crate::engine::kernels::cumprod_series)
+                }
+                crate::query::logical::ScalarFunction::Diff => {
+                    map_vector(&values, crate::engine::kernels::diff_series)
+                }
+                crate::query::logical::ScalarFunction::RollingMean => match values.get(1) {
+                    Some(Value::Int(w)) if *w > 0 => map_vector(&values, |xs| {
+                        crate::engine::kernels::rolling_mean_series(xs, *w as usize)
+                    }),
+                    _ => Value::Null,
+                },
+                crate::query::logical::ScalarFunction::CosineSim => {
+                    vector_pair(&values, crate::engine::kernels::cosine_similarity_1d)
+                }
+                crate::query::logical::ScalarFunction::Dot => {
+                    vector_pair(&values, crate::engine::kernels::dot_1d)
+                }
+                crate::query::logical::ScalarFunction::L2Dist => {
+                    vector_pair(&values, crate::engine::kernels::distance_1d)
+                }
+                crate::query::logical::ScalarFunction::Norm => match values.first() {
+                    Some(Value::Vector(v)) => crate::engine::kernels::l2_norm_1d(&vector_tensor(v))
+                        .map_or(Value::Null, Value::Float),
+                    _ => Value::Null,
+                },
+                crate::query::logical::ScalarFunction::Upper => match values.first() {
+                    Some(Value::String(s)) => Value::String(s.to_uppercase()),
+                    _ => Value::Null,
+                },
+                crate::query::logical::ScalarFunction::Lower => match values.first() {
+                    Some(Value::String(s)) => Value::String(s.to_lowercase()),
+                    _ => Value::Null,
+                },
+                crate::query::logical::ScalarFunction::Length => match values.first() {
+                    Some(Value::String(s)) => Value::Int(s.chars().count() as i64),
+                    Some(Value::Vector(v)) => Value::Int(v.len() as i64),
+                    _ => Value::Null,
+                },
+                crate::query::logical::ScalarFunction::Substr => substr(&values),
+                crate::query::logical::ScalarFunction::Concat => Value::String(
+                    values
+                        .iter()
+                        .filter(|v| !v.is_null())
+                        .map(|v| match v {
+                            Value::String(s) => s.clone(),
+                            other => other.to_string(),
+                        })
+                        .collect(),
+                ),
+                crate::query::logical::ScalarFunction::Abs => match values.first() {
+                    Some(Value::Int(i)) => i.checked_abs().map_or(Value::Null, Value::Int),
+                    Some(Value::Float(f)) => Value::Float(f.abs()),
+                    _ => Value::Null,
+                },
+                crate::query::logical::ScalarFunction::Round => round(&values),
+                crate::query::logical::ScalarFunction::Floor => match values.first() {
+                    Some(Value::Int(i)) => Value::Int(*i),
+                    Some(Value::Float(f)) => Value::Float(f.floor()),
+                    _ => Value::Null,
+                },
+                crate::query::logical::ScalarFunction::Sqrt => {
+                    float_function(&values, |x| (x >= 0.0).then(|| x.sqrt()))
+                }
+                crate::query::logical::ScalarFunction::Log => {
+                    float_function(&values, |x| (x > 0.0).then(|| x.ln()))
+                }
+                crate::query::logical::ScalarFunction::Exp => {
+                    float_function(&values, |x| Some(x.exp()))
+                }
+                crate::query::logical::ScalarFunction::Now => Value::Timestamp(chrono::Utc::now()),
+                crate::query::logical::ScalarFunction::DateTrunc => date_trunc(&values),
+                crate::query::logical::ScalarFunction::Coalesce => coalesce(values),
+                crate::query::logical::ScalarFunction::Case => case_select(values),
+                crate::query::logical::ScalarFunction::Cast(target) => match values.first() {
+                    Some(value) => value.cast_to(target).unwrap_or(Value::Null),
+                    None => Value::Null,
+                },
+                crate::query::logical::ScalarFunction::JsonGet => match json_get(&values) {
+                    Some(field) => Value::Json(field.clone()),
+                    None => Value::Null,
+                },
+                crate::query::logical::ScalarFunction::JsonGetText => match json_get(&values) {
+                    Some(serde_json::Value::String(s)) => Value::String(s.clone()),
+                    Some(serde_json::Value::Null) | None => Value::Null,
+                    Some(field) => Value::String(field.to_string()),
+                },
+                crate::query::logical::ScalarFunction::ElementAt => element_at(&values),
+                crate::query::logical::ScalarFunction::ArrayContains => {
+                    let items = match &values[..] {
+                        [Value::List(items), _] => items.clone(),
+                        [Value::Vector(v), _] => v.iter().map(|f| Value::Float(*f)).collect(),
+                        _ => return Value::Null,
+                    };
+                    if values[1].is_null() {
+                        return Value::Null;
+                    }
+                    Value::Bool(
+                        items
+                            .iter()
+                            .any(|item| compare_values(item, "=", &values[1]) == Value::Bool(true)),
+                    )
+                }
+                // Expanded into rows by UnnestExec before projection
+                crate::query::logical::ScalarFunction::Unnest => Value::Null,
+                crate::query::logical::ScalarFunction::Hash => hash_value(&values),
+                crate::query::logical::ScalarFunction::MaskEmail => match values.first() {
+                    Some(Value::String(s)) => Value::String(mask_email(s)),
+                    _ => Value::Null,
+                },
+                crate::query::logical::ScalarFunction::TruncateIp => match values.first() {
+                    Some(Value::String(s)) => truncate_ip(s).map_or(Value::Null, Value::String),
+                    _ => Value::Null,
+                },
+            }
+        }
+        crate::query::logical::Expr::Alias { expr, .. } => evaluate_expression(expr, row),
         _ => Value::Null,
     }
 }
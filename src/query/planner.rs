@@ -1,24 +1,54 @@
+use crate::core::dataset_legacy::Sample;
 use crate::core::tuple::Schema;
+use crate::core::value::Value;
 use crate::engine::{EngineError, TensorDb};
-use crate::query::logical::{Expr, LogicalPlan};
+use crate::query::columnar::{can_vectorize_aggregate, can_vectorize_filter};
+use crate::query::logical::{projected_field, Expr, LogicalPlan};
 use crate::query::physical::{
-    AggregateExec, FilterExec, IndexScanExec, LimitExec, PhysicalPlan, ProjectionExec, SeqScanExec,
-    SortExec, VectorSearchExec,
+    AggregateExec, AsOfJoinExec, ColumnarAggregateExec, ColumnarFilterExec, DistinctExec,
+    FilterExec, HashJoinExec, IndexScanExec, JoinStrategy, LimitExec, PhysicalPlan,
+    ProjectedColumn, ProjectionExec, RerankExec, SampleExec, SemiJoinExec, SeqScanExec,
+    SimilarityJoinExec, SortExec, TopKExec, UnnestExec, VectorSearchExec, WindowExec,
 };
-use std::sync::Arc;
+use crate::query::profile::{ProfiledExec, QueryProfile};
+use std::sync::{Arc, Mutex};
 
 pub struct Planner<'a> {
     db: &'a TensorDb,
+    /// Set for EXPLAIN ANALYZE: every operator records its statistics here
+    profile: Option<Arc<Mutex<QueryProfile>>>,
 }
 
 impl<'a> Planner<'a> {
     pub fn new(db: &'a TensorDb) -> Self {
-        Self { db }
+        Self { db, profile: None }
+    }
+
+    /// Planner whose operators record their runtime statistics into `profile`
+    pub fn with_profile(db: &'a TensorDb, profile: Arc<Mutex<QueryProfile>>) -> Self {
+        Self {
+            db,
+            profile: Some(profile),
+        }
     }
 
     pub fn create_physical_plan(
         &self,
         logical_plan: &LogicalPlan,
+    ) -> Result<Box<dyn PhysicalPlan>, EngineError> {
+        let plan = self.build_physical_plan(logical_plan)?;
+        Ok(match &self.profile {
+            Some(profile) => Box::new(ProfiledExec {
+                input: plan,
+                profile: profile.clone(),
+            }),
+            None => plan,
+        })
+    }
+
+    fn build_physical_plan(
+        &self,
+        logical_plan: &LogicalPlan,
     ) -> Result<Box<dyn PhysicalPlan>, EngineError> {
         match logical_plan {
             LogicalPlan::Scan {
@@ -48,6 +78,12 @@ impl<'a> Planner<'a> {
                 // We need to convert logical Expr to a physical predicate closure
                 // This is tricky because closures need to be generic or boxed.
                 // For MVP, we'll implement a simple interpreter for Expr inside predicate.
+                if self.is_large(input) && can_vectorize_filter(predicate, &input_plan.schema()) {
+                    return Ok(Box::new(ColumnarFilterExec {
+                        input: input_plan,
+                        predicate: predicate.clone(),
+                    }));
+                }
                 let predicate_clone = predicate.clone();
                 let predicate_fn = Box::new(move |row: &crate::core::tuple::Tuple| {
                     evaluate_expr(&predicate_clone, row)
@@ -58,29 +94,28 @@ impl<'a> Planner<'a> {
                     predicate: predicate_fn,
                 }))
             }
-            LogicalPlan::Project { input, columns } => {
+            LogicalPlan::Project { input, exprs } => {
                 let input_plan = self.create_physical_plan(input)?;
                 let input_schema = input_plan.schema();
 
-                let column_indices: Vec<usize> = columns
-                    .iter()
-                    .map(|name| {
-                        input_schema.get_field_index(name).ok_or_else(|| {
-                            EngineError::InvalidOp(format!("Column not found: {}", name))
-                        })
-                    })
-                    .collect::<Result<_, _>>()?;
-
-                let output_fields = column_indices
-                    .iter()
-                    .map(|&idx| input_schema.fields[idx].clone())
-                    .collect();
+                let mut columns = Vec::with_capacity(exprs.len());
+                let mut output_fields = Vec::with_capacity(exprs.len());
+                for expr in exprs {
+                    check_columns(expr, &input_schema)?;
+                    columns.push(match expr.unaliased() {
+                        Expr::Column(name) => ProjectedColumn::Input(
+                            input_schema.get_field_index(name).expect("checked above"),
+                        ),
+                        other => ProjectedColumn::Computed(other.clone()),
+                    });
+                    output_fields.extend(projected_field(expr, &input_schema));
+                }
                 let output_schema = Arc::new(Schema::new(output_fields));
 
                 Ok(Box::new(ProjectionExec {
                     input: input_plan,
                     output_schema,
-                    column_indices,
+                    columns,
                 }))
             }
             LogicalPlan::VectorSearch {
@@ -125,12 +160,37 @@ impl<'a> Planner<'a> {
                 }
             }
             LogicalPlan::Limit { input, n } => {
+                // OPTIMIZATION: Sort + Limit becomes a bounded top-k selection
+                if let LogicalPlan::Sort {
+                    input,
+                    column,
+                    ascending,
+                } = input.as_ref()
+                {
+                    return Ok(Box::new(TopKExec {
+                        input: self.create_physical_plan(input)?,
+                        column: column.clone(),
+                        ascending: *ascending,
+                        k: *n,
+                    }));
+                }
                 let input_plan = self.create_physical_plan(input)?;
                 Ok(Box::new(LimitExec {
                     input: input_plan,
                     n: *n,
                 }))
             }
+            LogicalPlan::Distinct { input } => {
+                let input_plan = self.create_physical_plan(input)?;
+                Ok(Box::new(DistinctExec { input: input_plan }))
+            }
+            LogicalPlan::Sample { input, sample } => {
+                let input_plan = self.create_physical_plan(input)?;
+                Ok(Box::new(SampleExec {
+                    input: input_plan,
+                    sample: *sample,
+                }))
+            }
             LogicalPlan::Sort {
                 input,
                 column,
@@ -147,17 +207,321 @@ impl<'a> Planner<'a> {
                 input,
                 group_expr,
                 aggr_expr,
+                rollup,
             } => {
                 let input_plan = self.create_physical_plan(input)?;
                 let schema = logical_plan.schema(); // Get helper schema
+                if group_expr.is_empty()
+                    && self.is_large(input)
+                    && can_vectorize_aggregate(aggr_expr, &input_plan.schema())
+                {
+                    return Ok(Box::new(ColumnarAggregateExec {
+                        input: input_plan,
+                        aggr_expr: aggr_expr.clone(),
+                        schema,
+                    }));
+                }
                 Ok(Box::new(AggregateExec {
                     input: input_plan,
                     group_expr: group_expr.clone(),
                     aggr_expr: aggr_expr.clone(),
+                    rollup: *rollup,
                     schema,
                 }))
             }
+            LogicalPlan::Rerank {
+                input,
+                service,
+                column,
+                query,
+            } => {
+                let service_config = self
+                    .db
+                    .config
+                    .rerank
+                    .services
+                    .get(service)
+                    .cloned()
+                    .ok_or_else(|| {
+                        EngineError::InvalidOp(format!("Unknown rerank service '{}'", service))
+                    })?;
+                let input_plan = self.create_physical_plan(input)?;
+                if input_plan.schema().get_field(column).is_none() {
+                    return Err(EngineError::InvalidOp(format!(
+                        "Rerank column not found: {}",
+                        column
+                    )));
+                }
+                Ok(Box::new(RerankExec {
+                    input: input_plan,
+                    service: service_config,
+                    column: column.clone(),
+                    query: query.clone(),
+                    schema: logical_plan.schema(),
+                }))
+            }
+            LogicalPlan::Window { input, window_expr } => {
+                let input_plan = self.create_physical_plan(input)?;
+                let schema = logical_plan.schema();
+                Ok(Box::new(WindowExec {
+                    input: input_plan,
+                    window_expr: window_expr.clone(),
+                    schema,
+                }))
+            }
+            LogicalPlan::Unnest { input, expr } => Ok(Box::new(UnnestExec {
+                input: self.create_physical_plan(input)?,
+                expr: expr.clone(),
+                schema: logical_plan.schema(),
+            })),
+            LogicalPlan::SemiJoin {
+                input,
+                subquery,
+                expr,
+                anti,
+            } => {
+                let subquery_plan = self.create_physical_plan(subquery)?;
+                if subquery_plan.schema().len() != 1 {
+                    return Err(EngineError::InvalidOp(format!(
+                        "Subquery in IN must return one column, got {}",
+                        subquery_plan.schema().len()
+                    )));
+                }
+                Ok(Box::new(SemiJoinExec {
+                    input: self.create_physical_plan(input)?,
+                    subquery: subquery_plan,
+                    expr: expr.clone(),
+                    anti: *anti,
+                }))
+            }
+            LogicalPlan::Join {
+                left,
+                right,
+                right_alias,
+                left_key,
+                right_key,
+                join_type,
+                asof,
+                similarity,
+                ..
+            } => {
+                let left_plan = self.create_physical_plan(left)?;
+                let right_plan = self.create_physical_plan(right)?;
+                let schema = logical_plan.schema();
+                let left_width = left_plan.schema().len();
+
+                // Keys are output column names: the left key must fall in the
+                // left columns and the right key in the right ones
+                let key_index = |name: &str| {
+                    schema.get_field_index(name).ok_or_else(|| {
+                        EngineError::InvalidOp(format!("Join key not found: {}", name))
+                    })
+                };
+                let left_idx = key_index(left_key)?;
+                let right_idx = key_index(right_key)?;
+                if left_idx >= left_width || right_idx < left_width {
+                    return Err(EngineError::InvalidOp(format!(
+                        "Join keys must compare a left column with a right column: {} = {}",
+                        left_key, right_key
+                    )));
+                }
+
+                if let Some(threshold) = similarity {
+                    return Ok(Box::new(SimilarityJoinExec {
+                        right_index: self.vector_index_scan(right, right_alias, right_key),
+                        left: left_plan,
+                        right: right_plan,
+                        left_key: left_idx,
+                        right_key: right_idx - left_width,
+                        threshold: *threshold,
+                        join_type: *join_type,
+                        schema,
+                    }));
+                }
+                if let Some((left_time, right_time)) = asof {
+                    let left_time = key_index(left_time)?;
+                    let right_time = key_index(right_time)? - left_width;
+                    return Ok(Box::new(AsOfJoinExec {
+                        left: left_plan,
+                        right: right_plan,
+                        left_key: left_idx,
+                        right_key: right_idx - left_width,
+                        left_time,
+                        right_time,
+                        join_type: *join_type,
+                        schema,
+                    }));
+                }
+                Ok(Box::new(HashJoinExec {
+                    left: left_plan,
+                    right: right_plan,
+                    left_key: left_idx,
+                    right_key: right_idx - left_width,
+                    join_type: *join_type,
+                    strategy: self.join_strategy(left, right),
+                    schema,
+                }))
+            }
+        }
+    }
+
+    /// `(dataset, column)` when `plan` scans a dataset with a vector index
+    /// on the column behind the join key `key` (named `alias.column`)
+    fn vector_index_scan(
+        &self,
+        plan: &LogicalPlan,
+        alias: &str,
+        key: &str,
+    ) -> Option<(String, String)> {
+        let LogicalPlan::Scan { dataset_name, .. } = plan else {
+            return None;
+        };
+        let column = key.strip_prefix(alias)?.strip_prefix('.')?;
+        let dataset = self.db.get_dataset(dataset_name).ok()?;
+        dataset
+            .get_index(column)
+            .filter(|index| index.index_type() == crate::core::index::IndexType::Vector)
+            .map(|_| (dataset_name.clone(), column.to_string()))
+    }
+
+    /// Broadcast the smaller join input when its estimated row count is
+    /// within `[execution] broadcast_join_threshold`, otherwise partition
+    /// both inputs across the available cores
+    fn join_strategy(&self, left: &LogicalPlan, right: &LogicalPlan) -> JoinStrategy {
+        let threshold = self.db.config.execution.broadcast_join_threshold;
+        let left_rows = self.estimated_rows(left);
+        let right_rows = self.estimated_rows(right);
+        let small = |rows: Option<usize>| rows.is_some_and(|n| n <= threshold);
+        match (left_rows, right_rows) {
+            (Some(l), Some(r)) if small(left_rows) && l < r => {
+                JoinStrategy::Broadcast { build_left: true }
+            }
+            (_, r) if small(r) => JoinStrategy::Broadcast { build_left: false },
+            (l, _) if small(l) => JoinStrategy::Broadcast { build_left: true },
+            _ => JoinStrategy::Partitioned {
+                partitions: std::thread::available_parallelism().map_or(4, |n| n.get()),
+            },
+        }
+    }
+
+    /// Rows `plan` produces, from the row counts of the datasets it scans
+    /// scaled by the selectivity of filters on analyzed columns; unknown
+    /// through joins
+    fn estimated_rows(&self, plan: &LogicalPlan) -> Option<usize> {
+        match plan {
+            LogicalPlan::Scan { dataset_name, .. } => self
+                .db
+                .get_dataset(dataset_name)
+                .ok()
+                .map(|ds| ds.rows.len()),
+            LogicalPlan::Filter { input, predicate } => {
+                let rows = self.estimated_rows(input)?;
+                Some((rows as f64 * self.selectivity(input, predicate)).ceil() as usize)
+            }
+            LogicalPlan::Project { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Distinct { input }
+            | LogicalPlan::Aggregate { input, .. }
+            | LogicalPlan::Window { input, .. }
+            | LogicalPlan::Rerank { input, .. }
+            | LogicalPlan::SemiJoin { input, .. } => self.estimated_rows(input),
+            LogicalPlan::Limit { input, n } => {
+                Some(self.estimated_rows(input).map_or(*n, |rows| rows.min(*n)))
+            }
+            LogicalPlan::Sample {
+                input,
+                sample: Sample::Rows(n),
+            } => Some(self.estimated_rows(input).map_or(*n, |rows| rows.min(*n))),
+            LogicalPlan::Sample {
+                input,
+                sample: Sample::Percent(percent),
+            } => {
+                let rows = self.estimated_rows(input)?;
+                Some((rows as f64 * percent / 100.0).ceil() as usize)
+            }
+            LogicalPlan::VectorSearch { k, .. } => Some(*k),
+            LogicalPlan::Join { .. } | LogicalPlan::Unnest { .. } => None,
+        }
+    }
+
+    /// Estimated fraction of the rows of `input` that pass `predicate`, from
+    /// the column statistics of the dataset `input` filters. Each condition
+    /// of a conjunction counts independently; a condition other than
+    /// `column op literal`, or on a column `ANALYZE` has not seen, keeps
+    /// every row.
+    fn selectivity(&self, input: &LogicalPlan, predicate: &Expr) -> f64 {
+        fn conjuncts<'e>(expr: &'e Expr, out: &mut Vec<&'e Expr>) {
+            match expr {
+                Expr::BinaryExpr { left, op, right } if op == "AND" => {
+                    conjuncts(left, out);
+                    conjuncts(right, out);
+                }
+                other => out.push(other),
+            }
+        }
+
+        let mut scanned = input;
+        while let LogicalPlan::Filter { input, .. } = scanned {
+            scanned = input;
+        }
+        let LogicalPlan::Scan { dataset_name, .. } = scanned else {
+            return 1.0;
+        };
+        let Ok(dataset) = self.db.get_dataset(dataset_name) else {
+            return 1.0;
+        };
+        let metadata = &dataset.metadata;
+
+        let mut conditions = Vec::new();
+        conjuncts(predicate, &mut conditions);
+        conditions
+            .into_iter()
+            .map(|condition| {
+                let Expr::BinaryExpr { left, op, right } = condition else {
+                    return 1.0;
+                };
+                let (column, op, value) = match (left.as_ref(), right.as_ref()) {
+                    (Expr::Column(column), Expr::Literal(value)) => (column, op.as_str(), value),
+                    (Expr::Literal(value), Expr::Column(column)) => {
+                        let mirrored = match op.as_str() {
+                            "<" => ">",
+                            "<=" => ">=",
+                            ">" => "<",
+                            ">=" => "<=",
+                            other => other,
+                        };
+                        (column, mirrored, value)
+                    }
+                    _ => return 1.0,
+                };
+                // Join inputs qualify their columns by alias
+                let stats = metadata.column_stats.get(column).or_else(|| {
+                    let (_, name) = column.rsplit_once('.')?;
+                    metadata.column_stats.get(name)
+                });
+                stats
+                    .and_then(|stats| stats.selectivity(op, value, metadata.row_count))
+                    .unwrap_or(1.0)
+            })
+            .product()
+    }
+
+    /// Whether `plan` reads at least `[execution] columnar_threshold` rows,
+    /// judged by the dataset it scans
+    fn is_large(&self, plan: &LogicalPlan) -> bool {
+        fn scanned(plan: &LogicalPlan) -> Option<&str> {
+            match plan {
+                LogicalPlan::Scan { dataset_name, .. } => Some(dataset_name),
+                LogicalPlan::Filter { input, .. }
+                | LogicalPlan::Project { input, .. }
+                | LogicalPlan::Sort { input, .. }
+                | LogicalPlan::Distinct { input } => scanned(input),
+                _ => None,
+            }
         }
+        scanned(plan)
+            .and_then(|name| self.db.get_dataset(name).ok())
+            .is_some_and(|ds| ds.rows.len() >= self.db.config.execution.columnar_threshold)
     }
 
     fn try_optimize_filter(
@@ -193,34 +557,77 @@ impl<'a> Planner<'a> {
     }
 }
 
-fn evaluate_expr(expr: &Expr, row: &crate::core::tuple::Tuple) -> bool {
-    // Basic evaluator
+/// Fail on projected columns missing from `schema`, which would otherwise
+/// evaluate to NULL, and on aggregates or window functions nested in an expression
+fn check_columns(expr: &Expr, schema: &Schema) -> Result<(), EngineError> {
     match expr {
-        Expr::BinaryExpr { left, op, right } => {
-            let left_val = eval_value(left, row);
-            let right_val = eval_value(right, row);
-
-            if let (Some(l), Some(r)) = (left_val, right_val) {
-                let ord = l.compare(&r);
-                match op.as_str() {
-                    "=" => ord == Some(std::cmp::Ordering::Equal),
-                    "!=" => ord.is_some() && ord != Some(std::cmp::Ordering::Equal),
-                    ">" => ord == Some(std::cmp::Ordering::Greater),
-                    "<" => ord == Some(std::cmp::Ordering::Less),
-                    _ => false, // TODO: Implement others
-                }
-            } else {
-                false
-            }
+        Expr::Column(name) => match schema.get_field_index(name) {
+            Some(_) => Ok(()),
+            None => Err(EngineError::InvalidOp(format!(
+                "Column not found: {}",
+                name
+            ))),
+        },
+        Expr::Literal(_) => Ok(()),
+        Expr::BinaryExpr { left, right, .. } => {
+            check_columns(left, schema)?;
+            check_columns(right, schema)
         }
-        _ => false, // Only binary exprs supported as predicates top level
+        Expr::ScalarFunction { args, .. } => {
+            args.iter().try_for_each(|arg| check_columns(arg, schema))
+        }
+        Expr::Alias { expr, .. } => check_columns(expr, schema),
+        Expr::AggregateExpr { .. } | Expr::WindowFunction { .. } => Err(EngineError::InvalidOp(
+            format!("{} cannot be used inside an expression", expr.output_name()),
+        )),
     }
 }
 
-fn eval_value(expr: &Expr, row: &crate::core::tuple::Tuple) -> Option<crate::core::value::Value> {
-    match expr {
-        Expr::Column(name) => row.get(name).cloned(),
-        Expr::Literal(val) => Some(val.clone()),
-        _ => None,
+/// Evaluate a WHERE/FILTER predicate against a row
+/// Whether `row` passes the predicate. Comparisons with NULL are unknown
+/// and, like FALSE, filter the row out
+pub(crate) fn evaluate_expr(expr: &Expr, row: &crate::core::tuple::Tuple) -> bool {
+    matches!(
+        crate::query::physical::evaluate_expression(expr, row),
+        Value::Bool(true)
+    )
+}
+
+/// SQL LIKE: `%` matches any run of characters, `_` exactly one, and a
+/// backslash makes the next character literal
+pub(crate) fn like_match(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+
+    // Greedy match with backtracking to the last `%`
+    let (mut t, mut p) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        let literal = match pattern.get(p) {
+            Some('\\') => pattern.get(p + 1).map(|c| (*c, 2)),
+            Some('%') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+                continue;
+            }
+            Some('_') => Some((text[t], 1)),
+            Some(c) => Some((*c, 1)),
+            None => None,
+        };
+        match literal {
+            Some((c, width)) if c == text[t] => {
+                t += 1;
+                p += width;
+            }
+            _ => match backtrack {
+                Some((bp, bt)) => {
+                    p = bp;
+                    t = bt + 1;
+                    backtrack = Some((bp, bt + 1));
+                }
+                None => return false,
+            },
+        }
     }
+    pattern[p..].iter().all(|c| *c == '%')
 }
@@ -0,0 +1,111 @@
+//! Runtime statistics for `EXPLAIN ANALYZE`. The planner wraps every
+//! operator in a `ProfiledExec`, which records wall time, rows produced and
+//! their estimated memory into a shared `QueryProfile` as the plan runs.
+
+use crate::core::tuple::{Schema, Tuple};
+use crate::engine::memory::rows_size;
+use crate::engine::{EngineError, TensorDb};
+use crate::query::physical::PhysicalPlan;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Statistics of one executed operator
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OperatorProfile {
+    pub name: String,
+    /// Distance from the root of the plan
+    pub depth: usize,
+    pub rows: usize,
+    /// Wall time including the operator's inputs
+    pub elapsed: Duration,
+    /// Estimated size of the rows produced
+    pub bytes: usize,
+}
+
+/// Operators in the order they started executing (pre-order), so each
+/// operator's inputs follow it one level deeper
+#[derive(Debug, Default)]
+pub struct QueryProfile {
+    pub operators: Vec<OperatorProfile>,
+    depth: usize,
+}
+
+impl QueryProfile {
+    /// Wall time of operator `idx` minus that of its direct inputs
+    pub fn self_time(&self, idx: usize) -> Duration {
+        let op = &self.operators[idx];
+        let inputs: Duration = self.operators[idx + 1..]
+            .iter()
+            .take_while(|child| child.depth > op.depth)
+            .filter(|child| child.depth == op.depth + 1)
+            .map(|child| child.elapsed)
+            .sum();
+        op.elapsed.saturating_sub(inputs)
+    }
+
+    /// The operators as an indented tree, one line each
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (idx, op) in self.operators.iter().enumerate() {
+            let prefix = match op.depth {
+                0 => String::new(),
+                depth => format!("{}└─ ", "   ".repeat(depth - 1)),
+            };
+            out.push_str(&format!(
+                "{}{}  rows={} time={:.3}ms self={:.3}ms memory={}B\n",
+                prefix,
+                op.name,
+                op.rows,
+                op.elapsed.as_secs_f64() * 1000.0,
+                self.self_time(idx).as_secs_f64() * 1000.0,
+                op.bytes
+            ));
+        }
+        out
+    }
+}
+
+/// Runs `input` and records its statistics into `profile`
+#[derive(Debug)]
+pub struct ProfiledExec {
+    pub input: Box<dyn PhysicalPlan>,
+    pub profile: Arc<Mutex<QueryProfile>>,
+}
+
+impl PhysicalPlan for ProfiledExec {
+    fn schema(&self) -> Arc<Schema> {
+        self.input.schema()
+    }
+
+    fn name(&self) -> String {
+        self.input.name()
+    }
+
+    fn execute(&self, db: &TensorDb) -> Result<Vec<Tuple>, EngineError> {
+        let idx = {
+            let mut profile = self.profile.lock().unwrap();
+            let depth = profile.depth;
+            profile.operators.push(OperatorProfile {
+                name: self.input.name(),
+                depth,
+                ..Default::default()
+            });
+            profile.depth += 1;
+            profile.operators.len() - 1
+        };
+
+        let start = Instant::now();
+        let result = self.input.execute(db);
+        let elapsed = start.elapsed();
+
+        let mut profile = self.profile.lock().unwrap();
+        profile.depth -= 1;
+        let op = &mut profile.operators[idx];
+        op.elapsed = elapsed;
+        if let Ok(rows) = &result {
+            op.rows = rows.len();
+            op.bytes = rows_size(rows);
+        }
+        result
+    }
+}
@@ -0,0 +1,45 @@
+use crate::core::config::RerankServiceConfig;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Serialize)]
+struct RerankRequest<'a> {
+    query: &'a str,
+    documents: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct RerankResponse {
+    scores: Vec<f32>,
+}
+
+/// Ask a rerank service to score `documents` against `query`.
+/// Returns one score per document, in the same order.
+pub fn request_scores(
+    service: &RerankServiceConfig,
+    query: &str,
+    documents: &[String],
+) -> Result<Vec<f32>, String> {
+    if documents.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_millis(service.timeout_ms))
+        .build();
+    let response: RerankResponse = agent
+        .post(&service.url)
+        .send_json(RerankRequest { query, documents })
+        .map_err(|e| format!("Rerank request to '{}' failed: {}", service.url, e))?
+        .into_json()
+        .map_err(|e| format!("Invalid rerank response from '{}': {}", service.url, e))?;
+
+    if response.scores.len() != documents.len() {
+        return Err(format!(
+            "Rerank service returned {} scores for {} documents",
+            response.scores.len(),
+            documents.len()
+        ));
+    }
+    Ok(response.scores)
+}
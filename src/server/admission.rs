@@ -0,0 +1,95 @@
+use crate::core::config::ServerConfig;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Caps the commands executing at once. Commands past the cap queue up to
+/// `max_queued_queries`; beyond that they are turned away so a burst of heavy
+/// queries does not pile up behind the database locks.
+pub struct Admission {
+    /// None when concurrency is unlimited
+    slots: Option<Arc<Semaphore>>,
+    queued: Arc<AtomicUsize>,
+    max_queued: usize,
+    pub retry_after_secs: u64,
+}
+
+impl Admission {
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            slots: (config.max_concurrent_queries > 0)
+                .then(|| Arc::new(Semaphore::new(config.max_concurrent_queries))),
+            queued: Arc::new(AtomicUsize::new(0)),
+            max_queued: config.max_queued_queries,
+            retry_after_secs: config.retry_after_secs,
+        }
+    }
+
+    /// Claim a free slot or a place in the queue; None when the queue is full
+    pub fn enqueue(&self) -> Option<Ticket> {
+        let Some(slots) = &self.slots else {
+            return Some(Ticket::default());
+        };
+        if let Ok(permit) = slots.clone().try_acquire_owned() {
+            return Some(Ticket {
+                slots: None,
+                queued: None,
+                permit: Some(permit),
+            });
+        }
+        self.queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued < self.max_queued).then_some(queued + 1)
+            })
+            .ok()?;
+        Some(Ticket {
+            slots: Some(slots.clone()),
+            queued: Some(self.queued.clone()),
+            permit: None,
+        })
+    }
+
+    /// Commands waiting for a slot
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Acquire)
+    }
+}
+
+/// Place of a command in the queue, or the slot it already holds
+#[derive(Default)]
+pub struct Ticket {
+    slots: Option<Arc<Semaphore>>,
+    queued: Option<Arc<AtomicUsize>>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Ticket {
+    /// Wait for a slot. The command may execute while the returned permit lives.
+    pub async fn admit(mut self) -> Permit {
+        if let (None, Some(slots)) = (&self.permit, &self.slots) {
+            let permit = slots
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("admission semaphore is never closed");
+            self.permit = Some(permit);
+        }
+        Permit {
+            _slot: self.permit.take(),
+        }
+    }
+}
+
+impl Drop for Ticket {
+    /// Leaves the queue once admitted, or when the request gives up waiting
+    fn drop(&mut self) {
+        if let Some(queued) = &self.queued {
+            queued.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+/// Slot held by an executing command, released on drop
+pub struct Permit {
+    _slot: Option<OwnedSemaphorePermit>,
+}
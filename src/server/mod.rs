@@ -1,20 +1,35 @@
-use crate::dsl::{execute_line, DslOutput};
-use crate::engine::TensorDb;
+pub mod admission;
+pub mod sessions;
+pub mod shaping;
+
+use crate::dsl::handlers::copy::{CopyFormat, CopyStatement, COPY_BATCH_ROWS};
+use crate::dsl::parser::{classify, Command};
+use crate::dsl::{execute_line, execute_sql, DslOutput};
+use crate::engine::audit::mask_api_key;
+use crate::engine::{ExecutionStats, QueryStatus, TensorDb};
+use admission::Admission;
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use sessions::{Caller, DatabaseLocks, LockScope};
+use std::sync::{Arc, RwLock};
 use toon_format::encode_default;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 struct AppState {
-    db: Arc<Mutex<TensorDb>>,
+    /// Catalog lock; commands hold it only to borrow and return their
+    /// database (write) or to copy from it (read)
+    db: Arc<RwLock<TensorDb>>,
+    locks: DatabaseLocks,
+    admission: Admission,
 }
 
 const MAX_COMMAND_LENGTH: usize = 16 * 1024; // 16KB
@@ -25,12 +40,49 @@ struct ExecuteParams {
     /// Format of the output: 'toon' (default) or 'json'
     #[serde(default = "default_format")]
     format: String,
+    /// Language of the command: 'dsl' (default) or 'sql'
+    #[serde(default = "default_lang")]
+    lang: String,
+    /// Vector and matrix columns in result tables: 'exclude' (default), 'truncate' or 'full'
+    vectors: Option<String>,
+    /// Components kept per vector with vectors=truncate (default 8)
+    vector_len: Option<usize>,
+    /// Decimal places kept for float values
+    precision: Option<u32>,
+    /// Database the command runs against (overrides the X-Linal-Database header)
+    db: Option<String>,
 }
 
 fn default_format() -> String {
     "toon".to_string()
 }
 
+fn default_lang() -> String {
+    "dsl".to_string()
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+struct IngestParams {
+    /// Encoding of the body rows: 'csv' (default, values in column order) or 'json' (one object per line)
+    #[serde(default = "default_row_format")]
+    row_format: String,
+    /// Format of the output: 'toon' (default) or 'json'
+    #[serde(default = "default_format")]
+    format: String,
+    /// Database holding the dataset (overrides the X-Linal-Database header)
+    db: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+struct SnapshotParams {
+    /// Database to export (overrides the X-Linal-Database header)
+    db: Option<String>,
+}
+
+fn default_row_format() -> String {
+    "csv".to_string()
+}
+
 #[derive(Deserialize, utoipa::ToSchema)]
 pub struct ExecuteRequest {
     command: String,
@@ -43,16 +95,61 @@ pub struct ExecuteResponse {
     result: Option<DslOutput>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<ExecutionMetadata>,
+}
+
+/// Resource usage of one executed command
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct ExecutionMetadata {
+    /// Rows read from datasets by scans and index lookups
+    rows_scanned: usize,
+    /// Rows in the result table (0 for messages and tensors)
+    rows_returned: usize,
+    elapsed_ms: f64,
+    /// Whether a hash or vector index served the query
+    index_used: bool,
+    /// Whether the result was served from a result cache (none exists yet, so always false)
+    cache_hit: bool,
+}
+
+impl ExecutionMetadata {
+    fn new(
+        stats: ExecutionStats,
+        output: Option<&DslOutput>,
+        elapsed: std::time::Duration,
+    ) -> Self {
+        let rows_returned = match output {
+            Some(DslOutput::Table(ds)) => ds.rows.len(),
+            _ => 0,
+        };
+        Self {
+            rows_scanned: stats.rows_scanned,
+            rows_returned,
+            elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+            index_used: stats.index_used,
+            cache_hit: false,
+        }
+    }
+}
+
+/// Commands that have run for at least `[execution] progress_after_secs`
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct QueriesResponse {
+    queries: Vec<QueryStatus>,
 }
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         execute_command,
+        ingest_rows,
+        export_snapshot,
+        list_queries,
         health_check
     ),
     components(
-        schemas(ExecuteRequest, ExecuteResponse)
+        schemas(ExecuteRequest, ExecuteResponse, ExecutionMetadata, QueriesResponse)
     ),
     tags(
         (name = "VectorDB", description = "LINAL Analytical Engine API")
@@ -60,13 +157,34 @@ pub struct ExecuteResponse {
 )]
 struct ApiDoc;
 
-pub async fn start_server(db: Arc<Mutex<TensorDb>>, port: u16) {
-    let state = Arc::new(AppState { db });
+pub async fn start_server(db: Arc<RwLock<TensorDb>>, port: u16) {
+    let (reap_interval, idle_timeout, admission) = {
+        let db = db.read().unwrap();
+        (
+            db.config.storage.ttl_reap_interval_secs,
+            db.config.server.transaction_idle_timeout_secs,
+            Admission::new(&db.config.server),
+        )
+    };
+    let state = Arc::new(AppState {
+        db,
+        locks: DatabaseLocks::default(),
+        admission,
+    });
+    if reap_interval > 0 {
+        tokio::spawn(reap_expired_datasets(state.clone(), reap_interval));
+    }
+    if idle_timeout > 0 {
+        tokio::spawn(roll_back_idle_transactions(state.clone(), idle_timeout));
+    }
 
     let app = Router::new()
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .route("/health", get(health_check))
         .route("/execute", post(execute_command))
+        .route("/datasets/:name/rows", post(ingest_rows))
+        .route("/admin/snapshot", get(export_snapshot))
+        .route("/queries", get(list_queries))
         .with_state(state);
 
     let addr = format!("0.0.0.0:{}", port);
@@ -75,6 +193,47 @@ pub async fn start_server(db: Arc<Mutex<TensorDb>>, port: u16) {
     axum::serve(listener, app).await.unwrap();
 }
 
+/// Background task dropping rows and datasets whose TTL elapsed
+async fn reap_expired_datasets(state: Arc<AppState>, interval_secs: u64) {
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+    ticker.tick().await; // first tick completes immediately
+    loop {
+        ticker.tick().await;
+        let report = state.db.write().unwrap().reap_expired();
+        if report.rows_removed > 0 || !report.datasets_dropped.is_empty() {
+            state.locks.invalidate_snapshots();
+            println!(
+                "TTL: expired {} rows, dropped {} datasets",
+                report.rows_removed,
+                report.datasets_dropped.len()
+            );
+        }
+    }
+}
+
+/// Background task rolling back transactions their session left idle
+async fn roll_back_idle_transactions(state: Arc<AppState>, timeout_secs: u64) {
+    let timeout = std::time::Duration::from_secs(timeout_secs);
+    let check_every = std::time::Duration::from_secs((timeout_secs / 4).max(1));
+    let mut ticker = tokio::time::interval(check_every);
+    ticker.tick().await; // first tick completes immediately
+    loop {
+        ticker.tick().await;
+        let state = state.clone();
+        let rolled_back = tokio::task::spawn_blocking(move || {
+            state.locks.roll_back_idle_transactions(&state.db, timeout)
+        })
+        .await
+        .unwrap_or_default();
+        for name in rolled_back {
+            println!(
+                "Transaction: rolled back the idle transaction of database '{}'",
+                name
+            );
+        }
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/health",
@@ -86,6 +245,20 @@ async fn health_check() -> (StatusCode, Json<serde_json::Value>) {
     (StatusCode::OK, Json(serde_json::json!({ "status": "ok" })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/queries",
+    responses(
+        (status = 200, description = "Progress of the long-running commands", body = QueriesResponse)
+    )
+)]
+async fn list_queries(State(state): State<Arc<AppState>>) -> Json<QueriesResponse> {
+    // Sessions hold the catalog only to borrow and return their database,
+    // so this does not wait for the queries it reports on
+    let queries = state.db.read().unwrap().running_queries();
+    Json(QueriesResponse { queries })
+}
+
 #[utoipa::path(
     post,
     path = "/execute",
@@ -94,13 +267,14 @@ async fn health_check() -> (StatusCode, Json<serde_json::Value>) {
         ExecuteParams
     ),
     responses(
-        (status = 200, description = "Execution result", body = ExecuteResponse)
+        (status = 200, description = "Execution result", body = ExecuteResponse),
+        (status = 503, description = "Admission queue full; retry after the Retry-After seconds", body = ExecuteResponse)
     )
 )]
 async fn execute_command(
     State(state): State<Arc<AppState>>,
     Query(params): Query<ExecuteParams>,
-    headers: axum::http::HeaderMap,
+    headers: HeaderMap,
     body: String,
 ) -> impl IntoResponse {
     // Determine if request is JSON (legacy) or plain text (preferred)
@@ -127,83 +301,131 @@ async fn execute_command(
     };
 
     if command.len() > MAX_COMMAND_LENGTH {
-        return (
-            StatusCode::BAD_REQUEST,
-            [(axum::http::header::CONTENT_TYPE, "application/json")],
-            serde_json::to_string(&ExecuteResponse {
-                status: "error".to_string(),
-                result: None,
-                error: Some(format!(
-                    "Command too long (max {} bytes)",
-                    MAX_COMMAND_LENGTH
-                )),
-            })
-            .unwrap(),
-        )
-            .into_response();
+        return bad_request(format!(
+            "Command too long (max {} bytes)",
+            MAX_COMMAND_LENGTH
+        ));
     }
 
     if command.is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            [(axum::http::header::CONTENT_TYPE, "application/json")],
-            serde_json::to_string(&ExecuteResponse {
-                status: "error".to_string(),
-                result: None,
-                error: Some("Command cannot be empty".to_string()),
-            })
-            .unwrap(),
-        )
-            .into_response();
+        return bad_request("Command cannot be empty".to_string());
+    }
+
+    let shape = match shaping::ResponseShape::from_params(
+        params.vectors.as_deref(),
+        params.vector_len,
+        params.precision,
+    ) {
+        Ok(shape) => shape,
+        Err(e) => return bad_request(e),
+    };
+
+    let use_sql = match params.lang.as_str() {
+        "dsl" => false,
+        "sql" => true,
+        other => {
+            return bad_request(format!(
+                "Unknown lang '{}' (expected 'dsl' or 'sql')",
+                other
+            ));
+        }
+    };
+
+    let caller = caller(&headers);
+    let api_key = api_key(&headers);
+    let database = request_database(params.db, &headers);
+
+    // Transactions belong to the client session that began them
+    let begin = !use_sql && matches!(classify(&command, 1), Ok(Some((Command::Begin, _))));
+    if begin && caller.session.is_none() {
+        return bad_request("BEGIN requires an X-Linal-Session header".to_string());
     }
 
-    // Wrap execution in timeout and spawn_blocking to keep server responsive
-    let db_arc = state.db.clone();
+    let scope = if use_sql {
+        LockScope::of_command(&format!("SQL {}", command))
+    } else {
+        LockScope::of_command(&command)
+    };
+
+    let Some(ticket) = state.admission.enqueue() else {
+        return overloaded(&state.admission);
+    };
+
+    // Wrap execution in timeout and spawn_blocking to keep server responsive.
+    // Time spent queued for a slot counts towards the timeout.
+    let state = state.clone();
     let command_clone = command.clone();
 
     let exec_result = tokio::time::timeout(
         std::time::Duration::from_secs(QUERY_TIMEOUT_SECS),
-        tokio::task::spawn_blocking(move || {
-            let mut db = db_arc.lock().unwrap();
-            execute_line(&mut db, &command_clone, 1)
-        }),
+        async move {
+            let permit = ticket.admit().await;
+            tokio::task::spawn_blocking(move || {
+                // Held until the command finishes, even past the timeout
+                let _permit = permit;
+                let started = std::time::Instant::now();
+                let (result, stats) =
+                    state
+                        .locks
+                        .run(&state.db, scope, database.as_deref(), caller, |db| {
+                            let role = db.config.security.role_of(api_key.as_deref());
+                            db.set_role(Some(role));
+                            if use_sql {
+                                execute_sql(db, &command_clone, 1)
+                            } else {
+                                execute_line(db, &command_clone, 1)
+                            }
+                        });
+                (result, stats, started.elapsed())
+            })
+            .await
+        },
     )
     .await;
 
     let response = match exec_result {
-        Ok(Ok(Ok(output))) => {
+        Ok(Ok((Ok(output), stats, elapsed))) => {
             let result = match output {
                 DslOutput::None => None,
                 _ => Some(output),
             };
+            let metadata = ExecutionMetadata::new(stats, result.as_ref(), elapsed);
             ExecuteResponse {
                 status: "ok".to_string(),
-                result,
+                result: result.map(|output| shape.apply(output)),
                 error: None,
+                metadata: Some(metadata),
             }
         }
-        Ok(Ok(Err(e))) => ExecuteResponse {
+        Ok(Ok((Err(e), stats, elapsed))) => ExecuteResponse {
             status: "error".to_string(),
             result: None,
             error: Some(format!("{}", e)),
+            metadata: Some(ExecutionMetadata::new(stats, None, elapsed)),
         },
         Ok(Err(e)) => ExecuteResponse {
             status: "error".to_string(),
             result: None,
             error: Some(format!("Execution task panicked: {}", e)),
+            metadata: None,
         },
         Err(_) => ExecuteResponse {
             status: "error".to_string(),
             result: None,
             error: Some(format!("Query timed out after {}s", QUERY_TIMEOUT_SECS)),
+            metadata: None,
         },
     };
 
-    // Serialize based on requested format
-    match params.format.as_str() {
+    render(&params.format, &response)
+}
+
+/// Serialize a response in the requested format
+fn render(format: &str, response: &ExecuteResponse) -> Response {
+    match format {
         "json" => {
             // JSON format (opt-in)
-            let body = serde_json::to_string(&response).unwrap_or_else(|e| {
+            let body = serde_json::to_string(response).unwrap_or_else(|e| {
                 format!(
                     "{{\"status\": \"error\", \"error\": \"Serialization failed: {}\"}}",
                     e
@@ -218,7 +440,7 @@ async fn execute_command(
         }
         _ => {
             // TOON format (default)
-            let body = encode_default(&response)
+            let body = encode_default(response)
                 .unwrap_or_else(|e| format!("status: error\nerror: Serialization failed: {}", e));
             (
                 StatusCode::OK,
@@ -229,3 +451,382 @@ async fn execute_command(
         }
     }
 }
+
+/// 503 with Retry-After for a command finding the admission queue full
+fn overloaded(admission: &Admission) -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                "application/json".to_string(),
+            ),
+            (
+                axum::http::header::RETRY_AFTER,
+                admission.retry_after_secs.to_string(),
+            ),
+        ],
+        serde_json::to_string(&ExecuteResponse {
+            status: "error".to_string(),
+            result: None,
+            error: Some(format!(
+                "Server busy: {} queries already waiting, retry later",
+                admission.queued()
+            )),
+            metadata: None,
+        })
+        .unwrap(),
+    )
+        .into_response()
+}
+
+fn bad_request(error: String) -> Response {
+    error_response(StatusCode::BAD_REQUEST, error)
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (
+        status,
+        [(axum::http::header::CONTENT_TYPE, "application/json")],
+        serde_json::to_string(&ExecuteResponse {
+            status: "error".to_string(),
+            result: None,
+            error: Some(error),
+            metadata: None,
+        })
+        .unwrap(),
+    )
+        .into_response()
+}
+
+/// Database chosen by the client: the `db` query parameter, else the
+/// X-Linal-Database header. `None` runs against the server's active database.
+fn request_database(param: Option<String>, headers: &HeaderMap) -> Option<String> {
+    param.or_else(|| {
+        headers
+            .get("x-linal-database")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string())
+    })
+}
+
+fn api_key(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Audit identity: masked X-API-Key, if the client sent one
+fn audit_actor(headers: &HeaderMap) -> String {
+    api_key(headers)
+        .map(|key| mask_api_key(&key))
+        .unwrap_or_else(|| "anonymous".to_string())
+}
+
+/// Audit identity and client session (X-Linal-Session header) of a request
+fn caller(headers: &HeaderMap) -> Caller {
+    Caller {
+        actor: audit_actor(headers),
+        session: headers
+            .get("x-linal-session")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/datasets/{name}/rows",
+    request_body = String,
+    params(
+        ("name" = String, Path, description = "Dataset receiving the rows"),
+        IngestParams
+    ),
+    responses(
+        (status = 200, description = "Rows ingested", body = ExecuteResponse)
+    )
+)]
+async fn ingest_rows(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(params): Query<IngestParams>,
+    headers: HeaderMap,
+    body: Body,
+) -> impl IntoResponse {
+    let Some(row_format) = CopyFormat::parse(&params.row_format) else {
+        return bad_request(format!(
+            "Unknown row_format '{}' (expected 'csv' or 'json')",
+            params.row_format
+        ));
+    };
+    let statement = CopyStatement::new(name, row_format);
+    let caller = caller(&headers);
+    let database = request_database(params.db, &headers);
+
+    let started = std::time::Instant::now();
+    let result = copy_stream(&state, &statement, &caller, database, body).await;
+    let metadata = ExecutionMetadata::new(ExecutionStats::default(), None, started.elapsed());
+    let response = match result {
+        Ok(rows) => ExecuteResponse {
+            status: "ok".to_string(),
+            result: Some(DslOutput::Affected {
+                rows,
+                op: "COPY".to_string(),
+            }),
+            error: None,
+            metadata: Some(metadata),
+        },
+        Err(e) => ExecuteResponse {
+            status: "error".to_string(),
+            result: None,
+            error: Some(e),
+            metadata: Some(metadata),
+        },
+    };
+    render(&params.format, &response)
+}
+
+/// Feed a newline-delimited body to COPY, `COPY_BATCH_ROWS` lines per command.
+/// Each batch commits on its own, so rows of batches before a failing one stay.
+async fn copy_stream(
+    state: &Arc<AppState>,
+    statement: &CopyStatement,
+    caller: &Caller,
+    database: Option<String>,
+    body: Body,
+) -> Result<usize, String> {
+    let mut stream = body.into_data_stream();
+    let mut pending: Vec<u8> = Vec::new();
+    let mut batch: Vec<String> = Vec::new();
+    let mut lines_read = 0;
+    let mut ingested = 0;
+    let mut batches = 0;
+
+    loop {
+        let chunk = stream
+            .next()
+            .await
+            .transpose()
+            .map_err(|e| format!("Failed to read request body: {}", e))?;
+        let done = chunk.is_none();
+        match chunk {
+            Some(bytes) => pending.extend_from_slice(&bytes),
+            // A last row without a trailing newline
+            None if !pending.is_empty() => pending.push(b'\n'),
+            None => {}
+        }
+
+        while let Some(pos) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=pos).collect();
+            lines_read += 1;
+            let line = String::from_utf8(line)
+                .map_err(|_| format!("Line {} is not valid UTF-8", lines_read))?;
+            batch.push(line.trim_end_matches(['\r', '\n']).to_string());
+
+            if batch.len() == COPY_BATCH_ROWS {
+                ingested += run_copy_batch(
+                    state, statement, &batch, caller, &database, lines_read, ingested,
+                )
+                .await?;
+                batches += 1;
+                batch.clear();
+            }
+        }
+
+        if done {
+            // An empty body still runs once so an unknown dataset is reported
+            if !batch.is_empty() || batches == 0 {
+                ingested += run_copy_batch(
+                    state, statement, &batch, caller, &database, lines_read, ingested,
+                )
+                .await?;
+            }
+            return Ok(ingested);
+        }
+    }
+}
+
+async fn run_copy_batch(
+    state: &Arc<AppState>,
+    statement: &CopyStatement,
+    batch: &[String],
+    caller: &Caller,
+    database: &Option<String>,
+    last_line: usize,
+    ingested: usize,
+) -> Result<usize, String> {
+    let command = statement.command(batch.iter().map(String::as_str));
+    let state = state.clone();
+    let caller = caller.clone();
+    let database = database.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        let scope = LockScope::Database(None);
+        state
+            .locks
+            .run(&state.db, scope, database.as_deref(), caller, |db| {
+                execute_line(db, &command, 1)
+            })
+            .0
+    });
+
+    let error = match tokio::time::timeout(std::time::Duration::from_secs(QUERY_TIMEOUT_SECS), task)
+        .await
+    {
+        Ok(Ok(Ok(DslOutput::Affected { rows, .. }))) => return Ok(rows),
+        Ok(Ok(Ok(_))) => return Ok(0),
+        Ok(Ok(Err(e))) => e.to_string(),
+        Ok(Err(e)) => format!("Execution task panicked: {}", e),
+        Err(_) => format!("Query timed out after {}s", QUERY_TIMEOUT_SECS),
+    };
+    Err(format!(
+        "{} (batch of lines {}-{}; {} rows ingested before it)",
+        error,
+        last_line + 1 - batch.len(),
+        last_line,
+        ingested
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/snapshot",
+    params(
+        SnapshotParams
+    ),
+    responses(
+        (status = 200, description = "Tar archive of the database in the SAVE ALL layout", content_type = "application/x-tar"),
+        (status = 400, description = "Unknown database", body = ExecuteResponse),
+        (status = 403, description = "A masking policy of the database applies to the caller's role", body = ExecuteResponse)
+    )
+)]
+async fn export_snapshot(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SnapshotParams>,
+    headers: HeaderMap,
+) -> Response {
+    let database = request_database(params.db, &headers);
+    let api_key = api_key(&headers);
+    let fork_state = state.clone();
+    // Writes to the database wait only while it is copied
+    let forked = tokio::task::spawn_blocking(move || {
+        let state = fork_state;
+        let name = database
+            .unwrap_or_else(|| state.db.read().unwrap().active_database().to_string());
+        let fork = state.locks.read(&state.db, &name, |db| {
+            let fork = db
+                .fork_database(&name)
+                .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+            // The archive holds raw values, which masked callers must not get
+            let role = db.config.security.role_of(api_key.as_deref());
+            let masked = fork.datasets.iter().find(|ds| {
+                ds.metadata
+                    .masking
+                    .values()
+                    .any(|policy| policy.applies_to(Some(&role)))
+            });
+            match masked {
+                Some(ds) if !db.config.security.is_admin(&role) => Err((
+                    StatusCode::FORBIDDEN,
+                    format!(
+                        "Role '{}' may not export database '{}': dataset '{}' has masked columns",
+                        role,
+                        name,
+                        ds.metadata.name.as_deref().unwrap_or_default()
+                    ),
+                )),
+                _ => Ok(fork),
+            }
+        })?;
+
+        static EXPORTS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "linal-snapshot-{}-{}",
+            std::process::id(),
+            EXPORTS.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        if let Err(e) = fork.save(&dir) {
+            let _ = std::fs::remove_dir_all(&dir);
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Failed to write snapshot: {}", e),
+            ));
+        }
+        Ok((fork.name, dir))
+    })
+    .await;
+
+    let (name, dir) = match forked {
+        Ok(Ok(forked)) => forked,
+        Ok(Err((status, e))) => return error_response(status, e),
+        Err(e) => return bad_request(format!("Snapshot task panicked: {}", e)),
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel(4);
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = stream_archive(&dir, &tx) {
+            // Aborts the response so the client does not mistake it for a whole archive
+            let _ = tx.blocking_send(Err(e));
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    });
+    let chunks = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    (
+        StatusCode::OK,
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                "application/x-tar".to_string(),
+            ),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.tar\"", name),
+            ),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response()
+}
+
+type ArchiveChunk = Result<bytes::Bytes, std::io::Error>;
+
+/// Send the files under `dir` as a tar archive, one chunk per header or read
+fn stream_archive(
+    dir: &std::path::Path,
+    tx: &tokio::sync::mpsc::Sender<ArchiveChunk>,
+) -> std::io::Result<()> {
+    use crate::utils::tar;
+    use std::io::Read;
+
+    let send = |chunk: Vec<u8>| {
+        tx.blocking_send(Ok(chunk.into())).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::BrokenPipe, "client disconnected")
+        })
+    };
+    let mtime = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    for (name, path) in tar::list_files(dir)? {
+        let mut file = std::fs::File::open(&path)?;
+        let size = file.metadata()?.len();
+        let header = tar::file_header(&name, size, mtime)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        send(header.to_vec())?;
+
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            send(buf[..n].to_vec())?;
+        }
+        send(vec![0; tar::padding(size)])?;
+    }
+    send(tar::END_OF_ARCHIVE.to_vec())
+}
@@ -0,0 +1,351 @@
+use crate::dsl::lexer::{tokenize, TokenKind};
+use crate::dsl::parser::{classify, Command};
+use crate::dsl::{execute_line, DslError, DslOutput};
+use crate::engine::{DatabaseSnapshot, EngineError, ExecutionStats, TensorDb};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// What a command has to lock while it runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockScope {
+    /// The database list itself (CREATE/DROP/USE DATABASE, SHOW DATABASES,
+    /// and SHOW QUERIES and CANCEL JOB, which wait for no database)
+    Catalog,
+    /// One database: the named one, else the one the request selected
+    Database(Option<String>),
+    /// A read-only query of the request's database, run on its latest
+    /// snapshot without waiting for the commands that hold its lock
+    Snapshot,
+}
+
+impl LockScope {
+    /// Scope of a DSL command. Commands that fail to parse are scoped to the
+    /// request's database and report their error from there.
+    pub fn of_command(command: &str) -> Self {
+        let Ok(Some((kind, _))) = classify(command, 1) else {
+            return LockScope::Database(None);
+        };
+        let tokens = tokenize(command).unwrap_or_default();
+        let word = |i: usize| tokens.get(i).filter(|t| t.kind == TokenKind::Word);
+
+        match kind {
+            Command::CreateDatabase | Command::DropDatabase | Command::Use | Command::CancelJob => {
+                LockScope::Catalog
+            }
+            Command::Show
+                if word(1)
+                    .is_some_and(|t| t.is_keyword("DATABASES") || t.is_keyword("QUERIES"))
+                    || word(2).is_some_and(|t| t.is_keyword("DATABASES")) =>
+            {
+                LockScope::Catalog
+            }
+            Command::SnapshotDatabase | Command::RestoreDatabase => {
+                LockScope::Database(word(2).map(|t| t.text.to_string()))
+            }
+            _ if kind.is_read_only(command) => LockScope::Snapshot,
+            _ => LockScope::Database(None),
+        }
+    }
+}
+
+/// Who a command runs for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller {
+    /// Identity recorded in the audit log
+    pub actor: String,
+    /// Client session, the owner of the transactions it begins
+    pub session: Option<String>,
+}
+
+impl Caller {
+    /// Caller outside of any client session
+    pub fn new(actor: impl Into<String>) -> Self {
+        Self {
+            actor: actor.into(),
+            session: None,
+        }
+    }
+}
+
+/// One read-write lock per database, created on first use. Commands that
+/// may change a database hold its write lock while they run; the catalog's
+/// write lock is taken only for as long as it takes to lend the database out
+/// and to take it back. Readers of the database as it sits in the catalog
+/// (snapshots, forks) share its read lock and the catalog's.
+///
+/// After each write the database's state is published as a snapshot, and
+/// read-only queries run on a copy of the latest one without taking the
+/// lock: they see every command completed before they started and nothing
+/// of the commands still running (nor of an open transaction). The session
+/// that began a transaction reads its own writes instead, so its read-only
+/// queries take the database's lock like any other command.
+#[derive(Default)]
+pub struct DatabaseLocks {
+    locks: Mutex<HashMap<String, Arc<RwLock<()>>>>,
+    snapshots: Mutex<HashMap<String, DatabaseSnapshot>>,
+    /// Open transaction of each database
+    transactions: Mutex<HashMap<String, OpenTransaction>>,
+}
+
+/// Transaction open on a database, as tracked between commands
+struct OpenTransaction {
+    /// Session that began it
+    session: String,
+    /// When that session last ran a command on the database
+    last_used: Instant,
+}
+
+impl DatabaseLocks {
+    fn get(&self, name: &str) -> Arc<RwLock<()>> {
+        self.locks
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Latest snapshot of database `name`. The first read of a database
+    /// takes its read lock once so the database is in the catalog to copy from.
+    fn snapshot(&self, catalog: &RwLock<TensorDb>, name: &str) -> Option<DatabaseSnapshot> {
+        if let Some(snapshot) = self.snapshots.lock().unwrap().get(name) {
+            return Some(snapshot.clone());
+        }
+        let lock = self.get(name);
+        let _guard = lock.read().unwrap_or_else(|e| e.into_inner());
+        let snapshot = catalog.read().unwrap().read_snapshot(name)?;
+        self.snapshots
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), snapshot.clone());
+        Some(snapshot)
+    }
+
+    /// Make the state of `session` what readers of database `name` see
+    fn publish(&self, session: &TensorDb, name: &str) {
+        if let Some(snapshot) = session.read_snapshot(name) {
+            self.snapshots
+                .lock()
+                .unwrap()
+                .insert(name.to_string(), snapshot);
+        }
+    }
+
+    /// Run `read` on database `name` once no command has it lent out,
+    /// holding read locks on it and the catalog for the duration of `read`.
+    /// Other readers go on; writers wait until `read` returns.
+    pub fn read<R>(
+        &self,
+        catalog: &RwLock<TensorDb>,
+        name: &str,
+        read: impl FnOnce(&TensorDb) -> R,
+    ) -> R {
+        let lock = self.get(name);
+        let _guard = lock.read().unwrap_or_else(|e| e.into_inner());
+        let db = catalog.read().unwrap();
+        read(&db)
+    }
+
+    /// Drop the published snapshots, for changes made to the catalog
+    /// outside of `run` (such as TTL expiry). Databases with an open
+    /// transaction keep the snapshot taken before BEGIN, since one taken now
+    /// would hold the transaction's uncommitted writes.
+    pub fn invalidate_snapshots(&self) {
+        let transactions = self.transactions.lock().unwrap();
+        self.snapshots
+            .lock()
+            .unwrap()
+            .retain(|name, _| transactions.contains_key(name));
+    }
+
+    /// Roll back the transactions whose session has run no command on them
+    /// for `idle`, so a client that went away does not keep its database
+    /// from taking writes. Returns the databases rolled back.
+    pub fn roll_back_idle_transactions(
+        &self,
+        catalog: &RwLock<TensorDb>,
+        idle: Duration,
+    ) -> Vec<String> {
+        let is_idle = |name: &str| {
+            self.transactions
+                .lock()
+                .unwrap()
+                .get(name)
+                .is_some_and(|open| open.last_used.elapsed() >= idle)
+        };
+        let expired: Vec<(String, String)> = self
+            .transactions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, open)| open.last_used.elapsed() >= idle)
+            .map(|(name, open)| (name.clone(), open.session.clone()))
+            .collect();
+
+        let mut rolled_back = Vec::new();
+        for (name, session) in expired {
+            let caller = Caller {
+                actor: "system".to_string(),
+                session: Some(session),
+            };
+            let scope = LockScope::Database(Some(name.clone()));
+            let (result, _) = self.run(catalog, scope, None, caller, |db| {
+                // The session may have come back while the lock was awaited
+                if is_idle(&name) {
+                    execute_line(db, "ROLLBACK", 1)
+                } else {
+                    Ok(DslOutput::None)
+                }
+            });
+            if matches!(result, Ok(DslOutput::Message(_))) {
+                rolled_back.push(name);
+            }
+        }
+        rolled_back
+    }
+
+    /// Run `run` under `scope` for `caller`. `database` is the database the
+    /// request selected; `None` means the catalog's active database.
+    pub fn run(
+        &self,
+        catalog: &RwLock<TensorDb>,
+        scope: LockScope,
+        database: Option<&str>,
+        caller: Caller,
+        run: impl FnOnce(&mut TensorDb) -> Result<DslOutput, DslError>,
+    ) -> (Result<DslOutput, DslError>, ExecutionStats) {
+        let target = |named: Option<String>| {
+            named.unwrap_or_else(|| match database {
+                Some(name) => name.to_string(),
+                None => catalog.read().unwrap().active_database().to_string(),
+            })
+        };
+        let target = match scope {
+            LockScope::Catalog => {
+                // Databases may be dropped or replaced, along with their
+                // open transactions
+                let outcome = run_on_catalog(catalog, database, caller, run);
+                let databases = catalog.read().unwrap().list_databases();
+                self.transactions
+                    .lock()
+                    .unwrap()
+                    .retain(|name, _| databases.contains(name));
+                self.invalidate_snapshots();
+                return outcome;
+            }
+            LockScope::Snapshot => {
+                let target = target(None);
+                // Finished background jobs are installed by a command holding
+                // the database, which then publishes them
+                let installing = catalog.read().unwrap().has_finished_jobs(&target);
+                // The session that began a transaction reads its own writes;
+                // any other reads the snapshot from before BEGIN, and never
+                // the database itself, which holds uncommitted writes
+                let owner = self
+                    .transactions
+                    .lock()
+                    .unwrap()
+                    .get(&target)
+                    .map(|open| open.session.clone());
+                let own_transaction = owner.is_some() && owner == caller.session;
+                let foreign_transaction = owner.is_some() && !own_transaction;
+                match self.snapshot(catalog, &target) {
+                    Some(snapshot) if foreign_transaction || (!installing && !own_transaction) => {
+                        return run_on_snapshot(catalog, snapshot, caller, run)
+                    }
+                    None if foreign_transaction => {
+                        let source = EngineError::InvalidOp(format!(
+                            "Database '{}' has a transaction open by another session",
+                            target
+                        ));
+                        let error = DslError::Engine { line: 1, source };
+                        return (Err(error), ExecutionStats::default());
+                    }
+                    // Unknown databases are reported as for any other command
+                    _ => target,
+                }
+            }
+            LockScope::Database(named) => target(named),
+        };
+
+        let lock = self.get(&target);
+        // A panicking command has already put its database back
+        let _guard = lock.write().unwrap_or_else(|e| e.into_inner());
+        let detached = catalog.write().unwrap().detach_session(&target);
+        let Ok(mut session) = detached else {
+            // Unknown databases are reported (or created by RESTORE) by the catalog
+            return run_on_catalog(catalog, database, caller, run);
+        };
+
+        session.set_audit_actor(caller.actor);
+        session.set_session_id(caller.session.clone());
+        if !self.snapshots.lock().unwrap().contains_key(&target) {
+            self.publish(&session, &target);
+        }
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run(&mut session)));
+        let stats = session.execution_stats();
+        // Inside a transaction readers keep the snapshot taken before BEGIN
+        self.publish(&session, &target);
+        {
+            let mut transactions = self.transactions.lock().unwrap();
+            match session.transaction_session() {
+                // Only commands of its own session keep a transaction in use
+                Some(owner) => {
+                    let tracked = matches!(
+                        transactions.get(&target),
+                        Some(open) if open.session == owner
+                    );
+                    if !tracked || caller.session.as_deref() == Some(owner) {
+                        let open = OpenTransaction {
+                            session: owner.to_string(),
+                            last_used: Instant::now(),
+                        };
+                        transactions.insert(target, open);
+                    }
+                }
+                None => {
+                    transactions.remove(&target);
+                }
+            }
+        }
+        catalog.write().unwrap().attach_session(session);
+        match result {
+            Ok(result) => (result, stats),
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+/// Run a read-only command on a session over `snapshot`, which is then dropped
+fn run_on_snapshot(
+    catalog: &RwLock<TensorDb>,
+    snapshot: DatabaseSnapshot,
+    caller: Caller,
+    run: impl FnOnce(&mut TensorDb) -> Result<DslOutput, DslError>,
+) -> (Result<DslOutput, DslError>, ExecutionStats) {
+    let mut session = catalog.read().unwrap().snapshot_session(snapshot);
+    session.set_audit_actor(caller.actor);
+    session.set_session_id(caller.session);
+    let result = run(&mut session);
+    (result, session.execution_stats())
+}
+
+fn run_on_catalog(
+    catalog: &RwLock<TensorDb>,
+    database: Option<&str>,
+    caller: Caller,
+    run: impl FnOnce(&mut TensorDb) -> Result<DslOutput, DslError>,
+) -> (Result<DslOutput, DslError>, ExecutionStats) {
+    let mut db = catalog.write().unwrap();
+    db.set_audit_actor(caller.actor);
+    db.set_session_id(caller.session);
+    db.reset_execution_stats();
+    let result = match database {
+        Some(name) => db
+            .with_database(name, run)
+            .unwrap_or_else(|source| Err(DslError::Engine { line: 1, source })),
+        None => run(&mut db),
+    };
+    (result, db.execution_stats())
+}
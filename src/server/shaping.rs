@@ -0,0 +1,194 @@
+use crate::core::dataset_legacy::Dataset;
+use crate::core::tensor::Tensor;
+use crate::core::tuple::{Field, Schema, Tuple};
+use crate::core::value::{Value, ValueType};
+use crate::dsl::DslOutput;
+use std::sync::Arc;
+
+/// How vector and matrix columns appear in responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorMode {
+    /// Drop vector and matrix columns from result tables
+    Exclude,
+    /// Keep only the first `len` components of every vector
+    Truncate(usize),
+    /// Return vectors unchanged
+    Full,
+}
+
+/// Response shaping options, taken from the `/execute` query string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseShape {
+    /// Decimal places kept for floats, vectors and tensors
+    pub precision: Option<u32>,
+    pub vectors: VectorMode,
+}
+
+impl Default for ResponseShape {
+    fn default() -> Self {
+        Self {
+            precision: None,
+            vectors: VectorMode::Exclude,
+        }
+    }
+}
+
+impl ResponseShape {
+    /// Build from `vectors=exclude|truncate|full`, `vector_len` and `precision`
+    pub fn from_params(
+        vectors: Option<&str>,
+        vector_len: Option<usize>,
+        precision: Option<u32>,
+    ) -> Result<Self, String> {
+        let vectors = match vectors.unwrap_or("exclude") {
+            "exclude" => VectorMode::Exclude,
+            "truncate" => VectorMode::Truncate(vector_len.unwrap_or(8)),
+            "full" => VectorMode::Full,
+            other => {
+                return Err(format!(
+                    "Unknown vectors mode '{}' (expected 'exclude', 'truncate' or 'full')",
+                    other
+                ))
+            }
+        };
+        Ok(Self { precision, vectors })
+    }
+
+    /// Apply the options to a command result
+    pub fn apply(&self, output: DslOutput) -> DslOutput {
+        match output {
+            DslOutput::Table(ds) => DslOutput::Table(self.shape_dataset(&ds)),
+            DslOutput::Tensor(tensor) if self.precision.is_some() => {
+                DslOutput::Tensor(self.shape_tensor(tensor))
+            }
+            other => other,
+        }
+    }
+
+    fn shape_dataset(&self, ds: &Dataset) -> Dataset {
+        let keep: Vec<usize> = ds
+            .schema
+            .fields
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| {
+                self.vectors != VectorMode::Exclude
+                    || !matches!(f.value_type, ValueType::Vector(_) | ValueType::Matrix(_, _))
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        let fields: Vec<Field> = keep
+            .iter()
+            .map(|&i| {
+                let mut field = ds.schema.fields[i].clone();
+                if let (VectorMode::Truncate(len), ValueType::Vector(dim)) =
+                    (self.vectors, &field.value_type)
+                {
+                    field.value_type = ValueType::Vector((*dim).min(len));
+                }
+                field
+            })
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+
+        // Rows are built directly: truncated vectors no longer match the source dims
+        let rows: Vec<Tuple> = ds
+            .rows
+            .iter()
+            .map(|row| Tuple {
+                schema: schema.clone(),
+                values: keep
+                    .iter()
+                    .map(|&i| self.shape_value(row.values[i].clone()))
+                    .collect(),
+            })
+            .collect();
+
+        let mut shaped = Dataset::new(ds.id, schema.clone(), ds.metadata.name.clone());
+        shaped.metadata.extra = ds.metadata.extra.clone();
+        shaped.metadata.update_stats(&schema, &rows);
+        shaped.rows = rows;
+        shaped
+    }
+
+    fn shape_value(&self, value: Value) -> Value {
+        match value {
+            Value::Float(f) => Value::Float(self.round(f)),
+            Value::Vector(mut v) => {
+                if let VectorMode::Truncate(len) = self.vectors {
+                    v.truncate(len);
+                }
+                Value::Vector(v.into_iter().map(|x| self.round(x)).collect())
+            }
+            Value::Matrix(m) => Value::Matrix(
+                m.into_iter()
+                    .map(|r| r.into_iter().map(|x| self.round(x)).collect())
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    fn shape_tensor(&self, tensor: Tensor) -> Tensor {
+        let data = tensor.data.iter().map(|x| self.round(*x)).collect();
+        Tensor {
+            data: Arc::new(data),
+            ..tensor
+        }
+    }
+
+    fn round(&self, x: f32) -> f32 {
+        match self.precision {
+            Some(p) => {
+                let scale = 10f64.powi(p as i32);
+                ((x as f64 * scale).round() / scale) as f32
+            }
+            None => x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::dataset_legacy::DatasetId;
+
+    fn sample() -> Dataset {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", ValueType::Int),
+            Field::new("score", ValueType::Float),
+            Field::new("emb", ValueType::Vector(4)),
+        ]));
+        let row = Tuple::new(
+            schema.clone(),
+            vec![
+                Value::Int(1),
+                Value::Float(0.123456),
+                Value::Vector(vec![0.11111, 0.2, 0.3, 0.4]),
+            ],
+        )
+        .unwrap();
+        Dataset::with_rows(DatasetId(0), schema, vec![row], Some("hits".into())).unwrap()
+    }
+
+    #[test]
+    fn test_exclude_vectors_and_round_floats() {
+        let shape = ResponseShape {
+            precision: Some(2),
+            vectors: VectorMode::Exclude,
+        };
+        let ds = shape.shape_dataset(&sample());
+        assert_eq!(ds.schema.fields.len(), 2);
+        assert_eq!(ds.rows[0].values, vec![Value::Int(1), Value::Float(0.12)]);
+    }
+
+    #[test]
+    fn test_truncate_vectors() {
+        let shape = ResponseShape::from_params(Some("truncate"), Some(2), Some(1)).unwrap();
+        let ds = shape.shape_dataset(&sample());
+        assert_eq!(ds.schema.fields[2].value_type, ValueType::Vector(2));
+        assert_eq!(ds.rows[0].values[2], Value::Vector(vec![0.1, 0.2]));
+        assert!(ResponseShape::from_params(Some("some"), None, None).is_err());
+    }
+}
@@ -1 +1,3 @@
 pub mod parsing;
+pub mod rng;
+pub mod tar;
@@ -35,3 +35,203 @@ pub fn parse_f32_list(text: &str) -> Result<Vec<f32>, String> {
     }
     Ok(out)
 }
+
+/// Tracks whether a left-to-right scan is inside a `"..."` literal.
+/// A backslash inside a literal escapes the character after it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct QuoteState {
+    in_string: bool,
+    escaped: bool,
+}
+
+impl QuoteState {
+    /// Advance past `ch`; returns whether it belongs to a string literal (quotes included)
+    pub fn step(&mut self, ch: char) -> bool {
+        if !self.in_string {
+            self.in_string = ch == '"';
+            return self.in_string;
+        }
+        if self.escaped {
+            self.escaped = false;
+        } else if ch == '\\' {
+            self.escaped = true;
+        } else if ch == '"' {
+            self.in_string = false;
+        }
+        true
+    }
+
+    pub fn in_string(&self) -> bool {
+        self.in_string
+    }
+}
+
+/// Byte offset of the first `pat` in `s` outside string literals
+pub fn find_unquoted(s: &str, pat: &str) -> Option<usize> {
+    let mut quotes = QuoteState::default();
+    s.char_indices()
+        .find(|&(i, ch)| !quotes.step(ch) && s[i..].starts_with(pat))
+        .map(|(i, _)| i)
+}
+
+/// `(byte offset, char)` of every character of `s` outside string literals
+pub fn unquoted_char_indices(s: &str) -> Vec<(usize, char)> {
+    let mut quotes = QuoteState::default();
+    s.char_indices()
+        .filter(|&(_, ch)| !quotes.step(ch))
+        .collect()
+}
+
+/// Decode `s` if it is exactly one `"..."` literal; `None` when it is not quoted
+pub fn parse_string_literal(s: &str) -> Option<Result<String, String>> {
+    if !s.starts_with('"') {
+        return None;
+    }
+    let mut quotes = QuoteState::default();
+    let end = s
+        .char_indices()
+        .find(|&(i, ch)| {
+            quotes.step(ch);
+            i > 0 && !quotes.in_string()
+        })
+        .map(|(i, _)| i);
+    match end {
+        Some(end) if end == s.len() - 1 => Some(unescape_string(&s[1..end])),
+        Some(_) => None,
+        None => Some(Err(format!("Unterminated string literal: {}", s))),
+    }
+}
+
+/// Parse `s` if it is an integer literal (optional sign, then digits);
+/// `None` when it is not one, an error when it does not fit in an i64
+pub fn parse_int(s: &str) -> Option<Result<i64, String>> {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(s.parse::<i64>().map_err(|_| {
+        format!(
+            "Integer {} is out of range for Int (must be between {} and {}); store it as a String or Float",
+            s,
+            i64::MIN,
+            i64::MAX
+        )
+    }))
+}
+
+/// RFC 3339 timestamp, `YYYY-MM-DD[ |T]HH:MM:SS[.f]` taken as UTC, or a
+/// bare `YYYY-MM-DD` date at midnight UTC
+pub fn parse_timestamp(s: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(ts) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Some(ts.with_timezone(&chrono::Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(|ts| ts.and_utc())
+}
+
+/// Seconds in an interval such as `"1 day"` or `"2 hours 30 minutes"`.
+/// Units run from seconds to weeks, singular or plural.
+pub fn parse_interval(s: &str) -> Result<i64, String> {
+    let words: Vec<&str> = s.split_whitespace().collect();
+    if words.is_empty() || !words.len().is_multiple_of(2) {
+        return Err(format!(
+            "Invalid interval: '{}'. Expected e.g. '1 day' or '2 hours 30 minutes'",
+            s
+        ));
+    }
+    let mut total: i64 = 0;
+    for pair in words.chunks(2) {
+        let count: i64 = pair[0]
+            .parse()
+            .map_err(|_| format!("Invalid interval count: {}", pair[0]))?;
+        let unit = pair[1].to_lowercase();
+        let seconds = match unit.strip_suffix('s').unwrap_or(&unit) {
+            "second" | "sec" => 1,
+            "minute" | "min" => 60,
+            "hour" => 3_600,
+            "day" => 86_400,
+            "week" => 604_800,
+            _ => return Err(format!("Unknown interval unit: {}", pair[1])),
+        };
+        total = count
+            .checked_mul(seconds)
+            .and_then(|s| total.checked_add(s))
+            .ok_or_else(|| format!("Interval out of range: '{}'", s))?;
+    }
+    Ok(total)
+}
+
+/// Decode the body of a `"..."` literal: `\"`, `\\`, `\n`, `\t`, `\r` and
+/// `\uXXXX` (surrogate pairs combine). Other escapes are kept as written, so
+/// regex patterns such as `"\d+"` need no doubling.
+pub fn unescape_string(content: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('u') => {
+                let mut code = read_hex4(&mut chars)?;
+                if (0xD800..0xDC00).contains(&code) {
+                    let mut rest = chars.clone();
+                    if rest.next() == Some('\\') && rest.next() == Some('u') {
+                        let low = read_hex4(&mut rest)?;
+                        if (0xDC00..0xE000).contains(&low) {
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            chars = rest;
+                        }
+                    }
+                }
+                let decoded = char::from_u32(code)
+                    .ok_or_else(|| format!("Invalid unicode escape \\u{:04X}", code))?;
+                out.push(decoded);
+            }
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => return Err("String literal ends with a lone backslash".to_string()),
+        }
+    }
+    Ok(out)
+}
+
+fn read_hex4(chars: &mut impl Iterator<Item = char>) -> Result<u32, String> {
+    let digits: String = chars.take(4).collect();
+    if digits.len() != 4 {
+        return Err(format!("Expected 4 hex digits after \\u, got '{}'", digits));
+    }
+    u32::from_str_radix(&digits, 16).map_err(|_| format!("Invalid unicode escape \\u{}", digits))
+}
+
+/// Body of a `"..."` literal that `unescape_string` reads back as `s`
+pub fn escape_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
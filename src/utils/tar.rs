@@ -0,0 +1,90 @@
+//! Minimal writer for uncompressed POSIX (ustar) archives, enough to stream
+//! a snapshot directory to a client that unpacks it with `tar -xf`.
+
+use std::path::{Path, PathBuf};
+
+/// Archives are written in blocks of this many bytes
+pub const BLOCK: usize = 512;
+
+/// Two zero blocks close an archive
+pub const END_OF_ARCHIVE: [u8; 2 * BLOCK] = [0; 2 * BLOCK];
+
+/// Header block of a regular file `name` holding `size` bytes. Names longer
+/// than 100 bytes are split into the ustar prefix at a `/`.
+pub fn file_header(name: &str, size: u64, mtime: u64) -> Result<[u8; BLOCK], String> {
+    let (prefix, name) = split_name(name)?;
+    let mut header = [0u8; BLOCK];
+    put(&mut header[0..100], name.as_bytes());
+    put_octal(&mut header[100..108], 0o644);
+    put_octal(&mut header[108..116], 0);
+    put_octal(&mut header[116..124], 0);
+    put_octal(&mut header[124..136], size);
+    put_octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    put(&mut header[257..263], b"ustar\0");
+    put(&mut header[263..265], b"00");
+    put(&mut header[345..500], prefix.as_bytes());
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].fill(b' ');
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+    put(
+        &mut header[148..156],
+        format!("{:06o}\0 ", checksum).as_bytes(),
+    );
+    Ok(header)
+}
+
+/// Zero bytes that pad `size` bytes of file content to a whole block
+pub fn padding(size: u64) -> usize {
+    (BLOCK - (size as usize % BLOCK)) % BLOCK
+}
+
+/// Regular files under `root`, recursively, as (archive name, path) pairs
+/// sorted by name. Archive names use `/` and are relative to `root`.
+pub fn list_files(root: &Path) -> std::io::Result<Vec<(String, PathBuf)>> {
+    fn walk(root: &Path, dir: &Path, out: &mut Vec<(String, PathBuf)>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(root, &path, out)?;
+            } else {
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                let name: Vec<String> = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect();
+                out.push((name.join("/"), path));
+            }
+        }
+        Ok(())
+    }
+    let mut files = Vec::new();
+    walk(root, root, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn split_name(name: &str) -> Result<(&str, &str), String> {
+    if name.len() <= 100 {
+        return Ok(("", name));
+    }
+    name.char_indices()
+        .filter(|&(i, c)| c == '/' && i <= 155 && name.len() - i - 1 <= 100)
+        .map(|(i, _)| (&name[..i], &name[i + 1..]))
+        .next()
+        .ok_or_else(|| format!("Path too long for a tar archive: {}", name))
+}
+
+fn put(field: &mut [u8], bytes: &[u8]) {
+    field[..bytes.len()].copy_from_slice(bytes);
+}
+
+/// Zero-padded octal number filling all but the field's last byte (NUL)
+fn put_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    put(
+        field,
+        format!("{:0width$o}", value, width = digits).as_bytes(),
+    );
+}
@@ -0,0 +1,74 @@
+use linal::core::config::ServerConfig;
+use linal::server::admission::Admission;
+use std::time::Duration;
+
+fn admission(max_concurrent_queries: usize, max_queued_queries: usize) -> Admission {
+    Admission::new(&ServerConfig {
+        max_concurrent_queries,
+        max_queued_queries,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_unlimited_admission_never_queues() {
+    let admission = admission(0, 0);
+    let mut permits = Vec::new();
+    for _ in 0..100 {
+        permits.push(admission.enqueue().unwrap().admit().await);
+    }
+    assert_eq!(admission.queued(), 0);
+}
+
+#[tokio::test]
+async fn test_full_queue_rejects_commands() {
+    let admission = admission(1, 1);
+    let running = admission.enqueue().unwrap().admit().await;
+
+    let waiting = admission.enqueue().unwrap();
+    assert_eq!(admission.queued(), 1);
+    assert!(admission.enqueue().is_none());
+
+    // The queued command gets the slot once the running one finishes
+    let admitting = tokio::spawn(waiting.admit());
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!admitting.is_finished());
+    drop(running);
+    let _next = tokio::time::timeout(Duration::from_secs(5), admitting)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(admission.queued(), 0);
+    assert!(admission.enqueue().is_some());
+}
+
+#[tokio::test]
+async fn test_abandoned_tickets_leave_the_queue() {
+    let admission = admission(1, 2);
+    let _running = admission.enqueue().unwrap().admit().await;
+
+    let first = admission.enqueue().unwrap();
+    let _second = admission.enqueue().unwrap();
+    assert!(admission.enqueue().is_none());
+
+    // A request that times out while queued frees its place
+    let timed_out = tokio::time::timeout(Duration::from_millis(20), first.admit()).await;
+    assert!(timed_out.is_err());
+    assert_eq!(admission.queued(), 1);
+    assert!(admission.enqueue().is_some());
+}
+
+#[tokio::test]
+async fn test_server_config_defaults() {
+    let config = ServerConfig::default();
+    assert_eq!(config.max_concurrent_queries, 0);
+    assert_eq!(config.max_queued_queries, 64);
+    assert_eq!(config.retry_after_secs, 1);
+
+    let parsed: linal::core::config::EngineConfig = toml::from_str(
+        "[storage]\ndata_dir = \"./data\"\ndefault_db = \"default\"\n\n[server]\nmax_concurrent_queries = 4\n",
+    )
+    .unwrap();
+    assert_eq!(parsed.server.max_concurrent_queries, 4);
+    assert_eq!(parsed.server.max_queued_queries, 64);
+}
@@ -32,6 +32,7 @@ fn test_aggregation_execution_workflow() {
             func: AggregateFunction::Sum,
             expr: Box::new(Expr::Column("amount".to_string())),
         }],
+        rollup: false,
     };
 
     let planner = Planner::new(&db);
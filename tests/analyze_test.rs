@@ -0,0 +1,194 @@
+use linal::core::config::{EngineConfig, ExecutionConfig, NonFinitePolicy};
+use linal::core::dataset_legacy::ColumnStats;
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, execute_sql, DslOutput};
+use linal::engine::TensorDb;
+
+fn stats(db: &TensorDb, dataset: &str, column: &str) -> ColumnStats {
+    db.get_dataset(dataset).unwrap().metadata.column_stats[column].clone()
+}
+
+#[test]
+fn test_inserts_update_stats_incrementally() {
+    let mut db = TensorDb::new();
+    execute_sql(&mut db, "CREATE TABLE t (id INT, score FLOAT)", 1).unwrap();
+    for i in 0..50 {
+        let score = if i % 10 == 0 {
+            "NULL".to_string()
+        } else {
+            format!("{}.5", 100 - i)
+        };
+        let sql = format!("INSERT INTO t VALUES ({}, {})", i, score);
+        execute_sql(&mut db, &sql, i + 2).unwrap();
+    }
+
+    let dataset = db.get_dataset("t").unwrap();
+    assert_eq!(dataset.metadata.row_count, 50);
+    let id = stats(&db, "t", "id");
+    assert_eq!(
+        (id.min, id.max),
+        (Some(Value::Int(0)), Some(Value::Int(49)))
+    );
+    let score = stats(&db, "t", "score");
+    assert_eq!(score.null_count, 5);
+    assert_eq!(score.min, Some(Value::Float(51.5)));
+    assert_eq!(score.max, Some(Value::Float(99.5)));
+
+    // A full recomputation agrees with the running totals
+    let mut recomputed = dataset.metadata.clone();
+    recomputed.update_stats(&dataset.schema, &dataset.rows);
+    let full = &recomputed.column_stats["score"];
+    assert_eq!(full.null_count, score.null_count);
+    assert_eq!((&full.min, &full.max), (&score.min, &score.max));
+}
+
+#[test]
+fn test_analyze_tightens_bounds_after_upserts() {
+    let mut db = TensorDb::new();
+    execute_script(
+        &mut db,
+        r#"
+        DATASET users COLUMNS (id: Int PRIMARY KEY, age: Int)
+        INSERT INTO users VALUES (1, 90), (2, 30), (3, 20)
+        UPSERT INTO users VALUES (1, 40)
+        UPSERT INTO users VALUES (3, 35)
+        "#,
+    )
+    .unwrap();
+
+    // The replaced extremes linger until the next full scan
+    let age = stats(&db, "users", "age");
+    assert_eq!(age.min, Some(Value::Int(20)));
+    assert_eq!(age.max, Some(Value::Int(90)));
+
+    match execute_line(&mut db, "ANALYZE users", 10).unwrap() {
+        DslOutput::Message(msg) => assert_eq!(msg, "Analyzed dataset 'users': 3 rows"),
+        other => panic!("Expected a message, got {:?}", other),
+    }
+    let age = stats(&db, "users", "age");
+    assert_eq!(age.min, Some(Value::Int(30)));
+    assert_eq!(age.max, Some(Value::Int(40)));
+}
+
+#[test]
+fn test_incremental_stats_keep_the_non_finite_policy() {
+    let config = EngineConfig {
+        execution: ExecutionConfig {
+            non_finite: NonFinitePolicy::Skip,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+    execute_line(&mut db, "DATASET t COLUMNS (x: Float)", 1).unwrap();
+    execute_line(&mut db, "INSERT INTO t VALUES (2.0), (5.0)", 2).unwrap();
+    execute_line(&mut db, "INSERT INTO t VALUES (1.0)", 3).unwrap();
+
+    let dataset = db.get_dataset("t").unwrap();
+    assert_eq!(dataset.metadata.non_finite, NonFinitePolicy::Skip);
+    let x = stats(&db, "t", "x");
+    assert_eq!(x.min, Some(Value::Float(1.0)));
+    assert_eq!(x.max, Some(Value::Float(5.0)));
+}
+
+#[test]
+fn test_analyze_errors() {
+    let mut db = TensorDb::new();
+    assert!(execute_line(&mut db, "ANALYZE missing", 1).is_err());
+    assert!(execute_line(&mut db, "ANALYZE", 2).is_err());
+    assert!(execute_line(&mut db, "ANALYZE a b", 3).is_err());
+
+    // EXPLAIN ANALYZE is unaffected by the new command
+    execute_line(&mut db, "DATASET t COLUMNS (id: Int)", 4).unwrap();
+    assert!(execute_line(&mut db, "EXPLAIN ANALYZE SELECT id FROM t", 5).is_ok());
+}
+
+#[test]
+fn test_analyze_builds_distinct_counts_and_histograms() {
+    let mut db = TensorDb::new();
+    execute_line(&mut db, "DATASET t COLUMNS (id: Int, region: String)", 1).unwrap();
+    let values: Vec<String> = (0..1000)
+        .map(|i| format!("({}, \"r{}\")", i, i % 4))
+        .collect();
+    let insert = format!("INSERT INTO t VALUES {}", values.join(", "));
+    execute_line(&mut db, &insert, 2).unwrap();
+
+    // Appends keep the cheap statistics only
+    let id = stats(&db, "t", "id");
+    assert!(id.distinct_count.is_none() && id.histogram.is_none());
+    assert_eq!(id.selectivity("<", &Value::Int(100), 1000), None);
+
+    execute_line(&mut db, "ANALYZE t", 3).unwrap();
+    let id = stats(&db, "t", "id");
+    let distinct = id.distinct_count.unwrap() as f64;
+    assert!((distinct - 1000.0).abs() / 1000.0 < 0.05, "{}", distinct);
+    let histogram = id.histogram.clone().unwrap();
+    assert_eq!(histogram.counts.iter().sum::<usize>(), 1000);
+    assert_eq!(histogram.bounds.last(), Some(&Value::Int(999)));
+
+    let close = |estimate: Option<f64>, expected: f64| {
+        let estimate = estimate.unwrap();
+        assert!(
+            (estimate - expected).abs() < 0.02,
+            "{} vs {}",
+            estimate,
+            expected
+        );
+    };
+    close(id.selectivity("<", &Value::Int(100), 1000), 0.1);
+    close(id.selectivity(">=", &Value::Int(750), 1000), 0.25);
+    close(id.selectivity("=", &Value::Int(7), 1000), 0.001);
+    close(id.selectivity("=", &Value::Int(5000), 1000), 0.0);
+
+    let region = stats(&db, "t", "region");
+    assert_eq!(region.distinct_count, Some(4));
+    close(
+        region.selectivity("=", &Value::String("r1".into()), 1000),
+        0.25,
+    );
+    // Equal values never span two buckets
+    assert_eq!(region.histogram.unwrap().counts, vec![250; 4]);
+}
+
+#[test]
+fn test_filter_selectivity_guides_join_strategy() {
+    let mut db = TensorDb::new();
+    db.config.execution.broadcast_join_threshold = 20;
+    execute_script(
+        &mut db,
+        r#"
+        DATASET a COLUMNS (id: Int, score: Float)
+        DATASET b COLUMNS (id: Int, a_id: Int)
+        "#,
+    )
+    .unwrap();
+    let a: Vec<String> = (0..200).map(|i| format!("({}, {}.0)", i, i)).collect();
+    let b: Vec<String> = (0..200).map(|i| format!("({}, {})", i, i % 50)).collect();
+    execute_line(
+        &mut db,
+        &format!("INSERT INTO a VALUES {}", a.join(", ")),
+        3,
+    )
+    .unwrap();
+    execute_line(
+        &mut db,
+        &format!("INSERT INTO b VALUES {}", b.join(", ")),
+        4,
+    )
+    .unwrap();
+
+    let query = "EXPLAIN WITH low AS (SELECT id FROM a WHERE score < 10) \
+                 SELECT x.id, y.id FROM low x JOIN b y ON x.id = y.a_id";
+    let explain = |db: &mut TensorDb| match execute_line(db, query, 5).unwrap() {
+        DslOutput::Message(plan) => plan,
+        other => panic!("Expected plan message, got {:?}", other),
+    };
+
+    // Without a histogram the filter keeps all 200 rows of `a`
+    let plan = explain(&mut db);
+    assert!(plan.contains("Partitioned"), "{}", plan);
+
+    execute_line(&mut db, "ANALYZE a", 6).unwrap();
+    let plan = explain(&mut db);
+    assert!(plan.contains("build_left: true"), "{}", plan);
+}
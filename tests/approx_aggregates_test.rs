@@ -0,0 +1,101 @@
+use linal::core::tuple::Tuple;
+use linal::core::value::Value;
+use linal::dsl::{execute_line, DslOutput};
+use linal::engine::TensorDb;
+
+/// 20,000 rows: `user` takes 5,000 distinct values, `latency` runs 0..10_000
+/// twice, and `region` splits the rows in half
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    execute_line(
+        &mut db,
+        "DATASET hits COLUMNS (user: Int, region: String, latency: Float)",
+        1,
+    )
+    .unwrap();
+    let schema = db.get_dataset("hits").unwrap().schema.clone();
+    let tuples = (0..20_000)
+        .map(|i| {
+            let region = if i % 2 == 0 { "eu" } else { "us" };
+            let values = vec![
+                Value::Int(i % 5_000),
+                Value::String(region.to_string()),
+                Value::Float(((i * 7_919) % 10_000) as f32),
+            ];
+            Tuple::new(schema.clone(), values).unwrap()
+        })
+        .collect();
+    db.insert_rows("hits", tuples).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows.iter().map(|r| r.values.clone()).collect(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn close(value: &Value, expected: f32, tolerance: f32) {
+    let actual = value.as_float().unwrap();
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {} ± {}, got {}",
+        expected,
+        tolerance,
+        actual
+    );
+}
+
+#[test]
+fn test_approx_count_distinct() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT APPROX_COUNT_DISTINCT(user) AS users, COUNT(DISTINCT user) AS exact FROM hits",
+    );
+    assert_eq!(result[0][1], Value::Int(5_000));
+    close(&result[0][0], 5_000.0, 5_000.0 * 0.05);
+    assert!(matches!(result[0][0], Value::Int(_)));
+}
+
+#[test]
+fn test_approx_quantile() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT APPROX_QUANTILE(latency, 0.5), APPROX_QUANTILE(latency, 0.99), APPROX_QUANTILE(latency, 0) FROM hits",
+    );
+    close(&result[0][0], 5_000.0, 100.0);
+    close(&result[0][1], 9_900.0, 50.0);
+    assert_eq!(result[0][2], Value::Float(0.0));
+}
+
+#[test]
+fn test_approx_aggregates_per_group() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT region, APPROX_COUNT_DISTINCT(user) AS users, APPROX_QUANTILE(latency, 0.5) AS p50 FROM hits GROUP BY region ORDER BY region",
+    );
+    assert_eq!(result.len(), 2);
+    for row in &result {
+        // Even and odd users each make up half of the 5,000
+        close(&row[1], 2_500.0, 2_500.0 * 0.05);
+        close(&row[2], 5_000.0, 150.0);
+    }
+}
+
+#[test]
+fn test_approx_aggregates_in_sql_and_errors() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SQL SELECT APPROX_COUNT_DISTINCT(region), APPROX_QUANTILE(latency, 0.25) FROM hits",
+    );
+    assert_eq!(result[0][0], Value::Int(2));
+    close(&result[0][1], 2_500.0, 100.0);
+
+    assert!(execute_line(&mut db, "SELECT APPROX_QUANTILE(latency, 1.5) FROM hits", 1).is_err());
+    assert!(execute_line(&mut db, "SELECT APPROX_QUANTILE(latency) FROM hits", 1).is_err());
+}
@@ -0,0 +1,114 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET trades COLUMNS (id: Int, sym: String, at: Int)
+    INSERT INTO trades VALUES (1, "AAA", 5), (2, "BBB", 12), (3, "AAA", 20), (4, "AAA", 1), (5, "CCC", 30), (6, "AAA", 10)
+    DATASET quotes COLUMNS (sym: String, at: Int, price: Float)
+    INSERT INTO quotes VALUES ("AAA", 10, 101.0), ("AAA", 2, 100.0), ("BBB", 11, 50.0), ("AAA", 15, 102.0), ("BBB", 13, 51.0), ("AAA", 10, 101.5)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn matched(id: i64, price: Option<f32>) -> Vec<Value> {
+    vec![Value::Int(id), price.map_or(Value::Null, Value::Float)]
+}
+
+#[test]
+fn test_asof_join_takes_latest_earlier_row_per_key() {
+    let mut db = setup();
+    let out = rows(
+        &mut db,
+        "SELECT t.id, q.price FROM trades t ASOF JOIN quotes q ON t.sym = q.sym AND t.at >= q.at",
+    );
+    // Trade 4 predates every AAA quote and CCC has none; of the two quotes
+    // at 10 the later inserted one wins
+    assert_eq!(
+        out,
+        vec![
+            matched(1, Some(100.0)),
+            matched(2, Some(50.0)),
+            matched(3, Some(102.0)),
+            matched(6, Some(101.5)),
+        ]
+    );
+}
+
+#[test]
+fn test_asof_left_join_keeps_unmatched_rows() {
+    let mut db = setup();
+    let out = rows(
+        &mut db,
+        "SELECT t.id, q.price FROM trades t ASOF LEFT JOIN quotes q ON q.sym = t.sym AND q.at <= t.at WHERE t.sym != \"BBB\" ORDER BY t.id",
+    );
+    assert_eq!(
+        out,
+        vec![
+            matched(1, Some(100.0)),
+            matched(3, Some(102.0)),
+            matched(4, None),
+            matched(5, None),
+            matched(6, Some(101.5)),
+        ]
+    );
+}
+
+#[test]
+fn test_asof_join_with_timestamps_and_aggregates() {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET events COLUMNS (user: Int, at: Timestamp)
+    INSERT INTO events VALUES (1, "2024-03-01T12:00:00Z"), (1, "2024-03-05T12:00:00Z"), (2, "2024-03-02T00:00:00Z")
+    DATASET plans COLUMNS (user: Int, since: Timestamp, tier: String)
+    INSERT INTO plans VALUES (1, "2024-02-01", "free"), (1, "2024-03-03", "pro"), (2, "2024-01-01", "pro")
+    "#;
+    execute_script(&mut db, script).unwrap();
+    let out = rows(
+        &mut db,
+        "SELECT p.tier, COUNT(*) AS n FROM events e ASOF JOIN plans p ON e.user = p.user AND e.at >= p.since GROUP BY p.tier ORDER BY p.tier",
+    );
+    assert_eq!(
+        out,
+        vec![
+            vec![Value::String("free".into()), Value::Int(1)],
+            vec![Value::String("pro".into()), Value::Int(2)],
+        ]
+    );
+}
+
+#[test]
+fn test_asof_join_errors() {
+    let mut db = setup();
+    // The time condition is required
+    assert!(execute_line(
+        &mut db,
+        "SELECT t.id FROM trades t ASOF JOIN quotes q ON t.sym = q.sym",
+        1
+    )
+    .is_err());
+    // The left row's time must be the later one
+    assert!(execute_line(
+        &mut db,
+        "SELECT t.id FROM trades t ASOF JOIN quotes q ON t.sym = q.sym AND q.at >= t.at",
+        1
+    )
+    .is_err());
+    assert!(execute_line(
+        &mut db,
+        "SELECT t.id FROM trades t ASOF JOIN quotes q ON t.sym = q.sym AND t.at > q.at",
+        1
+    )
+    .is_err());
+    assert!(execute_line(&mut db, "SELECT t.id FROM trades t ASOF quotes q", 1).is_err());
+}
@@ -0,0 +1,120 @@
+use linal::core::config::{AuditConfig, EngineConfig, StorageConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use std::fs;
+use std::path::PathBuf;
+
+fn audit_rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).expect("Query failed") {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_audit_log_records_mutations_with_row_delta() {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String)
+    INSERT INTO users VALUES (1, "Alice")
+    INSERT INTO users VALUES (2, "Bob")
+    SELECT * FROM users
+    VECTOR v = [1.0, 2.0]
+    "#;
+    execute_script(&mut db, script).unwrap();
+
+    // Reads are not audited; each mutation is
+    let rows = audit_rows(
+        &mut db,
+        "SELECT command, datasets, rows_before, rows_after, row_delta, actor FROM system.audit_log",
+    );
+    assert_eq!(rows.len(), 4);
+    assert_eq!(
+        rows[2],
+        vec![
+            Value::String("INSERT INTO users VALUES (2, \"Bob\")".to_string()),
+            Value::String("users".to_string()),
+            Value::Int(1),
+            Value::Int(2),
+            Value::Int(1),
+            Value::String("local".to_string()),
+        ]
+    );
+    // Tensor commands touch no dataset
+    assert_eq!(rows[3][1], Value::String(String::new()));
+    assert_eq!(rows[3][4], Value::Int(0));
+
+    // Failed commands are recorded with their error
+    assert!(execute_line(&mut db, "INSERT INTO users VALUES (3)", 1).is_err());
+    let rows = audit_rows(
+        &mut db,
+        "SELECT row_delta, error FROM system.audit_log WHERE success = false",
+    );
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0][0], Value::Int(0));
+    assert!(matches!(&rows[0][1], Value::String(e) if e.contains("Expected 2 values")));
+
+    // The audit log is read-only
+    assert!(execute_line(&mut db, "INSERT INTO system.audit_log VALUES (1)", 1).is_err());
+}
+
+#[test]
+fn test_audit_log_actor_and_export() {
+    let temp_dir = "/tmp/linal_test_audit_export";
+    let _ = fs::remove_dir_all(temp_dir);
+
+    let mut db = TensorDb::new();
+    db.set_audit_actor("key:abcd****");
+    execute_line(&mut db, "DATASET t COLUMNS (x: Int)", 1).unwrap();
+
+    execute_line(
+        &mut db,
+        &format!(
+            r#"EXPORT (SELECT actor, command FROM system.audit_log) TO "{}/audit.csv""#,
+            temp_dir
+        ),
+        1,
+    )
+    .unwrap();
+    let csv = fs::read_to_string(format!("{}/audit.csv", temp_dir)).unwrap();
+    assert!(csv.starts_with("actor,command"));
+    assert!(csv.contains("key:abcd****,DATASET t COLUMNS (x: Int)"));
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_audit_log_persists_across_restarts() {
+    let temp_dir = "/tmp/linal_test_audit_persist";
+    let _ = fs::remove_dir_all(temp_dir);
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            default_db: "default".to_string(),
+            auto_persist: false,
+            wal: false,
+            ..Default::default()
+        },
+        audit: AuditConfig { persist: true },
+        ..Default::default()
+    };
+
+    {
+        let mut db = TensorDb::with_config(config.clone());
+        execute_line(&mut db, "DATASET t COLUMNS (x: Int)", 1).unwrap();
+        execute_line(&mut db, "INSERT INTO t VALUES (1)", 2).unwrap();
+    }
+
+    let mut db = TensorDb::with_config(config);
+    let rows = audit_rows(&mut db, "SELECT command FROM system.audit_log");
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::String("DATASET t COLUMNS (x: Int)".to_string())],
+            vec![Value::String("INSERT INTO t VALUES (1)".to_string())],
+        ]
+    );
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
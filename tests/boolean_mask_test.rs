@@ -0,0 +1,112 @@
+use linal::core::config::EngineConfig;
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn run(script: &str) -> TensorDb {
+    let mut db = TensorDb::with_config(EngineConfig::default());
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn tensor(db: &TensorDb, name: &str) -> (Vec<usize>, Vec<f32>) {
+    let t = db.get(name).unwrap();
+    (t.shape.dims.clone(), t.data.to_vec())
+}
+
+#[test]
+fn test_comparisons_produce_masks() {
+    let db = run("VECTOR v = [0.1, 0.7, 0.5, 0.9]\n\
+         VECTOR w = [0.1, 0.2, 0.5, 1.0]\n\
+         LET gt = v > 0.5\n\
+         LET ge = v >= 0.5\n\
+         LET eq = v = w\n\
+         LET ne = v != w\n\
+         LET lt = 0.5 < v");
+    assert_eq!(tensor(&db, "gt"), (vec![4], vec![0.0, 1.0, 0.0, 1.0]));
+    assert_eq!(tensor(&db, "ge").1, vec![0.0, 1.0, 1.0, 1.0]);
+    assert_eq!(tensor(&db, "eq").1, vec![1.0, 0.0, 1.0, 0.0]);
+    assert_eq!(tensor(&db, "ne").1, vec![0.0, 1.0, 0.0, 1.0]);
+    assert_eq!(tensor(&db, "lt").1, vec![0.0, 1.0, 0.0, 1.0]);
+}
+
+#[test]
+fn test_mask_selects_tensor_elements_and_rows() {
+    let db = run("VECTOR v = [0.1, 0.7, 0.4, 0.9]\n\
+         LET big = v[v > 0.5]\n\
+         LET keep = v <= 0.4\n\
+         LET small = v[keep]\n\
+         MATRIX m = [[1, 2], [3, 4], [5, 6]]\n\
+         LET rows = m[m[:, 0] >= 3]\n\
+         LET flat = m[m != 4]\n\
+         LET none = v[v > 1]");
+    assert_eq!(tensor(&db, "big"), (vec![2], vec![0.7, 0.9]));
+    assert_eq!(tensor(&db, "small"), (vec![2], vec![0.1, 0.4]));
+    assert_eq!(tensor(&db, "rows"), (vec![2, 2], vec![3.0, 4.0, 5.0, 6.0]));
+    assert_eq!(
+        tensor(&db, "flat"),
+        (vec![5], vec![1.0, 2.0, 3.0, 5.0, 6.0])
+    );
+    assert_eq!(tensor(&db, "none"), (vec![0], vec![]));
+    // The mask computed for the selection is not left behind
+    assert!(db.get("_tmp_big_mask").is_err());
+}
+
+#[test]
+fn test_mask_filters_dataset_rows() {
+    let mut db = run("DATASET users COLUMNS (id: Int, age: Int)\n\
+         INSERT INTO users VALUES (1, 20), (2, 35), (3, 50)");
+    let out = execute_line(&mut db, "LET adults = users[users.age > 30]", 1).unwrap();
+    assert_eq!(out.to_string(), "Created dataset: adults (2 rows)");
+
+    let ids = match execute_line(&mut db, "SELECT id FROM adults", 1).unwrap() {
+        DslOutput::Table(ds) => ds
+            .rows
+            .iter()
+            .map(|r| r.values[0].clone())
+            .collect::<Vec<_>>(),
+        other => panic!("unexpected output: {:?}", other),
+    };
+    assert_eq!(ids, vec![Value::Int(2), Value::Int(3)]);
+}
+
+#[test]
+fn test_mask_shape_must_match() {
+    let mut db = run("VECTOR v = [1, 2, 3]\n\
+         VECTOR short = [1, 0]\n\
+         DATASET users COLUMNS (id: Int)\n\
+         INSERT INTO users VALUES (1), (2), (3)");
+    let err = execute_line(&mut db, "LET x = v[short]", 1)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("does not match"), "{}", err);
+    let err = execute_line(&mut db, "LET x = users[short]", 1)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("3 rows of dataset 'users'"), "{}", err);
+}
+
+#[test]
+fn test_mask_and_where_leave_no_temporaries() {
+    let mut db = run("VECTOR v = [0.1, 0.7, 0.4, 0.9]\n\
+         DATASET users COLUMNS (id: Int, age: Int)\n\
+         INSERT INTO users VALUES (1, 20), (2, 35)");
+    let mut names = db.list_names();
+    names.sort();
+
+    let script = "LET big = v[v > 0.5]\n\
+         LET rows = users[users.age > 30]\n\
+         LET relu = WHERE(v > 0.5, v, 0)\n\
+         LET clipped = WHERE(v >= 0.8, 1, WHERE(v < 0.2, 0, v * 2))";
+    execute_script(&mut db, script).unwrap();
+    // Failing operations clean up the operands bound before the failure
+    assert!(execute_line(&mut db, "LET bad = v[v > missing]", 1).is_err());
+    assert!(execute_line(&mut db, "LET bad = WHERE(v > 0.5, 1, missing)", 1).is_err());
+    assert!(execute_line(&mut db, "LET bad = WHERE(v > 0.5, 1, v[9])", 1).is_err());
+
+    names.extend(["big", "clipped", "relu"].map(String::from));
+    names.sort();
+    let mut after = db.list_names();
+    after.sort();
+    assert_eq!(after, names);
+}
@@ -0,0 +1,108 @@
+use linal::core::tuple::Tuple;
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String)
+    INSERT INTO users VALUES (1, "Alice"), (2, "Bob"), (3, "Carol")
+    DATASET orders COLUMNS (id: Int, user_id: Int, total: Float)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    let schema = db.get_dataset("orders").unwrap().schema.clone();
+    let orders = (0..40)
+        .map(|i| {
+            let values = vec![
+                Value::Int(100 + i),
+                Value::Int(i % 5),
+                Value::Float(i as f32 * 0.5),
+            ];
+            Tuple::new(schema.clone(), values).unwrap()
+        })
+        .collect();
+    db.insert_rows("orders", orders).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn explain(db: &mut TensorDb, query: &str) -> String {
+    match execute_line(db, &format!("EXPLAIN {}", query), 1).unwrap() {
+        DslOutput::Message(plan) => plan,
+        other => panic!("Expected plan message, got {:?}", other),
+    }
+}
+
+const INNER: &str = "SELECT a.name, b.id FROM users a JOIN orders b ON a.id = b.user_id";
+const LEFT: &str = "SELECT b.id, a.name FROM orders b LEFT JOIN users a ON b.user_id = a.id";
+
+#[test]
+fn test_small_side_is_broadcast() {
+    let mut db = setup();
+    // users (3 rows) is smaller than orders (40 rows) on either side
+    let plan = explain(&mut db, INNER);
+    assert!(plan.contains("Broadcast"), "{}", plan);
+    assert!(plan.contains("build_left: true"), "{}", plan);
+
+    let plan = explain(&mut db, LEFT);
+    assert!(plan.contains("build_left: false"), "{}", plan);
+
+    // A filter keeps the estimate of the dataset it reads
+    let plan = explain(
+        &mut db,
+        "SELECT a.name, b.id FROM orders b JOIN users a ON b.user_id = a.id WHERE a.id > 1",
+    );
+    assert!(plan.contains("Broadcast"), "{}", plan);
+}
+
+#[test]
+fn test_large_inputs_are_partitioned() {
+    let mut db = setup();
+    db.config.execution.broadcast_join_threshold = 2;
+    let plan = explain(&mut db, INNER);
+    assert!(plan.contains("Partitioned"), "{}", plan);
+    assert!(!plan.contains("Broadcast"), "{}", plan);
+}
+
+#[test]
+fn test_strategies_return_the_same_rows() {
+    let mut db = setup();
+    let broadcast_inner = rows(&mut db, INNER);
+    let broadcast_left = rows(&mut db, LEFT);
+    assert_eq!(broadcast_inner.len(), 24);
+    assert_eq!(broadcast_left.len(), 40);
+    // Orders of user 0 and 4 have no user
+    assert_eq!(broadcast_left[0], vec![Value::Int(100), Value::Null]);
+
+    db.config.execution.broadcast_join_threshold = 0;
+    assert_eq!(rows(&mut db, INNER), broadcast_inner);
+    assert_eq!(rows(&mut db, LEFT), broadcast_left);
+}
+
+#[test]
+fn test_explain_analyze_names_the_strategy() {
+    let mut db = setup();
+    let report = match execute_line(&mut db, &format!("EXPLAIN ANALYZE {}", INNER), 1).unwrap() {
+        DslOutput::Message(report) => report,
+        other => panic!("Expected report message, got {:?}", other),
+    };
+    assert!(
+        report.contains("HashJoinExec(broadcast left)"),
+        "{}",
+        report
+    );
+
+    db.config.execution.broadcast_join_threshold = 0;
+    let report = match execute_line(&mut db, &format!("EXPLAIN ANALYZE {}", INNER), 1).unwrap() {
+        DslOutput::Message(report) => report,
+        other => panic!("Expected report message, got {:?}", other),
+    };
+    assert!(report.contains("HashJoinExec(partitioned x"), "{}", report);
+}
@@ -0,0 +1,119 @@
+use linal::core::tuple::Tuple;
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int PRIMARY KEY, name: String)
+    CREATE INDEX name_idx ON users(name)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+#[test]
+fn test_insert_multiple_rows() {
+    let mut db = setup();
+
+    let out = execute_line(
+        &mut db,
+        "INSERT INTO users VALUES (1, \"Alice\"), (2, \"Bob (admin)\"),(3, \"Carol\")",
+        1,
+    )
+    .unwrap();
+    assert!(matches!(out, DslOutput::Affected { rows: 3, .. }));
+
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(users.len(), 3);
+    assert_eq!(users.metadata.row_count, 3);
+    assert_eq!(
+        users.rows[1].get("name"),
+        Some(&Value::String("Bob (admin)".into()))
+    );
+    let name_idx = users.get_index("name").unwrap();
+    assert_eq!(
+        name_idx.lookup(&Value::String("Carol".into())).unwrap(),
+        vec![2]
+    );
+
+    let out = execute_line(
+        &mut db,
+        "INSERT INTO users VALUES (4, \"Dan\"), (5, \"Eve\") RETURNING id",
+        2,
+    )
+    .unwrap();
+    match out {
+        DslOutput::Table(ds) => assert_eq!(
+            ds.rows
+                .iter()
+                .map(|r| r.values[0].clone())
+                .collect::<Vec<_>>(),
+            vec![Value::Int(4), Value::Int(5)]
+        ),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_bad_row_rejects_whole_batch() {
+    let mut db = setup();
+
+    // Second row has the wrong arity
+    assert!(execute_line(&mut db, "INSERT INTO users VALUES (1, \"a\"), (2)", 1).is_err());
+    // Duplicate key within the batch
+    let err = execute_line(
+        &mut db,
+        "INSERT INTO users VALUES (1, \"a\"), (1, \"b\")",
+        2,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Duplicate primary key"), "{}", err);
+    // Garbage between rows
+    assert!(execute_line(&mut db, "INSERT INTO users VALUES (1, \"a\") (2, \"b\")", 3).is_err());
+
+    assert!(db.get_dataset("users").unwrap().is_empty());
+}
+
+#[test]
+fn test_tensor_db_insert_rows() {
+    let mut db = setup();
+    let schema = db.get_dataset("users").unwrap().schema.clone();
+    let rows = (1..=100)
+        .map(|i| {
+            Tuple::new(
+                schema.clone(),
+                vec![Value::Int(i), Value::String(format!("user{}", i))],
+            )
+            .unwrap()
+        })
+        .collect();
+
+    db.insert_rows("users", rows).unwrap();
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(users.len(), 100);
+    assert_eq!(users.metadata.row_count, 100);
+    assert_eq!(
+        users
+            .get_index("name")
+            .unwrap()
+            .lookup(&Value::String("user42".into()))
+            .unwrap(),
+        vec![41]
+    );
+
+    // Upserts can be batched as well
+    execute_line(
+        &mut db,
+        "UPSERT INTO users VALUES (1, \"first\"), (101, \"new\")",
+        1,
+    )
+    .unwrap();
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(users.len(), 101);
+    assert_eq!(
+        users.rows[0].get("name"),
+        Some(&Value::String("first".into()))
+    );
+}
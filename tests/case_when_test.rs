@@ -0,0 +1,101 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET students COLUMNS (id: Int, name: String, score: Float, active: Bool)
+    INSERT INTO students VALUES (1, "Ana", 95.0, true), (2, "Ben", 72.5, false), (3, "Cy", 58.0, true), (4, "Di", 81.0, true)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows.iter().map(|r| r.values.clone()).collect(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<Vec<Value>> {
+    values
+        .iter()
+        .map(|s| vec![Value::String(s.to_string())])
+        .collect()
+}
+
+#[test]
+fn test_case_buckets_scores_into_labels() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT CASE WHEN score >= 90 THEN \"A\" WHEN score >= 75 THEN \"B\" WHEN score >= 60 THEN \"C\" ELSE \"F\" END AS grade FROM students ORDER BY id",
+    );
+    assert_eq!(result, strings(&["A", "C", "F", "B"]));
+}
+
+#[test]
+fn test_case_without_else_and_simple_form() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT CASE WHEN active AND score > 80 THEN name END FROM students ORDER BY id",
+    );
+    assert_eq!(
+        result,
+        vec![
+            vec![Value::String("Ana".into())],
+            vec![Value::Null],
+            vec![Value::Null],
+            vec![Value::String("Di".into())],
+        ]
+    );
+
+    let result = rows(
+        &mut db,
+        "SELECT CASE id WHEN 1 THEN \"first\" WHEN 2 THEN \"second\" ELSE \"other\" END AS place FROM students ORDER BY id",
+    );
+    assert_eq!(result, strings(&["first", "second", "other", "other"]));
+}
+
+#[test]
+fn test_case_in_computed_column_and_filter() {
+    let mut db = setup();
+    execute_line(
+        &mut db,
+        "DATASET students ADD COLUMN bonus = CASE WHEN active THEN score * 0.1 ELSE 0 END",
+        1,
+    )
+    .unwrap();
+    let result = rows(&mut db, "SELECT bonus FROM students ORDER BY id");
+    assert_eq!(
+        result,
+        vec![
+            vec![Value::Float(9.5)],
+            vec![Value::Float(0.0)],
+            vec![Value::Float(5.8)],
+            vec![Value::Float(8.1)],
+        ]
+    );
+
+    let result = rows(
+        &mut db,
+        "SELECT name FROM students WHERE CASE WHEN active THEN score ELSE 100 END > 80 ORDER BY id",
+    );
+    assert_eq!(result, strings(&["Ana", "Ben", "Di"]));
+}
+
+#[test]
+fn test_case_in_sql_and_errors() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SQL SELECT CASE WHEN score < 60 THEN 'fail' ELSE 'pass' END AS outcome FROM students ORDER BY id",
+    );
+    assert_eq!(result, strings(&["pass", "pass", "fail", "pass"]));
+
+    assert!(execute_line(&mut db, "SELECT CASE ELSE 1 END FROM students", 1).is_err());
+    assert!(execute_line(&mut db, "SELECT CASE WHEN active THEN 1 FROM students", 1).is_err());
+}
@@ -0,0 +1,116 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET items COLUMNS (id: Int, price: Float, code: String, active: Bool, at: Timestamp)
+    INSERT INTO items VALUES (1, 9.75, "42", true, "2024-03-01T10:00:00Z"), (2, 3, "n/a", false, "2024-03-05")
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn query(db: &mut TensorDb, query: &str) -> (Vec<String>, Vec<Vec<Value>>) {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => (
+            result
+                .schema
+                .fields
+                .iter()
+                .map(|f| f.name.clone())
+                .collect(),
+            result.rows.iter().map(|r| r.values.clone()).collect(),
+        ),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+#[test]
+fn test_cast_between_scalar_types() {
+    let mut db = setup();
+    let (names, rows) = query(
+        &mut db,
+        "SELECT CAST(id AS FLOAT), CAST(price AS INT), CAST(code AS INT), CAST(active AS INT), CAST(id AS STRING) FROM items ORDER BY id",
+    );
+    assert_eq!(names[0], "CAST(id AS FLOAT)");
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                Value::Float(1.0),
+                Value::Int(9),
+                Value::Int(42),
+                Value::Int(1),
+                Value::String("1".into()),
+            ],
+            // "n/a" does not parse as an Int, so the cast is NULL
+            vec![
+                Value::Float(2.0),
+                Value::Int(3),
+                Value::Null,
+                Value::Int(0),
+                Value::String("2".into()),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn test_cast_in_filters_and_computed_columns() {
+    let mut db = setup();
+    let (_, rows) = query(&mut db, "SELECT id FROM items WHERE CAST(code AS INT) > 40");
+    assert_eq!(rows, vec![vec![Value::Int(1)]]);
+
+    execute_line(
+        &mut db,
+        "DATASET items ADD COLUMN day = CAST(DATE_TRUNC(\"day\", at) AS STRING)",
+        1,
+    )
+    .unwrap();
+    let (_, rows) = query(&mut db, "SELECT day FROM items ORDER BY id");
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::String("2024-03-01T00:00:00Z".into())],
+            vec![Value::String("2024-03-05T00:00:00Z".into())],
+        ]
+    );
+
+    assert!(execute_line(&mut db, "SELECT CAST(id AS VECTOR) FROM items", 1).is_err());
+    assert!(execute_line(&mut db, "SELECT CAST(id FLOAT) FROM items", 1).is_err());
+}
+
+#[test]
+fn test_implicit_coercions() {
+    let mut db = setup();
+    // The Int literal 3 was stored in the Float column as 3.0
+    let (_, rows) = query(&mut db, "SELECT price FROM items WHERE id = 2");
+    assert_eq!(rows, vec![vec![Value::Float(3.0)]]);
+
+    // Strings meet Timestamps as timestamps
+    let (_, rows) = query(&mut db, "SELECT id FROM items WHERE at > \"2024-03-02\"");
+    assert_eq!(rows, vec![vec![Value::Int(2)]]);
+
+    // No implicit String -> Int: the comparison is unknown
+    let (_, rows) = query(&mut db, "SELECT id FROM items WHERE code = 42");
+    assert!(rows.is_empty());
+
+    // CASE branches meet at their common type
+    let (_, rows) = query(
+        &mut db,
+        "SELECT CASE WHEN active THEN id ELSE price END FROM items ORDER BY id",
+    );
+    assert_eq!(rows, vec![vec![Value::Float(1.0)], vec![Value::Float(3.0)]]);
+}
+
+#[test]
+fn test_sql_cast() {
+    let mut db = setup();
+    let (_, rows) = query(
+        &mut db,
+        "SQL SELECT CAST(price AS INTEGER) AS whole, CAST(code AS DOUBLE) FROM items WHERE CAST(active AS INT) = 1",
+    );
+    assert_eq!(rows, vec![vec![Value::Int(9), Value::Float(42.0)]]);
+}
@@ -0,0 +1,110 @@
+use linal::core::config::{EngineConfig, ExecutionConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_sql, DslOutput};
+use linal::engine::TensorDb;
+
+fn db_with_threshold(columnar_threshold: usize) -> TensorDb {
+    let config = EngineConfig {
+        execution: ExecutionConfig {
+            columnar_threshold,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+    execute_sql(
+        &mut db,
+        "CREATE TABLE m (id INT NOT NULL, qty INT, price DOUBLE, ok BOOLEAN, tag TEXT)",
+        1,
+    )
+    .unwrap();
+    for i in 0..60i64 {
+        let qty = if i % 7 == 0 {
+            "NULL".to_string()
+        } else {
+            (i % 11 - 3).to_string()
+        };
+        let price = if i % 5 == 0 {
+            "NULL".to_string()
+        } else {
+            format!("{}.25", i % 13)
+        };
+        let sql = format!(
+            "INSERT INTO m VALUES ({}, {}, {}, {}, 't{}')",
+            i,
+            qty,
+            price,
+            i % 2 == 0,
+            i % 3
+        );
+        execute_sql(&mut db, &sql, 1).unwrap();
+    }
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn explain(db: &mut TensorDb, query: &str) -> String {
+    match execute_line(db, &format!("EXPLAIN {}", query), 1).unwrap() {
+        DslOutput::Message(plan) => plan,
+        other => panic!("Expected plan message, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_columnar_results_match_row_execution() {
+    let mut columnar = db_with_threshold(1);
+    let mut row_based = db_with_threshold(usize::MAX);
+
+    let queries = [
+        "SELECT id FROM m WHERE qty > 2",
+        "SELECT id FROM m WHERE qty < 2",
+        "SELECT id FROM m WHERE 4 <= qty AND price != 3.25",
+        "SELECT id FROM m WHERE price >= 6 OR ok = true",
+        "SELECT id FROM m WHERE qty = 1.0 OR price < 2",
+        "SELECT id, tag FROM m WHERE tag = \"t1\" AND qty > 0",
+        "SELECT COUNT(*), SUM(qty), MIN(qty), MAX(qty), AVG(qty) FROM m",
+        "SELECT SUM(price), MIN(price), MAX(price), AVG(price), COUNT(tag) FROM m",
+        "SELECT SUM(qty), AVG(price) FROM m WHERE ok = true",
+        "SELECT SUM(price) FROM m WHERE price > 100",
+    ];
+    for query in queries {
+        assert_eq!(
+            rows(&mut columnar, query),
+            rows(&mut row_based, query),
+            "{}",
+            query
+        );
+    }
+}
+
+#[test]
+fn test_columnar_kernels_are_chosen_above_the_threshold() {
+    let mut db = db_with_threshold(50);
+
+    let plan = explain(&mut db, "SELECT id FROM m WHERE qty > 2 AND ok = true");
+    assert!(plan.contains("ColumnarFilterExec"), "{}", plan);
+    let plan = explain(&mut db, "SELECT SUM(qty), COUNT(*) FROM m WHERE qty > 2");
+    assert!(plan.contains("ColumnarAggregateExec"), "{}", plan);
+
+    // String comparisons run on dictionary codes; GROUP BY stays in AggregateExec
+    let plan = explain(&mut db, "SELECT id FROM m WHERE tag = \"t1\"");
+    assert!(plan.contains("ColumnarFilterExec"), "{}", plan);
+    let plan = explain(&mut db, "SELECT tag, SUM(qty) FROM m GROUP BY tag");
+    assert!(!plan.contains("Columnar"), "{}", plan);
+
+    // Below the threshold nothing changes
+    let mut small = db_with_threshold(61);
+    let plan = explain(&mut small, "SELECT id FROM m WHERE qty > 2");
+    assert!(!plan.contains("Columnar"), "{}", plan);
+
+    assert_eq!(
+        rows(&mut db, "SELECT COUNT(*), MAX(price) FROM m WHERE id >= 58"),
+        vec![vec![Value::Int(2), Value::Float(7.25)]]
+    );
+}
@@ -0,0 +1,188 @@
+use linal::dsl::{execute_line, execute_script};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET accounts COLUMNS (id: Int PRIMARY KEY, email: String UNIQUE, name: String NOT NULL)
+    INSERT INTO accounts VALUES (1, "ana@example.com", "Ana")
+    INSERT INTO accounts VALUES (2, "ben@example.com", "Ben")
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+#[test]
+fn test_unique_column_rejects_duplicate_insert() {
+    let mut db = setup();
+    let accounts = db.get_dataset("accounts").unwrap();
+    assert!(accounts.schema.get_field("email").unwrap().unique);
+    assert!(!accounts.schema.get_field("name").unwrap().nullable);
+
+    let err = execute_line(
+        &mut db,
+        "INSERT INTO accounts VALUES (3, \"ana@example.com\", \"Other\")",
+        1,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "UNIQUE constraint violated: duplicate value \"ana@example.com\" in column 'email'"
+        ),
+        "{}",
+        err
+    );
+
+    // A batch that repeats a value adds none of its rows
+    assert!(execute_line(
+        &mut db,
+        "INSERT INTO accounts VALUES (3, \"cy@example.com\", \"Cy\"), (4, \"cy@example.com\", \"Dee\")",
+        2,
+    )
+    .is_err());
+    assert_eq!(db.get_dataset("accounts").unwrap().len(), 2);
+
+    execute_line(
+        &mut db,
+        "INSERT INTO accounts VALUES (3, \"cy@example.com\", \"Cy\")",
+        3,
+    )
+    .unwrap();
+    assert_eq!(db.get_dataset("accounts").unwrap().len(), 3);
+}
+
+#[test]
+fn test_update_and_upsert_keep_values_unique() {
+    let mut db = setup();
+    let err = execute_line(
+        &mut db,
+        "UPDATE accounts SET email = \"ana@example.com\" WHERE id = 2",
+        1,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("UNIQUE constraint violated"),
+        "{}",
+        err
+    );
+
+    // Replacing a row may keep its own value but not take another row's
+    execute_line(
+        &mut db,
+        "UPSERT INTO accounts VALUES (1, \"ana@example.com\", \"Ana B.\")",
+        2,
+    )
+    .unwrap();
+    let err = execute_line(
+        &mut db,
+        "UPSERT INTO accounts VALUES (1, \"ben@example.com\", \"Ana\")",
+        3,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("UNIQUE constraint violated"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_not_null_violation_is_reported() {
+    let mut db = TensorDb::new();
+    execute_line(
+        &mut db,
+        "SQL CREATE TABLE people (id INT PRIMARY KEY, email TEXT UNIQUE, name TEXT NOT NULL)",
+        1,
+    )
+    .unwrap();
+
+    let err = execute_line(
+        &mut db,
+        "SQL INSERT INTO people VALUES (1, 'a@example.com', NULL)",
+        2,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("NOT NULL constraint violated: column 'name' cannot be NULL"),
+        "{}",
+        err
+    );
+
+    // NULLs do not collide in a UNIQUE column
+    execute_line(
+        &mut db,
+        "SQL INSERT INTO people VALUES (1, NULL, 'Ana'), (2, NULL, 'Ben')",
+        3,
+    )
+    .unwrap();
+    assert_eq!(db.get_dataset("people").unwrap().len(), 2);
+}
+
+#[test]
+fn test_nullable_dsl_columns() {
+    let mut db = TensorDb::new();
+    execute_line(
+        &mut db,
+        "DATASET contacts COLUMNS (id: Int PRIMARY KEY, phone: String? UNIQUE, name: String NOT NULL)",
+        1,
+    )
+    .unwrap();
+    let phone = db
+        .get_dataset("contacts")
+        .unwrap()
+        .schema
+        .get_field("phone")
+        .unwrap()
+        .clone();
+    assert!(phone.nullable && phone.unique);
+
+    execute_line(
+        &mut db,
+        r#"INSERT INTO contacts VALUES (1, NULL, "Ana"), (2, NULL, "Ben")"#,
+        2,
+    )
+    .unwrap();
+    let err = execute_line(
+        &mut db,
+        r#"INSERT INTO contacts VALUES (3, "555-0100", NULL)"#,
+        3,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("NOT NULL constraint violated: column 'name' cannot be NULL"),
+        "{}",
+        err
+    );
+    assert_eq!(db.get_dataset("contacts").unwrap().len(), 2);
+
+    // A nullable column cannot also be NOT NULL or the primary key
+    assert!(execute_line(&mut db, "DATASET bad COLUMNS (id: Int? NOT NULL)", 4).is_err());
+    assert!(execute_line(&mut db, "DATASET bad COLUMNS (id: Int? PRIMARY KEY)", 5).is_err());
+}
+
+#[test]
+fn test_sql_table_constraints() {
+    let mut db = TensorDb::new();
+    execute_line(
+        &mut db,
+        "SQL CREATE TABLE tags (id INT, label TEXT, PRIMARY KEY (id), UNIQUE (label))",
+        1,
+    )
+    .unwrap();
+    let tags = db.get_dataset("tags").unwrap();
+    assert_eq!(tags.schema.primary_key().unwrap().name, "id");
+    assert!(tags.schema.get_field("label").unwrap().unique);
+
+    execute_line(&mut db, "SQL INSERT INTO tags VALUES (1, 'ml')", 2).unwrap();
+    assert!(execute_line(&mut db, "SQL INSERT INTO tags VALUES (2, 'ml')", 3).is_err());
+    assert!(execute_line(&mut db, "SQL INSERT INTO tags VALUES (1, 'db')", 4).is_err());
+
+    assert!(execute_line(
+        &mut db,
+        "SQL CREATE TABLE pairs (a INT, b INT, UNIQUE (a, b))",
+        5
+    )
+    .is_err());
+}
@@ -0,0 +1,212 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::core::value::Value;
+use linal::dsl::handlers::copy::{CopyFormat, CopyStatement};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use linal::server::start_server;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::time::sleep;
+
+const SCHEMA: &str =
+    "DATASET users COLUMNS (id: Int PRIMARY KEY, name: String, score: Float, emb: Vector(2))";
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    execute_script(&mut db, SCHEMA).unwrap();
+    db
+}
+
+#[test]
+fn test_copy_csv_rows() {
+    let mut db = setup();
+    let statement = CopyStatement::parse("COPY users FROM STDIN FORMAT csv", 1).unwrap();
+    assert_eq!(statement, CopyStatement::new("users", CopyFormat::Csv));
+
+    let command = statement.command([
+        "1,Alice,0.5,\"[1.0, 0.0]\"",
+        "",
+        "2,\"Smith, Bob\",3,\"[0.0, 1.0]\"",
+    ]);
+    let out = execute_line(&mut db, &command, 1).unwrap();
+    assert!(matches!(out, DslOutput::Affected { rows: 2, ref op } if op == "COPY"));
+
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(
+        users.rows[1].values,
+        vec![
+            Value::Int(2),
+            Value::String("Smith, Bob".into()),
+            Value::Float(3.0),
+            Value::Vector(vec![0.0, 1.0]),
+        ]
+    );
+
+    // A bare header copies nothing
+    let out = execute_line(&mut db, "COPY users FROM STDIN", 2).unwrap();
+    assert!(matches!(out, DslOutput::Affected { rows: 0, .. }));
+
+    // Empty cells are NULL
+    execute_line(&mut db, "SQL CREATE TABLE notes (id INT, body TEXT)", 3).unwrap();
+    execute_line(&mut db, "COPY notes FROM STDIN\n1,", 4).unwrap();
+    assert_eq!(
+        db.get_dataset("notes").unwrap().rows[0].values,
+        vec![Value::Int(1), Value::Null]
+    );
+}
+
+#[test]
+fn test_copy_json_rows() {
+    let mut db = setup();
+    let command = "COPY users FROM STDIN FORMAT json\n\
+        {\"id\": 1, \"name\": \"Alice\", \"score\": 2, \"emb\": [0.5, 0.5]}\n\
+        {\"id\": 2, \"name\": \"Bob\", \"score\": 0.25, \"emb\": [1, 2]}";
+    execute_line(&mut db, command, 1).unwrap();
+
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(users.len(), 2);
+    assert_eq!(users.rows[0].get("score"), Some(&Value::Float(2.0)));
+    assert_eq!(
+        users.rows[1].get("emb"),
+        Some(&Value::Vector(vec![1.0, 2.0]))
+    );
+}
+
+#[test]
+fn test_copy_bad_row_rejects_batch() {
+    let mut db = setup();
+
+    let err = execute_line(
+        &mut db,
+        "COPY users FROM STDIN\n1,Alice,0.5,\"[1, 0]\"\n2,Bob,high,\"[1, 0]\"",
+        1,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("COPY row 2"), "{}", err);
+
+    let err = execute_line(
+        &mut db,
+        "COPY users FROM STDIN FORMAT json\n{\"nope\": 1}",
+        2,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("unknown column 'nope'"), "{}", err);
+
+    // Keys are checked against the batch as for a multi-row INSERT
+    let err = execute_line(
+        &mut db,
+        "COPY users FROM STDIN\n1,a,0,\"[1, 0]\"\n1,b,0,\"[1, 0]\"",
+        3,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Duplicate primary key"), "{}", err);
+
+    assert!(execute_line(&mut db, "COPY users FROM STDIN FORMAT xml", 4).is_err());
+    assert!(execute_line(&mut db, "COPY missing FROM STDIN\n1", 5).is_err());
+    assert!(db.get_dataset("users").unwrap().is_empty());
+}
+
+#[test]
+fn test_copy_batches_replay_from_wal() {
+    let temp_dir = "/tmp/linal_test_copy_wal";
+    let _ = std::fs::remove_dir_all(temp_dir);
+    let config = || EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            wal: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    {
+        let mut db = TensorDb::with_config(config());
+        execute_line(&mut db, SCHEMA, 1).unwrap();
+        let statement = CopyStatement::new("users", CopyFormat::Csv);
+        execute_line(
+            &mut db,
+            &statement.command(["1,Alice,1,\"[1, 0]\"", "2,Bob,2,\"[0, 1]\""]),
+            2,
+        )
+        .unwrap();
+    }
+
+    let db = TensorDb::with_config(config());
+    assert_eq!(db.get_dataset("users").unwrap().len(), 2);
+
+    let _ = std::fs::remove_dir_all(temp_dir);
+}
+
+#[tokio::test]
+async fn test_ingest_rows_endpoint() {
+    let db = Arc::new(RwLock::new(setup()));
+    let port = 8115;
+    let db_clone = db.clone();
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+    sleep(Duration::from_millis(1000)).await;
+
+    // Well past the 16KB command limit and several COPY batches
+    let body: String = (1..=2500)
+        .map(|i| format!("{},user{},{}.5,\"[{}, 0]\"\n", i, i, i, i))
+        .collect();
+    assert!(body.len() > 16 * 1024);
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!(
+            "http://localhost:{}/datasets/users/rows?format=json",
+            port
+        ))
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let json: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(json["status"], "ok", "{}", json);
+    assert_eq!(json["result"]["Affected"]["rows"], 2500);
+    assert_eq!(db.read().unwrap().get_dataset("users").unwrap().len(), 2500);
+
+    // NDJSON rows; a failing batch reports the lines it covered
+    let resp = client
+        .post(format!(
+            "http://localhost:{}/datasets/users/rows?row_format=json&format=json",
+            port
+        ))
+        .body("{\"id\": 3000, \"name\": \"x\", \"score\": 0, \"emb\": [0, 0]}\n{\"id\": 1, \"name\": \"dup\", \"score\": 0, \"emb\": [0, 0]}")
+        .send()
+        .await
+        .unwrap();
+    let json: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(json["status"], "error");
+    let error = json["error"].as_str().unwrap();
+    assert!(error.contains("Duplicate primary key"), "{}", error);
+    assert!(error.contains("lines 1-2"), "{}", error);
+    assert_eq!(db.read().unwrap().get_dataset("users").unwrap().len(), 2500);
+
+    let resp = client
+        .post(format!(
+            "http://localhost:{}/datasets/missing/rows?format=json",
+            port
+        ))
+        .body("")
+        .send()
+        .await
+        .unwrap();
+    let json: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(json["status"], "error");
+
+    let resp = client
+        .post(format!(
+            "http://localhost:{}/datasets/users/rows?row_format=xml",
+            port
+        ))
+        .body("1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
@@ -0,0 +1,59 @@
+use linal::dsl::{execute_line, execute_script};
+use linal::engine::TensorDb;
+
+fn run(script: &str) -> TensorDb {
+    let mut db = TensorDb::new();
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn tensor(db: &TensorDb, name: &str) -> (Vec<usize>, Vec<f32>) {
+    let t = db.get(name).unwrap();
+    (t.shape.dims.clone(), t.data.to_vec())
+}
+
+#[test]
+fn test_covariance_of_columns() {
+    let db = run("MATRIX m = [[1, 2], [3, 4], [5, 9]]\n\
+         LET c = COV m\n\
+         LET p = COV m POPULATION");
+    assert_eq!(tensor(&db, "c"), (vec![2, 2], vec![4.0, 7.0, 7.0, 13.0]));
+    let (dims, p) = tensor(&db, "p");
+    assert_eq!(dims, vec![2, 2]);
+    for (got, want) in p
+        .iter()
+        .zip([8.0 / 3.0, 14.0 / 3.0, 14.0 / 3.0, 26.0 / 3.0])
+    {
+        assert!((got - want).abs() < 1e-5, "{} vs {}", got, want);
+    }
+}
+
+#[test]
+fn test_gram_matrix_of_rows() {
+    let db = run("MATRIX m = [[1, 2], [3, 4]]\n\
+         LET g = GRAM m\n\
+         LET gc = GRAM m CENTERED");
+    assert_eq!(tensor(&db, "g"), (vec![2, 2], vec![5.0, 11.0, 11.0, 25.0]));
+    assert_eq!(tensor(&db, "gc").1, vec![2.0, -2.0, -2.0, 2.0]);
+}
+
+#[test]
+fn test_gram_matches_matmul_with_transpose() {
+    let db = run("MATRIX m = [[1, 0, 2], [0, 3, 1], [4, 1, 0], [2, 2, 2]]\n\
+         LET g = GRAM m\n\
+         LET t = TRANSPOSE m\n\
+         LET mm = MATMUL m t");
+    assert_eq!(tensor(&db, "g"), tensor(&db, "mm"));
+}
+
+#[test]
+fn test_invalid_inputs_are_rejected() {
+    let mut db = run("VECTOR v = [1, 2, 3]\n\
+         MATRIX one = [[1, 2]]");
+    let err = execute_line(&mut db, "LET c = COV v", 1).unwrap_err();
+    assert!(err.to_string().contains("COV expects a rank-2"), "{}", err);
+    let err = execute_line(&mut db, "LET c = COV one", 1).unwrap_err();
+    assert!(err.to_string().contains("at least 2 rows"), "{}", err);
+    assert!(execute_line(&mut db, "LET c = COV one POPULATION", 1).is_ok());
+    assert!(execute_line(&mut db, "LET g = GRAM one SIDEWAYS", 1).is_err());
+}
@@ -0,0 +1,161 @@
+use linal::core::value::{Value, ValueType};
+use linal::dsl::execute_line;
+use linal::utils::parsing::parse_timestamp;
+use linal::TensorDb;
+use std::fs;
+
+fn write_csv(dir: &str, file: &str, content: &str) -> String {
+    let _ = fs::remove_dir_all(dir);
+    fs::create_dir_all(dir).unwrap();
+    let path = format!("{}/{}", dir, file);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_load_csv_with_header_and_inference() {
+    let dir = "/tmp/linal_test_csv_header";
+    let path = write_csv(
+        dir,
+        "products.csv",
+        "id,name,price,in_stock,embedding\n\
+         1,Pen,1.5,true,\"[0.1, 0.2]\"\n\
+         2,Ink,3,false,\"[0.3, 0.4]\"\n\
+         3,,2.25,TRUE,\"[0.5, 0.6]\"\n",
+    );
+
+    let mut db = TensorDb::new();
+    execute_line(
+        &mut db,
+        &format!(r#"LOAD DATASET products FROM "{}""#, path),
+        1,
+    )
+    .unwrap();
+
+    let ds = db.get_dataset("products").unwrap();
+    let types: Vec<(&str, ValueType)> = ds
+        .schema
+        .fields
+        .iter()
+        .map(|f| (f.name.as_str(), f.value_type.clone()))
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            ("id", ValueType::Int),
+            ("name", ValueType::String),
+            ("price", ValueType::Float),
+            ("in_stock", ValueType::Bool),
+            ("embedding", ValueType::Vector(2)),
+        ]
+    );
+    assert!(ds.schema.get_field("name").unwrap().nullable);
+
+    assert_eq!(ds.rows.len(), 3);
+    assert_eq!(ds.rows[1].get("price"), Some(&Value::Float(3.0)));
+    assert_eq!(ds.rows[2].get("name"), Some(&Value::Null));
+    assert_eq!(ds.rows[2].get("in_stock"), Some(&Value::Bool(true)));
+    assert_eq!(
+        ds.rows[0].get("embedding"),
+        Some(&Value::Vector(vec![0.1, 0.2]))
+    );
+
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_load_csv_delimiter_and_headerless() {
+    let dir = "/tmp/linal_test_csv_options";
+    let path = write_csv(dir, "scores.csv", "alice;10\nbob;20\n");
+
+    let mut db = TensorDb::new();
+    // Header detection: "alice" / "10" is data, not a header
+    execute_line(
+        &mut db,
+        &format!(r#"LOAD DATASET scores FROM "{}" DELIMITER ";""#, path),
+        1,
+    )
+    .unwrap();
+    let ds = db.get_dataset("scores").unwrap();
+    assert_eq!(ds.rows.len(), 2);
+    assert_eq!(ds.rows[0].get("col1"), Some(&Value::String("alice".into())));
+    assert_eq!(ds.rows[1].get("col2"), Some(&Value::Int(20)));
+
+    // All-text rows need an explicit NO HEADER
+    let path = write_csv(dir, "tags.csv", "red\tbright\nblue\tdark\n");
+    execute_line(
+        &mut db,
+        &format!(
+            r#"LOAD DATASET tags FROM "{}" DELIMITER "\t" NO HEADER"#,
+            path
+        ),
+        2,
+    )
+    .unwrap();
+    assert_eq!(db.get_dataset("tags").unwrap().rows.len(), 2);
+
+    // Ragged rows and bad options are rejected
+    let path = write_csv(dir, "bad.csv", "a,b\n1,2,3\n");
+    assert!(execute_line(&mut db, &format!(r#"LOAD DATASET bad FROM "{}""#, path), 3).is_err());
+    assert!(execute_line(
+        &mut db,
+        &format!(r#"LOAD DATASET bad FROM "{}" DELIMITER "::""#, path),
+        4
+    )
+    .is_err());
+
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_load_csv_infers_timestamps_and_reports_ambiguity() {
+    let dir = "/tmp/linal_test_csv_inference";
+    let path = write_csv(
+        dir,
+        "events.csv",
+        "at,day,active,embedding,code,big_id\n\
+         2024-03-01T10:15:30Z,2024-03-01,False,\"[1, 2]\",17,99999999999999999999\n\
+         2024-03-01 11:00:00,2024-03-02,true,\"[3, 4, 5]\",x9,1\n\
+         2024-03-02T08:00:00+02:00,,TRUE,\"[6, 7]\",42,2\n",
+    );
+
+    let mut db = TensorDb::new();
+    let out = execute_line(
+        &mut db,
+        &format!(r#"LOAD DATASET events FROM "{}""#, path),
+        1,
+    )
+    .unwrap()
+    .to_string();
+
+    let ds = db.get_dataset("events").unwrap();
+    let types: Vec<ValueType> = ds
+        .schema
+        .fields
+        .iter()
+        .map(|f| f.value_type.clone())
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            ValueType::Timestamp,
+            ValueType::Timestamp,
+            ValueType::Bool,
+            ValueType::String,
+            ValueType::String,
+            ValueType::String,
+        ]
+    );
+    let at = parse_timestamp("2024-03-02T06:00:00Z").unwrap();
+    assert_eq!(ds.rows[2].get("at"), Some(&Value::Timestamp(at)));
+    assert_eq!(ds.rows[2].get("day"), Some(&Value::Null));
+    assert_eq!(ds.rows[0].get("active"), Some(&Value::Bool(false)));
+
+    assert!(out.contains("at: TIMESTAMP\n"));
+    assert!(out.contains("day: TIMESTAMP (nullable)"));
+    assert!(out.contains("embedding: STRING -- ambiguous: vectors of dimensions 2, 3"));
+    assert!(out.contains("code: STRING -- ambiguous: 2 of 3 values are numbers, but not 'x9'"));
+    assert!(out.contains("big_id: STRING -- ambiguous: integers outside the Int range"));
+
+    let _ = fs::remove_dir_all(dir);
+}
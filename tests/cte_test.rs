@@ -0,0 +1,131 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup_events(db: &mut TensorDb) {
+    let script = r#"
+    DATASET events COLUMNS (id: Int, category: String, ts: Int)
+    INSERT INTO events VALUES (1, "click", 10)
+    INSERT INTO events VALUES (2, "view", 20)
+    INSERT INTO events VALUES (3, "click", 30)
+    INSERT INTO events VALUES (4, "click", 40)
+    INSERT INTO events VALUES (5, "view", 50)
+    "#;
+    execute_script(db, script).expect("Setup failed");
+}
+
+fn select_rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).expect("Query failed") {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_cte_with_group_by() {
+    let mut db = TensorDb::new();
+    setup_events(&mut db);
+
+    let mut rows = select_rows(
+        &mut db,
+        "WITH recent AS (SELECT * FROM events FILTER ts > 15) SELECT category, COUNT(*) FROM recent GROUP BY category",
+    );
+    // Group order is unspecified
+    rows.sort_by_key(|r| format!("{:?}", r[0]));
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::String("click".into()), Value::Int(2)],
+            vec![Value::String("view".into()), Value::Int(2)],
+        ]
+    );
+
+    // No throwaway dataset is registered
+    assert!(db.get_dataset("recent").is_err());
+}
+
+#[test]
+fn test_chained_ctes() {
+    let mut db = TensorDb::new();
+    setup_events(&mut db);
+
+    let rows = select_rows(
+        &mut db,
+        r#"WITH clicks AS (SELECT id, ts FROM events WHERE category = "click"), late AS (SELECT id FROM clicks WHERE ts > 20) SELECT * FROM late ORDER BY id DESC"#,
+    );
+    assert_eq!(rows, vec![vec![Value::Int(4)], vec![Value::Int(3)]]);
+}
+
+#[test]
+fn test_cte_syntax_errors() {
+    let mut db = TensorDb::new();
+    setup_events(&mut db);
+
+    // Missing parentheses, unclosed body, non-SELECT body, duplicate names
+    assert!(execute_line(&mut db, "WITH r AS SELECT * FROM events SELECT * FROM r", 1).is_err());
+    assert!(execute_line(
+        &mut db,
+        "WITH r AS (SELECT * FROM events SELECT * FROM r",
+        1
+    )
+    .is_err());
+    assert!(execute_line(&mut db, "WITH r AS (SHOW ALL) SELECT * FROM r", 1).is_err());
+    assert!(execute_line(
+        &mut db,
+        "WITH r AS (SELECT * FROM events), r AS (SELECT * FROM events) SELECT * FROM r",
+        1
+    )
+    .is_err());
+}
+
+#[test]
+fn test_explain_cte() {
+    let mut db = TensorDb::new();
+    setup_events(&mut db);
+
+    let out = execute_line(
+        &mut db,
+        "EXPLAIN WITH recent AS (SELECT * FROM events FILTER ts > 15) SELECT id FROM recent",
+        1,
+    )
+    .unwrap();
+    match out {
+        DslOutput::Message(plan) => assert!(plan.contains("Scan")),
+        other => panic!("Expected plan message, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_from_subquery() {
+    let mut db = TensorDb::new();
+    setup_events(&mut db);
+
+    let rows = select_rows(
+        &mut db,
+        r#"SELECT id FROM (SELECT id, ts FROM events FILTER category = "click") WHERE ts > 15 LIMIT 1"#,
+    );
+    assert_eq!(rows, vec![vec![Value::Int(3)]]);
+
+    // Nested subqueries with an alias, and wildcard expansion from the subquery schema
+    let rows = select_rows(
+        &mut db,
+        "SELECT * FROM (SELECT id, category FROM (SELECT * FROM events WHERE ts > 30)) AS late",
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(4), Value::String("click".into())],
+            vec![Value::Int(5), Value::String("view".into())],
+        ]
+    );
+
+    // Subqueries can read CTEs
+    let rows = select_rows(
+        &mut db,
+        r#"WITH views AS (SELECT * FROM events WHERE category = "view") SELECT COUNT(*) FROM (SELECT id FROM views)"#,
+    );
+    assert_eq!(rows, vec![vec![Value::Int(2)]]);
+
+    assert!(execute_line(&mut db, "SELECT * FROM (SELECT * FROM events", 1).is_err());
+    assert!(execute_line(&mut db, "SELECT * FROM (SHOW ALL)", 1).is_err());
+}
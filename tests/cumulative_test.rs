@@ -0,0 +1,69 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn run(script: &str) -> TensorDb {
+    let mut db = TensorDb::new();
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn tensor(db: &TensorDb, name: &str) -> (Vec<usize>, Vec<f32>) {
+    let t = db.get(name).unwrap();
+    (t.shape.dims.clone(), t.data.to_vec())
+}
+
+#[test]
+fn test_cumulative_verbs_on_vectors() {
+    let db = run("VECTOR v = [1, 2, 3, 4, 5]\n\
+         LET c = CUMSUM v\n\
+         LET p = CUMPROD v\n\
+         LET d = DIFF v\n\
+         LET r = ROLLING_MEAN v WINDOW 2");
+    assert_eq!(tensor(&db, "c"), (vec![5], vec![1.0, 3.0, 6.0, 10.0, 15.0]));
+    assert_eq!(tensor(&db, "p").1, vec![1.0, 2.0, 6.0, 24.0, 120.0]);
+    assert_eq!(tensor(&db, "d"), (vec![4], vec![1.0, 1.0, 1.0, 1.0]));
+    assert_eq!(tensor(&db, "r"), (vec![4], vec![1.5, 2.5, 3.5, 4.5]));
+}
+
+#[test]
+fn test_matrices_run_along_the_last_axis() {
+    let db = run("MATRIX m = [[1, 2, 3], [4, 5, 6]]\n\
+         LET c = CUMSUM m\n\
+         LET d = DIFF m\n\
+         LET r = ROLLING_MEAN m WINDOW 3");
+    assert_eq!(
+        tensor(&db, "c"),
+        (vec![2, 3], vec![1.0, 3.0, 6.0, 4.0, 9.0, 15.0])
+    );
+    assert_eq!(tensor(&db, "d"), (vec![2, 2], vec![1.0, 1.0, 1.0, 1.0]));
+    assert_eq!(tensor(&db, "r"), (vec![2, 1], vec![2.0, 5.0]));
+}
+
+#[test]
+fn test_rolling_window_is_validated() {
+    let mut db = run("VECTOR v = [1, 2, 3]");
+    let err = execute_line(&mut db, "LET r = ROLLING_MEAN v WINDOW 4", 1).unwrap_err();
+    assert!(err.to_string().contains("Rolling window 4"), "{}", err);
+    assert!(execute_line(&mut db, "LET r = ROLLING_MEAN v WINDOW 0", 1).is_err());
+    assert!(execute_line(&mut db, "LET r = ROLLING_MEAN v", 1).is_err());
+}
+
+#[test]
+fn test_series_functions_on_vector_columns() {
+    let mut db = run("DATASET ts COLUMNS (id: Int, s: Vector(4))\n\
+         INSERT INTO ts VALUES (1, [1, 2, 3, 4]), (2, [2, 4, 8, 16])");
+    let query = "SELECT CUMSUM(s) AS c, DIFF(s) AS d, ROLLING_MEAN(s, 2) AS r FROM ts";
+    let DslOutput::Table(result) = execute_line(&mut db, query, 1).unwrap() else {
+        panic!("expected a table");
+    };
+    assert_eq!(result.schema.fields[1].value_type.to_string(), "VECTOR[3]");
+    assert_eq!(
+        result.rows[1].values,
+        vec![
+            Value::Vector(vec![2.0, 6.0, 14.0, 30.0]),
+            Value::Vector(vec![2.0, 4.0, 8.0]),
+            Value::Vector(vec![3.0, 6.0, 12.0]),
+        ]
+    );
+}
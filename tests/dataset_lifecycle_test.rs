@@ -0,0 +1,127 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use std::fs;
+use std::path::PathBuf;
+
+fn setup_users(db: &mut TensorDb) {
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String)
+    INSERT INTO users VALUES (1, "Ada")
+    INSERT INTO users VALUES (2, "Bob")
+    CREATE INDEX name_idx ON users(name)
+    "#;
+    execute_script(db, script).unwrap();
+}
+
+fn count(db: &mut TensorDb, query: &str) -> usize {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.len(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_drop_dataset() {
+    let mut db = TensorDb::new();
+    setup_users(&mut db);
+
+    let out = execute_line(&mut db, "DROP DATASET users", 1).unwrap();
+    assert!(out.to_string().contains("Dropped dataset 'users' (2 rows)"));
+    assert!(db.get_dataset("users").is_err());
+    assert!(db.list_indices().is_empty());
+    assert!(execute_line(&mut db, "DROP DATASET users", 2).is_err());
+
+    // The name is free again
+    execute_line(&mut db, "DATASET users COLUMNS (id: Int)", 3).unwrap();
+    assert_eq!(count(&mut db, "SELECT * FROM users"), 0);
+}
+
+#[test]
+fn test_rename_dataset() {
+    let mut db = TensorDb::new();
+    setup_users(&mut db);
+    execute_line(&mut db, "DATASET other COLUMNS (id: Int)", 1).unwrap();
+
+    execute_line(&mut db, "RENAME DATASET users TO people", 2).unwrap();
+    assert!(db.get_dataset("users").is_err());
+    let people = db.get_dataset("people").unwrap();
+    assert_eq!(people.metadata.name.as_deref(), Some("people"));
+    assert_eq!(
+        db.list_indices(),
+        vec![("people".to_string(), "name".to_string(), "HASH".to_string())]
+    );
+    assert_eq!(
+        count(&mut db, r#"SELECT id FROM people WHERE name = "Bob""#),
+        1
+    );
+
+    assert!(execute_line(&mut db, "RENAME DATASET people TO other", 3).is_err());
+    assert!(execute_line(&mut db, "RENAME DATASET missing TO x", 4).is_err());
+    let err = execute_line(&mut db, "RENAME DATASET people other", 5).unwrap_err();
+    assert!(err.to_string().contains("Expected: RENAME DATASET"));
+}
+
+#[test]
+fn test_truncate_dataset() {
+    let mut db = TensorDb::new();
+    setup_users(&mut db);
+
+    let out = execute_line(&mut db, "TRUNCATE DATASET users", 1).unwrap();
+    assert!(matches!(out, DslOutput::Affected { rows: 2, .. }));
+    let users = db.get_dataset("users").unwrap();
+    assert!(users.rows.is_empty());
+    assert_eq!(users.schema.len(), 2);
+    assert_eq!(users.metadata.row_count, 0);
+
+    // The index is kept, emptied, and picks up new rows
+    assert_eq!(
+        count(&mut db, r#"SELECT * FROM users WHERE name = "Bob""#),
+        0
+    );
+    execute_line(&mut db, r#"INSERT INTO users VALUES (3, "Bob")"#, 2).unwrap();
+    assert_eq!(
+        count(&mut db, r#"SELECT * FROM users WHERE name = "Bob""#),
+        1
+    );
+}
+
+#[test]
+fn test_lifecycle_commands_persist() {
+    let temp_dir = "/tmp/linal_test_dataset_lifecycle";
+    let _ = fs::remove_dir_all(temp_dir);
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            auto_persist: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    {
+        let mut db = TensorDb::with_config(config.clone());
+        setup_users(&mut db);
+        execute_script(
+            &mut db,
+            r#"
+            DATASET logs COLUMNS (id: Int)
+            INSERT INTO logs VALUES (1)
+            DATASET scratch COLUMNS (id: Int)
+            RENAME DATASET users TO people
+            TRUNCATE DATASET logs
+            DROP DATASET scratch
+            "#,
+        )
+        .unwrap();
+    }
+
+    let db = TensorDb::with_config(config);
+    let mut names = db.list_dataset_names();
+    names.sort();
+    assert_eq!(names, vec!["logs", "people"]);
+    assert_eq!(db.get_dataset("people").unwrap().len(), 2);
+    assert!(db.get_dataset("logs").unwrap().is_empty());
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
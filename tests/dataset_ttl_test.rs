@@ -0,0 +1,111 @@
+use chrono::{Duration, Utc};
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script};
+use linal::engine::TensorDb;
+use std::fs;
+use std::path::PathBuf;
+
+fn setup_cache(db: &mut TensorDb) {
+    let script = r#"
+    DATASET cache COLUMNS (key: String, emb: Vector(2))
+    INSERT INTO cache VALUES ("a", [1.0, 0.0])
+    INSERT INTO cache VALUES ("b", [0.0, 1.0])
+    "#;
+    execute_script(db, script).unwrap();
+}
+
+#[test]
+fn test_row_ttl_expires_old_rows() {
+    let mut db = TensorDb::with_config(EngineConfig::default());
+    setup_cache(&mut db);
+    execute_line(&mut db, "CREATE INDEX key_idx ON cache(key)", 1).unwrap();
+    execute_line(&mut db, "DATASET cache SET TTL 3600", 2).unwrap();
+
+    // Nothing is due yet
+    let report = db.reap_expired();
+    assert_eq!(report.rows_removed, 0);
+
+    // Backdate the first row so only it is past its TTL
+    db.get_dataset_mut("cache").unwrap().row_inserted_at[0] = Utc::now() - Duration::hours(2);
+    let report = db.reap_expired();
+    assert_eq!(report.rows_removed, 1);
+    assert!(report.datasets_dropped.is_empty());
+
+    let cache = db.get_dataset("cache").unwrap();
+    assert_eq!(cache.rows.len(), 1);
+    assert_eq!(cache.rows[0].get("key"), Some(&Value::String("b".into())));
+
+    // The index was rebuilt against the remaining rows
+    let out = execute_line(&mut db, "SELECT key FROM cache WHERE key = \"b\"", 3).unwrap();
+    assert!(format!("{:?}", out).contains("\"b\""));
+
+    // Every row expires once the whole TTL has passed
+    let report = db.reap_expired_at(Utc::now() + Duration::hours(2));
+    assert_eq!(report.rows_removed, 1);
+    assert!(db.get_dataset("cache").unwrap().rows.is_empty());
+}
+
+#[test]
+fn test_dataset_ttl_drops_idle_dataset() {
+    let mut db = TensorDb::with_config(EngineConfig::default());
+    setup_cache(&mut db);
+    execute_line(&mut db, "DATASET cache SET TTL 60 DATASET", 1).unwrap();
+
+    assert!(db.reap_expired().datasets_dropped.is_empty());
+    let report = db.reap_expired_at(Utc::now() + Duration::seconds(61));
+    assert_eq!(
+        report.datasets_dropped,
+        vec![("default".to_string(), "cache".to_string())]
+    );
+    assert!(db.get_dataset("cache").is_err());
+}
+
+#[test]
+fn test_ttl_via_metadata_and_removal() {
+    let mut db = TensorDb::with_config(EngineConfig::default());
+    setup_cache(&mut db);
+
+    // The TTL is plain metadata, so SET DATASET ... METADATA works too
+    execute_line(&mut db, "SET DATASET cache METADATA ttl = 10", 1).unwrap();
+    assert_eq!(
+        db.get_dataset("cache").unwrap().ttl(),
+        Some(Duration::seconds(10))
+    );
+
+    execute_line(&mut db, "DATASET cache SET TTL NONE", 2).unwrap();
+    assert_eq!(db.get_dataset("cache").unwrap().ttl(), None);
+    let report = db.reap_expired_at(Utc::now() + Duration::days(1));
+    assert_eq!(report.rows_removed, 0);
+
+    assert!(execute_line(&mut db, "DATASET cache SET TTL soon", 3).is_err());
+    assert!(execute_line(&mut db, "DATASET missing SET TTL 5", 4).is_err());
+}
+
+#[test]
+fn test_ttl_persists_and_expiry_is_flushed() {
+    let temp_dir = "/tmp/linal_test_dataset_ttl";
+    let _ = fs::remove_dir_all(temp_dir);
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            auto_persist: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    {
+        let mut db = TensorDb::with_config(config.clone());
+        setup_cache(&mut db);
+        execute_line(&mut db, "DATASET cache SET TTL 30", 1).unwrap();
+        db.reap_expired_at(Utc::now() + Duration::minutes(1));
+    }
+
+    let db = TensorDb::with_config(config);
+    let cache = db.get_dataset("cache").unwrap();
+    assert_eq!(cache.ttl(), Some(Duration::seconds(30)));
+    assert!(cache.rows.is_empty());
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
@@ -0,0 +1,85 @@
+use linal::core::config::{EngineConfig, VersioningConfig};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn versioned_db(retention: usize) -> TensorDb {
+    let config = EngineConfig {
+        versioning: VersioningConfig { retention },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String)
+    INSERT INTO users VALUES (1, "Alice")
+    INSERT INTO users VALUES (2, "Bob")
+    INSERT INTO users VALUES (3, "Carol")
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn row_count(db: &mut TensorDb, query: &str) -> usize {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.len(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_each_mutation_bumps_version() {
+    let mut db = versioned_db(10);
+    // Created at version 1, then one bump per insert
+    assert_eq!(db.get_dataset("users").unwrap().metadata.version, 4);
+
+    assert_eq!(row_count(&mut db, "SELECT * FROM users VERSION 1"), 0);
+    assert_eq!(row_count(&mut db, "SELECT * FROM users VERSION 3"), 2);
+    assert_eq!(row_count(&mut db, "SELECT * FROM users VERSION 4"), 3);
+    assert_eq!(
+        row_count(&mut db, "SELECT name FROM users VERSION 3 WHERE id > 1"),
+        1
+    );
+
+    // Unrelated datasets keep their version
+    execute_line(&mut db, "DATASET other COLUMNS (id: Int)", 1).unwrap();
+    execute_line(&mut db, "INSERT INTO other VALUES (1)", 2).unwrap();
+    assert_eq!(db.get_dataset("users").unwrap().metadata.version, 4);
+    assert_eq!(db.get_dataset("other").unwrap().metadata.version, 2);
+}
+
+#[test]
+fn test_select_as_of_timestamp() {
+    let mut db = versioned_db(10);
+    let v2_time = db.get_dataset("users@2").unwrap().metadata.updated_at;
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    execute_line(&mut db, "INSERT INTO users VALUES (4, \"Dan\")", 1).unwrap();
+
+    let query = format!("SELECT * FROM users AS OF '{}'", v2_time.to_rfc3339());
+    assert_eq!(row_count(&mut db, &query), 1);
+    assert_eq!(
+        row_count(&mut db, "SELECT * FROM users AS OF '2999-01-01 00:00:00'"),
+        4
+    );
+
+    let err = execute_line(
+        &mut db,
+        "SELECT * FROM users AS OF '2000-01-01T00:00:00Z'",
+        2,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("No retained version"), "{}", err);
+    assert!(execute_line(&mut db, "SELECT * FROM users AS OF 'yesterday'", 3).is_err());
+}
+
+#[test]
+fn test_retention_limits_history() {
+    let mut db = versioned_db(2);
+    assert_eq!(row_count(&mut db, "SELECT * FROM users VERSION 3"), 2);
+    let err = execute_line(&mut db, "SELECT * FROM users VERSION 2", 1).unwrap_err();
+    assert!(err.to_string().contains("not retained"), "{}", err);
+
+    // Without retention only the current version can be queried
+    let mut db = versioned_db(0);
+    assert_eq!(row_count(&mut db, "SELECT * FROM users VERSION 4"), 3);
+    assert!(execute_line(&mut db, "SELECT * FROM users VERSION 3", 1).is_err());
+    assert!(execute_line(&mut db, "SELECT * FROM users VERSION x", 2).is_err());
+}
@@ -0,0 +1,144 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String, age: Int, score: Float, active: Bool)
+    INSERT INTO users VALUES (1, "Alice", 30, 10.0, true)
+    INSERT INTO users VALUES (2, "Bob", 15, 20.0, false)
+    INSERT INTO users VALUES (3, "Carol", 17, 30.0, true)
+    INSERT INTO users VALUES (4, "Dan", 40, 40.0, true)
+    CREATE INDEX name_idx ON users(name)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn affected(output: DslOutput) -> (usize, String) {
+    match output {
+        DslOutput::Affected { rows, op } => (rows, op),
+        other => panic!("Expected affected rows, got {:?}", other),
+    }
+}
+
+fn names(db: &mut TensorDb, query: &str) -> Vec<Value> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.iter().map(|r| r.values[0].clone()).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_delete_with_where() {
+    let mut db = setup();
+
+    let out = execute_line(&mut db, "DELETE FROM users WHERE age < 18", 1).unwrap();
+    assert_eq!(affected(out), (2, "DELETE".to_string()));
+
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(users.len(), 2);
+    assert_eq!(users.metadata.row_count, 2);
+
+    // The index was rebuilt for the shifted row positions
+    assert_eq!(
+        names(&mut db, "SELECT id FROM users WHERE name = \"Dan\""),
+        vec![Value::Int(4)]
+    );
+    assert!(names(&mut db, "SELECT id FROM users WHERE name = \"Bob\"").is_empty());
+
+    // Nothing matches: no rows affected
+    let out = execute_line(&mut db, "DELETE FROM users WHERE age > 100", 2).unwrap();
+    assert_eq!(affected(out).0, 0);
+
+    // Without WHERE every row goes
+    let out = execute_line(&mut db, "DELETE FROM users", 3).unwrap();
+    assert_eq!(affected(out).0, 2);
+    assert!(db.get_dataset("users").unwrap().is_empty());
+}
+
+#[test]
+fn test_update_with_expression() {
+    let mut db = setup();
+
+    let out = execute_line(
+        &mut db,
+        "UPDATE users SET score = score * 1.5, name = \"X WHERE Y\" WHERE active = true",
+        1,
+    )
+    .unwrap();
+    assert_eq!(affected(out), (3, "UPDATE".to_string()));
+
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(users.rows[0].get("score"), Some(&Value::Float(15.0)));
+    assert_eq!(users.rows[1].get("score"), Some(&Value::Float(20.0)));
+    assert_eq!(
+        users.rows[3].get("name"),
+        Some(&Value::String("X WHERE Y".into()))
+    );
+
+    // The name index sees the new values
+    let index = users.get_index("name").unwrap();
+    assert_eq!(
+        index.lookup(&Value::String("X WHERE Y".into())).unwrap(),
+        vec![0, 2, 3]
+    );
+
+    // Results are cast to the column type
+    execute_line(&mut db, "UPDATE users SET age = age + 0.5 WHERE id = 2", 2).unwrap();
+    assert_eq!(
+        db.get_dataset("users").unwrap().rows[1].get("age"),
+        Some(&Value::Int(15))
+    );
+}
+
+#[test]
+fn test_update_and_delete_returning() {
+    let mut db = setup();
+
+    let out = execute_line(
+        &mut db,
+        "UPDATE users SET age = age + 1 WHERE id = 1 RETURNING id, age",
+        1,
+    )
+    .unwrap();
+    match out {
+        DslOutput::Table(ds) => {
+            assert_eq!(ds.rows.len(), 1);
+            assert_eq!(ds.rows[0].values, vec![Value::Int(1), Value::Int(31)]);
+        }
+        other => panic!("Expected table output, got {:?}", other),
+    }
+
+    let out = execute_line(
+        &mut db,
+        "DELETE FROM users WHERE age > 35 RETURNING name",
+        2,
+    )
+    .unwrap();
+    match out {
+        DslOutput::Table(ds) => {
+            assert_eq!(ds.rows.len(), 1);
+            assert_eq!(ds.rows[0].values, vec![Value::String("Dan".into())]);
+        }
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_update_errors_leave_rows_untouched() {
+    let mut db = setup();
+
+    assert!(execute_line(&mut db, "UPDATE users SET nope = 1", 1).is_err());
+    assert!(execute_line(&mut db, "UPDATE users score = 1", 2).is_err());
+    assert!(execute_line(&mut db, "DELETE FROM missing WHERE id = 1", 3).is_err());
+
+    // A failed cast aborts the whole update
+    let err = execute_line(&mut db, "UPDATE users SET age = name", 4).unwrap_err();
+    assert!(err.to_string().contains("Cannot cast"), "{}", err);
+    assert_eq!(
+        db.get_dataset("users").unwrap().rows[0].get("age"),
+        Some(&Value::Int(30))
+    );
+}
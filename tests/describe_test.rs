@@ -0,0 +1,107 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup_db() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String, score: Float, embedding: Vector(2))
+    INSERT INTO users VALUES (1, "Ada", 2.0, [1.0, 0.0])
+    INSERT INTO users VALUES (2, "Bob", 4.0, [0.0, 1.0])
+    INSERT INTO users VALUES (3, "Cy", 6.0, [1.0, 1.0])
+    INSERT INTO users VALUES (4, "Ada", 8.0, [0.5, 0.5])
+    DATASET users ADD COLUMN age: Int?
+    UPDATE users SET age = 36 WHERE id = 1
+    UPDATE users SET age = 20 WHERE id = 3
+    UPDATE users SET age = 28 WHERE id = 4
+    CREATE INDEX idx_id ON users(id)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+/// Result rows keyed by their first column
+fn rows(db: &mut TensorDb, command: &str) -> Vec<(String, Vec<Value>)> {
+    match execute_line(db, command, 1).unwrap() {
+        DslOutput::Table(ds) => ds
+            .rows
+            .iter()
+            .map(|r| match &r.values[0] {
+                Value::String(column) => (column.clone(), r.values[1..].to_vec()),
+                other => panic!("Expected a column name, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn text(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+#[test]
+fn test_describe_dataset() {
+    let mut db = setup_db();
+    let described = rows(&mut db, "DESCRIBE users");
+
+    let columns: Vec<&str> = described.iter().map(|(c, _)| c.as_str()).collect();
+    assert_eq!(columns, vec!["id", "name", "score", "embedding", "age"]);
+
+    // type, nullable, index, non_null, rows
+    assert_eq!(
+        described[0].1,
+        vec![
+            text("INT"),
+            Value::Bool(false),
+            text("HASH"),
+            Value::Int(4),
+            Value::Int(4)
+        ]
+    );
+    assert_eq!(described[3].1[0], text("VECTOR[2]"));
+    assert_eq!(described[4].1[1], Value::Bool(true));
+    assert_eq!(described[4].1[2], Value::Null);
+    assert_eq!(described[4].1[3], Value::Int(3));
+}
+
+#[test]
+fn test_summarize_dataset() {
+    let mut db = setup_db();
+    let summary = rows(&mut db, "SUMMARIZE users");
+    assert_eq!(summary.len(), 5);
+
+    // type, count, null_pct, distinct, min, max, mean, stddev
+    let age = &summary[4].1;
+    assert_eq!(age[1], Value::Int(3));
+    assert_eq!(age[2], Value::Float(25.0));
+    assert_eq!(age[3], Value::Int(3));
+    assert_eq!((&age[4], &age[5]), (&text("20"), &text("36")));
+    assert_eq!(age[6], Value::Float(28.0));
+    assert_eq!(age[7], Value::Float(8.0));
+
+    let score = &summary[2].1;
+    assert_eq!(score[6], Value::Float(5.0));
+    match score[7] {
+        Value::Float(stddev) => assert!((stddev - 2.5819888).abs() < 1e-5),
+        ref other => panic!("Expected a standard deviation, got {:?}", other),
+    }
+
+    // Strings have bounds and distinct counts but no mean
+    let name = &summary[1].1;
+    assert_eq!(name[3], Value::Int(3));
+    assert_eq!((&name[4], &name[5]), (&text("Ada"), &text("Cy")));
+    assert_eq!((&name[6], &name[7]), (&Value::Null, &Value::Null));
+
+    // Vectors only get counts
+    let embedding = &summary[3].1;
+    assert_eq!(embedding[3], Value::Int(4));
+    assert_eq!(embedding[4], Value::Null);
+}
+
+#[test]
+fn test_describe_errors() {
+    let mut db = setup_db();
+    assert!(execute_line(&mut db, "DESCRIBE missing", 1).is_err());
+    let err = execute_line(&mut db, "SUMMARIZE users extra", 1).unwrap_err();
+    assert!(err.to_string().contains("Expected: SUMMARIZE <dataset>"));
+}
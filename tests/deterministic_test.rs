@@ -0,0 +1,99 @@
+use linal::core::config::{EngineConfig, ExecutionConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn deterministic_db(seed: u64) -> TensorDb {
+    let config = EngineConfig {
+        execution: ExecutionConfig {
+            deterministic: true,
+            seed,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+    let script = r#"
+    DATASET sales COLUMNS (region: String, year: Int, amount: Float)
+    INSERT INTO sales VALUES ("west", 2024, 10.0), ("east", 2023, 5.0), ("north", 2024, 7.0)
+    INSERT INTO sales VALUES ("east", 2024, 3.0), ("south", 2023, 1.0), ("west", 2023, 2.0)
+    INSERT INTO sales VALUES ("central", 2024, 4.0), ("north", 2023, 6.0), ("east", 2024, 8.0)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_group_by_output_is_sorted_by_key() {
+    let mut db = deterministic_db(0);
+
+    let regions: Vec<Value> = rows(
+        &mut db,
+        "SELECT region, SUM(amount) FROM sales GROUP BY region",
+    )
+    .into_iter()
+    .map(|r| r[0].clone())
+    .collect();
+    let expected: Vec<Value> = ["central", "east", "north", "south", "west"]
+        .iter()
+        .map(|s| Value::String(s.to_string()))
+        .collect();
+    assert_eq!(regions, expected);
+
+    // Multi-column keys sort column by column
+    let keys: Vec<(Value, Value)> = rows(
+        &mut db,
+        "SELECT year, region, COUNT(*) FROM sales GROUP BY year, region",
+    )
+    .into_iter()
+    .map(|r| (r[0].clone(), r[1].clone()))
+    .collect();
+    let mut sorted = keys.clone();
+    sorted.sort_by(|a, b| {
+        a.0.compare(&b.0)
+            .unwrap()
+            .then_with(|| a.1.compare(&b.1).unwrap())
+    });
+    assert_eq!(keys, sorted);
+}
+
+#[test]
+fn test_repeated_runs_return_identical_results() {
+    let query = "SELECT region, year, AVG(amount) FROM sales GROUP BY region, year";
+    let first = rows(&mut deterministic_db(0), query);
+    for _ in 0..5 {
+        assert_eq!(rows(&mut deterministic_db(0), query), first);
+    }
+}
+
+#[test]
+fn test_seeded_rng_is_reproducible() {
+    let sequence = |db: &TensorDb| -> Vec<Vec<u64>> {
+        (0..3)
+            .map(|_| {
+                let mut rng = db.rng();
+                (0..4).map(|_| rng.next_u64()).collect()
+            })
+            .collect()
+    };
+
+    let a = sequence(&deterministic_db(42));
+    assert_eq!(a, sequence(&deterministic_db(42)));
+    assert_ne!(a, sequence(&deterministic_db(7)));
+    // Each generator handed out gets its own stream
+    assert_ne!(a[0], a[1]);
+
+    let mut rng = deterministic_db(42).rng();
+    for _ in 0..100 {
+        let x = rng.next_f64();
+        assert!((0.0..1.0).contains(&x));
+        assert!(rng.below(10) < 10);
+    }
+}
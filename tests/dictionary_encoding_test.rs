@@ -0,0 +1,164 @@
+use linal::core::config::{EngineConfig, ExecutionConfig};
+use linal::core::tuple::{Field, Schema, Tuple};
+use linal::core::value::{Value, ValueType};
+use linal::dsl::{execute_line, execute_sql, DslOutput};
+use linal::engine::TensorDb;
+use linal::query::columnar::ColumnChunk;
+use std::sync::Arc;
+
+fn db_with_threshold(columnar_threshold: usize) -> TensorDb {
+    let config = EngineConfig {
+        execution: ExecutionConfig {
+            columnar_threshold,
+            deterministic: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+    execute_sql(
+        &mut db,
+        "CREATE TABLE sales (id INT NOT NULL, category TEXT, region TEXT, note TEXT, amount INT)",
+        1,
+    )
+    .unwrap();
+    let categories = ["books", "games", "garden", "music"];
+    for i in 0..80usize {
+        let note = match i % 5 {
+            0 => "NULL",
+            1 | 2 => "'gift'",
+            _ => "'return'",
+        };
+        let sql = format!(
+            "INSERT INTO sales VALUES ({}, '{}', '{}', {}, {})",
+            i,
+            categories[i % 4],
+            if i % 3 == 0 { "north" } else { "south" },
+            note,
+            i % 17
+        );
+        execute_sql(&mut db, &sql, 1).unwrap();
+    }
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn string_rows(values: &[Option<&str>]) -> Vec<Tuple> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("s", ValueType::String).nullable()
+    ]));
+    values
+        .iter()
+        .map(|v| {
+            let value = v.map_or(Value::Null, |s| Value::String(s.to_string()));
+            Tuple::new(schema.clone(), vec![value]).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn test_low_cardinality_strings_are_dictionary_encoded() {
+    let rows = string_rows(&[Some("a"), Some("b"), None, Some("a"), Some("b"), Some("a")]);
+    match ColumnChunk::from_rows(&rows, 0, &ValueType::String).unwrap() {
+        ColumnChunk::Dictionary {
+            codes,
+            dictionary,
+            valid,
+        } => {
+            assert_eq!(dictionary.to_vec(), vec!["a".to_string(), "b".to_string()]);
+            assert_eq!(codes, vec![0, 1, 0, 0, 1, 0]);
+            assert_eq!(valid, vec![true, true, false, true, true, true]);
+        }
+        other => panic!("Expected a dictionary chunk, got {:?}", other),
+    }
+
+    // Mostly distinct strings are left to the row path
+    let rows = string_rows(&[Some("a"), Some("b"), Some("c"), Some("a")]);
+    assert!(ColumnChunk::from_rows(&rows, 0, &ValueType::String).is_none());
+}
+
+#[test]
+fn test_dictionary_filters_match_row_execution() {
+    let mut columnar = db_with_threshold(1);
+    let mut row_based = db_with_threshold(usize::MAX);
+
+    let queries = [
+        "SELECT id FROM sales WHERE category = \"games\"",
+        "SELECT id FROM sales WHERE category != \"games\" AND amount > 5",
+        "SELECT id FROM sales WHERE \"garden\" <= category",
+        "SELECT id FROM sales WHERE category LIKE \"g%\" OR region = \"north\"",
+        "SELECT id FROM sales WHERE category NOT LIKE \"%s\"",
+        "SELECT id FROM sales WHERE category = \"toys\"",
+        "SELECT id FROM sales WHERE note = \"gift\" OR note != \"gift\"",
+        "SELECT id FROM sales WHERE note < \"h\" AND category >= \"games\"",
+    ];
+    for query in queries {
+        assert_eq!(
+            rows(&mut columnar, query),
+            rows(&mut row_based, query),
+            "{}",
+            query
+        );
+    }
+}
+
+#[test]
+fn test_group_by_dictionary_codes_matches_row_execution() {
+    let mut columnar = db_with_threshold(1);
+    let mut row_based = db_with_threshold(usize::MAX);
+
+    let queries = [
+        "SELECT category, COUNT(*), SUM(amount) FROM sales GROUP BY category",
+        "SELECT region, category, MAX(amount), AVG(amount) FROM sales GROUP BY region, category",
+        "SELECT category, COUNT(*) AS n FROM sales WHERE amount > 3 GROUP BY category HAVING n > 10",
+    ];
+    for query in queries {
+        assert_eq!(
+            rows(&mut columnar, query),
+            rows(&mut row_based, query),
+            "{}",
+            query
+        );
+    }
+
+    let groups = rows(
+        &mut columnar,
+        "SELECT category, COUNT(*) FROM sales GROUP BY category",
+    );
+    assert_eq!(
+        groups,
+        vec![
+            vec![Value::String("books".into()), Value::Int(20)],
+            vec![Value::String("games".into()), Value::Int(20)],
+            vec![Value::String("garden".into()), Value::Int(20)],
+            vec![Value::String("music".into()), Value::Int(20)],
+        ]
+    );
+}
+
+#[test]
+fn test_high_cardinality_strings_fall_back_to_rows() {
+    let mut db = db_with_threshold(1);
+    execute_sql(&mut db, "CREATE TABLE users (id INT, email TEXT)", 1).unwrap();
+    for i in 0..10 {
+        let sql = format!("INSERT INTO users VALUES ({}, 'user{}@example.com')", i, i);
+        execute_sql(&mut db, &sql, 1).unwrap();
+    }
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT id FROM users WHERE email = \"user3@example.com\""
+        ),
+        vec![vec![Value::Int(3)]]
+    );
+    assert_eq!(
+        rows(&mut db, "SELECT email, COUNT(*) FROM users GROUP BY email").len(),
+        10
+    );
+}
@@ -0,0 +1,143 @@
+use linal::core::config::{EngineConfig, ExecutionConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup(db: &mut TensorDb) {
+    let script = r#"
+    DATASET docs COLUMNS (id: Int, category: String, emb: Vector(2))
+    INSERT INTO docs VALUES (1, "news", [1.0, 0.0]), (2, "blog", [1.0, 0.0])
+    INSERT INTO docs VALUES (3, "news", [0.0, -0.0]), (4, "news", [0.0, 0.0])
+    INSERT INTO docs VALUES (5, "wiki", [0.5, 0.5]), (6, "blog", [1.0, 0.0])
+    "#;
+    execute_script(db, script).unwrap();
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<Vec<Value>> {
+    values
+        .iter()
+        .map(|s| vec![Value::String(s.to_string())])
+        .collect()
+}
+
+#[test]
+fn test_select_distinct_keeps_first_occurrences() {
+    let mut db = TensorDb::new();
+    setup(&mut db);
+
+    assert_eq!(
+        rows(&mut db, "SELECT DISTINCT category FROM docs"),
+        strings(&["news", "blog", "wiki"])
+    );
+    // Whole rows are compared
+    assert_eq!(
+        rows(&mut db, "SELECT DISTINCT category, id FROM docs").len(),
+        6
+    );
+    assert_eq!(
+        rows(&mut db, "select distinct category from docs where id > 1"),
+        strings(&["blog", "news", "wiki"])
+    );
+}
+
+#[test]
+fn test_order_by_and_limit_apply_after_distinct() {
+    let mut db = TensorDb::new();
+    setup(&mut db);
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT DISTINCT category FROM docs ORDER BY category DESC LIMIT 2"
+        ),
+        strings(&["wiki", "news"])
+    );
+
+    execute_line(
+        &mut db,
+        "DATASET categories FROM docs SELECT DISTINCT category LIMIT 2",
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        rows(&mut db, "SELECT * FROM categories"),
+        strings(&["news", "blog"])
+    );
+
+    assert_eq!(
+        rows(
+            &mut db,
+            "SQL SELECT DISTINCT category FROM docs ORDER BY category"
+        ),
+        strings(&["blog", "news", "wiki"])
+    );
+}
+
+#[test]
+fn test_vector_keys_dedupe_and_group() {
+    let mut db = TensorDb::new();
+    setup(&mut db);
+
+    // [0.0, -0.0] and [0.0, 0.0] are the same key
+    let distinct = rows(&mut db, "SELECT DISTINCT emb FROM docs");
+    assert_eq!(
+        distinct,
+        vec![
+            vec![Value::Vector(vec![1.0, 0.0])],
+            vec![Value::Vector(vec![0.0, -0.0])],
+            vec![Value::Vector(vec![0.5, 0.5])],
+        ]
+    );
+
+    let mut counts: Vec<i64> = rows(&mut db, "SELECT emb, COUNT(*) FROM docs GROUP BY emb")
+        .iter()
+        .map(|r| match r[1] {
+            Value::Int(n) => n,
+            ref other => panic!("Expected a count, got {:?}", other),
+        })
+        .collect();
+    counts.sort();
+    assert_eq!(counts, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_vector_groups_are_ordered_in_deterministic_mode() {
+    let config = EngineConfig {
+        execution: ExecutionConfig {
+            deterministic: true,
+            seed: 0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+    setup(&mut db);
+
+    let keys: Vec<Value> = rows(&mut db, "SELECT emb, COUNT(*) FROM docs GROUP BY emb")
+        .into_iter()
+        .map(|r| r[0].clone())
+        .collect();
+    assert_eq!(
+        keys,
+        vec![
+            Value::Vector(vec![0.0, -0.0]),
+            Value::Vector(vec![0.5, 0.5]),
+            Value::Vector(vec![1.0, 0.0]),
+        ]
+    );
+}
+
+#[test]
+fn test_distinct_on_is_rejected_in_sql() {
+    let mut db = TensorDb::new();
+    setup(&mut db);
+
+    assert!(execute_line(&mut db, "SQL SELECT DISTINCT ON (category) id FROM docs", 1).is_err());
+}
@@ -263,3 +263,61 @@ fn test_dataset_duplicate_name() {
 
     execute_script(&mut db, script).unwrap();
 }
+
+#[test]
+fn test_clause_order_is_canonical() {
+    use linal::core::value::Value;
+    use linal::dsl::execute_line;
+
+    let mut db = TensorDb::new();
+
+    let script = r#"
+        DATASET numbers COLUMNS (value: INT, parity: STRING)
+        INSERT INTO numbers VALUES (3, "odd")
+        INSERT INTO numbers VALUES (1, "odd")
+        INSERT INTO numbers VALUES (5, "odd")
+        INSERT INTO numbers VALUES (2, "even")
+        INSERT INTO numbers VALUES (4, "even")
+
+        DATASET top_two FROM numbers LIMIT 2 ORDER BY value DESC
+        DATASET top_odd FROM numbers LIMIT 2 SELECT value ORDER BY value DESC FILTER parity = "odd"
+    "#;
+    execute_script(&mut db, script).unwrap();
+
+    // LIMIT applies after ORDER BY, whatever the textual order
+    let values = |db: &TensorDb, name: &str| -> Vec<Value> {
+        let ds = db.get_dataset(name).unwrap();
+        ds.rows.iter().map(|r| r.get("value").unwrap().clone()).collect()
+    };
+    assert_eq!(values(&db, "top_two"), vec![Value::Int(5), Value::Int(4)]);
+    assert_eq!(values(&db, "top_odd"), vec![Value::Int(5), Value::Int(3)]);
+
+    // ORDER BY sorts aggregated groups rather than input rows
+    execute_line(
+        &mut db,
+        "DATASET by_parity FROM numbers GROUP BY parity SELECT parity, SUM(value) ORDER BY parity",
+        1,
+    )
+    .unwrap();
+    let parities: Vec<Value> = db
+        .get_dataset("by_parity")
+        .unwrap()
+        .rows
+        .iter()
+        .map(|r| r.get("parity").unwrap().clone())
+        .collect();
+    assert_eq!(
+        parities,
+        vec![Value::String("even".into()), Value::String("odd".into())]
+    );
+
+    // Ambiguous or meaningless clause combinations are rejected
+    assert!(execute_line(&mut db, "DATASET bad1 FROM numbers LIMIT 2 LIMIT 3", 2).is_err());
+    assert!(execute_line(
+        &mut db,
+        "DATASET bad2 FROM numbers ORDER BY value ORDER BY parity",
+        3
+    )
+    .is_err());
+    assert!(execute_line(&mut db, "SELECT value FROM numbers HAVING value > 1", 4).is_err());
+}
@@ -0,0 +1,119 @@
+use linal::core::config::{EngineConfig, MemoryConfig, StorageConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::memory::estimated_size;
+use linal::engine::TensorDb;
+use std::fs;
+use std::path::PathBuf;
+
+fn config(data_dir: &str, auto_persist: bool) -> EngineConfig {
+    let _ = fs::remove_dir_all(data_dir);
+    EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(data_dir),
+            default_db: "default".to_string(),
+            auto_persist,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Three datasets of ten rows each, `a` created first; the budget is set
+/// afterwards so that only two of them fit under the threshold
+fn setup(db: &mut TensorDb) {
+    for name in ["a", "b", "c"] {
+        let script = format!(
+            "DATASET {name} COLUMNS (id: Int, v: Float)\n\
+             INSERT INTO {name} VALUES (1, 1.0), (2, 2.0), (3, 3.0), (4, 4.0), (5, 5.0), \
+             (6, 6.0), (7, 7.0), (8, 8.0), (9, 9.0), (10, 10.0)"
+        );
+        execute_script(db, &script).unwrap();
+    }
+    let size = estimated_size(db.get_dataset("a").unwrap());
+    db.config.memory = MemoryConfig {
+        budget_bytes: size * 5 / 2,
+        evict_at: 1.0,
+    };
+}
+
+fn sum_of_v(db: &mut TensorDb, dataset: &str) -> Value {
+    let query = format!("SELECT SUM(v) FROM {}", dataset);
+    match execute_line(db, &query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows[0].values[0].clone(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+#[test]
+fn test_least_recently_used_dataset_is_evicted_and_reloaded() {
+    let data_dir = "/tmp/linal_test_eviction";
+    let mut db = TensorDb::with_config(config(data_dir, true));
+    setup(&mut db);
+
+    // Reading `a` makes `b` the coldest dataset
+    execute_line(&mut db, "SELECT * FROM a", 1).unwrap();
+    let evicted: Vec<String> = db.evicted_datasets().into_iter().map(|e| e.name).collect();
+    assert_eq!(evicted, vec!["b".to_string()]);
+    let err = db.get_dataset("b").unwrap_err().to_string();
+    assert!(err.contains("evicted"), "{}", err);
+
+    let report = execute_line(&mut db, "SHOW EVICTIONS", 1)
+        .unwrap()
+        .to_string();
+    assert!(report.contains("Evictions: 1"), "{}", report);
+    assert!(report.lines().any(|l| l.starts_with("b ")), "{}", report);
+
+    // Naming `b` brings it back with its rows; `c` is the coldest now
+    assert_eq!(sum_of_v(&mut db, "b"), Value::Float(55.0));
+    let stats = db.memory_stats();
+    assert_eq!((stats.evictions, stats.reloads), (2, 1));
+    assert_eq!(stats.bytes_reloaded, stats.bytes_evicted / 2);
+    let evicted: Vec<String> = db.evicted_datasets().into_iter().map(|e| e.name).collect();
+    assert_eq!(evicted, vec!["c".to_string()]);
+    assert!(stats.resident_bytes <= db.config.memory.budget_bytes);
+
+    // Writes to a reloaded dataset are kept
+    execute_line(&mut db, "INSERT INTO c VALUES (11, 11.0)", 1).unwrap();
+    assert_eq!(sum_of_v(&mut db, "c"), Value::Float(66.0));
+
+    fs::remove_dir_all(data_dir).unwrap();
+}
+
+#[test]
+fn test_snapshots_include_evicted_datasets() {
+    let data_dir = "/tmp/linal_test_eviction_snapshot";
+    let mut db = TensorDb::with_config(config(data_dir, true));
+    setup(&mut db);
+    execute_line(&mut db, "SELECT * FROM a", 1).unwrap();
+    assert_eq!(db.evicted_datasets().len(), 1);
+
+    let fork = db.fork_database("default").unwrap();
+    let mut names: Vec<String> = fork
+        .datasets
+        .iter()
+        .filter_map(|ds| ds.metadata.name.clone())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["a", "b", "c"]);
+
+    fs::remove_dir_all(data_dir).unwrap();
+}
+
+#[test]
+fn test_nothing_is_evicted_without_persistence() {
+    let data_dir = "/tmp/linal_test_eviction_memory_only";
+    let mut db = TensorDb::with_config(config(data_dir, false));
+    setup(&mut db);
+
+    execute_line(&mut db, "SELECT * FROM a", 1).unwrap();
+    assert!(db.evicted_datasets().is_empty());
+    for name in ["a", "b", "c"] {
+        assert!(db.get_dataset(name).is_ok());
+    }
+    let stats = db.memory_stats();
+    assert_eq!(stats.evictions, 0);
+    assert!(stats.resident_bytes > db.config.memory.budget_bytes);
+
+    let _ = fs::remove_dir_all(data_dir);
+}
@@ -0,0 +1,72 @@
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String, age: Int)
+    INSERT INTO users VALUES (1, "Alice", 30), (2, "Bob", 25), (3, "Carol", 41)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn explain(db: &mut TensorDb, query: &str) -> String {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Message(msg) => msg,
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+#[test]
+fn test_explain_analyze_reports_every_operator() {
+    let mut db = setup();
+    let report = explain(
+        &mut db,
+        "EXPLAIN ANALYZE SELECT name FROM users WHERE age > 28 ORDER BY age DESC LIMIT 1",
+    );
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[0], "--- EXPLAIN ANALYZE ---");
+    assert!(
+        lines[1].starts_with("ProjectionExec  rows=1 "),
+        "{}",
+        report
+    );
+    assert!(lines[2].starts_with("└─ TopKExec  rows=1 "), "{}", report);
+    assert!(
+        lines[3].starts_with("   └─ FilterExec  rows=2 "),
+        "{}",
+        report
+    );
+    assert!(
+        lines[4].starts_with("      └─ SeqScanExec(users)  rows=3 "),
+        "{}",
+        report
+    );
+    for line in &lines[1..5] {
+        assert!(
+            line.contains(" time=") && line.contains(" self="),
+            "{}",
+            line
+        );
+        assert!(!line.ends_with("memory=0B"), "{}", line);
+    }
+    assert!(report.contains("Total: 1 rows in "), "{}", report);
+}
+
+#[test]
+fn test_explain_analyze_dataset_query_does_not_create_target() {
+    let mut db = setup();
+    let report = explain(
+        &mut db,
+        "EXPLAIN ANALYZE DATASET adults FROM users FILTER age > 28",
+    );
+    assert!(report.contains("Total: 2 rows in "), "{}", report);
+    assert!(db.get_dataset("adults").is_err());
+}
+
+#[test]
+fn test_explain_analyze_reports_errors_of_the_query() {
+    let mut db = setup();
+    assert!(execute_line(&mut db, "EXPLAIN ANALYZE SELECT * FROM missing", 1).is_err());
+}
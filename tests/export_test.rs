@@ -0,0 +1,175 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use std::fs;
+
+fn setup_db() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET items COLUMNS (id: Int, name: String, price: Float, emb: Vector(2))
+    INSERT INTO items VALUES (1, "pen", 1.5, [0.1, 0.2])
+    INSERT INTO items VALUES (2, "ink", 3.25, [0.3, 0.4])
+    INSERT INTO items VALUES (3, "pad", 0.5, [0.5, 0.6])
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn reset_dir(dir: &str) {
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_export_json() {
+    let dir = "/tmp/linal_test_export_json";
+    reset_dir(dir);
+    let mut db = setup_db();
+
+    let out = execute_line(
+        &mut db,
+        &format!(
+            r#"EXPORT (SELECT id, name, emb FROM items WHERE price > 1.0 ORDER BY price DESC) TO "{}/out.json" FORMAT json"#,
+            dir
+        ),
+        1,
+    )
+    .unwrap();
+    assert!(matches!(out, DslOutput::Message(ref m) if m.contains("Exported 2 rows")));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(format!("{}/out.json", dir)).unwrap()).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([
+            {"id": 2, "name": "ink", "emb": [0.3, 0.4]},
+            {"id": 1, "name": "pen", "emb": [0.1, 0.2]},
+        ])
+    );
+
+    reset_dir(dir);
+}
+
+#[test]
+fn test_export_csv_round_trips_through_load() {
+    let dir = "/tmp/linal_test_export_csv";
+    reset_dir(dir);
+    let mut db = setup_db();
+
+    // Format inferred from the extension
+    execute_line(
+        &mut db,
+        &format!(r#"EXPORT (SELECT * FROM items) TO "{}/items.csv""#, dir),
+        1,
+    )
+    .unwrap();
+    let csv = fs::read_to_string(format!("{}/items.csv", dir)).unwrap();
+    assert!(csv.starts_with("id,name,price,emb\n1,pen,1.5,\"[0.1,0.2]\"\n"));
+
+    execute_line(
+        &mut db,
+        &format!(r#"LOAD DATASET items_copy FROM "{}/items.csv""#, dir),
+        2,
+    )
+    .unwrap();
+    let original = db.get_dataset("items").unwrap().rows.clone();
+    let copy = &db.get_dataset("items_copy").unwrap().rows;
+    let values = |rows: &[linal::core::tuple::Tuple]| -> Vec<Vec<Value>> {
+        rows.iter().map(|r| r.values.clone()).collect()
+    };
+    assert_eq!(values(&original), values(copy));
+
+    reset_dir(dir);
+}
+
+#[test]
+fn test_export_parquet() {
+    let dir = "/tmp/linal_test_export_parquet";
+    reset_dir(dir);
+    let mut db = setup_db();
+
+    execute_line(
+        &mut db,
+        &format!(
+            r#"EXPORT (SELECT id, price FROM items LIMIT 2) TO "{}/sub/items.parquet" FORMAT PARQUET"#,
+            dir
+        ),
+        1,
+    )
+    .unwrap();
+    let bytes = fs::read(format!("{}/sub/items.parquet", dir)).unwrap();
+    assert_eq!(&bytes[..4], b"PAR1");
+
+    reset_dir(dir);
+}
+
+#[test]
+fn test_export_parquet_options() {
+    use parquet::basic::Compression;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let dir = "/tmp/linal_test_export_parquet_options";
+    reset_dir(dir);
+    let mut db = setup_db();
+
+    execute_line(
+        &mut db,
+        &format!(
+            r#"EXPORT (SELECT id, name FROM items) TO "{}/items.parquet" COMPRESSION snappy ROW GROUP 2 NO STATISTICS"#,
+            dir
+        ),
+        1,
+    )
+    .unwrap();
+    let file = fs::File::open(format!("{}/items.parquet", dir)).unwrap();
+    let metadata = SerializedFileReader::new(file).unwrap().metadata().clone();
+    assert_eq!(metadata.num_row_groups(), 2);
+    let column = metadata.row_group(0).column(1);
+    assert_eq!(column.compression(), Compression::SNAPPY);
+    assert!(column.statistics().is_none());
+
+    // Without options the [storage] settings apply: zstd with statistics
+    execute_line(
+        &mut db,
+        &format!(r#"EXPORT (SELECT * FROM items) TO "{}/all.parquet""#, dir),
+        2,
+    )
+    .unwrap();
+    let file = fs::File::open(format!("{}/all.parquet", dir)).unwrap();
+    let metadata = SerializedFileReader::new(file).unwrap().metadata().clone();
+    let column = metadata.row_group(0).column(0);
+    assert!(matches!(column.compression(), Compression::ZSTD(_)));
+    assert!(column.statistics().is_some());
+
+    let mut export = |options: &str| {
+        execute_line(
+            &mut db,
+            &format!(
+                r#"EXPORT (SELECT * FROM items) TO "{}/x.parquet" {}"#,
+                dir, options
+            ),
+            3,
+        )
+    };
+    assert!(export("COMPRESSION brotli").is_err());
+    assert!(export("ROW GROUP 0").is_err());
+    assert!(export("FORMAT csv COMPRESSION zstd").is_err());
+    assert!(export("NO DICTIONARY STATISTICS FORMAT parquet").is_ok());
+
+    reset_dir(dir);
+}
+
+#[test]
+fn test_export_errors() {
+    let mut db = setup_db();
+
+    assert!(execute_line(&mut db, r#"EXPORT SELECT * FROM items TO "x.csv""#, 1).is_err());
+    assert!(execute_line(&mut db, r#"EXPORT (SELECT * FROM items) TO "x.txt""#, 1).is_err());
+    assert!(execute_line(
+        &mut db,
+        r#"EXPORT (SELECT * FROM items) TO "x.csv" FORMAT xml"#,
+        1
+    )
+    .is_err());
+    assert!(execute_line(&mut db, r#"EXPORT (SHOW ALL) TO "x.csv""#, 1).is_err());
+    assert!(execute_line(&mut db, r#"EXPORT (SELECT * FROM missing) TO "x.csv""#, 1).is_err());
+}
@@ -0,0 +1,139 @@
+//! Golden-file tests: every `tests/cases/*.lnl` script runs against a fresh
+//! database and its rendered output must match the `.out` file next to it.
+//!
+//! Regenerate snapshots after an intended change with:
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test --test golden_test
+//! ```
+
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::dsl::{execute_line, split_statements, DslOutput};
+use linal::engine::TensorDb;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CASES_DIR: &str = "tests/cases";
+
+/// Anything a script writes lands in a scratch directory
+fn golden_db() -> TensorDb {
+    TensorDb::with_config(EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from("/tmp/linal_test_golden"),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
+
+/// Tables print their rows; everything else uses the DslOutput display
+fn render_output(out: &mut String, output: &DslOutput) {
+    match output {
+        DslOutput::None => {}
+        DslOutput::Table(ds) => {
+            let header: Vec<String> = ds
+                .schema
+                .fields
+                .iter()
+                .map(|f| format!("{}: {}", f.name, f.value_type))
+                .collect();
+            writeln!(out, "{}", header.join(" | ")).unwrap();
+            for row in &ds.rows {
+                let cells: Vec<String> = row.values.iter().map(|v| v.to_string()).collect();
+                writeln!(out, "{}", cells.join(" | ")).unwrap();
+            }
+            writeln!(out, "({} row(s))", ds.rows.len()).unwrap();
+        }
+        other => writeln!(out, "{}", other.to_string().trim_end()).unwrap(),
+    }
+}
+
+/// Echo each statement followed by its output or error, so a script keeps
+/// going after a failing statement and the error text is part of the snapshot
+fn run_case(script: &str) -> String {
+    let mut db = golden_db();
+    let mut out = String::new();
+    let statements = match split_statements(script) {
+        Ok(statements) => statements,
+        Err(e) => return format!("error: {}\n", e),
+    };
+    for (line_no, cmd) in statements {
+        writeln!(out, "> {}", cmd).unwrap();
+        match execute_line(&mut db, &cmd, line_no) {
+            Ok(output) => render_output(&mut out, &output),
+            Err(e) => writeln!(out, "error: {}", e).unwrap(),
+        }
+    }
+    out
+}
+
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (e, a) => {
+                return format!(
+                    "line {}:\n  expected: {}\n  actual:   {}",
+                    line,
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                )
+            }
+        }
+    }
+}
+
+fn case_files() -> Vec<PathBuf> {
+    let mut cases: Vec<PathBuf> = fs::read_dir(CASES_DIR)
+        .expect("tests/cases should exist")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lnl"))
+        .collect();
+    cases.sort();
+    cases
+}
+
+fn check_case(path: &Path, update: bool) -> Result<(), String> {
+    let script = fs::read_to_string(path).unwrap();
+    let actual = run_case(&script);
+    let snapshot = path.with_extension("out");
+
+    if update {
+        fs::write(&snapshot, &actual).unwrap();
+        return Ok(());
+    }
+    let expected = fs::read_to_string(&snapshot)
+        .map_err(|_| format!("{}: missing snapshot", snapshot.display()))?
+        .replace("\r\n", "\n");
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: {}",
+            path.display(),
+            first_difference(&expected, &actual)
+        ))
+    }
+}
+
+#[test]
+fn test_golden_cases() {
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1");
+    let cases = case_files();
+    assert!(!cases.is_empty(), "no .lnl cases in {}", CASES_DIR);
+
+    let failures: Vec<String> = cases
+        .iter()
+        .filter_map(|path| check_case(path, update).err())
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "{} golden case(s) failed (rerun with UPDATE_GOLDEN=1 to accept):\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,105 @@
+use linal::core::value::{Value, ValueType};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET visits COLUMNS (id: Int, at: Timestamp, ms: Float)
+    INSERT INTO visits VALUES (1, "2024-03-01T08:00:00Z", 10.0), (12, "2024-03-01T17:30:00Z", 20.0), (21, "2024-03-02T09:00:00Z", 30.0), (13, "2024-03-02T11:00:00Z", 40.0), (4, "2024-03-02T23:00:00Z", 50.0)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn query(db: &mut TensorDb, query: &str) -> (Vec<String>, Vec<Vec<Value>>) {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => (
+            result
+                .schema
+                .fields
+                .iter()
+                .map(|f| f.name.clone())
+                .collect(),
+            result.rows.iter().map(|r| r.values.clone()).collect(),
+        ),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn ts(s: &str) -> Value {
+    Value::String(s.to_string())
+        .cast_to(&ValueType::Timestamp)
+        .unwrap()
+}
+
+#[test]
+fn test_group_by_arithmetic_key() {
+    let mut db = setup();
+    let (names, rows) = query(
+        &mut db,
+        "SELECT id % 10, COUNT(*) FROM visits GROUP BY id % 10 ORDER BY id % 10",
+    );
+    assert_eq!(names[0], "id % 10");
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), Value::Int(2)],
+            vec![Value::Int(2), Value::Int(1)],
+            vec![Value::Int(3), Value::Int(1)],
+            vec![Value::Int(4), Value::Int(1)],
+        ]
+    );
+}
+
+#[test]
+fn test_group_by_function_key_with_alias() {
+    let mut db = setup();
+    let (names, rows) = query(
+        &mut db,
+        "SELECT DATE_TRUNC(\"day\", at) AS day, SUM(ms) AS total FROM visits GROUP BY DATE_TRUNC(\"day\", at) ORDER BY day",
+    );
+    assert_eq!(names, vec!["day", "total"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![ts("2024-03-01"), Value::Float(30.0)],
+            vec![ts("2024-03-02"), Value::Float(120.0)],
+        ]
+    );
+}
+
+#[test]
+fn test_group_by_select_alias() {
+    let mut db = setup();
+    let (names, rows) = query(
+        &mut db,
+        "SELECT id / 10 AS decade, MAX(ms) FROM visits GROUP BY decade ORDER BY decade DESC",
+    );
+    assert_eq!(names[0], "decade");
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(2), Value::Float(30.0)],
+            vec![Value::Int(1), Value::Float(40.0)],
+            vec![Value::Int(0), Value::Float(50.0)],
+        ]
+    );
+}
+
+#[test]
+fn test_group_by_expression_in_sql() {
+    let mut db = setup();
+    let (names, rows) = query(
+        &mut db,
+        "SQL SELECT id % 2 AS parity, COUNT(*) AS n FROM visits GROUP BY id % 2 ORDER BY parity",
+    );
+    assert_eq!(names, vec!["parity", "n"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(0), Value::Int(2)],
+            vec![Value::Int(1), Value::Int(3)],
+        ]
+    );
+}
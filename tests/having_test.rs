@@ -0,0 +1,79 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET sales COLUMNS (region: String, amount: Int)
+    INSERT INTO sales VALUES ("N", 50), ("N", 70), ("S", 20), ("E", 200)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => {
+            assert_eq!(
+                result.schema.fields.len(),
+                result.rows.first().map_or(0, |r| r.values.len())
+            );
+            result.rows.iter().map(|r| r.values.clone()).collect()
+        }
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn row(region: &str, total: i64) -> Vec<Value> {
+    vec![Value::String(region.to_string()), Value::Int(total)]
+}
+
+#[test]
+fn test_having_filters_on_aggregate_outputs() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT region, SUM(amount) FROM sales GROUP BY region HAVING SUM(amount) > 100 ORDER BY region",
+    );
+    assert_eq!(result, vec![row("E", 200), row("N", 120)]);
+
+    // Case and spacing of the call do not matter
+    let result = rows(
+        &mut db,
+        "SELECT region, sum(amount) FROM sales GROUP BY region HAVING sum( amount ) > 100 ORDER BY region",
+    );
+    assert_eq!(result, vec![row("E", 200), row("N", 120)]);
+}
+
+#[test]
+fn test_having_resolves_aliases() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT region, SUM(amount) AS total FROM sales GROUP BY region HAVING total > 100 ORDER BY region",
+    );
+    assert_eq!(result, vec![row("E", 200), row("N", 120)]);
+}
+
+#[test]
+fn test_having_on_unselected_aggregates() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT region FROM sales GROUP BY region HAVING SUM(amount) > 100 AND COUNT(*) > 1",
+    );
+    assert_eq!(result, vec![vec![Value::String("N".to_string())]]);
+
+    let result = rows(
+        &mut db,
+        "SQL SELECT region FROM sales GROUP BY region HAVING MAX(amount) < 100 ORDER BY region",
+    );
+    assert_eq!(
+        result,
+        vec![
+            vec![Value::String("N".to_string())],
+            vec![Value::String("S".to_string())]
+        ]
+    );
+}
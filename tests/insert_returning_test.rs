@@ -0,0 +1,95 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, DslOutput};
+use linal::engine::TensorDb;
+
+fn table(output: DslOutput) -> linal::core::dataset_legacy::Dataset {
+    match output {
+        DslOutput::Table(ds) => ds,
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_dsl_insert_returning() {
+    let mut db = TensorDb::new();
+    execute_line(
+        &mut db,
+        "DATASET users COLUMNS (id: Int, name: String, score: Float)",
+        1,
+    )
+    .unwrap();
+
+    let out = execute_line(
+        &mut db,
+        "INSERT INTO users VALUES (1, \"Alice RETURNING\", 0.5) RETURNING name, id",
+        2,
+    )
+    .unwrap();
+    let ds = table(out);
+    let names: Vec<&str> = ds.schema.fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["name", "id"]);
+    assert_eq!(
+        ds.rows[0].values,
+        vec![Value::String("Alice RETURNING".into()), Value::Int(1)]
+    );
+
+    let ds = table(
+        execute_line(
+            &mut db,
+            "INSERT INTO users VALUES (2, \"Bob\", 1.0) RETURNING *",
+            3,
+        )
+        .unwrap(),
+    );
+    assert_eq!(ds.schema.len(), 3);
+    assert_eq!(ds.rows.len(), 1);
+    assert_eq!(ds.rows[0].values[0], Value::Int(2));
+
+    // Without RETURNING only the affected row count comes back
+    let out = execute_line(&mut db, "INSERT INTO users VALUES (3, \"Carol\", 2.0)", 4).unwrap();
+    assert!(matches!(out, DslOutput::Affected { rows: 1, .. }));
+
+    // An unknown column is rejected before the row is inserted
+    assert!(execute_line(
+        &mut db,
+        "INSERT INTO users VALUES (4, \"Dan\", 0.0) RETURNING nope",
+        5
+    )
+    .is_err());
+    assert_eq!(db.get_dataset("users").unwrap().rows.len(), 3);
+}
+
+#[test]
+fn test_sql_insert_returning() {
+    let mut db = TensorDb::new();
+    execute_line(
+        &mut db,
+        "SQL CREATE TABLE items (id INT NOT NULL, label TEXT)",
+        1,
+    )
+    .unwrap();
+
+    let out = execute_line(
+        &mut db,
+        "SQL INSERT INTO items (id, label) VALUES (1, 'a'), (2, 'b') RETURNING id",
+        2,
+    )
+    .unwrap();
+    let ds = table(out);
+    assert_eq!(ds.schema.len(), 1);
+    assert_eq!(
+        ds.rows
+            .iter()
+            .map(|r| r.values[0].clone())
+            .collect::<Vec<_>>(),
+        vec![Value::Int(1), Value::Int(2)]
+    );
+
+    assert!(execute_line(
+        &mut db,
+        "SQL INSERT INTO items VALUES (3, 'c') RETURNING id + 1",
+        3
+    )
+    .is_err());
+    assert_eq!(db.get_dataset("items").unwrap().rows.len(), 2);
+}
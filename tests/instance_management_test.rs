@@ -12,7 +12,11 @@ fn setup_test_db(temp_dir: &str) -> TensorDb {
         storage: StorageConfig {
             data_dir: PathBuf::from(temp_dir),
             default_db: "default".to_string(),
+            auto_persist: false,
+            wal: false,
+            ..Default::default()
         },
+        ..Default::default()
     };
     TensorDb::with_config(config)
 }
@@ -126,7 +130,11 @@ fn test_database_recovery() {
         storage: StorageConfig {
             data_dir: PathBuf::from(temp_dir),
             default_db: "default".to_string(),
+            auto_persist: false,
+            wal: false,
+            ..Default::default()
         },
+        ..Default::default()
     };
     let mut db2 = TensorDb::with_config(config);
 
@@ -168,3 +176,115 @@ fn test_drop_database() {
 
     fs::remove_dir_all(temp_dir).unwrap();
 }
+
+#[test]
+fn test_auto_persist_write_through_and_recovery() {
+    let temp_dir = "/tmp/linal_test_auto_persist";
+    let _ = fs::remove_dir_all(temp_dir);
+    fs::create_dir_all(temp_dir).unwrap();
+
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            default_db: "default".to_string(),
+            auto_persist: true,
+            wal: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    {
+        let mut db = TensorDb::with_config(config.clone());
+        execute_line(&mut db, "DATASET users COLUMNS (id: Int, name: String)", 1).unwrap();
+        let meta_path = format!("{}/default/datasets/users.meta.json", temp_dir);
+        assert!(std::path::Path::new(&meta_path).exists());
+
+        execute_line(&mut db, r#"INSERT INTO users VALUES (1, "Alice")"#, 2).unwrap();
+        execute_line(&mut db, r#"INSERT INTO users VALUES (2, "Bob")"#, 3).unwrap();
+        execute_line(&mut db, "ALTER DATASET users ADD COLUMN active: Bool", 4).unwrap();
+        // No SAVE issued
+    }
+
+    // A fresh engine recovers the data without LOAD
+    let db2 = TensorDb::with_config(config);
+    let users = db2.get_dataset("users").unwrap();
+    assert_eq!(users.len(), 2);
+    assert!(users.schema.get_field("active").is_some());
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_snapshot_and_restore_database() {
+    let temp_dir = "/tmp/linal_test_db_snapshot";
+    let mut db = setup_test_db(temp_dir);
+
+    execute_line(&mut db, "CREATE DATABASE shop", 1).unwrap();
+    execute_line(&mut db, "USE shop", 2).unwrap();
+    execute_line(&mut db, "DATASET items COLUMNS (id: Int, name: String)", 3).unwrap();
+    execute_line(&mut db, r#"INSERT INTO items VALUES (1, "pen")"#, 4).unwrap();
+    execute_line(&mut db, "CREATE INDEX id_idx ON items(id)", 5).unwrap();
+    execute_line(&mut db, "VECTOR prices = [1.5, 2.5]", 6).unwrap();
+
+    execute_line(&mut db, r#"SNAPSHOT DATABASE shop AS "v1""#, 7).unwrap();
+    assert!(std::path::Path::new(&format!("{}/shop/snapshots/v1", temp_dir)).is_dir());
+
+    // Diverge from the snapshot
+    execute_line(&mut db, r#"INSERT INTO items VALUES (2, "ink")"#, 8).unwrap();
+    execute_line(&mut db, "VECTOR discounts = [0.1]", 9).unwrap();
+
+    execute_line(&mut db, r#"RESTORE DATABASE shop FROM "v1""#, 10).unwrap();
+    assert_eq!(db.get_dataset("items").unwrap().len(), 1);
+    assert_eq!(*db.get("prices").unwrap().data, vec![1.5, 2.5]);
+    assert!(db.get("discounts").is_err());
+    assert!(db
+        .list_indices()
+        .iter()
+        .any(|(ds, col, _)| ds == "items" && col == "id"));
+
+    // Restoring into a new database name is not possible without its own snapshot
+    assert!(execute_line(&mut db, r#"RESTORE DATABASE other FROM "v1""#, 11).is_err());
+    assert!(execute_line(&mut db, r#"SNAPSHOT DATABASE missing AS "v1""#, 12).is_err());
+    assert!(execute_line(&mut db, r#"SNAPSHOT DATABASE shop AS "../v1""#, 13).is_err());
+    assert!(execute_line(&mut db, "SNAPSHOT DATABASE shop", 14).is_err());
+
+    fs::remove_dir_all(temp_dir).unwrap();
+}
+
+#[test]
+fn test_detached_database_sessions() {
+    let temp_dir = "/tmp/linal_test_db_sessions";
+    let mut db = setup_test_db(temp_dir);
+    execute_line(&mut db, "CREATE DATABASE shop", 1).unwrap();
+
+    // A session owns its database; the catalog keeps listing it
+    let mut session = db.detach_session("shop").unwrap();
+    assert!(db.list_databases().contains(&"shop".to_string()));
+    assert!(db.detach_session("shop").is_err());
+    assert!(db.detach_session("missing").is_err());
+
+    execute_line(&mut session, "DATASET items COLUMNS (id: Int)", 2).unwrap();
+    execute_line(&mut session, "INSERT INTO items VALUES (1), (2)", 3).unwrap();
+
+    // Other databases stay usable while the session runs
+    execute_line(&mut db, "DATASET notes COLUMNS (id: Int)", 4).unwrap();
+    execute_line(&mut db, "CREATE DATABASE archive", 5).unwrap();
+
+    db.attach_session(session);
+    db.use_database("shop").unwrap();
+    assert_eq!(db.get_dataset("items").unwrap().len(), 2);
+    assert!(db.get_dataset("notes").is_err());
+    // Audit entries of the session are merged into the engine's log
+    let audited = db.get_dataset("system.audit_log").unwrap().len();
+    assert_eq!(audited, 5);
+
+    // Dropping a database while a session holds it discards the session's work
+    db.use_database("default").unwrap();
+    let session = db.detach_session("archive").unwrap();
+    execute_line(&mut db, "DROP DATABASE archive", 6).unwrap();
+    db.attach_session(session);
+    assert!(!db.list_databases().contains(&"archive".to_string()));
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
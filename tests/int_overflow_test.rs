@@ -0,0 +1,102 @@
+use linal::core::value::{Value, ValueType};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn table(db: &mut TensorDb, query: &str) -> linal::core::dataset_legacy::Dataset {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds,
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_out_of_range_integers_are_parse_errors() {
+    let mut db = TensorDb::new();
+    execute_line(&mut db, "DATASET ids COLUMNS (id: Int, label: String)", 1).unwrap();
+
+    execute_line(
+        &mut db,
+        "INSERT INTO ids VALUES (9223372036854775807, \"max\"), (-9223372036854775808, \"min\")",
+        2,
+    )
+    .unwrap();
+    assert_eq!(
+        db.get_dataset("ids").unwrap().rows[0].get("id"),
+        Some(&Value::Int(i64::MAX))
+    );
+
+    let err = execute_line(
+        &mut db,
+        "INSERT INTO ids VALUES (18446744073709551615, \"u64\")",
+        3,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Integer 18446744073709551615 is out of range for Int"),
+        "{}",
+        err
+    );
+
+    let err = execute_line(
+        &mut db,
+        "SQL INSERT INTO ids VALUES (99999999999999999999, 'big')",
+        4,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{}", err);
+
+    // Casts report the range instead of a generic failure
+    let err = Value::String("18446744073709551615".into())
+        .cast_to(&ValueType::Int)
+        .unwrap_err();
+    assert!(err.contains("out of range"), "{}", err);
+    assert!(Value::Float(1.0e19).cast_to(&ValueType::Int).is_err());
+    assert_eq!(db.get_dataset("ids").unwrap().len(), 2);
+}
+
+#[test]
+fn test_int_overflow_promotes_to_float() {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET big COLUMNS (id: Int, grp: String, n: Int, f: Float)
+    INSERT INTO big VALUES (1, "a", 9223372036854775807, 0.0), (2, "a", 10, 0.0), (3, "b", 5, 0.0), (4, "b", 6, 0.0)
+    "#;
+    execute_script(&mut db, script).unwrap();
+
+    // SUM past i64::MAX becomes a Float; other groups stay exact
+    let ds = table(
+        &mut db,
+        "SELECT grp, SUM(n) FROM big GROUP BY grp ORDER BY grp",
+    );
+    assert_eq!(ds.schema.fields[1].value_type, ValueType::Float);
+    assert_eq!(
+        ds.rows[0].values[1],
+        Value::Float(9223372036854775807.0 + 10.0)
+    );
+    assert_eq!(ds.rows[1].values[1], Value::Float(11.0));
+
+    let ds = table(
+        &mut db,
+        "SELECT grp, SUM(n) FROM big WHERE grp = \"b\" GROUP BY grp",
+    );
+    assert_eq!(ds.rows[0].values[1], Value::Int(11));
+
+    // Row arithmetic promotes instead of wrapping
+    execute_line(&mut db, "UPDATE big SET f = n * 2", 1).unwrap();
+    let big = db.get_dataset("big").unwrap();
+    assert_eq!(
+        big.rows[0].get("f"),
+        Some(&Value::Float(9223372036854775807.0 * 2.0))
+    );
+    assert_eq!(big.rows[1].get("f"), Some(&Value::Float(20.0)));
+
+    // Storing an overflowed result back into an Int column is an error
+    let err = execute_line(&mut db, "UPDATE big SET n = n + 1 WHERE id = 1", 2).unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{}", err);
+    execute_line(&mut db, "UPDATE big SET n = n - 1 WHERE id = 1", 3).unwrap();
+    assert_eq!(
+        db.get_dataset("big").unwrap().rows[0].get("n"),
+        Some(&Value::Int(i64::MAX - 1))
+    );
+}
@@ -0,0 +1,202 @@
+use linal::core::config::{EngineConfig, ExecutionConfig, StorageConfig};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::{JobState, TensorDb};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn setup_users(db: &mut TensorDb) {
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String)
+    INSERT INTO users VALUES (1, "Ada")
+    INSERT INTO users VALUES (2, "Bob")
+    "#;
+    execute_script(db, script).unwrap();
+}
+
+fn count(db: &mut TensorDb, query: &str) -> usize {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.len(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+/// Wait until job `id` is no longer running
+fn wait_for_job(db: &TensorDb, id: u64) -> JobState {
+    let started = Instant::now();
+    loop {
+        let state = db
+            .jobs()
+            .into_iter()
+            .find(|job| job.id == id)
+            .unwrap()
+            .state;
+        if state != JobState::Running {
+            return state;
+        }
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "job {} hangs",
+            id
+        );
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn started_job(output: DslOutput) -> u64 {
+    let message = output.to_string();
+    let id = message
+        .strip_prefix("Started job ")
+        .and_then(|rest| rest.split(':').next())
+        .unwrap_or_else(|| panic!("Expected a started job, got {}", message));
+    id.parse().unwrap()
+}
+
+#[test]
+fn test_async_index_is_installed_by_next_command() {
+    let mut db = TensorDb::new();
+    setup_users(&mut db);
+
+    let out = execute_line(&mut db, "CREATE INDEX name_idx ON users(name) ASYNC", 1).unwrap();
+    let id = started_job(out);
+    // A write made while the job runs is covered once it is installed
+    execute_line(&mut db, r#"INSERT INTO users VALUES (3, "Cy")"#, 2).unwrap();
+
+    while wait_for_job(&db, id) != JobState::Done {
+        execute_line(&mut db, "SHOW JOBS", 3).unwrap();
+    }
+    assert_eq!(
+        db.list_indices(),
+        vec![("users".to_string(), "name".to_string(), "HASH".to_string())]
+    );
+    assert_eq!(
+        count(&mut db, r#"SELECT * FROM users WHERE name = "Cy""#),
+        1
+    );
+    let out = execute_line(&mut db, "SHOW JOBS", 4).unwrap().to_string();
+    assert!(out.contains("done"));
+    assert!(out.contains("CREATE INDEX name_idx ON users(name) ASYNC"));
+
+    let err = execute_line(&mut db, "CREATE INDEX x ON users(missing) ASYNC", 5).unwrap_err();
+    assert!(err.to_string().contains("Column 'missing' not found"));
+}
+
+#[test]
+fn test_cancel_job() {
+    let mut db = TensorDb::new();
+    setup_users(&mut db);
+
+    let out = execute_line(&mut db, "CREATE INDEX name_idx ON users(name) ASYNC", 1).unwrap();
+    let id = started_job(out);
+    // A finished job that is not installed yet can still be cancelled
+    assert_eq!(wait_for_job(&db, id), JobState::Ready);
+    let out = execute_line(&mut db, &format!("CANCEL JOB {}", id), 2).unwrap();
+    assert!(out.to_string().contains(&format!("Cancelled job {}", id)));
+
+    assert!(db.list_indices().is_empty());
+    assert_eq!(db.jobs()[0].state, JobState::Cancelled);
+    let err = execute_line(&mut db, &format!("CANCEL JOB {}", id), 3).unwrap_err();
+    assert!(err.to_string().contains("already cancelled"));
+    assert!(execute_line(&mut db, "CANCEL JOB 99", 4).is_err());
+    assert!(execute_line(&mut db, "CANCEL JOB", 5).is_err());
+
+    // No jobs inside a transaction
+    execute_line(&mut db, "BEGIN", 6).unwrap();
+    assert!(execute_line(&mut db, "CREATE INDEX i ON users(id) ASYNC", 7).is_err());
+}
+
+#[test]
+fn test_finished_jobs_are_pruned() {
+    let config = EngineConfig {
+        execution: ExecutionConfig {
+            finished_jobs_kept: 2,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+    setup_users(&mut db);
+
+    let mut cancelled = Vec::new();
+    for line in 1..=3 {
+        let out =
+            execute_line(&mut db, "CREATE INDEX name_idx ON users(name) ASYNC", line).unwrap();
+        let id = started_job(out);
+        assert_eq!(wait_for_job(&db, id), JobState::Ready);
+        execute_line(&mut db, &format!("CANCEL JOB {}", id), line).unwrap();
+        cancelled.push(id);
+    }
+    let listed: Vec<u64> = db.jobs().iter().map(|job| job.id).collect();
+    assert_eq!(listed, cancelled[1..]);
+    let err = execute_line(&mut db, &format!("CANCEL JOB {}", cancelled[0]), 4).unwrap_err();
+    assert!(err.to_string().contains("not found"));
+
+    // Jobs that are not finished are kept whatever their number
+    let out = execute_line(&mut db, "CREATE INDEX name_idx ON users(name) ASYNC", 5).unwrap();
+    let id = started_job(out);
+    assert_eq!(wait_for_job(&db, id), JobState::Ready);
+    assert_eq!(db.jobs().len(), 3);
+    execute_line(&mut db, "SHOW JOBS", 6).unwrap();
+    let jobs = db.jobs();
+    let listed: Vec<u64> = jobs.iter().map(|job| job.id).collect();
+    assert_eq!(listed, vec![cancelled[2], id]);
+    assert_eq!(jobs[1].state, JobState::Done);
+}
+
+#[test]
+fn test_async_materialize() {
+    let mut db = TensorDb::new();
+    execute_script(
+        &mut db,
+        r#"
+        DATASET test COLUMNS a: INT, b: INT
+        INSERT INTO test VALUES (1, 2)
+        INSERT INTO test VALUES (3, 4)
+        DATASET test ADD COLUMN c = a + b LAZY
+        "#,
+    )
+    .unwrap();
+
+    let id = started_job(execute_line(&mut db, "MATERIALIZE test ASYNC", 1).unwrap());
+    assert_eq!(wait_for_job(&db, id), JobState::Ready);
+    execute_line(&mut db, "SHOW JOBS", 2).unwrap();
+
+    let test = db.get_dataset("test").unwrap();
+    assert!(test.schema.fields.iter().all(|f| !f.is_lazy));
+    assert!(test.lazy_expressions.is_empty());
+    assert_eq!(db.jobs()[0].state, JobState::Done);
+
+    // Nothing left to materialize, so no job
+    let out = execute_line(&mut db, "MATERIALIZE test ASYNC", 3).unwrap();
+    assert!(out.to_string().contains("Materialized lazy columns"));
+}
+
+#[test]
+fn test_async_index_reaches_wal_when_installed() {
+    let temp_dir = "/tmp/linal_test_jobs_wal";
+    let _ = fs::remove_dir_all(temp_dir);
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            wal: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    {
+        let mut db = TensorDb::with_config(config.clone());
+        setup_users(&mut db);
+        let out = execute_line(&mut db, "CREATE INDEX name_idx ON users(name) ASYNC", 1).unwrap();
+        let id = started_job(out);
+        wait_for_job(&db, id);
+        execute_line(&mut db, "SHOW JOBS", 2).unwrap();
+    }
+
+    // Replay builds the index in place
+    let db = TensorDb::with_config(config);
+    assert_eq!(db.list_indices().len(), 1);
+    assert!(db.jobs().is_empty());
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
@@ -0,0 +1,180 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String)
+    INSERT INTO users VALUES (1, "Alice"), (2, "Bob"), (3, "Carol")
+    DATASET orders COLUMNS (id: Int, user_id: Int, total: Float)
+    INSERT INTO orders VALUES (10, 1, 5.0), (11, 2, 7.5), (12, 1, 2.5), (13, 9, 1.0)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_inner_join() {
+    let mut db = setup();
+
+    let out = rows(
+        &mut db,
+        "SELECT a.name, b.total FROM users a JOIN orders b ON a.id = b.user_id",
+    );
+    assert_eq!(
+        out,
+        vec![
+            vec![Value::String("Alice".into()), Value::Float(5.0)],
+            vec![Value::String("Alice".into()), Value::Float(2.5)],
+            vec![Value::String("Bob".into()), Value::Float(7.5)],
+        ]
+    );
+
+    // Unqualified names resolve when only one source has them; the
+    // condition may be written either way round
+    let out = rows(
+        &mut db,
+        "SELECT name, total FROM users AS a INNER JOIN orders AS b ON b.user_id = a.id WHERE total > 3.0 ORDER BY total DESC",
+    );
+    assert_eq!(
+        out,
+        vec![
+            vec![Value::String("Bob".into()), Value::Float(7.5)],
+            vec![Value::String("Alice".into()), Value::Float(5.0)],
+        ]
+    );
+}
+
+#[test]
+fn test_left_join_and_aggregates() {
+    let mut db = setup();
+
+    let out = rows(
+        &mut db,
+        "SELECT a.name, b.id FROM users a LEFT JOIN orders b ON a.id = b.user_id WHERE a.id > 1",
+    );
+    assert_eq!(
+        out,
+        vec![
+            vec![Value::String("Bob".into()), Value::Int(11)],
+            vec![Value::String("Carol".into()), Value::Null],
+        ]
+    );
+
+    let out = rows(
+        &mut db,
+        "SELECT a.name, SUM(b.total) FROM users a JOIN orders b ON a.id = b.user_id GROUP BY a.name ORDER BY a.name",
+    );
+    assert_eq!(
+        out,
+        vec![
+            vec![Value::String("Alice".into()), Value::Float(7.5)],
+            vec![Value::String("Bob".into()), Value::Float(7.5)],
+        ]
+    );
+}
+
+#[test]
+fn test_star_self_join_and_chained_join() {
+    let mut db = setup();
+
+    match execute_line(
+        &mut db,
+        "SELECT * FROM users JOIN orders ON users.id = orders.user_id LIMIT 1",
+        1,
+    )
+    .unwrap()
+    {
+        DslOutput::Table(ds) => {
+            let names: Vec<&str> = ds.schema.fields.iter().map(|f| f.name.as_str()).collect();
+            assert_eq!(
+                names,
+                vec![
+                    "users.id",
+                    "users.name",
+                    "orders.id",
+                    "orders.user_id",
+                    "orders.total"
+                ]
+            );
+        }
+        other => panic!("Expected table output, got {:?}", other),
+    }
+
+    let out = rows(
+        &mut db,
+        "SELECT x.id, y.id FROM orders x JOIN orders y ON x.user_id = y.user_id WHERE x.id = 10",
+    );
+    assert_eq!(
+        out,
+        vec![
+            vec![Value::Int(10), Value::Int(10)],
+            vec![Value::Int(10), Value::Int(12)],
+        ]
+    );
+
+    execute_script(
+        &mut db,
+        r#"
+        DATASET refunds COLUMNS (order_id: Int, amount: Float)
+        INSERT INTO refunds VALUES (12, 2.5)
+        "#,
+    )
+    .unwrap();
+    let out = rows(
+        &mut db,
+        "SELECT u.name, r.amount FROM users u JOIN orders o ON u.id = o.user_id JOIN refunds r ON r.order_id = o.id",
+    );
+    assert_eq!(
+        out,
+        vec![vec![Value::String("Alice".into()), Value::Float(2.5)]]
+    );
+}
+
+#[test]
+fn test_join_errors() {
+    let mut db = setup();
+
+    // `id` exists on both sides
+    let err = execute_line(
+        &mut db,
+        "SELECT id FROM users a JOIN orders b ON a.id = b.user_id",
+        1,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Ambiguous column 'id'"), "{}", err);
+
+    assert!(execute_line(&mut db, "SELECT * FROM users a JOIN orders b", 2).is_err());
+    assert!(execute_line(
+        &mut db,
+        "SELECT * FROM users a JOIN orders b ON a.id > b.user_id",
+        3
+    )
+    .is_err());
+    assert!(execute_line(
+        &mut db,
+        "SELECT * FROM users a JOIN orders b ON a.id = a.name",
+        4
+    )
+    .is_err());
+    assert!(execute_line(
+        &mut db,
+        "SELECT * FROM users a JOIN missing b ON a.id = b.id",
+        5
+    )
+    .is_err());
+    assert!(execute_line(
+        &mut db,
+        "SELECT * FROM users a JOIN orders a ON a.id = a.user_id",
+        6
+    )
+    .is_err());
+}
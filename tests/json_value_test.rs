@@ -0,0 +1,139 @@
+use linal::core::value::{Value, ValueType};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET docs COLUMNS (id: Int, meta: Json)
+    INSERT INTO docs VALUES (1, "{\"author\": {\"name\": \"Ana\", \"age\": 41}, \"tags\": [\"ml\", \"db\"], \"year\": 2021}")
+    INSERT INTO docs VALUES (2, "{\"author\": {\"name\": \"Ben\"}, \"tags\": [], \"year\": 1999}")
+    INSERT INTO docs VALUES (3, "{\"title\": \"untitled\"}")
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn query(db: &mut TensorDb, query: &str) -> (Vec<String>, Vec<Vec<Value>>) {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => (
+            result
+                .schema
+                .fields
+                .iter()
+                .map(|f| f.name.clone())
+                .collect(),
+            result.rows.iter().map(|r| r.values.clone()).collect(),
+        ),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn s(text: &str) -> Value {
+    Value::String(text.to_string())
+}
+
+#[test]
+fn test_json_column_stores_documents() {
+    let mut db = setup();
+    let ds = db.get_dataset("docs").unwrap();
+    assert_eq!(ds.schema.fields[1].value_type, ValueType::Json);
+    assert_eq!(
+        ds.rows[2].values[1],
+        Value::Json(serde_json::json!({ "title": "untitled" }))
+    );
+
+    // Text that is not JSON does not fit the column
+    assert!(execute_line(&mut db, "INSERT INTO docs VALUES (4, \"{oops\")", 1).is_err());
+
+    // Documents survive a Parquet round trip
+    let dir = "/tmp/linal_test_json_value";
+    let _ = std::fs::remove_dir_all(dir);
+    execute_line(&mut db, &format!("SAVE DATASET docs TO \"{}\"", dir), 1).unwrap();
+    let mut restored = TensorDb::new();
+    execute_line(
+        &mut restored,
+        &format!("LOAD DATASET docs FROM \"{}\"", dir),
+        1,
+    )
+    .unwrap();
+    let (_, rows) = query(&mut restored, "SELECT meta FROM docs ORDER BY id");
+    let (_, expected) = query(&mut db, "SELECT meta FROM docs ORDER BY id");
+    assert_eq!(rows, expected);
+}
+
+#[test]
+fn test_path_access() {
+    let mut db = setup();
+    let (names, rows) = query(
+        &mut db,
+        "SELECT meta->'author'->>'name', meta->'tags'->0, meta->>'year' FROM docs ORDER BY id",
+    );
+    assert_eq!(
+        names,
+        vec![
+            "meta->'author'->>'name'",
+            "meta->'tags'->0",
+            "meta->>'year'"
+        ]
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![s("Ana"), Value::Json(serde_json::json!("ml")), s("2021")],
+            vec![s("Ben"), Value::Null, s("1999")],
+            vec![Value::Null, Value::Null, Value::Null],
+        ]
+    );
+
+    let (_, rows) = query(
+        &mut db,
+        "SELECT JSON_GET_TEXT(JSON_GET(meta, \"author\"), \"age\") AS age FROM docs WHERE id = 1",
+    );
+    assert_eq!(rows, vec![vec![s("41")]]);
+
+    assert!(execute_line(&mut db, "SELECT meta-> FROM docs", 1).is_err());
+}
+
+#[test]
+fn test_filters_and_casts_on_json_fields() {
+    let mut db = setup();
+    // JSON scalars compare as the value they hold
+    let (_, rows) = query(&mut db, "SELECT id FROM docs WHERE meta->'year' > 2000");
+    assert_eq!(rows, vec![vec![Value::Int(1)]]);
+    let (_, rows) = query(
+        &mut db,
+        "SELECT id FROM docs WHERE meta->'author'->>'name' = \"Ben\"",
+    );
+    assert_eq!(rows, vec![vec![Value::Int(2)]]);
+
+    let (_, rows) = query(
+        &mut db,
+        "SELECT CAST(meta->'year' AS INT) + 1, CAST(meta->'tags' AS STRING) FROM docs WHERE id = 1",
+    );
+    assert_eq!(rows, vec![vec![Value::Int(2022), s("[\"ml\",\"db\"]")]]);
+}
+
+#[test]
+fn test_sql_json() {
+    let mut db = TensorDb::new();
+    execute_script(
+        &mut db,
+        r#"
+        SQL CREATE TABLE events (id INT NOT NULL, payload JSON)
+        SQL INSERT INTO events VALUES (1, '{"kind": "click", "x": 3}'), (2, '{"kind": "view"}'), (3, NULL)
+        "#,
+    )
+    .unwrap();
+    // The SQL parser binds -> looser than comparisons
+    let (_, rows) = query(
+        &mut db,
+        "SQL SELECT id, payload->>'kind' AS kind FROM events WHERE (payload->'x') = 3",
+    );
+    assert_eq!(rows, vec![vec![Value::Int(1), s("click")]]);
+    let (_, rows) = query(
+        &mut db,
+        "SQL SELECT COUNT(*) FROM events WHERE (payload->>'kind') IS NOT NULL",
+    );
+    assert_eq!(rows, vec![vec![Value::Int(2)]]);
+}
@@ -0,0 +1,123 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use linal::query::lineage::ColumnLineage;
+use std::fs;
+use std::path::PathBuf;
+
+fn setup_db(temp_dir: &str) -> TensorDb {
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            default_db: "default".to_string(),
+            auto_persist: false,
+            wal: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+
+    let script = r#"
+    DATASET orders COLUMNS (id: Int, customer: Int, price: Float, qty: Int)
+    INSERT INTO orders VALUES (1, 10, 2.5, 4)
+    INSERT INTO orders VALUES (2, 11, 8.0, 1)
+    DATASET customers COLUMNS (id: Int, name: String)
+    INSERT INTO customers VALUES (10, "Ada")
+    INSERT INTO customers VALUES (11, "Bob")
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn lineage(output: DslOutput) -> Vec<ColumnLineage> {
+    match output {
+        DslOutput::Table(ds) => ds.metadata.lineage,
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn sources(lineage: &ColumnLineage) -> Vec<String> {
+    lineage.sources.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_lineage_of_projections_and_aggregates() {
+    let temp_dir = "/tmp/linal_test_lineage_select";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+
+    let out = execute_line(
+        &mut db,
+        "SELECT id AS order_id, price * qty AS total, 1 AS one FROM orders",
+        1,
+    )
+    .unwrap();
+    let columns = lineage(out);
+    assert_eq!(columns.len(), 3);
+    assert_eq!(columns[0].column, "order_id");
+    assert_eq!(sources(&columns[0]), vec!["orders.id"]);
+    assert!(columns[0].transforms.is_empty());
+    assert_eq!(sources(&columns[1]), vec!["orders.price", "orders.qty"]);
+    assert_eq!(columns[1].transforms.len(), 1);
+    assert!(columns[2].sources.is_empty());
+
+    let out = execute_line(
+        &mut db,
+        "SELECT customer, SUM(price) FROM orders GROUP BY customer",
+        2,
+    )
+    .unwrap();
+    let columns = lineage(out);
+    assert_eq!(sources(&columns[0]), vec!["orders.customer"]);
+    assert!(columns[0].transforms.is_empty());
+    assert_eq!(sources(&columns[1]), vec!["orders.price"]);
+    assert!(columns[1].transforms[0].contains("SUM"));
+
+    let out = execute_line(
+        &mut db,
+        "SELECT o.id, c.name FROM orders o JOIN customers c ON o.customer = c.id",
+        3,
+    )
+    .unwrap();
+    let columns = lineage(out);
+    assert_eq!(sources(&columns[0]), vec!["orders.id"]);
+    assert_eq!(sources(&columns[1]), vec!["customers.name"]);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_lineage_follows_derived_datasets() {
+    let temp_dir = "/tmp/linal_test_lineage_derived";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+
+    execute_script(
+        &mut db,
+        r#"
+        DATASET totals FROM orders SELECT id, price * qty AS total
+        DATASET big FROM totals FILTER total > 9 SELECT id, total
+        "#,
+    )
+    .unwrap();
+
+    // `big` is two datasets away from the columns its values come from
+    let big = db.get_dataset("big").unwrap();
+    let total = big
+        .metadata
+        .lineage
+        .iter()
+        .find(|l| l.column == "total")
+        .unwrap();
+    assert_eq!(sources(total), vec!["orders.price", "orders.qty"]);
+
+    let out = execute_line(&mut db, "EXPLAIN LINEAGE SELECT total AS t FROM big", 1)
+        .unwrap()
+        .to_string();
+    assert!(out.contains("--- LINEAGE ---"));
+    let row = out.lines().find(|line| line.starts_with("t ")).unwrap();
+    assert!(row.contains("orders.price, orders.qty"));
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
@@ -0,0 +1,171 @@
+use linal::core::value::{Value, ValueType};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET posts COLUMNS (id: Int, tags: List(String), scores: List(Int))
+    INSERT INTO posts VALUES (1, ["ml", "db"], [3, 5, 8])
+    INSERT INTO posts VALUES (2, ["db"], [1])
+    INSERT INTO posts VALUES (3, [], [])
+    INSERT INTO posts VALUES (4, ["ml", "web", "db"], [2, 2])
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn query(db: &mut TensorDb, query: &str) -> (Vec<String>, Vec<Vec<Value>>) {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => (
+            result
+                .schema
+                .fields
+                .iter()
+                .map(|f| f.name.clone())
+                .collect(),
+            result.rows.iter().map(|r| r.values.clone()).collect(),
+        ),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn s(text: &str) -> Value {
+    Value::String(text.to_string())
+}
+
+#[test]
+fn test_list_columns_store_variable_length_lists() {
+    let mut db = setup();
+    let ds = db.get_dataset("posts").unwrap();
+    assert_eq!(
+        ds.schema.fields[1].value_type,
+        ValueType::List(Box::new(ValueType::String))
+    );
+    assert_eq!(ds.rows[0].values[1], Value::List(vec![s("ml"), s("db")]));
+    assert_eq!(ds.rows[2].values[2], Value::List(vec![]));
+
+    // Elements must have the column's element type
+    assert!(execute_line(&mut db, "INSERT INTO posts VALUES (5, [\"a\"], [\"b\"])", 1).is_err());
+
+    // Lists survive a Parquet round trip
+    let dir = "/tmp/linal_test_list_value";
+    let _ = std::fs::remove_dir_all(dir);
+    execute_line(&mut db, &format!("SAVE DATASET posts TO \"{}\"", dir), 1).unwrap();
+    let mut restored = TensorDb::new();
+    execute_line(
+        &mut restored,
+        &format!("LOAD DATASET posts FROM \"{}\"", dir),
+        1,
+    )
+    .unwrap();
+    let (_, rows) = query(&mut restored, "SELECT tags, scores FROM posts ORDER BY id");
+    assert_eq!(
+        rows[3],
+        vec![
+            Value::List(vec![s("ml"), s("web"), s("db")]),
+            Value::List(vec![Value::Int(2), Value::Int(2)]),
+        ]
+    );
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_element_access_and_array_contains() {
+    let mut db = setup();
+    let (names, rows) = query(
+        &mut db,
+        "SELECT id, tags[1], ELEMENT_AT(scores, 3) AS third FROM posts ORDER BY id",
+    );
+    assert_eq!(names, vec!["id", "tags[1]", "third"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), s("ml"), Value::Int(8)],
+            vec![Value::Int(2), s("db"), Value::Null],
+            vec![Value::Int(3), Value::Null, Value::Null],
+            vec![Value::Int(4), s("ml"), Value::Null],
+        ]
+    );
+
+    let (_, rows) = query(
+        &mut db,
+        "SELECT id FROM posts WHERE ARRAY_CONTAINS(tags, \"db\") AND ARRAY_CONTAINS(scores, 1) = false ORDER BY id",
+    );
+    assert_eq!(rows, vec![vec![Value::Int(1)], vec![Value::Int(4)]]);
+}
+
+#[test]
+fn test_unnest_expands_rows() {
+    let mut db = setup();
+    let (names, rows) = query(
+        &mut db,
+        "SELECT id, UNNEST(tags) AS tag FROM posts WHERE id < 3 ORDER BY id",
+    );
+    assert_eq!(names, vec!["id", "tag"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(1), s("ml")],
+            vec![Value::Int(1), s("db")],
+            vec![Value::Int(2), s("db")],
+        ]
+    );
+
+    // Empty lists produce no rows; the elements can be grouped
+    let (_, rows) = query(
+        &mut db,
+        "SELECT UNNEST(tags) AS tag, COUNT(*) AS n FROM posts GROUP BY tag ORDER BY tag",
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![s("db"), Value::Int(3)],
+            vec![s("ml"), Value::Int(2)],
+            vec![s("web"), Value::Int(1)],
+        ]
+    );
+
+    assert!(execute_line(
+        &mut db,
+        "SELECT id FROM posts WHERE UNNEST(tags) = \"db\"",
+        1
+    )
+    .is_err());
+    assert!(execute_line(&mut db, "SELECT UNNEST(tags), UNNEST(scores) FROM posts", 1).is_err());
+}
+
+#[test]
+fn test_sql_arrays() {
+    let mut db = TensorDb::new();
+    execute_line(
+        &mut db,
+        "SQL CREATE TABLE events (id INT, labels TEXT[], hits INT[])",
+        1,
+    )
+    .unwrap();
+    execute_line(
+        &mut db,
+        "SQL INSERT INTO events VALUES (1, ARRAY['a', 'b'], ARRAY[4, 7]), (2, ARRAY['c'], ARRAY[9])",
+        1,
+    )
+    .unwrap();
+    let (_, rows) = query(
+        &mut db,
+        "SQL SELECT id, labels[2], hits[1] FROM events WHERE ARRAY_CONTAINS(labels, 'a')",
+    );
+    assert_eq!(rows, vec![vec![Value::Int(1), s("b"), Value::Int(4)]]);
+
+    let (_, rows) = query(
+        &mut db,
+        "SQL SELECT UNNEST(hits) AS h FROM events ORDER BY h",
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(4)],
+            vec![Value::Int(7)],
+            vec![Value::Int(9)]
+        ]
+    );
+}
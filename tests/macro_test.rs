@@ -0,0 +1,153 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use std::fs;
+use std::path::PathBuf;
+
+fn setup_db(temp_dir: &str) -> TensorDb {
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            default_db: "default".to_string(),
+            auto_persist: false,
+            wal: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+
+    let script = r#"
+    DATASET sales COLUMNS (id: Int, region: String, amount: Float)
+    INSERT INTO sales VALUES (1, "EU", 250.0)
+    INSERT INTO sales VALUES (2, "US", 80.0)
+    INSERT INTO sales VALUES (3, "EU", 120.0)
+    INSERT INTO sales VALUES (4, "US", 400.0)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn ids(output: DslOutput) -> Vec<Value> {
+    match output {
+        DslOutput::Table(ds) => ds.rows.iter().map(|r| r.values[0].clone()).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_macro_call_expands_to_its_body() {
+    let temp_dir = "/tmp/linal_test_macro_expand";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+
+    let out = execute_line(
+        &mut db,
+        "DEFINE MACRO topk(ds, col, k) AS (SELECT * FROM $ds ORDER BY $col DESC LIMIT $k)",
+        1,
+    )
+    .unwrap();
+    assert!(out.to_string().contains("topk(ds, col, k)"));
+
+    let out = execute_line(&mut db, "topk(sales, amount, 2)", 2).unwrap();
+    assert_eq!(ids(out), vec![Value::Int(4), Value::Int(1)]);
+
+    // Arguments may be expressions; string literals in the body are left alone
+    execute_line(
+        &mut db,
+        r#"define macro in_region(r, min) as (SELECT id FROM sales WHERE region = $r AND amount > $min ORDER BY id)"#,
+        3,
+    )
+    .unwrap();
+    let out = execute_line(&mut db, r#"in_region("EU", 100 + 50)"#, 4).unwrap();
+    assert_eq!(ids(out), vec![Value::Int(1)]);
+
+    // A macro without parameters is a command alias, called with or without ()
+    execute_line(&mut db, "DEFINE MACRO eu AS (in_region(\"EU\", 0))", 5).unwrap();
+    let out = execute_line(&mut db, "eu", 6).unwrap();
+    assert_eq!(ids(out), vec![Value::Int(1), Value::Int(3)]);
+    let out = execute_line(&mut db, "eu()", 7).unwrap();
+    assert_eq!(ids(out), vec![Value::Int(1), Value::Int(3)]);
+
+    let out = execute_line(&mut db, "SHOW MACROS", 8).unwrap().to_string();
+    assert!(out.contains("eu() AS (in_region(\"EU\", 0))"));
+    assert!(out.contains("topk(ds, col, k) AS (SELECT * FROM $ds ORDER BY $col DESC LIMIT $k)"));
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_macro_expanding_to_a_mutation_changes_the_dataset() {
+    let temp_dir = "/tmp/linal_test_macro_mutation";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+
+    execute_script(
+        &mut db,
+        r#"
+        DEFINE MACRO record(id, region, amount) AS (INSERT INTO sales VALUES ($id, $region, $amount))
+        record(5, "EU", 10.0)
+        "#,
+    )
+    .unwrap();
+    assert_eq!(db.get_dataset("sales").unwrap().len(), 5);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_macros_survive_restart() {
+    let temp_dir = "/tmp/linal_test_macro_persist";
+    let _ = fs::remove_dir_all(temp_dir);
+
+    {
+        let mut db = setup_db(temp_dir);
+        execute_line(
+            &mut db,
+            "DEFINE MACRO big(min) AS (SELECT id FROM sales WHERE amount > $min)",
+            1,
+        )
+        .unwrap();
+    }
+
+    let mut db = setup_db(temp_dir);
+    let out = execute_line(&mut db, "big(300)", 1).unwrap();
+    assert_eq!(ids(out), vec![Value::Int(4)]);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_macro_errors() {
+    let temp_dir = "/tmp/linal_test_macro_errors";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+
+    execute_line(
+        &mut db,
+        "DEFINE MACRO topk(ds, col, k) AS (SELECT * FROM $ds ORDER BY $col DESC LIMIT $k)",
+        1,
+    )
+    .unwrap();
+
+    // Wrong argument count, trailing input
+    let err = execute_line(&mut db, "topk(sales, amount)", 2).unwrap_err();
+    assert!(err.to_string().contains("expects 3 argument(s), got 2"));
+    assert!(execute_line(&mut db, "topk(sales, amount, 1) LIMIT 2", 3).is_err());
+
+    // Command keywords, unknown or duplicate parameters, missing parentheses
+    let err = execute_line(&mut db, "DEFINE MACRO show AS (LIST DATASETS)", 4).unwrap_err();
+    assert!(err.to_string().contains("command keyword"));
+    let err = execute_line(&mut db, "DEFINE MACRO f(a) AS (SELECT * FROM $b)", 5).unwrap_err();
+    assert!(err.to_string().contains("no parameter 'b'"));
+    assert!(execute_line(&mut db, "DEFINE MACRO f(a, a) AS (SELECT * FROM $a)", 6).is_err());
+    assert!(execute_line(&mut db, "DEFINE MACRO f(a) AS SELECT * FROM $a", 7).is_err());
+
+    // Macros calling themselves stop expanding
+    execute_line(&mut db, "DEFINE MACRO again AS (again())", 8).unwrap();
+    let err = execute_line(&mut db, "again", 9).unwrap_err();
+    assert!(err.to_string().contains("recursive macro"));
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
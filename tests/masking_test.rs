@@ -0,0 +1,212 @@
+use linal::core::config::{EngineConfig, SecurityConfig, StorageConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+fn setup_db(temp_dir: &str) -> TensorDb {
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            default_db: "default".to_string(),
+            auto_persist: false,
+            wal: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+
+    let script = r#"
+    DATASET users COLUMNS (id: Int, email: String, ip: String)
+    INSERT INTO users VALUES (1, "alice@example.com", "192.168.1.77")
+    INSERT INTO users VALUES (2, "bob@corp.io", "2001:db8:85a3::8a2e:370:7334")
+    INSERT INTO users VALUES (3, "nobody", "not an ip")
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn column(db: &mut TensorDb, query: &str, index: usize) -> Vec<Value> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.iter().map(|r| r.values[index].clone()).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<Value> {
+    values
+        .iter()
+        .map(|s| Value::String(s.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_masking_functions() {
+    let temp_dir = "/tmp/linal_test_masking_functions";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+
+    assert_eq!(
+        column(&mut db, "SELECT MASK_EMAIL(email) FROM users", 0),
+        strings(&["a***@example.com", "b***@corp.io", "***"])
+    );
+
+    let truncated = column(&mut db, "SELECT TRUNCATE_IP(ip) FROM users", 0);
+    assert_eq!(
+        truncated[..2],
+        strings(&["192.168.1.0", "2001:db8:85a3::"])[..]
+    );
+    assert_eq!(truncated[2], Value::Null);
+
+    let hashes = column(&mut db, r#"SELECT HASH(email, "pepper") FROM users"#, 0);
+    assert_eq!(
+        hashes[0],
+        Value::String("8b8d9adc4875c0dca816e3e17b7ac87b45e40945b731fa02e3b42bf101589e21".into())
+    );
+    let unsalted = column(&mut db, "SELECT HASH(email) FROM users", 0);
+    assert_ne!(hashes[0], unsalted[0]);
+    assert!(
+        matches!(&column(&mut db, "SELECT HASH(id) FROM users", 0)[0], Value::String(h) if h.len() == 64)
+    );
+
+    assert!(execute_line(&mut db, "SELECT HASH(email, 1, 2) FROM users", 1).is_err());
+    assert!(execute_line(&mut db, "SELECT MASK_EMAIL() FROM users", 1).is_err());
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_masking_policy_depends_on_role() {
+    let temp_dir = "/tmp/linal_test_masking_policy";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+
+    execute_script(
+        &mut db,
+        r#"
+        DATASET users MASK COLUMN email USING MASK_EMAIL(email) EXCEPT ROLES admin, auditor
+        DATASET users MASK COLUMN id USING HASH(id, "s")
+        "#,
+    )
+    .unwrap();
+    let raw = strings(&["alice@example.com", "bob@corp.io", "nobody"]);
+    let masked = strings(&["a***@example.com", "b***@corp.io", "***"]);
+
+    // Local sessions have no role and see raw values
+    assert_eq!(column(&mut db, "SELECT email FROM users", 0), raw);
+
+    db.set_role(Some("analyst".to_string()));
+    assert_eq!(column(&mut db, "SELECT email FROM users", 0), masked);
+    assert_eq!(column(&mut db, "SELECT * FROM users", 1), masked);
+    assert!(
+        matches!(&column(&mut db, "SELECT id FROM users", 0)[0], Value::String(h) if h.len() == 64)
+    );
+    // Conditions see masked values too, so raw values cannot be probed
+    assert!(column(
+        &mut db,
+        r#"SELECT id FROM users WHERE email = "alice@example.com""#,
+        0
+    )
+    .is_empty());
+    // Unmasked columns are untouched
+    assert_eq!(
+        column(&mut db, "SELECT ip FROM users", 0)[0],
+        Value::String("192.168.1.77".into())
+    );
+    // SHOW and derived datasets only get masked values
+    assert_eq!(column(&mut db, "SHOW users", 1), masked);
+    execute_line(&mut db, "DATASET copy FROM users SELECT email", 1).unwrap();
+    assert_eq!(column(&mut db, "SELECT email FROM copy", 0), masked);
+
+    db.set_role(Some("auditor".to_string()));
+    assert_eq!(column(&mut db, "SELECT email FROM users", 0), raw);
+    assert!(matches!(
+        &column(&mut db, "SELECT id FROM users", 0)[0],
+        Value::String(_)
+    ));
+
+    // Roles a policy applies to can neither remove nor replace it
+    db.set_role(Some("analyst".to_string()));
+    let err = execute_line(&mut db, "DATASET users UNMASK COLUMN email", 1).unwrap_err();
+    assert!(
+        err.to_string().contains("may not change the masking"),
+        "{}",
+        err
+    );
+    assert!(execute_line(
+        &mut db,
+        "DATASET users MASK COLUMN email USING email EXCEPT ROLES analyst",
+        2
+    )
+    .is_err());
+    assert!(execute_line(
+        &mut db,
+        "DATASET users MASK COLUMN ip USING TRUNCATE_IP(ip)",
+        3
+    )
+    .is_err());
+    assert_eq!(column(&mut db, "SELECT email FROM users", 0), masked);
+
+    // Exempt roles may change the policy exempting them, admin roles any
+    db.set_role(Some("auditor".to_string()));
+    assert!(execute_line(&mut db, "DATASET users UNMASK COLUMN id", 4).is_err());
+    execute_line(&mut db, "DATASET users UNMASK COLUMN email", 5).unwrap();
+    db.set_role(Some("admin".to_string()));
+    execute_line(&mut db, "DATASET users UNMASK COLUMN id", 6).unwrap();
+    db.set_role(Some("analyst".to_string()));
+    assert_eq!(column(&mut db, "SELECT email FROM users", 0), raw);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_masking_policy_errors() {
+    let temp_dir = "/tmp/linal_test_masking_errors";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+
+    let err = execute_line(
+        &mut db,
+        "DATASET users MASK COLUMN phone USING HASH(phone)",
+        1,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Column 'phone' not found"));
+    let err = execute_line(
+        &mut db,
+        "DATASET users MASK COLUMN email USING MASK_MAIL(email)",
+        2,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("unknown column"));
+    let err = execute_line(&mut db, "DATASET users UNMASK COLUMN email", 3).unwrap_err();
+    assert!(err.to_string().contains("is not masked"));
+    assert!(execute_line(&mut db, "DATASET users MASK COLUMN email", 4).is_err());
+
+    // A masked column cannot be dropped or renamed while its policy exists
+    execute_line(
+        &mut db,
+        "DATASET users MASK COLUMN email USING MASK_EMAIL(email)",
+        5,
+    )
+    .unwrap();
+    let err = execute_line(&mut db, "DATASET users DROP COLUMN email", 6).unwrap_err();
+    assert!(err.to_string().contains("masking policy"));
+    assert!(execute_line(&mut db, "DATASET users RENAME COLUMN email TO mail", 7).is_err());
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_api_keys_map_to_roles() {
+    let security = SecurityConfig {
+        api_key_roles: HashMap::from([("k-admin".to_string(), "admin".to_string())]),
+        ..Default::default()
+    };
+    assert_eq!(security.role_of(Some("k-admin")), "admin");
+    assert_eq!(security.role_of(Some("other")), "public");
+    assert_eq!(security.role_of(None), "public");
+}
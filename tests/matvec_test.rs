@@ -0,0 +1,52 @@
+use linal::dsl::{execute_line, execute_script};
+use linal::engine::TensorDb;
+
+fn run(script: &str) -> TensorDb {
+    let mut db = TensorDb::new();
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn tensor(db: &TensorDb, name: &str) -> (Vec<usize>, Vec<f32>) {
+    let t = db.get(name).unwrap();
+    (t.shape.dims.clone(), t.data.to_vec())
+}
+
+#[test]
+fn test_matrix_vector_product_scores_each_row() {
+    let db = run("MATRIX e = [[1, 0, 0], [0, 1, 0], [1, 1, 1]]\n\
+         VECTOR q = [1, 2, 3]\n\
+         LET s = e @ q\n\
+         LET m = MATMUL e q");
+    assert_eq!(tensor(&db, "s"), (vec![3], vec![1.0, 2.0, 6.0]));
+    assert_eq!(tensor(&db, "m"), tensor(&db, "s"));
+}
+
+#[test]
+fn test_infix_matmul_of_matrices() {
+    let db = run("MATRIX a = [[1, 2], [3, 4]]\n\
+         MATRIX b = [[0, 1], [1, 0]]\n\
+         LET c = a @ b");
+    assert_eq!(tensor(&db, "c"), (vec![2, 2], vec![2.0, 1.0, 4.0, 3.0]));
+}
+
+#[test]
+fn test_outer_product() {
+    let db = run("VECTOR u = [1, 2, 3]\n\
+         VECTOR v = [10, 20]\n\
+         LET o = OUTER u v");
+    assert_eq!(
+        tensor(&db, "o"),
+        (vec![3, 2], vec![10.0, 20.0, 20.0, 40.0, 30.0, 60.0])
+    );
+}
+
+#[test]
+fn test_shape_errors() {
+    let mut db = run("MATRIX e = [[1, 2], [3, 4]]\n\
+         VECTOR v = [1, 2, 3]");
+    let err = execute_line(&mut db, "LET s = e @ v", 1).unwrap_err();
+    assert!(err.to_string().contains("MATMUL"), "{}", err);
+    let err = execute_line(&mut db, "LET o = OUTER e v", 1).unwrap_err();
+    assert!(err.to_string().contains("OUTER"), "{}", err);
+}
@@ -0,0 +1,110 @@
+use linal::core::config::{EngineConfig, ExecutionConfig, NonFinitePolicy};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn db_with(policy: NonFinitePolicy) -> TensorDb {
+    let config = EngineConfig {
+        execution: ExecutionConfig {
+            non_finite: policy,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+    // x / y is Inf for row 2 and NaN for row 3
+    let script = r#"
+    DATASET t COLUMNS (g: String, x: Float, y: Float)
+    INSERT INTO t VALUES ("a", 1.0, 1.0), ("a", 1.0, 0.0), ("b", 0.0, 0.0), ("b", 4.0, 2.0)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows.iter().map(|r| r.values.clone()).collect(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+const QUERY: &str = "SELECT g, SUM(x / y), AVG(x / y), MAX(x / y) FROM t GROUP BY g ORDER BY g";
+
+#[test]
+fn test_propagate_keeps_nan_and_inf() {
+    let mut db = db_with(NonFinitePolicy::Propagate);
+    let result = rows(&mut db, QUERY);
+    assert_eq!(result[0][1], Value::Float(f32::INFINITY));
+    assert!(matches!(result[1][1], Value::Float(f) if f.is_nan()));
+}
+
+#[test]
+fn test_skip_ignores_non_finite_values() {
+    let mut db = db_with(NonFinitePolicy::Skip);
+    let result = rows(&mut db, QUERY);
+    assert_eq!(
+        result,
+        vec![
+            vec![
+                Value::String("a".into()),
+                Value::Float(1.0),
+                Value::Float(1.0),
+                Value::Float(1.0)
+            ],
+            vec![
+                Value::String("b".into()),
+                Value::Float(2.0),
+                Value::Float(2.0),
+                Value::Float(2.0)
+            ],
+        ]
+    );
+    // Global aggregates agree, whichever path runs them
+    let result = rows(&mut db, "SELECT SUM(x / y), STDDEV(x / y) FROM t");
+    assert_eq!(result[0][0], Value::Float(3.0));
+    assert!(matches!(result[0][1], Value::Float(f) if f.is_finite()));
+}
+
+#[test]
+fn test_error_rejects_aggregates_over_non_finite_values() {
+    let mut db = db_with(NonFinitePolicy::Error);
+    let err = execute_line(&mut db, QUERY, 1).unwrap_err();
+    assert!(err.to_string().contains("non-finite"), "{}", err);
+    // Finite inputs still aggregate
+    let result = rows(&mut db, "SELECT SUM(x) FROM t");
+    assert_eq!(result, vec![vec![Value::Float(6.0)]]);
+}
+
+#[test]
+fn test_error_rejects_tensor_results_and_keeps_the_old_binding() {
+    let mut db = db_with(NonFinitePolicy::Error);
+    execute_script(&mut db, "VECTOR v = [1e30, 1e30]\nLET r = v * 2").unwrap();
+    // The running product overflows to Inf
+    let err = execute_line(&mut db, "LET r = CUMPROD v", 1).unwrap_err();
+    assert!(err.to_string().contains("NaN or Inf"), "{}", err);
+    assert_eq!(db.get("r").unwrap().data.to_vec(), vec![2e30, 2e30]);
+
+    // The default policy lets the same operation through
+    let mut db = db_with(NonFinitePolicy::Propagate);
+    execute_script(&mut db, "VECTOR v = [1e30, 1e30]\nLET r = CUMPROD v").unwrap();
+    assert_eq!(db.get("r").unwrap().data[1], f32::INFINITY);
+}
+
+#[test]
+fn test_column_stats_follow_the_policy() {
+    let stats = |policy| {
+        let mut db = db_with(policy);
+        execute_line(&mut db, "DATASET t ADD COLUMN r = x / y", 1).unwrap();
+        let ds = db.get_dataset("t").unwrap();
+        ds.metadata.column_stats["r"].clone()
+    };
+
+    let skipped = stats(NonFinitePolicy::Skip);
+    assert_eq!(skipped.non_finite_count, 2);
+    assert_eq!(skipped.min, Some(Value::Float(1.0)));
+    assert_eq!(skipped.max, Some(Value::Float(2.0)));
+
+    let propagated = stats(NonFinitePolicy::Propagate);
+    assert_eq!(propagated.non_finite_count, 2);
+    assert!(matches!(propagated.max, Some(Value::Float(f)) if f.is_nan()));
+}
@@ -0,0 +1,210 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    SQL CREATE TABLE people (id INT NOT NULL, age INT, score FLOAT, city TEXT)
+    SQL INSERT INTO people VALUES (1, 30, 1.5, 'Oslo'), (2, NULL, 2.5, NULL), (3, 45, NULL, 'Rome'), (4, NULL, NULL, 'Oslo')
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows.iter().map(|r| r.values.clone()).collect(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn ids(db: &mut TensorDb, query: &str) -> Vec<i64> {
+    rows(db, query)
+        .iter()
+        .map(|r| r[0].as_int().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_comparisons_with_null_are_unknown() {
+    let mut db = setup();
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM people WHERE age > 20 ORDER BY id"),
+        vec![1, 3]
+    );
+    // NULL ages match neither a comparison nor its negation
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM people WHERE NOT age > 20"),
+        Vec::<i64>::new()
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM people WHERE age != 30"),
+        vec![3]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM people WHERE city = NULL"),
+        Vec::<i64>::new()
+    );
+    // NOT IN over a NULL value is unknown, so the row is dropped
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM people WHERE city NOT IN (\"Rome\") ORDER BY id"
+        ),
+        vec![1, 4]
+    );
+    // OR with one true side is true even when the other side is unknown
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM people WHERE age > 40 OR score > 2 ORDER BY id"
+        ),
+        vec![2, 3]
+    );
+}
+
+#[test]
+fn test_is_null_and_is_not_null() {
+    let mut db = setup();
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM people WHERE age IS NULL ORDER BY id"
+        ),
+        vec![2, 4]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM people WHERE score IS NOT NULL AND city IS NOT NULL"
+        ),
+        vec![1]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM people WHERE NOT city IS NULL ORDER BY id"
+        ),
+        vec![1, 3, 4]
+    );
+
+    let result = rows(
+        &mut db,
+        "SELECT age IS NULL AS missing FROM people ORDER BY id",
+    );
+    let missing: Vec<Value> = result.into_iter().map(|r| r[0].clone()).collect();
+    assert_eq!(
+        missing,
+        vec![
+            Value::Bool(false),
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::Bool(true)
+        ]
+    );
+
+    assert!(execute_line(&mut db, "SELECT id FROM people WHERE age IS 3", 1).is_err());
+}
+
+#[test]
+fn test_coalesce_and_ifnull() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT COALESCE(age, 0), IFNULL(city, \"unknown\"), COALESCE(score, age, -1) FROM people ORDER BY id",
+    );
+    assert_eq!(
+        result,
+        vec![
+            vec![
+                Value::Int(30),
+                Value::String("Oslo".into()),
+                Value::Float(1.5)
+            ],
+            vec![
+                Value::Int(0),
+                Value::String("unknown".into()),
+                Value::Float(2.5)
+            ],
+            vec![
+                Value::Int(45),
+                Value::String("Rome".into()),
+                Value::Float(45.0)
+            ],
+            vec![
+                Value::Int(0),
+                Value::String("Oslo".into()),
+                Value::Float(-1.0)
+            ],
+        ]
+    );
+
+    assert!(execute_line(&mut db, "SELECT IFNULL(age) FROM people", 1).is_err());
+    assert!(execute_line(&mut db, "SELECT COALESCE() FROM people", 1).is_err());
+}
+
+#[test]
+fn test_null_semantics_in_sql() {
+    let mut db = setup();
+    assert_eq!(
+        ids(
+            &mut db,
+            "SQL SELECT id FROM people WHERE age IS NULL AND city IS NOT NULL"
+        ),
+        vec![4]
+    );
+    assert_eq!(
+        ids(&mut db, "SQL SELECT id FROM people WHERE age NOT IN (30)"),
+        vec![3]
+    );
+    let result = rows(
+        &mut db,
+        "SQL SELECT id, COALESCE(city, 'n/a') FROM people WHERE IFNULL(age, 0) = 0",
+    );
+    assert_eq!(
+        result,
+        vec![
+            vec![Value::Int(2), Value::String("n/a".into())],
+            vec![Value::Int(4), Value::String("Oslo".into())],
+        ]
+    );
+}
+
+#[test]
+fn test_dsl_insert_null() {
+    let mut db = setup();
+    execute_line(
+        &mut db,
+        r#"INSERT INTO people VALUES (5, NULL, 0.5, "Oslo"), (6, 20, null, NULL)"#,
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT age, score, city FROM people WHERE id >= 5 ORDER BY id"
+        ),
+        vec![
+            vec![Value::Null, Value::Float(0.5), Value::String("Oslo".into())],
+            vec![Value::Int(20), Value::Null, Value::Null],
+        ]
+    );
+
+    // id is NOT NULL; the statement fails and no row is written
+    let err = execute_line(
+        &mut db,
+        r#"INSERT INTO people VALUES (7, 1, 1.0, "Rome"), (NULL, 2, 2.0, "Rome")"#,
+        2,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("NOT NULL constraint violated"),
+        "{}",
+        err
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM people"),
+        vec![1, 2, 3, 4, 5, 6]
+    );
+}
@@ -0,0 +1,132 @@
+use linal::core::config::{EngineConfig, StorageBackend, StorageConfig};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn local_backend_config(temp_dir: &str, auto_persist: bool) -> EngineConfig {
+    EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(format!("{}/data", temp_dir)),
+            default_db: "default".to_string(),
+            auto_persist,
+            backend: StorageBackend::Local,
+            bucket: Some(format!("{}/bucket", temp_dir)),
+            prefix: Some("warehouse".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_auto_persist_to_object_store_and_recover() {
+    let temp_dir = "/tmp/linal_test_object_store_recover";
+    let _ = fs::remove_dir_all(temp_dir);
+
+    {
+        let mut db = TensorDb::with_config(local_backend_config(temp_dir, true));
+        let script = r#"
+        DATASET users COLUMNS (id: Int, name: String)
+        INSERT INTO users VALUES (1, "Alice")
+        INSERT INTO users VALUES (2, "Bob")
+        CREATE DATABASE analytics
+        USE analytics
+        DATASET events COLUMNS (id: Int)
+        INSERT INTO events VALUES (7)
+        "#;
+        execute_script(&mut db, script).unwrap();
+    }
+
+    // Objects follow the ParquetStorage layout under bucket/prefix/db
+    let root = format!("{}/bucket/warehouse", temp_dir);
+    assert!(Path::new(&format!("{}/default/datasets/users.parquet", root)).exists());
+    assert!(Path::new(&format!("{}/default/datasets/users.meta.json", root)).exists());
+    assert!(Path::new(&format!("{}/analytics/datasets/events.parquet", root)).exists());
+
+    // Databases are discovered from the bucket, not data_dir
+    let mut db = TensorDb::with_config(local_backend_config(temp_dir, true));
+    assert_eq!(db.get_dataset("users").unwrap().len(), 2);
+    execute_line(&mut db, "USE analytics", 1).unwrap();
+    assert_eq!(db.get_dataset("events").unwrap().len(), 1);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_save_load_and_list_use_configured_backend() {
+    let temp_dir = "/tmp/linal_test_object_store_save_load";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = TensorDb::with_config(local_backend_config(temp_dir, false));
+
+    let script = r#"
+    DATASET items COLUMNS (id: Int, score: Float)
+    INSERT INTO items VALUES (1, 0.5)
+    CREATE INDEX id_idx ON items(id)
+    MATRIX m = [[1.0, 2.0], [3.0, 4.0]]
+    SAVE DATASET items
+    SAVE TENSOR m
+    "#;
+    execute_script(&mut db, script).unwrap();
+
+    match execute_line(&mut db, "LIST DATASETS", 1).unwrap() {
+        DslOutput::Message(msg) => {
+            assert!(msg.contains("- items"), "{}", msg);
+            assert!(msg.contains("file://"), "{}", msg);
+        }
+        other => panic!("Expected message, got {:?}", other),
+    }
+
+    let mut fresh = TensorDb::with_config(local_backend_config(temp_dir, false));
+    execute_line(&mut fresh, "LOAD DATASET items", 1).unwrap();
+    execute_line(&mut fresh, "LOAD TENSOR m", 2).unwrap();
+    let items = fresh.get_dataset("items").unwrap();
+    assert_eq!(items.len(), 1);
+    assert!(items.indices.contains_key("id"));
+    assert_eq!(fresh.get("m").unwrap().shape.dims, vec![2, 2]);
+    assert_eq!(*fresh.get("m").unwrap().data, vec![1.0, 2.0, 3.0, 4.0]);
+
+    // An explicit path still writes plain files
+    let explicit = format!("{}/explicit", temp_dir);
+    execute_line(
+        &mut db,
+        &format!("SAVE DATASET items TO \"{}\"", explicit),
+        3,
+    )
+    .unwrap();
+    assert!(Path::new(&format!("{}/datasets/items.parquet", explicit)).exists());
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[cfg(not(feature = "s3"))]
+#[test]
+fn test_s3_backend_requires_feature() {
+    let mut config = local_backend_config("/tmp/linal_test_object_store_s3", false);
+    config.storage.backend = StorageBackend::S3;
+    let mut db = TensorDb::with_config(config);
+
+    execute_line(&mut db, "VECTOR v = [1.0]", 1).unwrap();
+    let err = execute_line(&mut db, "SAVE TENSOR v", 2).unwrap_err();
+    assert!(err.to_string().contains("`s3` feature"), "{}", err);
+}
+
+#[test]
+fn test_storage_backend_from_toml() {
+    let toml = r#"
+        [storage]
+        data_dir = "./data"
+        default_db = "default"
+        backend = "s3"
+        bucket = "my-bucket"
+        prefix = "linal"
+    "#;
+    let config: EngineConfig = toml::from_str(toml).unwrap();
+    assert_eq!(config.storage.backend, StorageBackend::S3);
+    assert_eq!(config.storage.bucket.as_deref(), Some("my-bucket"));
+    assert_eq!(config.storage.prefix.as_deref(), Some("linal"));
+
+    let config: EngineConfig =
+        toml::from_str("[storage]\ndata_dir = \"d\"\ndefault_db = \"x\"").unwrap();
+    assert_eq!(config.storage.backend, StorageBackend::File);
+}
@@ -0,0 +1,128 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use linal::query::logical::{Expr, LogicalPlan};
+use linal::query::optimizer::optimize;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET t COLUMNS (id: Int, name: String, score: Float, tag: String)
+    INSERT INTO t VALUES (1, "a", 1.5, "x"), (2, "b", 2.5, "y"), (3, "c", 3.5, "x")
+    CREATE INDEX tag_idx ON t(tag)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn table(db: &mut TensorDb, query: &str) -> (Vec<String>, Vec<Vec<Value>>) {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => (
+            ds.schema.fields.iter().map(|f| f.name.clone()).collect(),
+            ds.rows.into_iter().map(|r| r.values).collect(),
+        ),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn explain(db: &mut TensorDb, query: &str) -> String {
+    match execute_line(db, &format!("EXPLAIN {}", query), 1).unwrap() {
+        DslOutput::Message(plan) => plan,
+        other => panic!("Expected plan message, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_filters_move_below_renaming_projections() {
+    let mut db = setup();
+    let query = r#"SELECT k FROM (SELECT id AS k, tag AS label FROM t) WHERE label = "x""#;
+
+    let (_, rows) = table(&mut db, query);
+    assert_eq!(rows, vec![vec![Value::Int(1)], vec![Value::Int(3)]]);
+
+    // The pushed filter reaches the scan and can use its index
+    let plan = explain(&mut db, query);
+    assert!(plan.contains("IndexScanExec"), "{}", plan);
+
+    // Computed columns keep their filter above the projection
+    let query = "SELECT d FROM (SELECT score * 2 AS d FROM t) WHERE d > 4";
+    let (_, rows) = table(&mut db, query);
+    assert_eq!(rows, vec![vec![Value::Float(5.0)], vec![Value::Float(7.0)]]);
+}
+
+#[test]
+fn test_constant_folding() {
+    let mut db = setup();
+
+    // Folded columns keep the name of the expression they replace
+    let (names, rows) = table(&mut db, "SELECT id, 1 + 2 FROM t WHERE id = 1");
+    assert_eq!(names, vec!["id", "1 + 2"]);
+    assert_eq!(rows, vec![vec![Value::Int(1), Value::Int(3)]]);
+
+    let (_, rows) = table(&mut db, "SELECT id FROM t WHERE 1 = 0 OR id < 2 * 1");
+    assert_eq!(rows, vec![vec![Value::Int(1)]]);
+    let (_, rows) = table(&mut db, "SELECT id FROM t WHERE 2 > 1");
+    assert_eq!(rows.len(), 3);
+    let (_, rows) = table(&mut db, "SELECT id FROM t WHERE 1 > 2 AND id = 1");
+    assert!(rows.is_empty());
+
+    let plan = explain(&mut db, "SELECT id FROM t WHERE 2 > 1 AND id < 2 * 3");
+    assert!(
+        !plan.contains("AND") && !plan.contains(r#"op: "*""#),
+        "{}",
+        plan
+    );
+    let plan = explain(&mut db, "SELECT id FROM t WHERE 3 > 1");
+    assert!(!plan.contains("Filter"), "{}", plan);
+}
+
+#[test]
+fn test_scans_read_only_needed_columns() {
+    let db = setup();
+    let schema = db.get_dataset("t").unwrap().schema.clone();
+    let plan = LogicalPlan::Project {
+        input: Box::new(LogicalPlan::Filter {
+            input: Box::new(LogicalPlan::Scan {
+                dataset_name: "t".to_string(),
+                schema,
+            }),
+            predicate: Expr::BinaryExpr {
+                left: Box::new(Expr::Column("score".to_string())),
+                op: ">".to_string(),
+                right: Box::new(Expr::Literal(Value::Float(2.0))),
+            },
+        }),
+        exprs: vec![Expr::Column("name".to_string())],
+    };
+
+    let LogicalPlan::Project { input, .. } = optimize(plan.clone()) else {
+        panic!("Expected the projection to stay");
+    };
+    let LogicalPlan::Filter { input, .. } = *input else {
+        panic!("Expected a filter");
+    };
+    let LogicalPlan::Scan { schema, .. } = *input else {
+        panic!("Expected a scan");
+    };
+    let scanned: Vec<&str> = schema.fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(scanned, vec!["name", "score"]);
+
+    let result = db.execute_plan(&plan).unwrap();
+    assert_eq!(result.schema.fields.len(), 1);
+    assert_eq!(result.rows.len(), 2);
+}
+
+#[test]
+fn test_noop_projections_are_removed() {
+    let mut db = setup();
+    let plan = explain(&mut db, "SELECT id, name, score, tag FROM t");
+    assert!(!plan.contains("Project"), "{}", plan);
+
+    let (names, rows) = table(&mut db, "SELECT id, name, score, tag FROM t");
+    assert_eq!(names, vec!["id", "name", "score", "tag"]);
+    assert_eq!(rows.len(), 3);
+
+    // Reordering is not a no-op
+    let plan = explain(&mut db, "SELECT name, id, score, tag FROM t");
+    assert!(plan.contains("Project"), "{}", plan);
+}
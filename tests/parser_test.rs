@@ -0,0 +1,196 @@
+use linal::core::value::Value;
+use linal::dsl::lexer::{tokenize, TokenKind};
+use linal::dsl::parser::{classify, parse_statement, Command, FromClause, Statement};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use linal::query::logical::Expr;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET notes COLUMNS (id: Int, body: String, done: Bool)
+    INSERT INTO notes VALUES (1, "FROM ME", false), (2, "SELECT * WHERE x", true)
+    INSERT INTO notes VALUES (3, "a COLUMNS b", true), (4, "x.add_column(y)", false)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn ids(db: &mut TensorDb, query: &str) -> Vec<i64> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds
+            .rows
+            .iter()
+            .map(|r| match r.values[0] {
+                Value::Int(id) => id,
+                ref other => panic!("Expected an Int id, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn command(line: &str) -> Command {
+    classify(line, 1).unwrap().expect("not a comment").0
+}
+
+#[test]
+fn test_string_literals_are_single_tokens() {
+    let tokens = tokenize(r#"FILTER name = "FROM \"ME\", (x)" AND n >= 1.5e3"#).unwrap();
+    let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TokenKind::Word,
+            TokenKind::Word,
+            TokenKind::Symbol,
+            TokenKind::String,
+            TokenKind::Word,
+            TokenKind::Word,
+            TokenKind::Symbol,
+            TokenKind::Number,
+        ]
+    );
+    assert_eq!(tokens[3].text, r#""FROM \"ME\", (x)""#);
+    assert_eq!(tokens[6].text, ">=");
+
+    assert!(tokenize(r#"FILTER name = "open"#).is_err());
+}
+
+#[test]
+fn test_keywords_inside_strings_do_not_split_queries() {
+    let mut db = setup();
+
+    assert_eq!(
+        ids(&mut db, r#"SELECT id FROM notes FILTER body = "FROM ME""#),
+        vec![1]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            r#"SELECT id FROM notes WHERE body = "SELECT * WHERE x" OR body = "a COLUMNS b""#
+        ),
+        vec![2, 3]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            r#"SELECT id FROM notes WHERE body LIKE "%ORDER BY%""#
+        ),
+        Vec::<i64>::new()
+    );
+
+    execute_line(
+        &mut db,
+        r#"DATASET picked FROM notes FILTER body = "a COLUMNS b" SELECT id"#,
+        1,
+    )
+    .unwrap();
+    assert_eq!(ids(&mut db, "SELECT id FROM picked"), vec![3]);
+}
+
+#[test]
+fn test_commands_are_identified_by_leading_keywords() {
+    assert_eq!(
+        command(r#"DATASET d FROM notes FILTER body = "x COLUMNS y""#),
+        Command::DatasetQuery
+    );
+    assert_eq!(
+        command("DATASET d COLUMNS (id: Int, src: String)"),
+        Command::CreateDataset
+    );
+    assert_eq!(
+        command("ALTER DATASET d ADD COLUMN n: Int"),
+        Command::AddColumn
+    );
+    assert_eq!(
+        command(r#"INSERT INTO notes VALUES (5, "y.add_column(z)", true)"#),
+        Command::Insert
+    );
+    assert_eq!(
+        command(r#"notes.add_column("v", t)"#),
+        Command::AddTensorColumn
+    );
+    assert_eq!(
+        command("CREATE VECTOR INDEX ON d(emb)"),
+        Command::CreateIndex
+    );
+    assert_eq!(command("CHECKPOINT"), Command::Checkpoint);
+    assert!(classify("# a comment", 1).unwrap().is_none());
+    assert!(classify("FETCH everything", 1).is_err());
+    assert!(classify("CHECKPOINT now", 1).is_err());
+
+    // A string containing `.add_column(` is still an INSERT
+    let mut db = setup();
+    execute_line(
+        &mut db,
+        r#"INSERT INTO notes VALUES (5, "y.add_column(z)", true)"#,
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        ids(
+            &mut db,
+            r#"SELECT id FROM notes WHERE body = "y.add_column(z)""#
+        ),
+        vec![5]
+    );
+}
+
+#[test]
+fn test_keywords_are_case_insensitive() {
+    let mut db = setup();
+
+    assert_eq!(
+        ids(&mut db, "select id from notes where done and id > 2"),
+        vec![3]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "Select id From notes Where id Not In (1, 2) Order By id Desc Limit 1"
+        ),
+        vec![4]
+    );
+    execute_line(&mut db, "update notes set done = TRUE where id = 1", 1).unwrap();
+    execute_line(&mut db, "delete from notes where not done", 1).unwrap();
+    assert_eq!(ids(&mut db, "SELECT id FROM notes"), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_select_parses_into_a_syntax_tree() {
+    let statement = parse_statement(
+        r#"SELECT id, COUNT(*) FROM notes WHERE done AND body != "x" GROUP BY id LIMIT 3"#,
+        1,
+    )
+    .unwrap();
+    let Statement::Select(select) = statement else {
+        panic!("Expected a SELECT, got {:?}", statement);
+    };
+
+    assert_eq!(select.items.len(), 2);
+    assert!(matches!(&select.items[0], Expr::Column(c) if c == "id"));
+    assert!(matches!(select.items[1], Expr::AggregateExpr { .. }));
+    assert!(matches!(&select.from, FromClause::Source { name, version: None } if name == "notes"));
+    // Top-level ANDs become separate filters
+    assert_eq!(select.clauses.filters.len(), 2);
+    assert_eq!(select.clauses.limit, Some(3));
+
+    let Statement::Command { command, line } = parse_statement("show all", 1).unwrap() else {
+        panic!("Expected a command");
+    };
+    assert_eq!(command, Command::Show);
+    assert_eq!(line, "SHOW all");
+}
+
+#[test]
+fn test_parse_errors() {
+    let err = |line: &str| parse_statement(line, 7).unwrap_err().to_string();
+
+    assert!(err("SELECT id FROM notes LIMIT ten").contains("Invalid LIMIT"));
+    assert!(err("SELECT id FROM notes LIMIT 1 LIMIT 2").contains("Duplicate LIMIT clause"));
+    assert!(err("SELECT id FROM notes SHUFFLE").contains("Unknown clause: SHUFFLE"));
+    assert!(err("SELECT id FROM notes WHERE id >").contains("line 7"));
+    assert!(err("SELECT id FROM notes WHERE body = \"open").contains("Unterminated"));
+    assert!(err("SELECT id FROM notes WHERE (id > 1").contains("Expected ')'"));
+}
@@ -1,8 +1,12 @@
 use linal::core::dataset_legacy::{Dataset, DatasetId};
+use linal::core::index::hash::HashIndex;
+use linal::core::index::IndexType;
 use linal::core::storage::{ParquetStorage, StorageEngine};
 use linal::core::tensor::{Shape, Tensor, TensorId};
 use linal::core::tuple::{Field, Schema, Tuple};
 use linal::core::value::{Value, ValueType};
+use linal::dsl::{execute_line, execute_script};
+use linal::TensorDb;
 use std::fs;
 use std::sync::Arc;
 
@@ -143,7 +147,7 @@ fn test_save_tensor() {
 
     // Verify file exists
     assert!(storage.tensor_exists("embedding"));
-    assert!(std::path::Path::new(&format!("{}/tensors/embedding.json", temp_dir)).exists());
+    assert!(std::path::Path::new(&format!("{}/tensors/embedding.npy", temp_dir)).exists());
 
     // Clean up
     let _ = fs::remove_dir_all(temp_dir);
@@ -344,3 +348,188 @@ fn test_dataset_round_trip() {
     // Clean up
     let _ = fs::remove_dir_all(temp_dir);
 }
+
+#[test]
+fn test_index_round_trip() {
+    let temp_dir = "/tmp/linal_test_persistence_index_round_trip";
+    let _ = fs::remove_dir_all(temp_dir);
+
+    let storage = ParquetStorage::new(temp_dir);
+    let mut dataset = create_test_dataset("indexed_users");
+    dataset
+        .create_index("name".to_string(), Box::new(HashIndex::new()))
+        .unwrap();
+
+    storage.save_dataset(&dataset).unwrap();
+    assert!(
+        std::path::Path::new(&format!("{}/datasets/indexed_users.indices.json", temp_dir)).exists()
+    );
+
+    // Indices come back populated, without rebuilding from rows
+    let loaded = storage.load_dataset("indexed_users").unwrap();
+    let index = loaded.get_index("name").expect("index should be restored");
+    assert_eq!(index.index_type(), IndexType::Hash);
+    assert_eq!(
+        index.lookup(&Value::String("Bob".to_string())).unwrap(),
+        vec![1]
+    );
+
+    // Deleting the dataset removes the index file as well
+    storage.delete_dataset("indexed_users").unwrap();
+    assert!(
+        !std::path::Path::new(&format!("{}/datasets/indexed_users.indices.json", temp_dir))
+            .exists()
+    );
+
+    // Clean up
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_dsl_save_load_restores_indices() {
+    let temp_dir = "/tmp/linal_test_persistence_dsl_indices";
+    let _ = fs::remove_dir_all(temp_dir);
+
+    let mut db = TensorDb::new();
+    let script = format!(
+        r#"
+    DATASET items COLUMNS (id: Int, category: String, embedding: Vector(3))
+    CREATE INDEX cat_idx ON items(category)
+    CREATE VECTOR INDEX vec_idx ON items(embedding)
+    INSERT INTO items VALUES (1, "A", [1.0, 0.0, 0.0])
+    INSERT INTO items VALUES (2, "B", [0.0, 1.0, 0.0])
+    SAVE DATASET items TO "{}"
+    "#,
+        temp_dir
+    );
+    execute_script(&mut db, &script).unwrap();
+
+    let mut db2 = TensorDb::new();
+    execute_script(
+        &mut db2,
+        &format!(r#"LOAD DATASET items FROM "{}""#, temp_dir),
+    )
+    .unwrap();
+
+    let indices = db2.list_indices();
+    assert_eq!(indices.len(), 2);
+    assert!(indices
+        .iter()
+        .any(|(ds, col, t)| ds == "items" && col == "category" && t == "HASH"));
+    assert!(indices
+        .iter()
+        .any(|(ds, col, t)| ds == "items" && col == "embedding" && t == "VECTOR"));
+
+    // Restored indices keep being maintained on new inserts
+    execute_script(
+        &mut db2,
+        r#"INSERT INTO items VALUES (3, "A", [0.0, 0.0, 1.0])"#,
+    )
+    .unwrap();
+    let ds = db2.get_dataset("items").unwrap();
+    let hits = ds
+        .get_index("category")
+        .unwrap()
+        .lookup(&Value::String("A".to_string()))
+        .unwrap();
+    assert_eq!(hits, vec![0, 2]);
+
+    // Clean up
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_dsl_save_all() {
+    let temp_dir = "/tmp/linal_test_persistence_save_all";
+    let _ = fs::remove_dir_all(temp_dir);
+
+    let mut db = TensorDb::new();
+    let script = format!(
+        r#"
+    DATASET users COLUMNS (id: Int, name: String)
+    INSERT INTO users VALUES (1, "Alice")
+    INSERT INTO users VALUES (2, "Bob")
+    VECTOR w = [1.0, 2.0, 3.0]
+    SAVE ALL TO "{}"
+    "#,
+        temp_dir
+    );
+    execute_script(&mut db, &script).unwrap();
+
+    let storage = ParquetStorage::new(temp_dir);
+    assert_eq!(storage.list_datasets().unwrap(), vec!["users".to_string()]);
+    assert_eq!(storage.list_tensors().unwrap(), vec!["w".to_string()]);
+
+    let mut db2 = TensorDb::new();
+    execute_script(
+        &mut db2,
+        &format!(
+            r#"
+    LOAD DATASET users FROM "{0}"
+    LOAD TENSOR w FROM "{0}"
+    "#,
+            temp_dir
+        ),
+    )
+    .unwrap();
+    assert_eq!(db2.get_dataset("users").unwrap().len(), 2);
+    assert_eq!(*db2.get("w").unwrap().data, vec![1.0, 2.0, 3.0]);
+
+    // Trailing garbage is rejected
+    assert!(execute_script(&mut db, "SAVE ALL users").is_err());
+
+    // Clean up
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_save_and_load_tensor_npy_file() {
+    let temp_dir = "/tmp/linal_test_npy_dsl";
+    let _ = fs::remove_dir_all(temp_dir);
+
+    let mut db = TensorDb::new();
+    execute_line(&mut db, "MATRIX w = [[1.5, -2.0, 3.0], [4.0, 5.0, 6.25]]", 1).unwrap();
+    execute_line(&mut db, &format!("SAVE TENSOR w TO \"{}/w.npy\"", temp_dir), 2).unwrap();
+
+    // Standard NPY v1.0 file: 64-byte aligned header followed by raw float32 data
+    let bytes = fs::read(format!("{}/w.npy", temp_dir)).unwrap();
+    assert_eq!(&bytes[..6], b"\x93NUMPY");
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+    assert!(header.contains("'descr': '<f4'"));
+    assert!(header.contains("'shape': (2, 3)"));
+    assert_eq!(bytes.len(), 10 + header_len + 6 * 4);
+
+    execute_line(&mut db, &format!("LOAD TENSOR w2 FROM \"{}/w.npy\"", temp_dir), 3).unwrap();
+    let loaded = db.get("w2").unwrap();
+    assert_eq!(loaded.shape.dims, vec![2, 3]);
+    assert_eq!(*loaded.data, vec![1.5, -2.0, 3.0, 4.0, 5.0, 6.25]);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_load_legacy_json_tensor() {
+    let temp_dir = "/tmp/linal_test_legacy_tensor";
+    let _ = fs::remove_dir_all(temp_dir);
+    fs::create_dir_all(format!("{}/tensors", temp_dir)).unwrap();
+
+    // Layout written by earlier versions
+    let tensor = create_test_tensor(7, vec![2, 2]);
+    fs::write(
+        format!("{}/tensors/old.json", temp_dir),
+        serde_json::to_string_pretty(&tensor).unwrap(),
+    )
+    .unwrap();
+
+    let storage = ParquetStorage::new(temp_dir);
+    assert!(storage.tensor_exists("old"));
+    assert_eq!(storage.load_tensor("old").unwrap().data, tensor.data);
+
+    // Re-saving migrates it to .npy
+    storage.save_tensor("old", &tensor).unwrap();
+    assert!(!std::path::Path::new(&format!("{}/tensors/old.json", temp_dir)).exists());
+    assert_eq!(storage.list_tensors().unwrap(), vec!["old".to_string()]);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
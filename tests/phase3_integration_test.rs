@@ -4,8 +4,11 @@ use linal::TensorDb;
 #[test]
 fn test_phase3_symbol_resolution() {
     let mut db = TensorDb::new();
+    let output = std::env::temp_dir().join("linal_phase3_test_output.parquet");
+    let _ = std::fs::remove_dir_all(&output);
 
-    let script = r#"
+    let script = format!(
+        r#"
         VECTOR v1 = [1.0, 2.0, 3.0]
         LET ds = dataset("test_ds")
         ds.add_column("vec", v1)
@@ -14,12 +17,14 @@ fn test_phase3_symbol_resolution() {
         
         ds.add_column("vec_doubled", v2)
         
-        SAVE DATASET test_ds TO "test_output.parquet"
+        SAVE DATASET test_ds TO "{}"
         
         SHOW ds
-    "#;
+    "#,
+        output.display()
+    );
 
-    execute_script(&mut db, script).unwrap();
+    execute_script(&mut db, &script).unwrap();
 
     let ds = db.get_tensor_dataset("test_ds").unwrap();
     assert_eq!(ds.columns.len(), 2);
@@ -30,12 +35,16 @@ fn test_phase3_symbol_resolution() {
 
     // Clear and load back
     let mut db2 = TensorDb::new();
-    let load_script = r#"
-        LOAD DATASET test_ds FROM "test_output.parquet"
+    let load_script = format!(
+        r#"
+        LOAD DATASET test_ds FROM "{}"
         SHOW test_ds
-    "#;
-    execute_script(&mut db2, load_script).unwrap();
+    "#,
+        output.display()
+    );
+    execute_script(&mut db2, &load_script).unwrap();
 
     let loaded_ds = db2.get_dataset("test_ds").unwrap();
     assert_eq!(loaded_ds.len(), 3);
+    let _ = std::fs::remove_dir_all(&output);
 }
@@ -94,3 +94,50 @@ fn test_explain_command() {
         ),
     }
 }
+
+#[test]
+fn test_execute_plan_without_dsl() {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String, age: Int)
+    CREATE INDEX name_idx ON users(name)
+    INSERT INTO users VALUES (1, "Alice", 30)
+    INSERT INTO users VALUES (2, "Bob", 25)
+    INSERT INTO users VALUES (3, "Alice", 41)
+    "#;
+    linal::dsl::execute_script(&mut db, script).expect("Setup failed");
+
+    let schema = db.get_dataset("users").unwrap().schema.clone();
+
+    // Project(id) -> Sort(age DESC) -> Filter(name = "Alice", index-backed) -> Scan(users)
+    let plan = LogicalPlan::Project {
+        input: Box::new(LogicalPlan::Sort {
+            input: Box::new(LogicalPlan::Filter {
+                input: Box::new(LogicalPlan::Scan {
+                    dataset_name: "users".to_string(),
+                    schema,
+                }),
+                predicate: Expr::BinaryExpr {
+                    left: Box::new(Expr::Column("name".to_string())),
+                    op: "=".to_string(),
+                    right: Box::new(Expr::Literal(Value::String("Alice".to_string()))),
+                },
+            }),
+            column: "age".to_string(),
+            ascending: false,
+        }),
+        exprs: vec![Expr::Column("id".to_string())],
+    };
+
+    let result = db.execute_plan(&plan).expect("Execution failed");
+    assert_eq!(result.schema.len(), 1);
+    let ids: Vec<Value> = result.rows.iter().map(|r| r.values[0].clone()).collect();
+    assert_eq!(ids, vec![Value::Int(3), Value::Int(1)]);
+
+    // Planning errors surface as engine errors
+    let bad = LogicalPlan::Scan {
+        dataset_name: "missing".to_string(),
+        schema: result.schema.clone(),
+    };
+    assert!(db.execute_plan(&bad).is_err());
+}
@@ -0,0 +1,154 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::core::dataset_legacy::Dataset;
+use linal::core::tuple::Tuple;
+use linal::dsl::execute_line;
+use linal::engine::{EnginePlugin, QueryEvent, TensorDb};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<String>>,
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.events.lock().unwrap())
+    }
+}
+
+impl EnginePlugin for Recorder {
+    fn name(&self) -> &str {
+        "recorder"
+    }
+
+    fn on_dataset_created(&self, database: &str, dataset: &Dataset) {
+        self.events.lock().unwrap().push(format!(
+            "created {}.{} ({} columns)",
+            database,
+            dataset.metadata.name.as_deref().unwrap_or("?"),
+            dataset.schema.len()
+        ));
+    }
+
+    fn on_rows_inserted(&self, database: &str, dataset: &str, rows: &[Tuple]) {
+        let ids: Vec<String> = rows.iter().map(|r| r.values[0].to_string()).collect();
+        self.events.lock().unwrap().push(format!(
+            "inserted {}.{} [{}]",
+            database,
+            dataset,
+            ids.join(", ")
+        ));
+    }
+
+    fn on_query_executed(&self, event: &QueryEvent<'_>) {
+        self.events.lock().unwrap().push(format!(
+            "query {} rows={:?} error={}",
+            event.command,
+            event.rows_returned,
+            event.error.is_some()
+        ));
+    }
+}
+
+fn setup() -> (TensorDb, Arc<Recorder>) {
+    setup_with(EngineConfig::default())
+}
+
+fn setup_with(config: EngineConfig) -> (TensorDb, Arc<Recorder>) {
+    let recorder = Arc::new(Recorder::default());
+    let db = TensorDb::with_plugins(config, vec![recorder.clone()]);
+    (db, recorder)
+}
+
+#[test]
+fn test_dataset_hooks_see_created_datasets_and_new_rows() {
+    let (mut db, recorder) = setup();
+    assert_eq!(db.plugin_names(), vec!["recorder"]);
+
+    execute_line(
+        &mut db,
+        "DATASET users COLUMNS (id: Int PRIMARY KEY, name: String)",
+        1,
+    )
+    .unwrap();
+    execute_line(
+        &mut db,
+        "INSERT INTO users VALUES (1, \"Ana\"), (2, \"Ben\")",
+        2,
+    )
+    .unwrap();
+    execute_line(&mut db, "UPSERT INTO users VALUES (1, \"Ana B.\")", 3).unwrap();
+
+    let events = recorder.take();
+    let hooks: Vec<&String> = events.iter().filter(|e| !e.starts_with("query")).collect();
+    assert_eq!(
+        hooks,
+        vec![
+            "created default.users (2 columns)",
+            "inserted default.users [1, 2]",
+            "inserted default.users [1]",
+        ]
+    );
+}
+
+#[test]
+fn test_query_hook_reports_results_and_errors() {
+    let (mut db, recorder) = setup();
+    execute_line(&mut db, "SQL CREATE TABLE t (id INT, tag TEXT)", 1).unwrap();
+    execute_line(&mut db, "SQL INSERT INTO t VALUES (1, 'a'), (2, 'b')", 2).unwrap();
+    recorder.take();
+
+    execute_line(&mut db, "SELECT id FROM t WHERE id > 0", 3).unwrap();
+    assert!(execute_line(&mut db, "SELECT id FROM missing", 4).is_err());
+    assert_eq!(
+        recorder.take(),
+        vec![
+            "query SELECT id FROM t WHERE id > 0 rows=Some(2) error=false",
+            "query SELECT id FROM missing rows=None error=true",
+        ]
+    );
+}
+
+#[test]
+fn test_stored_queries_report_once() {
+    // Stored queries are saved under data_dir
+    let temp_dir = "/tmp/linal_test_plugin_stored_query";
+    let _ = fs::remove_dir_all(temp_dir);
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (mut db, recorder) = setup_with(config);
+    execute_line(&mut db, "DATASET t COLUMNS (id: Int)", 1).unwrap();
+    execute_line(&mut db, "INSERT INTO t VALUES (1)", 2).unwrap();
+    execute_line(&mut db, "CREATE QUERY all_t AS SELECT id FROM t", 3).unwrap();
+    recorder.take();
+
+    execute_line(&mut db, "RUN QUERY all_t", 4).unwrap();
+    assert_eq!(
+        recorder.take(),
+        vec!["query RUN QUERY all_t rows=Some(1) error=false"]
+    );
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_sessions_keep_the_plugins() {
+    let (mut catalog, recorder) = setup();
+    execute_line(&mut catalog, "CREATE DATABASE analytics", 1).unwrap();
+    let mut session = catalog.detach_session("analytics").unwrap();
+    execute_line(&mut session, "DATASET events COLUMNS (id: Int)", 1).unwrap();
+    catalog.attach_session(session);
+
+    assert!(recorder
+        .take()
+        .contains(&"created analytics.events (1 columns)".to_string()));
+    assert!(TensorDb::with_config(EngineConfig::default())
+        .plugin_names()
+        .is_empty());
+}
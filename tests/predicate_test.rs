@@ -0,0 +1,172 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String, age: Int, score: Float, active: Bool)
+    INSERT INTO users VALUES (1, "Alice", 30, 10.0, true), (2, "Bob", 15, 20.0, false)
+    INSERT INTO users VALUES (3, "Carol", 17, 30.0, true), (4, "Dan", 40, 40.0, true)
+    INSERT INTO users VALUES (5, "al_x%", 25, 50.0, false)
+    CREATE INDEX name_idx ON users(name)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn ids(db: &mut TensorDb, query: &str) -> Vec<i64> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds
+            .rows
+            .iter()
+            .map(|r| match r.values[0] {
+                Value::Int(id) => id,
+                ref other => panic!("Expected an Int id, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_and_or_not_and_parentheses() {
+    let mut db = setup();
+
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE age > 16 AND active = true"
+        ),
+        vec![1, 3, 4]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE age < 16 OR score >= 40.0"
+        ),
+        vec![2, 4, 5]
+    );
+    // AND binds tighter than OR; parentheses override it
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE id = 1 OR id = 2 AND active = true"
+        ),
+        vec![1]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE (id = 1 OR id = 2) AND NOT (active = true)"
+        ),
+        vec![2]
+    );
+    // A bare Bool column is a predicate
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE NOT active OR age > 35"),
+        vec![2, 4, 5]
+    );
+    // Keywords inside strings are just text; the indexed conjunct still works
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE name = \"Bob\" AND name != \"x AND y OR z\""
+        ),
+        vec![2]
+    );
+}
+
+#[test]
+fn test_in_between_and_like() {
+    let mut db = setup();
+
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE name IN (\"Bob\", \"Dan\", \"Zed\")"
+        ),
+        vec![2, 4]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE id NOT IN (1, 2, 3)"),
+        vec![4, 5]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE age BETWEEN 17 AND 30 AND active = true"
+        ),
+        vec![1, 3]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE age NOT BETWEEN 16 AND 35"
+        ),
+        vec![2, 4]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE name LIKE \"%a%\""),
+        vec![3, 4, 5]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE name LIKE \"_o%\""),
+        vec![2]
+    );
+    // Backslash escapes a wildcard
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE name LIKE \"al\\_x\\%\""
+        ),
+        vec![5]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE name NOT LIKE \"%l%\""),
+        vec![2, 4]
+    );
+}
+
+#[test]
+fn test_compound_predicates_in_update_delete_and_sql() {
+    let mut db = setup();
+
+    execute_line(
+        &mut db,
+        "UPDATE users SET score = 0.0 WHERE active = false OR name LIKE \"C%\"",
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users WHERE score = 0.0"),
+        vec![2, 3, 5]
+    );
+
+    let out = execute_line(&mut db, "DELETE FROM users WHERE id IN (1, 5)", 2).unwrap();
+    assert!(matches!(out, DslOutput::Affected { rows: 2, .. }));
+
+    assert_eq!(
+        ids(
+            &mut db,
+            "SQL SELECT id FROM users WHERE (age BETWEEN 10 AND 20 OR name LIKE 'D%') AND id NOT IN (2)"
+        ),
+        vec![3, 4]
+    );
+}
+
+#[test]
+fn test_invalid_predicates() {
+    let mut db = setup();
+
+    for query in [
+        "SELECT id FROM users WHERE id IN ()",
+        "SELECT id FROM users WHERE id IN 1, 2",
+        "SELECT id FROM users WHERE age BETWEEN 1",
+        "SELECT id FROM users WHERE name LIKE 5",
+        "SELECT id FROM users WHERE age > 1 AND",
+        "SELECT id FROM users WHERE age = [1",
+    ] {
+        assert!(execute_line(&mut db, query, 1).is_err(), "{}", query);
+    }
+}
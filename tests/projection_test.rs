@@ -0,0 +1,118 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET sales COLUMNS (name: String, price: Float, quantity: Int, region: String)
+    INSERT INTO sales VALUES ("pen", 1.5, 4, "west"), ("ink", 3.0, 2, "east")
+    INSERT INTO sales VALUES ("pad", 2.0, 5, "west")
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+/// Column names and rows of a query result
+fn table(db: &mut TensorDb, query: &str) -> (Vec<String>, Vec<Vec<Value>>) {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => (
+            ds.schema.fields.iter().map(|f| f.name.clone()).collect(),
+            ds.rows.into_iter().map(|r| r.values).collect(),
+        ),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn s(value: &str) -> Value {
+    Value::String(value.to_string())
+}
+
+#[test]
+fn test_expressions_are_evaluated_and_named_by_alias() {
+    let mut db = setup();
+
+    let (columns, rows) = table(&mut db, "SELECT price * quantity AS total, name FROM sales");
+    assert_eq!(columns, vec!["total", "name"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Float(6.0), s("pen")],
+            vec![Value::Float(6.0), s("ink")],
+            vec![Value::Float(10.0), s("pad")],
+        ]
+    );
+
+    // Without AS the expression text names the column; plain columns can be renamed
+    let (columns, rows) = table(
+        &mut db,
+        "SELECT name AS item, quantity + 1 FROM sales WHERE region = \"west\"",
+    );
+    assert_eq!(columns, vec!["item", "quantity + 1"]);
+    assert_eq!(rows[1], vec![s("pad"), Value::Int(6)]);
+
+    let (columns, _) = table(
+        &mut db,
+        "SQL SELECT name AS n, price * 2 AS double_price FROM sales",
+    );
+    assert_eq!(columns, vec!["n", "double_price"]);
+}
+
+#[test]
+fn test_order_by_alias() {
+    let mut db = setup();
+
+    let (_, rows) = table(
+        &mut db,
+        "SELECT name, price * quantity AS total FROM sales ORDER BY total DESC LIMIT 2",
+    );
+    assert_eq!(rows[0], vec![s("pad"), Value::Float(10.0)]);
+    assert_eq!(rows.len(), 2);
+}
+
+#[test]
+fn test_aliased_aggregates_and_group_keys() {
+    let mut db = setup();
+
+    let (columns, rows) = table(
+        &mut db,
+        "SELECT region AS r, SUM(quantity) AS units FROM sales GROUP BY region ORDER BY units DESC",
+    );
+    assert_eq!(columns, vec!["r", "units"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![s("west"), Value::Int(9)],
+            vec![s("east"), Value::Int(2)]
+        ]
+    );
+}
+
+#[test]
+fn test_dataset_query_projects_expressions() {
+    let mut db = setup();
+
+    execute_line(
+        &mut db,
+        "DATASET big FROM sales FILTER quantity > 3 SELECT name, price * quantity AS total",
+        1,
+    )
+    .unwrap();
+    let (columns, rows) = table(&mut db, "SELECT * FROM big");
+    assert_eq!(columns, vec!["name", "total"]);
+    assert_eq!(rows[1], vec![s("pad"), Value::Float(10.0)]);
+}
+
+#[test]
+fn test_unknown_columns_in_expressions_fail() {
+    let mut db = setup();
+
+    let err = execute_line(&mut db, "SELECT nope * 2 AS x FROM sales", 1).unwrap_err();
+    assert!(
+        err.to_string().contains("Column not found: nope"),
+        "{}",
+        err
+    );
+    let err = execute_line(&mut db, "SELECT name AS 5 FROM sales", 1).unwrap_err();
+    assert!(err.to_string().contains("after AS"), "{}", err);
+}
@@ -74,3 +74,55 @@ fn test_vector_search_workflow() {
         panic!("Result row does not have id");
     }
 }
+
+fn ids(db: &mut TensorDb, query: &str) -> Vec<i64> {
+    match linal::dsl::execute_line(db, query, 1).unwrap() {
+        linal::dsl::DslOutput::Table(ds) => ds
+            .rows
+            .iter()
+            .map(|r| match r.values[0] {
+                linal::core::value::Value::Int(id) => id,
+                ref other => panic!("Expected an Int id, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_order_by_limit_uses_top_k() {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET scores COLUMNS (id: Int, score: Int)
+    INSERT INTO scores VALUES (1, 50), (2, 90), (3, 70), (4, 90), (5, 10), (6, 70), (7, 30)
+    "#;
+    linal::dsl::execute_script(&mut db, script).unwrap();
+
+    // Top-k matches the full sort, ties included
+    for (order, k) in [("ASC", 3), ("DESC", 3), ("DESC", 2), ("ASC", 20)] {
+        let full = ids(
+            &mut db,
+            &format!("SELECT id FROM scores ORDER BY score {}", order),
+        );
+        let top = ids(
+            &mut db,
+            &format!("SELECT id FROM scores ORDER BY score {} LIMIT {}", order, k),
+        );
+        assert_eq!(top, full.into_iter().take(k).collect::<Vec<_>>());
+    }
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM scores ORDER BY score DESC LIMIT 3"),
+        vec![2, 4, 3]
+    );
+    assert!(ids(&mut db, "SELECT id FROM scores ORDER BY score LIMIT 0").is_empty());
+
+    match linal::dsl::execute_line(&mut db, "EXPLAIN SELECT id FROM scores ORDER BY score LIMIT 2", 1)
+        .unwrap()
+    {
+        linal::dsl::DslOutput::Message(plan) => {
+            assert!(plan.contains("TopKExec"));
+            assert!(!plan.contains("SortExec"));
+        }
+        other => panic!("Expected plan message, got {:?}", other),
+    }
+}
@@ -0,0 +1,118 @@
+use linal::core::config::{EngineConfig, ExecutionConfig};
+use linal::dsl::{execute_line, DslOutput};
+use linal::engine::TensorDb;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn db_listing_after(progress_after_secs: u64) -> TensorDb {
+    TensorDb::with_config(EngineConfig {
+        execution: ExecutionConfig {
+            progress_after_secs,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
+
+fn show_queries(db: &mut TensorDb) -> String {
+    match execute_line(db, "SHOW QUERIES", 1).unwrap() {
+        DslOutput::Message(text) => text,
+        other => panic!("Expected message output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_show_queries_reports_progress() {
+    let mut db = db_listing_after(0);
+    let registry = db.query_registry();
+    let query = registry.begin("default", "SELECT * FROM big");
+    query
+        .progress()
+        .start_operator("SeqScanExec(big)".to_string(), 10_000);
+    query.progress().advance(2_500);
+
+    let running = db.running_queries();
+    assert_eq!(running.len(), 1);
+    assert_eq!(running[0].operator.as_deref(), Some("SeqScanExec(big)"));
+    assert_eq!(running[0].rows_processed, 2_500);
+    assert_eq!(running[0].rows_remaining, Some(7_500));
+    assert!(running[0].estimated_remaining_secs.is_some());
+
+    // SHOW QUERIES does not list itself
+    let output = show_queries(&mut db);
+    assert!(output.contains("SeqScanExec(big)"), "{}", output);
+    assert!(output.contains("SELECT * FROM big"), "{}", output);
+    assert!(!output.contains("SHOW QUERIES"), "{}", output);
+
+    drop(query);
+    assert!(db.running_queries().is_empty());
+}
+
+#[test]
+fn test_short_queries_are_not_listed() {
+    let mut db = db_listing_after(5);
+    let _query = db.query_registry().begin("default", "SELECT * FROM big");
+    assert!(db.running_queries().is_empty());
+    assert!(!show_queries(&mut db).contains("SELECT * FROM big"));
+}
+
+#[test]
+fn test_running_scan_is_visible_from_another_thread() {
+    let mut db = db_listing_after(0);
+    execute_line(&mut db, "DATASET big COLUMNS (id: Int)", 1).unwrap();
+    for batch in 0..5 {
+        let values: Vec<String> = (0..1000)
+            .map(|i| format!("({})", batch * 1000 + i))
+            .collect();
+        let insert = format!("INSERT INTO big VALUES {}", values.join(", "));
+        execute_line(&mut db, &insert, 1).unwrap();
+    }
+
+    let registry = db.query_registry();
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker = {
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                execute_line(&mut db, "SELECT id FROM big WHERE id > 10", 1).unwrap();
+            }
+        })
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let mut seen = None;
+    while seen.is_none() && Instant::now() < deadline {
+        seen = registry
+            .running(Duration::ZERO)
+            .into_iter()
+            .find(|q| q.operator.as_deref() == Some("SeqScanExec(big)") && q.rows_processed > 0);
+    }
+    stop.store(true, Ordering::Relaxed);
+    worker.join().unwrap();
+
+    let seen = seen.expect("the scan never reported progress");
+    assert_eq!(seen.command, "SELECT id FROM big WHERE id > 10");
+    assert_eq!(seen.database, "default");
+    assert!(seen.rows_processed <= 5_000);
+    // Finished commands leave the registry
+    assert!(registry.running(Duration::ZERO).is_empty());
+}
+
+#[test]
+fn test_sessions_share_the_registry() {
+    let mut catalog = db_listing_after(0);
+    execute_line(&mut catalog, "CREATE DATABASE analytics", 1).unwrap();
+    let mut session = catalog.detach_session("analytics").unwrap();
+
+    assert!(session.begin_query("SELECT * FROM events"));
+    // Commands run by a command report under it
+    assert!(!session.begin_query("SELECT 1"));
+    let running = catalog.running_queries();
+    assert_eq!(running.len(), 1);
+    assert_eq!(running[0].database, "analytics");
+
+    session.end_query();
+    assert!(catalog.running_queries().is_empty());
+    catalog.attach_session(session);
+}
@@ -0,0 +1,164 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup_scores(db: &mut TensorDb) {
+    let script = r#"
+    DATASET scores COLUMNS (id: Int, score: Float)
+    INSERT INTO scores VALUES (1, 10.0)
+    INSERT INTO scores VALUES (2, 40.0)
+    INSERT INTO scores VALUES (3, 20.0)
+    INSERT INTO scores VALUES (4, 40.0)
+    INSERT INTO scores VALUES (5, 95.0)
+    "#;
+    execute_script(db, script).expect("Setup failed");
+}
+
+fn select_rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).expect("Query failed") {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_ntile_buckets_follow_order() {
+    let mut db = TensorDb::new();
+    setup_scores(&mut db);
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT id, NTILE(2) OVER (ORDER BY score) FROM scores",
+    );
+    assert_eq!(rows.len(), 5);
+
+    // Input order is preserved; 5 rows over 2 buckets -> sizes 3 and 2
+    let buckets: Vec<Value> = rows.iter().map(|r| r[1].clone()).collect();
+    assert_eq!(
+        buckets,
+        vec![
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(2),
+            Value::Int(2)
+        ]
+    );
+}
+
+#[test]
+fn test_percent_rank_with_ties_and_desc() {
+    let mut db = TensorDb::new();
+    setup_scores(&mut db);
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT id, PERCENT_RANK() OVER (ORDER BY score) FROM scores",
+    );
+    let ranks: Vec<Value> = rows.iter().map(|r| r[1].clone()).collect();
+    // Ties (score 40.0) share the same rank
+    assert_eq!(
+        ranks,
+        vec![
+            Value::Float(0.0),
+            Value::Float(0.5),
+            Value::Float(0.25),
+            Value::Float(0.5),
+            Value::Float(1.0)
+        ]
+    );
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT id, PERCENT_RANK() OVER (ORDER BY score DESC) FROM scores",
+    );
+    assert_eq!(rows[4][1], Value::Float(0.0));
+    assert_eq!(rows[0][1], Value::Float(1.0));
+}
+
+#[test]
+fn test_window_computed_before_order_and_limit() {
+    let mut db = TensorDb::new();
+    setup_scores(&mut db);
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT id, NTILE(5) OVER (ORDER BY score) FROM scores ORDER BY score DESC LIMIT 1",
+    );
+    assert_eq!(rows, vec![vec![Value::Int(5), Value::Int(5)]]);
+}
+
+#[test]
+fn test_width_bucket_in_select_and_computed_column() {
+    let mut db = TensorDb::new();
+    setup_scores(&mut db);
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT id, WIDTH_BUCKET(score, 0, 100, 4) FROM scores",
+    );
+    let buckets: Vec<Value> = rows.iter().map(|r| r[1].clone()).collect();
+    assert_eq!(
+        buckets,
+        vec![
+            Value::Int(1),
+            Value::Int(2),
+            Value::Int(1),
+            Value::Int(2),
+            Value::Int(4)
+        ]
+    );
+
+    // Out-of-range values land in the underflow/overflow buckets
+    let rows = select_rows(
+        &mut db,
+        "SELECT id, WIDTH_BUCKET(score, -10, 50, 3) FROM scores FILTER id = 5",
+    );
+    assert_eq!(rows[0][1], Value::Int(4));
+
+    execute_line(
+        &mut db,
+        "DATASET scores ADD COLUMN bucket = WIDTH_BUCKET(score, 0, 100, 2)",
+        1,
+    )
+    .expect("Computed column failed");
+    let ds = db.get_dataset("scores").unwrap();
+    assert_eq!(ds.rows[4].get("bucket"), Some(&Value::Int(2)));
+}
+
+#[test]
+fn test_window_functions_in_dataset_pipeline() {
+    let mut db = TensorDb::new();
+    setup_scores(&mut db);
+
+    execute_line(
+        &mut db,
+        "DATASET ranked FROM scores SELECT id, NTILE(2) OVER (ORDER BY score DESC)",
+        1,
+    )
+    .expect("Pipeline failed");
+
+    let ds = db.get_dataset("ranked").unwrap();
+    assert_eq!(ds.schema.fields[1].name, "NTILE(2)");
+    assert_eq!(ds.rows[4].values[1], Value::Int(1));
+}
+
+#[test]
+fn test_invalid_window_syntax() {
+    let mut db = TensorDb::new();
+    setup_scores(&mut db);
+
+    assert!(execute_line(
+        &mut db,
+        "SELECT NTILE(0) OVER (ORDER BY score) FROM scores",
+        1
+    )
+    .is_err());
+    assert!(execute_line(&mut db, "SELECT NTILE(2) OVER (score) FROM scores", 1).is_err());
+    assert!(execute_line(
+        &mut db,
+        "SELECT NTILE(2) OVER (ORDER BY missing) FROM scores",
+        1
+    )
+    .is_err());
+}
@@ -0,0 +1,118 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup_logs(db: &mut TensorDb) {
+    let script = r#"
+    DATASET logs COLUMNS (id: Int, line: String)
+    INSERT INTO logs VALUES (1, "GET https://example.com/users/42 200")
+    INSERT INTO logs VALUES (2, "POST http://api.test/orders 500")
+    INSERT INTO logs VALUES (3, "healthcheck ok")
+    "#;
+    execute_script(db, script).expect("Setup failed");
+}
+
+fn select_rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).expect("Query failed") {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_regexp_match_as_filter() {
+    let mut db = TensorDb::new();
+    setup_logs(&mut db);
+
+    let rows = select_rows(
+        &mut db,
+        r#"SELECT id FROM logs WHERE REGEXP_MATCH(line, "^(GET|POST) https?://")"#,
+    );
+    assert_eq!(rows, vec![vec![Value::Int(1)], vec![Value::Int(2)]]);
+
+    let rows = select_rows(
+        &mut db,
+        r#"SELECT id FROM logs FILTER REGEXP_MATCH(line, "\s5\d\d$")"#,
+    );
+    assert_eq!(rows, vec![vec![Value::Int(2)]]);
+}
+
+#[test]
+fn test_regexp_match_in_projection() {
+    let mut db = TensorDb::new();
+    setup_logs(&mut db);
+
+    let rows = select_rows(&mut db, r#"SELECT id, REGEXP_MATCH(line, "ok$") FROM logs"#);
+    let flags: Vec<Value> = rows.iter().map(|r| r[1].clone()).collect();
+    assert_eq!(
+        flags,
+        vec![Value::Bool(false), Value::Bool(false), Value::Bool(true)]
+    );
+}
+
+#[test]
+fn test_regexp_extract_groups() {
+    let mut db = TensorDb::new();
+    setup_logs(&mut db);
+
+    // Pattern contains '/', '+', '-' and parentheses, which must not be
+    // mistaken for operators or nesting
+    let rows = select_rows(
+        &mut db,
+        r#"SELECT id, REGEXP_EXTRACT(line, "https?://([a-z.-]+)/", 1) FROM logs"#,
+    );
+    let hosts: Vec<Value> = rows.iter().map(|r| r[1].clone()).collect();
+    assert_eq!(
+        hosts,
+        vec![
+            Value::String("example.com".to_string()),
+            Value::String("api.test".to_string()),
+            Value::Null,
+        ]
+    );
+
+    // Group 0 returns the whole match; a missing group yields NULL
+    let rows = select_rows(
+        &mut db,
+        r#"SELECT REGEXP_EXTRACT(line, "\d{3}$", 0), REGEXP_EXTRACT(line, "ok", 2) FROM logs"#,
+    );
+    assert_eq!(rows[0][0], Value::String("200".to_string()));
+    assert_eq!(rows[2][1], Value::Null);
+}
+
+#[test]
+fn test_regexp_extract_in_dataset_pipeline() {
+    let mut db = TensorDb::new();
+    setup_logs(&mut db);
+
+    execute_line(
+        &mut db,
+        r#"DATASET errors FROM logs FILTER REGEXP_MATCH(line, " 5\d\d$") SELECT id, REGEXP_EXTRACT(line, "^([A-Z]+)", 1)"#,
+        1,
+    )
+    .expect("Pipeline failed");
+
+    let ds = db.get_dataset("errors").unwrap();
+    assert_eq!(ds.rows.len(), 1);
+    assert_eq!(
+        ds.rows[0].get(r#"REGEXP_EXTRACT(line, "^([A-Z]+)", 1)"#),
+        Some(&Value::String("POST".to_string()))
+    );
+}
+
+#[test]
+fn test_regexp_invalid_usage() {
+    let mut db = TensorDb::new();
+    setup_logs(&mut db);
+
+    // Invalid pattern is reported at parse time
+    assert!(execute_line(&mut db, r#"SELECT REGEXP_MATCH(line, "(") FROM logs"#, 1).is_err());
+    // Wrong arity / non-integer group
+    assert!(execute_line(&mut db, r#"SELECT REGEXP_MATCH(line) FROM logs"#, 1).is_err());
+    assert!(execute_line(
+        &mut db,
+        r#"SELECT REGEXP_EXTRACT(line, "a", "b") FROM logs"#,
+        1
+    )
+    .is_err());
+}
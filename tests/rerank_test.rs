@@ -0,0 +1,121 @@
+use linal::core::config::{EngineConfig, RerankServiceConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script};
+use linal::engine::TensorDb;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+/// Minimal rerank service: scores each document by its length.
+/// Serves `requests` connections and then exits.
+fn spawn_length_scorer(requests: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/rerank", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for stream in listener.incoming().take(requests) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header == "\r\n" || header.is_empty() {
+                    break;
+                }
+                if let Some(v) = header.to_lowercase().strip_prefix("content-length:") {
+                    content_length = v.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(request["query"], "best pizza");
+            let scores: Vec<f32> = request["documents"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|d| d.as_str().unwrap().len() as f32)
+                .collect();
+
+            let response = serde_json::json!({ "scores": scores }).to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+        }
+    });
+
+    url
+}
+
+fn setup_db(url: &str) -> TensorDb {
+    let mut config = EngineConfig::default();
+    config.rerank.services.insert(
+        "cross-encoder".to_string(),
+        RerankServiceConfig {
+            url: url.to_string(),
+            timeout_ms: 5_000,
+        },
+    );
+    let mut db = TensorDb::with_config(config);
+
+    let script = r#"
+    DATASET docs COLUMNS (id: Int, body: String, embedding: Vector(2))
+    CREATE VECTOR INDEX emb_idx ON docs(embedding)
+    INSERT INTO docs VALUES (1, "pizza", [1.0, 0.0])
+    INSERT INTO docs VALUES (2, "pizza margherita napoletana", [0.9, 0.1])
+    INSERT INTO docs VALUES (3, "pizza slice", [0.8, 0.2])
+    INSERT INTO docs VALUES (4, "sushi", [0.0, 1.0])
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+#[test]
+fn test_search_rerank_reorders_candidates() {
+    let url = spawn_length_scorer(1);
+    let mut db = setup_db(&url);
+
+    execute_line(
+        &mut db,
+        r#"SEARCH top FROM docs QUERY [1.0, 0.0] ON embedding K=3 RERANK USING SERVICE "cross-encoder" ON body QUERY "best pizza""#,
+        1,
+    )
+    .unwrap();
+
+    let ds = db.get_dataset("top").unwrap();
+    let ids: Vec<Value> = ds
+        .rows
+        .iter()
+        .map(|r| r.get("id").unwrap().clone())
+        .collect();
+    // Vector order was 1, 2, 3; the service prefers longer bodies
+    assert_eq!(ids, vec![Value::Int(2), Value::Int(3), Value::Int(1)]);
+    assert_eq!(ds.rows[0].get("rerank_score"), Some(&Value::Float(27.0)));
+}
+
+#[test]
+fn test_rerank_unknown_service_and_bad_syntax() {
+    let mut db = setup_db("http://127.0.0.1:9/unused");
+
+    let err = execute_line(
+        &mut db,
+        r#"SEARCH docs WHERE embedding ~= [1.0, 0.0] LIMIT 2 RERANK USING SERVICE "missing" ON body QUERY "best pizza""#,
+        1,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Unknown rerank service"));
+
+    assert!(execute_line(
+        &mut db,
+        r#"SEARCH docs WHERE embedding ~= [1.0, 0.0] LIMIT 2 RERANK USING "cross-encoder""#,
+        1,
+    )
+    .is_err());
+}
@@ -0,0 +1,102 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use linal::server::start_server;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+const SETUP: &str = r#"
+DATASET docs COLUMNS (id: Int, text: String, score: Float, emb: Vector(3))
+INSERT INTO docs VALUES (1, "alpha", 0.123456, [1.0, 0.0, 0.0])
+INSERT INTO docs VALUES (2, "beta", 0.654321, [0.0, 1.0, 0.0])
+CREATE VECTOR INDEX emb_idx ON docs(emb)
+"#;
+
+#[test]
+fn test_search_returning_columns() {
+    let mut db = TensorDb::new();
+    execute_script(&mut db, SETUP).unwrap();
+
+    let out = execute_line(
+        &mut db,
+        "SEARCH docs WHERE emb ~= [1.0, 0.1, 0.0] LIMIT 1 RETURNING (text, id)",
+        1,
+    )
+    .unwrap();
+    match out {
+        DslOutput::Table(ds) => {
+            let names: Vec<&str> = ds.schema.fields.iter().map(|f| f.name.as_str()).collect();
+            assert_eq!(names, vec!["text", "id"]);
+            assert_eq!(
+                ds.rows[0].values,
+                vec![Value::String("alpha".into()), Value::Int(1)]
+            );
+        }
+        other => panic!("Expected table output, got {:?}", other),
+    }
+
+    // The hits are still stored in the target dataset
+    assert_eq!(db.get_dataset("search_results").unwrap().rows.len(), 1);
+
+    assert!(execute_line(
+        &mut db,
+        "SEARCH docs WHERE emb ~= [1.0, 0.0, 0.0] LIMIT 1 RETURNING (nope)",
+        2
+    )
+    .is_err());
+    assert!(execute_line(
+        &mut db,
+        "SEARCH docs WHERE emb ~= [1.0, 0.0, 0.0] LIMIT 1 RETURNING id",
+        3
+    )
+    .is_err());
+}
+
+#[tokio::test]
+async fn test_response_shaping_params() {
+    let db = Arc::new(RwLock::new(TensorDb::new()));
+    execute_script(&mut db.write().unwrap(), SETUP).unwrap();
+    let port = 8113;
+    let db_clone = db.clone();
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    let query = |params: &'static str| {
+        let client = client.clone();
+        async move {
+            let resp = client
+                .post(format!(
+                    "http://localhost:{}/execute?format=json{}",
+                    port, params
+                ))
+                .header("Content-Type", "text/plain")
+                .body("SELECT * FROM docs WHERE id = 1")
+                .send()
+                .await
+                .unwrap();
+            let status = resp.status();
+            (status, resp.json::<serde_json::Value>().await.unwrap())
+        }
+    };
+
+    // Vector columns are left out by default
+    let (_, body) = query("").await;
+    let row = &body["result"]["Table"]["rows"][0]["values"];
+    assert_eq!(row.as_array().unwrap().len(), 3, "{}", body);
+
+    let (_, body) = query("&vectors=full").await;
+    let row = &body["result"]["Table"]["rows"][0]["values"];
+    assert_eq!(row[3]["Vector"], serde_json::json!([1.0, 0.0, 0.0]));
+
+    let (_, body) = query("&vectors=truncate&vector_len=1&precision=2").await;
+    let row = &body["result"]["Table"]["rows"][0]["values"];
+    assert_eq!(row[2]["Float"], serde_json::json!(0.12));
+    assert_eq!(row[3]["Vector"], serde_json::json!([1.0]));
+
+    let (status, body) = query("&vectors=some").await;
+    assert_eq!(status, 400);
+    assert_eq!(body["status"], "error");
+}
@@ -0,0 +1,111 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET sales COLUMNS (region: String, category: String, units: Int)
+    INSERT INTO sales VALUES ("eu", "books", 5), ("eu", "games", 3), ("us", "books", 7), ("eu", "books", 1), ("us", "toys", 2)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows.iter().map(|r| r.values.clone()).collect(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn s(text: &str) -> Value {
+    Value::String(text.to_string())
+}
+
+#[test]
+fn test_rollup_single_key_adds_grand_total() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT category, SUM(units) AS units FROM sales GROUP BY category WITH ROLLUP ORDER BY category",
+    );
+    assert_eq!(
+        result,
+        vec![
+            // NULLs sort first: the grand total
+            vec![Value::Null, Value::Int(18)],
+            vec![s("books"), Value::Int(13)],
+            vec![s("games"), Value::Int(3)],
+            vec![s("toys"), Value::Int(2)],
+        ]
+    );
+}
+
+#[test]
+fn test_rollup_two_keys_adds_subtotals() {
+    let mut db = setup();
+    let mut result = rows(
+        &mut db,
+        "SELECT region, category, SUM(units), COUNT(*) FROM sales GROUP BY ROLLUP(region, category)",
+    );
+    result.sort_by(|a, b| {
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| x.total_cmp(y))
+            .find(|o| o.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    assert_eq!(
+        result,
+        vec![
+            vec![Value::Null, Value::Null, Value::Int(18), Value::Int(5)],
+            vec![s("eu"), Value::Null, Value::Int(9), Value::Int(3)],
+            vec![s("eu"), s("books"), Value::Int(6), Value::Int(2)],
+            vec![s("eu"), s("games"), Value::Int(3), Value::Int(1)],
+            vec![s("us"), Value::Null, Value::Int(9), Value::Int(2)],
+            vec![s("us"), s("books"), Value::Int(7), Value::Int(1)],
+            vec![s("us"), s("toys"), Value::Int(2), Value::Int(1)],
+        ]
+    );
+}
+
+#[test]
+fn test_rollup_with_having_and_dataset_target() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT region, SUM(units) AS units FROM sales GROUP BY region WITH ROLLUP HAVING units > 9",
+    );
+    assert_eq!(result, vec![vec![Value::Null, Value::Int(18)]]);
+
+    execute_line(
+        &mut db,
+        "DATASET totals FROM sales GROUP BY region WITH ROLLUP SELECT region, SUM(units)",
+        1,
+    )
+    .unwrap();
+    assert_eq!(db.get_dataset("totals").unwrap().rows.len(), 3);
+}
+
+#[test]
+fn test_sql_rollup() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SQL SELECT region, SUM(units) FROM sales GROUP BY region WITH ROLLUP ORDER BY region",
+    );
+    assert_eq!(
+        result,
+        vec![
+            vec![Value::Null, Value::Int(18)],
+            vec![s("eu"), Value::Int(9)],
+            vec![s("us"), Value::Int(9)],
+        ]
+    );
+    let result = rows(
+        &mut db,
+        "SQL SELECT region, category, SUM(units) FROM sales GROUP BY ROLLUP(region, category)",
+    );
+    assert_eq!(result.len(), 7);
+}
@@ -0,0 +1,128 @@
+use linal::core::config::{EngineConfig, ExecutionConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use linal::utils::rng::Rng;
+use linal::Sample;
+
+const EVENTS: i64 = 1000;
+
+fn events_db(seed: u64) -> TensorDb {
+    let config = EngineConfig {
+        execution: ExecutionConfig {
+            deterministic: true,
+            seed,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+    let values: Vec<String> = (0..EVENTS)
+        .map(|id| {
+            format!(
+                "({}, \"{}\")",
+                id,
+                if id % 2 == 0 { "click" } else { "view" }
+            )
+        })
+        .collect();
+    let script = format!(
+        "DATASET events COLUMNS (id: Int, kind: String)\nINSERT INTO events VALUES {}",
+        values.join(", ")
+    );
+    execute_script(&mut db, &script).unwrap();
+    db
+}
+
+fn ids(db: &mut TensorDb, query: &str) -> Vec<i64> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds
+            .rows
+            .iter()
+            .map(|r| match r.values[0] {
+                Value::Int(id) => id,
+                ref other => panic!("Expected an id, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn is_increasing(ids: &[i64]) -> bool {
+    ids.windows(2).all(|w| w[0] < w[1])
+}
+
+#[test]
+fn test_sample_rows_and_percent() {
+    let mut db = events_db(7);
+
+    // Exactly n rows, in dataset order
+    let sampled = ids(&mut db, "SELECT id FROM events SAMPLE 25 ROWS");
+    assert_eq!(sampled.len(), 25);
+    assert!(is_increasing(&sampled));
+    assert_ne!(sampled, (0..25).collect::<Vec<_>>());
+    assert_eq!(
+        ids(&mut db, "SELECT * FROM events SAMPLE 5000 ROWS").len(),
+        1000
+    );
+
+    // Each row kept independently
+    let sampled = ids(&mut db, "SELECT * FROM events SAMPLE 10 PERCENT");
+    assert!((50..=150).contains(&sampled.len()), "{}", sampled.len());
+    assert!(is_increasing(&sampled));
+    assert!(ids(&mut db, "SELECT id FROM events SAMPLE 0 PERCENT").is_empty());
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM events SAMPLE 100 PERCENT").len(),
+        1000
+    );
+
+    // The sample is drawn from the source rows, before conditions and LIMIT
+    let sampled = ids(
+        &mut db,
+        r#"SELECT id FROM events WHERE kind = "click" SAMPLE 40 ROWS LIMIT 100"#,
+    );
+    assert!(sampled.len() < 40);
+    assert!(sampled.iter().all(|id| id % 2 == 0));
+
+    // DATASET ... FROM accepts the same clause
+    execute_line(&mut db, "DATASET preview FROM events SAMPLE 12 ROWS", 1).unwrap();
+    assert_eq!(db.get_dataset("preview").unwrap().len(), 12);
+}
+
+#[test]
+fn test_sample_is_reproducible_in_deterministic_mode() {
+    let query = "SELECT id FROM events SAMPLE 3 PERCENT";
+    let first = ids(&mut events_db(42), query);
+    assert_eq!(first, ids(&mut events_db(42), query));
+    assert_ne!(first, ids(&mut events_db(43), query));
+}
+
+#[test]
+fn test_dataset_sample_api() {
+    let db = events_db(0);
+    let events = db.get_dataset("events").unwrap();
+
+    let mut rng = Rng::new(1);
+    let sample = events.sample(Sample::Rows(10), &mut rng);
+    assert_eq!(sample.len(), 10);
+    assert_eq!(sample.schema, events.schema);
+    assert_eq!(sample.metadata.column_stats["id"].null_count, 0);
+
+    let sample = events.sample(Sample::Percent(50.0), &mut rng);
+    assert!((400..=600).contains(&sample.len()));
+}
+
+#[test]
+fn test_invalid_sample_clauses() {
+    let mut db = events_db(0);
+    for query in [
+        "SELECT id FROM events SAMPLE 150 PERCENT",
+        "SELECT id FROM events SAMPLE 10",
+        "SELECT id FROM events SAMPLE ten ROWS",
+        "SELECT id FROM events SAMPLE 2.5 ROWS",
+        "SELECT id FROM events SAMPLE 5 ROWS SAMPLE 5 ROWS",
+    ] {
+        let err = execute_line(&mut db, query, 1).unwrap_err();
+        assert!(err.to_string().contains("SAMPLE"), "{}: {}", query, err);
+    }
+}
@@ -0,0 +1,108 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET people COLUMNS (id: Int, name: String, score: Float)
+    INSERT INTO people VALUES (1, "Alice", -2.5), (2, "bob", 16.0), (3, "Ñandú", 0.25)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows.iter().map(|r| r.values.clone()).collect(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+#[test]
+fn test_string_functions_in_select() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT UPPER(name), lower(name), LENGTH(name), SUBSTR(name, 2, 3), CONCAT(name, \"#\", id) FROM people WHERE id = 3",
+    );
+    assert_eq!(
+        result,
+        vec![vec![
+            string("ÑANDÚ"),
+            string("ñandú"),
+            Value::Int(5),
+            string("and"),
+            string("Ñandú#3"),
+        ]]
+    );
+
+    let result = rows(
+        &mut db,
+        "SELECT SUBSTR(name, 0, 2), SUBSTR(name, 4) FROM people WHERE id = 1",
+    );
+    assert_eq!(result, vec![vec![string("A"), string("ce")]]);
+}
+
+#[test]
+fn test_math_functions_in_select() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT ABS(score), ROUND(score), FLOOR(score), SQRT(score), ROUND(score * 3.14159, 2), ABS(id - 5) FROM people WHERE id = 1",
+    );
+    assert_eq!(
+        result,
+        vec![vec![
+            Value::Float(2.5),
+            Value::Float(-3.0),
+            Value::Float(-3.0),
+            Value::Null,
+            Value::Float(-7.85),
+            Value::Int(4),
+        ]]
+    );
+
+    let result = rows(
+        &mut db,
+        "SELECT SQRT(score), LOG(EXP(1.0)), ROUND(id * 14, -1) FROM people WHERE id = 2",
+    );
+    assert_eq!(result[0][0], Value::Float(4.0));
+    match result[0][1] {
+        Value::Float(x) => assert!((x - 1.0).abs() < 1e-6),
+        ref other => panic!("expected a float, got {:?}", other),
+    }
+    assert_eq!(result[0][2], Value::Int(30));
+}
+
+#[test]
+fn test_functions_in_filters_and_computed_columns() {
+    let mut db = setup();
+    let result = rows(&mut db, "SELECT id FROM people WHERE UPPER(name) = \"BOB\"");
+    assert_eq!(result, vec![vec![Value::Int(2)]]);
+
+    execute_script(
+        &mut db,
+        "DATASET short FROM people FILTER LENGTH(name) <= 3\n\
+         DATASET people ADD COLUMN initial = UPPER(SUBSTR(name, 1, 1))",
+    )
+    .unwrap();
+    assert_eq!(db.get_dataset("short").unwrap().rows.len(), 1);
+    let result = rows(&mut db, "SELECT initial FROM people ORDER BY id");
+    assert_eq!(
+        result,
+        vec![vec![string("A")], vec![string("B")], vec![string("Ñ")]]
+    );
+}
+
+#[test]
+fn test_wrong_arity_is_a_parse_error() {
+    let mut db = setup();
+    let err = execute_line(&mut db, "SELECT SUBSTR(name) FROM people", 1).unwrap_err();
+    assert!(err.to_string().contains("SUBSTR(string, start"), "{}", err);
+    assert!(execute_line(&mut db, "SELECT ABS(score, 1) FROM people", 1).is_err());
+}
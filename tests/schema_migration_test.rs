@@ -0,0 +1,99 @@
+// tests/schema_migration_test.rs
+//
+// Tests for DROP COLUMN, RENAME COLUMN and ALTER COLUMN ... TYPE
+
+use linal::core::value::{Value, ValueType};
+use linal::dsl::{execute_line, DslOutput};
+use linal::{execute_script, TensorDb};
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+        DATASET users COLUMNS (id: INT, name: STRING, age: INT, code: STRING)
+        INSERT INTO users VALUES (1, "Alice", 30, "10")
+        INSERT INTO users VALUES (2, "Bob", 25, "20")
+        CREATE INDEX age_idx ON users(age)
+    "#;
+    execute_script(&mut db, script).expect("Setup failed");
+    db
+}
+
+#[test]
+fn test_drop_column() {
+    let mut db = setup();
+
+    let output = execute_line(&mut db, "DATASET users DROP COLUMN age", 1).unwrap();
+    match output {
+        DslOutput::Message(msg) => assert!(msg.contains("Dropped column 'age'")),
+        _ => panic!("Expected Message output"),
+    }
+
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(users.schema.len(), 3);
+    assert!(users.schema.get_field("age").is_none());
+    assert!(!users.indices.contains_key("age"));
+    assert_eq!(
+        users.rows[1].values,
+        vec![
+            Value::Int(2),
+            Value::String("Bob".into()),
+            Value::String("20".into())
+        ]
+    );
+
+    assert!(execute_line(&mut db, "DATASET users DROP COLUMN age", 2).is_err());
+}
+
+#[test]
+fn test_rename_column_keeps_index() {
+    let mut db = setup();
+
+    execute_line(&mut db, "DATASET users RENAME COLUMN age TO years", 1).unwrap();
+    let users = db.get_dataset("users").unwrap();
+    assert!(users.schema.get_field("age").is_none());
+    assert!(users.indices.contains_key("years"));
+    assert_eq!(users.rows[0].get("years"), Some(&Value::Int(30)));
+
+    let output = execute_line(&mut db, "SELECT name FROM users WHERE years = 25", 2).unwrap();
+    match output {
+        DslOutput::Table(ds) => {
+            assert_eq!(ds.rows.len(), 1);
+            assert_eq!(ds.rows[0].values[0], Value::String("Bob".into()));
+        }
+        _ => panic!("Expected Table output"),
+    }
+
+    // Target name must be free
+    assert!(execute_line(&mut db, "DATASET users RENAME COLUMN years TO name", 3).is_err());
+}
+
+#[test]
+fn test_alter_column_type_casts_values() {
+    let mut db = setup();
+
+    execute_line(&mut db, "DATASET users ALTER COLUMN age TYPE FLOAT", 1).unwrap();
+    execute_line(&mut db, "DATASET users ALTER COLUMN code TYPE INT", 2).unwrap();
+
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(
+        users.schema.get_field("age").unwrap().value_type,
+        ValueType::Float
+    );
+    assert_eq!(users.rows[0].get("age"), Some(&Value::Float(30.0)));
+    assert_eq!(users.rows[1].get("code"), Some(&Value::Int(20)));
+
+    // The index is rebuilt with the cast values
+    let output = execute_line(&mut db, "SELECT id FROM users WHERE age = 25.0", 3).unwrap();
+    match output {
+        DslOutput::Table(ds) => assert_eq!(ds.rows.len(), 1),
+        _ => panic!("Expected Table output"),
+    }
+
+    // A failed cast leaves the column untouched
+    let err = execute_line(&mut db, "DATASET users ALTER COLUMN name TYPE INT", 4).unwrap_err();
+    assert!(err.to_string().contains("Cannot cast"), "{}", err);
+    assert_eq!(
+        db.get_dataset("users").unwrap().rows[0].get("name"),
+        Some(&Value::String("Alice".into()))
+    );
+}
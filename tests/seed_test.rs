@@ -0,0 +1,107 @@
+use linal::core::config::{EngineConfig, SeedConfig, StorageConfig};
+use linal::dsl::{execute_line, execute_script};
+use linal::engine::seed::seed_empty_data_dir;
+use linal::engine::TensorDb;
+use std::fs;
+use std::path::PathBuf;
+
+/// Reference data saved with SAVE ALL and a script building on it
+fn write_seed_files(root: &str) -> SeedConfig {
+    let _ = fs::remove_dir_all(root);
+    fs::create_dir_all(root).unwrap();
+
+    let mut source = TensorDb::with_config(EngineConfig::default());
+    let script = format!(
+        r#"
+        DATASET countries COLUMNS (code: String, name: String)
+        INSERT INTO countries VALUES ("ES", "Spain"), ("AR", "Argentina")
+        VECTOR weights = [0.5, 0.25]
+        SAVE ALL TO "{}/reference"
+        "#,
+        root
+    );
+    execute_script(&mut source, &script).unwrap();
+
+    let script_path = format!("{}/schema.lnl", root);
+    fs::write(
+        &script_path,
+        "DATASET users COLUMNS (id: Int, country: String)\n\
+         # reference data is loaded before scripts run\n\
+         CREATE INDEX code_idx ON countries(code)\n\
+         USE default\n",
+    )
+    .unwrap();
+
+    SeedConfig {
+        parquet: vec![PathBuf::from(format!("{}/reference", root))],
+        scripts: vec![PathBuf::from(script_path)],
+    }
+}
+
+fn config(data_dir: &str, seed: SeedConfig) -> EngineConfig {
+    EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(data_dir),
+            default_db: "default".to_string(),
+            auto_persist: true,
+            ..Default::default()
+        },
+        seed,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_seed_runs_on_first_boot_only() {
+    let root = "/tmp/linal_test_seed";
+    let seed = write_seed_files(root);
+    let data_dir = format!("{}/data", root);
+
+    {
+        let mut db = TensorDb::with_config(config(&data_dir, seed.clone()));
+        assert!(seed_empty_data_dir(&mut db).unwrap());
+        assert_eq!(db.get_dataset("countries").unwrap().len(), 2);
+        assert_eq!(*db.get("weights").unwrap().data, vec![0.5, 0.25]);
+        assert!(db.get_dataset("users").is_ok());
+        assert_eq!(db.active_database(), "default");
+        execute_line(&mut db, "INSERT INTO users VALUES (1, \"ES\")", 1).unwrap();
+    }
+
+    // Persisted data is recovered and not seeded over
+    let mut db = TensorDb::with_config(config(&data_dir, seed));
+    assert!(!seed_empty_data_dir(&mut db).unwrap());
+    assert_eq!(db.get_dataset("users").unwrap().len(), 1);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_seed_errors_name_their_source() {
+    let root = "/tmp/linal_test_seed_errors";
+    let mut seed = write_seed_files(root);
+    let data_dir = format!("{}/data", root);
+
+    let bad_script = format!("{}/bad.lnl", root);
+    fs::write(
+        &bad_script,
+        "DATASET t COLUMNS (id: Int)\nFETCH everything\n",
+    )
+    .unwrap();
+    seed.scripts = vec![PathBuf::from(&bad_script)];
+    let mut db = TensorDb::with_config(config(&data_dir, seed.clone()));
+    let err = seed_empty_data_dir(&mut db).unwrap_err().to_string();
+    assert!(err.contains("bad.lnl") && err.contains("line 2"), "{}", err);
+
+    seed.scripts.clear();
+    seed.parquet = vec![PathBuf::from(format!("{}/missing", root))];
+    let _ = fs::remove_dir_all(&data_dir);
+    let mut db = TensorDb::with_config(config(&data_dir, seed));
+    let err = seed_empty_data_dir(&mut db).unwrap_err().to_string();
+    assert!(err.contains("missing"), "{}", err);
+
+    // Nothing configured: nothing to do
+    let mut db = TensorDb::with_config(config(&data_dir, SeedConfig::default()));
+    assert!(!seed_empty_data_dir(&mut db).unwrap());
+
+    fs::remove_dir_all(root).unwrap();
+}
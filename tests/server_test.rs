@@ -1,5 +1,5 @@
 use axum::http::StatusCode;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tokio::time::sleep;
 use linal::engine::TensorDb;
@@ -8,7 +8,7 @@ use linal::server::start_server;
 #[tokio::test]
 async fn test_toon_server_output() {
     // 1. Setup DB and start server in background
-    let db = Arc::new(Mutex::new(TensorDb::new()));
+    let db = Arc::new(RwLock::new(TensorDb::new()));
     let port = 8095; // Use valid test port
     let db_clone = db.clone();
 
@@ -55,7 +55,7 @@ async fn test_toon_server_output() {
 #[tokio::test]
 async fn test_toon_dsl_output() {
     // 1. Setup DB and start server
-    let db = Arc::new(Mutex::new(TensorDb::new()));
+    let db = Arc::new(RwLock::new(TensorDb::new()));
     let port = 8096;
     let db_clone = db.clone();
 
@@ -108,7 +108,7 @@ async fn test_toon_dsl_output() {
 #[tokio::test]
 async fn test_json_backward_compatibility() {
     // Test that JSON format still works (with deprecation warning)
-    let db = Arc::new(Mutex::new(TensorDb::new()));
+    let db = Arc::new(RwLock::new(TensorDb::new()));
     let port = 8097;
     let db_clone = db.clone();
 
@@ -141,7 +141,7 @@ async fn test_json_backward_compatibility() {
 #[tokio::test]
 async fn test_json_format_response() {
     // Test JSON format via query parameter
-    let db = Arc::new(Mutex::new(TensorDb::new()));
+    let db = Arc::new(RwLock::new(TensorDb::new()));
     let port = 8098;
     let db_clone = db.clone();
 
@@ -185,7 +185,7 @@ async fn test_json_format_response() {
 #[tokio::test]
 async fn test_toon_format_explicit() {
     // Test explicit TOON format via query parameter
-    let db = Arc::new(Mutex::new(TensorDb::new()));
+    let db = Arc::new(RwLock::new(TensorDb::new()));
     let port = 8099;
     let db_clone = db.clone();
 
@@ -227,7 +227,7 @@ async fn test_toon_format_explicit() {
 #[tokio::test]
 async fn test_invalid_format_defaults_to_toon() {
     // Test that invalid format defaults to TOON
-    let db = Arc::new(Mutex::new(TensorDb::new()));
+    let db = Arc::new(RwLock::new(TensorDb::new()));
     let port = 8100;
     let db_clone = db.clone();
 
@@ -264,7 +264,7 @@ async fn test_invalid_format_defaults_to_toon() {
 }
 #[tokio::test]
 async fn test_server_validation_empty() {
-    let db = Arc::new(Mutex::new(TensorDb::new()));
+    let db = Arc::new(RwLock::new(TensorDb::new()));
     let port = 8101;
     let db_clone = db.clone();
 
@@ -290,7 +290,7 @@ async fn test_server_validation_empty() {
 
 #[tokio::test]
 async fn test_server_validation_length() {
-    let db = Arc::new(Mutex::new(TensorDb::new()));
+    let db = Arc::new(RwLock::new(TensorDb::new()));
     let port = 8102;
     let db_clone = db.clone();
 
@@ -314,3 +314,589 @@ async fn test_server_validation_length() {
     let body = resp.text().await.unwrap();
     assert!(body.contains("Command too long"));
 }
+
+#[tokio::test]
+async fn test_execution_metadata_in_response() {
+    let db = Arc::new(RwLock::new(TensorDb::new()));
+    let port = 8112;
+    let db_clone = db.clone();
+
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+
+    sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    let url = format!("http://localhost:{}/execute?format=json", port);
+    let execute = |command: &'static str| {
+        let client = client.clone();
+        let url = url.clone();
+        async move {
+            let resp = client
+                .post(url)
+                .header("Content-Type", "text/plain")
+                .body(command)
+                .send()
+                .await
+                .expect("Failed to send request");
+            resp.json::<serde_json::Value>().await.unwrap()
+        }
+    };
+
+    for command in [
+        "DATASET meta_items COLUMNS (id: Int, category: String)",
+        "INSERT INTO meta_items VALUES (1, \"a\")",
+        "INSERT INTO meta_items VALUES (2, \"b\")",
+        "INSERT INTO meta_items VALUES (3, \"a\")",
+        "CREATE INDEX meta_cat_idx ON meta_items(category)",
+    ] {
+        execute(command).await;
+    }
+
+    // Full scan: every row is read, one is returned
+    let body = execute("SELECT id FROM meta_items WHERE id = 2").await;
+    let metadata = &body["metadata"];
+    assert_eq!(metadata["rows_scanned"], 3, "{}", body);
+    assert_eq!(metadata["rows_returned"], 1);
+    assert_eq!(metadata["index_used"], false);
+    assert_eq!(metadata["cache_hit"], false);
+    assert!(metadata["elapsed_ms"].as_f64().unwrap() >= 0.0);
+
+    // Index lookup only touches the matching rows
+    let body = execute("SELECT id FROM meta_items WHERE category = \"a\"").await;
+    let metadata = &body["metadata"];
+    assert_eq!(metadata["rows_scanned"], 2, "{}", body);
+    assert_eq!(metadata["rows_returned"], 2);
+    assert_eq!(metadata["index_used"], true);
+
+    // Errors still report timing
+    let body = execute("SELECT id FROM missing_dataset").await;
+    assert_eq!(body["status"], "error");
+    assert_eq!(body["metadata"]["rows_returned"], 0);
+}
+
+#[tokio::test]
+async fn test_affected_rows_in_response() {
+    let db = Arc::new(RwLock::new(TensorDb::new()));
+    let port = 8114;
+    let db_clone = db.clone();
+
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+
+    sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    let execute = |query: &'static str, command: &'static str| {
+        let client = client.clone();
+        async move {
+            let resp = client
+                .post(format!("http://localhost:{}/execute?{}", port, query))
+                .header("Content-Type", "text/plain")
+                .body(command)
+                .send()
+                .await
+                .expect("Failed to send request");
+            resp.text().await.unwrap()
+        }
+    };
+
+    execute("format=json", "DATASET affected_items COLUMNS (id: Int)").await;
+
+    let body = execute("format=json", "INSERT INTO affected_items VALUES (1)").await;
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        json["result"]["Affected"],
+        serde_json::json!({"rows": 1, "op": "INSERT"}),
+        "{}",
+        body
+    );
+
+    let body = execute(
+        "format=json&lang=sql",
+        "INSERT INTO affected_items VALUES (2), (3)",
+    )
+    .await;
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["result"]["Affected"]["rows"], 2, "{}", body);
+
+    // TOON output carries the same fields
+    let body = execute("", "INSERT INTO affected_items VALUES (4)").await;
+    assert!(body.contains("Affected"), "{}", body);
+    assert!(body.contains("INSERT"), "{}", body);
+}
+
+#[tokio::test]
+async fn test_database_selected_per_request() {
+    let db = Arc::new(RwLock::new(TensorDb::new()));
+    let port = 8116;
+    let db_clone = db.clone();
+
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+
+    sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    let execute = |path: &'static str, database: Option<&'static str>, command: &'static str| {
+        let client = client.clone();
+        async move {
+            let mut request = client
+                .post(format!("http://localhost:{}{}", port, path))
+                .header("Content-Type", "text/plain")
+                .body(command);
+            if let Some(name) = database {
+                request = request.header("X-Linal-Database", name);
+            }
+            let body = request.send().await.unwrap().text().await.unwrap();
+            serde_json::from_str::<serde_json::Value>(&body).unwrap()
+        }
+    };
+
+    execute("/execute?format=json", None, "CREATE DATABASE tenant_a").await;
+    let resp = execute(
+        "/execute?format=json",
+        Some("tenant_a"),
+        "DATASET events COLUMNS (id: Int)",
+    )
+    .await;
+    assert_eq!(resp["status"], "ok", "{}", resp);
+    let resp = execute(
+        "/datasets/events/rows?format=json",
+        Some("tenant_a"),
+        "1\n2\n",
+    )
+    .await;
+    assert_eq!(resp["result"]["Affected"]["rows"], 2, "{}", resp);
+
+    // The dataset lives in tenant_a only; the query parameter also selects it
+    let resp = execute("/execute?format=json", None, "SELECT * FROM events").await;
+    assert_eq!(resp["status"], "error", "{}", resp);
+    let resp = execute(
+        "/execute?format=json&db=tenant_a",
+        None,
+        "SELECT * FROM events",
+    )
+    .await;
+    assert_eq!(resp["status"], "ok", "{}", resp);
+
+    // USE DATABASE inside a scoped request does not leak to other clients
+    execute("/execute?format=json", Some("tenant_a"), "USE DATABASE tenant_a").await;
+    assert_eq!(db.read().unwrap().active_database(), "default");
+
+    let resp = execute("/execute?format=json", Some("missing"), "SHOW ALL").await;
+    assert!(
+        resp["error"].as_str().unwrap().contains("Database 'missing' not found"),
+        "{}",
+        resp
+    );
+}
+
+#[tokio::test]
+async fn test_concurrent_requests_on_different_databases() {
+    let db = Arc::new(RwLock::new(TensorDb::new()));
+    let port = 8117;
+    let db_clone = db.clone();
+
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+
+    sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    let execute = |database: String, command: String| {
+        let client = client.clone();
+        async move {
+            let body = client
+                .post(format!("http://localhost:{}/execute?format=json&db={}", port, database))
+                .header("Content-Type", "text/plain")
+                .body(command)
+                .send()
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap();
+            serde_json::from_str::<serde_json::Value>(&body).unwrap()
+        }
+    };
+
+    for name in ["shard_a", "shard_b"] {
+        execute("default".into(), format!("CREATE DATABASE {}", name)).await;
+        let resp = execute(name.into(), "DATASET events COLUMNS (id: Int)".into()).await;
+        assert_eq!(resp["status"], "ok", "{}", resp);
+    }
+
+    // Inserts on both databases interleave with catalog commands
+    let mut requests = Vec::new();
+    for i in 0..20 {
+        let database = if i % 2 == 0 { "shard_a" } else { "shard_b" };
+        requests.push(tokio::spawn(execute(
+            database.into(),
+            format!("INSERT INTO events VALUES ({})", i),
+        )));
+        if i % 5 == 0 {
+            requests.push(tokio::spawn(execute(
+                "default".into(),
+                "SHOW DATABASES".into(),
+            )));
+        }
+    }
+    for request in requests {
+        let resp = request.await.unwrap();
+        assert_eq!(resp["status"], "ok", "{}", resp);
+    }
+
+    for name in ["shard_a", "shard_b"] {
+        let resp = execute(name.into(), "SELECT COUNT(*) FROM events".into()).await;
+        let count = &resp["result"]["Table"]["rows"][0]["values"][0];
+        assert_eq!(count.to_string(), r#"{"Int":10}"#, "{}", resp);
+    }
+    let db = db.read().unwrap();
+    assert_eq!(db.active_database(), "default");
+    assert!(db.list_databases().contains(&"shard_b".to_string()));
+}
+
+/// Unpack a ustar archive into `dir`, returning the file names
+fn untar(archive: &[u8], dir: &std::path::Path) -> Vec<String> {
+    let field = |b: &[u8]| String::from_utf8_lossy(b).trim_end_matches('\0').to_string();
+    let mut names = Vec::new();
+    let mut pos = 0;
+    while pos + 512 <= archive.len() && archive[pos..pos + 512].iter().any(|&b| b != 0) {
+        let header = &archive[pos..pos + 512];
+        let prefix = field(&header[345..500]);
+        let name = match prefix.as_str() {
+            "" => field(&header[0..100]),
+            prefix => format!("{}/{}", prefix, field(&header[0..100])),
+        };
+        let size = usize::from_str_radix(field(&header[124..135]).trim(), 8).unwrap();
+        let path = dir.join(&name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &archive[pos + 512..pos + 512 + size]).unwrap();
+        names.push(name);
+        pos += 512 + size.div_ceil(512) * 512;
+    }
+    assert_eq!(archive.len(), pos + 1024, "archive must end with two zero blocks");
+    names
+}
+
+#[tokio::test]
+async fn test_snapshot_export_endpoint() {
+    let db = Arc::new(RwLock::new(TensorDb::new()));
+    let port = 8118;
+    let db_clone = db.clone();
+
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+
+    sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    for command in [
+        "CREATE DATABASE reporting",
+        "USE reporting",
+        "DATASET sales COLUMNS (id: Int, amount: Float)",
+        "INSERT INTO sales VALUES (1, 2.5), (2, 4.0)",
+        "VECTOR weights = [1, 2, 3]",
+        "USE default",
+    ] {
+        let resp = client
+            .post(format!("http://localhost:{}/execute?format=json", port))
+            .header("Content-Type", "text/plain")
+            .body(command)
+            .send()
+            .await
+            .unwrap();
+        assert!(resp.text().await.unwrap().contains("\"ok\""), "{}", command);
+    }
+
+    let resp = client
+        .get(format!("http://localhost:{}/admin/snapshot", port))
+        .header("X-Linal-Database", "reporting")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["content-type"], "application/x-tar");
+    assert!(resp.headers()["content-disposition"]
+        .to_str()
+        .unwrap()
+        .contains("reporting.tar"));
+    let archive = resp.bytes().await.unwrap();
+
+    let dir = std::env::temp_dir().join(format!("linal_snapshot_export_{}", port));
+    let _ = std::fs::remove_dir_all(&dir);
+    let names = untar(&archive, &dir);
+    assert!(names.contains(&"datasets/sales.parquet".to_string()), "{:?}", names);
+    assert!(names.contains(&"tensors/weights.npy".to_string()), "{:?}", names);
+
+    // The archive loads like a SAVE ALL directory
+    let mut replica = TensorDb::new();
+    let load = format!("LOAD DATASET sales FROM \"{}\"", dir.display());
+    linal::dsl::execute_line(&mut replica, &load, 1).unwrap();
+    assert_eq!(replica.get_dataset("sales").unwrap().rows.len(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let resp = client
+        .get(format!("http://localhost:{}/admin/snapshot?db=missing", port))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert!(resp.text().await.unwrap().contains("Database 'missing' not found"));
+}
+
+#[tokio::test]
+async fn test_transaction_belongs_to_its_session() {
+    let db = Arc::new(RwLock::new(TensorDb::new()));
+    let port = 8119;
+    let db_clone = db.clone();
+
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+
+    sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    let send = |session: Option<&'static str>, command: &'static str| {
+        let mut request = client
+            .post(format!("http://localhost:{}/execute?format=json", port))
+            .header("Content-Type", "text/plain")
+            .header("X-Linal-Database", "ledger");
+        if let Some(session) = session {
+            request = request.header("X-Linal-Session", session);
+        }
+        async move {
+            let resp = request.body(command).send().await.unwrap();
+            let status = resp.status();
+            let body: serde_json::Value = resp.json().await.unwrap();
+            (status, body)
+        }
+    };
+
+    client
+        .post(format!("http://localhost:{}/execute?format=json", port))
+        .body("CREATE DATABASE ledger")
+        .send()
+        .await
+        .unwrap();
+    for command in [
+        "DATASET accounts COLUMNS (id: Int, balance: Float)",
+        "INSERT INTO accounts VALUES (1, 10.0), (2, 20.0)",
+    ] {
+        let (_, body) = send(None, command).await;
+        assert_eq!(body["status"], "ok", "{}", body);
+    }
+
+    // A transaction needs a session to belong to
+    let (status, body) = send(None, "BEGIN").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("X-Linal-Session"));
+
+    let (_, body) = send(Some("a"), "BEGIN").await;
+    assert_eq!(body["status"], "ok", "{}", body);
+    let (_, body) = send(Some("a"), "INSERT INTO accounts VALUES (3, 30.0)").await;
+    assert_eq!(body["status"], "ok", "{}", body);
+
+    // Other clients can neither write into it nor end it
+    let (_, body) = send(Some("b"), "INSERT INTO accounts VALUES (4, 40.0)").await;
+    assert_eq!(body["status"], "error");
+    assert!(
+        body["error"].as_str().unwrap().contains("another session"),
+        "{}",
+        body
+    );
+    let (_, body) = send(None, "DELETE FROM accounts WHERE id = 1").await;
+    assert_eq!(body["status"], "error");
+    let (_, body) = send(Some("b"), "COMMIT").await;
+    assert_eq!(body["status"], "error");
+
+    // Rolling back only undoes the owner's changes
+    let (_, body) = send(Some("a"), "ROLLBACK").await;
+    assert_eq!(body["status"], "ok", "{}", body);
+    let (_, body) = send(Some("b"), "INSERT INTO accounts VALUES (4, 40.0)").await;
+    assert_eq!(body["status"], "ok", "{}", body);
+    let (_, body) = send(None, "SELECT * FROM accounts").await;
+    assert_eq!(body["metadata"]["rows_returned"], 3, "{}", body);
+}
+
+#[tokio::test]
+async fn test_transaction_reads_its_own_writes() {
+    let db = Arc::new(RwLock::new(TensorDb::new()));
+    let port = 8120;
+    let db_clone = db.clone();
+
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+
+    sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    let send = |session: Option<&'static str>, command: &'static str| {
+        let mut request = client
+            .post(format!("http://localhost:{}/execute?format=json", port))
+            .header("Content-Type", "text/plain")
+            .header("X-Linal-Database", "orders");
+        if let Some(session) = session {
+            request = request.header("X-Linal-Session", session);
+        }
+        async move {
+            let resp = request.body(command).send().await.unwrap();
+            let body: serde_json::Value = resp.json().await.unwrap();
+            assert_eq!(body["status"], "ok", "{}: {}", command, body);
+            body
+        }
+    };
+    let rows = |body: serde_json::Value| body["metadata"]["rows_returned"].clone();
+
+    client
+        .post(format!("http://localhost:{}/execute?format=json", port))
+        .body("CREATE DATABASE orders")
+        .send()
+        .await
+        .unwrap();
+    send(None, "DATASET items COLUMNS (id: Int, qty: Int)").await;
+    send(None, "INSERT INTO items VALUES (1, 1), (2, 2)").await;
+
+    send(Some("a"), "BEGIN").await;
+    send(Some("a"), "INSERT INTO items VALUES (3, 3)").await;
+    send(Some("a"), "UPDATE items SET qty = 10 WHERE id = 1").await;
+    send(Some("a"), "DELETE FROM items WHERE id = 2").await;
+
+    // The transaction sees its writes; other clients see the rows before BEGIN
+    let select = "SELECT * FROM items WHERE qty > 2";
+    assert_eq!(rows(send(Some("a"), select).await), 2);
+    assert_eq!(rows(send(Some("a"), "SELECT * FROM items").await), 2);
+    assert_eq!(rows(send(Some("b"), select).await), 0);
+    assert_eq!(rows(send(None, "SELECT * FROM items").await), 2);
+
+    send(Some("a"), "ROLLBACK").await;
+    assert_eq!(rows(send(Some("a"), select).await), 0);
+    assert_eq!(rows(send(Some("a"), "SELECT * FROM items").await), 2);
+
+    send(Some("a"), "BEGIN").await;
+    send(Some("a"), "INSERT INTO items VALUES (3, 3)").await;
+    assert_eq!(rows(send(Some("a"), "SELECT * FROM items").await), 3);
+    assert_eq!(rows(send(Some("b"), "SELECT * FROM items").await), 2);
+    send(Some("a"), "COMMIT").await;
+    assert_eq!(rows(send(Some("b"), "SELECT * FROM items").await), 3);
+}
+
+#[tokio::test]
+async fn test_snapshot_export_respects_masking() {
+    let config = linal::core::config::EngineConfig {
+        security: linal::core::config::SecurityConfig {
+            api_key_roles: std::collections::HashMap::from([(
+                "k-admin".to_string(),
+                "admin".to_string(),
+            )]),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let db = Arc::new(RwLock::new(TensorDb::with_config(config)));
+    let port = 8121;
+    let db_clone = db.clone();
+
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+
+    sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    for command in [
+        "CREATE DATABASE crm",
+        "USE crm",
+        "DATASET users COLUMNS (id: Int, email: String)",
+        "INSERT INTO users VALUES (1, \"alice@example.com\")",
+        "DATASET users MASK COLUMN email USING MASK_EMAIL(email)",
+        "USE default",
+    ] {
+        let resp = client
+            .post(format!("http://localhost:{}/execute?format=json", port))
+            .header("X-API-Key", "k-admin")
+            .body(command)
+            .send()
+            .await
+            .unwrap();
+        assert!(resp.text().await.unwrap().contains("\"ok\""), "{}", command);
+    }
+
+    // The archive holds raw values, so masked roles cannot download it
+    let resp = client
+        .get(format!("http://localhost:{}/admin/snapshot?db=crm", port))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(resp.text().await.unwrap().contains("masked columns"));
+
+    let resp = client
+        .get(format!("http://localhost:{}/admin/snapshot?db=crm", port))
+        .header("X-API-Key", "k-admin")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_catalog_commands_keep_transactions_isolated() {
+    let db = Arc::new(RwLock::new(TensorDb::new()));
+    let port = 8122;
+    let db_clone = db.clone();
+
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+
+    sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    let send = |session: Option<&'static str>, command: &'static str| {
+        let mut request = client
+            .post(format!("http://localhost:{}/execute?format=json", port))
+            .header("Content-Type", "text/plain")
+            .header("X-Linal-Database", "stock");
+        if let Some(session) = session {
+            request = request.header("X-Linal-Session", session);
+        }
+        async move {
+            let resp = request.body(command).send().await.unwrap();
+            let body: serde_json::Value = resp.json().await.unwrap();
+            assert_eq!(body["status"], "ok", "{}: {}", command, body);
+            body["metadata"]["rows_returned"].clone()
+        }
+    };
+
+    client
+        .post(format!("http://localhost:{}/execute?format=json", port))
+        .body("CREATE DATABASE stock")
+        .send()
+        .await
+        .unwrap();
+    send(None, "DATASET parts COLUMNS (id: Int)").await;
+    send(None, "INSERT INTO parts VALUES (1), (2)").await;
+
+    send(Some("a"), "BEGIN").await;
+    send(Some("a"), "INSERT INTO parts VALUES (3)").await;
+
+    // Catalog commands drop the published snapshots, but not the one other
+    // sessions read while the transaction is open
+    send(Some("b"), "SHOW DATABASES").await;
+    assert_eq!(send(Some("b"), "SELECT * FROM parts").await, 2);
+    assert_eq!(send(None, "SELECT * FROM parts").await, 2);
+    assert_eq!(send(Some("a"), "SELECT * FROM parts").await, 3);
+
+    send(Some("a"), "COMMIT").await;
+    send(Some("b"), "SHOW DATABASES").await;
+    assert_eq!(send(Some("b"), "SELECT * FROM parts").await, 3);
+}
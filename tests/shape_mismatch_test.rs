@@ -0,0 +1,96 @@
+use linal::core::tensor::Shape;
+use linal::dsl::{execute_line, execute_script, DslError};
+use linal::engine::{BinaryOp, EngineError, TensorDb};
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    VECTOR a = [1, 2, 3]
+    VECTOR b = [1, 2, 3, 4]
+    MATRIX m = [[1, 2], [3, 4]]
+    MATRIX n = [[1, 2, 3], [4, 5, 6], [7, 8, 9]]
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+#[test]
+fn test_engine_reports_both_shapes_and_names() {
+    let mut db = setup();
+    let mut ctx = linal::engine::context::ExecutionContext::new();
+
+    let err = db
+        .eval_binary(&mut ctx, "c", "m", "n", BinaryOp::Add)
+        .unwrap_err();
+    match err {
+        EngineError::ShapeMismatch {
+            left_name,
+            right_name,
+            left_shape,
+            right_shape,
+            op,
+        } => {
+            assert_eq!(left_name, "m");
+            assert_eq!(right_name, "n");
+            assert_eq!(left_shape, vec![2, 2]);
+            assert_eq!(right_shape, vec![3, 3]);
+            assert_eq!(op, "ADD");
+        }
+        other => panic!("Expected ShapeMismatch, got {:?}", other),
+    }
+
+    // Vector ops need equal lengths
+    let err = db
+        .eval_binary(&mut ctx, "c", "a", "b", BinaryOp::Distance)
+        .unwrap_err();
+    assert!(matches!(err, EngineError::ShapeMismatch { .. }));
+
+    let err = db.eval_matmul(&mut ctx, "c", "m", "n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Shape mismatch in MATMUL: 'm' has shape [2, 2] but 'n' has shape [3, 3]"
+    );
+}
+
+#[test]
+fn test_dsl_error_carries_expression_text() {
+    let mut db = setup();
+
+    let err = execute_line(&mut db, "LET c = SIMILARITY a WITH b", 1).unwrap_err();
+    match &err {
+        DslError::ShapeMismatch { line, expr, source } => {
+            assert_eq!(*line, 1);
+            assert_eq!(expr, "SIMILARITY a WITH b");
+            assert!(matches!(**source, EngineError::ShapeMismatch { .. }));
+        }
+        other => panic!("Expected ShapeMismatch, got {:?}", other),
+    }
+    assert!(
+        err.to_string().contains("In `SIMILARITY a WITH b`"),
+        "{}",
+        err
+    );
+
+    // Infix operands are reported as written, not as temporaries
+    let err = execute_line(&mut db, "LET c = m * n[0:2, *]", 2).unwrap_err();
+    match err {
+        DslError::ShapeMismatch { expr, source, .. } => {
+            assert_eq!(expr, "m * n[0:2, *]");
+            let msg = source.to_string();
+            assert!(msg.contains("'n[0:2, *]' has shape [2, 3]"), "{}", msg);
+        }
+        other => panic!("Expected ShapeMismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_compatible_shapes_still_work() {
+    let mut db = setup();
+
+    // Vectors of different lengths are padded, scalars broadcast
+    execute_line(&mut db, "LET c = a + b", 1).unwrap();
+    assert_eq!(db.get("c").unwrap().shape, Shape::new(vec![4]));
+    execute_line(&mut db, "LET d = m * 2", 2).unwrap();
+    execute_line(&mut db, "LET e = MATMUL m m", 3).unwrap();
+    assert_eq!(db.get("e").unwrap().data_ref(), &[7.0, 10.0, 15.0, 22.0]);
+}
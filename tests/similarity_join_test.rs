@@ -0,0 +1,104 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET crm COLUMNS (id: Int, emb: Vector(2))
+    INSERT INTO crm VALUES (1, [1.0, 0.0]), (2, [0.0, 1.0]), (3, [-1.0, 0.0])
+    DATASET shop COLUMNS (sku: String, emb: Vector(2))
+    INSERT INTO shop VALUES ("a", [0.6, 0.8]), ("b", [1.0, 0.1]), ("c", [0.1, 1.0]), ("d", [0.99, 0.0])
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn explain(db: &mut TensorDb, query: &str) -> String {
+    match execute_line(db, &format!("EXPLAIN {}", query), 1).unwrap() {
+        DslOutput::Message(plan) => plan,
+        other => panic!("Expected plan message, got {:?}", other),
+    }
+}
+
+fn pair(id: i64, sku: Option<&str>) -> Vec<Value> {
+    vec![
+        Value::Int(id),
+        sku.map_or(Value::Null, |s| Value::String(s.to_string())),
+    ]
+}
+
+const QUERY: &str = "SELECT c.id, s.sku FROM crm c JOIN shop s ON SIMILARITY(c.emb, s.emb) > 0.9";
+
+#[test]
+fn test_similarity_join_pairs_rows_above_threshold() {
+    let mut db = setup();
+    // Best match first within each left row
+    assert_eq!(
+        rows(&mut db, QUERY),
+        vec![pair(1, Some("d")), pair(1, Some("b")), pair(2, Some("c")),]
+    );
+    assert!(explain(&mut db, QUERY).contains("right_index: None"));
+
+    // Either argument order, and COSINE_SIM as the function name
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT c.id, s.sku FROM crm c JOIN shop s ON COSINE_SIM(s.emb, c.emb) >= 0.8 WHERE c.id = 2",
+        ),
+        vec![pair(2, Some("c")), pair(2, Some("a"))]
+    );
+}
+
+#[test]
+fn test_similarity_join_uses_vector_index() {
+    let mut db = setup();
+    let without_index = rows(&mut db, QUERY);
+    execute_line(&mut db, "CREATE VECTOR INDEX shop_emb ON shop(emb)", 1).unwrap();
+
+    let plan = explain(&mut db, QUERY);
+    assert!(plan.contains("SimilarityJoinExec"), "{}", plan);
+    assert!(plan.contains("right_index: Some("), "{}", plan);
+    assert_eq!(rows(&mut db, QUERY), without_index);
+}
+
+#[test]
+fn test_left_similarity_join_keeps_unmatched_rows() {
+    let mut db = setup();
+    assert_eq!(
+        rows(
+            &mut db,
+            "SELECT c.id, s.sku FROM crm c LEFT JOIN shop s ON SIMILARITY(c.emb, s.emb) > 0.95",
+        ),
+        vec![
+            pair(1, Some("d")),
+            pair(1, Some("b")),
+            pair(2, Some("c")),
+            pair(3, None),
+        ]
+    );
+}
+
+#[test]
+fn test_similarity_join_rejects_non_vector_columns() {
+    let mut db = setup();
+    assert!(execute_line(
+        &mut db,
+        "SELECT c.id FROM crm c JOIN shop s ON SIMILARITY(c.id, s.emb) > 0.9",
+        1
+    )
+    .is_err());
+    assert!(execute_line(
+        &mut db,
+        "SELECT c.id FROM crm c JOIN shop s ON SIMILARITY(c.emb, s.emb)",
+        1
+    )
+    .is_err());
+}
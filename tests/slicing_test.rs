@@ -0,0 +1,89 @@
+use linal::core::config::EngineConfig;
+use linal::dsl::execute_script;
+use linal::engine::TensorDb;
+
+fn run(script: &str) -> TensorDb {
+    let mut db = TensorDb::with_config(EngineConfig::default());
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn tensor(db: &TensorDb, name: &str) -> (Vec<usize>, Vec<f32>) {
+    let t = db.get(name).unwrap();
+    (t.shape.dims.clone(), t.data.to_vec())
+}
+
+#[test]
+fn test_negative_indices_count_from_the_end() {
+    let db = run("VECTOR v = [1, 2, 3, 4, 5]\n\
+         MATRIX m = [[1, 2, 3], [4, 5, 6]]\n\
+         LET last = v[-1]\n\
+         LET corner = m[-1, -1]\n\
+         LET tail = v[-2:]\n\
+         LET row = m[-1, :]");
+    assert_eq!(tensor(&db, "last"), (vec![], vec![5.0]));
+    assert_eq!(tensor(&db, "corner"), (vec![], vec![6.0]));
+    assert_eq!(tensor(&db, "tail"), (vec![2], vec![4.0, 5.0]));
+    assert_eq!(tensor(&db, "row"), (vec![3], vec![4.0, 5.0, 6.0]));
+}
+
+#[test]
+fn test_steps_stride_and_reverse() {
+    let db = run("VECTOR v = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]\n\
+         MATRIX m = [[1, 2, 3], [4, 5, 6], [7, 8, 9]]\n\
+         LET even = v[0:10:2]\n\
+         LET reversed = v[::-1]\n\
+         LET back = v[7:2:-2]\n\
+         LET flipped = m[::-1, :]\n\
+         LET odd_cols = m[:, 1::2]\n\
+         LET diagonal_corner = m[::2, ::-2]");
+    assert_eq!(
+        tensor(&db, "even"),
+        (vec![5], vec![0.0, 2.0, 4.0, 6.0, 8.0])
+    );
+    assert_eq!(
+        tensor(&db, "reversed").1,
+        vec![9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0, 1.0, 0.0]
+    );
+    assert_eq!(tensor(&db, "back"), (vec![3], vec![7.0, 5.0, 3.0]));
+    assert_eq!(
+        tensor(&db, "flipped"),
+        (
+            vec![3, 3],
+            vec![7.0, 8.0, 9.0, 4.0, 5.0, 6.0, 1.0, 2.0, 3.0]
+        )
+    );
+    assert_eq!(tensor(&db, "odd_cols"), (vec![3, 1], vec![2.0, 5.0, 8.0]));
+    assert_eq!(
+        tensor(&db, "diagonal_corner"),
+        (vec![2, 2], vec![3.0, 1.0, 9.0, 7.0])
+    );
+}
+
+#[test]
+fn test_out_of_range_bounds_are_clamped() {
+    let db = run("VECTOR v = [1, 2, 3]\n\
+         LET all = v[-10:10]\n\
+         LET none = v[2:1]\n\
+         LET empty_back = v[0:2:-1]");
+    assert_eq!(tensor(&db, "all"), (vec![3], vec![1.0, 2.0, 3.0]));
+    assert_eq!(tensor(&db, "none"), (vec![0], vec![]));
+    assert_eq!(tensor(&db, "empty_back"), (vec![0], vec![]));
+}
+
+#[test]
+fn test_invalid_slices_are_rejected() {
+    let mut db = TensorDb::with_config(EngineConfig::default());
+    execute_script(&mut db, "VECTOR v = [1, 2, 3]").unwrap();
+    for (line, expected) in [
+        ("LET x = v[3]", "out of bounds"),
+        ("LET x = v[-4]", "out of bounds"),
+        ("LET x = v[::0]", "step cannot be zero"),
+        ("LET x = v[1:2:3:4]", "Invalid range spec"),
+    ] {
+        let err = linal::dsl::execute_line(&mut db, line, 1)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(expected), "{}: {}", line, err);
+    }
+}
@@ -0,0 +1,329 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::dsl::{execute_line, DslOutput};
+use linal::engine::TensorDb;
+use linal::server::sessions::{Caller, DatabaseLocks, LockScope};
+use std::sync::mpsc;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+fn setup_db() -> TensorDb {
+    let config = EngineConfig {
+        storage: StorageConfig {
+            auto_persist: false,
+            wal: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+    execute_line(&mut db, "DATASET items COLUMNS (id: Int)", 1).unwrap();
+    execute_line(&mut db, "INSERT INTO items VALUES (1), (2)", 2).unwrap();
+    db
+}
+
+fn count(db: &mut TensorDb) -> usize {
+    match execute_line(db, "SELECT * FROM items", 1).unwrap() {
+        DslOutput::Table(ds) => ds.len(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_snapshot_is_isolated_from_later_writes() {
+    let mut db = setup_db();
+    let name = db.active_database().to_string();
+    let snapshot = db.read_snapshot(&name).unwrap();
+
+    execute_line(&mut db, "INSERT INTO items VALUES (3)", 3).unwrap();
+    execute_line(&mut db, "DATASET other COLUMNS (id: Int)", 4).unwrap();
+
+    let mut reader = db.snapshot_session(snapshot.clone());
+    assert_eq!(count(&mut reader), 2);
+    assert!(reader.get_dataset("other").is_err());
+    // The reader's own changes stay in its session
+    execute_line(&mut reader, "INSERT INTO items VALUES (9)", 1).unwrap();
+    assert_eq!(count(&mut db.snapshot_session(snapshot)), 2);
+    assert_eq!(count(&mut db), 3);
+
+    let mut reader = db.snapshot_session(db.read_snapshot(&name).unwrap());
+    assert_eq!(count(&mut reader), 3);
+}
+
+#[test]
+fn test_no_snapshot_of_private_state() {
+    let mut db = setup_db();
+    let name = db.active_database().to_string();
+    assert!(db.read_snapshot("missing").is_none());
+
+    execute_line(&mut db, "BEGIN", 1).unwrap();
+    assert!(db.read_snapshot(&name).is_none());
+    execute_line(&mut db, "COMMIT", 2).unwrap();
+
+    execute_line(&mut db, "CREATE DATABASE shop", 3).unwrap();
+    let session = db.detach_session("shop").unwrap();
+    assert!(db.read_snapshot("shop").is_none());
+    db.attach_session(session);
+    assert!(db.read_snapshot("shop").is_some());
+}
+
+#[test]
+fn test_reads_do_not_wait_for_writers() {
+    let catalog = Arc::new(RwLock::new(setup_db()));
+    let locks = Arc::new(DatabaseLocks::default());
+    let select = "SELECT * FROM items";
+    assert_eq!(LockScope::of_command(select), LockScope::Snapshot);
+    assert_eq!(
+        LockScope::of_command("SQL SELECT * FROM items"),
+        LockScope::Snapshot
+    );
+    assert_eq!(
+        LockScope::of_command("INSERT INTO items VALUES (3)"),
+        LockScope::Database(None)
+    );
+
+    // A writer takes the database and stalls halfway through its command
+    let (entered_tx, entered_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let writer = {
+        let (catalog, locks) = (catalog.clone(), locks.clone());
+        std::thread::spawn(move || {
+            locks.run(
+                &catalog,
+                LockScope::Database(None),
+                None,
+                Caller::new("writer"),
+                |db| {
+                    let output = execute_line(db, "INSERT INTO items VALUES (3)", 1);
+                    entered_tx.send(()).unwrap();
+                    release_rx.recv().unwrap();
+                    output
+                },
+            )
+        })
+    };
+    entered_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+
+    let read = |locks: &DatabaseLocks| {
+        let (result, _) = locks.run(
+            &catalog,
+            LockScope::of_command(select),
+            None,
+            Caller::new("reader"),
+            |db| execute_line(db, select, 1),
+        );
+        match result.unwrap() {
+            DslOutput::Table(ds) => ds.len(),
+            other => panic!("Expected table output, got {:?}", other),
+        }
+    };
+    // The read runs on the state before the write
+    assert_eq!(read(&locks), 2);
+
+    release_tx.send(()).unwrap();
+    writer.join().unwrap().0.unwrap();
+    assert_eq!(read(&locks), 3);
+}
+
+#[test]
+fn test_readers_share_database_lock() {
+    let catalog = Arc::new(RwLock::new(setup_db()));
+    let locks = Arc::new(DatabaseLocks::default());
+    let name = catalog.read().unwrap().active_database().to_string();
+
+    // One reader holds the database and waits inside its read
+    let (entered_tx, entered_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let reader = {
+        let (catalog, locks, name) = (catalog.clone(), locks.clone(), name.clone());
+        std::thread::spawn(move || {
+            locks.read(&catalog, &name, |db| {
+                entered_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+                db.get_dataset("items").unwrap().len()
+            })
+        })
+    };
+    entered_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+
+    // A second reader does not wait for it
+    let (done_tx, done_rx) = mpsc::channel();
+    {
+        let (catalog, locks, name) = (catalog.clone(), locks.clone(), name.clone());
+        std::thread::spawn(move || {
+            let rows = locks.read(&catalog, &name, |db| db.get_dataset("items").unwrap().len());
+            done_tx.send(rows).unwrap();
+        });
+    }
+    assert_eq!(done_rx.recv_timeout(Duration::from_secs(10)).unwrap(), 2);
+
+    release_tx.send(()).unwrap();
+    assert_eq!(reader.join().unwrap(), 2);
+}
+
+fn run_as(
+    catalog: &RwLock<TensorDb>,
+    locks: &DatabaseLocks,
+    database: Option<&str>,
+    caller: &Caller,
+    command: &str,
+) -> Result<DslOutput, linal::dsl::DslError> {
+    let scope = LockScope::of_command(command);
+    let (result, _) = locks.run(catalog, scope, database, caller.clone(), |db| {
+        execute_line(db, command, 1)
+    });
+    result
+}
+
+fn rows(output: Result<DslOutput, linal::dsl::DslError>) -> usize {
+    match output.unwrap() {
+        DslOutput::Table(ds) => ds.len(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_idle_transactions_are_rolled_back() {
+    let catalog = RwLock::new(setup_db());
+    let locks = DatabaseLocks::default();
+    let owner = Caller {
+        actor: "alice".to_string(),
+        session: Some("a".to_string()),
+    };
+    let other = Caller {
+        actor: "bob".to_string(),
+        session: Some("b".to_string()),
+    };
+
+    run_as(&catalog, &locks, None, &owner, "BEGIN").unwrap();
+    run_as(
+        &catalog,
+        &locks,
+        None,
+        &owner,
+        "INSERT INTO items VALUES (3)",
+    )
+    .unwrap();
+    assert!(locks
+        .roll_back_idle_transactions(&catalog, Duration::from_secs(60))
+        .is_empty());
+
+    // Commands of other sessions do not keep the transaction in use
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(run_as(
+        &catalog,
+        &locks,
+        None,
+        &other,
+        "INSERT INTO items VALUES (4)"
+    )
+    .is_err());
+    let name = catalog.read().unwrap().active_database().to_string();
+    assert_eq!(
+        locks.roll_back_idle_transactions(&catalog, Duration::from_millis(20)),
+        vec![name]
+    );
+
+    run_as(
+        &catalog,
+        &locks,
+        None,
+        &other,
+        "INSERT INTO items VALUES (4)",
+    )
+    .unwrap();
+    assert_eq!(
+        rows(run_as(
+            &catalog,
+            &locks,
+            None,
+            &other,
+            "SELECT * FROM items"
+        )),
+        3
+    );
+    assert!(run_as(&catalog, &locks, None, &owner, "COMMIT").is_err());
+}
+
+#[test]
+fn test_dropped_database_forgets_its_transaction() {
+    let catalog = RwLock::new(setup_db());
+    let locks = DatabaseLocks::default();
+    let owner = Caller {
+        actor: "alice".to_string(),
+        session: Some("a".to_string()),
+    };
+    let other = Caller {
+        actor: "bob".to_string(),
+        session: Some("b".to_string()),
+    };
+    let scratch = Some("scratch");
+
+    run_as(&catalog, &locks, None, &owner, "CREATE DATABASE scratch").unwrap();
+    run_as(
+        &catalog,
+        &locks,
+        scratch,
+        &owner,
+        "DATASET parts COLUMNS (id: Int)",
+    )
+    .unwrap();
+    run_as(
+        &catalog,
+        &locks,
+        scratch,
+        &owner,
+        "INSERT INTO parts VALUES (1), (2)",
+    )
+    .unwrap();
+    run_as(&catalog, &locks, scratch, &owner, "BEGIN").unwrap();
+    run_as(
+        &catalog,
+        &locks,
+        scratch,
+        &owner,
+        "INSERT INTO parts VALUES (3)",
+    )
+    .unwrap();
+    assert_eq!(
+        rows(run_as(
+            &catalog,
+            &locks,
+            scratch,
+            &other,
+            "SELECT * FROM parts"
+        )),
+        2
+    );
+
+    run_as(&catalog, &locks, None, &other, "DROP DATABASE scratch").unwrap();
+    run_as(&catalog, &locks, None, &other, "CREATE DATABASE scratch").unwrap();
+
+    // The new database has neither the old rows nor the old transaction
+    assert!(run_as(&catalog, &locks, scratch, &other, "SELECT * FROM parts").is_err());
+    run_as(
+        &catalog,
+        &locks,
+        scratch,
+        &other,
+        "DATASET parts COLUMNS (id: Int)",
+    )
+    .unwrap();
+    run_as(
+        &catalog,
+        &locks,
+        scratch,
+        &other,
+        "INSERT INTO parts VALUES (5)",
+    )
+    .unwrap();
+    assert_eq!(
+        rows(run_as(
+            &catalog,
+            &locks,
+            scratch,
+            &other,
+            "SELECT * FROM parts"
+        )),
+        1
+    );
+}
@@ -0,0 +1,75 @@
+use linal::dsl::{execute_line, execute_script};
+use linal::engine::TensorDb;
+
+fn run(script: &str) -> TensorDb {
+    let mut db = TensorDb::new();
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn tensor(db: &TensorDb, name: &str) -> (Vec<usize>, Vec<f32>) {
+    let t = db.get(name).unwrap();
+    (t.shape.dims.clone(), t.data.to_vec())
+}
+
+#[test]
+fn test_sort_and_argsort_vectors() {
+    let db = run("VECTOR v = [3, 1, 4, 1, 5]\n\
+         LET s = SORT v\n\
+         LET d = SORT v DESC\n\
+         LET a = ARGSORT v\n\
+         LET ad = ARGSORT v DESC");
+    assert_eq!(tensor(&db, "s"), (vec![5], vec![1.0, 1.0, 3.0, 4.0, 5.0]));
+    assert_eq!(tensor(&db, "d").1, vec![5.0, 4.0, 3.0, 1.0, 1.0]);
+    // Ties keep their original order
+    assert_eq!(tensor(&db, "a").1, vec![1.0, 3.0, 0.0, 2.0, 4.0]);
+    assert_eq!(tensor(&db, "ad").1, vec![4.0, 2.0, 0.0, 1.0, 3.0]);
+}
+
+#[test]
+fn test_topk_returns_values_and_indices() {
+    let db = run("VECTOR scores = [0.2, 0.9, 0.5, 0.7]\n\
+         LET best, at = TOPK scores 2\n\
+         LET only = TOPK scores 1");
+    assert_eq!(tensor(&db, "best"), (vec![2], vec![0.9, 0.7]));
+    assert_eq!(tensor(&db, "at"), (vec![2], vec![1.0, 3.0]));
+    assert_eq!(tensor(&db, "only").1, vec![0.9]);
+}
+
+#[test]
+fn test_matrices_sort_each_row() {
+    let db = run("MATRIX m = [[1, 3, 2], [9, 7, 8]]\n\
+         LET s = SORT m DESC\n\
+         LET top, at = TOPK m 1");
+    assert_eq!(
+        tensor(&db, "s"),
+        (vec![2, 3], vec![3.0, 2.0, 1.0, 9.0, 8.0, 7.0])
+    );
+    assert_eq!(tensor(&db, "top"), (vec![2, 1], vec![3.0, 9.0]));
+    assert_eq!(tensor(&db, "at").1, vec![1.0, 0.0]);
+}
+
+#[test]
+fn test_nan_sorts_last() {
+    let db = run("VECTOR v = [2, NaN, 1]\n\
+         LET s = SORT v\n\
+         LET d = SORT v DESC\n\
+         LET top = TOPK v 1");
+    let (_, s) = tensor(&db, "s");
+    assert_eq!(&s[..2], &[1.0, 2.0]);
+    assert!(s[2].is_nan());
+    let (_, d) = tensor(&db, "d");
+    assert_eq!(&d[..2], &[2.0, 1.0]);
+    assert!(d[2].is_nan());
+    assert_eq!(tensor(&db, "top").1, vec![2.0]);
+}
+
+#[test]
+fn test_invalid_arguments_are_rejected() {
+    let mut db = run("VECTOR v = [1, 2, 3]");
+    let err = execute_line(&mut db, "LET t = TOPK v 4", 1).unwrap_err();
+    assert!(err.to_string().contains("TOPK k = 4"), "{}", err);
+    assert!(execute_line(&mut db, "LET t = TOPK v 0", 1).is_err());
+    assert!(execute_line(&mut db, "LET s = SORT v SIDEWAYS", 1).is_err());
+    assert!(execute_line(&mut db, "LET s = SORT", 1).is_err());
+}
@@ -0,0 +1,186 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_sql, DslOutput};
+use linal::engine::TensorDb;
+use linal::server::start_server;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+fn setup_orders(db: &mut TensorDb) {
+    let statements = [
+        "CREATE TABLE orders (id INT NOT NULL, region TEXT, amount DOUBLE, emb VECTOR(2))",
+        "INSERT INTO orders VALUES (1, 'north', 10, [1.0, 0.0]), (2, 'south', 25.5, [0.0, 1.0])",
+        "INSERT INTO orders (id, region, amount) VALUES (3, 'north', 40.0), (4, 'east', 5.0)",
+    ];
+    for sql in statements {
+        execute_sql(db, sql, 1).expect("SQL setup failed");
+    }
+}
+
+fn select_rows(db: &mut TensorDb, sql: &str) -> Vec<Vec<Value>> {
+    match execute_sql(db, sql, 1).expect("Query failed") {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sql_create_table_and_insert() {
+    let mut db = TensorDb::new();
+    setup_orders(&mut db);
+
+    let ds = db.get_dataset("orders").unwrap();
+    assert_eq!(ds.rows.len(), 4);
+    assert!(!ds.schema.get_field("id").unwrap().nullable);
+    assert!(ds.schema.get_field("emb").unwrap().nullable);
+
+    // Integer literal coerced to the DOUBLE column; omitted columns are NULL
+    assert_eq!(ds.rows[0].get("amount"), Some(&Value::Float(10.0)));
+    assert_eq!(ds.rows[0].get("emb"), Some(&Value::Vector(vec![1.0, 0.0])));
+    assert_eq!(ds.rows[2].get("emb"), Some(&Value::Null));
+
+    // Tables created in SQL are ordinary datasets for the DSL
+    let out = execute_line(&mut db, "SELECT id FROM orders WHERE region = \"east\"", 1).unwrap();
+    match out {
+        DslOutput::Table(ds) => assert_eq!(ds.rows[0].values, vec![Value::Int(4)]),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_sql_select_clauses() {
+    let mut db = TensorDb::new();
+    setup_orders(&mut db);
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT id, amount FROM orders WHERE region = 'north' AND amount >= 10 ORDER BY amount DESC LIMIT 1",
+    );
+    assert_eq!(rows, vec![vec![Value::Int(3), Value::Float(40.0)]]);
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT region, SUM(amount), COUNT(*) FROM orders GROUP BY region HAVING COUNT(*) > 1",
+    );
+    assert_eq!(
+        rows,
+        vec![vec![
+            Value::String("north".to_string()),
+            Value::Float(50.0),
+            Value::Int(2)
+        ]]
+    );
+
+    let rows = select_rows(&mut db, "SELECT * FROM orders ORDER BY id LIMIT 2");
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].len(), 4);
+}
+
+#[test]
+fn test_sql_errors() {
+    let mut db = TensorDb::new();
+    setup_orders(&mut db);
+
+    // Bad syntax, unsupported features and type mismatches are reported
+    assert!(execute_sql(&mut db, "SELEC id FROM orders", 1).is_err());
+    assert!(execute_sql(&mut db, "SELECT orders.* FROM orders", 1).is_err());
+    assert!(execute_sql(&mut db, "DELETE FROM orders", 1).is_err());
+    assert!(execute_sql(&mut db, "CREATE TABLE t (x BLOB)", 1).is_err());
+    assert!(execute_sql(&mut db, "INSERT INTO orders (id, nope) VALUES (5, 1)", 1).is_err());
+
+    // A failing row leaves the table untouched
+    assert!(execute_sql(
+        &mut db,
+        "INSERT INTO orders VALUES (5, 'west', 1.0, NULL), (NULL, 'west', 1.0, NULL)",
+        1
+    )
+    .is_err());
+    assert_eq!(db.get_dataset("orders").unwrap().rows.len(), 4);
+}
+
+#[test]
+fn test_sql_statements_replay_from_wal() {
+    let temp_dir = "/tmp/linal_test_sql_wal";
+    let _ = fs::remove_dir_all(temp_dir);
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            default_db: "default".to_string(),
+            auto_persist: false,
+            wal: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    {
+        let mut db = TensorDb::with_config(config.clone());
+        setup_orders(&mut db);
+        select_rows(&mut db, "SELECT id FROM orders");
+    }
+
+    let db = TensorDb::with_config(config);
+    assert_eq!(db.get_dataset("orders").unwrap().rows.len(), 4);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[tokio::test]
+async fn test_execute_endpoint_with_sql_lang() {
+    let db = Arc::new(RwLock::new(TensorDb::new()));
+    let port = 8111;
+    let db_clone = db.clone();
+    tokio::spawn(async move {
+        start_server(db_clone, port).await;
+    });
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+
+    let client = reqwest::Client::new();
+    let url = format!("http://localhost:{}/execute", port);
+    for sql in [
+        "CREATE TABLE items (id INT, name TEXT)",
+        "INSERT INTO items VALUES (1, 'a'), (2, 'b')",
+    ] {
+        let resp = client
+            .post(format!("{}?lang=sql&format=json", url))
+            .header("Content-Type", "text/plain")
+            .body(sql)
+            .send()
+            .await
+            .unwrap();
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["status"], "ok", "{}", body);
+    }
+
+    let resp = client
+        .post(format!("{}?lang=sql&format=json", url))
+        .header("Content-Type", "text/plain")
+        .body("SELECT name FROM items WHERE id > 1")
+        .send()
+        .await
+        .unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("\"b\""), "{}", body);
+    assert!(!body.contains("\"a\""), "{}", body);
+
+    // Without lang=sql the statement is parsed as DSL
+    let resp = client
+        .post(format!("{}?format=json", url))
+        .header("Content-Type", "text/plain")
+        .body("CREATE TABLE other (id INT)")
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["status"], "error");
+
+    let resp = client
+        .post(format!("{}?lang=cobol", url))
+        .body("SELECT 1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
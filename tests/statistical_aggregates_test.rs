@@ -0,0 +1,148 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET latency COLUMNS (svc: String, ms: Float, region: String)
+    INSERT INTO latency VALUES ("api", 2.0, "eu"), ("api", 4.0, "eu"), ("api", 4.0, "us")
+    INSERT INTO latency VALUES ("api", 4.0, "us"), ("api", 5.0, "eu"), ("api", 5.0, "us")
+    INSERT INTO latency VALUES ("api", 7.0, "eu"), ("api", 9.0, "us"), ("db", 10.0, "eu")
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn table(db: &mut TensorDb, query: &str) -> (Vec<String>, Vec<Vec<Value>>) {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => (
+            ds.schema.fields.iter().map(|f| f.name.clone()).collect(),
+            ds.rows.into_iter().map(|r| r.values).collect(),
+        ),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+fn float(value: &Value) -> f32 {
+    match value {
+        Value::Float(f) => *f,
+        other => panic!("Expected a Float, got {:?}", other),
+    }
+}
+
+fn assert_close(value: &Value, expected: f32) {
+    assert!(
+        (float(value) - expected).abs() < 1e-4,
+        "{:?} != {}",
+        value,
+        expected
+    );
+}
+
+#[test]
+fn test_variance_and_stddev() {
+    let mut db = setup();
+
+    // api: 2, 4, 4, 4, 5, 5, 7, 9 -> mean 5, squared deviations sum to 32
+    let (names, rows) = table(
+        &mut db,
+        "SELECT svc, VARIANCE(ms), STDDEV(ms) FROM latency GROUP BY svc ORDER BY svc",
+    );
+    assert_eq!(names, vec!["svc", "VARIANCE(ms)", "STDDEV(ms)"]);
+    assert_close(&rows[0][1], 32.0 / 7.0);
+    assert_close(&rows[0][2], (32.0f32 / 7.0).sqrt());
+    // A single value has no sample variance
+    assert_eq!(rows[1][1], Value::Null);
+    assert_eq!(rows[1][2], Value::Null);
+}
+
+#[test]
+fn test_median_and_percentiles() {
+    let mut db = setup();
+
+    let (names, rows) = table(
+        &mut db,
+        "SELECT svc, MEDIAN(ms), PERCENTILE(ms, 0.9), PERCENTILE(ms, 0) FROM latency GROUP BY svc ORDER BY svc",
+    );
+    assert_eq!(
+        names,
+        vec![
+            "svc",
+            "MEDIAN(ms)",
+            "PERCENTILE(ms, 0.9)",
+            "PERCENTILE(ms, 0)"
+        ]
+    );
+    // Even count: halfway between 4 and 5
+    assert_close(&rows[0][1], 4.5);
+    // Rank 0.9 * 7 = 6.3 -> 7 + 0.3 * (9 - 7)
+    assert_close(&rows[0][2], 7.6);
+    assert_close(&rows[0][3], 2.0);
+    assert_close(&rows[1][1], 10.0);
+
+    // Without GROUP BY
+    let (_, rows) = table(
+        &mut db,
+        "SELECT MEDIAN(ms) FROM latency WHERE region = \"us\"",
+    );
+    assert_close(&rows[0][0], 4.5);
+}
+
+#[test]
+fn test_count_distinct() {
+    let mut db = setup();
+
+    let (names, rows) = table(
+        &mut db,
+        "SELECT svc, COUNT(DISTINCT region), COUNT(DISTINCT ms), COUNT(*) FROM latency GROUP BY svc ORDER BY svc",
+    );
+    assert_eq!(
+        names,
+        vec![
+            "svc",
+            "COUNT(DISTINCT region)",
+            "COUNT(DISTINCT ms)",
+            "COUNT(val)"
+        ]
+    );
+    assert_eq!(rows[0][1..], [Value::Int(2), Value::Int(5), Value::Int(8)]);
+    assert_eq!(rows[1][1..], [Value::Int(1), Value::Int(1), Value::Int(1)]);
+}
+
+#[test]
+fn test_statistical_aggregates_in_sql() {
+    let mut db = setup();
+
+    let (names, rows) = table(
+        &mut db,
+        "SQL SELECT svc, STDDEV(ms), COUNT(DISTINCT region), PERCENTILE(ms, 0.5) FROM latency GROUP BY svc HAVING COUNT(DISTINCT region) > 1",
+    );
+    assert_eq!(
+        names,
+        vec![
+            "svc",
+            "STDDEV(ms)",
+            "COUNT(DISTINCT region)",
+            "PERCENTILE(ms, 0.5)"
+        ]
+    );
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0][0], Value::String("api".into()));
+    assert_close(&rows[0][3], 4.5);
+}
+
+#[test]
+fn test_invalid_statistical_aggregates() {
+    let mut db = setup();
+
+    for query in [
+        "SELECT PERCENTILE(ms) FROM latency",
+        "SELECT PERCENTILE(ms, 1.5) FROM latency",
+        "SELECT svc, PERCENTILE(ms, x) FROM latency GROUP BY svc",
+        "SQL SELECT SUM(DISTINCT ms) FROM latency",
+        "SQL SELECT PERCENTILE(ms, 2) FROM latency",
+    ] {
+        assert!(execute_line(&mut db, query, 1).is_err(), "{}", query);
+    }
+}
@@ -0,0 +1,127 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use std::fs;
+use std::path::PathBuf;
+
+fn setup_db(temp_dir: &str) -> TensorDb {
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            default_db: "default".to_string(),
+            auto_persist: false,
+            wal: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut db = TensorDb::with_config(config);
+
+    let script = r#"
+    DATASET customers COLUMNS (id: Int, region: String, spend: Float)
+    INSERT INTO customers VALUES (1, "EU", 250.0)
+    INSERT INTO customers VALUES (2, "US", 80.0)
+    INSERT INTO customers VALUES (3, "EU", 120.0)
+    INSERT INTO customers VALUES (4, "EU", 40.0)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn ids(output: DslOutput) -> Vec<Value> {
+    match output {
+        DslOutput::Table(ds) => ds.rows.iter().map(|r| r.values[0].clone()).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_create_and_run_parameterized_query() {
+    let temp_dir = "/tmp/linal_test_stored_query_run";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+
+    execute_line(
+        &mut db,
+        r#"CREATE QUERY top_customers AS SELECT id FROM customers WHERE region = $region ORDER BY spend DESC LIMIT $n"#,
+        1,
+    )
+    .unwrap();
+
+    let out = execute_line(
+        &mut db,
+        r#"RUN QUERY top_customers WITH ($n = 2, $region = "EU")"#,
+        2,
+    )
+    .unwrap();
+    assert_eq!(ids(out), vec![Value::Int(1), Value::Int(3)]);
+
+    let out = execute_line(
+        &mut db,
+        r#"RUN QUERY top_customers WITH ($region = "US", $n = 5)"#,
+        3,
+    )
+    .unwrap();
+    assert_eq!(ids(out), vec![Value::Int(2)]);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_stored_query_survives_restart() {
+    let temp_dir = "/tmp/linal_test_stored_query_persist";
+    let _ = fs::remove_dir_all(temp_dir);
+
+    {
+        let mut db = setup_db(temp_dir);
+        execute_line(
+            &mut db,
+            "CREATE QUERY big_spenders AS SELECT id FROM customers WHERE spend > 200",
+            1,
+        )
+        .unwrap();
+    }
+
+    let mut db = setup_db(temp_dir);
+    let out = execute_line(&mut db, "RUN QUERY big_spenders", 1).unwrap();
+    assert_eq!(ids(out), vec![Value::Int(1)]);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_stored_query_errors() {
+    let temp_dir = "/tmp/linal_test_stored_query_errors";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+
+    execute_line(
+        &mut db,
+        "CREATE QUERY by_spend AS SELECT id FROM customers WHERE spend > $min",
+        1,
+    )
+    .unwrap();
+
+    // Duplicate name, non-SELECT body
+    assert!(execute_line(
+        &mut db,
+        "CREATE QUERY by_spend AS SELECT id FROM customers",
+        2
+    )
+    .is_err());
+    assert!(execute_line(
+        &mut db,
+        "CREATE QUERY wipe AS DATASET x COLUMNS (a: Int)",
+        3
+    )
+    .is_err());
+
+    // Unknown query, missing parameter, non-literal value
+    assert!(execute_line(&mut db, "RUN QUERY nope", 4).is_err());
+    let err = execute_line(&mut db, "RUN QUERY by_spend", 5).unwrap_err();
+    assert!(err.to_string().contains("$min"));
+    assert!(execute_line(&mut db, "RUN QUERY by_spend WITH ($min = spend)", 6).is_err());
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
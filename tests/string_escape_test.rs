@@ -0,0 +1,151 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use linal::utils::parsing::{escape_string, unescape_string};
+use std::fs;
+use std::path::PathBuf;
+
+fn setup() -> TensorDb {
+    with_notes(TensorDb::new())
+}
+
+/// Stored queries are saved under data_dir, so tests creating them get their own
+fn setup_db(temp_dir: &str) -> TensorDb {
+    let config = EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    with_notes(TensorDb::with_config(config))
+}
+
+fn with_notes(mut db: TensorDb) -> TensorDb {
+    execute_line(&mut db, "DATASET notes COLUMNS (id: Int, body: String)", 1).unwrap();
+    db
+}
+
+fn ids(db: &mut TensorDb, query: &str) -> Vec<Value> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.iter().map(|r| r.values[0].clone()).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_escapes_in_inserted_strings() {
+    let mut db = setup();
+    execute_line(
+        &mut db,
+        r#"INSERT INTO notes VALUES (1, "She said \"hi, (there)\"\nbye"), (2, "a\\b\tc é 😀")"#,
+        1,
+    )
+    .unwrap();
+
+    let notes = db.get_dataset("notes").unwrap();
+    assert_eq!(
+        notes.rows[0].get("body"),
+        Some(&Value::String("She said \"hi, (there)\"\nbye".into()))
+    );
+    assert_eq!(
+        notes.rows[1].get("body"),
+        Some(&Value::String("a\\b\tc é 😀".into()))
+    );
+
+    // Escaped quotes do not end the literal for WHERE, operators or keywords
+    execute_line(
+        &mut db,
+        r#"INSERT INTO notes VALUES (3, "x != \" WHERE y")"#,
+        2,
+    )
+    .unwrap();
+    assert_eq!(
+        ids(
+            &mut db,
+            r#"SELECT id FROM notes WHERE body = "x != \" WHERE y""#
+        ),
+        vec![Value::Int(3)]
+    );
+    execute_line(
+        &mut db,
+        r#"UPDATE notes SET body = "\"quoted\"" WHERE body = "x != \" WHERE y""#,
+        3,
+    )
+    .unwrap();
+    assert_eq!(
+        db.get_dataset("notes").unwrap().rows[2].get("body"),
+        Some(&Value::String("\"quoted\"".into()))
+    );
+}
+
+#[test]
+fn test_display_round_trips_through_the_dsl() {
+    let mut db = setup();
+    let text = "tab\t\"quote\" back\\slash\nline \u{1} (paren";
+    let literal = Value::String(text.into()).to_string();
+    assert_eq!(
+        literal,
+        r#""tab\t\"quote\" back\\slash\nline \u0001 (paren""#
+    );
+
+    // Scripts balance parentheses outside string literals only
+    let script = format!("INSERT INTO notes VALUES (1, {})", literal);
+    execute_script(&mut db, &script).unwrap();
+    assert_eq!(
+        db.get_dataset("notes").unwrap().rows[0].get("body"),
+        Some(&Value::String(text.into()))
+    );
+
+    assert_eq!(unescape_string(&escape_string(text)).unwrap(), text);
+}
+
+#[test]
+fn test_unknown_escapes_are_kept_and_bad_ones_rejected() {
+    let mut db = setup();
+    execute_line(&mut db, r#"INSERT INTO notes VALUES (1, "order 42")"#, 1).unwrap();
+
+    // Regex patterns keep their backslashes
+    assert_eq!(
+        ids(
+            &mut db,
+            r#"SELECT id FROM notes WHERE REGEXP_MATCH(body, "\d+$")"#
+        ),
+        vec![Value::Int(1)]
+    );
+
+    let err = execute_line(&mut db, r#"INSERT INTO notes VALUES (2, "\u12")"#, 2).unwrap_err();
+    assert!(err.to_string().contains("hex digits"), "{}", err);
+    assert!(execute_line(&mut db, r#"INSERT INTO notes VALUES (2, "\uD800")"#, 3).is_err());
+    assert!(execute_line(&mut db, r#"INSERT INTO notes VALUES (2, "open\")"#, 4).is_err());
+    assert_eq!(db.get_dataset("notes").unwrap().len(), 1);
+}
+
+#[test]
+fn test_stored_query_accepts_escaped_parameter() {
+    let temp_dir = "/tmp/linal_test_string_escape_query";
+    let _ = fs::remove_dir_all(temp_dir);
+    let mut db = setup_db(temp_dir);
+    let script = r#"
+    INSERT INTO notes VALUES (1, "say \"hi\"")
+    CREATE QUERY by_body AS SELECT id FROM notes WHERE body = $body
+    SET DATASET notes METADATA owner = "ops \"team\""
+    "#;
+    execute_script(&mut db, script).unwrap();
+
+    assert_eq!(
+        ids(&mut db, r#"RUN QUERY by_body WITH ($body = "say \"hi\"")"#),
+        vec![Value::Int(1)]
+    );
+    assert_eq!(
+        db.get_dataset("notes")
+            .unwrap()
+            .metadata
+            .extra
+            .get("owner")
+            .map(String::as_str),
+        Some("ops \"team\"")
+    );
+    let _ = fs::remove_dir_all(temp_dir);
+}
@@ -0,0 +1,110 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String)
+    INSERT INTO users VALUES (1, "Alice"), (2, "Bob"), (3, "Carol"), (4, "Dave")
+    DATASET orders COLUMNS (oid: Int, user_id: Int)
+    INSERT INTO orders VALUES (10, 1), (11, 3), (12, 3)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn column(db: &mut TensorDb, query: &str) -> Vec<Value> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows.iter().map(|r| r.values[0].clone()).collect(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn names(values: &[&str]) -> Vec<Value> {
+    values
+        .iter()
+        .map(|v| Value::String(v.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_in_subquery_is_a_semi_join() {
+    let mut db = setup();
+    let rows = column(
+        &mut db,
+        "SELECT name FROM users WHERE id IN (SELECT user_id FROM orders)",
+    );
+    assert_eq!(rows, names(&["Alice", "Carol"]));
+
+    let rows = column(
+        &mut db,
+        "SELECT name FROM users WHERE id NOT IN (SELECT user_id FROM orders WHERE oid > 10) AND id > 1",
+    );
+    assert_eq!(rows, names(&["Bob", "Dave"]));
+
+    // Subqueries nest
+    let rows = column(
+        &mut db,
+        "SELECT COUNT(*) FROM users WHERE id IN \
+         (SELECT user_id FROM orders WHERE user_id IN (SELECT id FROM users WHERE name = \"Carol\"))",
+    );
+    assert_eq!(rows, vec![Value::Int(1)]);
+}
+
+#[test]
+fn test_in_subquery_in_dataset_queries() {
+    let mut db = setup();
+    execute_line(
+        &mut db,
+        "DATASET buyers FROM users FILTER id IN (SELECT user_id FROM orders)",
+        1,
+    )
+    .unwrap();
+    assert_eq!(db.get_dataset("buyers").unwrap().rows.len(), 2);
+}
+
+#[test]
+fn test_not_in_with_null_in_subquery_keeps_nothing() {
+    let mut db = setup();
+    let rows = column(
+        &mut db,
+        "SELECT name FROM users WHERE id NOT IN (SELECT WHERE(oid > 11, NULL, user_id) AS u FROM orders)",
+    );
+    assert!(rows.is_empty());
+}
+
+#[test]
+fn test_invalid_in_subqueries_are_rejected() {
+    let mut db = setup();
+    let err = execute_line(
+        &mut db,
+        "SELECT name FROM users WHERE id IN (SELECT user_id FROM orders) OR id = 2",
+        1,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("combined with AND"), "{}", err);
+
+    let err = execute_line(
+        &mut db,
+        "SELECT name FROM users WHERE id IN (SELECT oid, user_id FROM orders)",
+        1,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("one column"), "{}", err);
+}
+
+#[test]
+fn test_derived_table_columns_may_use_its_alias() {
+    let mut db = setup();
+    let rows = column(
+        &mut db,
+        "SELECT t.name FROM (SELECT id, name FROM users WHERE id > 1) AS t WHERE t.id < 4",
+    );
+    assert_eq!(rows, names(&["Bob", "Carol"]));
+    let rows = column(
+        &mut db,
+        "SELECT name FROM (SELECT id, name FROM users) u WHERE u.id IN (SELECT user_id FROM orders)",
+    );
+    assert_eq!(rows, names(&["Alice", "Carol"]));
+}
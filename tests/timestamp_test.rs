@@ -0,0 +1,159 @@
+use linal::core::value::{Value, ValueType};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET events COLUMNS (id: Int, kind: String, at: Timestamp)
+    INSERT INTO events VALUES (1, "click", "2024-03-01T10:15:30Z"), (2, "view", TIMESTAMP "2024-03-01 23:59:00"), (3, "click", "2024-03-04")
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows.iter().map(|r| r.values.clone()).collect(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn ts(s: &str) -> Value {
+    Value::String(s.to_string())
+        .cast_to(&ValueType::Timestamp)
+        .unwrap()
+}
+
+#[test]
+fn test_insert_parses_iso_literals() {
+    let mut db = setup();
+    let result = rows(&mut db, "SELECT at FROM events ORDER BY id");
+    assert_eq!(
+        result,
+        vec![
+            vec![ts("2024-03-01T10:15:30Z")],
+            vec![ts("2024-03-01T23:59:00Z")],
+            vec![ts("2024-03-04T00:00:00Z")],
+        ]
+    );
+    assert_eq!(result[0][0].to_string(), "2024-03-01T10:15:30Z");
+
+    let err = execute_line(&mut db, "INSERT INTO events VALUES (4, \"x\", \"soon\")", 1);
+    assert!(err.is_err());
+}
+
+#[test]
+fn test_filter_and_order_by_timestamp() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT id FROM events WHERE at >= TIMESTAMP \"2024-03-01T12:00:00Z\" ORDER BY at DESC",
+    );
+    assert_eq!(result, vec![vec![Value::Int(3)], vec![Value::Int(2)]]);
+
+    let result = rows(&mut db, "SELECT id FROM events WHERE at < NOW()");
+    assert_eq!(result.len(), 3);
+}
+
+#[test]
+fn test_date_trunc() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT DATE_TRUNC(\"hour\", at), DATE_TRUNC(\"day\", at), DATE_TRUNC(\"week\", at), DATE_TRUNC(\"month\", at) FROM events WHERE id = 1",
+    );
+    assert_eq!(
+        result,
+        vec![vec![
+            ts("2024-03-01T10:00:00Z"),
+            ts("2024-03-01"),
+            ts("2024-02-26"),
+            ts("2024-03-01"),
+        ]]
+    );
+
+    let err = execute_line(
+        &mut db,
+        "SELECT DATE_TRUNC(\"fortnight\", at) FROM events",
+        1,
+    );
+    assert!(err.is_err());
+}
+
+#[test]
+fn test_interval_arithmetic() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT at + INTERVAL \"1 day 2 hours\", at - INTERVAL \"30 minutes\", at - TIMESTAMP \"2024-03-01\" FROM events WHERE id = 1",
+    );
+    assert_eq!(
+        result,
+        vec![vec![
+            ts("2024-03-02T12:15:30Z"),
+            ts("2024-03-01T09:45:30Z"),
+            Value::Int(10 * 3600 + 15 * 60 + 30),
+        ]]
+    );
+}
+
+#[test]
+fn test_group_by_and_aggregate_timestamps() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT kind, MIN(at) AS first, MAX(at) AS last FROM events GROUP BY kind ORDER BY kind",
+    );
+    assert_eq!(
+        result,
+        vec![
+            vec![
+                Value::String("click".into()),
+                ts("2024-03-01T10:15:30Z"),
+                ts("2024-03-04"),
+            ],
+            vec![
+                Value::String("view".into()),
+                ts("2024-03-01T23:59:00Z"),
+                ts("2024-03-01T23:59:00Z"),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn test_sql_timestamp_columns_and_literals() {
+    let mut db = TensorDb::new();
+    let script = r#"
+    SQL CREATE TABLE logins (id INT, at TIMESTAMP)
+    SQL INSERT INTO logins VALUES (1, '2024-05-01T08:00:00Z'), (2, TIMESTAMP '2024-05-02 08:00:00')
+    "#;
+    execute_script(&mut db, script).unwrap();
+    let result = rows(
+        &mut db,
+        "SQL SELECT id FROM logins WHERE at > TIMESTAMP '2024-05-01 00:00:00' + INTERVAL '1' DAY",
+    );
+    assert_eq!(result, vec![vec![Value::Int(2)]]);
+}
+
+#[test]
+fn test_timestamps_survive_parquet_round_trip() {
+    use linal::core::storage::{ParquetStorage, StorageEngine};
+
+    let temp_dir = "/tmp/linal_test_timestamps";
+    let _ = std::fs::remove_dir_all(temp_dir);
+    let db = setup();
+    let dataset = db.get_dataset("events").unwrap();
+
+    let storage = ParquetStorage::new(temp_dir);
+    storage.save_dataset(dataset).unwrap();
+    let loaded = storage.load_dataset("events").unwrap();
+    assert_eq!(
+        loaded.schema.get_field("at").unwrap().value_type,
+        ValueType::Timestamp
+    );
+    assert_eq!(loaded.rows[1].values[2], ts("2024-03-01T23:59:00Z"));
+
+    let _ = std::fs::remove_dir_all(temp_dir);
+}
@@ -0,0 +1,179 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use std::fs;
+use std::path::PathBuf;
+
+fn setup_users(db: &mut TensorDb) {
+    let script = r#"
+    DATASET users COLUMNS (id: Int, name: String)
+    INSERT INTO users VALUES (1, "Ada")
+    INSERT INTO users VALUES (2, "Bob")
+    CREATE INDEX name_idx ON users(name)
+    "#;
+    execute_script(db, script).unwrap();
+}
+
+fn count(db: &mut TensorDb, query: &str) -> usize {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.len(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+/// Either the WAL or auto_persist keeps the changes, never both
+fn config(temp_dir: &str, auto_persist: bool) -> EngineConfig {
+    EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            auto_persist,
+            wal: !auto_persist,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_commit_keeps_changes() {
+    let mut db = TensorDb::new();
+    setup_users(&mut db);
+
+    execute_line(&mut db, "BEGIN", 1).unwrap();
+    assert!(db.in_transaction());
+    execute_line(&mut db, r#"INSERT INTO users VALUES (3, "Cy")"#, 2).unwrap();
+    execute_line(&mut db, "DELETE FROM users WHERE id = 1", 3).unwrap();
+    let out = execute_line(&mut db, "COMMIT", 4).unwrap();
+    assert!(out.to_string().contains("Transaction committed"));
+    assert!(!db.in_transaction());
+
+    assert_eq!(count(&mut db, "SELECT * FROM users"), 2);
+    assert!(execute_line(&mut db, "COMMIT", 5).is_err());
+}
+
+#[test]
+fn test_rollback_restores_datasets() {
+    let mut db = TensorDb::new();
+    setup_users(&mut db);
+    execute_line(&mut db, "VECTOR x = [1.0]", 1).unwrap();
+
+    execute_script(
+        &mut db,
+        r#"
+        BEGIN
+        INSERT INTO users VALUES (3, "Cy")
+        UPDATE users SET name = "Eve" WHERE id = 1
+        DATASET logs COLUMNS (id: Int)
+        VECTOR x = [2.0]
+        VECTOR y = [3.0]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(count(&mut db, "SELECT * FROM users"), 3);
+
+    let out = execute_line(&mut db, "ROLLBACK", 2).unwrap();
+    assert!(out
+        .to_string()
+        .contains("Transaction rolled back (1 datasets restored, 1 dropped)"));
+
+    assert_eq!(count(&mut db, "SELECT * FROM users"), 2);
+    // The index matches the restored rows
+    assert_eq!(
+        count(&mut db, r#"SELECT * FROM users WHERE name = "Ada""#),
+        1
+    );
+    assert_eq!(
+        count(&mut db, r#"SELECT * FROM users WHERE name = "Eve""#),
+        0
+    );
+    assert!(db.get_dataset("logs").is_err());
+    assert_eq!(db.get("x").unwrap().data.as_slice(), &[1.0]);
+    assert!(db.get("y").is_err());
+    assert!(execute_line(&mut db, "ROLLBACK", 3).is_err());
+}
+
+#[test]
+fn test_failed_script_rolls_back() {
+    let mut db = TensorDb::new();
+    setup_users(&mut db);
+
+    let err = execute_script(
+        &mut db,
+        r#"
+        BEGIN
+        INSERT INTO users VALUES (3, "Cy")
+        DROP DATASET users
+        INSERT INTO users VALUES (4, "Dan")
+        COMMIT
+        "#,
+    );
+    assert!(err.is_err());
+    assert!(!db.in_transaction());
+    assert_eq!(count(&mut db, "SELECT * FROM users"), 2);
+
+    assert!(execute_line(&mut db, "BEGIN", 1).is_ok());
+    assert!(execute_line(&mut db, "BEGIN", 2).is_err());
+    assert!(execute_line(&mut db, "BEGIN now", 3).is_err());
+}
+
+#[test]
+fn test_transaction_belongs_to_its_session() {
+    let mut db = TensorDb::new();
+    setup_users(&mut db);
+
+    db.set_session_id(Some("a".to_string()));
+    execute_line(&mut db, "BEGIN", 1).unwrap();
+    execute_line(&mut db, r#"INSERT INTO users VALUES (3, "Cy")"#, 2).unwrap();
+
+    // Another session can neither write into the transaction nor end it
+    db.set_session_id(Some("b".to_string()));
+    let err = execute_line(&mut db, r#"INSERT INTO users VALUES (4, "Di")"#, 3).unwrap_err();
+    assert!(err.to_string().contains("another session"), "{}", err);
+    assert!(execute_line(&mut db, "COMMIT", 4).is_err());
+    assert!(execute_line(&mut db, "ROLLBACK", 5).is_err());
+    db.set_session_id(None);
+    assert!(execute_line(&mut db, "DELETE FROM users WHERE id = 1", 6).is_err());
+    assert_eq!(count(&mut db, "SELECT * FROM users"), 3);
+
+    db.set_session_id(Some("a".to_string()));
+    execute_line(&mut db, "ROLLBACK", 7).unwrap();
+    assert_eq!(count(&mut db, "SELECT * FROM users"), 2);
+}
+
+#[test]
+fn test_transaction_wal_and_storage() {
+    for auto_persist in [false, true] {
+        check_persisted_transactions(auto_persist);
+    }
+}
+
+fn check_persisted_transactions(auto_persist: bool) {
+    let temp_dir = "/tmp/linal_test_transaction_storage";
+    let _ = fs::remove_dir_all(temp_dir);
+
+    {
+        let mut db = TensorDb::with_config(config(temp_dir, auto_persist));
+        setup_users(&mut db);
+        execute_script(
+            &mut db,
+            r#"
+            BEGIN
+            INSERT INTO users VALUES (3, "Cy")
+            DATASET scratch COLUMNS (id: Int)
+            ROLLBACK
+            BEGIN
+            INSERT INTO users VALUES (4, "Dan")
+            COMMIT
+            "#,
+        )
+        .unwrap();
+    }
+
+    // Rolled back statements reach neither the stored files nor the WAL
+    let mut db = TensorDb::with_config(config(temp_dir, auto_persist));
+    assert_eq!(db.list_dataset_names(), vec!["users"]);
+    assert_eq!(count(&mut db, "SELECT * FROM users"), 3);
+    assert_eq!(count(&mut db, "SELECT * FROM users WHERE id = 3"), 0);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
@@ -0,0 +1,133 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+use std::fs;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET usuários COLUMNS (id: Int, nome: String, pontuação: Float)
+    INSERT INTO usuários VALUES (1, "José 🚀", 1.5)
+    INSERT INTO usuários VALUES (2, "Zoë (ß)", 2.5), (3, "日本語", 3.0)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn column(db: &mut TensorDb, query: &str) -> Vec<Value> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(ds) => ds.rows.iter().map(|r| r.values[0].clone()).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_filter_non_ascii_strings() {
+    let mut db = setup();
+
+    assert_eq!(
+        column(&mut db, "SELECT id FROM usuários WHERE nome = \"日本語\""),
+        vec![Value::Int(3)]
+    );
+    assert_eq!(
+        column(
+            &mut db,
+            "SELECT nome FROM usuários WHERE pontuação > 2.0 ORDER BY pontuação DESC"
+        ),
+        vec![
+            Value::String("日本語".into()),
+            Value::String("Zoë (ß)".into())
+        ]
+    );
+
+    execute_line(&mut db, "CREATE INDEX idx ON usuários(nome)", 1).unwrap();
+    execute_line(
+        &mut db,
+        "UPDATE usuários SET nome = \"Ünïcödé\" WHERE nome = \"José 🚀\"",
+        2,
+    )
+    .unwrap();
+    assert_eq!(
+        column(&mut db, "SELECT id FROM usuários WHERE nome = \"Ünïcödé\""),
+        vec![Value::Int(1)]
+    );
+}
+
+#[test]
+fn test_expressions_with_multibyte_names() {
+    let mut db = setup();
+
+    // Operators after multibyte identifiers used to be located by char index
+    execute_line(
+        &mut db,
+        "DATASET usuários ADD COLUMN dobro = pontuação * 2",
+        1,
+    )
+    .unwrap();
+    execute_line(
+        &mut db,
+        "DATASET usuários ADD COLUMN 日本 = pontuação + 1 LAZY",
+        2,
+    )
+    .unwrap();
+    // Case mapping of 'ı' changes its byte length
+    execute_line(
+        &mut db,
+        "DATASET usuários ADD COLUMN ıd = pontuação - 1 lazy",
+        3,
+    )
+    .unwrap();
+
+    let ds = db.get_dataset("usuários").unwrap();
+    assert_eq!(ds.rows[0].get("dobro"), Some(&Value::Float(3.0)));
+    let row = ds.get_row_evaluated(2).unwrap();
+    assert_eq!(row.get("日本"), Some(&Value::Float(4.0)));
+    assert_eq!(row.get("ıd"), Some(&Value::Float(2.0)));
+
+    // A lone quote is an error, not a panic
+    assert!(execute_line(&mut db, "INSERT INTO usuários VALUES (4, \", 1.0)", 4).is_err());
+}
+
+#[test]
+fn test_non_ascii_round_trips_through_files() {
+    let dir = "/tmp/linal_test_unicode";
+    let _ = fs::remove_dir_all(dir);
+    let mut db = setup();
+
+    execute_line(
+        &mut db,
+        &format!(r#"SAVE DATASET usuários TO "{}""#, dir),
+        1,
+    )
+    .unwrap();
+    execute_line(
+        &mut db,
+        &format!(r#"EXPORT (SELECT * FROM usuários) TO "{}/u.csv""#, dir),
+        2,
+    )
+    .unwrap();
+
+    let mut db = TensorDb::new();
+    execute_line(
+        &mut db,
+        &format!(r#"LOAD DATASET usuários FROM "{}""#, dir),
+        3,
+    )
+    .unwrap();
+    execute_line(
+        &mut db,
+        &format!(r#"LOAD DATASET de_csv FROM "{}/u.csv""#, dir),
+        4,
+    )
+    .unwrap();
+
+    let expected = vec![
+        Value::String("José 🚀".into()),
+        Value::String("Zoë (ß)".into()),
+        Value::String("日本語".into()),
+    ];
+    assert_eq!(column(&mut db, "SELECT nome FROM usuários"), expected);
+    assert_eq!(column(&mut db, "SELECT nome FROM de_csv"), expected);
+
+    let _ = fs::remove_dir_all(dir);
+}
@@ -0,0 +1,105 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET users COLUMNS (id: Int PRIMARY KEY, name: String, score: Float)
+    INSERT INTO users VALUES (1, "Alice", 1.0)
+    INSERT INTO users VALUES (2, "Bob", 2.0)
+    CREATE INDEX name_idx ON users(name)
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+#[test]
+fn test_primary_key_rejects_duplicate_insert() {
+    let mut db = setup();
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(users.schema.primary_key().unwrap().name, "id");
+
+    let err = execute_line(&mut db, "INSERT INTO users VALUES (1, \"Eve\", 0.0)", 1).unwrap_err();
+    assert!(
+        err.to_string().contains("Duplicate primary key 1"),
+        "{}",
+        err
+    );
+    assert_eq!(db.get_dataset("users").unwrap().len(), 2);
+
+    // Updating the key into an existing one is rejected as well
+    let err = execute_line(&mut db, "UPDATE users SET id = 1 WHERE id = 2", 2).unwrap_err();
+    assert!(err.to_string().contains("Duplicate primary key"), "{}", err);
+
+    assert!(execute_line(
+        &mut db,
+        "DATASET bad COLUMNS (a: Int PRIMARY KEY, b: Int PRIMARY KEY)",
+        3
+    )
+    .is_err());
+}
+
+#[test]
+fn test_upsert_replaces_matching_row() {
+    let mut db = setup();
+
+    let out = execute_line(&mut db, "UPSERT INTO users VALUES (1, \"Alicia\", 5.0)", 1).unwrap();
+    assert!(matches!(out, DslOutput::Affected { rows: 1, ref op } if op == "UPSERT"));
+
+    let users = db.get_dataset("users").unwrap();
+    assert_eq!(users.len(), 2);
+    assert_eq!(
+        users.rows[0].get("name"),
+        Some(&Value::String("Alicia".into()))
+    );
+    assert_eq!(users.rows[0].get("score"), Some(&Value::Float(5.0)));
+
+    // Secondary indices follow the replaced values
+    let name_idx = users.get_index("name").unwrap();
+    assert_eq!(
+        name_idx.lookup(&Value::String("Alicia".into())).unwrap(),
+        vec![0]
+    );
+    assert!(name_idx
+        .lookup(&Value::String("Alice".into()))
+        .unwrap()
+        .is_empty());
+
+    // A new key is inserted
+    let out = execute_line(
+        &mut db,
+        "UPSERT INTO users VALUES (3, \"Carol\", 3.0) RETURNING id, name",
+        2,
+    )
+    .unwrap();
+    match out {
+        DslOutput::Table(ds) => assert_eq!(
+            ds.rows[0].values,
+            vec![Value::Int(3), Value::String("Carol".into())]
+        ),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+    assert_eq!(db.get_dataset("users").unwrap().len(), 3);
+}
+
+#[test]
+fn test_upsert_requires_primary_key() {
+    let mut db = TensorDb::new();
+    execute_line(&mut db, "DATASET plain COLUMNS (id: Int)", 1).unwrap();
+    let err = execute_line(&mut db, "UPSERT INTO plain VALUES (1)", 2).unwrap_err();
+    assert!(err.to_string().contains("PRIMARY KEY"), "{}", err);
+
+    // SQL tables take the key from the column constraint
+    execute_line(
+        &mut db,
+        "SQL CREATE TABLE items (id INT PRIMARY KEY, label TEXT)",
+        3,
+    )
+    .unwrap();
+    execute_line(&mut db, "UPSERT INTO items VALUES (1, \"a\")", 4).unwrap();
+    execute_line(&mut db, "UPSERT INTO items VALUES (1, \"b\")", 5).unwrap();
+    let items = db.get_dataset("items").unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items.rows[0].get("label"), Some(&Value::String("b".into())));
+}
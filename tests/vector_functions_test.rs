@@ -0,0 +1,78 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn setup() -> TensorDb {
+    let mut db = TensorDb::new();
+    let script = r#"
+    DATASET docs COLUMNS (id: Int, embedding: Vector(3))
+    INSERT INTO docs VALUES (1, [1, 0, 0]), (2, [0, 1, 0]), (3, [1, 1, 0]), (4, [0, 0, 0])
+    "#;
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows.iter().map(|r| r.values.clone()).collect(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn float(value: &Value) -> f32 {
+    match value {
+        Value::Float(f) => *f,
+        other => panic!("expected a float, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_cosine_similarity_ranks_rows() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT id, COSINE_SIM(embedding, [1.0, 0.2, 0.0]) AS sim FROM docs ORDER BY sim DESC LIMIT 2",
+    );
+    let ids: Vec<Value> = result.iter().map(|r| r[0].clone()).collect();
+    assert_eq!(ids, vec![Value::Int(1), Value::Int(3)]);
+    assert!(float(&result[0][1]) > float(&result[1][1]));
+}
+
+#[test]
+fn test_dot_distance_and_norm() {
+    let mut db = setup();
+    let result = rows(
+        &mut db,
+        "SELECT DOT(embedding, [2, 3, 4]), L2_DIST(embedding, [1, 1, 0]), NORM(embedding) FROM docs WHERE id = 3",
+    );
+    assert_eq!(float(&result[0][0]), 5.0);
+    assert_eq!(float(&result[0][1]), 0.0);
+    assert!((float(&result[0][2]) - 2f32.sqrt()).abs() < 1e-6);
+
+    let result = rows(
+        &mut db,
+        "SELECT id FROM docs WHERE L2_DIST(embedding, [0, 0, 0]) < 1.1 ORDER BY id",
+    );
+    assert_eq!(
+        result,
+        vec![
+            vec![Value::Int(1)],
+            vec![Value::Int(2)],
+            vec![Value::Int(4)]
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_inputs_are_null() {
+    let mut db = setup();
+    // Zero-norm vectors and length mismatches have no similarity
+    let result = rows(
+        &mut db,
+        "SELECT COSINE_SIM(embedding, [1, 0, 0]), DOT(embedding, [1, 2]) FROM docs WHERE id = 4",
+    );
+    assert_eq!(result, vec![vec![Value::Null, Value::Null]]);
+
+    let err = execute_line(&mut db, "SELECT DOT(embedding) FROM docs", 1).unwrap_err();
+    assert!(err.to_string().contains("DOT(vector, vector)"), "{}", err);
+}
@@ -0,0 +1,79 @@
+use linal::core::config::{EngineConfig, StorageConfig};
+use linal::core::storage::wal::WriteAheadLog;
+use linal::dsl::{execute_line, execute_script};
+use linal::engine::TensorDb;
+use std::fs;
+use std::path::PathBuf;
+
+fn wal_config(temp_dir: &str) -> EngineConfig {
+    EngineConfig {
+        storage: StorageConfig {
+            data_dir: PathBuf::from(temp_dir),
+            default_db: "default".to_string(),
+            auto_persist: false,
+            wal: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_wal_replay_after_restart() {
+    let temp_dir = "/tmp/linal_test_wal_replay";
+    let _ = fs::remove_dir_all(temp_dir);
+
+    {
+        let mut db = TensorDb::with_config(wal_config(temp_dir));
+        let script = r#"
+        DATASET users COLUMNS (id: Int, name: String)
+        INSERT INTO users VALUES (1, "Alice")
+        INSERT INTO users VALUES (2, "Bob")
+        VECTOR w = [1.0, 2.0]
+        SHOW ALL
+        "#;
+        execute_script(&mut db, script).unwrap();
+        // Simulated crash: nothing saved explicitly
+    }
+
+    // Only mutating commands are logged
+    let wal = WriteAheadLog::for_database(format!("{}/default", temp_dir));
+    assert_eq!(wal.read_all().unwrap().len(), 4);
+
+    let db = TensorDb::with_config(wal_config(temp_dir));
+    assert_eq!(db.get_dataset("users").unwrap().len(), 2);
+    assert_eq!(*db.get("w").unwrap().data, vec![1.0, 2.0]);
+    // Replay does not append to the log again
+    assert_eq!(wal.read_all().unwrap().len(), 4);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
+
+#[test]
+fn test_checkpoint_truncates_wal() {
+    let temp_dir = "/tmp/linal_test_wal_checkpoint";
+    let _ = fs::remove_dir_all(temp_dir);
+    let wal = WriteAheadLog::for_database(format!("{}/default", temp_dir));
+
+    {
+        let mut db = TensorDb::with_config(wal_config(temp_dir));
+        let script = r#"
+        DATASET users COLUMNS (id: Int, name: String)
+        INSERT INTO users VALUES (1, "Alice")
+        VECTOR w = [1.0, 2.0]
+        CHECKPOINT
+        "#;
+        execute_script(&mut db, script).unwrap();
+        assert!(wal.read_all().unwrap().is_empty());
+
+        execute_line(&mut db, r#"INSERT INTO users VALUES (2, "Bob")"#, 1).unwrap();
+        assert_eq!(wal.read_all().unwrap().len(), 1);
+    }
+
+    // Snapshot plus the commands logged after it
+    let db = TensorDb::with_config(wal_config(temp_dir));
+    assert_eq!(db.get_dataset("users").unwrap().len(), 2);
+    assert_eq!(*db.get("w").unwrap().data, vec![1.0, 2.0]);
+
+    let _ = fs::remove_dir_all(temp_dir);
+}
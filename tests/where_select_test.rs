@@ -0,0 +1,78 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+fn run(script: &str) -> TensorDb {
+    let mut db = TensorDb::new();
+    execute_script(&mut db, script).unwrap();
+    db
+}
+
+fn tensor(db: &TensorDb, name: &str) -> (Vec<usize>, Vec<f32>) {
+    let t = db.get(name).unwrap();
+    (t.shape.dims.clone(), t.data.to_vec())
+}
+
+fn column(db: &mut TensorDb, query: &str, idx: usize) -> Vec<Value> {
+    match execute_line(db, query, 1).unwrap() {
+        DslOutput::Table(result) => result.rows.iter().map(|r| r.values[idx].clone()).collect(),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+#[test]
+fn test_where_selects_elementwise_with_scalar_broadcast() {
+    let db = run("VECTOR v = [-2, -1, 0, 1, 2]\n\
+         LET relu = WHERE(v > 0, v, 0)\n\
+         LET clipped = WHERE(v >= 1, 1, WHERE(v < -1, -1, v))\n\
+         LET keep = v != 0\n\
+         LET scaled = WHERE(keep, v * 10, v)\n\
+         MATRIX m = [[1, 5], [7, 2]]\n\
+         MATRIX n = [[0, 0], [0, 0]]\n\
+         LET mm = WHERE(m > 3, m, n)");
+    assert_eq!(
+        tensor(&db, "relu"),
+        (vec![5], vec![0.0, 0.0, 0.0, 1.0, 2.0])
+    );
+    assert_eq!(tensor(&db, "clipped").1, vec![-1.0, -1.0, 0.0, 1.0, 1.0]);
+    assert_eq!(tensor(&db, "scaled").1, vec![-20.0, -10.0, 0.0, 10.0, 20.0]);
+    assert_eq!(tensor(&db, "mm"), (vec![2, 2], vec![0.0, 5.0, 7.0, 0.0]));
+}
+
+#[test]
+fn test_where_rejects_mismatched_shapes() {
+    let mut db = run("VECTOR v = [1, 2, 3]\nVECTOR w = [1, 2]");
+    let err = execute_line(&mut db, "LET x = WHERE(v > 1, v, w)", 1).unwrap_err();
+    assert!(err.to_string().contains("share one shape"), "{}", err);
+    assert!(execute_line(&mut db, "LET x = WHERE(v > 1, v)", 1).is_err());
+}
+
+#[test]
+fn test_where_in_queries() {
+    let mut db = run("DATASET users COLUMNS (id: Int, age: Int, score: Float)\n\
+         INSERT INTO users VALUES (1, 30, 1.5), (2, 40, 2.5), (3, 50, 3.5)");
+
+    let query = "SELECT WHERE(age > 35, score, 0) AS s, \
+                 WHERE(age > 35 AND id > 2, \"old\", \"young\") AS label FROM users";
+    assert_eq!(
+        column(&mut db, query, 0),
+        vec![Value::Float(0.0), Value::Float(2.5), Value::Float(3.5)]
+    );
+    assert_eq!(
+        column(&mut db, query, 1),
+        vec![
+            Value::String("young".into()),
+            Value::String("young".into()),
+            Value::String("old".into())
+        ]
+    );
+
+    let capped = column(&mut db, "SELECT WHERE(age < 45, age, 45) FROM users", 0);
+    assert_eq!(capped, vec![Value::Int(30), Value::Int(40), Value::Int(45)]);
+    let ids = column(
+        &mut db,
+        "SELECT id FROM users WHERE WHERE(age > 35, 1, 0) = 1",
+        0,
+    );
+    assert_eq!(ids, vec![Value::Int(2), Value::Int(3)]);
+}
@@ -0,0 +1,190 @@
+use linal::core::value::Value;
+use linal::dsl::{execute_line, execute_script, DslOutput};
+use linal::engine::TensorDb;
+
+/// Two hosts with readings out of time order
+fn setup_metrics(db: &mut TensorDb) {
+    let script = r#"
+    DATASET metrics COLUMNS (host: String, ts: Int, value: Float)
+    INSERT INTO metrics VALUES ("a", 3, 30.0), ("b", 1, 5.0), ("a", 1, 10.0)
+    INSERT INTO metrics VALUES ("a", 2, 20.0), ("b", 2, 15.0), ("a", 4, 40.0)
+    INSERT INTO metrics VALUES ("b", 3, 25.0)
+    "#;
+    execute_script(db, script).expect("Setup failed");
+}
+
+fn select_rows(db: &mut TensorDb, query: &str) -> Vec<Vec<Value>> {
+    match execute_line(db, query, 1).expect("Query failed") {
+        DslOutput::Table(ds) => ds.rows.into_iter().map(|r| r.values).collect(),
+        other => panic!("Expected table output, got {:?}", other),
+    }
+}
+
+/// Second column of each row
+fn column(rows: &[Vec<Value>]) -> Vec<Value> {
+    rows.iter().map(|r| r[1].clone()).collect()
+}
+
+fn ints(values: &[i64]) -> Vec<Value> {
+    values.iter().map(|&v| Value::Int(v)).collect()
+}
+
+fn floats(values: &[f32]) -> Vec<Value> {
+    values.iter().map(|&v| Value::Float(v)).collect()
+}
+
+#[test]
+fn test_row_number_restarts_per_partition() {
+    let mut db = TensorDb::new();
+    setup_metrics(&mut db);
+
+    // Rows keep input order; numbering follows ts within each host
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, ROW_NUMBER() OVER (PARTITION BY host ORDER BY ts) FROM metrics",
+    );
+    assert_eq!(column(&rows), ints(&[3, 1, 1, 2, 2, 4, 3]));
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT host, ROW_NUMBER() OVER (ORDER BY ts DESC) FROM metrics",
+    );
+    // Ties on ts are numbered in input order
+    assert_eq!(column(&rows), ints(&[2, 6, 7, 4, 5, 1, 3]));
+}
+
+#[test]
+fn test_rank_and_dense_rank_share_ties() {
+    let mut db = TensorDb::new();
+    setup_metrics(&mut db);
+
+    let rows = select_rows(&mut db, "SELECT ts, RANK() OVER (ORDER BY ts) FROM metrics");
+    assert_eq!(column(&rows), ints(&[5, 1, 1, 3, 3, 7, 5]));
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, DENSE_RANK() OVER (ORDER BY ts) FROM metrics",
+    );
+    assert_eq!(column(&rows), ints(&[3, 1, 1, 2, 2, 4, 3]));
+}
+
+#[test]
+fn test_lag_and_lead() {
+    let mut db = TensorDb::new();
+    setup_metrics(&mut db);
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, LAG(value) OVER (PARTITION BY host ORDER BY ts) FROM metrics",
+    );
+    assert_eq!(
+        column(&rows),
+        vec![
+            Value::Float(20.0),
+            Value::Null,
+            Value::Null,
+            Value::Float(10.0),
+            Value::Float(5.0),
+            Value::Float(30.0),
+            Value::Float(15.0),
+        ]
+    );
+
+    // Offset 2 with a default for rows that have no successor that far ahead
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, LEAD(value, 2, 0.0) OVER (PARTITION BY host ORDER BY ts) FROM metrics",
+    );
+    assert_eq!(
+        column(&rows),
+        floats(&[0.0, 25.0, 30.0, 40.0, 0.0, 0.0, 0.0])
+    );
+}
+
+#[test]
+fn test_moving_average_and_running_sum() {
+    let mut db = TensorDb::new();
+    setup_metrics(&mut db);
+
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, AVG(value) OVER (PARTITION BY host ORDER BY ts ROWS BETWEEN 1 PRECEDING AND CURRENT ROW) FROM metrics WHERE host = \"a\"",
+    );
+    // a: ts 1..4 -> 10, 20, 30, 40
+    assert_eq!(column(&rows), floats(&[25.0, 10.0, 15.0, 35.0]));
+
+    // Shorthand frame, centered frame
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, SUM(value) OVER (ORDER BY ts ROWS 2 PRECEDING) FROM metrics WHERE host = \"a\"",
+    );
+    assert_eq!(column(&rows), floats(&[60.0, 10.0, 30.0, 90.0]));
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, MAX(value) OVER (ORDER BY ts ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING) FROM metrics WHERE host = \"a\"",
+    );
+    assert_eq!(column(&rows), floats(&[40.0, 20.0, 30.0, 40.0]));
+
+    // Without a frame the aggregate is running, and ORDER BY ties share a value
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, SUM(value) OVER (ORDER BY ts) FROM metrics",
+    );
+    assert_eq!(
+        column(&rows),
+        floats(&[105.0, 15.0, 15.0, 50.0, 50.0, 145.0, 105.0])
+    );
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, MEDIAN(value) OVER (ORDER BY ts ROWS 2 PRECEDING) FROM metrics WHERE host = \"a\"",
+    );
+    assert_eq!(column(&rows), floats(&[20.0, 10.0, 15.0, 30.0]));
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, COUNT(*) OVER (PARTITION BY host ORDER BY ts ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING) FROM metrics",
+    );
+    assert_eq!(column(&rows), ints(&[4, 3, 4, 4, 3, 4, 3]));
+}
+
+#[test]
+fn test_window_columns_are_named_and_typed() {
+    let mut db = TensorDb::new();
+    setup_metrics(&mut db);
+
+    execute_line(
+        &mut db,
+        "DATASET smoothed FROM metrics SELECT host, ts, AVG(value) OVER (PARTITION BY host ORDER BY ts ROWS 2 PRECEDING), RANK() OVER (ORDER BY value DESC)",
+        1,
+    )
+    .unwrap();
+    let ds = db.get_dataset("smoothed").unwrap();
+    let names: Vec<&str> = ds.schema.fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["host", "ts", "AVG(value)", "RANK()"]);
+
+    // ORDER BY / LIMIT apply after the window is computed
+    let rows = select_rows(
+        &mut db,
+        "SELECT ts, ROW_NUMBER() OVER (PARTITION BY host ORDER BY ts) FROM metrics ORDER BY value DESC LIMIT 2",
+    );
+    assert_eq!(rows, vec![ints(&[4, 4]), ints(&[3, 3])]);
+}
+
+#[test]
+fn test_window_syntax_errors() {
+    let mut db = TensorDb::new();
+    setup_metrics(&mut db);
+
+    for query in [
+        "SELECT ROW_NUMBER(ts) OVER (ORDER BY ts) FROM metrics",
+        "SELECT ROW_NUMBER() OVER (PARTITION BY host) FROM metrics",
+        "SELECT LAG() OVER (ORDER BY ts) FROM metrics",
+        "SELECT LAG(value, -1) OVER (ORDER BY ts) FROM metrics",
+        "SELECT RANK() OVER (ORDER BY ts ROWS 2 PRECEDING) FROM metrics",
+        "SELECT AVG(value) OVER (ORDER BY ts ROWS BETWEEN 2 PRECEDING) FROM metrics",
+        "SELECT AVG(value) OVER (ORDER BY ts ROWS BETWEEN 2 FOLLOWING AND CURRENT ROW) FROM metrics",
+        "SELECT MODE(value) OVER (ORDER BY ts) FROM metrics",
+        "SELECT ROW_NUMBER() OVER (PARTITION BY missing ORDER BY ts) FROM metrics",
+    ] {
+        assert!(execute_line(&mut db, query, 1).is_err(), "{}", query);
+    }
+}